@@ -0,0 +1,102 @@
+/**
+ * Repeatable benchmark for the WASM decoder's `simd` feature: the min/max
+ * scans behind `TiffResult.compute_stats` and the byte swap of big-endian
+ * uncompressed rasters, on synthetic 4096×4096 single-band pages.
+ *
+ * Run it once against each build and compare the medians:
+ *   npm run build:wasm && node test/wasm-simd-benchmark.js
+ *   (cd wasm/tiff-decoder && RUSTFLAGS="-C target-feature=+simd128" \
+ *     wasm-pack build --target web --release -- --features simd) \
+ *     && <copy pkg/ into media/wasm as build:wasm does> \
+ *     && node test/wasm-simd-benchmark.js
+ *
+ * Override the page size with WASM_BENCH_SIZE and the run count with
+ * WASM_BENCH_RUNS.
+ */
+const fs = require('fs');
+const path = require('path');
+const { performance } = require('perf_hooks');
+
+const wasmJs = path.join(__dirname, '..', 'media', 'wasm', 'tiff-wasm.js');
+const wasmBin = path.join(__dirname, '..', 'media', 'wasm', 'tiff-wasm.wasm');
+
+function positiveInteger(name, fallback) {
+	const value = Number(process.env[name]);
+	return Number.isInteger(value) && value > 0 ? value : fallback;
+}
+
+function median(values) {
+	const sorted = [...values].sort((a, b) => a - b);
+	const middle = Math.floor(sorted.length / 2);
+	return sorted.length % 2 ? sorted[middle] : (sorted[middle - 1] + sorted[middle]) / 2;
+}
+
+/**
+ * Uncompressed single-strip, single-band TIFF. `sampleFormat` is 1 (uint) or
+ * 3 (float); `sample(i)` gives the i-th value.
+ */
+function buildTiff(size, bitsPerSample, sampleFormat, littleEndian, sample) {
+	const bytesPerSample = bitsPerSample / 8;
+	const entries = [
+		[256, 3, size], [257, 3, size], [258, 3, bitsPerSample], [259, 3, 1], [262, 3, 1],
+		[273, 4, 0], [277, 3, 1], [278, 3, size], [279, 4, size * size * bytesPerSample], [339, 3, sampleFormat],
+	];
+	const ifdOffset = 8;
+	const dataOffset = ifdOffset + 2 + entries.length * 12 + 4;
+	const buffer = new ArrayBuffer(dataOffset + size * size * bytesPerSample);
+	const view = new DataView(buffer);
+	view.setUint16(0, littleEndian ? 0x4949 : 0x4d4d);
+	view.setUint16(2, 42, littleEndian);
+	view.setUint32(4, ifdOffset, littleEndian);
+	view.setUint16(ifdOffset, entries.length, littleEndian);
+	entries.forEach(([tag, type, value], i) => {
+		const at = ifdOffset + 2 + i * 12;
+		view.setUint16(at, tag, littleEndian);
+		view.setUint16(at + 2, type, littleEndian);
+		view.setUint32(at + 4, 1, littleEndian);
+		if (tag === 273) { value = dataOffset; }
+		if (type === 3) { view.setUint16(at + 8, value, littleEndian); } else { view.setUint32(at + 8, value, littleEndian); }
+	});
+	for (let i = 0; i < size * size; i++) {
+		const at = dataOffset + i * bytesPerSample;
+		if (sampleFormat === 3) { view.setFloat32(at, sample(i), littleEndian); } else { view.setUint16(at, sample(i), littleEndian); }
+	}
+	return new Uint8Array(buffer);
+}
+
+async function main() {
+	if (!fs.existsSync(wasmBin)) {
+		console.log('⚠️  media/wasm/tiff-wasm.wasm not found — run `npm run build:wasm` first. Skipping.');
+		return;
+	}
+	const mod = await import(wasmJs.replace(/\\/g, '/'));
+	await mod.default({ module_or_path: fs.readFileSync(wasmBin) });
+
+	const size = positiveInteger('WASM_BENCH_SIZE', 4096);
+	const runs = positiveInteger('WASM_BENCH_RUNS', 9);
+	const pages = {
+		'float32 LE': buildTiff(size, 32, 3, true, (i) => Math.sin(i)),
+		'uint16 LE': buildTiff(size, 16, 1, true, (i) => (i * 2654435761) & 0xffff),
+		'uint16 BE': buildTiff(size, 16, 1, false, (i) => (i * 2654435761) & 0xffff),
+	};
+
+	console.log(`🧪 WASM simd benchmark: ${size}×${size}, median of ${runs} runs\n`);
+	for (const [name, bytes] of Object.entries(pages)) {
+		const decodeMs = [];
+		const statsMs = [];
+		for (let run = 0; run < runs; run++) {
+			const start = performance.now();
+			const result = mod.decode_tiff_fast(bytes);
+			decodeMs.push(performance.now() - start);
+			result.compute_stats();
+			statsMs.push(result.timing_stats_ms);
+			result.free();
+		}
+		console.log(`${name.padEnd(11)} decode ${median(decodeMs).toFixed(2)} ms, compute_stats ${median(statsMs).toFixed(2)} ms`);
+	}
+}
+
+main().catch((error) => {
+	console.error(error);
+	process.exit(1);
+});
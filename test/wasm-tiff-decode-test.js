@@ -19,6 +19,7 @@
 const assert = require('assert');
 const fs = require('fs');
 const path = require('path');
+const zlib = require('zlib');
 
 const samplesDir = path.join(__dirname, '..', 'test-samples');
 const wasmJs = path.join(__dirname, '..', 'media', 'wasm', 'tiff-wasm.js');
@@ -37,6 +38,193 @@ function decode(mod, file) {
 	};
 }
 
+/**
+ * Single-page TIFF built in memory, for layouts no fixture covers.
+ * `sample(i)` gives the i-th interleaved sample; strips hold `rowsPerStrip`
+ * rows each (per band when `planar` is set), stored as is (compression 1)
+ * or zlib-compressed (8); strips listed in `sparseStrips` are left out
+ * with a zero offset and byte count, as GDAL writes empty ones.
+ * `sampleFormat` is 1 (uint), 2 (int) or 3 (float); `extraTags` adds
+ * [tag, type, values] entries (SHORT, LONG, ASCII, UNDEFINED bytes, or
+ * RATIONAL as flat numerator, denominator pairs).
+ */
+function buildTiff(width, height, sample, {
+	bitsPerSample = 8, sampleFormat = 1, samplesPerPixel = 1, photometric = 1, rowsPerStrip = height,
+	compression = 1, planar = false, sparseStrips = [], littleEndian = true, extraTags = [],
+} = {}) {
+	const bytesPerSample = bitsPerSample / 8;
+	const planes = planar ? samplesPerPixel : 1;
+	const rowBytes = width * (samplesPerPixel / planes) * bytesPerSample;
+	const raw = new DataView(new ArrayBuffer(planes * height * rowBytes));
+	const write = {
+		1: { 8: 'setUint8', 16: 'setUint16', 32: 'setUint32' },
+		2: { 8: 'setInt8', 16: 'setInt16', 32: 'setInt32' },
+		3: { 32: 'setFloat32', 64: 'setFloat64' },
+	}[sampleFormat][bitsPerSample];
+	const pixelCount = width * height;
+	for (let i = 0; i < pixelCount * samplesPerPixel; i++) {
+		const at = planar ? (i % samplesPerPixel) * pixelCount + Math.floor(i / samplesPerPixel) : i;
+		raw[write](at * bytesPerSample, sample(i), littleEndian);
+	}
+	const strips = [];
+	for (let plane = 0; plane < planes; plane++) {
+		for (let row = 0; row < height; row += rowsPerStrip) {
+			const start = (plane * height + row) * rowBytes;
+			const strip = new Uint8Array(raw.buffer, start, Math.min(rowsPerStrip, height - row) * rowBytes);
+			strips.push(compression === 8 ? new Uint8Array(zlib.deflateSync(strip)) : strip);
+		}
+	}
+	sparseStrips.forEach(strip => { strips[strip] = new Uint8Array(0); });
+
+	const entries = [
+		[256, 4, [width]], [257, 4, [height]], [258, 3, new Array(samplesPerPixel).fill(bitsPerSample)],
+		[259, 3, [compression]], [262, 3, [photometric]], [273, 4, strips.map(() => 0)], [277, 3, [samplesPerPixel]],
+		[278, 4, [rowsPerStrip]], [279, 4, strips.map(strip => strip.length)], [284, 3, [planar ? 2 : 1]],
+		[339, 3, new Array(samplesPerPixel).fill(sampleFormat)], ...extraTags,
+	].map(([tag, type, values]) => [tag, type, type === 2 ? [...Buffer.from(`${values}\0`, 'latin1')] : values]).sort((a, b) => a[0] - b[0]);
+	const valueSize = { 2: 1, 3: 2, 4: 4, 5: 4, 7: 1 };
+	const ifdOffset = 8;
+	let offset = ifdOffset + 2 + entries.length * 12 + 4;
+	const valueOffsets = entries.map(([, type, values]) => {
+		const size = values.length * valueSize[type];
+		if (size <= 4) {
+			return 0;
+		}
+		offset += offset % 2;
+		const at = offset;
+		offset += size;
+		return at;
+	});
+	const stripOffsets = strips.map(strip => {
+		if (strip.length === 0) {
+			return 0;
+		}
+		const at = offset;
+		offset += strip.length;
+		return at;
+	});
+	entries.find(([tag]) => tag === 273)[2] = stripOffsets;
+
+	const bytes = new Uint8Array(offset);
+	const view = new DataView(bytes.buffer);
+	view.setUint16(0, littleEndian ? 0x4949 : 0x4d4d);
+	view.setUint16(2, 42, littleEndian);
+	view.setUint32(4, ifdOffset, littleEndian);
+	view.setUint16(ifdOffset, entries.length, littleEndian);
+	entries.forEach(([tag, type, values], i) => {
+		const at = ifdOffset + 2 + i * 12;
+		view.setUint16(at, tag, littleEndian);
+		view.setUint16(at + 2, type, littleEndian);
+		view.setUint32(at + 4, type === 5 ? values.length / 2 : values.length, littleEndian);
+		if (valueOffsets[i]) {
+			view.setUint32(at + 8, valueOffsets[i], littleEndian);
+		}
+		const target = valueOffsets[i] || at + 8;
+		values.forEach((value, j) => {
+			const setter = { 2: 'setUint8', 3: 'setUint16', 4: 'setUint32', 5: 'setUint32', 7: 'setUint8' }[type];
+			view[setter](target + j * valueSize[type], value, littleEndian);
+		});
+	});
+	strips.forEach((strip, i) => bytes.set(strip, stripOffsets[i]));
+	return bytes;
+}
+
+/**
+ * Multi-page TIFF from little-endian `buildTiff` pages: each page's bytes
+ * after the header are appended, its out-of-line values and strip offsets
+ * moved by where it lands, and its IFD chained to the previous one.
+ */
+function joinTiffPages(pages) {
+	const size = { 1: 1, 2: 1, 3: 2, 4: 4, 5: 8, 7: 1 };
+	const bytes = new Uint8Array(8 + pages.reduce((sum, page) => sum + page.length - 8, 0));
+	const view = new DataView(bytes.buffer);
+	bytes.set(pages[0].subarray(0, 8));
+	let at = 8;
+	let nextLink = 4;
+	for (const page of pages) {
+		const shift = at - 8;
+		bytes.set(page.subarray(8), at);
+		const ifd = new DataView(page.buffer, page.byteOffset).getUint32(4, true) + shift;
+		view.setUint32(nextLink, ifd, true);
+		const count = view.getUint16(ifd, true);
+		for (let i = 0; i < count; i++) {
+			const entry = ifd + 2 + i * 12;
+			const [tag, type, n] = [view.getUint16(entry, true), view.getUint16(entry + 2, true), view.getUint32(entry + 4, true)];
+			const inline = n * size[type] <= 4;
+			if (!inline) {
+				view.setUint32(entry + 8, view.getUint32(entry + 8, true) + shift, true);
+			}
+			if (tag === 273) {
+				const offsets = inline ? entry + 8 : view.getUint32(entry + 8, true);
+				for (let j = 0; j < n; j++) {
+					const offset = view.getUint32(offsets + j * 4, true);
+					if (offset) {
+						view.setUint32(offsets + j * 4, offset + shift, true);
+					}
+				}
+			}
+		}
+		nextLink = ifd + 2 + count * 12;
+		at += page.length - 8;
+	}
+	return bytes;
+}
+
+function crc32(bytes) {
+	let crc = 0xffffffff;
+	for (const byte of bytes) {
+		crc ^= byte;
+		for (let bit = 0; bit < 8; bit++) {
+			crc = (crc >>> 1) ^ (crc & 1 ? 0xedb88320 : 0);
+		}
+	}
+	return (crc ^ 0xffffffff) >>> 0;
+}
+
+/**
+ * ZIP archive of `[name, bytes, deflate]` entries (stored unless `deflate`),
+ * with local headers, a central directory and the end record.
+ */
+function buildZip(files) {
+	const locals = [];
+	const directory = [];
+	let offset = 0;
+	for (const [name, bytes, deflate] of files) {
+		const data = deflate ? new Uint8Array(zlib.deflateRawSync(bytes)) : bytes;
+		const nameBytes = Buffer.from(name, 'utf8');
+		const fields = (header, signature) => {
+			const view = new DataView(header.buffer);
+			view.setUint32(0, signature, true);
+			const at = signature === 0x02014b50 ? 2 : 0;
+			view.setUint16(at + 4, 20, true);
+			view.setUint16(at + 8, deflate ? 8 : 0, true);
+			view.setUint32(at + 14, crc32(bytes), true);
+			view.setUint32(at + 18, data.length, true);
+			view.setUint32(at + 22, bytes.length, true);
+			view.setUint16(at + 26, nameBytes.length, true);
+			return view;
+		};
+		const local = new Uint8Array(30 + nameBytes.length);
+		fields(local, 0x04034b50);
+		local.set(nameBytes, 30);
+		const central = new Uint8Array(46 + nameBytes.length);
+		fields(central, 0x02014b50).setUint32(42, offset, true);
+		central.set(nameBytes, 46);
+		locals.push(local, data);
+		directory.push(central);
+		offset += local.length + data.length;
+	}
+	const directorySize = directory.reduce((sum, entry) => sum + entry.length, 0);
+	const end = new Uint8Array(22);
+	const view = new DataView(end.buffer);
+	view.setUint32(0, 0x06054b50, true);
+	view.setUint16(8, files.length, true);
+	view.setUint16(10, files.length, true);
+	view.setUint32(12, directorySize, true);
+	view.setUint32(16, offset, true);
+	return new Uint8Array(Buffer.concat([...locals, ...directory, end]));
+}
+
 async function main() {
 	if (!fs.existsSync(wasmBin)) {
 		console.log('⚠️  media/wasm/tiff-wasm.wasm not found — run `npm run build:wasm` first. Skipping.');
@@ -275,6 +463,106 @@ async function main() {
 		console.log(`✅ ${file} (planar=${result.planar_configuration}, tiled=${result.tile_width > 0}) matches ground truth exactly, every sample`);
 	}
 
+	// 5e. Horizontal differencing (Predictor 2) across every integer/float
+	//     sample type, channel count and layout, as written by older GDAL
+	//     versions for LZW/Deflate GeoTIFFs. Planar and tiled-LZW pages go
+	//     through try_decode_general_strips_tiles, which previously rejected
+	//     signed and 32/64-bit samples, read little-endian 16-bit samples
+	//     byte-swapped, and refused a last strip shorter than RowsPerStrip.
+	//     Each predictor/*.tif (13x10, 16x16 tiles, 3 rows per strip) holds the
+	//     same samples as its uncompressed predictor/ref_<dtype>_c<spp>.tif twin.
+	{
+		const predictorDir = path.join(samplesDir, 'predictor');
+		const files = fs.readdirSync(predictorDir).filter(f => f.startsWith('p2_')).sort();
+		assert.ok(files.length > 0, 'predictor fixtures present');
+		for (const file of files) {
+			const [, dtype, spp] = file.replace(/\.tif$/, '').split('_');
+			const z = decode(mod, path.join('predictor', file));
+			const r = decode(mod, path.join('predictor', `ref_${dtype}_${spp}.tif`));
+			assert.strictEqual(z.width, 13, `${file}: width`);
+			assert.strictEqual(z.height, 10, `${file}: height`);
+			assert.strictEqual(z.channels, r.channels, `${file}: channels`);
+			assert.strictEqual(z.data.length, r.data.length, `${file}: sample count`);
+			assert.deepStrictEqual(z.data, r.data,
+				`${file} must match the uncompressed reference exactly`);
+		}
+		console.log(`✅ Predictor 2 matches the uncompressed reference for all ${files.length} dtype/channel/layout fixtures`);
+	}
+
+	// 5f. FillOrder 2 (LSB-first bits, from some fax/scanners). Each
+	//     fillorder2_*.tif is its twin below with every raw strip/tile byte
+	//     bit-reversed and the FillOrder tag set to 2, so it must decode to the
+	//     same pixels (1-bit previously came out as noise, and the sub-byte
+	//     paths refused the file).
+	for (const [file, twin] of [
+		['fillorder2_bilevel.tif', 'ccitt_none.tif'],
+		['fillorder2_lzw_12bps.tif', 'shapes_lzw_12bps.tif'],
+		['fillorder2_lzw_planar_10bps.tif', 'shapes_lzw_planar_10bps.tif'],
+	]) {
+		const reversed = decode(mod, file);
+		const expected = decode(mod, twin);
+		assert.strictEqual(reversed.data.length, expected.data.length, `${file}: sample count`);
+		assert.deepStrictEqual(reversed.data, expected.data,
+			`${file} must match its FillOrder 1 twin ${twin} exactly`);
+		console.log(`✅ ${file} (FillOrder 2) matches ${twin}`);
+	}
+
+	// 5g. Partial edge tiles: each tiled_padding_*.tif is 13x10 in 16x16
+	//     tiles, with samples 0..99 and the tile padding beyond the image
+	//     filled with a sentinel (60000 for uint16, 1e30 for float32). The
+	//     padding must never reach the decoded raster, its min/max, a single
+	//     band plane or a TiffFile region.
+	for (const [file, planar] of [
+		['tiled_padding_u16_deflate.tif', false],
+		['tiled_padding_u16_lzw_planar.tif', true],
+		['tiled_padding_f32_deflate.tif', false],
+		['tiled_padding_f32_deflate_planar.tif', true],
+	]) {
+		const bytes = new Uint8Array(fs.readFileSync(path.join(samplesDir, file)));
+		const maxOf = values => values.reduce((m, v) => Math.max(m, v), -Infinity);
+		const result = mod.decode_tiff(bytes);
+		assert.strictEqual(result.get_data_as_f32().length, 13 * 10 * 3, `${file}: sample count`);
+		assert.strictEqual(maxOf(result.get_data_as_f32()), 99, `${file}: no padding in the raster`);
+		assert.strictEqual(result.max_value, 99, `${file}: no padding in max_value`);
+		for (let band = 0; band < 3; band++) {
+			const plane = mod.decode_tiff_plane(bytes, 0, band);
+			assert.strictEqual(plane.length, 13 * 10, `${file}: band ${band} size`);
+			assert.strictEqual(maxOf(plane), 99, `${file}: no padding in band ${band}`);
+		}
+		if (!planar) {
+			const tiffFile = new mod.TiffFile(bytes, 0);
+			assert.ok(maxOf(tiffFile.read_region(8, 4, 5, 6, 1)) <= 99, `${file}: no padding in an edge region`);
+		}
+		console.log(`✅ ${file}: edge tile padding is clipped from the raster, stats, band planes and regions`);
+	}
+
+	// 5h. Tile-level access on a TiffFile handle. tiled_grid_u16_deflate.tif
+	//     is 40x30 in 16x16 tiles (a 3x2 grid with partial right/bottom
+	//     tiles) whose pixel (x, y) holds y * 40 + x, so every tile's samples
+	//     are known without a reference decode.
+	{
+		const tiffFile = new mod.TiffFile(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'tiled_grid_u16_deflate.tif'))), 0);
+		assert.strictEqual(tiffFile.tiles_across, 3, 'tiles across');
+		assert.strictEqual(tiffFile.tiles_down, 2, 'tiles down');
+		for (let tileY = 0; tileY < 2; tileY++) {
+			for (let tileX = 0; tileX < 3; tileX++) {
+				const [x0, y0, w, h] = Array.from(tiffFile.tile_rect(tileX, tileY));
+				assert.deepStrictEqual([x0, y0, w, h], [tileX * 16, tileY * 16, Math.min(16, 40 - tileX * 16), Math.min(16, 30 - tileY * 16)],
+					`tile (${tileX}, ${tileY}) rect`);
+				const expected = [];
+				for (let y = y0; y < y0 + h; y++) {
+					for (let x = x0; x < x0 + w; x++) {
+						expected.push(y * 40 + x);
+					}
+				}
+				assert.deepStrictEqual(Array.from(tiffFile.decode_tile(tileX, tileY)), expected, `tile (${tileX}, ${tileY}) samples`);
+			}
+		}
+		assert.strictEqual(tiffFile.cached_chunk_count, 6, 'decoded tiles are cached');
+		assert.throws(() => tiffFile.decode_tile(3, 0), /outside the 3x2 tile grid/);
+		console.log('✅ TiffFile exposes its tile grid and decodes single tiles, edge tiles clipped');
+	}
+
 	// 6. WebP-compressed (compression 50001) decodes to RGB.
 	{
 		const webp = decode(mod, 'webp_rgb.tif');
@@ -356,6 +644,52 @@ async function main() {
 		console.log('✅ shapes_hyper.tif (RGB + 4 extra samples, SamplesPerPixel=7) decodes to exact ground-truth pixel values with the correct stride');
 	}
 
+	// 8b. Per-band colour interpretation. gdal_colorinterp_bgr_nir_alpha.tif is
+	//     a 5-band MinIsBlack page whose GDAL_METADATA names the bands Blue,
+	//     Green, Red, NIR and Alpha, so the default composite must be bands
+	//     2/1/0 rather than the first three. Without GDAL items the photometric
+	//     layout decides.
+	{
+		const gdal = JSON.parse(mod.decode_tiff(new Uint8Array(fs.readFileSync(
+			path.join(samplesDir, 'gdal_colorinterp_bgr_nir_alpha.tif')))).color_interpretation_json());
+		assert.deepStrictEqual(gdal, {
+			source: 'gdal',
+			bands: ['Blue', 'Green', 'Red', 'NIR', 'Alpha'],
+			composite: [2, 1, 0],
+		});
+		const hyper = JSON.parse(mod.decode_tiff(new Uint8Array(fs.readFileSync(
+			path.join(samplesDir, 'shapes_hyper.tif')))).color_interpretation_json());
+		assert.strictEqual(hyper.source, 'photometric');
+		assert.deepStrictEqual(hyper.bands.slice(0, 4), ['Red', 'Green', 'Blue', 'Undefined']);
+		assert.deepStrictEqual(hyper.composite, [0, 1, 2]);
+		console.log('✅ GDAL per-band colour interpretation drives the default composite');
+	}
+
+	// 8c. Sample value tags as the initial range. decode_tiff_fast skips the
+	//     stats pass, so a plausible MinSampleValue/MaxSampleValue (u16) or
+	//     SMinSampleValue/SMaxSampleValue (f32) range is reported instead;
+	//     a MaxSampleValue of 300 on 8-bit samples is ignored, and the full
+	//     decode always measures.
+	for (const [file, expected] of [
+		['sample_value_range_u16.tif', [0, 4095]],
+		['sample_value_range_f32.tif', [-1.5, 2.5]],
+		['sample_value_range_u8_implausible.tif', null],
+	]) {
+		const bytes = new Uint8Array(fs.readFileSync(path.join(samplesDir, file)));
+		const fast = mod.decode_tiff_fast(bytes);
+		assert.strictEqual(fast.range_from_tags, expected !== null, `${file}: range_from_tags`);
+		if (expected) {
+			assert.deepStrictEqual([fast.min_value, fast.max_value], expected, `${file}: tagged range`);
+		} else {
+			assert.ok(Number.isNaN(fast.min_value) && Number.isNaN(fast.max_value), `${file}: no range without a stats pass`);
+		}
+		const full = mod.decode_tiff(bytes);
+		const samples = Array.from(full.get_data_as_f32());
+		assert.strictEqual(full.range_from_tags, false, `${file}: full decode measures`);
+		assert.strictEqual(full.max_value, Math.fround(Math.max(...samples)), `${file}: measured max`);
+	}
+	console.log('✅ Plausible sample value tags seed the range of fast decodes');
+
 	// 9. Wide unsigned integer (uint32) samples must survive the Rust decoder
 	//    exactly, including values above 65535 (which a Uint16Array carrier
 	//    would wrap mod 65536). gray_u32.tif is a 64x48 grayscale uint32 image
@@ -484,6 +818,36 @@ async function main() {
 		console.log('✅ JPEG-YCbCr (photometric 6) honors the Orientation tag: orientation 4 vertically flips the decoded image');
 	}
 
+	// 11c. decode_tiff_region takes its rectangle in display coordinates, so
+	//      for every orientation the region must equal the same crop of the
+	//      full decode, with min/max over just the region. Tiled and palette
+	//      pages cover the chunk-skipping and crop-after-decode paths.
+	function cropRegion(data, width, channels, x, y, w, h) {
+		const out = [];
+		for (let row = y; row < y + h; row++) {
+			out.push(...data.slice((row * width + x) * channels, (row * width + x + w) * channels));
+		}
+		return out;
+	}
+	for (const [file, rect] of [
+		...[1, 2, 3, 4, 5, 6, 7, 8].map(orientation => [`orientation_tag${orientation}.tif`, [1, 2, 3, 2]]),
+		['tiled_padding_f32_deflate.tif', [7, 3, 6, 7]],
+		['palette.tif', [1, 1, 2, 3]],
+	]) {
+		const bytes = new Uint8Array(fs.readFileSync(path.join(samplesDir, file)));
+		const full = decode(mod, file);
+		const region = mod.decode_tiff_region(bytes, ...rect);
+		const expected = cropRegion(full.data, full.width, full.channels, ...rect);
+		assert.strictEqual(region.width, rect[2], `${file}: region width`);
+		assert.strictEqual(region.height, rect[3], `${file}: region height`);
+		assert.deepStrictEqual(Array.from(region.get_data_as_f32()), expected, `${file}: region pixels`);
+		assert.strictEqual(region.min_value, Math.min(...expected), `${file}: region min`);
+		assert.strictEqual(region.max_value, Math.max(...expected), `${file}: region max`);
+	}
+	assert.throws(() => mod.decode_tiff_region(
+		new Uint8Array(fs.readFileSync(path.join(samplesDir, 'orientation_tag6.tif'))), 3, 0, 3, 1));
+	console.log('✅ decode_tiff_region matches the display-space crop of the full decode for every orientation');
+
 	// 12. BigTIFF (8-byte offsets) regression guards: an uncompressed BigTIFF
 	//     and a Deflate-compressed tiled BigTIFF must decode with the correct
 	//     dimensions and exact pixel values. Ground truth extracted
@@ -531,6 +895,2156 @@ async function main() {
 		console.log('✅ Multi-page TIFF: page count, arbitrary IFD decoding, sample types, and page-local metadata');
 	}
 
+	// 14. Chunky pages whose output passes 32 MiB are decoded strip by strip
+	//     straight into the packed buffer. A 4096x2048 deflate page in eight
+	//     strips, pixel (x, y) = (7x + 3y) mod 256, must come out whole.
+	{
+		const bytes = buildTiff(4096, 2048, (i) => ((i % 4096) * 7 + Math.floor(i / 4096) * 3) & 255, { rowsPerStrip: 256, compression: 8 });
+		const img = mod.decode_tiff(bytes);
+		assert.deepStrictEqual([img.width, img.height, img.channels, img.strip_count], [4096, 2048, 1, 8]);
+		const data = img.get_data_bytes();
+		assert.strictEqual(data.length, 4096 * 2048);
+		for (let i = 0; i < data.length; i += 4099) {
+			assert.strictEqual(data[i], ((i % 4096) * 7 + Math.floor(i / 4096) * 3) & 255, `sample ${i}`);
+		}
+		assert.deepStrictEqual([img.min_value, img.max_value], [0, 255], 'stats are folded in per strip');
+		img.free();
+		console.log('✅ Large chunky pages stream strip by strip into the packed buffer');
+	}
+
+	// 15. float64 pages keep their samples as stored: get_data('auto') and
+	//     the stats are exact, and f32 is only a converted copy for rendering.
+	{
+		const values = Array.from({ length: 8 }, (_, i) => i * 0.1 - 0.25);
+		const img = mod.decode_tiff(buildTiff(4, 2, (i) => values[i], { bitsPerSample: 64, sampleFormat: 3 }));
+		assert.strictEqual(img.native_element, 'float64');
+		assert.deepStrictEqual(Array.from(img.get_data('auto')), values, 'native samples are bit-exact');
+		assert.deepStrictEqual([img.min_value, img.max_value], [values[0], values[7]], 'stats in double precision');
+		assert.deepStrictEqual(Array.from(img.get_data_as_f32()), values.map(Math.fround));
+		assert.deepStrictEqual(Array.from(img.take_data_as_f32()), values.map(Math.fround), 'the cached conversion is moved out');
+		assert.deepStrictEqual(Array.from(img.get_data('auto')), values, 'taking the f32 copy leaves the stored samples');
+		img.free();
+		console.log('✅ float64 samples stay native; f32 is converted lazily');
+	}
+
+	// 16. Uncompressed strips whose layout already matches the output are
+	//     copied (and byte-swapped if big-endian) without the tiff crate: a
+	//     5x3 big-endian uint16 RGB page in strips of 2 rows, sample i = 1000i.
+	{
+		const img = mod.decode_tiff(buildTiff(5, 3, (i) => i * 1000, {
+			bitsPerSample: 16, samplesPerPixel: 3, photometric: 2, rowsPerStrip: 2, littleEndian: false,
+		}));
+		assert.strictEqual(img.direct_decode, true, 'strips are copied directly');
+		assert.strictEqual(img.native_element, 'uint16');
+		assert.deepStrictEqual(Array.from(img.get_data('auto')), Array.from({ length: 45 }, (_, i) => i * 1000), 'byte-swapped samples, short last strip included');
+		assert.deepStrictEqual([img.min_value, img.max_value], [0, 44000]);
+		img.free();
+		console.log('✅ Layout-compatible uncompressed strips are copied straight into the output');
+	}
+
+	// 17. Planar/tiled blocks are decompressed in batches on the worker
+	//     pool (one worker in the WASM build) and must land in order: a
+	//     24x20 planar deflate uint16 RGB page in 21 strips of 3 rows,
+	//     sample i = 37i mod 65536.
+	{
+		const img = mod.decode_tiff(buildTiff(24, 20, (i) => (i * 37) & 0xffff, {
+			bitsPerSample: 16, samplesPerPixel: 3, photometric: 2, rowsPerStrip: 3, compression: 8, planar: true,
+		}));
+		assert.deepStrictEqual([img.planar_configuration, img.strip_count, img.direct_decode], [2, 21, true]);
+		assert.deepStrictEqual(Array.from(img.get_data_as_f32()), Array.from({ length: 24 * 20 * 3 }, (_, i) => (i * 37) & 0xffff),
+			'every block in place across batches');
+		assert.deepStrictEqual([img.min_value, img.max_value], [0, 1439 * 37]);
+		img.free();
+		console.log('✅ Planar strips decompressed in pool batches land in order');
+	}
+
+	// 18. decode_tiff_page_profiled records which path decoded the page and
+	//     each strip's compressed and decoded size; plain decodes skip it.
+	{
+		const planar = buildTiff(24, 20, (i) => i & 0xff, { samplesPerPixel: 3, photometric: 2, rowsPerStrip: 3, compression: 8, planar: true });
+		const profile = JSON.parse(mod.decode_tiff_page_profiled(planar, 0).chunk_profile_json);
+		assert.strictEqual(profile.path, 'planar_tiled');
+		assert.strictEqual(profile.chunk_kind, 'strip');
+		assert.deepStrictEqual(profile.chunks.map(chunk => chunk.index), Array.from({ length: 21 }, (_, i) => i));
+		assert.deepStrictEqual(profile.chunks.slice(5, 8).map(chunk => chunk.decoded_bytes), [72, 48, 72], 'the last strip of a band has 2 rows');
+		assert.ok(profile.chunks.every(chunk => chunk.compressed_bytes > 0 && chunk.decode_ms >= 0));
+
+		const copied = JSON.parse(mod.decode_tiff_page_profiled(buildTiff(5, 3, (i) => i, { rowsPerStrip: 2 }), 0).chunk_profile_json);
+		assert.strictEqual(copied.path, 'uncompressed_copy');
+		assert.deepStrictEqual(copied.chunks.map(chunk => [chunk.compressed_bytes, chunk.decoded_bytes]), [[10, 10], [5, 5]]);
+		assert.strictEqual(mod.decode_tiff(planar).chunk_profile_json, '', 'profiling is opt-in');
+		console.log('✅ decode_tiff_page_profiled: per-strip sizes and timings');
+	}
+
+	// 19. TiffFile decodes only the tiles a viewport overlaps and keeps them
+	//     in an LRU. shapes_tiled_multi.tif is 128x72 RGB in 32x32 LZW tiles
+	//     (4x3), with its samples in shapes_tiled_multi.gt.u8.bin.
+	{
+		const truth = fs.readFileSync(path.join(samplesDir, 'shapes_tiled_multi.gt.u8.bin'));
+		const crop = (x0, y0, width, height, step) => {
+			const values = [];
+			for (let y = y0; y < y0 + height; y += step) {
+				for (let x = x0; x < x0 + width; x += step) {
+					values.push(...truth.subarray((y * 128 + x) * 3, (y * 128 + x) * 3 + 3));
+				}
+			}
+			return values;
+		};
+		const tiffFile = new mod.TiffFile(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'shapes_tiled_multi.tif'))), 0);
+		assert.deepStrictEqual([tiffFile.width, tiffFile.height, tiffFile.channels, tiffFile.chunk_count], [128, 72, 3, 12]);
+		assert.deepStrictEqual(Array.from(tiffFile.read_region(40, 8, 30, 20, 1)), crop(40, 8, 30, 20, 1));
+		assert.strictEqual(tiffFile.cached_chunk_count, 2, 'only the two overlapped tiles are decoded');
+		assert.deepStrictEqual(Array.from(tiffFile.read_region(0, 0, 128, 72, 2)), crop(0, 0, 128, 72, 2), 'every second pixel');
+		assert.strictEqual(tiffFile.cached_chunk_count, 12);
+		tiffFile.set_cache_capacity(4);
+		assert.strictEqual(tiffFile.cached_chunk_count, 4, 'least recently used tiles are evicted');
+		assert.deepStrictEqual(Array.from(tiffFile.read_region(40, 8, 30, 20, 1)), crop(40, 8, 30, 20, 1), 'evicted tiles decode again');
+		assert.throws(() => tiffFile.read_region(100, 0, 40, 10, 1), /outside the 128x72 image/);
+		tiffFile.free();
+		console.log('✅ TiffFile: viewport regions from on-demand tiles with an LRU');
+	}
+
+	// 20. TiffFile.decode_rows reads full-width scanlines from only the
+	//     strips (or tile rows) covering them.
+	{
+		const striped = new mod.TiffFile(buildTiff(10, 12, (i) => i, { bitsPerSample: 16, rowsPerStrip: 4 }), 0);
+		assert.deepStrictEqual(Array.from(striped.decode_rows(3, 2)), Array.from({ length: 20 }, (_, i) => 30 + i));
+		assert.strictEqual(striped.cached_chunk_count, 2, 'rows 3-4 span strips 0 and 1');
+		assert.throws(() => striped.decode_rows(11, 2), /outside the 10x12 image/);
+		striped.free();
+
+		const tiled = new mod.TiffFile(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'tiled_grid_u16_deflate.tif'))), 0);
+		const expected = [];
+		for (let y = 14; y < 17; y++) {
+			for (let x = 0; x < 40; x++) {
+				expected.push(y * 40 + x);
+			}
+		}
+		assert.deepStrictEqual(Array.from(tiled.decode_rows(14, 3)), expected, 'rows across the tile row boundary at 16');
+		assert.strictEqual(tiled.cached_chunk_count, 6);
+		tiled.free();
+		console.log('✅ TiffFile.decode_rows: scanline ranges from the covering strips/tiles');
+	}
+
+	// 21. Sparse strips (zero offset and byte count) read back as the GDAL
+	//     nodata value, or 0 without one, in full decodes and TiffFile
+	//     reads alike. 4x6 int16 pages in strips of 2 rows, sample i = i.
+	{
+		const ramp = Array.from({ length: 24 }, (_, i) => i);
+		const withNodata = buildTiff(4, 6, (i) => i, {
+			bitsPerSample: 16, sampleFormat: 2, rowsPerStrip: 2, sparseStrips: [1], extraTags: [[42113, 2, '-9999']],
+		});
+		const img = mod.decode_tiff(withNodata);
+		const filled = ramp.map(v => (v >= 8 && v < 16 ? -9999 : v));
+		assert.deepStrictEqual(Array.from(img.get_data_as_f32()), filled);
+		assert.deepStrictEqual([img.min_value, img.max_value], [-9999, 23]);
+		const tiffFile = new mod.TiffFile(withNodata, 0);
+		assert.deepStrictEqual(Array.from(tiffFile.decode_rows(1, 4)), filled.slice(4, 20));
+		[img, tiffFile].forEach(o => o.free());
+
+		const deflated = mod.decode_tiff(buildTiff(4, 6, (i) => i, { bitsPerSample: 16, sampleFormat: 2, rowsPerStrip: 2, compression: 8, sparseStrips: [2] }));
+		assert.deepStrictEqual(Array.from(deflated.get_data_as_f32()), ramp.map(v => (v >= 16 ? 0 : v)), 'zero-filled without GDAL_NODATA');
+		deflated.free();
+		console.log('✅ Sparse strips are filled with nodata');
+	}
+
+	// 22. Session overviews: a pyramid of 2x2 averages built from a full
+	//     decode and cached by key, evicted least recently used beyond the
+	//     byte budget. tiled_grid_u16_deflate.tif is 40x30, pixel (x, y) =
+	//     40y + x, so the first level's pixel (x, y) is 80y + 2x + 20.5.
+	{
+		const img = mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'tiled_grid_u16_deflate.tif'))));
+		assert.strictEqual(img.build_session_overviews('grid', 8), 3);
+		assert.strictEqual(mod.session_overview_count('grid'), 3);
+		const sizes = [0, 1, 2].map(level => {
+			const overview = mod.session_overview('grid', level);
+			const size = [overview.width, overview.height, overview.channels];
+			if (level === 0) {
+				assert.deepStrictEqual(Array.from(overview.get_data_as_f32()).slice(20, 23), [100.5, 102.5, 104.5], 'row 1 of level 0');
+			}
+			overview.free();
+			return size;
+		});
+		assert.deepStrictEqual(sizes, [[20, 15, 1], [10, 8, 1], [5, 4, 1]]);
+		assert.strictEqual(mod.session_overview_bytes(), (300 + 80 + 20) * 4);
+		assert.throws(() => mod.session_overview('grid', 3), /No session overview level 3 for 'grid'/);
+
+		img.build_session_overviews('a', 8);
+		img.build_session_overviews('b', 8);
+		mod.set_session_overview_byte_budget(2 * 1600);
+		assert.deepStrictEqual(['grid', 'a', 'b'].map(key => mod.session_overview_count(key)), [0, 3, 3], 'the least recently used pyramid goes first');
+		mod.clear_session_overviews('a');
+		assert.strictEqual(mod.session_overview_count('a'), 0);
+		mod.clear_all_session_overviews();
+		assert.strictEqual(mod.session_overview_bytes(), 0);
+		mod.set_session_overview_byte_budget(256 * 1024 * 1024);
+		img.free();
+		console.log('✅ Session overviews: cached pyramids with a byte budget');
+	}
+
+	// 23. Per-band access on RGB pages with extra samples: shapes_hyper.tif
+	//     (128x72, 7 float bands) has bands 3-6 constant at 0.1, 0.2, 0.3
+	//     and 0.5 and band 0 spanning 0..1.
+	{
+		const img = mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'shapes_hyper.tif'))));
+		const band3 = img.get_band_as_f32(3);
+		assert.strictEqual(band3.length, 128 * 72);
+		assert.ok(band3.every(v => v === Math.fround(0.1)), 'band 3 is constant');
+		assert.deepStrictEqual(Array.from(img.band_min_max(4)), [Math.fround(0.2), Math.fround(0.2)]);
+		assert.deepStrictEqual(Array.from(img.band_min_max(0)), [0, 1]);
+		const composite = img.composite_bands_as_f32(3, 0, 6);
+		assert.strictEqual(composite.length, 128 * 72 * 3);
+		assert.deepStrictEqual(Array.from(composite.slice(0, 3)), [0.1, 1, 0.5].map(Math.fround), 'bands 3, 0 and 6 at (0, 0)');
+		assert.throws(() => img.get_band_as_f32(7), /Band 7 is out of range \(image has 7 channel\(s\)\)/);
+		img.free();
+		console.log('✅ Per-band accessors on multispectral RGB pages');
+	}
+
+	// 24. aggregate_bands_as_f32 collapses [start, end) band ranges into
+	//     one channel each: on shapes_hyper.tif bands 3-5 are 0.1, 0.2 and
+	//     0.3 everywhere.
+	{
+		const img = mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'shapes_hyper.tif'))));
+		const mean = img.aggregate_bands_as_f32(new Uint32Array([3, 6]), false);
+		assert.strictEqual(mean.length, 128 * 72);
+		assert.ok(mean.every(v => v === Math.fround(0.2)), 'mean of bands 3-5');
+		const sums = img.aggregate_bands_as_f32(new Uint32Array([3, 6, 0, 1]), true);
+		assert.strictEqual(sums.length, 128 * 72 * 2, 'one channel per range');
+		assert.deepStrictEqual(Array.from(sums.slice(0, 2)), [Math.fround(0.6), 1], 'sum of bands 3-5, and band 0 at (0, 0)');
+		assert.throws(() => img.aggregate_bands_as_f32(new Uint32Array([3]), true), /non-empty list of \[start, end\) pairs/);
+		assert.throws(() => img.aggregate_bands_as_f32(new Uint32Array([5, 9]), true), /Band range \[5, 9\) is empty or out of range/);
+		img.free();
+		console.log('✅ aggregate_bands_as_f32: band-range sums and means');
+	}
+
+	// 25. spectrum_at reads every band of one pixel, from one chunk per band
+	//     for planar files and straight from the bytes when uncompressed; it
+	//     must agree with the ground truth of the layout fixtures.
+	for (const [file, gtFile] of [
+		['shapes_lzw_planar.tif', 'shapes_lzw_planar.gt.u8.bin'],
+		['shapes_uncompressed_tiled_planar.tif', 'shapes_uncompressed_tiled_planar.gt.u8.bin'],
+		['shapes_tiled_multi.tif', 'shapes_tiled_multi.gt.u8.bin'],
+	]) {
+		const bytes = new Uint8Array(fs.readFileSync(path.join(samplesDir, file)));
+		const truth = fs.readFileSync(path.join(samplesDir, gtFile));
+		for (const [x, y] of [[0, 0], [20, 50], [90, 15], [127, 71]]) {
+			assert.deepStrictEqual(Array.from(mod.spectrum_at(bytes, 0, x, y)), Array.from(truth.subarray((y * 128 + x) * 3, (y * 128 + x) * 3 + 3)),
+				`${file}: pixel (${x}, ${y})`);
+		}
+	}
+	{
+		const hyper = mod.spectrum_at(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'shapes_hyper.tif'))), 0, 20, 50);
+		assert.deepStrictEqual(Array.from(hyper), [0.72156864, 0.87058824, 0.25882354, 0.1, 0.2, 0.3, 0.5].map(Math.fround), 'all 7 bands');
+	}
+	console.log('✅ spectrum_at: per-pixel band profiles');
+
+	// 26. summary() reports the layout, nodata, georeferencing and per-channel
+	//     statistics in one JSON object. gdal_band_descriptions_f32.tif is
+	//     3x2 with bands 1.5..6.5, 0..5/6 and 100..105.
+	{
+		const summary = (file) => {
+			const img = mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, file))));
+			const json = JSON.parse(img.summary());
+			img.free();
+			return json;
+		};
+		const bands = summary('gdal_band_descriptions_f32.tif');
+		assert.deepStrictEqual([bands.width, bands.height, bands.channels, bands.dtype, bands.tiled, bands.nodata], [3, 2, 3, 'float32', false, null]);
+		const [depth, , offset] = bands.channel_stats;
+		assert.deepStrictEqual([depth.min, depth.max, depth.mean, depth.p25, depth.p50, depth.p75], [1.5, 6.5, 4, 2.5, 4.5, 5.5]);
+		assert.deepStrictEqual([offset.min, offset.max, offset.mean], [100, 105, 102.5]);
+		const special = summary('special_values_f32.tif').channel_stats[0];
+		assert.deepStrictEqual([special.nan_count, special.inf_count, special.nodata_count, special.min, special.max], [2, 3, 1, 1, 2],
+			'NaN, ±Inf and nodata are counted, not measured');
+		assert.strictEqual(summary('special_values_f32.tif').nodata, -9999);
+		assert.deepStrictEqual(summary('geo_orientation6_u8.tif').geo.pixel_scale, [2, 3, 0]);
+		console.log('✅ summary(): layout, nodata, geo and per-channel stats');
+	}
+
+	// 27. Joint histograms pair two bands pixel by pixel. In
+	//     gdal_band_descriptions_f32.tif bands 0 and 2 both rise by 1 per
+	//     pixel, so every count sits on the diagonal (the last bin holds
+	//     its upper edge too).
+	{
+		const open = (file) => mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, file))));
+		const img = open('gdal_band_descriptions_f32.tif');
+		const diagonal = img.joint_histogram(0, 2, 5, 5);
+		assert.deepStrictEqual([diagonal.bins_x, diagonal.bins_y, diagonal.x_min, diagonal.x_max, diagonal.y_min, diagonal.y_max], [5, 5, 1.5, 6.5, 100, 105]);
+		const counts = Array.from(diagonal.get_counts());
+		assert.deepStrictEqual([0, 1, 2, 3, 4].map(i => counts[i * 5 + i]), [1, 1, 1, 1, 2]);
+		assert.strictEqual(counts.reduce((a, b) => a + b), 6);
+		const against = img.joint_histogram_with(1, img, 0, 2, 3);
+		assert.deepStrictEqual(Array.from(against.get_counts()), [2, 0, 1, 1, 0, 2]);
+		assert.strictEqual(against.x_max, Math.fround(5 / 6));
+		const bilevel = open('ccitt_none.tif');
+		assert.throws(() => img.joint_histogram_with(0, bilevel, 0, 4, 4), /image sizes differ \(3x2 vs 128x96\)/);
+		assert.throws(() => img.joint_histogram(0, 1, 0, 4), /bin counts must be non-zero/);
+		[img, bilevel, diagonal, against].forEach(o => o.free());
+		console.log('✅ joint_histogram / joint_histogram_with: 2D band histograms');
+	}
+
+	// 28. layout() describes the buffers get_data_bytes / get_data_as_f32
+	//     return: element type, strides and channel names.
+	{
+		const layout = (file) => {
+			const img = mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, file))));
+			const json = JSON.parse(img.layout());
+			img.free();
+			return json;
+		};
+		const grid = layout('tiled_grid_u16_deflate.tif');
+		assert.deepStrictEqual([grid.width, grid.height, grid.channels, grid.order, grid.interleave, grid.origin], [40, 30, 1, 'row-major', 'pixel', 'top-left']);
+		assert.deepStrictEqual(grid.bytes, { element: 'uint16', significant_bits: 16, endianness: 'little', pixel_stride_bytes: 2, row_stride_bytes: 80 });
+		assert.deepStrictEqual(grid.f32, { pixel_stride: 1, row_stride: 40 });
+		assert.deepStrictEqual(grid.channel_names, ['gray']);
+		const hyper = layout('shapes_hyper.tif');
+		assert.deepStrictEqual(hyper.channel_names, ['red', 'green', 'blue', 'band3', 'band4', 'band5', 'band6']);
+		assert.deepStrictEqual([hyper.bytes.pixel_stride_bytes, hyper.bytes.row_stride_bytes, hyper.f32.row_stride], [28, 128 * 28, 128 * 7]);
+		console.log('✅ layout(): output buffer description');
+	}
+
+	// 29. TIFFs inside ZIP archives are listed (directories, macOS metadata
+	//     and other files skipped) and decoded without extracting the rest.
+	{
+		const ramp = buildTiff(4, 3, (i) => i * 10, { bitsPerSample: 16 });
+		const bilevel = new Uint8Array(fs.readFileSync(path.join(samplesDir, 'ccitt_none.tif')));
+		const zip = buildZip([
+			['readme.txt', Buffer.from('hello'), false],
+			['data/', new Uint8Array(0), false],
+			['data/ramp.tif', ramp, true],
+			['__MACOSX/data/._ramp.tif', new Uint8Array(4), false],
+			['ccitt.TIFF', bilevel, false],
+		]);
+		const listed = JSON.parse(mod.zip_list_tiffs(zip));
+		assert.deepStrictEqual(listed.map(entry => [entry.name, entry.size]), [['data/ramp.tif', ramp.length], ['ccitt.TIFF', bilevel.length]]);
+		assert.strictEqual(listed[1].compressed_size, bilevel.length, 'stored entry');
+		const img = mod.decode_zip_tiff_page(zip, 'data/ramp.tif', 0);
+		assert.deepStrictEqual(Array.from(img.get_data_as_f32()), Array.from({ length: 12 }, (_, i) => i * 10), 'deflated entry');
+		const stored = mod.decode_zip_tiff_page(zip, 'ccitt.TIFF', 0);
+		assert.deepStrictEqual([stored.width, stored.height], [128, 96]);
+		assert.throws(() => mod.decode_zip_tiff_page(zip, 'missing.tif', 0), /ZIP: no entry named 'missing.tif'/);
+		assert.throws(() => mod.zip_list_tiffs(ramp), /not a ZIP archive/);
+		[img, stored].forEach(o => o.free());
+		console.log('✅ zip_list_tiffs / decode_zip_tiff_page: TIFFs inside ZIP archives');
+	}
+
+	// 30. Gzipped TIFFs (.tif.gz) are inflated transparently, also when they
+	//     are split over several gzip members or stored inside a ZIP.
+	{
+		const plain = buildTiff(5, 4, (i) => i * 3, { bitsPerSample: 16, rowsPerStrip: 2, compression: 8 });
+		const expected = Array.from({ length: 20 }, (_, i) => i * 3);
+		const gz = new Uint8Array(zlib.gzipSync(plain));
+		const half = plain.length >> 1;
+		const members = new Uint8Array(Buffer.concat([zlib.gzipSync(plain.subarray(0, half)), zlib.gzipSync(plain.subarray(half))]));
+		for (const bytes of [gz, members]) {
+			const img = mod.decode_tiff(bytes);
+			assert.deepStrictEqual([img.width, img.height], [5, 4]);
+			assert.deepStrictEqual(Array.from(img.get_data_as_f32()), expected);
+			img.free();
+		}
+		assert.strictEqual(mod.tiff_page_count(gz), 1);
+		const file = new mod.TiffFile(gz, 0);
+		assert.deepStrictEqual([file.width, file.height], [5, 4]);
+		file.free();
+		const zip = buildZip([['a/b.tif.gz', gz, false], ['c.gz', gz, false]]);
+		assert.deepStrictEqual(JSON.parse(mod.zip_list_tiffs(zip)).map(entry => entry.name), ['a/b.tif.gz']);
+		const zipped = mod.decode_zip_tiff_page(zip, 'a/b.tif.gz', 0);
+		assert.deepStrictEqual(Array.from(zipped.get_data_as_f32()), expected);
+		zipped.free();
+		assert.throws(() => mod.decode_tiff(gz.subarray(0, 20)), /Failed to gunzip input/);
+		console.log('✅ .tif.gz input inflated transparently');
+	}
+
+	// 31. decode_image picks the decoder from the magic bytes, so the
+	//     result matches the format's own entry point; containers it cannot
+	//     open as one image are rejected by name.
+	{
+		const sample = (file) => new Uint8Array(fs.readFileSync(path.join(samplesDir, file)));
+		const png = sample('png_u8_rgb.png');
+		const tiff = sample('rgb_none.tif');
+		assert.strictEqual(mod.sniff_image_format(tiff), 'tiff');
+		assert.strictEqual(mod.sniff_image_format(sample('bigtiff_u16.tif')), 'tiff');
+		assert.strictEqual(mod.sniff_image_format(png), 'png');
+		assert.strictEqual(mod.sniff_image_format(sample('pfm_gray.pfm')), 'pfm');
+		assert.strictEqual(mod.sniff_image_format(sample('npy_u16_gray.npy')), 'npy');
+		assert.strictEqual(mod.sniff_image_format(new Uint8Array([0xff, 0xd8, 0xff, 0xe0])), 'jpeg');
+		assert.strictEqual(mod.sniff_image_format(sample('npz_f32.npz')), 'zip');
+		assert.strictEqual(mod.sniff_image_format(new Uint8Array(zlib.gzipSync(png))), 'gzip', 'not the format inside');
+		assert.strictEqual(mod.sniff_image_format(new TextEncoder().encode('not an image')), '');
+		for (const [bytes, decode] of [[png, mod.decode_png], [tiff, mod.decode_tiff], [new Uint8Array(zlib.gzipSync(png)), mod.decode_png]]) {
+			const viaImage = mod.decode_image(bytes);
+			const direct = decode(bytes[0] === 0x1f ? png : bytes);
+			assert.deepStrictEqual([viaImage.width, viaImage.height, viaImage.channels], [direct.width, direct.height, direct.channels]);
+			assert.deepStrictEqual(viaImage.get_data_as_f32(), direct.get_data_as_f32());
+			[viaImage, direct].forEach(o => o.free());
+		}
+		const pfm = mod.decode_image(sample('pfm_gray.pfm'));
+		assert.deepStrictEqual([pfm.width, pfm.height, pfm.channels, pfm.bits_per_sample, pfm.sample_format], [10, 7, 1, 32, 3]);
+		pfm.free();
+		const npy = mod.decode_image(sample('npy_u16_gray.npy'));
+		assert.deepStrictEqual([npy.width, npy.height, npy.bits_per_sample], [12, 8, 16]);
+		assert.deepStrictEqual(Array.from(npy.get_data_as_f32().subarray(0, 3)), [0, 689, 1379]);
+		npy.free();
+		assert.throws(() => mod.decode_image(buildZip([['a.png', png, false]])), /ZIP archives hold several images/);
+		assert.throws(() => mod.decode_image(new Uint8Array(zlib.gzipSync(zlib.gzipSync(png)))), /nested gzip streams are not supported/);
+		console.log('✅ decode_image(): magic-byte sniffing routes each format to its decoder');
+	}
+
+	// 32. decode_png keeps 16-bit samples at full precision and expands
+	//     palette images (here 2-bit, with a tRNS chunk) to 8-bit RGBA.
+	{
+		const sample = (file) => new Uint8Array(fs.readFileSync(path.join(samplesDir, file)));
+		const depth = mod.decode_png(sample('png_u16_gray.png'));
+		assert.deepStrictEqual([depth.width, depth.height, depth.channels, depth.bits_per_sample], [16, 10, 1, 16]);
+		assert.deepStrictEqual([depth.min_value, depth.max_value], [0, 65535]);
+		assert.deepStrictEqual(Array.from(depth.get_data_as_f32().subarray(0, 4)), [0, 412, 824, 1236], 'not clamped to 8 bits');
+		depth.free();
+		const rgb = mod.decode_png(sample('png_u8_rgb.png'));
+		assert.deepStrictEqual([rgb.width, rgb.height, rgb.channels, rgb.bits_per_sample], [16, 10, 3, 8]);
+		rgb.free();
+		const chunk = (type, data) => {
+			const bytes = Buffer.alloc(12 + data.length);
+			bytes.writeUInt32BE(data.length, 0);
+			bytes.write(type, 4, 'latin1');
+			Buffer.from(data).copy(bytes, 8);
+			bytes.writeUInt32BE(crc32(bytes.subarray(4, 8 + data.length)), 8 + data.length);
+			return bytes;
+		};
+		const header = Buffer.alloc(13);
+		header.writeUInt32BE(3, 0);
+		header.writeUInt32BE(2, 4);
+		header[8] = 2;
+		header[9] = 3;
+		const palette = new Uint8Array(Buffer.concat([
+			Buffer.from([137, 80, 78, 71, 13, 10, 26, 10]),
+			chunk('IHDR', header),
+			chunk('PLTE', [255, 0, 0, 0, 255, 0, 0, 0, 255, 10, 20, 30]),
+			chunk('tRNS', [0, 128]),
+			chunk('IDAT', zlib.deflateSync(Buffer.from([0, 0b00011011, 0, 0b11100100]))),
+			chunk('IEND', []),
+		]));
+		const expanded = mod.decode_png(palette);
+		assert.deepStrictEqual([expanded.width, expanded.height, expanded.channels, expanded.bits_per_sample], [3, 2, 4, 8]);
+		assert.deepStrictEqual(Array.from(expanded.get_data_as_f32()), [
+			255, 0, 0, 0, 0, 255, 0, 128, 0, 0, 255, 255,
+			10, 20, 30, 255, 0, 0, 255, 255, 0, 255, 0, 128,
+		]);
+		expanded.free();
+		assert.throws(() => mod.decode_png(palette.subarray(0, 30)), /PNG/);
+		console.log('✅ decode_png(): 16-bit samples kept, palette + tRNS expanded to RGBA');
+	}
+
+	// 33. decode_jpeg returns the same pixels as the JPEG-in-TIFF path and
+	//     applies an EXIF Orientation. jpeg_ycbcr_color.tif holds one
+	//     complete 96x64 JPEG stream (no JPEGTables) at offset 352.
+	{
+		const tiffBytes = fs.readFileSync(path.join(samplesDir, 'jpeg_ycbcr_color.tif'));
+		const jpeg = new Uint8Array(tiffBytes.subarray(352, 352 + 9018));
+		const viaTiff = mod.decode_tiff(new Uint8Array(tiffBytes));
+		const photo = mod.decode_jpeg(jpeg);
+		assert.deepStrictEqual([photo.width, photo.height, photo.channels, photo.bits_per_sample], [96, 64, 3, 8]);
+		const pixels = photo.get_data_as_f32();
+		assert.deepStrictEqual(pixels, viaTiff.get_data_as_f32());
+		assert.deepStrictEqual(Uint8Array.from(pixels), mod.decode_jpeg_fast(jpeg).take_data_as_u8());
+		assert.strictEqual(mod.sniff_image_format(jpeg), 'jpeg');
+		// APP1 "Exif" segment holding a one-entry IFD: Orientation = 6.
+		const exif = Buffer.from([
+			0x45, 0x78, 0x69, 0x66, 0, 0, 0x49, 0x49, 42, 0, 8, 0, 0, 0,
+			1, 0, 0x12, 0x01, 3, 0, 1, 0, 0, 0, 6, 0, 0, 0, 0, 0, 0, 0,
+		]);
+		const rotated = mod.decode_jpeg(new Uint8Array(Buffer.concat([
+			jpeg.subarray(0, 2), Buffer.from([0xff, 0xe1, 0, exif.length + 2]), exif, jpeg.subarray(2),
+		])));
+		assert.deepStrictEqual([rotated.width, rotated.height, rotated.orientation], [64, 96, 6]);
+		assert.match(rotated.all_tags_json, /"Orientation"/);
+		const turned = rotated.get_data_as_f32();
+		for (const [x, y] of [[0, 0], [63, 0], [10, 50], [63, 95]]) {
+			const source = ((63 - x) * 96 + y) * 3;
+			assert.deepStrictEqual(Array.from(turned.subarray((y * 64 + x) * 3, (y * 64 + x) * 3 + 3)), Array.from(pixels.subarray(source, source + 3)), `pixel (${x}, ${y})`);
+		}
+		[viaTiff, photo, rotated].forEach(o => o.free());
+		assert.throws(() => mod.decode_jpeg(jpeg.subarray(0, 100)), /JPEG/);
+		console.log('✅ decode_jpeg(): photos through TiffResult, EXIF orientation applied');
+	}
+
+	// 34. decode_webp (directly and through decode_image) matches the
+	//     WebP-in-TIFF path. webp_rgb.tif holds one complete lossless 160x120
+	//     RIFF/WEBP stream at offset 272.
+	{
+		const tiffBytes = fs.readFileSync(path.join(samplesDir, 'webp_rgb.tif'));
+		const webp = new Uint8Array(tiffBytes.subarray(272, 272 + 6020));
+		assert.strictEqual(mod.sniff_image_format(webp), 'webp');
+		const viaTiff = mod.decode_tiff(new Uint8Array(tiffBytes));
+		const still = mod.decode_webp(webp);
+		const viaImage = mod.decode_image(webp);
+		assert.deepStrictEqual([still.width, still.height, still.channels, still.bits_per_sample], [160, 120, 3, 8]);
+		assert.deepStrictEqual(still.get_data_as_f32(), viaTiff.get_data_as_f32());
+		assert.deepStrictEqual(viaImage.get_data_as_f32(), viaTiff.get_data_as_f32());
+		[viaTiff, still, viaImage].forEach(o => o.free());
+		assert.throws(() => mod.decode_webp(webp.subarray(0, 8)), /WebP: failed to read header/);
+		console.log('✅ decode_webp(): WebP stills through TiffResult');
+	}
+
+	// 35. .npz archives: npz_list_arrays describes every image-shaped array
+	//     (more than 4 dimensions are left out) and decode_npz_array reads
+	//     one, stored or deflated, by the key it was saved under.
+	{
+		const sample = mod.npz_list_arrays(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'npz_f32.npz'))));
+		assert.deepStrictEqual(JSON.parse(sample), [{ name: 'arr_0', dtype: '<f4', shape: [6, 6], width: 6, height: 6, channels: 1, pages: 1 }]);
+		const npy = (descr, shape, values) => {
+			let header = `{'descr': '${descr}', 'fortran_order': False, 'shape': (${shape.join(', ')}${shape.length === 1 ? ',' : ''}), }`;
+			header = header.padEnd(Math.ceil((header.length + 11) / 64) * 64 - 11) + '\n';
+			const prefix = Buffer.alloc(10);
+			Buffer.from([0x93, ...Buffer.from('NUMPY'), 1, 0]).copy(prefix);
+			prefix.writeUInt16LE(header.length, 8);
+			return new Uint8Array(Buffer.concat([prefix, Buffer.from(header, 'latin1'), Buffer.from(values.buffer)]));
+		};
+		const npz = buildZip([
+			['depth.npy', npy('<f4', [3, 4], Float32Array.from({ length: 12 }, (_, i) => i / 2)), true],
+			['rgb.npy', npy('|u1', [2, 3, 3], Uint8Array.from({ length: 18 }, (_, i) => i)), false],
+			['stack.npy', npy('<u2', [3, 2, 5], Uint16Array.from({ length: 30 }, (_, i) => 1000 + i)), true],
+			['deep.npy', npy('<f4', [1, 1, 1, 1, 2], new Float32Array(2)), false],
+		]);
+		const listed = JSON.parse(mod.npz_list_arrays(npz));
+		assert.deepStrictEqual(listed.map(a => [a.name, a.dtype, a.width, a.height, a.channels, a.pages]), [
+			['depth', '<f4', 4, 3, 1, 1],
+			['rgb', '|u1', 3, 2, 3, 1],
+			['stack', '<u2', 5, 2, 1, 3],
+		]);
+		const depth = mod.decode_npz_array(npz, 'depth', 0);
+		assert.deepStrictEqual([depth.sample_format, depth.bits_per_sample], [3, 32]);
+		assert.deepStrictEqual(Array.from(depth.get_data_as_f32()), Array.from({ length: 12 }, (_, i) => i / 2));
+		const rgb = mod.decode_npz_array(npz, 'rgb.npy', 0);
+		assert.deepStrictEqual([rgb.width, rgb.height, rgb.channels], [3, 2, 3]);
+		assert.deepStrictEqual(Array.from(rgb.get_data_as_f32()), Array.from({ length: 18 }, (_, i) => i));
+		const page = mod.decode_npz_array(npz, 'stack', 1);
+		assert.deepStrictEqual([page.width, page.height, page.bits_per_sample], [5, 2, 16]);
+		assert.deepStrictEqual(Array.from(page.get_data_as_f32()), Array.from({ length: 10 }, (_, i) => 1010 + i));
+		[depth, rgb, page].forEach(o => o.free());
+		assert.throws(() => mod.decode_npz_array(npz, 'missing', 0), /NPZ: no array named 'missing'/);
+		console.log('✅ npz_list_arrays / decode_npz_array: arrays inside NumPy .npz archives');
+	}
+
+	// 36. Middlebury .flo: decode_flo reads the (u, v) pairs (unknown flow,
+	//     > 1e9, becomes NaN) and flow_color_wheel renders them, normalized
+	//     by flow_max_magnitude unless a maximum is given.
+	{
+		const vectors = [3, 4, 0, 0, -1, 0, 1e10, 0, 0, -2, 6, 8];
+		const flo = Buffer.alloc(12 + vectors.length * 4);
+		flo.write('PIEH', 0, 'latin1');
+		flo.writeInt32LE(3, 4);
+		flo.writeInt32LE(2, 8);
+		vectors.forEach((v, i) => flo.writeFloatLE(v, 12 + i * 4));
+		const bytes = new Uint8Array(flo);
+		assert.strictEqual(mod.sniff_image_format(bytes), 'flo');
+		const flow = mod.decode_flo(bytes);
+		assert.deepStrictEqual([flow.width, flow.height, flow.channels, flow.sample_format], [3, 2, 2, 3]);
+		assert.deepStrictEqual(Array.from(flow.get_data_as_f32()), [3, 4, 0, 0, -1, 0, NaN, 0, 0, -2, 6, 8]);
+		assert.strictEqual(flow.flow_max_magnitude(), 10);
+		assert.deepStrictEqual(Array.from(flow.flow_color_wheel(0)), [
+			255, 195, 128, 255, 255, 255, 255, 255, 230, 250, 255, 255,
+			0, 0, 0, 0, 222, 204, 255, 255, 255, 135, 0, 255,
+		], 'zero flow is white, unknown flow transparent, the longest vector saturated');
+		const capped = Array.from(flow.flow_color_wheel(5));
+		assert.deepStrictEqual(capped.slice(0, 4), [255, 135, 0, 255], 'a vector at the maximum is saturated');
+		assert.deepStrictEqual(capped.slice(20), [191, 102, 0, 255], 'vectors beyond it are darkened');
+		flow.free();
+		const viaImage = mod.decode_image(bytes);
+		assert.strictEqual(viaImage.channels, 2);
+		viaImage.free();
+		assert.throws(() => mod.decode_flo(bytes.subarray(0, 40)), /FLO/);
+		console.log('✅ decode_flo / flow_color_wheel: optical flow fields');
+	}
+
+	// 37. Photoshop layer data: photoshop_layer_names lists the layers of
+	//     the ImageSourceData blob and the tag dump summarises both Photoshop
+	//     blobs instead of listing their bytes; the page still decodes to
+	//     the flattened composite.
+	{
+		// ImageSourceData of a little-endian file: tagged blocks are signed
+		// "MIB8" with byte-reversed keys. The second layer's Pascal name is
+		// truncated and its full name sits in a "luni" block.
+		const u16 = (v) => { const b = Buffer.alloc(2); b.writeUInt16LE(v); return b; };
+		const u32 = (v) => { const b = Buffer.alloc(4); b.writeUInt32LE(v); return b; };
+		const block = (key, data) => Buffer.concat([
+			Buffer.from('MIB8'), Buffer.from(key).reverse(), u32(data.length), data, Buffer.alloc((4 - data.length % 4) % 4),
+		]);
+		const layer = (pascal, unicode) => {
+			const name = Buffer.concat([Buffer.from([pascal.length]), Buffer.from(pascal, 'latin1')]);
+			const extra = Buffer.concat([
+				u32(0), u32(0), name, Buffer.alloc((4 - name.length % 4) % 4),
+				unicode ? block('luni', Buffer.concat([u32(unicode.length), ...[...unicode].map(c => u16(c.charCodeAt(0)))])) : Buffer.alloc(0),
+			]);
+			return Buffer.concat([Buffer.alloc(16), u16(1), Buffer.alloc(6), Buffer.from('MIB8mron'), Buffer.from([255, 0, 0, 0]), u32(extra.length), extra]);
+		};
+		const sourceData = Buffer.concat([
+			Buffer.from('Adobe Photoshop Document Data Block\0', 'latin1'),
+			block('Layr', Buffer.concat([u16(2), layer('Background'), layer('Sky', 'Sky über Berlin')])),
+		]);
+		const layered = buildTiff(2, 2, (i) => i, { extraTags: [[37724, 7, [...sourceData]], [34377, 7, [...Buffer.from('8BIM')]]] });
+		assert.strictEqual(mod.photoshop_layer_names(layered, 0), '["Background","Sky über Berlin"]');
+		assert.strictEqual(mod.photoshop_layer_names(layered, 3), '[]', 'missing page');
+		assert.strictEqual(mod.photoshop_layer_names(buildTiff(2, 2, (i) => i), 0), '[]');
+		const img = mod.decode_tiff(layered);
+		assert.deepStrictEqual(Array.from(img.get_data_as_f32()), [0, 1, 2, 3]);
+		const tags = Object.fromEntries(JSON.parse(img.all_tags_json).map(entry => [entry.tag, entry.value]));
+		assert.strictEqual(tags[37724], `${sourceData.length} bytes (Photoshop layers: Background, Sky über Berlin)`);
+		assert.strictEqual(tags[34377], '4 bytes (Photoshop image resources)');
+		img.free();
+		console.log('✅ photoshop_layer_names(): layers summarised instead of dumped');
+	}
+
+	// 38. Stereo captures are found from metadata only (PageName /
+	//     ImageDescription) and rendered as a red-cyan anaglyph or side by
+	//     side, from two pages or from one packed page.
+	{
+		const left = buildTiff(4, 3, (i) => i, { extraTags: [[285, 2, 'Left eye']] });
+		const right = buildTiff(4, 3, (i) => 100 + i, { extraTags: [[285, 2, 'right eye']] });
+		const pair = joinTiffPages([left, right]);
+		assert.deepStrictEqual(JSON.parse(mod.detect_stereo(pair)), { layout: 'pages', left: 0, right: 1 });
+		assert.deepStrictEqual(JSON.parse(mod.detect_stereo(joinTiffPages([right, left]))), { layout: 'pages', left: 1, right: 0 });
+		const copyright = buildTiff(4, 3, (i) => i, { extraTags: [[270, 2, 'copyright']] });
+		assert.strictEqual(mod.detect_stereo(joinTiffPages([copyright, right])), 'null', 'whole words only');
+		const leftView = mod.decode_tiff_page(pair, 0);
+		const rightView = mod.decode_tiff_page(pair, 1);
+		const anaglyph = Array.from(leftView.anaglyph_with(rightView));
+		assert.deepStrictEqual(anaglyph.slice(0, 6), [0, 100, 100, 1, 101, 101]);
+		assert.strictEqual(anaglyph.length, 4 * 3 * 3);
+		assert.deepStrictEqual(Array.from(leftView.side_by_side_with(rightView)).slice(0, 8), [0, 1, 2, 3, 100, 101, 102, 103]);
+		[leftView, rightView].forEach(o => o.free());
+
+		const sbs = buildTiff(4, 2, (i) => i, { samplesPerPixel: 3, photometric: 2, extraTags: [[270, 2, 'Stereo SBS capture']] });
+		assert.deepStrictEqual(JSON.parse(mod.detect_stereo(sbs)), { layout: 'side_by_side', left: 0, right: 0 });
+		const packed = mod.decode_tiff(sbs);
+		assert.deepStrictEqual(Array.from(packed.packed_stereo_anaglyph('side_by_side')), [0, 7, 8, 3, 10, 11, 12, 19, 20, 15, 22, 23]);
+		packed.free();
+		const overUnder = buildTiff(2, 4, (i) => i, { extraTags: [[270, 2, 'over-under']] });
+		assert.strictEqual(JSON.parse(mod.detect_stereo(overUnder)).layout, 'over_under');
+		const stacked = mod.decode_tiff(overUnder);
+		assert.deepStrictEqual(Array.from(stacked.packed_stereo_anaglyph('over_under')), [0, 4, 4, 1, 5, 5, 2, 6, 6, 3, 7, 7]);
+		stacked.free();
+		console.log('✅ detect_stereo(): stereo pairs rendered as anaglyph / side by side');
+	}
+
+	// 39. Non-square pixels: XResolution 300 / YResolution 150 makes each
+	//     pixel half as wide as tall, so the 3x2 raster is stretched to 3x4
+	//     (linear along y); a transposing Orientation swaps the ratio.
+	{
+		const resolution = (x, y) => [[282, 5, [x, 1]], [283, 5, [y, 1]]];
+		const tall = mod.decode_tiff(buildTiff(3, 2, (i) => i * 10, { extraTags: resolution(300, 150) }));
+		assert.strictEqual(tall.pixel_aspect_ratio, 0.5);
+		assert.deepStrictEqual(Array.from(tall.square_pixel_dimensions()), [3, 4]);
+		assert.deepStrictEqual(Array.from(tall.resample_square_pixels_as_f32()), [0, 10, 20, 7.5, 17.5, 27.5, 22.5, 32.5, 42.5, 30, 40, 50]);
+		tall.free();
+		const turned = mod.decode_tiff(buildTiff(3, 2, (i) => i * 10, { extraTags: [[274, 3, [6]], ...resolution(300, 150)] }));
+		assert.deepStrictEqual([turned.width, turned.height, turned.pixel_aspect_ratio], [2, 3, 2]);
+		assert.deepStrictEqual(Array.from(turned.square_pixel_dimensions()), [4, 3]);
+		turned.free();
+		const square = mod.decode_tiff(buildTiff(3, 2, (i) => i * 10, { extraTags: resolution(72, 72) }));
+		assert.strictEqual(square.pixel_aspect_ratio, 1);
+		assert.deepStrictEqual(Array.from(square.resample_square_pixels_as_f32()), [0, 10, 20, 30, 40, 50]);
+		square.free();
+		console.log('✅ pixel_aspect_ratio / resample_square_pixels_as_f32: non-square pixels');
+	}
+
+	// 40. valid_coverage() is the percentage of finite, non-nodata samples
+	//     per band: special_values_f32.tif keeps 2 of its 8 samples past
+	//     NaN, ±Inf and nodata -9999, and a nodata of 0 counts per band.
+	{
+		const special = mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'special_values_f32.tif'))));
+		assert.deepStrictEqual(Array.from(special.valid_coverage()), [25]);
+		special.free();
+		const bands = [5, 0, 0, 7, 0, 0, 9, 9, 1, 2, 3, 0, 0, 0, 4, 4];
+		const img = mod.decode_tiff(buildTiff(4, 2, (i) => bands[i], { bitsPerSample: 16, samplesPerPixel: 2, extraTags: [[42113, 2, '0']] }));
+		assert.deepStrictEqual(Array.from(img.valid_coverage()), [62.5, 50]);
+		img.free();
+		console.log('✅ valid_coverage(): per-band share of valid pixels');
+	}
+
+	// 41. tiff_page_info() describes each page on its own terms: a full
+	//     resolution page, a reduced-resolution RGB copy, a transparency
+	//     mask and a float page of another size in one file.
+	{
+		const pages = joinTiffPages([
+			buildTiff(6, 4, (i) => i, { bitsPerSample: 16, compression: 8 }),
+			buildTiff(3, 2, (i) => i, { samplesPerPixel: 3, photometric: 2, extraTags: [[254, 4, [1]]] }),
+			buildTiff(6, 4, (i) => i % 2, { extraTags: [[254, 4, [4]]] }),
+			buildTiff(2, 2, (i) => i / 4, { bitsPerSample: 32, sampleFormat: 3 }),
+		]);
+		const info = JSON.parse(mod.tiff_page_info(pages));
+		assert.deepStrictEqual(info.map(p => [p.index, p.width, p.height, p.channels, p.bits_per_sample, p.sample_format, p.compression, p.role]), [
+			[0, 6, 4, 1, 16, 1, 8, 'page'],
+			[1, 3, 2, 3, 8, 1, 1, 'reduced'],
+			[2, 6, 4, 1, 8, 1, 1, 'mask'],
+			[3, 2, 2, 1, 32, 3, 1, 'page'],
+		]);
+		assert.deepStrictEqual(info.map(p => p.subfile_type), [0, 1, 4, 0]);
+		assert.strictEqual(info[1].photometric, 2);
+		const last = mod.decode_tiff_page(pages, 3);
+		assert.deepStrictEqual(Array.from(last.get_data_as_f32()), [0, 0.25, 0.5, 0.75]);
+		last.free();
+		const tiled = JSON.parse(mod.tiff_page_info(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'shapes_tiled_multi.tif')))));
+		assert.deepStrictEqual(tiled.map(p => [p.tiled, p.compression]), [[true, 5], [true, 5], [true, 5]]);
+		console.log('✅ tiff_page_info(): per-page dimensions and roles');
+	}
+
+	// 42. The TiffFile chunk cache is also bounded in decoded bytes (4 per
+	//     f32 sample, 256 MiB by default): shapes_tiled_multi.tif decodes to
+	//     128 * 72 * 3 * 4 bytes, and a chunk over the budget is not cached.
+	{
+		const tiffFile = new mod.TiffFile(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'shapes_tiled_multi.tif'))), 0);
+		assert.deepStrictEqual([tiffFile.cache_byte_budget, tiffFile.cached_bytes], [256 * 1024 * 1024, 0]);
+		const full = Array.from(tiffFile.read_region(0, 0, 128, 72, 1));
+		assert.deepStrictEqual([tiffFile.cached_chunk_count, tiffFile.cached_bytes], [12, 128 * 72 * 3 * 4]);
+		tiffFile.set_cache_byte_budget(3 * 32 * 32 * 3 * 4 + 100);
+		assert.deepStrictEqual([tiffFile.cached_chunk_count, tiffFile.cached_bytes], [6, 36864], 'least recently used tiles are evicted');
+		tiffFile.set_cache_byte_budget(1000);
+		assert.deepStrictEqual([tiffFile.cached_chunk_count, tiffFile.cached_bytes], [0, 0]);
+		assert.deepStrictEqual(Array.from(tiffFile.read_region(0, 0, 128, 72, 1)), full, 'decoded but not cached');
+		assert.strictEqual(tiffFile.cached_chunk_count, 0);
+		tiffFile.free();
+		console.log('✅ TiffFile: chunk cache bounded by a byte budget');
+	}
+
+	// 43. StatsJob recomputes per-band statistics a slice at a time: two
+	//     passes over the pixels, with nodata and the valid range replaced
+	//     for the job only.
+	{
+		const special = mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'special_values_f32.tif'))));
+		const job = special.start_stats(-9999, undefined, undefined);
+		const progress = [];
+		while (!job.step(special, 3)) {
+			progress.push(job.progress);
+		}
+		assert.ok(progress.length > 1 && progress.every((p, i) => p > 0 && p < 1 && (i === 0 || p > progress[i - 1])), 'progress rises');
+		assert.ok(job.done);
+		const [band] = JSON.parse(job.result_json());
+		assert.deepStrictEqual([band.count, band.min, band.max, band.mean, band.std], [2, 1, 2, 1.5, 0.5]);
+		assert.deepStrictEqual([band.nan_count, band.inf_count, band.nodata_count, band.out_of_range_count], [2, 3, 1, 0]);
+		const ranged = special.start_stats(undefined, 0, 1.5);
+		while (!ranged.step(special, 100)) { /* one slice per pass */ }
+		const [clipped] = JSON.parse(ranged.result_json());
+		assert.deepStrictEqual([clipped.count, clipped.min, clipped.max, clipped.nodata_count, clipped.out_of_range_count], [1, 1, 1, 0, 2]);
+		[job, ranged, special].forEach(o => o.free());
+
+		const grid = mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'tiled_grid_u16_deflate.tif'))));
+		const gridJob = grid.start_stats(undefined, undefined, undefined);
+		assert.strictEqual(JSON.parse(gridJob.result_json())[0].min, null, 'nothing computed yet');
+		while (!gridJob.step(grid, 500)) { /* yield to the event loop here */ }
+		const [values] = JSON.parse(gridJob.result_json());
+		assert.deepStrictEqual([values.count, values.min, values.max, values.mean], [1200, 0, 1199, 599.5]);
+		assert.ok(Math.abs(values.std - 346.41) < 0.01);
+		assert.ok(Math.abs(values.p50 - 600) < 1199 / 4096, 'nearest-rank percentiles to one histogram bin');
+		[gridJob, grid].forEach(o => o.free());
+		console.log('✅ StatsJob: resumable statistics with nodata and valid range');
+	}
+
+	// 44. set_fixed_point_bits() reads integer samples as Q-format numbers:
+	//     the f32 view, range and summary are in real units (nodata scaled
+	//     alike) while get_data_bytes keeps the stored integers.
+	{
+		const q16 = mod.decode_tiff(buildTiff(2, 2, (i) => [65536, 98304, -32768, 0][i], { bitsPerSample: 32, sampleFormat: 2, extraTags: [[42113, 2, '0']] }));
+		assert.strictEqual(q16.fixed_point_bits, 0);
+		q16.set_fixed_point_bits(16);
+		assert.strictEqual(q16.fixed_point_bits, 16);
+		assert.deepStrictEqual(Array.from(q16.get_data_as_f32()), [1, 1.5, -0.5, 0]);
+		assert.deepStrictEqual([q16.min_value, q16.max_value], [-0.5, 1.5]);
+		assert.deepStrictEqual(Array.from(new Int32Array(q16.get_data_bytes().slice().buffer)), [65536, 98304, -32768, 0], 'stored bytes stay raw');
+		const stats = JSON.parse(q16.summary()).channel_stats[0];
+		assert.deepStrictEqual([stats.min, stats.max, stats.nodata_count], [-0.5, 1.5, 1]);
+		q16.set_fixed_point_bits(0);
+		assert.deepStrictEqual(Array.from(q16.get_data_as_f32()), [65536, 98304, -32768, 0]);
+		assert.throws(() => q16.set_fixed_point_bits(33), /33 fractional bits exceed the 32-bit samples/);
+		q16.free();
+		const q8 = mod.decode_tiff(buildTiff(2, 1, (i) => [256, 512][i], { bitsPerSample: 16, extraTags: [[42113, 2, '512']] }));
+		q8.set_fixed_point_bits(8);
+		assert.deepStrictEqual(Array.from(q8.get_data_as_f32()), [1, 2]);
+		assert.deepStrictEqual(Array.from(q8.valid_coverage()), [50], 'nodata 512 is 2.0 in Q8');
+		q8.free();
+		const float = mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'special_values_f32.tif'))));
+		assert.throws(() => float.set_fixed_point_bits(8), /needs integer samples/);
+		float.free();
+		console.log('✅ set_fixed_point_bits(): Q-format integer samples');
+	}
+
+	// 45. set_value_transform() evaluates an expression of v per sample
+	//     during the f32 conversion (JS arrow syntax and Math. names
+	//     accepted); nodata samples (0 here) pass through untouched.
+	{
+		const img = mod.decode_tiff(buildTiff(4, 1, (i) => [1, 2, 4, 0][i], { bitsPerSample: 16, extraTags: [[42113, 2, '0']] }));
+		const cases = [
+			['1 / v', [1, 0.5, 0.25, 0], [0.25, 1]],
+			['(v) => 2 ^ v - 1', [1, 3, 15, 0], [1, 15]],
+			['clamp(v * 10, 15, 30)', [15, 20, 30, 0], [15, 30]],
+			['-v ** 2', [-1, -4, -16, 0], [-16, -1]],
+		];
+		for (const [expression, data, range] of cases) {
+			img.set_value_transform(expression);
+			assert.strictEqual(img.value_transform, expression);
+			assert.deepStrictEqual(Array.from(img.get_data_as_f32()), data, expression);
+			assert.deepStrictEqual([img.min_value, img.max_value], range, expression);
+		}
+		img.set_value_transform('v => Math.sqrt(v) * 2');
+		assert.deepStrictEqual(Array.from(img.get_data_as_f32()), [2, Math.fround(2 * Math.SQRT2), 4, 0]);
+		assert.deepStrictEqual(Array.from(img.get_data_bytes()), [1, 0, 2, 0, 4, 0, 0, 0], 'stored bytes unchanged');
+		assert.throws(() => img.set_value_transform('x => x + 1'), /parameter must be named v/);
+		assert.throws(() => img.set_value_transform('v +* 2'), /Value transform "v \+\* 2": unexpected/);
+		img.set_value_transform('');
+		assert.strictEqual(img.value_transform, '');
+		assert.deepStrictEqual(Array.from(img.get_data_as_f32()), [1, 2, 4, 0]);
+		img.free();
+		console.log('✅ set_value_transform(): per-sample expressions');
+	}
+
+	// 46. tiff_row_stream() is a lazy (async) iterator of row chunks; the
+	//     default chunk height is the strip height, so each strip decodes
+	//     once.
+	{
+		const bytes = buildTiff(5, 7, (i) => i, { bitsPerSample: 16, rowsPerStrip: 3, compression: 8 });
+		const chunks = [];
+		for (const chunk of mod.tiff_row_stream(bytes, 0, 0)) {
+			const data = chunk.take_data();
+			chunks.push([chunk.start_row, chunk.row_count, chunk.width, chunk.channels, data.length, data[0], data[data.length - 1]]);
+			chunk.free();
+		}
+		assert.deepStrictEqual(chunks, [[0, 3, 5, 1, 15, 0, 14], [3, 3, 5, 1, 15, 15, 29], [6, 1, 5, 1, 5, 30, 34]]);
+		const stream = mod.tiff_row_stream(bytes, 0, 4);
+		assert.deepStrictEqual([stream.width, stream.height, stream.channels, stream.next_row], [5, 7, 1, 0]);
+		const rows = [];
+		for await (const chunk of stream) {
+			rows.push(...chunk.take_data());
+			assert.strictEqual(stream.next_row, chunk.start_row + chunk.row_count);
+			chunk.free();
+		}
+		assert.deepStrictEqual(rows, Array.from({ length: 35 }, (_, i) => i));
+		assert.strictEqual(stream.next_chunk(), undefined, 'exhausted');
+		assert.deepStrictEqual(stream.next(), { value: undefined, done: true });
+		stream.free();
+		console.log('✅ tiff_row_stream(): iterable row chunks');
+	}
+
+	// 47. Session overviews of sRGB-encoded 8/16-bit images average in
+	//     linear light, so a black/white checkerboard stays as bright as it
+	//     looks; float and 16-bit gray data and alpha are averaged as stored.
+	{
+		const toLinear = (v) => (v <= 0.04045 ? v / 12.92 : ((v + 0.055) / 1.055) ** 2.4);
+		const toSrgb = (v) => (v <= 0.0031308 ? v * 12.92 : 1.055 * v ** (1 / 2.4) - 0.055);
+		const level = (key, bytes) => {
+			const img = mod.decode_tiff(bytes);
+			assert.strictEqual(img.build_session_overviews(key, 1), 1);
+			img.free();
+			const overview = mod.session_overview(key, 0);
+			const data = Array.from(overview.get_data_as_f32());
+			overview.free();
+			return data;
+		};
+		const close = (actual, expected, message) => {
+			assert.strictEqual(actual.length, expected.length, message);
+			actual.forEach((v, i) => assert.ok(Math.abs(v - expected[i]) < 1e-3, `${message}: ${actual} vs ${expected}`));
+		};
+		const checker = [0, 255, 255, 0];
+		const mid = toSrgb(0.5) * 255;
+		close(level('gray8', buildTiff(2, 2, (i) => checker[i])), [mid], '8-bit gray');
+		close(level('float', buildTiff(2, 2, (i) => checker[i], { bitsPerSample: 32, sampleFormat: 3 })), [127.5], 'float');
+		close(level('gray16', buildTiff(2, 2, (i) => checker[i] * 257, { bitsPerSample: 16 })), [32767.5], '16-bit gray');
+		const rgba = [0, 0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 100, 0, 0, 0, 0];
+		const red = toSrgb((1 + 1) / 4) * 255;
+		const green = toSrgb((1 + toLinear(0)) / 4) * 255;
+		close(level('rgba', buildTiff(2, 2, (i) => rgba[i], { samplesPerPixel: 4, photometric: 2, extraTags: [[338, 3, [2]]] })), [red, green, green, 88.75], 'RGBA');
+		mod.clear_all_session_overviews();
+		console.log('✅ build_session_overviews(): sRGB averaged in linear light');
+	}
+
+	// 48. export_raw() rewrites the stored samples for other tools: byte
+	//     order, channel selection/order and interleaved or planar layout.
+	//     The 2x1 RGB uint16 image holds 0x0102 * (i + 1) per sample.
+	{
+		const img = mod.decode_tiff(buildTiff(2, 1, (i) => 0x0102 * (i + 1), { bitsPerSample: 16, samplesPerPixel: 3, photometric: 2 }));
+		assert.deepStrictEqual(Array.from(img.export_raw(false, new Uint32Array(), false)), [2, 1, 4, 2, 6, 3, 8, 4, 10, 5, 12, 6]);
+		assert.deepStrictEqual(Array.from(img.export_raw(true, new Uint32Array([2, 1, 0]), false)), [3, 6, 2, 4, 1, 2, 6, 12, 5, 10, 4, 8], 'BGR, big endian');
+		assert.deepStrictEqual(Array.from(img.export_raw(true, new Uint32Array(), true)), [1, 2, 4, 8, 2, 4, 5, 10, 3, 6, 6, 12], 'planar');
+		assert.deepStrictEqual(Array.from(img.export_raw(false, new Uint32Array([1]), true)), [4, 2, 10, 5], 'one channel');
+		assert.throws(() => img.export_raw(false, new Uint32Array([3]), false), /channel 3 is out of range \(image has 3 channel\(s\)\)/);
+		img.free();
+		console.log('✅ export_raw(): byte order, channel order and layout');
+	}
+
+	// 49. histograms() bins every channel over its own finite, non-nodata
+	//     range in one call; a channel with no valid samples has zero counts
+	//     and a NaN range.
+	{
+		const bands = [[0, 1, 2, 3, 4, 5, 6, 8], new Array(8).fill(-9999), [10, 10, 10, 10, 10, 10, 10, NaN]];
+		const img = mod.decode_tiff(buildTiff(4, 2, (i) => bands[i % 3][Math.floor(i / 3)], {
+			bitsPerSample: 32, sampleFormat: 3, samplesPerPixel: 3, photometric: 2, extraTags: [[42113, 2, '-9999']],
+		}));
+		const histograms = img.histograms(4);
+		assert.deepStrictEqual([histograms.bins, histograms.channels], [4, 3]);
+		assert.deepStrictEqual(Array.from(histograms.get_mins()), [0, NaN, 10]);
+		assert.deepStrictEqual(Array.from(histograms.get_maxs()), [8, NaN, 10]);
+		assert.deepStrictEqual(Array.from(histograms.get_counts()), [2, 2, 2, 2, 0, 0, 0, 0, 7, 0, 0, 0], 'the last bin includes its upper edge');
+		assert.throws(() => img.histograms(0), /bin count must be non-zero/);
+		[histograms, img].forEach(o => o.free());
+		console.log('✅ histograms(): per-channel histograms in one call');
+	}
+
+	// 50. export_snapshot_png() bakes the on-screen rendering (window,
+	//     gamma, exposure, colormap, NaN color) into an RGBA PNG, read back
+	//     here through decode_png. Samples are 0, 0.5, 1 and NaN.
+	{
+		const img = mod.decode_tiff(buildTiff(4, 1, (i) => [0, 0.5, 1, NaN][i], { bitsPerSample: 32, sampleFormat: 3 }));
+		const snapshot = (min, max, gammaOut, exposure, colormap, nanColor) => {
+			const png = mod.decode_png(img.export_snapshot_png(min, max, 1, gammaOut, exposure, colormap, new Uint8Array(nanColor), undefined, undefined));
+			assert.deepStrictEqual([png.width, png.height, png.channels, png.bits_per_sample], [4, 1, 4, 8]);
+			const rgba = Array.from(png.get_data_as_f32());
+			png.free();
+			return rgba;
+		};
+		assert.deepStrictEqual(snapshot(0, 1, 1, 0, '', []), [0, 0, 0, 255, 128, 128, 128, 255, 255, 255, 255, 255, 255, 0, 255, 255], 'NaN is magenta by default');
+		assert.deepStrictEqual(snapshot(0, 1, 2, 0, '', []).slice(4, 8), [180, 180, 180, 255], 'gamma_out 2');
+		assert.deepStrictEqual(snapshot(0, 0.5, 1, -1, 'gray', []).slice(0, 12), [0, 0, 0, 255, 128, 128, 128, 255, 128, 128, 128, 255], 'clamped, then one stop darker');
+		assert.deepStrictEqual(snapshot(0, 1, 1, 0, 'viridis', [0, 255, 0]), [68, 1, 84, 255, 33, 145, 140, 255, 253, 231, 37, 255, 0, 255, 0, 255]);
+		assert.throws(() => img.export_snapshot_png(0, 1, 1, 1, 0, 'rainbowish', new Uint8Array(), undefined, undefined), /Snapshot: unknown colormap 'rainbowish'/);
+		img.free();
+		console.log('✅ export_snapshot_png(): the rendered view as a PNG');
+	}
+
+	// 51. decode_tiff_safe() validates the structure before decoding: a
+	//     strip running past the end of the file and a page too large for
+	//     wasm memory are refused with their kind, message and location.
+	{
+		const ok = buildTiff(3, 2, (i) => i * 7, { bitsPerSample: 16 });
+		const img = mod.decode_tiff_safe(ok, 0);
+		assert.deepStrictEqual(Array.from(img.get_data_as_f32()), [0, 7, 14, 21, 28, 35]);
+		img.free();
+		const failure = (bytes) => {
+			try {
+				mod.decode_tiff_safe(bytes, 0);
+			} catch (err) {
+				return JSON.parse(err.to_json());
+			}
+			assert.fail('decoded');
+		};
+		const truncated = failure(ok.subarray(0, ok.length - 3));
+		assert.strictEqual(truncated.code, 'truncated');
+		assert.strictEqual(truncated.message, 'strip 0 (12 bytes at offset 146) extends past the end of the file (155 bytes)');
+		assert.deepStrictEqual([truncated.ifd, truncated.tag, truncated.offset], [0, 273, 146]);
+		// ImageWidth, ImageLength and RowsPerStrip patched to 200000.
+		const huge = Uint8Array.from(ok);
+		const view = new DataView(huge.buffer);
+		[18, 30, 102].forEach(at => view.setUint32(at, 200000, true));
+		const tooLarge = failure(huge);
+		assert.strictEqual(tooLarge.code, 'too_large');
+		assert.match(tooLarge.message, /decoded page would need 80000000000 bytes \(limit 2147483648\)/);
+		assert.strictEqual(failure(new TextEncoder().encode('definitely not a tiff')).code, 'unrecognized_format');
+		console.log('✅ decode_tiff_safe(): structural validation before decoding');
+	}
+
+	// 52. tiff_byte_layout() maps IFDs and strip/tile byte ranges without
+	//     decoding, and from a prefix says how many bytes it needs next.
+	{
+		const bytes = joinTiffPages([
+			buildTiff(4, 6, (i) => i, { rowsPerStrip: 2 }),
+			buildTiff(2, 2, (i) => i, { samplesPerPixel: 2, planar: true, compression: 8 }),
+		]);
+		const layout = JSON.parse(mod.tiff_byte_layout(bytes));
+		assert.deepStrictEqual([layout.little_endian, layout.bigtiff, layout.complete, layout.needed_bytes], [true, false, true, null]);
+		assert.deepStrictEqual(layout.ifds.map(ifd => [ifd.index, ifd.width, ifd.height, ifd.samples_per_pixel, ifd.planar_configuration, ifd.chunk_height, ifd.chunk_byte_counts.length]), [
+			[0, 4, 6, 1, 1, 2, 3],
+			[1, 2, 2, 2, 2, 2, 2],
+		]);
+		assert.strictEqual(layout.ifds[0].next_ifd_offset, layout.ifds[1].offset);
+		layout.ifds[0].chunk_offsets.forEach((offset, strip) => {
+			const length = layout.ifds[0].chunk_byte_counts[strip];
+			assert.deepStrictEqual(Array.from(bytes.subarray(offset, offset + length)), Array.from({ length: 8 }, (_, i) => strip * 8 + i), `strip ${strip}`);
+		});
+		let fetched = 8;
+		let partial = JSON.parse(mod.tiff_byte_layout(bytes.subarray(0, fetched)));
+		while (!partial.complete) {
+			assert.ok(partial.needed_bytes > fetched);
+			fetched = partial.needed_bytes;
+			partial = JSON.parse(mod.tiff_byte_layout(bytes.subarray(0, fetched)));
+		}
+		assert.deepStrictEqual(partial.ifds, layout.ifds, 'fetching what it asks for converges');
+		const tiled = JSON.parse(mod.tiff_byte_layout(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'tiled_grid_u16_deflate.tif')))));
+		assert.deepStrictEqual([tiled.ifds[0].tiled, tiled.ifds[0].chunk_width, tiled.ifds[0].chunk_height, tiled.ifds[0].chunk_offsets.length], [true, 16, 16, 6]);
+		assert.throws(() => mod.tiff_byte_layout(new TextEncoder().encode('not a tiff')), /not a TIFF header/);
+		console.log('✅ tiff_byte_layout(): IFD and chunk byte ranges');
+	}
+
+	// 53. The format registry: the default build lists every decoder,
+	//     decode_image_header agrees with sniff_image_format and with what
+	//     decode_image returns (palette TIFF as 8-bit RGB, orientation
+	//     applied), without decoding the pixels itself.
+	{
+		assert.deepStrictEqual(JSON.parse(mod.supported_image_formats()), ['tiff', 'png', 'jpeg', 'webp', 'exr', 'hdr', 'pfm', 'npy', 'flo']);
+		const sample = (file) => new Uint8Array(fs.readFileSync(path.join(samplesDir, file)));
+		const flo = Buffer.alloc(12 + 3 * 2 * 8);
+		flo.write('PIEH', 0, 'latin1');
+		flo.writeInt32LE(3, 4);
+		flo.writeInt32LE(2, 8);
+		const inputs = [
+			[sample('palette.tif'), ['tiff', 160, 120, 3, 8, 1]],
+			[sample('orientation_tag6.tif'), ['tiff', 5, 4, 3, 8, 1]],
+			[sample('png_u16_gray.png'), ['png', 16, 10, 1, 16, 1]],
+			[sample('jpeg_ycbcr_color.tif').subarray(352, 352 + 9018), ['jpeg', 96, 64, 3, 8, 1]],
+			[sample('webp_rgb.tif').subarray(272, 272 + 6020), ['webp', 160, 120, 3, 8, 1]],
+			[sample('pfm_color.pfm'), ['pfm', 9, 6, 3, 32, 3]],
+			[sample('npy_u8_rgb.npy'), ['npy', 12, 8, 3, 8, 1]],
+			[new Uint8Array(flo), ['flo', 3, 2, 2, 32, 3]],
+		];
+		for (const [bytes, expected] of inputs) {
+			const header = JSON.parse(mod.decode_image_header(bytes));
+			assert.deepStrictEqual([header.format, header.width, header.height, header.channels, header.bits_per_sample, header.sample_format], expected);
+			assert.strictEqual(mod.sniff_image_format(bytes), header.format);
+			const img = mod.decode_image(bytes);
+			assert.deepStrictEqual([img.width, img.height, img.channels, img.bits_per_sample, img.sample_format], expected.slice(1), `${expected[0]} header matches the decode`);
+			img.free();
+		}
+		const hdr = new Uint8Array(Buffer.from('#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 2 +X 3\n', 'latin1'));
+		assert.deepStrictEqual(JSON.parse(mod.decode_image_header(hdr)), { format: 'hdr', width: 3, height: 2, channels: 3, bits_per_sample: 32, sample_format: 3 }, 'header only, no scanlines');
+		assert.throws(() => mod.decode_image_header(new TextEncoder().encode('nothing')), /decode_image_header: unrecognised image format/);
+		console.log('✅ supported_image_formats / decode_image_header: the format registry');
+	}
+
+	// 54. render_rgba() for a Display-P3 canvas converts sRGB colour to P3
+	//     primaries (gray unchanged), and set_source_primaries() maps wider
+	//     data such as Rec. 2020 into the output gamut by desaturating.
+	{
+		const pixels = [255, 0, 0, 0, 255, 0, 128, 128, 128];
+		const img = mod.decode_tiff(buildTiff(3, 1, (i) => pixels[i], { samplesPerPixel: 3, photometric: 2 }));
+		const render = (space) => Array.from(img.render_rgba(0, 255, 1, 1, 0, '', new Uint8Array(0), space, undefined, undefined));
+		assert.deepStrictEqual(Array.from(img.source_primaries()), [], 'no chromaticity tags: sRGB assumed');
+		assert.deepStrictEqual(render('srgb'), [255, 0, 0, 255, 0, 255, 0, 255, 128, 128, 128, 255]);
+		assert.deepStrictEqual(render('display-p3'), [234, 51, 35, 255, 117, 251, 76, 255, 128, 128, 128, 255]);
+		const rec2020 = [0.708, 0.292, 0.170, 0.797, 0.131, 0.046, 0.3127, 0.3290];
+		img.set_source_primaries(new Float64Array(rec2020));
+		assert.deepStrictEqual(Array.from(img.source_primaries()), rec2020);
+		assert.deepStrictEqual(render('srgb'), [255, 68, 95, 255, 0, 246, 140, 255, 128, 128, 128, 255], 'out-of-gamut Rec. 2020 red and green are desaturated');
+		assert.deepStrictEqual(render('display-p3'), [255, 56, 82, 255, 0, 250, 119, 255, 128, 128, 128, 255]);
+		img.set_source_primaries(new Float64Array(0));
+		assert.deepStrictEqual(render('srgb'), [255, 0, 0, 255, 0, 255, 0, 255, 128, 128, 128, 255], 'empty primaries reset to sRGB');
+		assert.throws(() => img.set_source_primaries(new Float64Array([0.64, 0.33])), /expected 8 values/);
+		assert.throws(() => render('rec2020'), /unknown output colour space 'rec2020'/);
+		img.free();
+		console.log('✅ render_rgba(display-p3): gamut-mapped wide-gamut output');
+	}
+
+	// 55. suggest_display() classifies an image for its first-open settings:
+	//     8-bit colour is a photo, a few integer ids a label map, float gray
+	//     depth (percentiles past an invalid zero), float colour above 1 HDR,
+	//     and an all-NaN page empty.
+	{
+		const suggest = (bytes) => {
+			const img = mod.decode_tiff(bytes);
+			const suggestion = JSON.parse(img.suggest_display(0));
+			img.free();
+			return suggestion;
+		};
+		const rgb = [255, 0, 0, 0, 255, 0, 128, 128, 128];
+		assert.deepStrictEqual(suggest(buildTiff(3, 1, (i) => rgb[i], { samplesPerPixel: 3, photometric: 2 })), {
+			kind: 'photo', mode: 'gamma', min: 0, max: 255, colormap: 'none', gamma_in: 1, gamma_out: 1, reason: '8-bit image over its full range',
+		});
+		assert.deepStrictEqual(suggest(buildTiff(8, 8, (i) => i % 4)), {
+			kind: 'label', mode: 'manual', min: 0, max: 3, colormap: 'turbo', gamma_in: 1, gamma_out: 1, reason: '4 distinct integer values',
+		});
+		const depth = suggest(buildTiff(10, 10, (i) => (i === 0 ? 0 : 1 + i / 10), { bitsPerSample: 32, sampleFormat: 3 }));
+		assert.deepStrictEqual([depth.kind, depth.mode, depth.colormap], ['depth', 'manual', 'viridis']);
+		assert.deepStrictEqual([depth.min, depth.max], [Math.fround(1.1), Math.fround(10.8)], 'the zero is below the 1st percentile');
+		const hdr = suggest(buildTiff(10, 10, (i) => (i % 30) / 5, { bitsPerSample: 32, sampleFormat: 3, samplesPerPixel: 3, photometric: 2 }));
+		assert.deepStrictEqual([hdr.kind, hdr.mode, hdr.min, hdr.max, hdr.gamma_out], ['hdr', 'manual', 0, Math.fround(5.8), 2.2]);
+		const empty = suggest(buildTiff(2, 2, () => NaN, { bitsPerSample: 32, sampleFormat: 3 }));
+		assert.deepStrictEqual([empty.kind, empty.mode, empty.min, empty.max], ['empty', 'auto', null, null]);
+		console.log('✅ suggest_display(): photo, label, depth, hdr and empty images');
+	}
+
+	// 56. decode_tiff_plane() reads one band of a page, planar (only that
+	//     plane's strips) or chunky, with the same values the whole-page
+	//     decode gives for that band.
+	{
+		const bytes = joinTiffPages([
+			buildTiff(4, 3, (i) => i * 7, { bitsPerSample: 16, samplesPerPixel: 3, photometric: 2, planar: true, rowsPerStrip: 2 }),
+			buildTiff(4, 3, (i) => 1000 + i, { bitsPerSample: 16, samplesPerPixel: 3, photometric: 2 }),
+		]);
+		for (let page = 0; page < 2; page++) {
+			const whole = mod.decode_tiff_page(bytes, page);
+			for (let band = 0; band < 3; band++) {
+				const plane = Array.from(mod.decode_tiff_plane(bytes, page, band));
+				const first = page === 0 ? band * 7 : 1000 + band;
+				const step = page === 0 ? 21 : 3;
+				assert.deepStrictEqual(plane, Array.from({ length: 12 }, (_, i) => first + i * step), `page ${page} band ${band}`);
+				assert.deepStrictEqual(plane, Array.from(whole.get_band_as_f32(band)));
+			}
+			whole.free();
+		}
+		assert.throws(() => mod.decode_tiff_plane(bytes, 0, 3), /plane 3 is out of range for a 3-band page/);
+		const palette = new Uint8Array(fs.readFileSync(path.join(samplesDir, 'palette.tif')));
+		assert.throws(() => mod.decode_tiff_plane(palette, 0, 0), /needs a whole-image decode; use decode_tiff_page/);
+		console.log('✅ decode_tiff_plane(): one band without the whole-image decode');
+	}
+
+	// 57. DecoderWorker answers the versioned message protocol: open, decode
+	//     a page, render and stats run in order from the queue, a queued
+	//     request can be cancelled, and malformed or stale requests get
+	//     protocol / handle errors.
+	{
+		assert.strictEqual(mod.worker_protocol_version(), 1);
+		const v = 1;
+		const worker = new mod.DecoderWorker();
+		assert.deepStrictEqual(worker.receive({ v, id: 1, type: 'open', data: joinTiffPages([buildTiff(2, 2, (i) => i), buildTiff(2, 2, (i) => i * 10)]) }), []);
+		assert.deepStrictEqual(worker.run_next(), { v, id: 1, type: 'open', ok: true, handle: 1, format: 'tiff', pages: 2 });
+		worker.receive({ v, id: 2, type: 'decode-page', handle: 1, page: 1 });
+		worker.receive({ v, id: 3, type: 'render', handle: 1, min: 0, max: 30 });
+		worker.receive({ v, id: 4, type: 'stats', handle: 1 });
+		worker.receive({ v, id: 5, type: 'render', handle: 1, min: 0, max: 255 });
+		assert.deepStrictEqual(worker.receive({ v, id: 6, type: 'cancel', target: 5 }), [
+			{ v, id: 5, type: 'render', ok: false, error: { kind: 'cancelled', message: 'cancelled before it started' } },
+			{ v, id: 6, type: 'cancel', ok: true, target: 5, dropped: true },
+		]);
+		assert.strictEqual(worker.pending, 3);
+		assert.deepStrictEqual(worker.run_next(), {
+			v, id: 2, type: 'decode-page', ok: true, page: 1, width: 2, height: 2, channels: 1, bits_per_sample: 8, sample_format: 1, min: 0, max: 30,
+		});
+		const render = worker.run_next();
+		assert.deepStrictEqual([render.id, render.ok, render.width, render.height], [3, true, 2, 2]);
+		assert.ok(render.rgba instanceof Uint8ClampedArray);
+		assert.deepStrictEqual(Array.from(render.rgba), [0, 0, 0, 255, 85, 85, 85, 255, 170, 170, 170, 255, 255, 255, 255, 255]);
+		assert.strictEqual(render.transfer[0], render.rgba.buffer, 'the pixels are listed for transfer');
+		const stats = worker.run_next();
+		assert.deepStrictEqual([stats.id, stats.bands.length, stats.bands[0].count, stats.bands[0].min, stats.bands[0].max, stats.bands[0].mean], [4, 1, 4, 0, 30, 15]);
+		assert.strictEqual(worker.run_next(), undefined, 'the cancelled render never runs');
+		assert.deepStrictEqual(worker.receive({ v: 2, id: 7, type: 'open' }), [
+			{ v, id: 7, type: 'open', ok: false, error: { kind: 'protocol', message: 'unsupported protocol version 2 (this decoder speaks 1)' } },
+		]);
+		assert.deepStrictEqual(worker.receive({ v, id: 8, type: 'resize' })[0].error, { kind: 'protocol', message: "unknown request type 'resize'" });
+		worker.receive({ v, id: 9, type: 'dispose', handle: 1 });
+		worker.receive({ v, id: 10, type: 'decode-page', handle: 1, page: 0 });
+		assert.deepStrictEqual(worker.run_next(), { v, id: 9, type: 'dispose', ok: true });
+		assert.deepStrictEqual(worker.run_next().error, { kind: 'handle', message: 'no open image with handle 1' });
+		worker.free();
+		console.log('✅ DecoderWorker: versioned request/response protocol');
+	}
+
+	// 58. A dither_seed adds reproducible TPDF dither: the same seed gives
+	//     the same bytes, another seed other bytes, each within one step of
+	//     the undithered render; render_settings_json reports every input.
+	{
+		const img = mod.decode_tiff(buildTiff(64, 1, (i) => 1000 + i * 4, { bitsPerSample: 16 }));
+		const render = (seed) => Array.from(img.render_rgba(0, 65535, 1, 1, 0, '', new Uint8Array(0), 'srgb', seed, undefined));
+		const red = (rgba) => rgba.filter((_, i) => i % 4 === 0);
+		const plain = red(render(undefined));
+		assert.deepStrictEqual(plain, Array.from({ length: 64 }, (_, i) => (i < 40 ? 4 : 5)), 'undithered: one band edge');
+		const seeded = render(7);
+		assert.deepStrictEqual(seeded, render(7), 'same seed, same bytes');
+		assert.deepStrictEqual(red(seeded).slice(0, 8), [4, 4, 4, 4, 3, 4, 4, 4]);
+		assert.notDeepStrictEqual(red(render(8)), red(seeded));
+		red(seeded).forEach((value, i) => assert.ok(Math.abs(value - plain[i]) <= 1, `sample ${i}`));
+		for (let i = 0; i < seeded.length; i += 4) {
+			assert.deepStrictEqual([seeded[i + 1], seeded[i + 2], seeded[i + 3]], [seeded[i], seeded[i], 255], 'gray stays gray');
+		}
+		assert.deepStrictEqual(JSON.parse(img.render_settings_json(0, 65535, 1, 1, 0, '', new Uint8Array(0), 'srgb', 7, undefined)), {
+			render_version: 1, min: 0, max: 65535, gamma_in: 1, gamma_out: 1, exposure_stops: 0, srgb_transfer: false,
+			colormap: null, nan_color: [255, 0, 255, 255], output_space: 'srgb', source_primaries: [0.64, 0.33, 0.3, 0.6, 0.15, 0.06, 0.3127, 0.329],
+			color_managed: false, gamut_mapped: false, dither: { kind: 'tpdf', seed: 7 }, clip_highlight: null,
+		});
+		const p3 = JSON.parse(img.render_settings_json(0, 1, 2.2, 1, 1, 'viridis', new Uint8Array([1, 2, 3]), 'display-p3', undefined, undefined));
+		assert.deepStrictEqual([p3.colormap, p3.nan_color, p3.output_space, p3.gamut_mapped, p3.dither], ['viridis', [1, 2, 3, 255], 'display-p3', true, null]);
+		img.free();
+		console.log('✅ render_rgba(dither_seed) / render_settings_json: reproducible dithered renders');
+	}
+
+	// 59. decode_tiff_rows() pushes the same row chunks to a callback and
+	//     returns the rows delivered; returning false stops early and a
+	//     thrown exception comes back out.
+	{
+		const bytes = buildTiff(5, 7, (i) => i, { bitsPerSample: 16, rowsPerStrip: 3, compression: 8 });
+		const chunks = [];
+		const delivered = mod.decode_tiff_rows(bytes, 0, 0, (chunk) => {
+			const data = chunk.take_data();
+			chunks.push([chunk.start_row, chunk.row_count, data[0], data[data.length - 1]]);
+			chunk.free();
+		});
+		assert.strictEqual(delivered, 7);
+		assert.deepStrictEqual(chunks, [[0, 3, 0, 14], [3, 3, 15, 29], [6, 1, 30, 34]]);
+		let calls = 0;
+		assert.strictEqual(mod.decode_tiff_rows(bytes, 0, 2, (chunk) => { chunk.free(); calls++; return false; }), 2, 'stopped after the first chunk');
+		assert.strictEqual(calls, 1);
+		assert.throws(() => mod.decode_tiff_rows(bytes, 0, 0, () => { throw new Error('encoder full'); }), /encoder full/);
+		console.log('✅ decode_tiff_rows(): callback row chunks with early stop');
+	}
+
+	// 60. An all-NaN/Inf page has NaN min_value/max_value and an "empty"
+	//     value_stats_json with null range, and renders over that NaN range
+	//     without throwing; value_stats_json also tells a fast decode's NaN
+	//     range (no statistics) from an empty page.
+	{
+		const empty = mod.decode_tiff(buildTiff(2, 2, (i) => [NaN, Infinity, NaN, -Infinity][i], { bitsPerSample: 32, sampleFormat: 3 }));
+		assert.ok(Number.isNaN(empty.min_value) && Number.isNaN(empty.max_value));
+		assert.deepStrictEqual(JSON.parse(empty.value_stats_json()), {
+			state: 'empty', min: null, max: null, valid_count: 0, nan_count: 2, inf_count: 2, nodata_count: 0, transparent_count: 0,
+		});
+		const magenta = [255, 0, 255, 255];
+		assert.deepStrictEqual(Array.from(empty.render_rgba(empty.min_value, empty.max_value, 1, 1, 0, '', new Uint8Array(0), 'srgb', undefined, undefined)), [...magenta, ...magenta, ...magenta, ...magenta]);
+		empty.free();
+		const bytes = buildTiff(2, 2, (i) => [NaN, 3, -9999, 1.5][i], { bitsPerSample: 32, sampleFormat: 3, extraTags: [[42113, 2, '-9999']] });
+		const expected = { state: 'valid', min: 1.5, max: 3, valid_count: 2, nan_count: 1, inf_count: 0, nodata_count: 1, transparent_count: 0 };
+		const full = mod.decode_tiff(bytes);
+		assert.deepStrictEqual(JSON.parse(full.value_stats_json()), expected, 'nodata is counted, not part of the range');
+		full.free();
+		const fast = mod.decode_tiff_fast(bytes);
+		assert.ok(Number.isNaN(fast.min_value), 'no statistics computed');
+		assert.deepStrictEqual(JSON.parse(fast.value_stats_json()), expected);
+		fast.free();
+		console.log('✅ value_stats_json(): empty pages have a defined NaN/null state');
+	}
+
+	// 61. A TiffDocument parses the same file once and answers the same
+	//     questions per page as the free functions above.
+	{
+		const bytes = new Uint8Array(fs.readFileSync(path.join(samplesDir, 'multipage_rgb_depth_mask.tif')));
+		const doc = new mod.TiffDocument(bytes);
+		assert.strictEqual(doc.page_count(), 3, 'document page count');
+		for (let page = 0; page < 3; page++) {
+			const direct = mod.decode_tiff_page(bytes, page);
+			assert.deepStrictEqual(Array.from(doc.dimensions(page)), [direct.width, direct.height], `page ${page} dimensions`);
+			assert.strictEqual(doc.metadata(page), direct.all_tags_json, `page ${page} metadata`);
+			const decoded = doc.decode_page(page);
+			assert.deepStrictEqual(Array.from(decoded.get_data_as_f32()), Array.from(direct.get_data_as_f32()), `page ${page} samples`);
+			assert.strictEqual(decoded.max_value, direct.max_value, `page ${page} max`);
+		}
+		assert.throws(() => doc.decode_page(3), /out of range/i);
+		console.log('✅ TiffDocument: page count, dimensions, metadata and decodes match the one-shot functions');
+	}
+
+	// 61b. TiffDocument keeps recently decoded pages: revisiting one is a
+	//      cache hit returning an independent copy, and the limits evict.
+	{
+		const bytes = new Uint8Array(fs.readFileSync(path.join(samplesDir, 'multipage_rgb_depth_mask.tif')));
+		const doc = new mod.TiffDocument(bytes);
+		const first = doc.decode_page(0);
+		const expected = Array.from(first.get_data_as_f32());
+		first.take_data_as_f32();
+		first.dispose();
+		doc.decode_page(1);
+		const again = doc.decode_page(0);
+		assert.deepStrictEqual(Array.from(again.get_data_as_f32()), expected, 'cached page 0 is unaffected by the disposed copy');
+		let stats = JSON.parse(doc.page_cache_stats());
+		assert.deepStrictEqual([stats.pages, stats.hits, stats.misses, stats.evictions], [[0, 1], 1, 2, 0]);
+		doc.set_page_cache_capacity(1);
+		stats = JSON.parse(doc.page_cache_stats());
+		assert.deepStrictEqual([stats.pages, stats.evictions], [[0], 1]);
+		doc.set_page_cache_byte_budget(16);
+		doc.decode_page(2);
+		stats = JSON.parse(doc.page_cache_stats());
+		assert.deepStrictEqual([stats.pages, stats.bytes, stats.misses], [[], 0, 3], 'pages over the byte budget are not kept');
+		console.log('✅ TiffDocument: page cache hits, copies and eviction');
+	}
+
+	// 62. The planar/tiled and sub-byte strip paths take min/max strip by
+	//     strip while decoding; the result equals a scan of the decoded
+	//     samples (NaN left out), and fast decodes still skip it.
+	{
+		const planar = buildTiff(5, 4, (i) => (i === 7 ? NaN : Math.sin(i) * 100), {
+			bitsPerSample: 32, sampleFormat: 3, samplesPerPixel: 3, photometric: 2, planar: true, rowsPerStrip: 1, compression: 8,
+		});
+		const subbit = new Uint8Array(fs.readFileSync(path.join(samplesDir, 'shapes_lzw_12bps.tif')));
+		for (const [bytes, route] of [[planar, 'planar_tiled'], [subbit, 'subbit']]) {
+			const img = mod.decode_tiff_page_profiled(bytes, 0);
+			assert.strictEqual(JSON.parse(img.chunk_profile_json).path, route);
+			const finite = Array.from(img.get_data_as_f32()).filter(Number.isFinite);
+			assert.deepStrictEqual([img.min_value, img.max_value], [Math.min(...finite), Math.max(...finite)], `${route} min/max`);
+			img.free();
+			const fast = mod.decode_tiff_fast(bytes);
+			assert.ok(Number.isNaN(fast.min_value), `${route}: no statistics in a fast decode`);
+			fast.free();
+		}
+		console.log('✅ strip-level min/max matches a scan of the decoded samples');
+	}
+
+	// 63. dispose() drops a result's buffers without waiting for GC; every
+	//     accessor throws a clear error afterwards instead of returning
+	//     empty or stale data.
+	{
+		const result = mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'gray_u32.tif'))));
+		assert.ok(result.get_data_as_f32().length > 0, 'result should hold samples before dispose');
+		assert.strictEqual(result.disposed, false);
+		result.dispose();
+		result.dispose();
+		assert.strictEqual(result.disposed, true);
+		assert.throws(() => result.width, /disposed/);
+		assert.throws(() => result.get_data_as_f32(), /disposed/);
+		assert.throws(() => result.get_data_bytes(), /disposed/);
+		assert.throws(() => result.summary(), /disposed/);
+		result.free();
+		console.log('✅ TiffResult.dispose(): buffers dropped, accessors throw afterwards');
+	}
+
+	// 64. take_data_bytes() hands over the same bytes as get_data_bytes()
+	//     by moving them out of the result instead of cloning them.
+	{
+		const bytes = new Uint8Array(fs.readFileSync(path.join(samplesDir, 'gray_u32.tif')));
+		const expected = Array.from(mod.decode_tiff(bytes).get_data_bytes());
+		const result = mod.decode_tiff(bytes);
+		assert.deepStrictEqual(Array.from(result.take_data_bytes()), expected, 'taken bytes should match get_data_bytes');
+		assert.strictEqual(result.get_data_bytes().length, 0, 'nothing should be left after take_data_bytes');
+		console.log('✅ TiffResult.take_data_bytes(): moves the packed samples out without a copy');
+	}
+
+	// 65. decode_tiff_into() writes the same bytes into a caller-owned
+	//     buffer, for integer and float pages alike, and refuses one that
+	//     is too small. The returned result keeps the statistics but not
+	//     the samples.
+	for (const file of ['gray_u32.tif', 'sample_value_range_f32.tif']) {
+		const bytes = new Uint8Array(fs.readFileSync(path.join(samplesDir, file)));
+		const reference = mod.decode_tiff(bytes);
+		const expected = reference.get_data_bytes();
+		const pool = new Uint8Array(expected.length + 16);
+		const result = mod.decode_tiff_into(bytes, pool);
+		assert.deepStrictEqual(Array.from(pool.subarray(0, expected.length)), Array.from(expected), `${file}: bytes in the caller's buffer`);
+		assert.ok(pool.subarray(expected.length).every(v => v === 0), `${file}: bytes past the samples are untouched`);
+		assert.strictEqual(result.width * result.height * result.channels * (result.bits_per_sample / 8), expected.length);
+		assert.deepStrictEqual([result.min_value, result.max_value], [reference.min_value, reference.max_value], `${file}: statistics kept`);
+		assert.strictEqual(result.get_data_bytes().length, 0, `${file}: samples released`);
+		reference.free();
+		result.free();
+		assert.throws(() => mod.decode_tiff_into(bytes, new Uint8Array(expected.length - 1)), /needs \d+/);
+	}
+	console.log('✅ decode_tiff_into(): samples copied into a caller-provided buffer');
+
+	// 66. Big-endian samples are swapped in bulk: for every 16/32/64-bit
+	//     type, an uncompressed or deflated big-endian page with a sample
+	//     count that is no multiple of the lane width decodes to exactly the
+	//     samples and stored bytes of its little-endian twin.
+	{
+		const kinds = [
+			[16, 1, (i) => i * 3000], [16, 2, (i) => (i - 10) * 3000],
+			[32, 1, (i) => i * 200000000], [32, 2, (i) => (i - 10) * 123456789],
+			[32, 3, (i) => (i - 10) * 1.25e-3], [64, 3, (i) => (i - 10) * 1.25e-3],
+		];
+		for (const [bitsPerSample, sampleFormat, sample] of kinds) {
+			for (const compression of [1, 8]) {
+				const label = `${bitsPerSample}-bit format ${sampleFormat}, compression ${compression}`;
+				const decode = (littleEndian) => mod.decode_tiff(buildTiff(7, 3, sample, { bitsPerSample, sampleFormat, compression, littleEndian, rowsPerStrip: 2 }));
+				const [le, be] = [decode(true), decode(false)];
+				assert.deepStrictEqual(Array.from(be.get_data_as_f32()), Array.from({ length: 21 }, (_, i) => Math.fround(sample(i))), label);
+				assert.deepStrictEqual(Array.from(be.export_raw(false, new Uint32Array(), false)), Array.from(le.export_raw(false, new Uint32Array(), false)), label);
+				assert.deepStrictEqual([be.min_value, be.max_value], [le.min_value, le.max_value], label);
+				le.free();
+				be.free();
+			}
+		}
+		console.log('✅ big-endian 16/32/64-bit samples match their little-endian twins');
+	}
+
+	// 67. A clip highlight paints samples below the display min blue and
+	//     above the max red; everything in range keeps its gray value.
+	{
+		const result = mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'gray_u32.tif'))));
+		const values = result.get_data_as_f32();
+		const sorted = Array.from(values).sort((a, b) => a - b);
+		const [lo, hi] = [sorted[Math.floor(sorted.length / 4)], sorted[Math.floor(sorted.length * 3 / 4)]];
+		const plain = result.render_rgba(lo, hi, 1, 1, 0, 'gray', new Uint8Array([0, 0, 0, 0]), 'srgb', undefined, undefined);
+		const solid = result.render_rgba(lo, hi, 1, 1, 0, 'gray', new Uint8Array([0, 0, 0, 0]), 'srgb', undefined, 'solid');
+		for (let i = 0; i < values.length; i++) {
+			const rgb = Array.from(solid.subarray(i * 4, i * 4 + 3));
+			const expected = values[i] < lo ? [0, 0, 255] : values[i] > hi ? [255, 0, 0] : Array.from(plain.subarray(i * 4, i * 4 + 3));
+			assert.deepStrictEqual(rgb, expected, `pixel ${i} (${values[i]}) against [${lo}, ${hi}]`);
+		}
+		const settings = JSON.parse(result.render_settings_json(lo, hi, 1, 1, 0, 'gray', new Uint8Array([0, 0, 0, 0]), 'srgb', undefined, 'zebra'));
+		assert.strictEqual(settings.clip_highlight.style, 'zebra');
+		assert.throws(() => result.render_rgba(lo, hi, 1, 1, 0, 'gray', new Uint8Array([0, 0, 0, 0]), 'srgb', undefined, 'stripes'), /unknown clip highlight/);
+		console.log('✅ render_rgba(clip_highlight): out-of-range pixels marked blue/red');
+	}
+
+	// 68. A TiffStream reads through a read(offset, length) callback and
+	//     fetches only what the requested page needs, yet decodes it to the
+	//     same samples as the in-memory functions.
+	{
+		const file = path.join(samplesDir, 'multipage_rgb_depth_mask.tif');
+		const bytes = new Uint8Array(fs.readFileSync(file));
+		const fd = fs.openSync(file, 'r');
+		try {
+			const read = (offset, length) => {
+				const chunk = new Uint8Array(length);
+				fs.readSync(fd, chunk, 0, length, offset);
+				return chunk;
+			};
+			const stream = new mod.TiffStream(read, bytes.length);
+			assert.strictEqual(stream.page_count(), 3, 'streamed page count');
+			const direct = mod.decode_tiff_page(bytes, 2);
+			const streamed = stream.decode_page(2);
+			assert.deepStrictEqual(Array.from(streamed.get_data_bytes()), Array.from(direct.get_data_bytes()), 'streamed page 2 samples');
+			assert.strictEqual(streamed.max_value, direct.max_value);
+			assert.ok(stream.bytes_read < bytes.length, `decoding one page read ${stream.bytes_read} of ${bytes.length} bytes`);
+
+			const lazy = stream.open_page(1);
+			const inMemory = new mod.TiffFile(bytes, 1);
+			assert.deepStrictEqual(Array.from(lazy.read_region(3, 4, 10, 6, 1)), Array.from(inMemory.read_region(3, 4, 10, 6, 1)), 'streamed TiffFile region');
+			assert.throws(() => stream.decode_page(3), /out of range/i);
+			assert.throws(() => new mod.TiffStream(read, 7), /end of the file|header/);
+		} finally {
+			fs.closeSync(fd);
+		}
+		console.log('✅ TiffStream: pages decoded from ranges fetched through a JS callback');
+	}
+
+	// 69. RemoteTiffReader drives the same decode through an async ranged
+	//     fetch (an HTTP Range request in practice); a region read only
+	//     fetches the strips/tiles it overlaps.
+	{
+		const bytes = new Uint8Array(fs.readFileSync(path.join(samplesDir, 'multipage_rgb_depth_mask.tif')));
+		const requested = [];
+		const fetchRange = async (offset, length) => {
+			requested.push([offset, length]);
+			return bytes.slice(offset, offset + length);
+		};
+		const remote = await mod.RemoteTiffReader.open(fetchRange, bytes.length);
+		assert.strictEqual(remote.page_count(), 3, 'remote page count');
+		const page = await remote.decode_page(1);
+		assert.deepStrictEqual(Array.from(page.get_data_bytes()), Array.from(mod.decode_tiff_page(bytes, 1).get_data_bytes()), 'remote page 1 samples');
+
+		const before = remote.bytes_fetched;
+		const region = await remote.read_region(1, 3, 4, 10, 6, 1);
+		assert.deepStrictEqual(Array.from(region), Array.from(new mod.TiffFile(bytes, 1).read_region(3, 4, 10, 6, 1)), 'remote region');
+		assert.ok(remote.bytes_fetched - before < bytes.length, 'a region read fetches only part of the file');
+		assert.strictEqual(remote.request_count, requested.length);
+		await assert.rejects(remote.decode_page(3), /out of range/i);
+		console.log('✅ RemoteTiffReader: pages and regions decoded from async ranged fetches');
+	}
+
+	// 70. colormap_previews() stacks one thumbnail per display colormap;
+	//     at full size each is exactly what render_rgba draws.
+	{
+		const result = mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'gray_u32.tif'))));
+		const names = JSON.parse(mod.display_colormap_names());
+		assert.ok(names.includes('viridis') && names.includes('gray'), 'colormap names');
+		const [min, max] = [result.min_value, result.max_value];
+		const strip = result.colormap_previews(min, max, 1, 1, 0, result.width, result.height);
+		const size = result.width * result.height * 4;
+		assert.strictEqual(strip.length, size * names.length);
+		names.forEach((name, i) => {
+			const full = result.render_rgba(min, max, 1, 1, 0, name, new Uint8Array(), 'srgb', undefined, undefined);
+			assert.deepStrictEqual(Array.from(strip.subarray(i * size, (i + 1) * size)), Array.from(full), `${name} preview`);
+		});
+		assert.strictEqual(result.colormap_previews(min, max, 1, 1, 0, 16, 8).length, 16 * 8 * 4 * names.length);
+		assert.throws(() => result.colormap_previews(min, max, 1, 1, 0, 0, 8), /thumbnail size/);
+		console.log('✅ colormap_previews(): one render per colormap in a single call');
+	}
+
+	// 71. COG overviews: reduced-resolution pages are listed as levels
+	//     (the mask page is not) and decode to the downsampled image.
+	{
+		const bytes = new Uint8Array(fs.readFileSync(path.join(samplesDir, 'cog_overviews_u16.tif')));
+		const levels = JSON.parse(mod.tiff_overviews(bytes));
+		assert.deepStrictEqual(levels.map(l => [l.page, l.width, l.height, l.scale]), [[0, 64, 48, 1], [1, 32, 24, 2], [2, 16, 12, 4]]);
+		const full = mod.decode_overview(bytes, 0).get_data_as_f32();
+		const level2 = mod.decode_overview(bytes, 2);
+		const samples = level2.get_data_as_f32();
+		for (let y = 0; y < level2.height; y++) {
+			for (let x = 0; x < level2.width; x++) {
+				assert.strictEqual(samples[y * level2.width + x], full[(y * 4) * 64 + x * 4], `level 2 pixel (${x}, ${y})`);
+			}
+		}
+		assert.throws(() => mod.decode_overview(bytes, 3), /out of range/);
+		assert.strictEqual(JSON.parse(mod.tiff_overviews(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'gray_u32.tif'))))).length, 1);
+		const stream = new mod.TiffStream((offset, length) => bytes.slice(offset, offset + length), bytes.length);
+		assert.strictEqual(stream.overviews(), mod.tiff_overviews(bytes), 'TiffStream lists the same levels');
+		console.log('✅ tiff_overviews()/decode_overview(): COG overview levels');
+	}
+
+	// 72. Sampled statistics: visiting every pixel reproduces the exact
+	//     StatsJob result, and a sparse sample stays inside its range.
+	{
+		const bytes = new Uint8Array(fs.readFileSync(path.join(samplesDir, 'cog_overviews_u16.tif')));
+		const image = mod.decode_tiff(bytes);
+		const job = image.start_stats(undefined, undefined, undefined);
+		while (!job.step(image, 1000)) { /* run to completion */ }
+		const exact = JSON.parse(job.result_json());
+		const all = JSON.parse(image.sampled_stats_json(0, 1, undefined, undefined, undefined));
+		assert.deepStrictEqual(all.bands, exact);
+		assert.strictEqual(all.coverage, 1);
+		assert.strictEqual(all.rank_error, 0);
+		const sampled = JSON.parse(image.sampled_stats_json(100, 7, undefined, undefined, undefined));
+		assert.ok(sampled.sampled_pixels <= 100 && sampled.sampled_pixels > 0);
+		assert.strictEqual(sampled.total_pixels, 64 * 48);
+		assert.ok(sampled.rank_error > 0 && sampled.rank_error < 1);
+		assert.ok(sampled.bands[0].min >= exact[0].min && sampled.bands[0].max <= exact[0].max);
+		assert.deepStrictEqual(JSON.parse(image.sampled_stats_json(100, 7, undefined, undefined, undefined)), sampled, 'same seed, same pixels');
+		console.log('✅ sampled_stats_json(): stratified approximate statistics');
+	}
+
+	// 73. Exact hover values: a native-type copy outlives take_data and
+	//     dispose, and ignores the fixed-point scaling of the f32 view.
+	{
+		const image = mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'pred_ref_f32.tif'))));
+		const exact = image.exact_values();
+		const data = image.take_data_as_f32();
+		image.dispose();
+		assert.strictEqual(exact.element, 'float32');
+		for (let y = 0; y < exact.height; y++) {
+			for (let x = 0; x < exact.width; x++) {
+				const values = exact.values_at(x, y);
+				const texts = JSON.parse(exact.value_text_at(x, y));
+				for (let c = 0; c < exact.channels; c++) {
+					const expected = data[(y * exact.width + x) * exact.channels + c];
+					assert.strictEqual(values[c], expected, `pixel (${x}, ${y}) band ${c}`);
+					assert.strictEqual(Math.fround(Number(texts[c])), expected, `text of pixel (${x}, ${y}) band ${c}`);
+				}
+			}
+		}
+		assert.throws(() => exact.values_at(exact.width, 0), /outside/);
+
+		const u16 = mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'pred_ref_u16.tif'))));
+		const raw = u16.get_data_as_f32();
+		u16.set_fixed_point_bits(4);
+		const u16Exact = u16.exact_values();
+		assert.strictEqual(u16Exact.element, 'uint16');
+		assert.strictEqual(u16Exact.byte_length, raw.length * 2);
+		assert.strictEqual(u16Exact.values_at(1, 0)[0], raw[u16.channels]);
+		assert.strictEqual(u16.get_data_as_f32()[u16.channels], raw[u16.channels] / 16);
+		console.log('✅ exact_values(): bit-exact readouts independent of the render data');
+	}
+
+	// 74. Raw tag dump: every entry with its field type and count, a
+	//     superset of all_tags_json, also for BigTIFF's 64-bit types.
+	{
+		const bytes = new Uint8Array(fs.readFileSync(path.join(samplesDir, 'sample_value_range_f32.tif')));
+		const tags = JSON.parse(mod.get_all_tags(bytes, 0));
+		const byId = new Map(tags.map(t => [t.id, t]));
+		assert.deepStrictEqual(byId.get(340), { id: 340, name: 'SMinSampleValue', group: 'TIFF', type: 12, type_name: 'DOUBLE', count: 1, value: [-1.5], truncated: false });
+		assert.strictEqual(byId.get(273).value.length, byId.get(273).count);
+		for (const tag of JSON.parse(mod.decode_tiff(bytes).all_tags_json)) {
+			assert.ok(byId.has(tag.tag), `tag ${tag.tag} (${tag.name}) is in the raw dump`);
+		}
+		assert.throws(() => mod.get_all_tags(bytes, 1), /out of range/);
+		const big = JSON.parse(mod.get_all_tags(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'bigtiff_u16.tif'))), 0));
+		assert.strictEqual(big.find(t => t.id === 273).type_name, 'LONG8');
+		console.log('✅ get_all_tags(): raw IFD dump with field types');
+	}
+
+	// 75. Alpha-aware statistics: the transparent left padding of an RGBA
+	//     render is left out of min/max, histograms and stats by default.
+	{
+		const bytes = new Uint8Array(fs.readFileSync(path.join(samplesDir, 'rgba_transparent_padding_u8.tif')));
+		const img = mod.decode_tiff(bytes);
+		assert.strictEqual(img.include_transparent_pixels, false);
+		assert.deepStrictEqual(Array.from(img.band_min_max(0)), [104, 115]);
+		assert.strictEqual(img.min_value, 50);
+		const stats = JSON.parse(img.value_stats_json());
+		assert.strictEqual(stats.transparent_count, 48);
+		const job = img.start_stats(undefined, undefined, undefined);
+		while (!job.step(img, 1000)) { /* run to completion */ }
+		const bands = JSON.parse(job.result_json());
+		assert.strictEqual(bands[0].count, 144);
+		assert.strictEqual(bands[3].transparent_count, 48);
+		img.set_include_transparent_pixels(true);
+		assert.deepStrictEqual(Array.from(img.band_min_max(0)), [0, 115]);
+		assert.strictEqual(img.min_value, 0);
+		assert.strictEqual(JSON.parse(img.value_stats_json()).transparent_count, 0);
+		img.free();
+		const fast = mod.decode_tiff_fast(bytes);
+		assert.ok(Number.isNaN(fast.min_value), 'a fast decode runs no stats pass');
+		fast.compute_stats();
+		assert.strictEqual(fast.min_value, 50);
+		fast.free();
+		console.log('✅ alpha-aware statistics skip fully transparent pixels');
+	}
+
+	// 76. Structured EXIF: capture settings from the Exif sub-IFD, camera
+	//     names from the page, null for a page without an Exif IFD.
+	{
+		const bytes = new Uint8Array(fs.readFileSync(path.join(samplesDir, 'exif_camera_u8.tif')));
+		const exif = JSON.parse(mod.get_exif(bytes, 0));
+		assert.strictEqual(exif.make, 'TestCam');
+		assert.strictEqual(exif.lens_model, 'TL 50mm F1.8');
+		assert.strictEqual(exif.exposure_time_text, '1/250');
+		assert.strictEqual(exif.f_number, 2.8);
+		assert.strictEqual(exif.iso, 400);
+		assert.strictEqual(exif.focal_length, 50);
+		assert.strictEqual(exif.exposure_program, 'aperture_priority');
+		assert.strictEqual(exif.flash_fired, false);
+		assert.strictEqual(exif.capture_time, '2024-06-01T14:30:05.25+02:00');
+		assert.strictEqual(mod.get_exif(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'house.tif'))), 0), 'null');
+		assert.throws(() => mod.get_exif(bytes, 1), /out of range/);
+		console.log('✅ get_exif(): structured capture settings');
+	}
+
+	// 77. Typed output: "auto" keeps the stored element type, "float32" is
+	//     the explicit conversion.
+	{
+		const expected = { 'pred_ref_rgb8.tif': [Uint8Array, 'uint8'], 'pred_ref_u16.tif': [Uint16Array, 'uint16'], 'pred_ref_f32.tif': [Float32Array, 'float32'] };
+		for (const [file, [arrayType, element]] of Object.entries(expected)) {
+			const img = mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, file))));
+			assert.strictEqual(img.native_element, element, `${file}: native element`);
+			const auto = img.get_data('auto');
+			assert.ok(auto instanceof arrayType, `${file}: auto output is a ${arrayType.name}`);
+			assert.deepStrictEqual(new Uint8Array(auto.buffer, auto.byteOffset, auto.byteLength), new Uint8Array(img.get_data_bytes()), `${file}: auto output is the stored samples`);
+			const floats = img.get_data('float32');
+			assert.ok(floats instanceof Float32Array);
+			assert.deepStrictEqual(Array.from(floats), Array.from(img.get_data_as_f32()), `${file}: float32 output`);
+			assert.throws(() => img.get_data('float16'), /unknown output/);
+			const taken = img.take_data('auto');
+			assert.strictEqual(taken.length, auto.length, `${file}: take_data moves the same samples`);
+			img.free();
+		}
+		console.log('✅ get_data()/take_data(): typed output without implicit f32');
+	}
+
+	// 78. ICC profile: the embedded Adobe RGB profile is exposed, and the
+	//     opt-in colour-managed render maps it into sRGB.
+	{
+		const img = mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'icc_adobe_rgb_u8.tif'))));
+		const icc = img.icc_profile;
+		assert.strictEqual(String.fromCharCode(...icc.slice(36, 40)), 'acsp');
+		const info = JSON.parse(img.icc_profile_json());
+		assert.strictEqual(info.description, 'Adobe RGB (1998) test');
+		assert.strictEqual(info.usable, true);
+		assert.strictEqual(info.tone_curves[0].kind, 'gamma');
+		const render = () => Array.from(img.render_rgba(0, 255, 1, 1, 0, '', new Uint8Array(0), 'srgb', undefined, undefined));
+		assert.deepStrictEqual(render(), [255, 0, 0, 255, 0, 255, 0, 255, 128, 128, 128, 255, 200, 120, 40, 255], 'unmanaged render is unchanged');
+		img.set_color_managed(true);
+		assert.strictEqual(img.color_managed, true);
+		assert.deepStrictEqual(render(), [255, 92, 92, 255, 0, 238, 128, 255, 129, 129, 129, 255, 224, 121, 25, 255], 'Adobe RGB mapped into sRGB');
+		assert.strictEqual(JSON.parse(img.render_settings_json(0, 255, 1, 1, 0, '', new Uint8Array(0), 'srgb', undefined, undefined)).color_managed, true);
+		img.free();
+		const plain = mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'pred_ref_rgb8.tif'))));
+		assert.strictEqual(plain.icc_profile.length, 0);
+		assert.strictEqual(plain.icc_profile_json(), 'null');
+		plain.set_color_managed(true);
+		assert.strictEqual(plain.color_managed, false, 'no profile, nothing to manage with');
+		plain.free();
+		console.log('✅ ICC profile access and colour-managed rendering');
+	}
+
+	// 79. decode_strided() samples every row_step-th row and col_step-th
+	//     column in the stored sample type; remotely only the tiles a
+	//     sampled pixel lands in are fetched. tiled_grid_u16_deflate.tif is
+	//     40x30 in 16x16 tiles with pixel (x, y) holding y * 40 + x.
+	{
+		const bytes = new Uint8Array(fs.readFileSync(path.join(samplesDir, 'tiled_grid_u16_deflate.tif')));
+		const expected = (rowStep, colStep) => {
+			const values = [];
+			for (let y = 0; y < 30; y += rowStep) {
+				for (let x = 0; x < 40; x += colStep) {
+					values.push(y * 40 + x);
+				}
+			}
+			return values;
+		};
+		const tiffFile = new mod.TiffFile(bytes, 0);
+		const sample = tiffFile.decode_strided(8, 5);
+		assert.strictEqual(sample.width, 8);
+		assert.strictEqual(sample.height, 4);
+		assert.strictEqual(sample.native_element, 'uint16');
+		assert.deepStrictEqual(Array.from(sample.get_data('auto')), expected(8, 5), 'strided samples');
+		assert.strictEqual(sample.min_value, 0);
+		assert.strictEqual(sample.max_value, 24 * 40 + 35);
+		assert.strictEqual(tiffFile.cached_chunk_count, 0, 'strided decodes bypass the chunk cache');
+		assert.throws(() => tiffFile.decode_strided(0, 1), /non-zero/);
+		sample.free();
+
+		const fetchRange = async (offset, length) => bytes.slice(offset, offset + length);
+		const remote = await mod.RemoteTiffReader.open(fetchRange, bytes.length);
+		const before = remote.bytes_fetched;
+		const firstRow = await remote.decode_strided(0, 32, 1);
+		assert.deepStrictEqual(Array.from(firstRow.get_data('auto')), expected(32, 1), 'remote strided samples');
+		const topTiles = remote.bytes_fetched - before;
+		const page = await remote.decode_page(0);
+		assert.ok(topTiles < remote.bytes_fetched - before - topTiles, 'only the top row of tiles is fetched');
+		firstRow.free();
+		page.free();
+		console.log('✅ decode_strided(): native-dtype strided previews, fetching only the sampled tiles');
+	}
+
+	// 80. decode_tiff_safe() errors say where the file went wrong:
+	//     corrupt_strip_u8_deflate.tif has garbage in its second strip (at
+	//     offset 32) and its IFD at offset 82.
+	{
+		const bytes = new Uint8Array(fs.readFileSync(path.join(samplesDir, 'corrupt_strip_u8_deflate.tif')));
+		const failure = (page) => {
+			try {
+				mod.decode_tiff_safe(bytes, page);
+			} catch (err) {
+				return JSON.parse(err.to_json());
+			}
+			assert.fail(`page ${page} decoded`);
+		};
+		const corrupt = failure(0);
+		assert.strictEqual(corrupt.code, 'corrupt');
+		assert.deepStrictEqual([corrupt.ifd, corrupt.ifd_offset, corrupt.tag, corrupt.offset], [0, 82, 273, 32], 'the bad strip is located');
+		const missing = failure(3);
+		assert.strictEqual(missing.code, 'invalid_argument');
+		assert.deepStrictEqual([missing.ifd, missing.ifd_offset, missing.tag, missing.offset], [3, null, null, null]);
+		console.log('✅ decode_tiff_safe(): errors carry the IFD, tag and file offset');
+	}
+
+	// 81. metadata_json() gathers the layout, stored bit depths,
+	//     resolution and descriptive tags into one object.
+	{
+		const metadata = (file) => {
+			const img = mod.decode_image(new Uint8Array(fs.readFileSync(path.join(samplesDir, file))));
+			const json = JSON.parse(img.metadata_json());
+			img.free();
+			return json;
+		};
+		const camera = metadata('exif_camera_u8.tif');
+		assert.strictEqual(camera.dtype, 'uint8');
+		assert.deepStrictEqual(camera.tags, { make: 'TestCam', model: 'Model X', software: 'fw 1.2' });
+		assert.strictEqual(camera.resolution, null);
+		const planar = metadata('shapes_lzw_planar_10bps.tif');
+		assert.deepStrictEqual(planar.bits_per_channel, [10, 10, 10]);
+		assert.strictEqual(planar.compression_name, 'LZW');
+		assert.deepStrictEqual(planar.resolution, { x: 72, y: 72, unit: 'inch' });
+		const cmyk = metadata('cmyk.tif');
+		assert.strictEqual(cmyk.photometric_name, 'CMYK');
+		assert.deepStrictEqual(cmyk.bits_per_channel, [8, 8, 8, 8], 'stored samples, before the RGB conversion');
+		const png = metadata('png_u16_gray.png');
+		assert.deepStrictEqual(png.bits_per_channel, [16]);
+		assert.deepStrictEqual(png.tags, {});
+		console.log('✅ metadata_json(): one structured metadata object');
+	}
+
+	// 82. display_to_source() and friends undo the applied orientation and
+	//     region offset. geo_orientation6_u8.tif stores a 7x4 raster whose
+	//     pixel (x, y) holds y * 7 + x, with Orientation=6 (shown as 4x7),
+	//     a 2x3 pixel scale and (0, 0) tied to model (100, 50).
+	{
+		const bytes = new Uint8Array(fs.readFileSync(path.join(samplesDir, 'geo_orientation6_u8.tif')));
+		const img = mod.decode_tiff(bytes);
+		assert.deepStrictEqual([img.width, img.height, img.orientation], [4, 7, 6]);
+		const data = img.get_data_as_f32();
+		for (let y = 0; y < img.height; y++) {
+			for (let x = 0; x < img.width; x++) {
+				const [sx, sy] = img.display_to_source(x + 0.5, y + 0.5);
+				assert.strictEqual(data[y * img.width + x], Math.floor(sy) * 7 + Math.floor(sx), `pixel (${x}, ${y})`);
+				assert.deepStrictEqual(Array.from(img.source_to_display(sx, sy)), [x + 0.5, y + 0.5]);
+				assert.deepStrictEqual(Array.from(img.display_to_model(x + 0.5, y + 0.5)), [100 + sx * 2, 50 - sy * 3]);
+			}
+		}
+		assert.deepStrictEqual(Array.from(img.model_to_display(101, 47)), [3, 0.5], 'stored (0.5, 1) is displayed at the top right');
+		const rect = [1, 2, 2, 3];
+		const source = Array.from(img.display_rect_to_source(...rect));
+		assert.deepStrictEqual(source, [2, 1, 3, 2], 'width and height swap');
+		const region = mod.decode_tiff_region(bytes, ...rect);
+		assert.deepStrictEqual(Array.from(region.display_rect_to_source(0, 0, 2, 3)), source, 'the crop offset is kept');
+		assert.deepStrictEqual(Array.from(region.source_rect_to_display(...source)), [0, 0, 2, 3]);
+		region.free();
+		img.free();
+		const upright = mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'orientation_tag1.tif'))));
+		assert.strictEqual(upright.orientation, 1);
+		upright.free();
+		console.log('✅ display_to_source(): pixel, ROI and model coordinates through orientation and region crops');
+	}
+
+	// 83. Decode failures are TiffError objects with a kind, so callers can
+	//     tell a truncated file from an unsupported one; String(error) is
+	//     still the message.
+	{
+		const caught = (decode) => {
+			try {
+				decode();
+			} catch (err) {
+				return err;
+			}
+			assert.fail('decoded');
+		};
+		const corrupt = new Uint8Array(fs.readFileSync(path.join(samplesDir, 'corrupt_strip_u8_deflate.tif')));
+		const truncated = caught(() => mod.decode_tiff(corrupt.slice(0, 60)));
+		assert.ok(truncated instanceof mod.TiffError);
+		assert.strictEqual(truncated.kind, mod.TiffErrorKind.Truncated);
+		assert.strictEqual(truncated.code, 'truncated');
+		assert.strictEqual(String(truncated), truncated.message);
+		assert.strictEqual(caught(() => mod.decode_tiff(corrupt)).code, 'corrupt');
+		assert.strictEqual(caught(() => mod.decode_tiff_page(corrupt, 2)).kind, mod.TiffErrorKind.InvalidArgument);
+		const notImage = caught(() => mod.decode_image(new TextEncoder().encode('not an image at all')));
+		assert.strictEqual(notImage.code, 'unrecognized_format');
+		const region = caught(() => mod.decode_tiff_region(corrupt, 6, 0, 4, 4));
+		assert.strictEqual(region.code, 'invalid_argument');
+		assert.match(String(region), /outside the 8x6 image/);
+		console.log('✅ TiffError: typed error kinds for truncated, corrupt, unrecognized and out-of-range input');
+	}
+
+	// 84. channel_min_values / channel_max_values measure every channel on
+	//     its own: rgb_channel_ranges_f32.tif holds R in 0..1, G in
+	//     100..200 and B in -5..-1, and the page-wide range spans them all.
+	{
+		const img = mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'rgb_channel_ranges_f32.tif'))));
+		assert.deepStrictEqual(Array.from(img.channel_min_values), [0, 100, -5]);
+		assert.deepStrictEqual(Array.from(img.channel_max_values), [1, 200, -1]);
+		assert.deepStrictEqual([img.min_value, img.max_value], [-5, 200]);
+		assert.deepStrictEqual(Array.from(img.band_min_max(1)), [100, 200]);
+		img.set_value_transform('v * 2');
+		assert.deepStrictEqual(Array.from(img.channel_max_values), [2, 400, -2]);
+		assert.deepStrictEqual([img.min_value, img.max_value], [-10, 400], 'the page-wide range follows the channels');
+		img.free();
+		console.log('✅ channel_min_values/channel_max_values: per-channel ranges behind min_value/max_value');
+	}
+
+	// 85. shared_view and page cache hits share the decoded planes instead
+	//     of copying them; settings stay per result, and taking the data
+	//     out of one copies it while the other still needs it.
+	{
+		const bytes = new Uint8Array(fs.readFileSync(path.join(samplesDir, 'rgb_channel_ranges_f32.tif')));
+		const img = mod.decode_tiff(bytes);
+		const expected = Array.from(img.get_data_as_f32());
+		const view = img.shared_view();
+		assert.ok(view.shares_planes_with(img));
+		view.set_value_transform('v * 2');
+		assert.deepStrictEqual([img.max_value, view.max_value], [200, 400], 'the value transform is the view\'s own');
+		const taken = img.shared_view();
+		assert.deepStrictEqual(Array.from(taken.take_data_as_f32()), expected);
+		taken.dispose();
+		assert.deepStrictEqual(Array.from(img.get_data_as_f32()), expected, 'taking from a view leaves the original its data');
+		view.free();
+		img.free();
+
+		const doc = new mod.TiffDocument(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'multipage_rgb_depth_mask.tif'))));
+		const first = doc.decode_page(1);
+		const hit = doc.decode_page(1);
+		assert.ok(hit.shares_planes_with(first), 'a page cache hit shares the cached planes');
+		assert.ok(!hit.shares_planes_with(doc.decode_page(0)));
+		console.log('✅ shared_view / page cache: derived results share planes, copy on take');
+	}
+
+	// 86. The TiffFile prefetcher queues the tiles around the last one
+	//     served, nearest first, and decodes them into the LRU on request
+	//     without ever evicting the tiles just served.
+	{
+		const bytes = new Uint8Array(fs.readFileSync(path.join(samplesDir, 'tiled_grid_u16_deflate.tif')));
+		const tiffFile = new mod.TiffFile(bytes, 0);
+		tiffFile.decode_tile(0, 0);
+		assert.strictEqual(tiffFile.pending_prefetch_count, 0, 'the prefetcher is off by default');
+		tiffFile.set_prefetch_margin(1);
+		tiffFile.decode_tile(0, 0);
+		assert.strictEqual(tiffFile.pending_prefetch_count, 3, 'the three neighbours of the corner tile');
+		assert.strictEqual(tiffFile.prefetch(1), 2);
+		assert.strictEqual(tiffFile.prefetch(10), 0);
+		assert.strictEqual(tiffFile.cached_chunk_count, 4);
+		assert.deepStrictEqual(Array.from(tiffFile.read_region(16, 16, 2, 1, 1)), [656, 657], 'prefetched tile (1, 1)');
+
+		const small = new mod.TiffFile(bytes, 0);
+		small.set_cache_capacity(2);
+		small.set_prefetch_margin(1);
+		small.decode_tile(1, 0);
+		assert.strictEqual(small.pending_prefetch_count, 5);
+		assert.strictEqual(small.prefetch(10), 0, 'the queue is dropped once the cache is full');
+		assert.strictEqual(small.cached_chunk_count, 2);
+		small.set_prefetch_margin(0);
+		small.decode_tile(2, 1);
+		assert.strictEqual(small.pending_prefetch_count, 0);
+		console.log('✅ TiffFile prefetch: neighbouring tiles decoded ahead within the cache limits');
+	}
+
+	// 87. compute_histogram / compute_band_histogram bin over a caller's
+	//     range (NaN: the page's or band's own), leaving out-of-range
+	//     samples out, and agree with `histograms` on the same range.
+	{
+		const img = mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'rgb_channel_ranges_f32.tif'))));
+		assert.deepStrictEqual(Array.from(img.compute_histogram(2, NaN, NaN)), [12, 6], 'R and B below 97.5, G above');
+		assert.deepStrictEqual(Array.from(img.compute_band_histogram(0, 4, NaN, NaN)), [2, 1, 1, 2]);
+		assert.deepStrictEqual(Array.from(img.compute_band_histogram(1, 5, 100, 150)), [1, 1, 1, 0, 1]);
+		assert.throws(() => img.compute_histogram(0, 0, 1), /bin count/);
+		assert.throws(() => img.compute_histogram(4, 1, 0), /not a finite, ordered range/);
+		img.free();
+
+		const rgba = mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'rgba_transparent_padding_u8.tif'))));
+		const histograms = rgba.histograms(16);
+		const counts = Array.from(histograms.get_counts());
+		for (let band = 0; band < 4; band++) {
+			const [min, max] = [histograms.get_mins()[band], histograms.get_maxs()[band]];
+			assert.deepStrictEqual(Array.from(rgba.compute_band_histogram(band, 16, min, max)), counts.slice(band * 16, band * 16 + 16), `band ${band}`);
+		}
+		rgba.free();
+		console.log('✅ compute_histogram: ranged bin counts in Rust, per page and per band');
+	}
+
+	// 88. TiffDocument.diff_pages: time_stack_f32.tif is three 4x3 float
+	//     pages where page t holds y * 4 + x + t * x, and page 1 marks
+	//     pixel 5 as nodata (-9999).
+	{
+		const doc = new mod.TiffDocument(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'time_stack_f32.tif'))));
+		const change = doc.diff_pages(0, 2);
+		assert.deepStrictEqual(Array.from(change.get_data_as_f32()), [0, 2, 4, 6, 0, 2, 4, 6, 0, 2, 4, 6], 'page 2 minus page 0');
+		assert.deepStrictEqual([change.min_value, change.max_value], [0, 6]);
+		const masked = doc.diff_pages(0, 1);
+		assert.ok(Number.isNaN(masked.get_data_as_f32()[5]), 'nodata in either page is NaN');
+		const stats = JSON.parse(masked.summary()).channel_stats[0];
+		assert.deepStrictEqual([stats.max, stats.nan_count, JSON.parse(masked.summary()).nodata], [3, 1, null]);
+		assert.throws(() => new mod.TiffDocument(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'multipage_rgb_depth_mask.tif')))).diff_pages(0, 1),
+			/differ in shape \(48x32x3 vs 48x32x1\)/);
+		console.log('✅ TiffDocument.diff_pages: per-pixel change between pages with stats');
+	}
+
+	// 89. channel_means / channel_std_devs / channel_medians come with the
+	//     per-channel min/max and agree with `summary`'s exact statistics.
+	{
+		const img = mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'rgb_channel_ranges_f32.tif'))));
+		const exact = JSON.parse(img.summary()).channel_stats;
+		const means = Array.from(img.channel_means);
+		const stdDevs = Array.from(img.channel_std_devs);
+		assert.deepStrictEqual(Array.from(img.channel_medians), [0.5, 150, -3], 'nearest-rank medians');
+		assert.deepStrictEqual(exact.map((stats) => stats.p50), [0.5, 150, -3]);
+		for (let band = 0; band < 3; band++) {
+			assert.ok(Math.abs(means[band] - exact[band].mean) < 1e-9, `band ${band} mean`);
+			assert.ok(Math.abs(stdDevs[band] - exact[band].std) < 1e-9, `band ${band} std`);
+		}
+		assert.ok(Math.abs(means[1] - 860 / 6) < 1e-9);
+		img.set_value_transform('v + 1000');
+		assert.ok(Math.abs(img.channel_means[2] - (1000 - 3.25)) < 1e-9, 'means follow the value transform');
+		assert.ok(Math.abs(img.channel_std_devs[2] - stdDevs[2]) < 1e-9, 'a shift leaves the spread alone');
+		img.free();
+		console.log('✅ channel_means / channel_std_devs / channel_medians per channel');
+	}
+
+	// 90. tile_heatmap: one pixel per tile. mosaic_dead_tile_f32.tif is 32x32
+	//     in 16x16 tiles of x + y, with the top-right tile all NaN and the
+	//     left half of the bottom-left tile nodata (-9999).
+	{
+		const img = mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'mosaic_dead_tile_f32.tif'))));
+		const heatmap = (metric, width = 0, height = 0) => {
+			const result = img.tile_heatmap(metric, width, height);
+			const values = Array.from(result.get_data_as_f32());
+			result.free();
+			return values;
+		};
+		assert.deepStrictEqual(heatmap('valid_fraction'), [1, 0, 0.5, 1], 'dead and half-nodata tiles');
+		assert.deepStrictEqual(heatmap('mean').map((v) => (Number.isNaN(v) ? null : v)), [15, null, 35, 47]);
+		assert.ok(Math.abs(heatmap('std')[0] - Math.sqrt(2 * (16 * 16 - 1) / 12)) < 1e-5, 'std of x + y over a 16x16 tile');
+		assert.strictEqual(heatmap('mean', 0, 8).length, 8, 'a custom tile height splits each tile row in two');
+		assert.throws(() => img.tile_heatmap('median', 0, 0), /unknown metric 'median'/);
+		img.free();
+
+		const grid = mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'tiled_grid_u16_deflate.tif'))));
+		const means = grid.tile_heatmap('mean', 0, 0);
+		assert.deepStrictEqual([means.width, means.height], [3, 2]);
+		assert.deepStrictEqual(Array.from(means.get_data_as_f32()), [307.5, 323.5, 335.5, 907.5, 923.5, 935.5]);
+		console.log('✅ tile_heatmap: per-tile mean / std / valid fraction raster');
+	}
+
+	// 91. resample_viewport: nearest copies the sample under each output
+	//     pixel, bilinear blends neighbours but not across NaN/nodata.
+	{
+		const img = mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'mosaic_dead_tile_f32.tif'))));
+		const row = (x, y, width, height, outWidth, outHeight, resampling) => {
+			const result = img.resample_viewport(x, y, width, height, outWidth, outHeight, resampling);
+			const values = Array.from(result.get_data_as_f32()).slice(0, outWidth);
+			result.free();
+			return values;
+		};
+		assert.deepStrictEqual(row(2, 2, 4, 4, 8, 8, 'nearest'), [4, 4, 5, 5, 6, 6, 7, 7], 'pixels are repeated');
+		assert.deepStrictEqual(row(2, 2, 4, 4, 8, 8, 'bilinear'), [3.5, 4, 4.5, 5, 5.5, 6, 6.5, 7], 'x + y is linear');
+		assert.deepStrictEqual(row(15, 0, 2, 1, 4, 1, 'bilinear').map((v) => (Number.isNaN(v) ? null : v)), [14.75, 15, null, null],
+			'next to the NaN tile the nearest sample is kept');
+		assert.throws(() => img.resample_viewport(0, 0, 4, 4, 8, 8, 'bicubic'), /unknown resampling 'bicubic'/);
+		assert.throws(() => img.resample_viewport(30, 0, 4, 4, 8, 8, 'nearest'), /not inside the 32x32 image/);
+		img.free();
+
+		const grid = mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'tiled_grid_u16_deflate.tif'))));
+		const smooth = grid.resample_viewport(0, 0, 3, 1, 4, 1, 'bilinear');
+		assert.strictEqual(JSON.parse(smooth.summary()).dtype, 'uint16', 'the sample type is kept');
+		assert.deepStrictEqual(Array.from(smooth.get_data_as_f32()), [0, 1, 1, 2], 'blended integers are rounded');
+		smooth.free();
+		grid.free();
+		console.log('✅ resample_viewport: nearest vs bilinear viewport resampling');
+	}
+
+	// 92. invalid_sample_counts: special_values_f32.tif is 4x2 float with
+	//     two NaN, two +Inf, one -Inf and one -9999 (its GDAL nodata).
+	{
+		const img = mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'special_values_f32.tif'))));
+		assert.deepStrictEqual(Array.from(img.invalid_sample_counts(undefined)), [2, 2, 1, 1], 'declared nodata');
+		assert.deepStrictEqual(Array.from(img.invalid_sample_counts(5)), [2, 2, 1, 0], 'a chosen nodata value replaces the declared one');
+		assert.deepStrictEqual(Array.from(img.invalid_sample_counts(NaN)), [0, 2, 1, 2], 'NaN as nodata');
+		img.free();
+		console.log('✅ invalid_sample_counts: NaN / +Inf / -Inf / nodata');
+	}
+
+	// 93. set_transfer_byte_budget: whole-image accessors over the budget
+	//     throw a too_large TiffError with a downsampling step that fits.
+	{
+		const img = mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'mosaic_dead_tile_f32.tif'))));
+		const refusal = (read) => {
+			try {
+				read();
+			} catch (err) {
+				return err;
+			}
+			assert.fail('the transfer went through');
+		};
+		img.set_transfer_byte_budget(300);
+		assert.strictEqual(img.transfer_byte_budget, 300);
+		const err = refusal(() => img.get_data_as_f32());
+		assert.strictEqual(err.code, 'too_large');
+		assert.strictEqual(err.suggested_downsample, 4, '8x8 f32 samples are the first preview under 300 bytes');
+		assert.match(err.message, /get_data_as_f32 would return 4096 bytes/);
+		assert.strictEqual(JSON.parse(err.to_json()).suggested_downsample, 4);
+		assert.strictEqual(refusal(() => img.render_rgba(0, 62, 1, 1, 0, '', new Uint8Array(0), 'srgb', undefined, undefined)).suggested_downsample, 4);
+		img.set_transfer_byte_budget(4096);
+		assert.strictEqual(img.get_data_as_f32().length, 32 * 32, 'a transfer at the budget goes through');
+		img.set_transfer_byte_budget(0);
+		assert.strictEqual(img.get_data_bytes().length, 4096);
+		img.free();
+		console.log('✅ set_transfer_byte_budget: oversized transfers are refused with a downsample hint');
+	}
+
+	// 94. DecodeOptions.set_compute_stats(false) skips the min/max pass;
+	//     compute_stats() runs it later and matches an eager decode.
+	{
+		const bytes = new Uint8Array(fs.readFileSync(path.join(samplesDir, 'rgb_channel_ranges_f32.tif')));
+		const options = new mod.DecodeOptions();
+		assert.strictEqual(options.compute_stats, true, 'stats are on by default');
+		options.set_compute_stats(false);
+		const img = mod.decode_tiff_with_options(bytes, 0, options);
+		assert.ok(Number.isNaN(img.min_value) && Number.isNaN(img.max_value), 'no stats pass yet');
+		img.compute_stats();
+		const eager = mod.decode_tiff(bytes);
+		assert.deepStrictEqual([img.min_value, img.max_value], [eager.min_value, eager.max_value]);
+		assert.deepStrictEqual([img.min_value, img.max_value], [-5, 200]);
+		eager.free();
+		img.free();
+		options.free();
+		console.log('✅ DecodeOptions: deferred statistics with compute_stats()');
+	}
+
+	// 95. channel_labels_json names bands from GDAL band descriptions and
+	//     OME-XML channels, falling back to the layout names.
+	{
+		const labels = (file) => {
+			const img = mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, file))));
+			const json = JSON.parse(img.channel_labels_json());
+			img.free();
+			return json;
+		};
+		assert.deepStrictEqual(labels('gdal_band_descriptions_f32.tif'), { source: 'gdal', names: ['depth', 'confidence', 'band2'] });
+		assert.deepStrictEqual(labels('ome_channels_u8.tif'), { source: 'ome', names: ['DAPI', 'Actin & Tubulin'] });
+		assert.deepStrictEqual(labels('rgb_channel_ranges_f32.tif'), { source: 'layout', names: ['red', 'green', 'blue'] });
+		console.log('✅ channel_labels_json: GDAL / OME channel names');
+	}
+
+	// 96. render_confidence_rgba fades the depth band by the confidence band
+	//     (0, 1/6, ..., 5/6 here) as alpha or as saturation.
+	{
+		const img = mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'gdal_band_descriptions_f32.tif'))));
+		const alpha = Array.from(img.render_confidence_rgba(1.5, 6.5, '', 0, 1, 0, 1, 'alpha', new Uint8Array(0)));
+		assert.deepStrictEqual(alpha.slice(0, 4), [0, 0, 0, 0], 'depth at min with no confidence');
+		assert.deepStrictEqual(alpha.slice(12, 16), [153, 153, 153, 128], 'depth 4.5 at confidence 0.5');
+		const plain = Array.from(img.render_rgba(1.5, 6.5, 1, 1, 0, 'viridis', new Uint8Array(0), 'srgb', undefined, undefined));
+		const faded = Array.from(img.render_confidence_rgba(1.5, 6.5, 'viridis', 0, 1, 0, 5 / 6, 'saturation', new Uint8Array(0)));
+		assert.deepStrictEqual(faded.slice(20, 24), plain.slice(20, 24), 'full confidence keeps the colormap colour');
+		const [r, g, b, a] = faded.slice(0, 4);
+		assert.ok(r === g && g === b && a === 255, 'zero confidence is gray and opaque');
+		assert.throws(() => img.render_confidence_rgba(1.5, 6.5, '', 0, 1, 0, 1, 'hue', new Uint8Array(0)), /unknown mode/);
+		assert.throws(() => img.render_confidence_rgba(1.5, 6.5, '', 0, 3, 0, 1, 'alpha', new Uint8Array(0)), /out of range/);
+		img.free();
+		console.log('✅ render_confidence_rgba: confidence as alpha or saturation');
+	}
+
+	// 97. render_to_rgba with RenderOptions matches render_rgba, taking the
+	//     image's own range (measured on demand) when min/max are unset.
+	{
+		const bytes = new Uint8Array(fs.readFileSync(path.join(samplesDir, 'rgb_channel_ranges_f32.tif')));
+		const img = mod.decode_tiff(bytes);
+		const options = new mod.RenderOptions();
+		assert.strictEqual(options.min, undefined);
+		assert.strictEqual(options.gamma, 1);
+		const none = new Uint8Array(0);
+		assert.deepStrictEqual(Array.from(img.render_to_rgba(options)), Array.from(img.render_rgba(-5, 200, 1, 1, 0, '', none, 'srgb', undefined, undefined)));
+		options.set_min(0);
+		options.set_max(100);
+		options.set_gamma(2.2);
+		assert.deepStrictEqual(Array.from(img.render_to_rgba(options)), Array.from(img.render_rgba(0, 100, 1, 2.2, 0, '', none, 'srgb', undefined, undefined)));
+		const fastOptions = new mod.DecodeOptions();
+		fastOptions.set_compute_stats(false);
+		const fast = mod.decode_tiff_with_options(bytes, 0, fastOptions);
+		const auto = new mod.RenderOptions();
+		assert.deepStrictEqual(Array.from(fast.render_to_rgba(auto)), Array.from(img.render_to_rgba(auto)), 'the automatic range is measured when stats were skipped');
+		options.set_gamma(0);
+		assert.throws(() => img.render_to_rgba(options), /gamma 0 must be finite and positive/);
+		[img, fast, options, auto, fastOptions].forEach(o => o.free());
+		console.log('✅ render_to_rgba: RenderOptions with automatic range and gamma');
+	}
+
+	// 98. match_histogram maps values onto the reference's quantiles: an
+	//     image matched to itself is unchanged, and matching to a bilevel
+	//     reference leaves only its two values.
+	{
+		const open = (file) => mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, file))));
+		const img = open('gdal_band_descriptions_f32.tif');
+		const bilevel = open('ccitt_none.tif');
+		const self = mod.match_histogram(img, img);
+		assert.deepStrictEqual(Array.from(self.get_data_as_f32()), Array.from(img.get_data_as_f32()));
+		const matched = mod.match_histogram(img, bilevel);
+		assert.strictEqual(matched.channels, 3);
+		assert.ok(Array.from(matched.get_data_as_f32()).every(v => v === 0 || v === 255), 'values come from the reference');
+		[img, bilevel, self, matched].forEach(o => o.free());
+		console.log('✅ match_histogram: quantile mapping onto a reference');
+	}
+
+	// 99. Annotations burn rectangles, points and polylines into rendered
+	//     RGBA, blended by their alpha, and into annotated snapshots.
+	{
+		const img = mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'ccitt_none.tif'))));
+		const annotations = new mod.Annotations();
+		annotations.add_rect(10, 10, 20, 10, 1, false, new Uint8Array([255, 0, 0]));
+		annotations.add_point(50, 50, 2, new Uint8Array([0, 255, 0, 128]));
+		annotations.add_polyline(new Float64Array([0, 90.5, 127, 90.5]), 1, new Uint8Array([0, 0, 255]));
+		assert.strictEqual(annotations.length, 3);
+		const none = new Uint8Array(0);
+		const rgba = img.render_rgba(0, 255, 1, 1, 0, '', none, 'srgb', undefined, undefined);
+		const plain = Array.from(rgba);
+		annotations.burn_into(rgba, img.width, img.height);
+		const at = (x, y) => Array.from(rgba.slice((y * img.width + x) * 4, (y * img.width + x) * 4 + 4));
+		assert.deepStrictEqual(at(10, 15), [255, 0, 0, 255], 'left edge of the rectangle');
+		assert.deepStrictEqual(at(20, 15), plain.slice((15 * img.width + 20) * 4, (15 * img.width + 20) * 4 + 4), 'the outline leaves the inside alone');
+		assert.deepStrictEqual(at(64, 90), [0, 0, 255, 255], 'polyline');
+		const under = plain[(50 * img.width + 50) * 4];
+		assert.deepStrictEqual(at(50, 50).slice(0, 2), [Math.round(under * 127 / 255), Math.round((255 * 128 + under * 127) / 255)]);
+		const png = img.export_annotated_snapshot_png(0, 255, 1, 1, 0, '', none, undefined, undefined, annotations);
+		assert.notDeepStrictEqual(Array.from(png), Array.from(img.export_snapshot_png(0, 255, 1, 1, 0, '', none, undefined, undefined)));
+		assert.throws(() => annotations.add_polyline(new Float64Array([1, 2, 3]), 1, new Uint8Array([0, 0, 0])), /x, y pairs/);
+		assert.throws(() => annotations.burn_into(new Uint8Array(8), img.width, img.height), /RGBA buffer/);
+		annotations.free();
+		img.free();
+		console.log('✅ Annotations: shapes burned into renders and snapshots');
+	}
+
+	// 100. RenderOptions.colormap maps single-channel data through the named
+	//      colormap inside render_to_rgba; "grayscale" is the gray ramp.
+	{
+		const img = mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'ccitt_none.tif'))));
+		const options = new mod.RenderOptions();
+		assert.strictEqual(options.colormap, '');
+		const none = new Uint8Array(0);
+		for (const name of ['viridis', 'magma', 'inferno', 'plasma', 'turbo', 'jet']) {
+			options.set_colormap(name);
+			assert.deepStrictEqual(Array.from(img.render_to_rgba(options)), Array.from(img.render_rgba(0, 255, 1, 1, 0, name, none, 'srgb', undefined, undefined)), name);
+		}
+		options.set_colormap('grayscale');
+		assert.deepStrictEqual(Array.from(img.render_to_rgba(options)), Array.from(img.render_rgba(0, 255, 1, 1, 0, 'gray', none, 'srgb', undefined, undefined)));
+		options.set_colormap('rainbow');
+		assert.throws(() => img.render_to_rgba(options), /unknown colormap 'rainbow'/);
+		options.free();
+		img.free();
+		console.log('✅ render_to_rgba: built-in colormaps by name');
+	}
+
+	// 101. RenderOptions.srgb_transfer encodes linear-light samples with the
+	//      sRGB curve instead of the gamma power.
+	{
+		const img = mod.decode_tiff(new Uint8Array(fs.readFileSync(path.join(samplesDir, 'gdal_band_descriptions_f32.tif'))));
+		const options = new mod.RenderOptions();
+		options.set_min(0);
+		options.set_max(100);
+		assert.strictEqual(options.srgb_transfer, false);
+		assert.deepStrictEqual(Array.from(img.render_to_rgba(options).slice(0, 8)), [4, 0, 255, 255, 6, 0, 255, 255]);
+		options.set_srgb_transfer(true);
+		assert.deepStrictEqual(Array.from(img.render_to_rgba(options).slice(0, 8)), [33, 0, 255, 255, 44, 5, 255, 255], 'sRGB lifts dark linear values, linear below 0.0031308');
+		options.set_gamma(3);
+		assert.deepStrictEqual(Array.from(img.render_to_rgba(options).slice(0, 4)), [33, 0, 255, 255], 'gamma is ignored with the sRGB curve');
+		options.free();
+		img.free();
+		console.log('✅ render_to_rgba: sRGB transfer for linear-light data');
+	}
+
 	console.log('\n🎉 All WASM TIFF decoder tests passed.\n');
 }
 
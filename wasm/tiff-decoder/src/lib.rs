@@ -5,16 +5,37 @@
 //! geotiff.js while maintaining compatibility with existing JavaScript code.
 
 use wasm_bindgen::prelude::*;
+use std::borrow::Cow;
+use std::cell::{Cell, OnceCell, RefCell};
+use std::collections::HashMap;
 use std::io::Cursor;
+use std::rc::Rc;
 use std::mem;
+#[cfg(feature = "exr")]
 use exr::prelude::FlatSamples;
 use tiff::decoder::{Decoder, DecodingResult};
 
 #[cfg(feature = "console_error_panic_hook")]
 pub use console_error_panic_hook::set_once as set_panic_hook;
 
+/// Milliseconds for the decode timings: `Date.now()` in the browser, and a
+/// process-relative std clock on native builds, where js_sys imports panic
+/// (tests, and the threaded worker pool, run there).
+#[cfg(target_arch = "wasm32")]
+fn now_ms() -> f64 {
+    js_sys::Date::now()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn now_ms() -> f64 {
+    use std::sync::OnceLock;
+    static EPOCH: OnceLock<std::time::Instant> = OnceLock::new();
+    EPOCH.get_or_init(std::time::Instant::now).elapsed().as_secs_f64() * 1000.0
+}
+
 /// Result type for TIFF decoding operations
 #[wasm_bindgen]
+#[derive(Clone)]
 pub struct TiffResult {
     width: u32,
     height: u32,
@@ -34,14 +55,26 @@ pub struct TiffResult {
     tile_length: u32,
     tile_count: u32,
     direct_decode: bool,
-    // Data stored as bytes, interpreted based on sample_format
-    data: Vec<u8>,
+    // Data stored as bytes, interpreted based on sample_format. The sample
+    // buffers are reference-counted so that results derived from this one
+    // (`shared_view`, page cache hits) share the planes instead of copying
+    // them; whichever result changes or takes a buffer still shared gets
+    // its own copy first.
+    data: Rc<Vec<u8>>,
     // Float representation used by the webview render pipeline. For float TIFFs
     // this avoids converting decoded f32 pixels to bytes and back again.
-    data_f32: Vec<f32>,
+    data_f32: Rc<Vec<f32>>,
+    // f16/f64 samples are kept exactly as decoded instead of being narrowed
+    // or widened to f32 up front; `data_f32_cache` is filled from them (or
+    // from integer `data`) the first time a caller asks for f32 data.
+    native_float: Option<Rc<DecodingResult>>,
+    data_f32_cache: OnceCell<Rc<Vec<f32>>>,
     // Computed statistics
     min_value: f64,
     max_value: f64,
+    // True when min/max were taken from the page's sample value tags
+    // instead of a stats pass (`tagged_sample_range`).
+    range_from_tags: bool,
     timing_metadata_ms: f64,
     timing_decode_ms: f64,
     timing_convert_ms: f64,
@@ -54,8 +87,50 @@ pub struct TiffResult {
     // every page result so restoring directly to a later page still has the
     // dataset's C/Z/T semantics without decoding page zero first.
     ome_xml: String,
+    // Per-strip/tile telemetry from `decode_tiff_page_profiled` (see
+    // `ChunkProfile::to_json`); empty for every other decode entry point.
+    chunk_profile_json: String,
+    // GeoTIFF georeferencing and GDAL nodata, reported by `summary`.
+    geo: GeoInfo,
+    // Stored bit depths, resolution and descriptive tags, reported by
+    // `metadata_json`.
+    page_metadata: PageMetadata,
+    // Width / height of one pixel from XResolution/YResolution, already
+    // swapped for transposing orientations; 1.0 for square or unknown.
+    pixel_aspect_ratio: f64,
+    // Orientation and region offset relating these pixels to the stored page
+    // (`display_to_source`); None is the stored page as is.
+    source_frame: Option<SourceFrame>,
+    // Q-format fractional bits (`set_fixed_point_bits`): integer samples are
+    // divided by 2^fractional_bits when converted to f32; 0 leaves them as is.
+    fractional_bits: u32,
+    // Per-sample expression from `set_value_transform`, applied after the
+    // fixed-point scaling; its own min/max replace the stored ones.
+    value_transform: Option<ValueTransform>,
+    // `set_include_transparent_pixels`; when false, pixels whose alpha is
+    // zero are left out of the statistics.
+    include_transparent: bool,
+    // Finite min/max, mean and spread of each channel (`channel_stats`),
+    // computed on first use and reset whenever what they are measured over
+    // changes.
+    channel_stats: OnceCell<Vec<ChannelStats>>,
+    // RGB primaries and white point of the colour samples as CIE xy (red,
+    // green, blue, white), from PrimaryChromaticities/WhitePoint or the EXR
+    // chromaticities; None means sRGB / Rec. 709.
+    primaries: Option<[f64; 8]>,
+    // Embedded ICC profile (InterColorProfile, 34675) as stored, empty when
+    // there is none, and whether `render_rgba` colour-manages with it
+    // (`set_color_managed`).
+    icc_profile: Vec<u8>,
+    color_managed: bool,
+    // `set_transfer_byte_budget`: the most bytes one whole-image accessor
+    // may hand to JS, 0 for no limit.
+    transfer_byte_budget: usize,
+    // Set by `dispose`: the buffers are gone and every accessor errors.
+    disposed: bool,
 }
 
+#[cfg(feature = "exr")]
 #[wasm_bindgen]
 pub struct ExrResult {
     width: u32,
@@ -73,8 +148,11 @@ pub struct ExrResult {
     // plus the crate's generic "other"/custom-attribute bags), in the same
     // {"tag","name","group","value"} shape as TiffResult.all_tags_json.
     all_tags_json: String,
+    // The chromaticities attribute, as `TiffResult::source_primaries`.
+    primaries: Option<[f64; 8]>,
 }
 
+#[cfg(feature = "png")]
 #[wasm_bindgen]
 pub struct PngResult {
     width: u32,
@@ -89,6 +167,7 @@ pub struct PngResult {
     timing_total_ms: f64,
 }
 
+#[cfg(feature = "hdr")]
 #[wasm_bindgen]
 pub struct HdrResult {
     data_f32: Vec<f32>,
@@ -98,6 +177,7 @@ pub struct HdrResult {
 
 /// Small, format-neutral result used when a container (currently DICOM)
 /// supplies an individual JPEG codestream to the shared Rust decoder.
+#[cfg(feature = "jpeg")]
 #[wasm_bindgen]
 pub struct JpegResult {
     width: u32,
@@ -106,6 +186,7 @@ pub struct JpegResult {
     data_u8: Vec<u8>,
 }
 
+#[cfg(feature = "jpeg")]
 #[wasm_bindgen]
 impl JpegResult {
     #[wasm_bindgen(getter)]
@@ -125,6 +206,7 @@ impl JpegResult {
 
 /// Decode a complete JPEG codestream. DICOM parsing and frame extraction stay
 /// in TypeScript; this reuses the same zune-jpeg codec already used by TIFF.
+#[cfg(feature = "jpeg")]
 #[wasm_bindgen]
 pub fn decode_jpeg_fast(data: &[u8]) -> Result<JpegResult, JsValue> {
     use zune_jpeg::JpegDecoder;
@@ -150,6 +232,110 @@ pub fn decode_jpeg_fast(data: &[u8]) -> Result<JpegResult, JsValue> {
     })
 }
 
+/// Decode a JPEG photo into a `TiffResult` (u8 gray, RGB or CMYK samples),
+/// so JPEGs in a mixed folder go through the same stats, normalization and
+/// pixel inspection as TIFFs instead of the browser `<img>` path, and hover
+/// values and histograms agree across formats. The EXIF Orientation is
+/// applied like the browser does (top-left origin afterwards) and the EXIF
+/// block is reported through `all_tags_json`.
+#[cfg(feature = "jpeg")]
+#[wasm_bindgen]
+pub fn decode_jpeg(data: &[u8]) -> Result<TiffResult, JsValue> {
+    #[cfg(feature = "console_error_panic_hook")]
+    console_error_panic_hook::set_once();
+
+    use zune_jpeg::JpegDecoder;
+
+    let mut decoder = JpegDecoder::new(Cursor::new(data));
+    let pixels = decoder.decode()
+        .map_err(|e| JsValue::from_str(&format!("JPEG decode failed: {:?}", e)))?;
+    let info = decoder.info()
+        .ok_or_else(|| JsValue::from_str("JPEG: missing image info"))?;
+    let (width, height) = (info.width as u32, info.height as u32);
+    let pixel_count = (width as usize).saturating_mul(height as usize);
+    if pixel_count == 0 || pixels.len() % pixel_count != 0 {
+        return Err(JsValue::from_str("JPEG: invalid decoded dimensions"));
+    }
+    let channels = (pixels.len() / pixel_count) as u32;
+    if channels != 1 && channels != 3 && channels != 4 {
+        return Err(JsValue::from_str("JPEG: unsupported decoded channel count"));
+    }
+
+    let exif = decoder.exif().map(|e| e.as_slice()).unwrap_or_default();
+    let all_tags_json = if exif.is_empty() { String::new() } else { extract_bare_ifd_tags_json(exif) };
+    let orientation = TiffOrientation::from_tag(exif_orientation(exif).unwrap_or(1) as u32);
+    let (pixels, width, height) = if orientation == TiffOrientation::TopLeft {
+        (pixels, width, height)
+    } else {
+        apply_orientation(&pixels, width, height, channels, orientation)
+    };
+    let mut result = TiffResult::from_decoded(width, height, channels, DecodingResult::U8(pixels), all_tags_json);
+    result.source_frame = Some(SourceFrame::new(orientation, info.width as u32, info.height as u32));
+    Ok(result)
+}
+
+/// Decode a still WebP (lossy VP8 or lossless VP8L, with or without alpha)
+/// into a u8 RGB/RGBA `TiffResult`, so WebP previews in a dataset folder
+/// open in the same viewer path as everything else. Animated files yield
+/// their first frame. An EXIF chunk is reported through `all_tags_json`.
+#[cfg(feature = "webp")]
+#[wasm_bindgen]
+pub fn decode_webp(data: &[u8]) -> Result<TiffResult, JsValue> {
+    #[cfg(feature = "console_error_panic_hook")]
+    console_error_panic_hook::set_once();
+
+    let mut decoder = image_webp::WebPDecoder::new(Cursor::new(data))
+        .map_err(|e| JsValue::from_str(&format!("WebP: failed to read header: {}", e)))?;
+    let (width, height) = decoder.dimensions();
+    let channels = if decoder.has_alpha() { 4 } else { 3 };
+    let exif = decoder.exif_metadata().ok().flatten().unwrap_or_default();
+    let exif = exif.strip_prefix(b"Exif\0\0").unwrap_or(&exif);
+    let all_tags_json = if exif.is_empty() { String::new() } else { extract_bare_ifd_tags_json(exif) };
+    let size = decoder.output_buffer_size()
+        .ok_or_else(|| JsValue::from_str("WebP: image is too large"))?;
+    let mut pixels = vec![0u8; size];
+    decoder.read_image(&mut pixels)
+        .map_err(|e| JsValue::from_str(&format!("WebP decode failed: {}", e)))?;
+    Ok(TiffResult::from_decoded(width, height, channels, DecodingResult::U8(pixels), all_tags_json))
+}
+
+/// Decode a Middlebury optical flow (`.flo`) file into a two-channel f32
+/// `TiffResult` (band 0 = horizontal u, band 1 = vertical v, in pixels);
+/// "unknown flow" entries are NaN. Render it with `flow_color_wheel`.
+#[cfg(feature = "flo")]
+#[wasm_bindgen]
+pub fn decode_flo(data: &[u8]) -> Result<TiffResult, JsValue> {
+    let (width, height, values) = parse_flo(data).map_err(|e| JsValue::from_str(&format!("FLO: {}", e)))?;
+    Ok(TiffResult::from_decoded(width, height, 2, DecodingResult::F32(values), String::new()))
+}
+
+/// Orientation (274) from IFD0 of a bare Exif blob (TIFF header first, as
+/// zune-jpeg hands it over), or None when absent or malformed.
+#[cfg(feature = "jpeg")]
+fn exif_orientation(exif: &[u8]) -> Option<u16> {
+    let big_endian = match exif.get(0..2)? {
+        b"II" => false,
+        b"MM" => true,
+        _ => return None,
+    };
+    let read_u16 = |offset: usize| -> Option<u16> {
+        let b = exif.get(offset..offset + 2)?;
+        Some(if big_endian { u16::from_be_bytes([b[0], b[1]]) } else { u16::from_le_bytes([b[0], b[1]]) })
+    };
+    let ifd0 = exif.get(4..8)?;
+    let ifd0 = if big_endian {
+        u32::from_be_bytes([ifd0[0], ifd0[1], ifd0[2], ifd0[3]])
+    } else {
+        u32::from_le_bytes([ifd0[0], ifd0[1], ifd0[2], ifd0[3]])
+    } as usize;
+    let entry_count = read_u16(ifd0)? as usize;
+    (0..entry_count)
+        .map(|i| ifd0 + 2 + i * 12)
+        .find(|&entry| read_u16(entry) == Some(274) && read_u16(entry + 2) == Some(3))
+        .and_then(|entry| read_u16(entry + 8))
+}
+
+#[cfg(feature = "hdr")]
 #[wasm_bindgen]
 impl HdrResult {
     #[wasm_bindgen(getter)]
@@ -168,6 +354,7 @@ impl HdrResult {
     }
 }
 
+#[cfg(feature = "png")]
 #[wasm_bindgen]
 impl PngResult {
     #[wasm_bindgen(getter)]
@@ -203,6 +390,7 @@ impl PngResult {
     }
 }
 
+#[cfg(feature = "exr")]
 #[wasm_bindgen]
 impl ExrResult {
     #[wasm_bindgen(getter)]
@@ -269,327 +457,9804 @@ impl ExrResult {
 #[wasm_bindgen]
 impl TiffResult {
     #[wasm_bindgen(getter)]
-    pub fn width(&self) -> u32 {
-        self.width
+    pub fn width(&self) -> Result<u32, JsValue> {
+        self.live()?;
+        Ok(self.width)
     }
 
     #[wasm_bindgen(getter)]
-    pub fn height(&self) -> u32 {
-        self.height
+    pub fn height(&self) -> Result<u32, JsValue> {
+        self.live()?;
+        Ok(self.height)
     }
 
     #[wasm_bindgen(getter)]
-    pub fn channels(&self) -> u32 {
-        self.channels
+    pub fn channels(&self) -> Result<u32, JsValue> {
+        self.live()?;
+        Ok(self.channels)
     }
 
     #[wasm_bindgen(getter)]
-    pub fn bits_per_sample(&self) -> u32 {
-        self.bits_per_sample
+    pub fn bits_per_sample(&self) -> Result<u32, JsValue> {
+        self.live()?;
+        Ok(self.bits_per_sample)
     }
 
     #[wasm_bindgen(getter)]
-    pub fn sample_format(&self) -> u32 {
-        self.sample_format
+    pub fn sample_format(&self) -> Result<u32, JsValue> {
+        self.live()?;
+        Ok(self.sample_format)
     }
 
+    /// Smallest sample found while decoding (or of the value transform);
+    /// NaN when statistics were not computed (`decode_tiff_fast`) or the
+    /// page has no finite sample - see `value_stats_json` to tell the two
+    /// apart.
     #[wasm_bindgen(getter)]
-    pub fn min_value(&self) -> f64 {
-        self.min_value
+    pub fn min_value(&self) -> Result<f64, JsValue> {
+        self.live()?;
+        Ok(self.value_range().0)
     }
 
+    /// Largest sample, NaN in the same cases as `min_value`.
     #[wasm_bindgen(getter)]
-    pub fn max_value(&self) -> f64 {
-        self.max_value
+    pub fn max_value(&self) -> Result<f64, JsValue> {
+        self.live()?;
+        Ok(self.value_range().1)
     }
 
+    /// Take min/max over the samples now, for a result decoded without them
+    /// (`decode_tiff_fast`, or `DecodeOptions` with stats off) once the
+    /// range is actually needed, e.g. after a quick thumbnail is on screen.
+    /// A range from the sample value tags is replaced by the measured one,
+    /// and `timing_stats_ms` becomes the time this pass took. Results that
+    /// already have statistics are measured again, which is harmless.
+    #[wasm_bindgen]
+    pub fn compute_stats(&mut self) -> Result<(), JsValue> {
+        self.live()?;
+        let start = now_ms();
+        (self.min_value, self.max_value) = self.measure_stored_range();
+        self.range_from_tags = false;
+        self.channel_stats = OnceCell::new();
+        self.timing_stats_ms = now_ms() - start;
+        Ok(())
+    }
+
+    /// True when `min_value`/`max_value` are the range the page declares in
+    /// its MinSampleValue/MaxSampleValue (or SMinSampleValue/
+    /// SMaxSampleValue) tags rather than measured ones: fast decodes take a
+    /// plausible tagged range as the initial display range instead of
+    /// reporting NaN, so the first open of a huge file needs no stats pass.
     #[wasm_bindgen(getter)]
-    pub fn timing_metadata_ms(&self) -> f64 {
-        self.timing_metadata_ms
+    pub fn range_from_tags(&self) -> Result<bool, JsValue> {
+        self.live()?;
+        Ok(self.range_from_tags)
     }
 
     #[wasm_bindgen(getter)]
-    pub fn timing_decode_ms(&self) -> f64 {
-        self.timing_decode_ms
+    pub fn timing_metadata_ms(&self) -> Result<f64, JsValue> {
+        self.live()?;
+        Ok(self.timing_metadata_ms)
     }
 
     #[wasm_bindgen(getter)]
-    pub fn timing_convert_ms(&self) -> f64 {
-        self.timing_convert_ms
+    pub fn timing_decode_ms(&self) -> Result<f64, JsValue> {
+        self.live()?;
+        Ok(self.timing_decode_ms)
     }
 
     #[wasm_bindgen(getter)]
-    pub fn timing_stats_ms(&self) -> f64 {
-        self.timing_stats_ms
+    pub fn timing_convert_ms(&self) -> Result<f64, JsValue> {
+        self.live()?;
+        Ok(self.timing_convert_ms)
     }
 
     #[wasm_bindgen(getter)]
-    pub fn timing_pack_ms(&self) -> f64 {
-        self.timing_pack_ms
+    pub fn timing_stats_ms(&self) -> Result<f64, JsValue> {
+        self.live()?;
+        Ok(self.timing_stats_ms)
     }
 
     #[wasm_bindgen(getter)]
-    pub fn compression(&self) -> u32 {
-        self.compression
+    pub fn timing_pack_ms(&self) -> Result<f64, JsValue> {
+        self.live()?;
+        Ok(self.timing_pack_ms)
     }
 
     #[wasm_bindgen(getter)]
-    pub fn predictor(&self) -> u32 {
-        self.predictor
+    pub fn compression(&self) -> Result<u32, JsValue> {
+        self.live()?;
+        Ok(self.compression)
     }
 
     #[wasm_bindgen(getter)]
-    pub fn photometric_interpretation(&self) -> u32 {
-        self.photometric_interpretation
+    pub fn predictor(&self) -> Result<u32, JsValue> {
+        self.live()?;
+        Ok(self.predictor)
     }
 
     #[wasm_bindgen(getter)]
-    pub fn planar_configuration(&self) -> u32 {
-        self.planar_configuration
+    pub fn photometric_interpretation(&self) -> Result<u32, JsValue> {
+        self.live()?;
+        Ok(self.photometric_interpretation)
     }
 
     #[wasm_bindgen(getter)]
-    pub fn rows_per_strip(&self) -> u32 {
-        self.rows_per_strip
+    pub fn planar_configuration(&self) -> Result<u32, JsValue> {
+        self.live()?;
+        Ok(self.planar_configuration)
     }
 
     #[wasm_bindgen(getter)]
-    pub fn strip_count(&self) -> u32 {
-        self.strip_count
+    pub fn rows_per_strip(&self) -> Result<u32, JsValue> {
+        self.live()?;
+        Ok(self.rows_per_strip)
     }
 
     #[wasm_bindgen(getter)]
-    pub fn strip_byte_count_total(&self) -> f64 {
-        self.strip_byte_count_total as f64
+    pub fn strip_count(&self) -> Result<u32, JsValue> {
+        self.live()?;
+        Ok(self.strip_count)
     }
 
     #[wasm_bindgen(getter)]
-    pub fn strip_byte_count_max(&self) -> f64 {
-        self.strip_byte_count_max as f64
+    pub fn strip_byte_count_total(&self) -> Result<f64, JsValue> {
+        self.live()?;
+        Ok(self.strip_byte_count_total as f64)
     }
 
     #[wasm_bindgen(getter)]
-    pub fn tile_width(&self) -> u32 {
-        self.tile_width
+    pub fn strip_byte_count_max(&self) -> Result<f64, JsValue> {
+        self.live()?;
+        Ok(self.strip_byte_count_max as f64)
     }
 
     #[wasm_bindgen(getter)]
-    pub fn tile_length(&self) -> u32 {
-        self.tile_length
+    pub fn tile_width(&self) -> Result<u32, JsValue> {
+        self.live()?;
+        Ok(self.tile_width)
     }
 
     #[wasm_bindgen(getter)]
-    pub fn tile_count(&self) -> u32 {
-        self.tile_count
+    pub fn tile_length(&self) -> Result<u32, JsValue> {
+        self.live()?;
+        Ok(self.tile_length)
     }
 
     #[wasm_bindgen(getter)]
-    pub fn direct_decode(&self) -> bool {
-        self.direct_decode
+    pub fn tile_count(&self) -> Result<u32, JsValue> {
+        self.live()?;
+        Ok(self.tile_count)
     }
 
     #[wasm_bindgen(getter)]
-    pub fn ome_xml(&self) -> String {
-        self.ome_xml.clone()
+    pub fn direct_decode(&self) -> Result<bool, JsValue> {
+        self.live()?;
+        Ok(self.direct_decode)
     }
 
     #[wasm_bindgen(getter)]
-    pub fn all_tags_json(&self) -> String {
-        self.all_tags_json.clone()
+    pub fn ome_xml(&self) -> Result<String, JsValue> {
+        self.live()?;
+        Ok(self.ome_xml.clone())
     }
 
-    /// Get raw data as bytes (for transferring to JS)
+    #[wasm_bindgen(getter)]
+    pub fn chunk_profile_json(&self) -> Result<String, JsValue> {
+        self.live()?;
+        Ok(self.chunk_profile_json.clone())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn all_tags_json(&self) -> Result<String, JsValue> {
+        self.live()?;
+        Ok(self.all_tags_json.clone())
+    }
+
+    /// Width / height of one pixel from XResolution/YResolution (already in
+    /// display orientation); 1.0 for square pixels or when the tags are
+    /// absent. Values away from 1 mean the raster shows distorted unless it
+    /// is stretched, see `resample_square_pixels_as_f32`.
+    #[wasm_bindgen(getter)]
+    pub fn pixel_aspect_ratio(&self) -> Result<f64, JsValue> {
+        self.live()?;
+        Ok(self.pixel_aspect_ratio)
+    }
+
+    /// The Orientation tag value (1-8) the decoder applied to these pixels;
+    /// 1 when they are in stored order.
+    #[wasm_bindgen(getter)]
+    pub fn orientation(&self) -> Result<u32, JsValue> {
+        self.live()?;
+        Ok(self.frame().orientation as u32)
+    }
+
+    /// The point of the stored page under `(x, y)` of this raster, as
+    /// `[x, y]`, undoing the applied orientation and any `decode_tiff_region`
+    /// offset. Coordinates are continuous (pixel `(i, j)` spans `i..i + 1`,
+    /// `j..j + 1`), so pass `x + 0.5, y + 0.5` and floor the result to map a
+    /// pixel index. Points outside the raster map outside the page alike.
     #[wasm_bindgen]
-    pub fn get_data_bytes(&self) -> Vec<u8> {
-        if self.data.is_empty() && !self.data_f32.is_empty() {
-            let mut bytes = Vec::with_capacity(self.data_f32.len() * 4);
-            for &value in &self.data_f32 {
-                bytes.extend_from_slice(&value.to_le_bytes());
-            }
-            return bytes;
-        }
-        self.data.clone()
+    pub fn display_to_source(&self, x: f64, y: f64) -> Result<Vec<f64>, JsValue> {
+        self.live()?;
+        let (x, y) = self.frame().to_source(x, y);
+        Ok(vec![x, y])
     }
 
-    /// Get data as Float32Array (most common for visualization)
+    /// The inverse of `display_to_source`: where the stored page's point
+    /// `(x, y)` is in this raster, as `[x, y]`.
     #[wasm_bindgen]
-    pub fn get_data_as_f32(&self) -> Vec<f32> {
-        if !self.data_f32.is_empty() {
-            return self.data_f32.clone();
-        }
+    pub fn source_to_display(&self, x: f64, y: f64) -> Result<Vec<f64>, JsValue> {
+        self.live()?;
+        let (x, y) = self.frame().to_display(x, y);
+        Ok(vec![x, y])
+    }
 
-        match self.sample_format {
-            3 => {
-                // Already float32
-                self.data
-                    .chunks_exact(4)
-                    .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
-                    .collect()
-            }
-            1 | 2 => {
-                // Convert integers to float
-                match self.bits_per_sample {
-                    8 => self.data.iter().map(|&v| v as f32).collect(),
-                    // 9..=15 covers the sub-16-bit direct decode path
-                    // (try_decode_subbit_strips): those samples are still
-                    // packed as 2 bytes each (via convert_u16_to_bytes_simd),
-                    // just with a smaller reported bits_per_sample.
-                    9..=16 => self.data
-                        .chunks_exact(2)
-                        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]) as f32)
-                        .collect(),
-                    32 => self.data
-                        .chunks_exact(4)
-                        .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32)
-                        .collect(),
-                    _ => vec![],
-                }
-            }
-            _ => vec![],
-        }
+    /// The stored-page rectangle `[x, y, width, height]` covered by the
+    /// rectangle `(x, y, width, height)` of this raster (width and height
+    /// swap for the transposing orientations), e.g. to read an ROI drawn on
+    /// the display back from the file with `TiffFile::read_region`.
+    #[wasm_bindgen]
+    pub fn display_rect_to_source(&self, x: f64, y: f64, width: f64, height: f64) -> Result<Vec<f64>, JsValue> {
+        self.live()?;
+        let frame = self.frame();
+        Ok(map_rect(|x, y| frame.to_source(x, y), x, y, width, height))
     }
 
-    /// Move float data out of the result when possible. This avoids cloning the
-    /// decoded f32 vector before wasm-bindgen copies it into JS-owned memory.
+    /// The inverse of `display_rect_to_source`.
     #[wasm_bindgen]
-    pub fn take_data_as_f32(&mut self) -> Vec<f32> {
-        if !self.data_f32.is_empty() {
-            return mem::take(&mut self.data_f32);
-        }
-        self.get_data_as_f32()
+    pub fn source_rect_to_display(&self, x: f64, y: f64, width: f64, height: f64) -> Result<Vec<f64>, JsValue> {
+        self.live()?;
+        let frame = self.frame();
+        Ok(map_rect(|x, y| frame.to_display(x, y), x, y, width, height))
     }
-}
 
-/// Decode a TIFF file from an ArrayBuffer
-/// Returns TiffResult with image data and metadata
-#[wasm_bindgen]
-pub fn decode_tiff(data: &[u8]) -> Result<TiffResult, JsValue> {
-    decode_tiff_impl(data, true, 0)
-}
+    /// The model (map) coordinates `[X, Y]` of the point `(x, y)` of this
+    /// raster, through `display_to_source` and the page's
+    /// ModelTransformation, or its ModelTiepoint and ModelPixelScale.
+    /// Errors when the page isn't georeferenced that way.
+    #[wasm_bindgen]
+    pub fn display_to_model(&self, x: f64, y: f64) -> Result<Vec<f64>, JsValue> {
+        self.live()?;
+        let [a, b, c, d, e, f] = self.raster_to_model()?;
+        let (i, j) = self.frame().to_source(x, y);
+        Ok(vec![a * i + b * j + c, d * i + e * j + f])
+    }
 
-/// Return the number of top-level image file directories (pages) in a TIFF.
-#[wasm_bindgen]
-pub fn tiff_page_count(data: &[u8]) -> Result<u32, JsValue> {
-    let mut decoder = Decoder::new(Cursor::new(data))
-        .map_err(|e| JsValue::from_str(&format!("Failed to create decoder: {}", e)))?;
-    let mut count = 1u32;
-    while decoder.more_images() {
-        decoder.next_image()
-            .map_err(|e| JsValue::from_str(&format!("Failed to enumerate TIFF pages: {}", e)))?;
-        count = count.saturating_add(1);
+    /// The inverse of `display_to_model`: the point of this raster at model
+    /// coordinates `(x, y)`, as `[x, y]`.
+    #[wasm_bindgen]
+    pub fn model_to_display(&self, x: f64, y: f64) -> Result<Vec<f64>, JsValue> {
+        self.live()?;
+        let [a, b, c, d, e, f] = self.raster_to_model()?;
+        let det = a * e - b * d;
+        if det == 0.0 || !det.is_finite() {
+            return Err(JsValue::from_str("Georeferencing: the raster-to-model transform is not invertible"));
+        }
+        let (x, y) = (x - c, y - f);
+        let (i, j) = ((e * x - b * y) / det, (a * y - d * x) / det);
+        let (x, y) = self.frame().to_display(i, j);
+        Ok(vec![x, y])
     }
-    Ok(count)
-}
 
-/// Decode an arbitrary zero-based TIFF page and compute min/max statistics.
-#[wasm_bindgen]
-pub fn decode_tiff_page(data: &[u8], page_index: u32) -> Result<TiffResult, JsValue> {
-    decode_tiff_impl(data, true, page_index)
-}
+    /// The RGB primaries and white point the colour samples are in, as eight
+    /// CIE xy values (red x, y, green x, y, blue x, y, white x, y), from the
+    /// PrimaryChromaticities/WhitePoint tags or the EXR chromaticities
+    /// attribute; empty when the file names none and sRGB is assumed.
+    /// `render_rgba` maps from these to the output gamut.
+    #[wasm_bindgen(getter)]
+    pub fn source_primaries(&self) -> Result<Vec<f64>, JsValue> {
+        self.live()?;
+        Ok(self.primaries.map(Vec::from).unwrap_or_default())
+    }
 
-/// Walk a raw Exif-only IFD blob (a JPEG APP1 payload with its "Exif\0\0"
-/// prefix already stripped, or a PNG eXIf chunk's raw bytes) and return
-/// every tag as JSON, in the same shape as `TiffResult.all_tags_json`.
-///
-/// These blobs are TIFF-*structured* (byte order + magic 42 + IFD entries)
-/// but are not full TIFF files — they carry no ImageWidth/PhotometricInterpretation/
-/// etc., so the `tiff` crate's `Decoder::new()` (which always validates a
-/// full image directory) rejects them. `extract_bare_ifd_tags_json` reads
-/// the IFD structure directly instead, bypassing `Decoder` entirely; real
-/// `.tif`/`.tiff` files keep using the `Decoder`-based `extract_all_tags_json`
-/// via `decode_tiff`/`decode_tiff_fast` above.
-#[wasm_bindgen]
-pub fn extract_exif_tags(data: &[u8]) -> String {
-    extract_bare_ifd_tags_json(data)
-}
+    /// Override the primaries `render_rgba` maps from (the eight xy values of
+    /// `source_primaries`; empty resets to sRGB), e.g. for Rec. 2020 or ACES
+    /// data whose file doesn't say so.
+    #[wasm_bindgen]
+    pub fn set_source_primaries(&mut self, primaries: &[f64]) -> Result<(), JsValue> {
+        self.live()?;
+        if primaries.is_empty() {
+            self.primaries = None;
+            return Ok(());
+        }
+        let primaries: [f64; 8] = primaries.try_into().map_err(|_| {
+            JsValue::from_str("Primaries: expected 8 values (red, green, blue and white x, y)")
+        })?;
+        if rgb_to_xyz_matrix(&primaries).is_none() {
+            return Err(JsValue::from_str("Primaries: the xy values don't describe a usable RGB gamut"));
+        }
+        self.primaries = Some(primaries);
+        Ok(())
+    }
 
-/// Decode a TIFF file without eagerly computing min/max statistics.
-///
-/// The webview render path computes stats lazily when a non-gamma mode needs
-/// them. Skipping eager stats saves a full pass over large float TIFFs during
-/// the common gamma-mode initial load.
-#[wasm_bindgen]
-pub fn decode_tiff_fast(data: &[u8]) -> Result<TiffResult, JsValue> {
-    decode_tiff_impl(data, false, 0)
-}
+    /// The embedded ICC profile (InterColorProfile, 34675) as stored, for
+    /// handing to a colour management system; empty when there is none.
+    #[wasm_bindgen(getter)]
+    pub fn icc_profile(&self) -> Result<Vec<u8>, JsValue> {
+        self.live()?;
+        Ok(self.icc_profile.clone())
+    }
+
+    /// What the embedded ICC profile says, as JSON: `{"size","version",
+    /// "device_class","color_space","pcs","description","matrix_shaper",
+    /// "usable","primaries","tone_curves"}`, where `color_space`/`pcs` are
+    /// the header signatures ("RGB", "XYZ"), `matrix_shaper` whether it has
+    /// the colorant matrix and tone curves, `usable` whether
+    /// `set_color_managed` can render with it, `primaries` its D50-adapted
+    /// colorants as xy (eight values like `source_primaries`) and
+    /// `tone_curves` the rTRC/gTRC/bTRC as
+    /// `{"kind":"gamma"|"table"|"parametric",...}`. `null`
+    /// without a profile, or when the bytes aren't one.
+    #[wasm_bindgen]
+    pub fn icc_profile_json(&self) -> Result<String, JsValue> {
+        self.live()?;
+        Ok(IccProfile::parse(&self.icc_profile).map_or("null".to_string(), |profile| profile.to_json(self.icc_profile.len())))
+    }
 
-/// Decode an arbitrary zero-based TIFF page without eagerly computing stats.
-#[wasm_bindgen]
-pub fn decode_tiff_page_fast(data: &[u8], page_index: u32) -> Result<TiffResult, JsValue> {
-    decode_tiff_impl(data, false, page_index)
-}
+    /// Whether `render_rgba` (and `export_snapshot_png`) colour-manage with
+    /// the embedded ICC profile: set with `set_color_managed` and the profile
+    /// `usable`.
+    #[wasm_bindgen(getter)]
+    pub fn color_managed(&self) -> Result<bool, JsValue> {
+        self.live()?;
+        Ok(self.managed_profile().is_some())
+    }
 
-#[wasm_bindgen]
-pub fn decode_exr_fast(data: &[u8]) -> Result<ExrResult, JsValue> {
-    #[cfg(feature = "console_error_panic_hook")]
-    console_error_panic_hook::set_once();
+    /// Render colour through the embedded ICC profile (off by default):
+    /// samples are decoded with its tone curves and taken from its colorants
+    /// to the output space, so a wide-gamut (e.g. Adobe RGB or ProPhoto)
+    /// scan no longer looks washed out as if it were sRGB. This replaces
+    /// `source_primaries` while on. Only RGB matrix/TRC profiles can be
+    /// applied (see `icc_profile_json`); pages without one render as before.
+    #[wasm_bindgen]
+    pub fn set_color_managed(&mut self, enabled: bool) -> Result<(), JsValue> {
+        self.live()?;
+        self.color_managed = enabled;
+        Ok(())
+    }
 
-    decode_exr_impl(data)
-}
+    /// Number of fractional bits integer samples are interpreted with (see
+    /// `set_fixed_point_bits`); 0 for plain integers.
+    #[wasm_bindgen(getter)]
+    pub fn fixed_point_bits(&self) -> Result<u32, JsValue> {
+        self.live()?;
+        Ok(self.fractional_bits)
+    }
+
+    /// Interpret integer samples as fixed-point (Q-format) numbers with
+    /// `fractional_bits` bits after the binary point, e.g. 16 for the Q16.16
+    /// depth some sensors write as uint32/int32, so f32 data, `min_value`/
+    /// `max_value`, `summary` and everything built on the f32 view report
+    /// real units; the GDAL nodata value is scaled the same way. Applied
+    /// during the f32 conversion (stored bytes from `get_data_bytes` stay
+    /// raw); 0 restores plain integers. Only integer
+    /// pages of up to 32 bits have this view, and `fractional_bits` can't
+    /// exceed `bits_per_sample`.
+    #[wasm_bindgen]
+    pub fn set_fixed_point_bits(&mut self, fractional_bits: u32) -> Result<(), JsValue> {
+        self.live()?;
+        if self.sample_format == 3 || self.native_float.is_some() || !self.data_f32.is_empty() {
+            return Err(JsValue::from_str("Fixed-point interpretation needs integer samples (this page is floating point)"));
+        }
+        if self.bits_per_sample > 32 {
+            return Err(JsValue::from_str(&format!("Fixed-point interpretation is not available for {}-bit integers", self.bits_per_sample)));
+        }
+        if fractional_bits > self.bits_per_sample {
+            return Err(JsValue::from_str(&format!(
+                "Fixed-point interpretation: {} fractional bits exceed the {}-bit samples",
+                fractional_bits, self.bits_per_sample
+            )));
+        }
+        let rescale = 2f64.powi(self.fractional_bits as i32 - fractional_bits as i32);
+        self.min_value *= rescale;
+        self.max_value *= rescale;
+        self.fractional_bits = fractional_bits;
+        self.data_f32_cache = OnceCell::new();
+        self.channel_stats = OnceCell::new();
+        Ok(())
+    }
 
-#[wasm_bindgen]
-pub fn decode_png16_fast(data: &[u8]) -> Result<PngResult, JsValue> {
-    #[cfg(feature = "console_error_panic_hook")]
-    console_error_panic_hook::set_once();
+    /// The expression set by `set_value_transform`, or "" when none is.
+    #[wasm_bindgen(getter)]
+    pub fn value_transform(&self) -> Result<String, JsValue> {
+        self.live()?;
+        Ok(self.value_transform.as_ref().map_or(String::new(), |transform| transform.source.clone()))
+    }
+
+    /// Transform every sample with an arithmetic expression of `v` while it
+    /// is converted to f32, e.g. `1 / v` to view depth as inverse depth, so
+    /// the f32 data, `min_value`/`max_value`, `summary` and every other
+    /// statistic see the transformed values from a single pass. A JS-style
+    /// `v => ...` prefix and `Math.` names are accepted so simple arrow
+    /// functions can be pasted as is.
+    /// Supports numbers, `v`, `pi`, `e`, `+ - * / % ^` (or `**`),
+    /// parentheses and the functions abs, sqrt, cbrt, exp, ln/log, log2,
+    /// log10, sin, cos, tan, floor, ceil, round, min, max, pow, clamp
+    /// (evaluated in f64). Nodata samples are passed through untouched so
+    /// they still match the nodata value. An empty string removes the
+    /// transform. The stored bytes (`get_data_bytes`) are never changed.
+    #[wasm_bindgen]
+    pub fn set_value_transform(&mut self, expression: &str) -> Result<(), JsValue> {
+        self.live()?;
+        self.value_transform = if expression.trim().is_empty() {
+            None
+        } else {
+            let expr = ValueExpr::parse(expression)
+                .map_err(|e| JsValue::from_str(&format!("Value transform {:?}: {}", expression, e)))?;
+            Some(ValueTransform { source: expression.to_string(), expr })
+        };
+        self.data_f32_cache = OnceCell::new();
+        self.channel_stats = OnceCell::new();
+        Ok(())
+    }
 
-    decode_png16_impl(data)
-}
+    /// Whether fully transparent pixels count towards the statistics.
+    #[wasm_bindgen(getter)]
+    pub fn include_transparent_pixels(&self) -> Result<bool, JsValue> {
+        self.live()?;
+        Ok(self.include_transparent)
+    }
+
+    /// By default, pixels of a gray + alpha or RGBA page whose alpha is
+    /// zero (or below, which draws just as transparent) are left out of
+    /// `min_value`/`max_value`, `band_min_max`, `histograms`,
+    /// `value_stats_json`, `suggest_display` and `start_stats`, so the
+    /// padding around a composited render doesn't stretch auto-contrast.
+    /// Pass true to count them like any other pixel. Pages without an alpha
+    /// band (see `layout`'s channel names) are unaffected either way.
+    #[wasm_bindgen]
+    pub fn set_include_transparent_pixels(&mut self, include: bool) -> Result<(), JsValue> {
+        self.live()?;
+        let changed = include != self.include_transparent;
+        self.include_transparent = include;
+        self.channel_stats = OnceCell::new();
+        // A measured range covers just the pixels the old setting counted;
+        // skipped or tagged statistics stay as they are.
+        let measured = !self.range_from_tags && !self.min_value.is_nan();
+        if changed && measured && alpha_band_index(self.photometric_interpretation, self.channels).is_some() {
+            let (min, max) = self.measure_stored_range();
+            if !min.is_nan() {
+                (self.min_value, self.max_value) = (min, max);
+            }
+        }
+        Ok(())
+    }
 
-fn decode_png16_impl(data: &[u8]) -> Result<PngResult, JsValue> {
-    let start_time = js_sys::Date::now();
-    let cursor = Cursor::new(data);
-    let mut limits = png::Limits::default();
-    limits.bytes = 512 * 1024 * 1024;
-    let decoder = png::Decoder::new_with_limits(cursor, limits);
-    let mut reader = decoder.read_info()
-        .map_err(|e| JsValue::from_str(&format!("Failed to read PNG info: {}", e)))?;
-    let read_info_time = js_sys::Date::now() - start_time;
+    /// Cap the bytes that one call of `get_data_bytes`, `take_data_bytes`,
+    /// `get_data_as_f32`, `take_data_as_f32`, `get_band_as_f32` or
+    /// `render_rgba` may return, so an embedder opening a huge page is
+    /// steered to region and preview reads instead of allocating a
+    /// multi-GB ArrayBuffer. Over the budget these throw a "too_large"
+    /// `TiffError` with a `suggested_downsample` step, before anything is
+    /// allocated. 0 (the default) means no limit.
+    #[wasm_bindgen]
+    pub fn set_transfer_byte_budget(&mut self, bytes: f64) -> Result<(), JsValue> {
+        self.live()?;
+        self.transfer_byte_budget = if bytes.is_finite() && bytes > 0.0 { bytes.min(usize::MAX as f64) as usize } else { 0 };
+        Ok(())
+    }
 
-    let decode_start = js_sys::Date::now();
-    let mut raw = vec![0u8; reader.output_buffer_size()];
-    let info = reader.next_frame(&mut raw)
-        .map_err(|e| JsValue::from_str(&format!("Failed to decode PNG frame: {}", e)))?;
-    raw.truncate(info.buffer_size());
-    let decode_time = js_sys::Date::now() - decode_start;
+    /// The limit set with `set_transfer_byte_budget`, 0 for none.
+    #[wasm_bindgen(getter)]
+    pub fn transfer_byte_budget(&self) -> Result<f64, JsValue> {
+        self.live()?;
+        Ok(self.transfer_byte_budget as f64)
+    }
 
-    if info.bit_depth != png::BitDepth::Sixteen {
-        return Err(JsValue::from_str("Rust PNG fast path only supports 16-bit PNG output"));
+    /// Get raw data as bytes (for transferring to JS)
+    #[wasm_bindgen]
+    pub fn get_data_bytes(&self) -> Result<Vec<u8>, JsValue> {
+        self.live()?;
+        self.check_transfer("get_data_bytes", if self.data.is_empty() { self.sample_count() * 4 } else { self.data.len() })?;
+        if self.data.is_empty() {
+            let floats = if self.data_f32.is_empty() { self.converted_f32() } else { &self.data_f32 };
+            return Ok(f32_to_le_bytes(floats));
+        }
+        Ok(self.data.to_vec())
     }
-    let channels = match info.color_type {
-        png::ColorType::Grayscale => 1,
-        png::ColorType::Rgb => 3,
-        png::ColorType::GrayscaleAlpha => 2,
-        png::ColorType::Rgba => 4,
-        png::ColorType::Indexed => return Err(JsValue::from_str("Rust PNG fast path does not support indexed 16-bit PNG")),
-    };
 
-    let expected_values = (info.width as usize)
-        .checked_mul(info.height as usize)
-        .and_then(|v| v.checked_mul(channels as usize))
-        .ok_or_else(|| JsValue::from_str("PNG dimensions overflow"))?;
-    if raw.len() < expected_values * 2 {
-        return Err(JsValue::from_str("PNG decoded byte count is smaller than expected"));
+    /// Get data as Float32Array (most common for visualization). Integer and
+    /// f16/f64 data is converted on the first call and the converted copy is
+    /// cached, so stats-only and metadata-only callers never pay for it.
+    #[wasm_bindgen]
+    pub fn get_data_as_f32(&self) -> Result<Vec<f32>, JsValue> {
+        self.live()?;
+        self.check_transfer("get_data_as_f32", self.sample_count() * 4)?;
+        if !self.data_f32.is_empty() && self.value_transform.is_none() {
+            return Ok(self.data_f32.to_vec());
+        }
+        Ok(self.converted_f32().to_vec())
     }
 
-    let convert_start = js_sys::Date::now();
-    let mut values: Vec<u16> = Vec::with_capacity(expected_values);
-    let src_ptr = raw.as_ptr();
-    let dst = values.as_mut_ptr();
-    for i in 0..expected_values {
-        // SAFETY: `raw` was checked to contain at least `expected_values * 2`
-        // bytes, and `values` has capacity for every output sample.
-        unsafe {
-            let be = (src_ptr.add(i * 2) as *const u16).read_unaligned();
-            dst.add(i).write(u16::from_be(be));
+    /// Move float data out of the result when possible. This avoids cloning the
+    /// decoded f32 vector before wasm-bindgen copies it into JS-owned memory;
+    /// planes still shared with a `shared_view` are copied instead, so the
+    /// other result keeps its data.
+    #[wasm_bindgen]
+    pub fn take_data_as_f32(&mut self) -> Result<Vec<f32>, JsValue> {
+        self.live()?;
+        self.check_transfer("take_data_as_f32", self.sample_count() * 4)?;
+        if !self.data_f32.is_empty() && self.value_transform.is_none() {
+            return Ok(Rc::unwrap_or_clone(mem::take(&mut self.data_f32)));
+        }
+        if let Some(cached) = self.data_f32_cache.take() {
+            return Ok(Rc::unwrap_or_clone(cached));
         }
+        Ok(self.convert_to_f32())
     }
-    unsafe {
-        values.set_len(expected_values);
+
+    /// `get_data_bytes` without the clone: integer samples are moved out of
+    /// the result, so a large page exists once on the Rust side and once in
+    /// the JS copy wasm-bindgen makes, rather than twice plus the copy.
+    /// Float pages are taken with `take_data_as_f32` and handed over as
+    /// their little-endian bytes. Afterwards `get_data_bytes` and the f32
+    /// accessors have nothing left to return.
+    #[wasm_bindgen]
+    pub fn take_data_bytes(&mut self) -> Result<Vec<u8>, JsValue> {
+        self.live()?;
+        self.check_transfer("take_data_bytes", if self.data.is_empty() { self.sample_count() * 4 } else { self.data.len() })?;
+        if self.data.is_empty() {
+            let floats = self.take_data_as_f32()?;
+            return Ok(f32_to_le_bytes(&floats));
+        }
+        self.data_f32_cache.take();
+        Ok(Rc::unwrap_or_clone(mem::take(&mut self.data)))
     }
-    let convert_time = js_sys::Date::now() - convert_start;
-    let total_time = js_sys::Date::now() - start_time;
 
-    Ok(PngResult {
+    /// A compact, bit-exact copy of the stored samples (native element type,
+    /// no f32 conversion, fixed-point scaling or value transform) for hover
+    /// readouts, so the display pipeline is free to take the data, render
+    /// from previews or session overviews, or `dispose` this result while
+    /// point queries still report the file's own values. Call before the
+    /// `take_data_*` methods; it errors once there is nothing left to copy.
+    #[wasm_bindgen]
+    pub fn exact_values(&self) -> Result<ExactValues, JsValue> {
+        self.live()?;
+        let sample_count = (self.width as usize) * (self.height as usize) * (self.channels as usize);
+        let element = self.stored_element("Exact values")?;
+        let data = self.stored_bytes();
+        let bytes_per_sample = element_bytes(element);
+        if sample_count == 0 || data.len() != sample_count * bytes_per_sample {
+            return Err(JsValue::from_str("Exact values: no pixel data (after take_data_as_f32?)"));
+        }
+        Ok(ExactValues { width: self.width, height: self.height, channels: self.channels, element, bytes_per_sample, data })
+    }
+
+    /// Element type of the samples as stored, which `get_data("auto")`
+    /// returns: "uint8".."uint64", "int8".."int64", "float16", "float32" or
+    /// "float64" (see `ExactValues::element`).
+    #[wasm_bindgen(getter)]
+    pub fn native_element(&self) -> Result<String, JsValue> {
+        self.live()?;
+        Ok(self.stored_element("Typed output")?.to_string())
+    }
+
+    /// The samples as a typed array, in the representation `output` asks
+    /// for:
+    /// - "auto": the smallest lossless one, i.e. the stored element type
+    ///   (`native_element`), so 8-bit data stays a Uint8Array and 9-16 bit
+    ///   data a Uint16Array; 64-bit integers come as BigInt64Array /
+    ///   BigUint64Array, and f16 as a Uint16Array of the IEEE half-float
+    ///   bits (JS has no Float16Array to put them in). These are the file's
+    ///   own values, without fixed-point scaling or value transform.
+    /// - "float32": the Float32Array of `get_data_as_f32`, converted (and
+    ///   transformed) for rendering.
+    ///
+    /// Converting is then always the caller's explicit choice.
+    #[wasm_bindgen]
+    pub fn get_data(&self, output: &str) -> Result<JsValue, JsValue> {
+        self.live()?;
+        match output {
+            "auto" => {
+                Ok(typed_array(self.stored_element("Typed output")?, &self.stored_bytes()))
+            }
+            "float32" => Ok(js_sys::Float32Array::from(self.get_data_as_f32()?.as_slice()).into()),
+            other => Err(JsValue::from_str(&format!("Typed output: unknown output '{}' (expected \"auto\" or \"float32\")", other))),
+        }
+    }
+
+    /// `get_data` that moves the samples out instead of copying them, like
+    /// `take_data_bytes`; the data accessors have nothing left afterwards.
+    #[wasm_bindgen]
+    pub fn take_data(&mut self, output: &str) -> Result<JsValue, JsValue> {
+        self.live()?;
+        match output {
+            "auto" => {
+                let element = self.stored_element("Typed output")?;
+                let bytes = match &self.native_float {
+                    Some(_) => self.stored_bytes(),
+                    None if !self.data.is_empty() => Rc::unwrap_or_clone(mem::take(&mut self.data)),
+                    None => self.take_data_as_f32()?.into_iter().flat_map(f32::to_le_bytes).collect(),
+                };
+                self.native_float = None;
+                self.data_f32_cache.take();
+                Ok(typed_array(element, &bytes))
+            }
+            "float32" => Ok(js_sys::Float32Array::from(self.take_data_as_f32()?.as_slice()).into()),
+            other => Err(JsValue::from_str(&format!("Typed output: unknown output '{}' (expected \"auto\" or \"float32\")", other))),
+        }
+    }
+
+    /// A second result over the same decoded planes, without copying them,
+    /// for views derived from one decode: it starts with this result's
+    /// fixed-point bits, value transform and transparency setting and can
+    /// change them on its own, so a transformed view of a 1 GB scene
+    /// doesn't hold the scene twice. Taking the data out of either result
+    /// copies the planes while the other still uses them; `dispose` only
+    /// releases this result's reference.
+    #[wasm_bindgen]
+    pub fn shared_view(&self) -> Result<TiffResult, JsValue> {
+        self.live()?;
+        Ok(self.clone())
+    }
+
+    /// Whether this result and `other` hold the same decoded planes (see
+    /// `shared_view`), i.e. whether the samples are in memory only once.
+    #[wasm_bindgen]
+    pub fn shares_planes_with(&self, other: &TiffResult) -> bool {
+        let shared_data = !self.data.is_empty() && Rc::ptr_eq(&self.data, &other.data);
+        let shared_f32 = !self.data_f32.is_empty() && Rc::ptr_eq(&self.data_f32, &other.data_f32);
+        let shared_native = matches!((&self.native_float, &other.native_float), (Some(a), Some(b)) if Rc::ptr_eq(a, b));
+        shared_data || shared_f32 || shared_native
+    }
+
+    /// Drop the pixel buffers (and the tag/OME-XML strings) right away
+    /// instead of whenever the JS wrapper is garbage-collected, which in a
+    /// webview may be never. Every accessor errors afterwards; the wrapper
+    /// itself is still released by `free()`. Calling it twice is harmless.
+    #[wasm_bindgen]
+    pub fn dispose(&mut self) {
+        self.data = Rc::default();
+        self.data_f32 = Rc::default();
+        self.native_float = None;
+        self.data_f32_cache = OnceCell::new();
+        self.channel_stats = OnceCell::new();
+        self.all_tags_json = String::new();
+        self.ome_xml = String::new();
+        self.chunk_profile_json = String::new();
+        self.icc_profile = Vec::new();
+        self.disposed = true;
+    }
+
+    /// True once `dispose` has been called.
+    #[wasm_bindgen(getter)]
+    pub fn disposed(&self) -> bool {
+        self.disposed
+    }
+
+    /// One band of an interleaved image of any channel count (e.g. a single
+    /// wavelength of a multispectral page) as a `width * height` f32 plane.
+    #[wasm_bindgen]
+    pub fn get_band_as_f32(&self, band: u32) -> Result<Vec<f32>, JsValue> {
+        self.live()?;
+        self.check_band(band)?;
+        self.check_transfer("get_band_as_f32", (self.width as usize) * (self.height as usize) * 4)?;
+        let channels = self.channels as usize;
+        Ok(self.samples_f32().iter().skip(band as usize).step_by(channels).copied().collect())
+    }
+
+    /// `[min, max]` of the finite samples of one band (NaN/Inf and fully
+    /// transparent pixels excluded), `[NaN, NaN]` if the band has none. The
+    /// page-wide `min_value` / `max_value` span every band, which is rarely
+    /// what a per-band contrast stretch wants once there are more than the
+    /// three or four display channels.
+    #[wasm_bindgen]
+    pub fn band_min_max(&self, band: u32) -> Result<Vec<f64>, JsValue> {
+        self.live()?;
+        self.check_band(band)?;
+        let (min, max) = self.channel_stats()[band as usize].range();
+        if min > max {
+            return Ok(vec![f64::NAN, f64::NAN]);
+        }
+        Ok(vec![min, max])
+    }
+
+    /// The smallest finite sample of each channel, NaN for a channel without
+    /// one; fully transparent pixels are left out as in `band_min_max`.
+    /// `min_value` is the smallest of these whenever statistics were taken,
+    /// so a per-band auto-normalization of RGB float data can stretch each
+    /// channel on its own.
+    #[wasm_bindgen(getter)]
+    pub fn channel_min_values(&self) -> Result<Vec<f64>, JsValue> {
+        self.live()?;
+        Ok(self.channel_stats().iter().map(|stats| if stats.count > 0 { stats.min } else { f64::NAN }).collect())
+    }
+
+    /// The largest finite sample of each channel, like `channel_min_values`.
+    #[wasm_bindgen(getter)]
+    pub fn channel_max_values(&self) -> Result<Vec<f64>, JsValue> {
+        self.live()?;
+        Ok(self.channel_stats().iter().map(|stats| if stats.count > 0 { stats.max } else { f64::NAN }).collect())
+    }
+
+    /// Mean of the finite samples of each channel, over the same samples as
+    /// `channel_min_values` and from the same pass; NaN for a channel
+    /// without any. With `channel_std_devs` and `channel_medians`, enough to
+    /// pick a default display window for scientific float data (say mean
+    /// +/- 2 sigma) without a histogram.
+    #[wasm_bindgen(getter)]
+    pub fn channel_means(&self) -> Result<Vec<f64>, JsValue> {
+        self.live()?;
+        Ok(self.channel_stats().iter().map(ChannelStats::mean).collect())
+    }
+
+    /// Population standard deviation of the finite samples of each
+    /// channel, like `channel_means`.
+    #[wasm_bindgen(getter)]
+    pub fn channel_std_devs(&self) -> Result<Vec<f64>, JsValue> {
+        self.live()?;
+        Ok(self.channel_stats().iter().map(ChannelStats::std_dev).collect())
+    }
+
+    /// Approximate median of the finite samples of each channel, like
+    /// `channel_means`: the nearest-rank median (`summary`'s exact `p50`)
+    /// read off a 4096-bin histogram of the channel's min..max, one more
+    /// pass over the samples and not cached. Exact when the median's bin
+    /// holds a single value, as it does for integer data of up to 4096
+    /// levels; otherwise interpolated between the bin's lowest and highest
+    /// sample.
+    #[wasm_bindgen(getter)]
+    pub fn channel_medians(&self) -> Result<Vec<f64>, JsValue> {
+        self.live()?;
+        const BINS: usize = 4096;
+        let stats = self.channel_stats();
+        // (count, lowest, highest) per bin and channel.
+        let mut bins = vec![(0u64, f64::INFINITY, f64::NEG_INFINITY); stats.len() * BINS];
+        self.visit_pixels(&mut |pixel: &[f64]| {
+            for (band, (channel, &value)) in stats.iter().zip(pixel).enumerate() {
+                if value.is_finite() && channel.max > channel.min {
+                    let index = (((value - channel.min) / (channel.max - channel.min) * BINS as f64) as usize).min(BINS - 1);
+                    let bin = &mut bins[band * BINS + index];
+                    *bin = (bin.0 + 1, bin.1.min(value), bin.2.max(value));
+                }
+            }
+        });
+        Ok(stats
+            .iter()
+            .zip(bins.chunks_exact(BINS))
+            .map(|(channel, bins)| {
+                if channel.count == 0 || channel.max == channel.min {
+                    return channel.mean();
+                }
+                let rank = ((channel.count - 1) as f64 / 2.0).round() as u64;
+                let mut below = 0u64;
+                for &(count, low, high) in bins {
+                    if below + count > rank {
+                        return if count == 1 { low } else { low + (high - low) * (rank - below) as f64 / (count - 1) as f64 };
+                    }
+                    below += count;
+                }
+                channel.max
+            })
+            .collect())
+    }
+
+    /// Interleaved 3-channel f32 composite built from any three bands (false
+    /// color), so pages with more than four channels can be shown through the
+    /// regular RGB pipeline. The same band may be picked more than once.
+    #[wasm_bindgen]
+    pub fn composite_bands_as_f32(&self, red: u32, green: u32, blue: u32) -> Result<Vec<f32>, JsValue> {
+        self.live()?;
+        for band in [red, green, blue] {
+            self.check_band(band)?;
+        }
+        let bands = [red as usize, green as usize, blue as usize];
+        let mut out = Vec::with_capacity((self.width as usize) * (self.height as usize) * 3);
+        for pixel in self.samples_f32().chunks_exact(self.channels as usize) {
+            out.extend(bands.iter().map(|&band| pixel[band]));
+        }
+        Ok(out)
+    }
+
+    /// Collapse contiguous band ranges of a hyperspectral cube into display
+    /// channels without handing the whole cube to JS. `ranges` is a flat list
+    /// of `[start, end)` band pairs, one pair per output channel (e.g.
+    /// `[0, 40, 40, 80, 80, 120]` for a three-channel preview); each output
+    /// sample is the sum of its range's samples, or their mean when `sum` is
+    /// false. NaN samples are skipped, and a pixel whose range is all NaN
+    /// yields NaN. Returns interleaved f32 with `ranges.len() / 2` channels.
+    #[wasm_bindgen]
+    pub fn aggregate_bands_as_f32(&self, ranges: &[u32], sum: bool) -> Result<Vec<f32>, JsValue> {
+        self.live()?;
+        if ranges.is_empty() || !ranges.len().is_multiple_of(2) {
+            return Err(JsValue::from_str("Band ranges must be a non-empty list of [start, end) pairs"));
+        }
+        let ranges: Vec<(usize, usize)> = ranges
+            .chunks_exact(2)
+            .map(|pair| (pair[0] as usize, pair[1] as usize))
+            .collect();
+        for &(start, end) in &ranges {
+            if start >= end || end > self.channels as usize {
+                return Err(JsValue::from_str(&format!(
+                    "Band range [{}, {}) is empty or out of range (image has {} channel(s))",
+                    start, end, self.channels
+                )));
+            }
+        }
+        self.check_band(0)?;
+
+        let mut out = Vec::with_capacity((self.width as usize) * (self.height as usize) * ranges.len());
+        for pixel in self.samples_f32().chunks_exact(self.channels as usize) {
+            for &(start, end) in &ranges {
+                let mut total = 0f64;
+                let mut count = 0u32;
+                for &value in &pixel[start..end] {
+                    if !value.is_nan() {
+                        total += value as f64;
+                        count += 1;
+                    }
+                }
+                out.push(match count {
+                    0 => f32::NAN,
+                    _ if sum => total as f32,
+                    _ => (total / count as f64) as f32,
+                });
+            }
+        }
+        Ok(out)
+    }
+
+    /// How `get_data_bytes` and `get_data_as_f32` are laid out, as JSON, so
+    /// consumers (WebGL uploads, numpy-style viewers, tests) need no
+    /// per-format knowledge. Both buffers are row-major with the origin at the
+    /// top-left corner (the Orientation tag is already applied) and
+    /// pixel-interleaved (channels innermost, also for PlanarConfiguration 2
+    /// sources), with no row padding. `channel_names` follows the decoded
+    /// data: palette, YCbCr and CMYK pages come out as RGB(A), and samples
+    /// beyond gray(+alpha) / RGB(A) are `band<N>` (zero-based sample index).
+    /// `bytes` gives the element type of `get_data_bytes` (always little
+    /// endian; `significant_bits` is below the element width for 9-15 bit
+    /// data) and `f32` is `null` when there is no f32 view (64-bit integers).
+    #[wasm_bindgen]
+    pub fn layout(&self) -> Result<String, JsValue> {
+        self.live()?;
+        let channels = self.channels as usize;
+        let pixel_count = (self.width as usize) * (self.height as usize);
+        let names = self.layout_channel_names();
+
+        let (element, bytes_per_sample) = if self.data.is_empty() {
+            ("float32".to_string(), 4)
+        } else {
+            let bytes_per_sample = match pixel_count * channels {
+                0 => 0,
+                samples => self.data.len() / samples,
+            };
+            let kind = match self.sample_format {
+                2 => "int",
+                3 => "float",
+                _ => "uint",
+            };
+            (format!("{}{}", kind, bytes_per_sample * 8), bytes_per_sample)
+        };
+        let significant_bits = if self.data.is_empty() { 32 } else { self.bits_per_sample.min(bytes_per_sample as u32 * 8) };
+        let has_f32 = !self.data_f32.is_empty()
+            || self.native_float.is_some()
+            || self.sample_format == 3
+            || matches!(self.bits_per_sample, 1..=16 | 32);
+        let f32_json = if has_f32 {
+            format!("{{\"pixel_stride\":{},\"row_stride\":{}}}", channels, channels * self.width as usize)
+        } else {
+            "null".to_string()
+        };
+
+        Ok(format!(
+            "{{\"width\":{},\"height\":{},\"channels\":{},\"order\":\"row-major\",\"interleave\":\"pixel\",\"origin\":\"top-left\",\"channel_names\":[{}],\"bytes\":{{\"element\":\"{}\",\"significant_bits\":{},\"endianness\":\"little\",\"pixel_stride_bytes\":{},\"row_stride_bytes\":{}}},\"f32\":{}}}",
+            self.width,
+            self.height,
+            self.channels,
+            names.iter().map(|name| format!("\"{}\"", name)).collect::<Vec<_>>().join(","),
+            element,
+            significant_bits,
+            bytes_per_sample * channels,
+            bytes_per_sample * channels * self.width as usize,
+            f32_json
+        ))
+    }
+
+    /// Names for the channels as JSON `{"source","names"}`, so a UI can
+    /// label bands "depth", "confidence" or "NIR" instead of "channel 2".
+    /// `source` says where they come from: "exr" (the EXR channels
+    /// `decode_image` shows, e.g. "Z"), "gdal" (the band descriptions in
+    /// GDAL_METADATA), "ome" (OME-XML `Channel` names, used when there are
+    /// exactly as many as channels) or "layout" when the file names none.
+    /// Bands a source leaves unnamed keep their `layout` name ("gray",
+    /// "red", "band3", ...). Palette, YCbCr and CMYK pages are decoded to
+    /// RGB(A), so GDAL's descriptions of their stored bands are not used.
+    #[wasm_bindgen]
+    pub fn channel_labels_json(&self) -> Result<String, JsValue> {
+        self.live()?;
+        let channels = self.channels as usize;
+        let converted = matches!(self.photometric_interpretation, 3 | 5 | 6);
+        let ome = ome_channel_names(&self.ome_xml);
+        let sources: [(&str, Vec<Option<String>>); 3] = [
+            ("exr", self.page_metadata.channel_names.iter().map(|name| (!name.is_empty()).then(|| name.clone())).collect()),
+            ("gdal", if converted { Vec::new() } else { self.geo.descriptions.clone() }),
+            ("ome", if ome.len() == channels { ome } else { Vec::new() }),
+        ];
+        let (source, labels) = sources
+            .into_iter()
+            .find(|(_, labels)| labels.iter().take(channels).any(Option::is_some))
+            .unwrap_or(("layout", Vec::new()));
+        let names: Vec<String> = self
+            .layout_channel_names()
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| labels.get(i).cloned().flatten().unwrap_or(name))
+            .collect();
+        Ok(format!(
+            "{{\"source\":\"{}\",\"names\":[{}]}}",
+            source,
+            names.iter().map(|name| format!("\"{}\"", json_escape(name))).collect::<Vec<_>>().join(",")
+        ))
+    }
+
+    /// The samples of `get_data_bytes` (same element type, see `layout`)
+    /// rearranged for tools that expect a fixed binary convention: big or
+    /// little endian, the channels listed in `channel_order` (zero-based, in
+    /// that order; empty keeps all of them as stored, and a subset or a
+    /// reordering like `[2, 1, 0]` for BGR is fine), and either
+    /// pixel-interleaved or planar (`planar`: every pixel of the first listed
+    /// channel, then the next, i.e. a C-order `[channel][row][column]`
+    /// array). Rows are top to bottom without padding in both layouts.
+    #[wasm_bindgen]
+    pub fn export_raw(&self, big_endian: bool, channel_order: &[u32], planar: bool) -> Result<Vec<u8>, JsValue> {
+        self.live()?;
+        let channels = self.channels as usize;
+        let pixel_count = (self.width as usize) * (self.height as usize);
+        if let Some(&band) = channel_order.iter().find(|&&band| band >= self.channels) {
+            return Err(JsValue::from_str(&format!(
+                "Raw export: channel {} is out of range (image has {} channel(s))",
+                band, self.channels
+            )));
+        }
+        let order: Vec<usize> = if channel_order.is_empty() {
+            (0..channels).collect()
+        } else {
+            channel_order.iter().map(|&band| band as usize).collect()
+        };
+
+        let bytes: Cow<'_, [u8]> = if self.data.is_empty() { Cow::Owned(self.get_data_bytes()?) } else { Cow::Borrowed(&self.data) };
+        let sample_count = pixel_count * channels;
+        if sample_count == 0 || bytes.is_empty() || !bytes.len().is_multiple_of(sample_count) {
+            return Err(JsValue::from_str("Raw export: no pixel data (after take_data_as_f32?)"));
+        }
+        let element = bytes.len() / sample_count;
+
+        let mut out = Vec::with_capacity(pixel_count * order.len() * element);
+        let mut push = |pixel: usize, band: usize| {
+            let start = (pixel * channels + band) * element;
+            let sample = &bytes[start..start + element];
+            if big_endian {
+                out.extend(sample.iter().rev());
+            } else {
+                out.extend_from_slice(sample);
+            }
+        };
+        if planar {
+            for &band in &order {
+                for pixel in 0..pixel_count {
+                    push(pixel, band);
+                }
+            }
+        } else {
+            for pixel in 0..pixel_count {
+                for &band in &order {
+                    push(pixel, band);
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// Everything a status bar or tooling integration usually asks for, as one
+    /// JSON object: dimensions, dtype, the layout/compression tags, the GDAL
+    /// nodata value, GeoTIFF georeferencing (`null` if none) and per-channel
+    /// stats. Each `channel_stats` entry has the min/max/mean, the population
+    /// standard deviation `std` and the 1st, 5th, 25th, 50th, 75th, 95th and
+    /// 99th percentiles of the finite non-nodata samples (nearest rank),
+    /// plus `nan_count`, `inf_count` and
+    /// `nodata_count`. Statistics are `null` for a channel with no valid
+    /// samples, and `channel_stats` is empty when the samples have no f32 view
+    /// (64-bit integers). Integer pages are converted for this pass only;
+    /// the copy is not kept in the `get_data_as_f32` cache.
+    #[wasm_bindgen]
+    pub fn summary(&self) -> Result<String, JsValue> {
+        self.live()?;
+        const PERCENTILES: [u32; 7] = [1, 5, 25, 50, 75, 95, 99];
+
+        let dtype = self.dtype();
+        let nodata = self.nodata();
+        let mut json = format!(
+            "{{\"width\":{},\"height\":{},\"channels\":{},\"dtype\":\"{}\",\"bits_per_sample\":{},\"sample_format\":{},\"compression\":{},\"predictor\":{},\"photometric_interpretation\":{},\"planar_configuration\":{},\"tiled\":{},\"nodata\":{},\"geo\":{},\"channel_stats\":[",
+            self.width,
+            self.height,
+            self.channels,
+            dtype,
+            self.bits_per_sample,
+            self.sample_format,
+            self.compression,
+            self.predictor,
+            self.photometric_interpretation,
+            self.planar_configuration,
+            self.tile_width > 0 && self.tile_length > 0,
+            nodata.map_or("null".to_string(), json_f64),
+            self.geo.to_json()
+        );
+
+        let channels = self.channels as usize;
+        let samples = self.samples_f32_uncached();
+        if channels > 0 && samples.len() == (self.width as usize) * (self.height as usize) * channels {
+            let is_nodata = self.nodata_matcher();
+            for band in 0..channels {
+                let (mut nan_count, mut inf_count, mut nodata_count) = (0u64, 0u64, 0u64);
+                let mut valid: Vec<f32> = Vec::with_capacity(samples.len() / channels);
+                for &value in samples.iter().skip(band).step_by(channels) {
+                    if is_nodata(value) {
+                        nodata_count += 1;
+                    } else if value.is_nan() {
+                        nan_count += 1;
+                    } else if value.is_infinite() {
+                        inf_count += 1;
+                    } else {
+                        valid.push(value);
+                    }
+                }
+
+                let (mut min, mut max, mut total) = (f64::NAN, f64::NAN, 0f64);
+                for &value in &valid {
+                    let value = value as f64;
+                    min = if min.is_nan() { value } else { min.min(value) };
+                    max = if max.is_nan() { value } else { max.max(value) };
+                    total += value;
+                }
+                let mean = if valid.is_empty() { f64::NAN } else { total / valid.len() as f64 };
+                let variance = valid.iter().map(|&value| (value as f64 - mean).powi(2)).sum::<f64>() / valid.len() as f64;
+                let mut percentiles = Vec::with_capacity(PERCENTILES.len());
+                for p in PERCENTILES {
+                    let value = if valid.is_empty() {
+                        f64::NAN
+                    } else {
+                        let rank = ((p as f64 / 100.0) * (valid.len() - 1) as f64).round() as usize;
+                        *valid.select_nth_unstable_by(rank, f32::total_cmp).1 as f64
+                    };
+                    percentiles.push(format!("\"p{}\":{}", p, json_f64(value)));
+                }
+
+                if band > 0 {
+                    json.push(',');
+                }
+                json.push_str(&format!(
+                    "{{\"min\":{},\"max\":{},\"mean\":{},\"std\":{},{},\"nan_count\":{},\"inf_count\":{},\"nodata_count\":{}}}",
+                    json_f64(min),
+                    json_f64(max),
+                    json_f64(mean),
+                    json_f64(variance.sqrt()),
+                    percentiles.join(","),
+                    nan_count,
+                    inf_count,
+                    nodata_count
+                ));
+            }
+        }
+        json.push_str("]}");
+        Ok(json)
+    }
+
+    /// The page's metadata as one JSON object, so a caller can read it in a
+    /// single call rather than through a dozen getters: `width`, `height`,
+    /// `channels`, `dtype`, `bits_per_sample` and `sample_format` of the
+    /// decoded samples, `bits_per_channel` (BitsPerSample as stored, one per
+    /// stored sample; the decoded depth for other formats), `compression`
+    /// and `photometric_interpretation` with their `compression_name` and
+    /// `photometric_name`, `predictor`, `planar_configuration`, the strip/
+    /// tile layout, `resolution` (`{"x","y","unit"}` with unit "none",
+    /// "inch" or "centimeter", or `null`), `pixel_aspect_ratio`, and `tags`,
+    /// an object of the descriptive ASCII tags present (`make`, `model`,
+    /// `software`, `date_time`, `image_description`, ...).
+    #[wasm_bindgen]
+    pub fn metadata_json(&self) -> Result<String, JsValue> {
+        self.live()?;
+        let metadata = &self.page_metadata;
+        let bits_per_channel = if metadata.bits_per_channel.is_empty() {
+            vec![self.bits_per_sample; self.channels as usize]
+        } else {
+            metadata.bits_per_channel.clone()
+        };
+        let bits_per_channel: Vec<String> = bits_per_channel.iter().map(u32::to_string).collect();
+        let resolution = metadata.resolution.map_or("null".to_string(), |(x, y, unit)| {
+            let unit = match unit {
+                1 => "none",
+                3 => "centimeter",
+                _ => "inch",
+            };
+            format!("{{\"x\":{},\"y\":{},\"unit\":\"{}\"}}", json_f64(x), json_f64(y), unit)
+        });
+        let tags: Vec<String> = metadata
+            .text
+            .iter()
+            .map(|(key, value)| format!("\"{}\":\"{}\"", key, json_escape(value)))
+            .collect();
+        Ok(format!(
+            "{{\"width\":{},\"height\":{},\"channels\":{},\"dtype\":\"{}\",\"bits_per_sample\":{},\"bits_per_channel\":[{}],\"sample_format\":{},\"compression\":{},\"compression_name\":\"{}\",\"predictor\":{},\"photometric_interpretation\":{},\"photometric_name\":\"{}\",\"planar_configuration\":{},\"tiled\":{},\"tile_width\":{},\"tile_length\":{},\"rows_per_strip\":{},\"resolution\":{},\"pixel_aspect_ratio\":{},\"tags\":{{{}}}}}",
+            self.width,
+            self.height,
+            self.channels,
+            self.dtype(),
+            self.bits_per_sample,
+            bits_per_channel.join(","),
+            self.sample_format,
+            self.compression,
+            compression_name(self.compression),
+            self.predictor,
+            self.photometric_interpretation,
+            photometric_name(self.photometric_interpretation),
+            self.planar_configuration,
+            self.tile_width > 0 && self.tile_length > 0,
+            self.tile_width,
+            self.tile_length,
+            self.rows_per_strip,
+            resolution,
+            json_f64(self.pixel_aspect_ratio),
+            tags.join(",")
+        ))
+    }
+
+    /// Colour interpretation of every decoded channel, as JSON
+    /// `{"source","bands","composite"}`. `bands` uses GDAL's names (Red,
+    /// Green, Blue, Gray, Alpha, Undefined, plus whatever else GDAL wrote,
+    /// e.g. NIR). `source` is "gdal" when GDAL_METADATA names any band of the
+    /// page (bands it leaves out take their default), else "photometric":
+    /// the defaults follow the photometric layout the way `layout` names the
+    /// channels. Palette, YCbCr and CMYK pages are decoded to RGB(A), so they
+    /// always report "photometric". `composite` is the default display
+    /// assignment: the `[red, green, blue]` band indices when all three are
+    /// present in any order, else `[gray]` (the first Gray band, or band 0).
+    #[wasm_bindgen]
+    pub fn color_interpretation_json(&self) -> Result<String, JsValue> {
+        self.live()?;
+        let channels = self.channels as usize;
+        let gray_like = matches!(self.photometric_interpretation, 0 | 1);
+        let converted = matches!(self.photometric_interpretation, 3 | 5 | 6);
+        let from_gdal = !converted && self.geo.color_interp.iter().take(channels).any(Option::is_some);
+        let bands: Vec<String> = (0..channels)
+            .map(|i| {
+                let gdal = if from_gdal { self.geo.color_interp.get(i).cloned().flatten() } else { None };
+                gdal.unwrap_or_else(|| match (gray_like, channels, i) {
+                    (true, _, 0) => "Gray",
+                    (true, 2, 1) | (false, 4, 3) => "Alpha",
+                    (false, 3.., 0) => "Red",
+                    (false, 3.., 1) => "Green",
+                    (false, 3.., 2) => "Blue",
+                    _ => "Undefined",
+                }.to_string())
+            })
+            .collect();
+
+        let find = |name: &str| bands.iter().position(|band| band.eq_ignore_ascii_case(name));
+        let composite = match (find("Red"), find("Green"), find("Blue")) {
+            (Some(r), Some(g), Some(b)) => vec![r, g, b],
+            _ => vec![find("Gray").unwrap_or(0)],
+        };
+        Ok(format!(
+            "{{\"source\":\"{}\",\"bands\":[{}],\"composite\":[{}]}}",
+            if from_gdal { "gdal" } else { "photometric" },
+            bands.iter().map(|band| format!("\"{}\"", json_escape(band))).collect::<Vec<_>>().join(","),
+            composite.iter().map(|index| index.to_string()).collect::<Vec<_>>().join(",")
+        ))
+    }
+
+    /// Display settings that should make the image "just look right" on
+    /// first open, as JSON `{"kind","mode","min","max","colormap","gamma_in",
+    /// "gamma_out","reason"}`. `kind` classifies the image from its type,
+    /// layout and values:
+    /// - "photo": unsigned colour, unsigned grayscale using most of its
+    ///   range, or float colour within 0..1. Shown in the webview's "gamma"
+    ///   mode over the type's full range (0..255, 0..65535, 0..1), or
+    ///   "manual" over 0..2^bits-1 for 10/12/14-bit samples and for 16-bit
+    ///   colour that only fills its low 10-15 bits. Other integer colour
+    ///   gets its 1st..99th percentile.
+    /// - "label": one integer-valued band with at most 32 distinct values
+    ///   (class maps, masks, segmentations). "manual" over the exact min..max
+    ///   with the "turbo" colormap so neighbouring ids stay distinguishable.
+    /// - "hdr": float colour brighter than 1, taken as linear light: "manual"
+    ///   from 0 to the 99.5th percentile with a 2.2 output gamma.
+    /// - "depth": any other single-band data (float depth/disparity, signed
+    ///   elevation, 16-bit integers filling under a quarter of their range):
+    ///   "manual" over the 1st..99th percentile with "viridis", so a few
+    ///   outliers or invalid zeros don't wash the rest out.
+    /// - "empty" when no sample is valid; the mode is then "auto".
+    ///
+    /// The values come from up to `max_samples` evenly spaced finite,
+    /// non-nodata samples of the colour bands (0 = all of them), skipping
+    /// fully transparent pixels.
+    #[wasm_bindgen]
+    pub fn suggest_display(&self, max_samples: u32) -> Result<String, JsValue> {
+        self.live()?;
+        const LABEL_MAX_DISTINCT: usize = 32;
+
+        let channels = self.channels as usize;
+        // Colour is judged by its RGB bands; gray + alpha and multiband
+        // grayscale pages by their first band.
+        let colour_bands = if channels >= 3 && !matches!(self.photometric_interpretation, 0 | 1) { 3 } else { 1 };
+        let samples = self.samples_f32();
+        let pixel_count = samples.len().checked_div(channels).unwrap_or(0);
+        let max_samples = if max_samples == 0 { usize::MAX } else { max_samples as usize };
+        let step = (pixel_count * colour_bands).div_ceil(max_samples).max(1);
+        let is_nodata = self.nodata_matcher();
+        let is_opaque = self.opaque_matcher();
+        let mut valid: Vec<f32> = samples
+            .chunks_exact(channels.max(1))
+            .step_by(step)
+            .filter(|pixel| is_opaque(pixel))
+            .flat_map(|pixel| &pixel[..colour_bands.min(pixel.len())])
+            .copied()
+            .filter(|&value| value.is_finite() && !is_nodata(value))
+            .collect();
+
+        let suggestion = |kind: &str, mode: &str, min: f64, max: f64, colormap: &str, gamma_out: f64, reason: &str| {
+            format!(
+                "{{\"kind\":\"{}\",\"mode\":\"{}\",\"min\":{},\"max\":{},\"colormap\":\"{}\",\"gamma_in\":1,\"gamma_out\":{},\"reason\":\"{}\"}}",
+                kind, mode, json_f64(min), json_f64(max), colormap, json_f64(gamma_out), json_escape(reason)
+            )
+        };
+        if valid.is_empty() {
+            return Ok(suggestion("empty", "auto", f64::NAN, f64::NAN, "none", 1.0, "no finite, non-nodata samples"));
+        }
+        valid.sort_unstable_by(f32::total_cmp);
+        let percentile = |p: f64| valid[((p / 100.0) * (valid.len() - 1) as f64).round() as usize] as f64;
+        let (min, max) = (valid[0] as f64, valid[valid.len() - 1] as f64);
+
+        let integer_type = self.sample_format != 3 && self.fractional_bits == 0 && self.value_transform.is_none();
+        let unsigned = integer_type && self.sample_format == 1;
+        let integral = integer_type || valid.iter().all(|value| value.fract() == 0.0);
+        if colour_bands == 1 && integral {
+            // Needs a few samples per value, or any small image would do.
+            let max_distinct = LABEL_MAX_DISTINCT.min(valid.len() / 4);
+            let distinct = 1 + valid.windows(2).filter(|pair| pair[0] != pair[1]).take(max_distinct).count();
+            if distinct <= max_distinct {
+                let reason = format!("{} distinct integer values", distinct);
+                return Ok(suggestion("label", "manual", min, max, "turbo", 1.0, &reason));
+            }
+        }
+
+        let (low, high) = (percentile(1.0), percentile(99.0));
+        let (low, high) = if high > low { (low, high) } else { (min, max) };
+        if unsigned && (8..=16).contains(&self.bits_per_sample) {
+            let full_scale = 2f64.powi(self.bits_per_sample as i32) - 1.0;
+            if colour_bands == 3 || self.bits_per_sample == 8 {
+                if self.bits_per_sample != 16 {
+                    let reason = format!("{}-bit image over its full range", self.bits_per_sample);
+                    let mode = if self.bits_per_sample == 8 { "gamma" } else { "manual" };
+                    return Ok(suggestion("photo", mode, 0.0, full_scale, "none", 1.0, &reason));
+                }
+                // 10/12/14-bit sensor data stored in 16 bits.
+                let used_bits = (max + 1.0).log2().ceil();
+                if (10.0..16.0).contains(&used_bits) {
+                    let reason = format!("16-bit container holding {}-bit data", used_bits);
+                    return Ok(suggestion("photo", "manual", 0.0, 2f64.powf(used_bits) - 1.0, "none", 1.0, &reason));
+                }
+                return Ok(suggestion("photo", "gamma", 0.0, full_scale, "none", 1.0, "16-bit image over its full range"));
+            }
+            if high - low >= full_scale / 4.0 {
+                let reason = format!("{}-bit grayscale using most of its range", self.bits_per_sample);
+                let mode = if self.bits_per_sample == 16 { "gamma" } else { "manual" };
+                return Ok(suggestion("photo", mode, 0.0, full_scale, "none", 1.0, &reason));
+            }
+        }
+        if colour_bands == 3 {
+            if integer_type {
+                return Ok(suggestion("photo", "manual", low, high, "none", 1.0, "1st to 99th percentile of the colour samples"));
+            }
+            let brightest = percentile(99.5);
+            if brightest <= 1.0 + 1e-3 && min >= -1e-3 {
+                return Ok(suggestion("photo", "gamma", 0.0, 1.0, "none", 1.0, "float colour within 0..1"));
+            }
+            let reason = "float colour beyond 1, treated as linear light";
+            return Ok(suggestion("hdr", "manual", 0.0, if brightest > 0.0 { brightest } else { max }, "none", 2.2, reason));
+        }
+
+        Ok(suggestion("depth", "manual", low, high, "viridis", 1.0, "1st to 99th percentile of the valid samples"))
+    }
+
+    /// Page-wide statistics state as JSON `{"state","min","max",
+    /// "valid_count","nan_count","inf_count","nodata_count",
+    /// "transparent_count"}`, counted over every sample of every band except
+    /// those of the `transparent_count` fully transparent pixels
+    /// (`set_include_transparent_pixels`). `state` is "valid" when at least
+    /// one sample is finite and not nodata, with `min`/`max` taken over those,
+    /// and "empty" for an all-NaN/Inf/nodata page, whose `min`/`max` are
+    /// null: a defined state for the caller to show (e.g. "no valid
+    /// data") rather than a range to normalize by. 64-bit integer pages,
+    /// which have no f32 view, count every sample as valid.
+    #[wasm_bindgen]
+    pub fn value_stats_json(&self) -> Result<String, JsValue> {
+        self.live()?;
+        let samples = self.samples_f32();
+        let sample_count = (self.width as usize) * (self.height as usize) * (self.channels as usize);
+        let (mut valid_count, mut nan_count, mut inf_count, mut nodata_count) = (0u64, 0u64, 0u64, 0u64);
+        let mut transparent_count = 0u64;
+        let (mut min, mut max) = (f64::INFINITY, f64::NEG_INFINITY);
+        if samples.len() == sample_count {
+            let is_nodata = self.nodata_matcher();
+            let is_opaque = self.opaque_matcher();
+            for pixel in samples.chunks_exact(self.channels.max(1) as usize) {
+                if !is_opaque(pixel) {
+                    transparent_count += 1;
+                    continue;
+                }
+                for &value in pixel {
+                    if is_nodata(value) {
+                        nodata_count += 1;
+                    } else if value.is_nan() {
+                        nan_count += 1;
+                    } else if value.is_infinite() {
+                        inf_count += 1;
+                    } else {
+                        valid_count += 1;
+                        min = min.min(value as f64);
+                        max = max.max(value as f64);
+                    }
+                }
+            }
+        } else {
+            valid_count = sample_count as u64;
+            (min, max) = self.value_range();
+        }
+        let state = if valid_count > 0 { "valid" } else { "empty" };
+        Ok(format!(
+            "{{\"state\":\"{}\",\"min\":{},\"max\":{},\"valid_count\":{},\"nan_count\":{},\"inf_count\":{},\"nodata_count\":{},\"transparent_count\":{}}}",
+            state,
+            json_f64(min),
+            json_f64(max),
+            valid_count,
+            nan_count,
+            inf_count,
+            nodata_count,
+            transparent_count
+        ))
+    }
+
+    /// `[nan, positive_inf, negative_inf, nodata]` sample counts over every
+    /// band, for warning about invalid data and deciding whether to mask it.
+    /// `nodata` is the value to count as nodata, the declared GDAL nodata
+    /// when undefined (none on a page without one); NaN counts NaN samples
+    /// as nodata. Transparent pixels are skipped as in `value_stats_json`,
+    /// and pages without an f32 view (64-bit integers) count nothing.
+    #[wasm_bindgen]
+    pub fn invalid_sample_counts(&self, nodata: Option<f64>) -> Result<Vec<f64>, JsValue> {
+        self.live()?;
+        let samples = self.samples_f32();
+        let sample_count = (self.width as usize) * (self.height as usize) * (self.channels as usize);
+        let mut counts = [0u64; 4];
+        if samples.len() == sample_count {
+            let nodata = nodata.or(self.nodata());
+            let is_nodata = |value: f32| nodata.is_some_and(|nd| value as f64 == nd || (nd.is_nan() && value.is_nan()));
+            let is_opaque = self.opaque_matcher();
+            for &value in samples.chunks_exact(self.channels.max(1) as usize).filter(|pixel| is_opaque(pixel)).flatten() {
+                let kind = if is_nodata(value) {
+                    3
+                } else if value.is_nan() {
+                    0
+                } else if value == f32::INFINITY {
+                    1
+                } else if value == f32::NEG_INFINITY {
+                    2
+                } else {
+                    continue;
+                };
+                counts[kind] += 1;
+            }
+        }
+        Ok(counts.iter().map(|&count| count as f64).collect())
+    }
+
+    /// Percentage (0-100) of valid pixels in each band, i.e. samples that
+    /// are finite and not the GDAL nodata value, in one pass over the
+    /// interleaved data, so a folder of tiles can be triaged for mostly empty
+    /// ones at a glance. Empty when the samples have no f32 view (64-bit
+    /// integers).
+    #[wasm_bindgen]
+    pub fn valid_coverage(&self) -> Result<Vec<f64>, JsValue> {
+        self.live()?;
+        let channels = self.channels as usize;
+        let pixel_count = (self.width as usize) * (self.height as usize);
+        let samples = self.samples_f32();
+        if channels == 0 || pixel_count == 0 || samples.len() != pixel_count * channels {
+            return Ok(Vec::new());
+        }
+        let is_nodata = self.nodata_matcher();
+        let mut valid = vec![0u64; channels];
+        for pixel in samples.chunks_exact(channels) {
+            for (count, &value) in valid.iter_mut().zip(pixel) {
+                if value.is_finite() && !is_nodata(value) {
+                    *count += 1;
+                }
+            }
+        }
+        Ok(valid.iter().map(|&count| count as f64 * 100.0 / pixel_count as f64).collect())
+    }
+
+    /// Begin recomputing per-band statistics under new settings without
+    /// blocking: the returned `StatsJob` is advanced a slice at a time with
+    /// `StatsJob::step`, so the caller can yield to the event loop between
+    /// slices (as the async decode does) and simply drop the job when the
+    /// user changes the nodata value or range again. `nodata` replaces the
+    /// file's GDAL nodata for this pass (pass the `nodata` from `summary` to
+    /// keep it; undefined means none), and samples outside
+    /// `valid_min..=valid_max` (either bound optional) are counted as out of
+    /// range instead of contributing to the statistics. Fully transparent
+    /// pixels are skipped unless `set_include_transparent_pixels` says
+    /// otherwise.
+    #[wasm_bindgen]
+    pub fn start_stats(&self, nodata: Option<f64>, valid_min: Option<f64>, valid_max: Option<f64>) -> Result<StatsJob, JsValue> {
+        self.live()?;
+        self.check_band(0)?;
+        Ok(StatsJob::new(self, nodata, valid_min, valid_max))
+    }
+
+    /// Approximate `start_stats` for a first look at a huge page, from at
+    /// most `max_pixels` pixels (0 = all of them) in milliseconds. The page
+    /// is split into a grid of about `max_pixels` equal cells and one
+    /// pixel is taken per cell at a position hashed from `seed` (stratified
+    /// jitter), so every part of the image is represented and the same seed
+    /// picks the same pixels. JSON `{"sampled_pixels","total_pixels",
+    /// "coverage","confidence","rank_error","bands"}`: `bands` is
+    /// `StatsJob::result_json` over the sampled pixels (its counts are of
+    /// samples, not scaled to the page), `coverage` the sampled fraction of
+    /// the pixels, and `rank_error` the Dvoretzky-Kiefer-Wolfowitz bound on
+    /// how far, as a fraction of the valid samples, any reported percentile
+    /// may be from its true rank at the given `confidence` (0 when every
+    /// pixel was visited, null with no valid samples). The sampled min/max
+    /// always lie within the exact ones.
+    #[wasm_bindgen]
+    pub fn sampled_stats_json(
+        &self,
+        max_pixels: u32,
+        seed: u32,
+        nodata: Option<f64>,
+        valid_min: Option<f64>,
+        valid_max: Option<f64>,
+    ) -> Result<String, JsValue> {
+        const CONFIDENCE: f64 = 0.95;
+
+        self.live()?;
+        self.check_band(0)?;
+        let (width, height, channels) = (self.width as usize, self.height as usize, self.channels as usize);
+        let total_pixels = width * height;
+        let samples = self.samples_f32();
+        let mut job = StatsJob::new(self, nodata, valid_min, valid_max);
+        let exact = max_pixels == 0 || max_pixels as usize >= total_pixels;
+        let sampled = if exact {
+            samples.to_vec()
+        } else {
+            // Cells shaped like the page, so they stay close to square.
+            let target = max_pixels as f64;
+            let cells_x = ((target * width as f64 / height as f64).sqrt().round() as usize).clamp(1, width);
+            let cells_y = (max_pixels as usize / cells_x).clamp(1, height);
+            let mut sampled = Vec::with_capacity(cells_x * cells_y * channels);
+            for cell_y in 0..cells_y {
+                let (y0, y1) = (cell_y * height / cells_y, (cell_y + 1) * height / cells_y);
+                for cell_x in 0..cells_x {
+                    let (x0, x1) = (cell_x * width / cells_x, (cell_x + 1) * width / cells_x);
+                    let bits = counter_hash(seed, (cell_y * cells_x + cell_x) as u64);
+                    let x = x0 + ((bits & 0xffff_ffff) as usize) % (x1 - x0);
+                    let y = y0 + ((bits >> 32) as usize) % (y1 - y0);
+                    let start = (y * width + x) * channels;
+                    sampled.extend_from_slice(&samples[start..start + channels]);
+                }
+            }
+            sampled
+        };
+        job.accumulate_moments(&sampled);
+        job.pass = 1;
+        job.accumulate_histogram(&sampled);
+        job.pass = 2;
+
+        let sampled_pixels = sampled.len() / channels;
+        let valid = job.bands.iter().map(|band| band.count).min().unwrap_or(0);
+        let rank_error = match valid {
+            0 => f64::NAN,
+            _ if exact => 0.0,
+            n => ((2.0 / (1.0 - CONFIDENCE)).ln() / (2.0 * n as f64)).sqrt().min(1.0),
+        };
+        let coverage = if total_pixels == 0 { 1.0 } else { sampled_pixels as f64 / total_pixels as f64 };
+        Ok(format!(
+            "{{\"sampled_pixels\":{},\"total_pixels\":{},\"coverage\":{},\"confidence\":{},\"rank_error\":{},\"bands\":{}}}",
+            sampled_pixels,
+            total_pixels,
+            json_f64(coverage),
+            json_f64(CONFIDENCE),
+            json_f64(rank_error),
+            job.result_json()
+        ))
+    }
+
+    /// 2D histogram of band `band_x` against band `band_y` of this image
+    /// (e.g. two spectral bands), for a scatter-density view of how they
+    /// correlate. See `joint_histogram_with` for the binning.
+    #[wasm_bindgen]
+    pub fn joint_histogram(&self, band_x: u32, band_y: u32, bins_x: u32, bins_y: u32) -> Result<JointHistogram, JsValue> {
+        self.live()?;
+        self.joint_histogram_with(band_x, self, band_y, bins_x, bins_y)
+    }
+
+    /// 2D histogram of band `band_x` of this image against band `band_y` of
+    /// `other` (e.g. predicted vs ground-truth depth), pairing samples at the
+    /// same pixel; both images must have the same dimensions. Each axis spans
+    /// its band's finite min..max in `bins_x`/`bins_y` equal bins, and pixels
+    /// where either sample is NaN/Inf are left out.
+    #[wasm_bindgen]
+    pub fn joint_histogram_with(
+        &self,
+        band_x: u32,
+        other: &TiffResult,
+        band_y: u32,
+        bins_x: u32,
+        bins_y: u32,
+    ) -> Result<JointHistogram, JsValue> {
+        self.live()?;
+        other.live()?;
+        if bins_x == 0 || bins_y == 0 {
+            return Err(JsValue::from_str("Joint histogram: bin counts must be non-zero"));
+        }
+        if self.width != other.width || self.height != other.height {
+            return Err(JsValue::from_str(&format!(
+                "Joint histogram: image sizes differ ({}x{} vs {}x{})",
+                self.width, self.height, other.width, other.height
+            )));
+        }
+        self.check_band(band_x)?;
+        other.check_band(band_y)?;
+
+        let xs = self.samples_f32().iter().skip(band_x as usize).step_by(self.channels as usize);
+        let ys = other.samples_f32().iter().skip(band_y as usize).step_by(other.channels as usize);
+        let pairs: Vec<(f32, f32)> = xs
+            .zip(ys)
+            .filter(|(x, y)| x.is_finite() && y.is_finite())
+            .map(|(&x, &y)| (x, y))
+            .collect();
+        Ok(JointHistogram::from_pairs(&pairs, bins_x, bins_y))
+    }
+
+    /// One `bins`-bin histogram per channel, for the RGB/multiband histogram
+    /// panel, from two passes over the interleaved samples (per-channel
+    /// min/max, then binning) that handle every channel at once instead of
+    /// one traversal per channel. Each channel spans its own finite min..max;
+    /// NaN/Inf and nodata samples and fully transparent pixels are left out.
+    #[wasm_bindgen]
+    pub fn histograms(&self, bins: u32) -> Result<ChannelHistograms, JsValue> {
+        self.live()?;
+        if bins == 0 {
+            return Err(JsValue::from_str("Histograms: bin count must be non-zero"));
+        }
+        self.check_band(0)?;
+        let channels = self.channels as usize;
+        let is_opaque = self.opaque_matcher();
+        let pixels = || self.samples_f32().chunks_exact(channels).filter(|pixel| is_opaque(pixel));
+        let is_nodata = self.nodata_matcher();
+        let valid = |value: f32| value.is_finite() && !is_nodata(value);
+
+        let mut mins = vec![f32::INFINITY; channels];
+        let mut maxs = vec![f32::NEG_INFINITY; channels];
+        for pixel in pixels() {
+            for ((min, max), &value) in mins.iter_mut().zip(maxs.iter_mut()).zip(pixel) {
+                if valid(value) {
+                    *min = min.min(value);
+                    *max = max.max(value);
+                }
+            }
+        }
+
+        let bins_usize = bins as usize;
+        let mut counts = vec![0u32; channels * bins_usize];
+        for pixel in pixels() {
+            for (band, &value) in pixel.iter().enumerate() {
+                if !valid(value) {
+                    continue;
+                }
+                let (min, max) = (mins[band], maxs[band]);
+                let bin = if max > min {
+                    ((((value - min) as f64 / (max - min) as f64) * bins as f64) as usize).min(bins_usize - 1)
+                } else {
+                    0
+                };
+                counts[band * bins_usize + bin] += 1;
+            }
+        }
+
+        let range = |min: f32, max: f32| if min <= max { (min as f64, max as f64) } else { (f64::NAN, f64::NAN) };
+        let (mins, maxs) = mins.iter().zip(&maxs).map(|(&min, &max)| range(min, max)).unzip();
+        Ok(ChannelHistograms { bins, channels: self.channels, mins, maxs, counts })
+    }
+
+    /// `bins` counts of every sample (all channels pooled, like `min_value`
+    /// and `max_value`) in `bins` equal bins over `min..max`, the last bin
+    /// including `max`, for a histogram panel with a range of its own (the
+    /// current contrast window, or a fixed 0..1) without pulling the f32
+    /// data into JS. A NaN bound stands for `min_value`/`max_value`.
+    /// Samples outside the range, NaN/Inf and nodata samples and fully
+    /// transparent pixels are left out.
+    #[wasm_bindgen]
+    pub fn compute_histogram(&self, bins: u32, min: f64, max: f64) -> Result<Vec<u32>, JsValue> {
+        self.live()?;
+        self.check_band(0)?;
+        let (page_min, page_max) = self.value_range();
+        self.histogram_counts(None, bins, if min.is_nan() { page_min } else { min }, if max.is_nan() { page_max } else { max })
+    }
+
+    /// `compute_histogram` of band `band` alone; a NaN bound stands for the
+    /// band's own `band_min_max`.
+    #[wasm_bindgen]
+    pub fn compute_band_histogram(&self, band: u32, bins: u32, min: f64, max: f64) -> Result<Vec<u32>, JsValue> {
+        self.live()?;
+        self.check_band(band)?;
+        let (band_min, band_max) = self.channel_stats()[band as usize].range();
+        self.histogram_counts(Some(band), bins, if min.is_nan() { band_min } else { min }, if max.is_nan() { band_max } else { max })
+    }
+
+    /// A small raster with one pixel per tile of the page holding `metric`
+    /// of that tile's samples, per band - "mean", "std" (population
+    /// standard deviation) or "valid_fraction" (share of samples that are
+    /// finite, not nodata and not fully transparent) - so dead tiles and
+    /// stitching seams in a mosaic stand out at a glance. Tiles are
+    /// `tile_width` x `tile_height` stored pixels, 0 taking the page's own
+    /// tiles (strips for stripped pages), laid out on the stored page and
+    /// shown in display orientation like the image; tiles outside a
+    /// `decode_tiff_region` crop, and mean/std of tiles without a valid
+    /// sample, are NaN. The result is float32 with min/max taken.
+    #[wasm_bindgen]
+    pub fn tile_heatmap(&self, metric: &str, tile_width: u32, tile_height: u32) -> Result<TiffResult, JsValue> {
+        self.live()?;
+        self.check_band(0)?;
+        let read: fn(&ChannelStats, u64) -> f64 = match metric {
+            "mean" => |stats, _| stats.mean(),
+            "std" => |stats, _| stats.std_dev(),
+            "valid_fraction" => |stats, total| if total == 0 { f64::NAN } else { stats.count as f64 / total as f64 },
+            other => {
+                return Err(JsValue::from_str(&format!(
+                    "Tile heatmap: unknown metric '{}' (expected \"mean\", \"std\" or \"valid_fraction\")",
+                    other
+                )))
+            }
+        };
+        let frame = self.frame();
+        let tiled = self.tile_width > 0 && self.tile_length > 0;
+        let tile_width = match tile_width {
+            0 if tiled => self.tile_width,
+            0 => frame.stored_width,
+            width => width,
+        };
+        let tile_height = match tile_height {
+            0 if tiled => self.tile_length,
+            0 => self.rows_per_strip.clamp(1, frame.stored_height.max(1)),
+            height => height,
+        };
+        let across = frame.stored_width.div_ceil(tile_width.max(1)).max(1);
+        let down = frame.stored_height.div_ceil(tile_height.max(1)).max(1);
+
+        let channels = self.channels as usize;
+        let is_opaque = self.opaque_matcher();
+        let is_nodata = self.nodata_matcher();
+        // Per stored tile: the samples seen and the statistics of the valid ones.
+        let mut totals = vec![0u64; (across * down) as usize];
+        let mut stats = vec![ChannelStats::default(); totals.len() * channels];
+        for (index, pixel) in self.samples_f32().chunks_exact(channels).enumerate() {
+            let (x, y) = ((index % self.width as usize) as f64, (index / self.width as usize) as f64);
+            let (stored_x, stored_y) = frame.to_source(x + 0.5, y + 0.5);
+            let column = ((stored_x / tile_width as f64) as u32).min(across - 1);
+            let row = ((stored_y / tile_height as f64) as u32).min(down - 1);
+            let tile = (row * across + column) as usize;
+            totals[tile] += 1;
+            if is_opaque(pixel) {
+                for (channel, &value) in stats[tile * channels..(tile + 1) * channels].iter_mut().zip(pixel) {
+                    if !is_nodata(value) {
+                        channel.add(value as f64);
+                    }
+                }
+            }
+        }
+
+        let orientation = frame.orientation;
+        let (out_width, out_height) = if orientation.transposes() { (down, across) } else { (across, down) };
+        let mut heatmap = vec![f32::NAN; (out_width * out_height) as usize * channels];
+        for row in 0..down {
+            for column in 0..across {
+                let tile = (row * across + column) as usize;
+                if totals[tile] == 0 {
+                    continue;
+                }
+                let (x, y) = orientation.stored_to_display(column as f64 + 0.5, row as f64 + 0.5, across, down);
+                let cell = (y as usize * out_width as usize + x as usize) * channels;
+                for (out, channel) in heatmap[cell..cell + channels].iter_mut().zip(&stats[tile * channels..(tile + 1) * channels]) {
+                    *out = read(channel, totals[tile]) as f32;
+                }
+            }
+        }
+        Ok(TiffResult::from_decoded(out_width, out_height, self.channels, DecodingResult::F32(heatmap), String::new()))
+    }
+
+    /// Largest finite `sqrt(u^2 + v^2)` over the pixels of a flow field
+    /// (band 0 = u, band 1 = v, as `decode_flo` returns), i.e. the
+    /// normalization `flow_color_wheel` uses when given no maximum; 0 if no
+    /// pixel has a finite flow vector.
+    #[wasm_bindgen]
+    pub fn flow_max_magnitude(&self) -> Result<f64, JsValue> {
+        self.live()?;
+        self.check_band(1)?;
+        let max = self
+            .samples_f32()
+            .chunks_exact(self.channels as usize)
+            .map(|px| (px[0] as f64).hypot(px[1] as f64))
+            .filter(|magnitude| magnitude.is_finite())
+            .fold(0.0, f64::max);
+        Ok(max)
+    }
+
+    /// Render the image to `width * height` RGBA bytes for an `ImageData`,
+    /// the way the viewer draws it. Mirrors `ImageRenderer` in the webview:
+    /// samples are normalized from `min`..`max` and clamped, then, unless
+    /// `gamma_in`, `gamma_out` and `exposure_stops` are an identity, raised
+    /// to `gamma_in`, scaled by `2^exposure_stops`, raised to `1 / gamma_out`
+    /// and clamped again. Single-channel images go through the display
+    /// `colormap` (one of the webview's `COLORMAP_NAMES`; "" or "none" keeps
+    /// gray). Two channels are gray + alpha and four RGBA, the alpha taken
+    /// as stored relative to the integer full scale (0..1 for float data);
+    /// bands beyond the first four are ignored. Pixels with a non-finite
+    /// color sample are drawn in `nan_color` (RGB or RGBA bytes, magenta when
+    /// empty), as on screen. A `min`/`max` that is not finite, such as the
+    /// NaN range of an all-NaN page, draws the finite samples black (like
+    /// `min == max`), so such a page renders as `nan_color` on black.
+    ///
+    /// `output_space` is the canvas colour space the bytes are for: "srgb",
+    /// or "display-p3" on a wide-gamut display (create the `ImageData` with
+    /// `{ colorSpace: "display-p3" }`). Rendered colour is taken to be in
+    /// `source_primaries` with the sRGB curve and converted to the output
+    /// primaries in linear light; colours outside the output gamut are
+    /// desaturated toward their own luminance until they fit, which keeps
+    /// hue and lightness where per-channel clipping would shift them.
+    /// Colormaps are defined in sRGB and converted the same way; gray stays
+    /// gray. With `set_color_managed`, the embedded ICC profile's tone curves
+    /// and colorants take the place of the sRGB curve and `source_primaries`.
+    ///
+    /// `dither_seed` adds triangular dither of one output step before the
+    /// colour is rounded to bytes, hiding the banding of smooth HDR or
+    /// 16-bit gradients (undefined renders without). The noise is a hash of
+    /// the seed and each sample's position, so the same settings and seed
+    /// give the same bytes on every run and platform, which golden-image
+    /// tests can rely on; `render_settings_json` reports the full set.
+    ///
+    /// `clip_highlight` ("solid" or "zebra"; undefined, "" or "none" for
+    /// off) marks pixels with a colour sample below `min` in blue and above
+    /// `max` in red, filled or striped, so clipping shows at a glance while
+    /// the range is being adjusted. It needs a finite, increasing range.
+    #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_rgba(
+        &self,
+        min: f64,
+        max: f64,
+        gamma_in: f64,
+        gamma_out: f64,
+        exposure_stops: f64,
+        colormap: &str,
+        nan_color: &[u8],
+        output_space: &str,
+        dither_seed: Option<u32>,
+        clip_highlight: Option<String>,
+    ) -> Result<Vec<u8>, JsValue> {
+        self.live()?;
+        self.check_transfer("render_rgba", (self.width as usize) * (self.height as usize) * 4)?;
+        let settings = RenderSettings::resolve(
+            "Render", min, max, gamma_in, gamma_out, exposure_stops, colormap, nan_color, output_space, dither_seed,
+            clip_highlight.as_deref().unwrap_or(""),
+        )?;
+        self.render_display_rgba(&settings)
+    }
+
+    /// `render_rgba` with its settings in a `RenderOptions`: the samples
+    /// normalized from the options' `min`..`max` (each defaulting to the
+    /// image's own range), put through the display `gamma` or the sRGB curve
+    /// (`srgb_transfer`) and, for single-channel data, the options'
+    /// `colormap`, as RGBA bytes ready for
+    /// `new ImageData(bytes, width, height)` on an sRGB canvas.
+    /// Non-finite samples are magenta. A page decoded without statistics has
+    /// them measured for the automatic range.
+    #[wasm_bindgen]
+    pub fn render_to_rgba(&self, options: &RenderOptions) -> Result<Vec<u8>, JsValue> {
+        self.live()?;
+        self.check_transfer("render_to_rgba", (self.width as usize) * (self.height as usize) * 4)?;
+        if !(options.gamma.is_finite() && options.gamma > 0.0) {
+            return Err(JsValue::from_str(&format!("Render: gamma {} must be finite and positive", options.gamma)));
+        }
+        let (auto_min, auto_max) = match (options.min, options.max) {
+            (Some(min), Some(max)) => (min, max),
+            _ => match self.value_range() {
+                (min, max) if min.is_finite() => (min, max),
+                _ => self.channel_stats().iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), stats| {
+                    (min.min(stats.min), max.max(stats.max))
+                }),
+            },
+        };
+        let settings = RenderSettings {
+            srgb_transfer: options.srgb_transfer,
+            ..RenderSettings::resolve(
+                "Render",
+                options.min.unwrap_or(auto_min),
+                options.max.unwrap_or(auto_max),
+                1.0,
+                options.gamma,
+                0.0,
+                &options.colormap,
+                &[],
+                "srgb",
+                None,
+                "",
+            )?
+        };
+        self.render_display_rgba(&settings)
+    }
+
+    /// Every input `render_rgba` would render with for these arguments, as
+    /// JSON with the defaults resolved: `{"render_version","min","max",
+    /// "gamma_in","gamma_out","exposure_stops","srgb_transfer","colormap",
+    /// "nan_color","output_space","source_primaries","color_managed",
+    /// "gamut_mapped","dither","clip_highlight"}`, where `srgb_transfer` is
+    /// always false here (see `RenderOptions`), `colormap` is null for gray,
+    /// `source_primaries` are the ICC profile's colorants while
+    /// `color_managed` (see `set_color_managed`), `dither` is
+    /// `{"kind":"tpdf","seed"}` or null, `clip_highlight` is
+    /// `{"style","below","above"}` (the two RGB colours) or null, and `render_version` changes whenever the renderer can produce
+    /// different bytes for the same settings. Stored next to a golden image
+    /// it says exactly how to reproduce it, and when it has to be redone.
+    #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_settings_json(
+        &self,
+        min: f64,
+        max: f64,
+        gamma_in: f64,
+        gamma_out: f64,
+        exposure_stops: f64,
+        colormap: &str,
+        nan_color: &[u8],
+        output_space: &str,
+        dither_seed: Option<u32>,
+        clip_highlight: Option<String>,
+    ) -> Result<String, JsValue> {
+        self.live()?;
+        let settings = RenderSettings::resolve(
+            "Render", min, max, gamma_in, gamma_out, exposure_stops, colormap, nan_color, output_space, dither_seed,
+            clip_highlight.as_deref().unwrap_or(""),
+        )?;
+        let (source, color_managed) = self.render_source();
+        Ok(settings.to_json(&source, color_managed))
+    }
+
+    /// This image's rendered RGBA bytes as an 8-bit RGBA PNG.
+    fn encode_snapshot_png(&self, rgba: &[u8]) -> Result<Vec<u8>, JsValue> {
+        let mut png_bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png_bytes, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(rgba))
+            .map_err(|e| JsValue::from_str(&format!("Snapshot: PNG encoding failed: {}", e)))?;
+        Ok(png_bytes)
+    }
+
+    /// Shared body of `render_rgba` and `export_snapshot_png`.
+    fn render_display_rgba(&self, settings: &RenderSettings) -> Result<Vec<u8>, JsValue> {
+        self.check_band(0)?;
+        let RenderSettings { min, max, nan_rgba, target, dither_seed, clip_highlight, .. } = *settings;
+        // Colour comes out in the source primaries and colormaps in sRGB;
+        // either is converted only when it isn't in the target gamut already.
+        let colour_mapper = match self.managed_profile() {
+            Some(profile) => GamutMapper::from_icc(profile, target),
+            None => GamutMapper::new(self.primaries.as_ref().unwrap_or(&SRGB_PRIMARIES), target),
+        };
+        let lut: Option<Vec<[u8; 3]>> = match (settings.lut.clone(), GamutMapper::new(&SRGB_PRIMARIES, target)) {
+            (Some(lut), Some(mapper)) => Some(lut.into_iter().map(|rgb| mapper.map_bytes(rgb)).collect()),
+            (lut, _) => lut,
+        };
+
+        let channels = self.channels as usize;
+        let clip_highlight = clip_highlight.filter(|_| min.is_finite() && max.is_finite() && max > min);
+        let to_unit = settings.unit_mapper();
+        // Sample `index` (pixel * 4 + channel) of a 0..1 value as a byte,
+        // dithered unless clipped to black/white or dithering is off.
+        let quantize = |unit: f64, index: usize| -> u8 {
+            let offset = match dither_seed {
+                Some(seed) if unit > 0.0 && unit < 1.0 => dither_offset(seed, index as u64),
+                _ => 0.0,
+            };
+            (unit * 255.0 + offset).round().clamp(0.0, 255.0) as u8
+        };
+        let (colour_channels, full_scale) = self.srgb_colour_channels();
+        let alpha_scale = if colour_channels > 0 { full_scale as f64 } else { 1.0 };
+        let to_alpha = |value: f32| -> u8 {
+            if value.is_finite() { ((value as f64 / alpha_scale).clamp(0.0, 1.0) * 255.0).round() as u8 } else { 255 }
+        };
+
+        let mut rgba = Vec::with_capacity((self.width as usize) * (self.height as usize) * 4);
+        for (index, pixel) in self.samples_f32().chunks_exact(channels).enumerate() {
+            let base = index * 4;
+            let (colour, alpha) = match channels {
+                1 => (&pixel[..1], None),
+                2 => (&pixel[..1], Some(pixel[1])),
+                3 => (&pixel[..3], None),
+                _ => (&pixel[..3], (channels == 4).then(|| pixel[3])),
+            };
+            if colour.iter().any(|value| !value.is_finite()) {
+                rgba.extend_from_slice(&nan_rgba);
+                continue;
+            }
+            let alpha = alpha.map_or(255, to_alpha);
+            match (colour, &lut) {
+                ([gray], Some(lut)) => {
+                    let [r, g, b] = lut[quantize(to_unit(*gray), base) as usize];
+                    rgba.extend_from_slice(&[r, g, b, alpha]);
+                }
+                ([gray], None) => {
+                    let gray = quantize(to_unit(*gray), base);
+                    rgba.extend_from_slice(&[gray, gray, gray, alpha]);
+                }
+                _ => {
+                    let unit = [to_unit(colour[0]), to_unit(colour[1]), to_unit(colour[2])];
+                    let unit = colour_mapper.as_ref().map_or(unit, |mapper| mapper.map(unit));
+                    let [r, g, b] = [0, 1, 2].map(|c| quantize(unit[c], base + c));
+                    rgba.extend_from_slice(&[r, g, b, alpha]);
+                }
+            }
+            if let Some(style) = clip_highlight {
+                let (x, y) = (index % self.width as usize, index / self.width as usize);
+                let clipped = if colour.iter().any(|&value| value as f64 > max) {
+                    Some(CLIP_ABOVE_RGB)
+                } else if colour.iter().any(|&value| (value as f64) < min) {
+                    Some(CLIP_BELOW_RGB)
+                } else {
+                    None
+                };
+                if let Some(rgb) = clipped.filter(|_| style.covers(x, y)) {
+                    rgba[base..base + 3].copy_from_slice(&rgb);
+                }
+            }
+        }
+
+        Ok(rgba)
+    }
+
+    /// Render the image the way the viewer draws it and encode that as an
+    /// 8-bit RGBA PNG, for pasting what is on screen into a report (unlike
+    /// `export_raw`, which keeps the data). The pixels are `render_rgba`'s
+    /// for an sRGB canvas, so colour in wider `source_primaries` is mapped
+    /// into sRGB, with the same optional `dither_seed` and `clip_highlight`.
+    #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
+    pub fn export_snapshot_png(
+        &self,
+        min: f64,
+        max: f64,
+        gamma_in: f64,
+        gamma_out: f64,
+        exposure_stops: f64,
+        colormap: &str,
+        nan_color: &[u8],
+        dither_seed: Option<u32>,
+        clip_highlight: Option<String>,
+    ) -> Result<Vec<u8>, JsValue> {
+        self.live()?;
+        let settings = RenderSettings::resolve(
+            "Snapshot", min, max, gamma_in, gamma_out, exposure_stops, colormap, nan_color, "srgb", dither_seed,
+            clip_highlight.as_deref().unwrap_or(""),
+        )?;
+        let rgba = self.render_display_rgba(&settings)?;
+        self.encode_snapshot_png(&rgba)
+    }
+
+    /// `export_snapshot_png` with `annotations` burned into the rendered
+    /// pixels before encoding, so the saved snapshot shows the regions
+    /// marked on screen.
+    #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
+    pub fn export_annotated_snapshot_png(
+        &self,
+        min: f64,
+        max: f64,
+        gamma_in: f64,
+        gamma_out: f64,
+        exposure_stops: f64,
+        colormap: &str,
+        nan_color: &[u8],
+        dither_seed: Option<u32>,
+        clip_highlight: Option<String>,
+        annotations: &Annotations,
+    ) -> Result<Vec<u8>, JsValue> {
+        self.live()?;
+        let settings = RenderSettings::resolve(
+            "Snapshot", min, max, gamma_in, gamma_out, exposure_stops, colormap, nan_color, "srgb", dither_seed,
+            clip_highlight.as_deref().unwrap_or(""),
+        )?;
+        let mut rgba = self.render_display_rgba(&settings)?;
+        annotations.burn_into(&mut rgba, self.width, self.height)?;
+        self.encode_snapshot_png(&rgba)
+    }
+
+    /// Render `depth_band` through `colormap` ("" or "none" for gray) from
+    /// `min`..`max`, faded by `confidence_band` in the same pass, for the
+    /// depth + confidence pairs ToF and stereo pipelines write. Confidence
+    /// is normalized from `confidence_min`..`confidence_max` and clamped;
+    /// `mode` "alpha" makes it the pixel's opacity, "saturation" blends the
+    /// colour toward its own gray (Rec. 709 luma) as confidence drops, so
+    /// unreliable depth stays visible but reads as washed out. A non-finite
+    /// confidence counts as none. Pixels with non-finite depth are drawn in
+    /// `nan_color` (RGB or RGBA bytes, magenta when empty), as `render_rgba`
+    /// draws them. Returns `width * height` RGBA bytes for an sRGB canvas.
+    #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_confidence_rgba(
+        &self,
+        min: f64,
+        max: f64,
+        colormap: &str,
+        depth_band: u32,
+        confidence_band: u32,
+        confidence_min: f64,
+        confidence_max: f64,
+        mode: &str,
+        nan_color: &[u8],
+    ) -> Result<Vec<u8>, JsValue> {
+        self.live()?;
+        self.check_band(depth_band)?;
+        self.check_band(confidence_band)?;
+        self.check_transfer("render_confidence_rgba", (self.width as usize) * (self.height as usize) * 4)?;
+        let mode = match mode {
+            "alpha" => ConfidenceMode::Alpha,
+            "saturation" => ConfidenceMode::Saturation,
+            other => {
+                return Err(JsValue::from_str(&format!(
+                    "Confidence render: unknown mode '{}' (expected \"alpha\" or \"saturation\")", other
+                )))
+            }
+        };
+        if !(confidence_min.is_finite() && confidence_max.is_finite() && confidence_max > confidence_min) {
+            return Err(JsValue::from_str(&format!(
+                "Confidence render: confidence range {}..{} must be finite and increasing", confidence_min, confidence_max
+            )));
+        }
+        let settings =
+            RenderSettings::resolve("Confidence render", min, max, 1.0, 1.0, 0.0, colormap, nan_color, "srgb", None, "")?;
+        let to_unit = settings.unit_mapper();
+        let inv_confidence_range = 1.0 / (confidence_max - confidence_min);
+
+        let (channels, depth_band, confidence_band) = (self.channels as usize, depth_band as usize, confidence_band as usize);
+        let mut rgba = Vec::with_capacity((self.width as usize) * (self.height as usize) * 4);
+        for pixel in self.samples_f32().chunks_exact(channels) {
+            let depth = pixel[depth_band];
+            if !depth.is_finite() {
+                rgba.extend_from_slice(&settings.nan_rgba);
+                continue;
+            }
+            let level = (to_unit(depth) * 255.0).round() as u8;
+            let rgb = settings.lut.as_ref().map_or([level; 3], |lut| lut[level as usize]);
+            let confidence = pixel[confidence_band] as f64;
+            let confidence = if confidence.is_finite() {
+                ((confidence - confidence_min) * inv_confidence_range).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            match mode {
+                ConfidenceMode::Alpha => {
+                    rgba.extend_from_slice(&rgb);
+                    rgba.push((confidence * 255.0).round() as u8);
+                }
+                ConfidenceMode::Saturation => {
+                    let gray = 0.2126 * rgb[0] as f64 + 0.7152 * rgb[1] as f64 + 0.0722 * rgb[2] as f64;
+                    rgba.extend(rgb.map(|c| (gray + confidence * (c as f64 - gray)).round().clamp(0.0, 255.0) as u8));
+                    rgba.push(255);
+                }
+            }
+        }
+        Ok(rgba)
+    }
+
+    /// Thumbnails of band 0 under every display colormap, for a colormap
+    /// picker that shows real previews without one `render_rgba` round trip
+    /// per entry. The image is sampled nearest-neighbour (pixel centres) to
+    /// `thumb_width` x `thumb_height` - up to 1024 each; pick them to keep
+    /// the aspect ratio - and each thumbnail is rendered like `render_rgba`
+    /// for an sRGB canvas with that colormap, no dither and NaN in magenta.
+    /// Returns RGBA bytes of a `thumb_width` x `thumb_height * N` strip, the
+    /// thumbnails stacked top to bottom in `display_colormap_names` order.
+    #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
+    pub fn colormap_previews(
+        &self,
+        min: f64,
+        max: f64,
+        gamma_in: f64,
+        gamma_out: f64,
+        exposure_stops: f64,
+        thumb_width: u32,
+        thumb_height: u32,
+    ) -> Result<Vec<u8>, JsValue> {
+        self.live()?;
+        self.check_band(0)?;
+        if thumb_width == 0 || thumb_height == 0 || thumb_width > 1024 || thumb_height > 1024 {
+            return Err(JsValue::from_str(&format!(
+                "Colormap preview: thumbnail size {}x{} must be 1..1024 on each side",
+                thumb_width, thumb_height
+            )));
+        }
+        let (width, height, channels) = (self.width as u64, self.height as u64, self.channels as usize);
+        let samples = self.samples_f32();
+        let thumbnail: Vec<f32> = (0..thumb_height as u64)
+            .flat_map(|ty| {
+                let y = (2 * ty + 1) * height / (2 * thumb_height as u64);
+                (0..thumb_width as u64).map(move |tx| (y, (2 * tx + 1) * width / (2 * thumb_width as u64)))
+            })
+            .map(|(y, x)| samples[(y * width + x) as usize * channels])
+            .collect();
+
+        let mut rgba = Vec::with_capacity(thumbnail.len() * 4 * DISPLAY_COLORMAP_NAMES.len());
+        for name in DISPLAY_COLORMAP_NAMES {
+            let settings = RenderSettings::resolve(
+                "Colormap preview", min, max, gamma_in, gamma_out, exposure_stops, name, &[], "srgb", None, "",
+            )?;
+            let (to_unit, lut) = (settings.unit_mapper(), settings.lut.as_deref().unwrap_or_default());
+            for &value in &thumbnail {
+                if value.is_finite() {
+                    let [r, g, b] = lut[(to_unit(value) * 255.0).round() as usize];
+                    rgba.extend_from_slice(&[r, g, b, 255]);
+                } else {
+                    rgba.extend_from_slice(&settings.nan_rgba);
+                }
+            }
+        }
+        Ok(rgba)
+    }
+
+    /// Render a flow field (band 0 = u, band 1 = v) with the standard
+    /// Middlebury color wheel: hue gives the direction and saturation the
+    /// magnitude relative to `max_magnitude` (vectors beyond it are drawn
+    /// darkened). `max_magnitude <= 0` normalizes by `flow_max_magnitude`,
+    /// so the longest vector in the image is fully saturated. Returns
+    /// `width * height` RGBA bytes for an `ImageData`; pixels with unknown
+    /// (NaN/Inf) flow are transparent.
+    #[wasm_bindgen]
+    pub fn flow_color_wheel(&self, max_magnitude: f64) -> Result<Vec<u8>, JsValue> {
+        self.live()?;
+        let max_magnitude = if max_magnitude > 0.0 { max_magnitude } else { self.flow_max_magnitude()? };
+        self.check_band(1)?;
+        let wheel = flow_color_wheel_table();
+        let scale = if max_magnitude > 0.0 { 1.0 / max_magnitude } else { 0.0 };
+        let mut out = Vec::with_capacity((self.width as usize) * (self.height as usize) * 4);
+        for px in self.samples_f32().chunks_exact(self.channels as usize) {
+            let (u, v) = (px[0] as f64 * scale, px[1] as f64 * scale);
+            if !u.is_finite() || !v.is_finite() {
+                out.extend_from_slice(&[0, 0, 0, 0]);
+                continue;
+            }
+            let radius = u.hypot(v);
+            let angle = (-v).atan2(-u) / std::f64::consts::PI;
+            let position = (angle + 1.0) / 2.0 * (wheel.len() - 1) as f64;
+            let k0 = position.floor() as usize;
+            let k1 = (k0 + 1) % wheel.len();
+            let f = position - k0 as f64;
+            for (&c0, &c1) in wheel[k0].iter().zip(&wheel[k1]) {
+                let color = (1.0 - f) * c0 as f64 / 255.0 + f * c1 as f64 / 255.0;
+                let color = if radius <= 1.0 { 1.0 - radius * (1.0 - color) } else { color * 0.75 };
+                out.push((255.0 * color).round() as u8);
+            }
+            out.push(255);
+        }
+        Ok(out)
+    }
+
+    /// Red-cyan anaglyph of a stereo pair, this image being the left view and
+    /// `right` the right one (same dimensions and channel count, e.g. pages
+    /// 0 and 1 of a pair found by `detect_stereo`): red comes from the left
+    /// view and green/blue from the right, using the first channel of each
+    /// for gray images. Returns interleaved 3-channel f32 in the source
+    /// units, for the regular RGB normalization pipeline.
+    #[wasm_bindgen]
+    pub fn anaglyph_with(&self, right: &TiffResult) -> Result<Vec<f32>, JsValue> {
+        self.live()?;
+        self.check_stereo_partner(right)?;
+        Ok(anaglyph(self.samples_f32(), right.samples_f32(), self.channels as usize))
+    }
+
+    /// The left (this) and right views next to each other as one
+    /// `2 * width` by `height` interleaved f32 image with this image's
+    /// channel count, to view a two-page stereo pair side by side.
+    #[wasm_bindgen]
+    pub fn side_by_side_with(&self, right: &TiffResult) -> Result<Vec<f32>, JsValue> {
+        self.live()?;
+        self.check_stereo_partner(right)?;
+        let row = (self.width as usize) * (self.channels as usize);
+        let mut out = Vec::with_capacity(self.samples_f32().len() * 2);
+        for (left_row, right_row) in self.samples_f32().chunks_exact(row).zip(right.samples_f32().chunks_exact(row)) {
+            out.extend_from_slice(left_row);
+            out.extend_from_slice(right_row);
+        }
+        Ok(out)
+    }
+
+    /// Red-cyan anaglyph (see `anaglyph_with`) of a page that packs both
+    /// views, `layout` being "side_by_side" (left view in the left half) or
+    /// "over_under" (left view on top) as `detect_stereo` reports. The result
+    /// is one half's size: `width / 2` by `height` or `width` by
+    /// `height / 2`, 3-channel f32.
+    #[wasm_bindgen]
+    pub fn packed_stereo_anaglyph(&self, layout: &str) -> Result<Vec<f32>, JsValue> {
+        self.live()?;
+        self.check_band(0)?;
+        let (width, height, channels) = (self.width as usize, self.height as usize, self.channels as usize);
+        let samples = self.samples_f32();
+        let (left, right): (Vec<f32>, Vec<f32>) = match layout {
+            "side_by_side" => {
+                let half = (width / 2) * channels;
+                let rows = samples.chunks_exact(width * channels);
+                (
+                    rows.clone().flat_map(|row| &row[..half]).copied().collect(),
+                    rows.flat_map(|row| &row[half..2 * half]).copied().collect(),
+                )
+            }
+            "over_under" => {
+                let half = (height / 2) * width * channels;
+                (samples[..half].to_vec(), samples[half..2 * half].to_vec())
+            }
+            other => return Err(JsValue::from_str(&format!("Stereo: unknown packed layout '{}'", other))),
+        };
+        Ok(anaglyph(&left, &right, channels))
+    }
+
+    /// `[width, height]` of the image once its pixels are made square by
+    /// `resample_square_pixels_as_f32`: the axis along which pixels are
+    /// longer is stretched by `pixel_aspect_ratio`, so no detail is dropped.
+    #[wasm_bindgen]
+    pub fn square_pixel_dimensions(&self) -> Result<Vec<u32>, JsValue> {
+        self.live()?;
+        let (width, height) = (self.width as f64, self.height as f64);
+        let ratio = self.pixel_aspect_ratio;
+        Ok(if ratio > 1.0 {
+            vec![((width * ratio).round() as u32).max(1), self.height]
+        } else {
+            vec![self.width, ((height / ratio).round() as u32).max(1)]
+        })
+    }
+
+    /// The image resampled to `square_pixel_dimensions` (linear
+    /// interpolation along the stretched axis, nearest sample next to
+    /// NaN/Inf), as interleaved f32 with the original channel count, so radar
+    /// and line-scan imagery with non-square pixels renders undistorted.
+    /// Returns the samples unchanged when the pixels are already square.
+    #[wasm_bindgen]
+    pub fn resample_square_pixels_as_f32(&self) -> Result<Vec<f32>, JsValue> {
+        self.live()?;
+        self.check_band(0)?;
+        let samples = self.samples_f32();
+        let dims = self.square_pixel_dimensions()?;
+        let (width, height, channels) = (self.width as usize, self.height as usize, self.channels as usize);
+        let (out_width, out_height) = (dims[0] as usize, dims[1] as usize);
+        if (out_width, out_height) == (width, height) {
+            return Ok(samples.to_vec());
+        }
+        // Source position, neighbour and weight for each output index along
+        // the stretched axis (pixel centres aligned).
+        let taps = |from: usize, to: usize| -> Vec<(usize, usize, f32)> {
+            (0..to)
+                .map(|o| {
+                    let position = ((o as f64 + 0.5) * from as f64 / to as f64 - 0.5).clamp(0.0, (from - 1) as f64);
+                    let i0 = position.floor() as usize;
+                    (i0, (i0 + 1).min(from - 1), (position - i0 as f64) as f32)
+                })
+                .collect()
+        };
+        let lerp = |a: f32, b: f32, t: f32| {
+            if a.is_finite() && b.is_finite() {
+                a + (b - a) * t
+            } else if t < 0.5 {
+                a
+            } else {
+                b
+            }
+        };
+        let mut out = Vec::with_capacity(out_width * out_height * channels);
+        if out_width != width {
+            let taps = taps(width, out_width);
+            for row in samples.chunks_exact(width * channels) {
+                for &(i0, i1, t) in &taps {
+                    for c in 0..channels {
+                        out.push(lerp(row[i0 * channels + c], row[i1 * channels + c], t));
+                    }
+                }
+            }
+        } else {
+            let row = width * channels;
+            for (i0, i1, t) in taps(height, out_height) {
+                let (a, b) = (&samples[i0 * row..(i0 + 1) * row], &samples[i1 * row..(i1 + 1) * row]);
+                out.extend(a.iter().zip(b).map(|(&a, &b)| lerp(a, b, t)));
+            }
+        }
+        Ok(out)
+    }
+
+    /// The `width` x `height` window at display (`x`, `y`) - fractional,
+    /// as a zoomed and panned viewport lands - resampled to `out_width` x
+    /// `out_height` pixels, for rendering at non-integer zoom levels with
+    /// `render_rgba`. `resampling` is "nearest", which copies the stored
+    /// sample under each output pixel centre so depth maps and masks are
+    /// shown unfiltered, or "bilinear", which blends the four neighbouring
+    /// pixels for smooth photo scaling (best for magnification; minifying
+    /// far below 1:1 still skips pixels). Bilinear takes the nearest sample
+    /// wherever a neighbour is NaN/Inf or nodata, so holes keep their edges
+    /// instead of smearing into the surroundings.
+    ///
+    /// The result keeps the sample type (rounding blended integers), value
+    /// interpretation, colour information and nodata of this image, with
+    /// statistics over the window; float16 samples come back as float32
+    /// when blended. Georeferencing and the source frame are dropped, as
+    /// the output pixels no longer sit on the page grid.
+    #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
+    pub fn resample_viewport(
+        &self,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        out_width: u32,
+        out_height: u32,
+        resampling: &str,
+    ) -> Result<TiffResult, JsValue> {
+        self.live()?;
+        self.check_band(0)?;
+        let bilinear = match resampling {
+            "nearest" => false,
+            "bilinear" => true,
+            other => {
+                return Err(JsValue::from_str(&format!(
+                    "Viewport: unknown resampling '{}' (expected \"nearest\" or \"bilinear\")", other
+                )))
+            }
+        };
+        let inside = |start: f64, span: f64, size: u32| {
+            start.is_finite() && span.is_finite() && start >= 0.0 && span > 0.0 && start + span <= size as f64
+        };
+        if !inside(x, width, self.width) || !inside(y, height, self.height) {
+            return Err(JsValue::from_str(&format!(
+                "Viewport: window ({}, {}) {}x{} is not inside the {}x{} image",
+                x, y, width, height, self.width, self.height
+            )));
+        }
+        if out_width == 0 || out_height == 0 || out_width > 16384 || out_height > 16384 {
+            return Err(JsValue::from_str(&format!(
+                "Viewport: output size {}x{} must be 1..16384 in each direction", out_width, out_height
+            )));
+        }
+
+        let (full_width, channels) = (self.width as usize, self.channels as usize);
+        // Neighbours and weight of the second along one axis for each output
+        // position, pixel centres aligned; nearest has a single neighbour.
+        let axis = |start: f64, span: f64, size: u32, out: u32| -> Vec<(usize, usize, f64)> {
+            let last = (size - 1) as f64;
+            (0..out)
+                .map(|o| {
+                    let centre = start + (o as f64 + 0.5) * span / out as f64;
+                    if !bilinear {
+                        let i = centre.floor().clamp(0.0, last) as usize;
+                        return (i, i, 0.0);
+                    }
+                    let position = (centre - 0.5).clamp(0.0, last);
+                    let i0 = position.floor() as usize;
+                    (i0, (i0 + 1).min(size as usize - 1), position - i0 as f64)
+                })
+                .collect()
+        };
+        let columns = axis(x, width, self.width, out_width);
+        let rows = axis(y, height, self.height, out_height);
+        let view = self.samples_f32();
+        let is_nodata = self.nodata_matcher();
+        let mut plan = Vec::with_capacity(columns.len() * rows.len() * channels);
+        for &(y0, y1, ty) in &rows {
+            for &(x0, x1, tx) in &columns {
+                let pixels = [y0 * full_width + x0, y0 * full_width + x1, y1 * full_width + x0, y1 * full_width + x1];
+                let weights = [(1.0 - tx) * (1.0 - ty), tx * (1.0 - ty), (1.0 - tx) * ty, tx * ty];
+                let nearest = (if ty < 0.5 { y0 } else { y1 }) * full_width + if tx < 0.5 { x0 } else { x1 };
+                for channel in 0..channels {
+                    let samples = pixels.map(|pixel| pixel * channels + channel);
+                    let blendable = bilinear
+                        && samples.iter().all(|&i| view[i].is_finite() && !is_nodata(view[i]));
+                    plan.push(if blendable {
+                        ViewportTap::Blend(samples, weights)
+                    } else {
+                        ViewportTap::Nearest(nearest * channels + channel)
+                    });
+                }
+            }
+        }
+
+        let mut result = self.clone();
+        let stats = !self.min_value.is_nan();
+        let (mut min, mut max) = (f64::NAN, f64::NAN);
+        if !self.data.is_empty() {
+            let sample_count = full_width * (self.height as usize) * channels;
+            let bytes_per_sample = self.data.len().checked_div(sample_count).unwrap_or(0);
+            macro_rules! le_resample {
+                ($ty:ty, $n:expr, $from:expr) => {{
+                    let values: Vec<$ty> =
+                        self.data.chunks_exact($n).map(|b| <$ty>::from_le_bytes(b.try_into().unwrap())).collect();
+                    resample_by_plan(&values, &plan, |v| v as f64, $from).into_iter().flat_map(|v| v.to_le_bytes()).collect()
+                }};
+            }
+            let bytes: Vec<u8> = match (self.sample_format, bytes_per_sample) {
+                (1, 1) => le_resample!(u8, 1, |v: f64| v.round() as u8),
+                (1, 2) => le_resample!(u16, 2, |v: f64| v.round() as u16),
+                (1, 4) => le_resample!(u32, 4, |v: f64| v.round() as u32),
+                (1, 8) => le_resample!(u64, 8, |v: f64| v.round() as u64),
+                (2, 1) => le_resample!(i8, 1, |v: f64| v.round() as i8),
+                (2, 2) => le_resample!(i16, 2, |v: f64| v.round() as i16),
+                (2, 4) => le_resample!(i32, 4, |v: f64| v.round() as i32),
+                (2, 8) => le_resample!(i64, 8, |v: f64| v.round() as i64),
+                (3, 4) => le_resample!(f32, 4, |v: f64| v as f32),
+                (3, 8) => le_resample!(f64, 8, |v: f64| v),
+                (format, bytes) => {
+                    return Err(JsValue::from_str(&format!(
+                        "Viewport: unsupported sample layout (format {}, {} bytes per sample)", format, bytes
+                    )))
+                }
+            };
+            if stats {
+                (min, max) = compute_stats_le_bytes(&bytes, self.sample_format, bytes_per_sample);
+            }
+            result.data = Rc::new(bytes);
+        } else if !self.data_f32.is_empty() {
+            let values = resample_by_plan(&self.data_f32, &plan, |v| v as f64, |v| v as f32);
+            (min, max) = compute_stats_f32(&values);
+            result.data_f32 = Rc::new(values);
+        } else if let Some(native) = self.native_float.as_deref() {
+            match native {
+                DecodingResult::F64(values) => {
+                    let values = resample_by_plan(values, &plan, |v| v, |v| v);
+                    (min, max) = compute_stats_f64(&values);
+                    result.native_float = Some(Rc::new(DecodingResult::F64(values)));
+                }
+                DecodingResult::F16(values) if !bilinear => {
+                    let values = resample_by_plan(values, &plan, |v| v.to_f64(), |_| unreachable!("nearest plans never blend"));
+                    (min, max) = compute_stats_f32(&values.iter().map(|v| v.to_f32()).collect::<Vec<_>>());
+                    result.native_float = Some(Rc::new(DecodingResult::F16(values)));
+                }
+                other => {
+                    let values = decoding_result_to_f32(other);
+                    let values = resample_by_plan(&values, &plan, |v| v as f64, |v| v as f32);
+                    (min, max) = compute_stats_f32(&values);
+                    result.native_float = None;
+                    result.bits_per_sample = 32;
+                    result.data_f32 = Rc::new(values);
+                }
+            }
+        }
+        if stats {
+            result.min_value = min;
+            result.max_value = max;
+        }
+        result.width = out_width;
+        result.height = out_height;
+        result.data_f32_cache = OnceCell::new();
+        result.channel_stats = OnceCell::new();
+        result.geo = GeoInfo {
+            nodata: self.geo.nodata,
+            color_interp: self.geo.color_interp.clone(),
+            descriptions: self.geo.descriptions.clone(),
+            ..GeoInfo::default()
+        };
+        result.source_frame = None;
+        result.pixel_aspect_ratio =
+            self.pixel_aspect_ratio * (width / out_width as f64) / (height / out_height as f64);
+        Ok(result)
+    }
+
+    /// Build a reduced-resolution pyramid (each level half the previous one,
+    /// down to the first level whose longer side is at most `min_size`) from
+    /// this fully decoded image and keep it in the session cache under `key`,
+    /// replacing any previous pyramid for that key (the cache evicts least
+    /// recently used pyramids beyond `set_session_overview_byte_budget`). Meant to be called once
+    /// after the first full decode of a file without stored overviews, so
+    /// later zoom-outs read `session_overview(key, level)` instead of
+    /// resampling the full image again. Call before `take_data_as_f32`.
+    /// sRGB-encoded integer images (`srgb_colour_channels`) are averaged in
+    /// linear light and re-encoded per level, so zoomed-out views keep the
+    /// brightness of the full-resolution image instead of darkening fine
+    /// detail; alpha and extra bands are averaged as stored.
+    /// Returns the number of levels built.
+    #[wasm_bindgen]
+    pub fn build_session_overviews(&self, key: &str, min_size: u32) -> Result<u32, JsValue> {
+        self.live()?;
+        let samples = self.samples_f32();
+        let expected = (self.width as usize) * (self.height as usize) * (self.channels as usize);
+        if samples.len() != expected || expected == 0 {
+            return Ok(0);
+        }
+        let channels = self.channels as usize;
+        let (colour_channels, scale) = self.srgb_colour_channels();
+        let transfer = |values: &[f32], to_linear: bool| -> Vec<f32> {
+            values
+                .iter()
+                .enumerate()
+                .map(|(i, &v)| match (i % channels < colour_channels, to_linear) {
+                    (true, true) => srgb_to_linear(v / scale),
+                    (true, false) => linear_to_srgb(v) * scale,
+                    (false, _) => v,
+                })
+                .collect()
+        };
+        let linear;
+        let source = if colour_channels > 0 {
+            linear = transfer(samples, true);
+            linear.as_slice()
+        } else {
+            samples
+        };
+
+        let min_size = min_size.max(1);
+        let mut levels: Vec<OverviewLevel> = Vec::new();
+        let (mut width, mut height) = (self.width, self.height);
+        while width.max(height) > min_size {
+            let previous = levels.last().map_or(source, |level| level.data.as_slice());
+            let (data, w, h) = downsample_2x_f32(previous, width, height, self.channels);
+            levels.push(OverviewLevel { width: w, height: h, channels: self.channels, data });
+            (width, height) = (w, h);
+        }
+        if colour_channels > 0 {
+            for level in &mut levels {
+                level.data = transfer(&level.data, false);
+            }
+        }
+
+        let count = levels.len() as u32;
+        SESSION_OVERVIEWS.with(|cache| {
+            cache.borrow_mut().insert(key, levels);
+        });
+        Ok(count)
+    }
+}
+
+impl TiffResult {
+    /// Wrap pixels from a non-TIFF decoder (`decode_image`), or sampled by
+    /// `TiffFile::decode_strided`, as a single-strip, uncompressed "page",
+    /// packed and with min/max computed exactly as `decode_tiff_impl` does
+    /// for TIFF samples of the same type.
+    fn from_decoded(width: u32, height: u32, channels: u32, pixels: DecodingResult, all_tags_json: String) -> TiffResult {
+        let mut channels = channels;
+        let mut bits_per_sample = match &pixels {
+            DecodingResult::U8(_) | DecodingResult::I8(_) => 8,
+            DecodingResult::U16(_) | DecodingResult::I16(_) | DecodingResult::F16(_) => 16,
+            DecodingResult::U32(_) | DecodingResult::I32(_) | DecodingResult::F32(_) => 32,
+            DecodingResult::U64(_) | DecodingResult::I64(_) | DecodingResult::F64(_) => 64,
+        };
+        let photometric_interpretation = if channels >= 3 { 2 } else { 1 };
+        // Like `decode_tiff_pipeline`, alpha images are measured per pixel
+        // once packed.
+        let alpha_stats = alpha_band_index(photometric_interpretation, channels).is_some();
+        let (mut stats_time, mut pack_time) = (0.0, 0.0);
+        let packed = pack_decoding_result(
+            pixels, true, width, height, &mut channels, &mut bits_per_sample,
+            photometric_interpretation, !alpha_stats, None, &mut stats_time, &mut pack_time,
+        );
+        let mut result = TiffResult {
+            width,
+            height,
+            channels,
+            bits_per_sample,
+            sample_format: packed.sample_format,
+            compression: 1,
+            predictor: 1,
+            photometric_interpretation,
+            planar_configuration: 1,
+            rows_per_strip: height,
+            strip_count: 0,
+            strip_byte_count_total: 0,
+            strip_byte_count_max: 0,
+            tile_width: 0,
+            tile_length: 0,
+            tile_count: 0,
+            direct_decode: false,
+            data: Rc::new(packed.bytes),
+            data_f32: Rc::new(packed.floats),
+            native_float: packed.native_float.map(Rc::new),
+            data_f32_cache: OnceCell::new(),
+            min_value: packed.min,
+            max_value: packed.max,
+            range_from_tags: false,
+            timing_metadata_ms: 0.0,
+            timing_decode_ms: 0.0,
+            timing_convert_ms: 0.0,
+            timing_stats_ms: stats_time,
+            timing_pack_ms: pack_time,
+            all_tags_json,
+            ome_xml: String::new(),
+            chunk_profile_json: String::new(),
+            geo: GeoInfo::default(),
+            page_metadata: PageMetadata::default(),
+            pixel_aspect_ratio: 1.0,
+            source_frame: None,
+            fractional_bits: 0,
+            value_transform: None,
+            include_transparent: false,
+            channel_stats: OnceCell::new(),
+            primaries: None,
+            icc_profile: Vec::new(),
+            color_managed: false,
+            transfer_byte_budget: 0,
+            disposed: false,
+        };
+        if alpha_stats {
+            let stats_start = now_ms();
+            (result.min_value, result.max_value) = result.measure_stored_range();
+            result.timing_stats_ms += now_ms() - stats_start;
+        }
+        result
+    }
+
+    /// Cut a fully decoded result down to the `(x, y, width, height)`
+    /// rectangle (display coordinates, already validated) for the
+    /// `decode_tiff_region` paths that cannot skip strips/tiles, re-taking
+    /// min/max over the kept pixels when the full decode computed them.
+    fn crop_to_region(&mut self, rect: (u32, u32, u32, u32)) {
+        let (full_width, full_height) = (self.width, self.height);
+        let pixel_count = (full_width as usize) * (full_height as usize);
+        if !self.data.is_empty() {
+            let bytes_per_pixel = self.data.len().checked_div(pixel_count).unwrap_or(0);
+            if bytes_per_pixel > 0 {
+                self.data = Rc::new(crop_interleaved(&self.data, full_width, bytes_per_pixel, rect));
+            }
+        } else if !self.data_f32.is_empty() {
+            self.data_f32 = Rc::new(crop_interleaved(&self.data_f32, full_width, self.channels as usize, rect));
+        } else if let Some(native) = &self.native_float {
+            self.native_float = Some(Rc::new(crop_decoding_result(native, full_width, self.channels, rect)));
+        }
+        self.offset_source_frame(rect.0, rect.1);
+        self.width = rect.2;
+        self.height = rect.3;
+        if !self.min_value.is_nan() {
+            (self.min_value, self.max_value) = self.measure_stored_range();
+        }
+        self.data_f32_cache = OnceCell::new();
+        self.channel_stats = OnceCell::new();
+    }
+
+    /// Min/max of the finite stored samples as the decode's stats pass takes
+    /// them, in the fixed-point units of `min_value`, leaving out fully
+    /// transparent pixels (`transparent_alpha_band`); NaN for sample
+    /// layouts the pass doesn't cover.
+    fn measure_stored_range(&self) -> (f64, f64) {
+        let stored = !self.data.is_empty() || !self.data_f32.is_empty() || self.native_float.is_some();
+        if stored && self.transparent_alpha_band().is_some() {
+            let (mut min, mut max) = (f64::INFINITY, f64::NEG_INFINITY);
+            self.visit_stored_pixels(&mut |pixel: &[f64]| {
+                for &value in pixel.iter().filter(|value| value.is_finite()) {
+                    min = min.min(value);
+                    max = max.max(value);
+                }
+            });
+            return (min, max);
+        }
+        let (min, max) = if !self.data.is_empty() {
+            let bytes_per_sample = self.data.len().checked_div(self.sample_count()).unwrap_or(0);
+            compute_stats_le_bytes(&self.data, self.sample_format, bytes_per_sample)
+        } else if !self.data_f32.is_empty() {
+            compute_stats_f32(&self.data_f32)
+        } else {
+            match self.native_float.as_deref() {
+                Some(DecodingResult::F64(values)) => compute_stats_f64(values),
+                Some(other) => compute_stats_f32(&decoding_result_to_f32(other)),
+                None => (f64::NAN, f64::NAN),
+            }
+        };
+        let scale = 2f64.powi(-(self.fractional_bits as i32));
+        (min * scale, max * scale)
+    }
+
+    /// Record that these pixels start at display `(x, y)` of the raster
+    /// `source_frame` describes (the whole page when it is unset).
+    fn offset_source_frame(&mut self, x: u32, y: u32) {
+        let frame = self.source_frame.get_or_insert(SourceFrame::new(TiffOrientation::TopLeft, self.width, self.height));
+        frame.origin_x += x;
+        frame.origin_y += y;
+    }
+
+    /// `GeoInfo::raster_to_model`, or an error for pages without it.
+    fn raster_to_model(&self) -> Result<[f64; 6], JsValue> {
+        self.geo.raster_to_model().ok_or_else(|| {
+            JsValue::from_str("Georeferencing: the page has no ModelTransformation or ModelTiepoint + ModelPixelScale")
+        })
+    }
+
+    /// `source_frame`, or the identity frame of these pixels when unset.
+    fn frame(&self) -> SourceFrame {
+        self.source_frame.unwrap_or(SourceFrame::new(TiffOrientation::TopLeft, self.width, self.height))
+    }
+
+    /// The lazily converted f32 copy behind `get_data_as_f32`, computed once.
+    fn converted_f32(&self) -> &Vec<f32> {
+        self.data_f32_cache.get_or_init(|| Rc::new(self.convert_to_f32()))
+    }
+
+    /// The samples as f32 without cloning: the native float data when the
+    /// page is float32, otherwise the cached conversion.
+    fn samples_f32(&self) -> &[f32] {
+        if self.data_f32.is_empty() || self.value_transform.is_some() { self.converted_f32() } else { &self.data_f32 }
+    }
+
+    /// The samples as f32 for a one-off pass: `samples_f32` when that needs
+    /// no new conversion (float32 pages, or a conversion already cached),
+    /// otherwise a conversion that is dropped afterwards instead of filling
+    /// the cache.
+    fn samples_f32_uncached(&self) -> Cow<'_, [f32]> {
+        if (self.data_f32.is_empty() || self.value_transform.is_some()) && self.data_f32_cache.get().is_none() {
+            Cow::Owned(self.convert_to_f32())
+        } else {
+            Cow::Borrowed(self.samples_f32())
+        }
+    }
+
+    /// How many leading channels hold sRGB-encoded colour, and the integer
+    /// full scale they are encoded against: the RGB bands of 8/16-bit
+    /// unsigned RGB pages and the gray band of 8-bit unsigned grayscale ones
+    /// (which covers PNG/JPEG/WebP via `decode_image`). (0, 1.0) for
+    /// anything else - float and 16-bit grayscale data is usually linear
+    /// measurements - and while a fixed-point or value transform is set.
+    fn srgb_colour_channels(&self) -> (usize, f32) {
+        if self.sample_format != 1 || self.fractional_bits > 0 || self.value_transform.is_some() || self.native_float.is_some() {
+            return (0, 1.0);
+        }
+        let scale = ((1u64 << self.bits_per_sample.min(16)) - 1) as f32;
+        match (self.photometric_interpretation, self.bits_per_sample) {
+            (2, 8 | 16) if self.channels >= 3 => (3, scale),
+            (1, 8) if self.channels <= 2 => (1, scale),
+            _ => (0, 1.0),
+        }
+    }
+
+    /// `min_value` / `max_value`: the decode's own running min/max (which
+    /// already leaves out fully transparent pixels), or under a value
+    /// transform the fold of `channel_stats`. Skipped or tagged statistics
+    /// are returned as stored, without a pass over the samples. The running
+    /// min/max is left at +/-Infinity when no sample was finite; that
+    /// becomes NaN here.
+    fn value_range(&self) -> (f64, f64) {
+        let measured = !self.range_from_tags && !self.min_value.is_nan();
+        let (min, max) = if measured && self.value_transform.is_some() {
+            self.channel_stats().iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), stats| {
+                (min.min(stats.min), max.max(stats.max))
+            })
+        } else {
+            (self.min_value, self.max_value)
+        };
+        if min.is_finite() && max.is_finite() && min <= max { (min, max) } else { (f64::NAN, f64::NAN) }
+    }
+
+    /// The GDAL nodata value in the units of the f32 view, i.e. scaled like
+    /// the samples when a fixed-point interpretation is set.
+    fn nodata(&self) -> Option<f64> {
+        self.geo.nodata.map(|nd| nd * 2f64.powi(-(self.fractional_bits as i32)))
+    }
+
+    /// Predicate for samples equal to the GDAL nodata value (a NaN nodata
+    /// matches NaN samples); never true when the file declares none.
+    fn nodata_matcher(&self) -> impl Fn(f32) -> bool {
+        let nodata = self.nodata();
+        move |value: f32| nodata.is_some_and(|nd| value as f64 == nd || (nd.is_nan() && value.is_nan()))
+    }
+
+    fn check_stereo_partner(&self, right: &TiffResult) -> Result<(), JsValue> {
+        right.live()?;
+        self.check_band(0)?;
+        right.check_band(0)?;
+        if (self.width, self.height, self.channels) != (right.width, right.height, right.channels) {
+            return Err(JsValue::from_str(&format!(
+                "Stereo: views differ in shape ({}x{}x{} vs {}x{}x{})",
+                self.width, self.height, self.channels, right.width, right.height, right.channels
+            )));
+        }
+        Ok(())
+    }
+
+    /// Copy the bytes of `get_data_bytes` into the front of `out`. Float
+    /// samples go through a Float32Array over the same buffer when `out` is
+    /// 4-byte aligned, so they are never re-packed into a byte vector.
+    fn write_data_bytes(&self, out: &js_sys::Uint8Array) -> Result<(), JsValue> {
+        let floats: &[f32] = match (self.data.is_empty(), self.data_f32.is_empty()) {
+            (false, _) => &[],
+            (true, true) => self.converted_f32(),
+            (true, false) => &self.data_f32,
+        };
+        let needed = if self.data.is_empty() { floats.len() * 4 } else { self.data.len() };
+        if (out.length() as usize) < needed {
+            return Err(JsValue::from_str(&format!(
+                "decode_tiff_into: output buffer holds {} bytes, the page needs {}",
+                out.length(), needed
+            )));
+        }
+        if !self.data.is_empty() {
+            out.subarray(0, needed as u32).copy_from(&self.data);
+        } else if out.byte_offset().is_multiple_of(4) {
+            js_sys::Float32Array::new_with_byte_offset_and_length(&out.buffer(), out.byte_offset(), floats.len() as u32)
+                .copy_from(floats);
+        } else {
+            out.subarray(0, needed as u32).copy_from(&self.get_data_bytes()?);
+        }
+        Ok(())
+    }
+
+    fn live(&self) -> Result<(), JsValue> {
+        if self.disposed {
+            return Err(JsValue::from_str("TiffResult: this result has been disposed"));
+        }
+        Ok(())
+    }
+
+    /// Refuse to hand `bytes` of whole-image output to JS from `accessor`
+    /// when that is over `set_transfer_byte_budget`, with a "too_large"
+    /// `TiffError` naming the smallest whole-number downsampling step whose
+    /// output fits (or the step that leaves a single pixel, if none does).
+    fn check_transfer(&self, accessor: &str, bytes: usize) -> Result<(), JsValue> {
+        let budget = self.transfer_byte_budget;
+        if budget == 0 || bytes <= budget {
+            return Ok(());
+        }
+        let (width, height) = (self.width.max(1) as usize, self.height.max(1) as usize);
+        let bytes_per_pixel = bytes.div_ceil(width * height);
+        let fits = |step: usize| width.div_ceil(step) * height.div_ceil(step) * bytes_per_pixel <= budget;
+        let largest = width.max(height);
+        let mut step = ((bytes as f64 / budget as f64).sqrt().floor() as usize).clamp(2, largest.max(2));
+        while step < largest && !fits(step) {
+            step += 1;
+        }
+        let mut error = TiffError::new(
+            TiffErrorKind::TooLarge,
+            format!(
+                "Transfer budget: {} would return {} bytes, over the budget of {}; read a region, or a preview \
+                 with step {} (TiffFile.decode_strided / read_region)",
+                accessor, bytes, budget, step
+            ),
+        );
+        error.suggested_downsample = Some(step as u32);
+        Err(error.into())
+    }
+
+    /// `layout`'s `channel_names`: what the photometric layout makes of
+    /// each decoded band.
+    fn layout_channel_names(&self) -> Vec<String> {
+        let channels = self.channels as usize;
+        let gray_like = matches!(self.photometric_interpretation, 0 | 1);
+        (0..channels)
+            .map(|i| match (gray_like, channels, i) {
+                (true, 1 | 2, 0) => "gray".to_string(),
+                (true, 2, 1) | (false, 4, 3) => "alpha".to_string(),
+                (false, 3.., 0) => "red".to_string(),
+                (false, 3.., 1) => "green".to_string(),
+                (false, 3.., 2) => "blue".to_string(),
+                _ => format!("band{}", i),
+            })
+            .collect()
+    }
+
+    /// Samples in the f32 view of the whole image.
+    fn sample_count(&self) -> usize {
+        (self.width as usize) * (self.height as usize) * (self.channels as usize)
+    }
+
+    fn check_band(&self, band: u32) -> Result<(), JsValue> {
+        if band >= self.channels {
+            return Err(JsValue::from_str(&format!(
+                "Band {} is out of range (image has {} channel(s))",
+                band, self.channels
+            )));
+        }
+        if self.f32_view_len() != self.sample_count() {
+            return Err(JsValue::from_str("Band access needs f32-convertible data (not available for this sample type or after take_data_as_f32)"));
+        }
+        Ok(())
+    }
+
+    /// How many samples `samples_f32` holds, worked out from the stored
+    /// buffers the way `convert_stored_to_f32` reads them, without
+    /// converting anything.
+    fn f32_view_len(&self) -> usize {
+        if let Some(cached) = self.data_f32_cache.get() {
+            return cached.len();
+        }
+        if !self.data_f32.is_empty() {
+            return self.data_f32.len();
+        }
+        if let Some(native @ (DecodingResult::F64(_) | DecodingResult::F16(_))) = self.native_float.as_deref() {
+            return decoding_result_len(native);
+        }
+        let bytes_per_sample = match (self.sample_format, self.bits_per_sample) {
+            (3, _) | (1 | 2, 32) => 4,
+            (1 | 2, 8) => 1,
+            (1, 9..=16) | (2, 16) => 2,
+            _ => return 0,
+        };
+        self.data.len() / bytes_per_sample
+    }
+
+    /// The alpha band whose zero marks pixels to leave out of the
+    /// statistics (`set_include_transparent_pixels`), from the channel
+    /// layout alone (`alpha_band_index`). None when transparent pixels are
+    /// included or the page has no alpha band.
+    fn transparent_alpha_band(&self) -> Option<usize> {
+        if self.include_transparent {
+            return None;
+        }
+        alpha_band_index(self.photometric_interpretation, self.channels)
+    }
+
+    /// Predicate on a pixel's samples: false for the fully transparent
+    /// pixels the statistics skip, true for every pixel when none are.
+    fn opaque_matcher(&self) -> impl Fn(&[f32]) -> bool {
+        let alpha = self.transparent_alpha_band();
+        move |pixel: &[f32]| alpha.is_none_or(|band| pixel[band] > 0.0 || pixel[band].is_nan())
+    }
+
+    /// The counts behind `compute_histogram` (`band` None: every channel)
+    /// and `compute_band_histogram`.
+    fn histogram_counts(&self, band: Option<u32>, bins: u32, min: f64, max: f64) -> Result<Vec<u32>, JsValue> {
+        if bins == 0 {
+            return Err(JsValue::from_str("Histogram: bin count must be non-zero"));
+        }
+        if !(min.is_finite() && max.is_finite() && min <= max) {
+            return Err(JsValue::from_str(&format!("Histogram: range {}..{} is not a finite, ordered range", min, max)));
+        }
+        let is_opaque = self.opaque_matcher();
+        let is_nodata = self.nodata_matcher();
+        let scale = if max > min { bins as f64 / (max - min) } else { 0.0 };
+        let mut counts = vec![0u32; bins as usize];
+        for pixel in self.samples_f32().chunks_exact(self.channels as usize).filter(|pixel| is_opaque(pixel)) {
+            let samples = match band {
+                Some(band) => &pixel[band as usize..band as usize + 1],
+                None => pixel,
+            };
+            for &value in samples {
+                let value_f64 = value as f64;
+                if !value.is_finite() || is_nodata(value) || value_f64 < min || value_f64 > max {
+                    continue;
+                }
+                counts[(((value_f64 - min) * scale) as usize).min(bins as usize - 1)] += 1;
+            }
+        }
+        Ok(counts)
+    }
+
+    /// `ChannelStats` of every channel over the pixels `visit_pixels`
+    /// passes on, from a single pass.
+    fn channel_stats(&self) -> &[ChannelStats] {
+        self.channel_stats.get_or_init(|| {
+            let mut stats = vec![ChannelStats::default(); self.channels.max(1) as usize];
+            self.visit_pixels(&mut |pixel: &[f64]| {
+                for (channel, &value) in stats.iter_mut().zip(pixel) {
+                    channel.add(value);
+                }
+            });
+            stats
+        })
+    }
+
+    /// Call `visit` with the samples of every pixel `opaque_matcher` keeps,
+    /// as f64. They are the stored samples in their own precision (scaled
+    /// for a fixed-point interpretation), or the f32 view under a value
+    /// transform, whose nodata samples come as NaN as they aren't
+    /// transformed.
+    fn visit_pixels(&self, visit: &mut impl FnMut(&[f64])) {
+        if self.value_transform.is_some() {
+            let is_nodata = self.nodata_matcher();
+            let samples = self.samples_f32().iter().map(|&value| if is_nodata(value) { f64::NAN } else { value as f64 });
+            return for_each_kept_pixel(samples, self.channels.max(1) as usize, self.transparent_alpha_band(), visit);
+        }
+        self.visit_stored_pixels(visit);
+    }
+
+    /// `visit_pixels` over the stored samples, ignoring any value transform.
+    fn visit_stored_pixels(&self, visit: &mut impl FnMut(&[f64])) {
+        let channels = self.channels.max(1) as usize;
+        let alpha = self.transparent_alpha_band();
+        if !self.data_f32.is_empty() {
+            return for_each_kept_pixel(self.data_f32.iter().map(|&value| value as f64), channels, alpha, visit);
+        }
+        match self.native_float.as_deref() {
+            Some(DecodingResult::F64(values)) => return for_each_kept_pixel(values.iter().copied(), channels, alpha, visit),
+            Some(DecodingResult::F16(values)) => {
+                return for_each_kept_pixel(values.iter().map(|v| v.to_f64()), channels, alpha, visit)
+            }
+            _ => {}
+        }
+        let scale = 2f64.powi(-(self.fractional_bits as i32));
+        let sample_count = (self.width as usize) * (self.height as usize) * channels;
+        macro_rules! le_pixels {
+            ($ty:ty, $n:expr) => {
+                for_each_kept_pixel(
+                    self.data.chunks_exact($n).map(|b| <$ty>::from_le_bytes(b.try_into().unwrap()) as f64 * scale),
+                    channels,
+                    alpha,
+                    visit,
+                )
+            };
+        }
+        match (self.sample_format, self.data.len().checked_div(sample_count).unwrap_or(0)) {
+            (1, 1) => le_pixels!(u8, 1),
+            (1, 2) => le_pixels!(u16, 2),
+            (1, 4) => le_pixels!(u32, 4),
+            (1, 8) => le_pixels!(u64, 8),
+            (2, 1) => le_pixels!(i8, 1),
+            (2, 2) => le_pixels!(i16, 2),
+            (2, 4) => le_pixels!(i32, 4),
+            (2, 8) => le_pixels!(i64, 8),
+            (3, 4) => le_pixels!(f32, 4),
+            (3, 8) => le_pixels!(f64, 8),
+            _ => {}
+        }
+    }
+
+    /// The decoded sample type as `summary` names it ("uint8", "float32",
+    /// ...).
+    fn dtype(&self) -> String {
+        match self.sample_format {
+            2 => format!("int{}", self.bits_per_sample),
+            3 => format!("float{}", self.bits_per_sample),
+            _ => format!("uint{}", self.bits_per_sample),
+        }
+    }
+
+    /// Element type of the samples as kept: f16/f64 from `native_float`,
+    /// integers (and 64-bit floats) from `data`, f32 otherwise. Errors are
+    /// prefixed with `context`.
+    fn stored_element(&self, context: &str) -> Result<&'static str, JsValue> {
+        match self.native_float.as_deref() {
+            Some(DecodingResult::F16(_)) => return Ok("float16"),
+            Some(DecodingResult::F64(_)) => return Ok("float64"),
+            _ if self.data.is_empty() => return Ok("float32"),
+            _ => {}
+        }
+        let sample_count = (self.width as usize) * (self.height as usize) * (self.channels as usize);
+        Ok(match (self.sample_format, self.data.len() / sample_count.max(1)) {
+            (3, 4) => "float32",
+            (3, 8) => "float64",
+            (2, 1) => "int8",
+            (2, 2) => "int16",
+            (2, 4) => "int32",
+            (2, 8) => "int64",
+            (_, 1) => "uint8",
+            (_, 2) => "uint16",
+            (_, 4) => "uint32",
+            (_, 8) => "uint64",
+            (format, bytes) => {
+                return Err(JsValue::from_str(&format!(
+                    "{}: unsupported {}-byte samples (SampleFormat {})", context, bytes, format
+                )))
+            }
+        })
+    }
+
+    /// The ICC profile to render through, when colour management is on and
+    /// the profile can be applied to this page's colour.
+    fn managed_profile(&self) -> Option<IccProfile> {
+        if !self.color_managed || self.channels < 3 {
+            return None;
+        }
+        IccProfile::parse(&self.icc_profile).filter(IccProfile::usable)
+    }
+
+    /// The primaries renders map from and whether that is the ICC profile's
+    /// (`render_settings_json`).
+    fn render_source(&self) -> ([f64; 8], bool) {
+        match self.managed_profile().and_then(|profile| profile.primaries()) {
+            Some(primaries) => (primaries, true),
+            None => (self.primaries.unwrap_or(SRGB_PRIMARIES), false),
+        }
+    }
+
+    /// Little-endian bytes of the samples as kept (see `stored_element`).
+    fn stored_bytes(&self) -> Vec<u8> {
+        match self.native_float.as_deref() {
+            Some(DecodingResult::F16(values)) => values.iter().flat_map(|v| v.to_bits().to_le_bytes()).collect(),
+            Some(DecodingResult::F64(values)) => f64_to_le_bytes(values),
+            _ if !self.data.is_empty() => self.data.to_vec(),
+            _ => f32_to_le_bytes(&self.data_f32),
+        }
+    }
+
+    /// Bytes held by the sample buffers (stored, native float and the cached
+    /// f32 view), what a cached copy of this result costs.
+    fn retained_bytes(&self) -> usize {
+        let native = match self.native_float.as_deref() {
+            Some(DecodingResult::F16(values)) => values.len() * 2,
+            Some(other) => decoding_result_len(other) * 8,
+            None => 0,
+        };
+        let cached = self.data_f32_cache.get().map_or(0, |values| values.len() * 4);
+        self.data.len() + self.data_f32.len() * 4 + native + cached
+    }
+
+    /// The stored samples as f32, with the fixed-point scaling of
+    /// `set_fixed_point_bits` applied.
+    fn convert_to_f32(&self) -> Vec<f32> {
+        let mut values = self.convert_stored_to_f32();
+        if self.fractional_bits > 0 {
+            let scale = 2f32.powi(-(self.fractional_bits as i32));
+            values.iter_mut().for_each(|value| *value *= scale);
+        }
+        if let Some(transform) = &self.value_transform {
+            let is_nodata = self.nodata_matcher();
+            for value in values.iter_mut().filter(|value| !is_nodata(**value)) {
+                *value = transform.expr.eval(*value as f64) as f32;
+            }
+        }
+        values
+    }
+
+    /// Convert the stored samples (integer bytes or native f16/f64) to f32.
+    fn convert_stored_to_f32(&self) -> Vec<f32> {
+        if !self.data_f32.is_empty() {
+            return self.data_f32.to_vec();
+        }
+        match self.native_float.as_deref() {
+            Some(DecodingResult::F64(values)) => return values.iter().map(|&v| v as f32).collect(),
+            Some(DecodingResult::F16(values)) => return values.iter().map(|v| v.to_f32()).collect(),
+            _ => {}
+        }
+
+        match self.sample_format {
+            3 => {
+                // Already float32
+                self.data
+                    .chunks_exact(4)
+                    .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                    .collect()
+            }
+            2 => {
+                // Signed integers, two's complement in the packed bytes
+                match self.bits_per_sample {
+                    8 => self.data.iter().map(|&v| v as i8 as f32).collect(),
+                    16 => self.data
+                        .chunks_exact(2)
+                        .map(|bytes| i16::from_le_bytes([bytes[0], bytes[1]]) as f32)
+                        .collect(),
+                    32 => self.data
+                        .chunks_exact(4)
+                        .map(|bytes| i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32)
+                        .collect(),
+                    _ => vec![],
+                }
+            }
+            1 => {
+                // Convert integers to float
+                match self.bits_per_sample {
+                    8 => self.data.iter().map(|&v| v as f32).collect(),
+                    // 9..=15 covers the sub-16-bit direct decode path
+                    // (try_decode_subbit_strips): those samples are still
+                    // packed as 2 bytes each (via convert_u16_to_bytes_simd),
+                    // just with a smaller reported bits_per_sample.
+                    9..=16 => self.data
+                        .chunks_exact(2)
+                        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]) as f32)
+                        .collect(),
+                    32 => self.data
+                        .chunks_exact(4)
+                        .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32)
+                        .collect(),
+                    _ => vec![],
+                }
+            }
+            _ => vec![],
+        }
+    }
+}
+
+/// `source` with its values remapped so their distribution matches
+/// `reference`'s, for before/after comparisons that should not be dominated
+/// by an exposure or gain difference: each channel's samples are replaced
+/// by the reference values at the same quantile (equal values keep equal
+/// results), so both can be shown with the reference's display range. The
+/// images may differ in size; `reference` needs the same channel count, or
+/// a single channel that every source channel is matched against.
+/// Non-finite and nodata samples are left out on both sides and come out
+/// as NaN. Returns a float32 `TiffResult` shaped like `source`.
+#[wasm_bindgen]
+pub fn match_histogram(source: &TiffResult, reference: &TiffResult) -> Result<TiffResult, JsValue> {
+    source.live()?;
+    reference.live()?;
+    source.check_band(0)?;
+    reference.check_band(0)?;
+    let (channels, reference_channels) = (source.channels as usize, reference.channels as usize);
+    if reference_channels != channels && reference_channels != 1 {
+        return Err(JsValue::from_str(&format!(
+            "Histogram matching: reference has {} channels, expected {} or 1",
+            reference_channels, channels
+        )));
+    }
+    let (source_nodata, reference_nodata) = (source.nodata_matcher(), reference.nodata_matcher());
+    let (samples, reference_samples) = (source.samples_f32(), reference.samples_f32());
+    let mut matched = vec![f32::NAN; samples.len()];
+    for channel in 0..channels {
+        let reference_channel = channel.min(reference_channels - 1);
+        let mut targets: Vec<f32> = reference_samples
+            .iter()
+            .skip(reference_channel)
+            .step_by(reference_channels)
+            .copied()
+            .filter(|&v| v.is_finite() && !reference_nodata(v))
+            .collect();
+        if targets.is_empty() {
+            return Err(JsValue::from_str(&format!(
+                "Histogram matching: reference channel {} has no valid samples",
+                reference_channel
+            )));
+        }
+        targets.sort_unstable_by(f32::total_cmp);
+        let mut order: Vec<usize> = (channel..samples.len())
+            .step_by(channels)
+            .filter(|&i| samples[i].is_finite() && !source_nodata(samples[i]))
+            .collect();
+        order.sort_unstable_by(|&a, &b| samples[a].total_cmp(&samples[b]));
+        match_quantiles(&order, samples, &targets, &mut matched);
+    }
+    let mut result =
+        TiffResult::from_decoded(source.width, source.height, source.channels, DecodingResult::F32(matched), String::new());
+    result.geo = GeoInfo { nodata: None, ..source.geo.clone() };
+    result.pixel_aspect_ratio = source.pixel_aspect_ratio;
+    result.source_frame = source.source_frame;
+    Ok(result)
+}
+
+/// Decode a TIFF file from an ArrayBuffer
+/// Returns TiffResult with image data and metadata
+#[wasm_bindgen]
+pub fn decode_tiff(data: &[u8]) -> Result<TiffResult, JsValue> {
+    decode_tiff_impl(data, true, 0, false, None)
+}
+
+/// Decode only the `width` x `height` rectangle at (`x`, `y`) of the first
+/// page, in display coordinates (after the Orientation tag is applied), with
+/// min/max computed over just that rectangle. For chunky 8/16/32/64-bit
+/// pages only the strips/tiles overlapping the rectangle are decompressed
+/// and only the rectangle is ever allocated (see
+/// `try_decode_streaming_chunks`); other layouts are decoded whole and
+/// cropped. Georeferencing and the tag dump still describe the full page.
+#[wasm_bindgen]
+pub fn decode_tiff_region(data: &[u8], x: u32, y: u32, width: u32, height: u32) -> Result<TiffResult, JsValue> {
+    decode_tiff_impl(data, true, 0, false, Some((x, y, width, height)))
+}
+
+/// Decode the first page like `decode_tiff` and copy its samples - the
+/// bytes `get_data_bytes` would return, laid out as `layout` describes -
+/// into `out`, a view of a caller-owned (e.g. pooled) buffer, so JS gets no
+/// new array per decode. The page is still decoded into wasm memory first;
+/// those buffers are dropped once copied, and the returned result keeps
+/// only the metadata and statistics (like after `take_data_bytes`, its
+/// sample accessors have nothing left to return). Fails, naming the size
+/// needed, when `out` is too small; only that many leading bytes are
+/// written.
+#[wasm_bindgen]
+pub fn decode_tiff_into(data: &[u8], out: &js_sys::Uint8Array) -> Result<TiffResult, JsValue> {
+    let mut result = decode_tiff_impl(data, true, 0, false, None)?;
+    result.write_data_bytes(out)?;
+    result.data = Rc::default();
+    result.data_f32 = Rc::default();
+    result.native_float = None;
+    result.data_f32_cache = OnceCell::new();
+    Ok(result)
+}
+
+/// Return the number of top-level image file directories (pages) in a TIFF.
+#[wasm_bindgen]
+pub fn tiff_page_count(data: &[u8]) -> Result<u32, JsValue> {
+    let data = gunzip_if_needed(data)?;
+    let data: &[u8] = &data;
+    let mut decoder = Decoder::new(Cursor::new(data))
+        .map_err(|e| TiffError::from_tiff("Failed to create decoder", e))?;
+    let mut count = 1u32;
+    while decoder.more_images() {
+        decoder.next_image()
+            .map_err(|e| TiffError::from_tiff("Failed to enumerate TIFF pages", e))?;
+        count = count.saturating_add(1);
+    }
+    Ok(count)
+}
+
+/// Describe every top-level page of a TIFF as a JSON array, so multi-page
+/// files whose pages differ in size or type (e.g. full resolution +
+/// thumbnail + slide label) need not be assumed uniform. Each entry is
+/// `{"index","width","height","channels","bits_per_sample","sample_format",
+/// "photometric","compression","tiled","subfile_type","role"}`, where `role`
+/// names the NewSubfileType (254) flags: "reduced" (a lower-resolution copy
+/// of another page), "mask" (a transparency mask) or "page". Every page
+/// decodes on its own terms through `decode_tiff_page(data, index)`.
+#[wasm_bindgen]
+pub fn tiff_page_info(data: &[u8]) -> Result<String, JsValue> {
+    use tiff::tags::Tag;
+
+    let data = gunzip_if_needed(data)?;
+    let data: &[u8] = &data;
+    let mut decoder = Decoder::new(Cursor::new(data))
+        .map_err(|e| TiffError::from_tiff("Failed to create decoder", e))?;
+    let mut pages = Vec::new();
+    loop {
+        let (width, height) = decoder.dimensions()
+            .map_err(|e| JsValue::from_str(&format!("Page {}: failed to get dimensions: {}", pages.len(), e)))?;
+        let bits_per_sample = decoder.get_tag_u32_vec(Tag::BitsPerSample).ok()
+            .and_then(|bits| bits.first().copied())
+            .unwrap_or(1);
+        let subfile_type = decoder.get_tag_u32(Tag::NewSubfileType).unwrap_or(0);
+        let role = if subfile_type & 4 != 0 {
+            "mask"
+        } else if subfile_type & 1 != 0 {
+            "reduced"
+        } else {
+            "page"
+        };
+        pages.push(format!(
+            "{{\"index\":{},\"width\":{},\"height\":{},\"channels\":{},\"bits_per_sample\":{},\"sample_format\":{},\"photometric\":{},\"compression\":{},\"tiled\":{},\"subfile_type\":{},\"role\":\"{}\"}}",
+            pages.len(),
+            width,
+            height,
+            decoder.get_tag_u32(Tag::SamplesPerPixel).unwrap_or(1),
+            bits_per_sample,
+            decoder.get_tag_u32_vec(Tag::SampleFormat).ok().and_then(|formats| formats.first().copied()).unwrap_or(1),
+            decoder.get_tag_u32(Tag::PhotometricInterpretation).unwrap_or(1),
+            decoder.get_tag_u32(Tag::Compression).unwrap_or(1),
+            decoder.get_tag_u32(Tag::TileWidth).is_ok(),
+            subfile_type,
+            role
+        ));
+        if !decoder.more_images() {
+            break;
+        }
+        decoder.next_image()
+            .map_err(|e| TiffError::from_tiff("Failed to enumerate TIFF pages", e))?;
+    }
+    Ok(format!("[{}]", pages.join(",")))
+}
+
+/// The overview pyramid of a (Cloud Optimized) GeoTIFF as a JSON array of
+/// `{"level","page","width","height","scale"}`, for picking the cheapest
+/// level that still covers the current zoom: level 0 is the full-resolution
+/// image and levels 1.. its reduced-resolution copies (`overview_levels`),
+/// largest first; `scale` is the full width over the level's width. A file
+/// without overviews lists level 0 only. Decode a level with
+/// `decode_overview`, or its `page` with `decode_tiff_page`.
+#[wasm_bindgen]
+pub fn tiff_overviews(data: &[u8]) -> Result<String, JsValue> {
+    let data = gunzip_if_needed(data)?;
+    Ok(overview_levels_json(&overview_levels(&page_subfile_layout(&data)?)))
+}
+
+/// Decode overview `level` of `tiff_overviews` with min/max statistics,
+/// like `decode_tiff_page` on that level's page.
+#[wasm_bindgen]
+pub fn decode_overview(data: &[u8], level: u32) -> Result<TiffResult, JsValue> {
+    let gunzipped = gunzip_if_needed(data)?;
+    let levels = overview_levels(&page_subfile_layout(&gunzipped)?);
+    let (page, _, _) = overview_level(&levels, level)?;
+    decode_tiff_impl(&gunzipped, true, page, false, None)
+}
+
+/// (width, height, NewSubfileType) of every top-level page, as stored.
+fn page_subfile_layout(data: &[u8]) -> Result<Vec<(u32, u32, u32)>, JsValue> {
+    let mut decoder = Decoder::new(Cursor::new(data))
+        .map_err(|e| TiffError::from_tiff("Failed to create decoder", e))?;
+    let mut pages = Vec::new();
+    loop {
+        let (width, height) = decoder.dimensions()
+            .map_err(|e| JsValue::from_str(&format!("Page {}: failed to get dimensions: {}", pages.len(), e)))?;
+        pages.push((width, height, decoder.get_tag_u32(tiff::tags::Tag::NewSubfileType).unwrap_or(0)));
+        if !decoder.more_images() {
+            break;
+        }
+        decoder.next_image()
+            .map_err(|e| TiffError::from_tiff("Failed to enumerate TIFF pages", e))?;
+    }
+    Ok(pages)
+}
+
+/// (page, width, height) of every overview level, from each page's
+/// (width, height, NewSubfileType): the first page that is neither reduced
+/// (bit 0) nor a mask (bit 2) - page 0 if every page is flagged - then each
+/// reduced, non-mask page smaller than it, largest first. Masks and the
+/// other pages of an ordinary multi-page file are not levels.
+fn overview_levels(pages: &[(u32, u32, u32)]) -> Vec<(u32, u32, u32)> {
+    let Some(full) = pages.iter().position(|&(_, _, subfile)| subfile & 5 == 0).or((!pages.is_empty()).then_some(0)) else {
+        return Vec::new();
+    };
+    let (full_width, full_height, _) = pages[full];
+    let mut levels: Vec<(u32, u32, u32)> = pages
+        .iter()
+        .enumerate()
+        .filter(|&(_, &(width, height, subfile))| {
+            subfile & 5 == 1 && width <= full_width && height <= full_height && (width, height) != (full_width, full_height)
+        })
+        .map(|(page, &(width, height, _))| (page as u32, width, height))
+        .collect();
+    levels.sort_by_key(|&(page, width, _)| (std::cmp::Reverse(width), page));
+    levels.insert(0, (full as u32, full_width, full_height));
+    levels
+}
+
+/// Overview `level` of `levels`, or an out-of-range error.
+fn overview_level(levels: &[(u32, u32, u32)], level: u32) -> Result<(u32, u32, u32), JsValue> {
+    levels.get(level as usize).copied().ok_or_else(|| {
+        JsValue::from_str(&format!("Overview level {} is out of range (only {} level(s))", level, levels.len()))
+    })
+}
+
+fn overview_levels_json(levels: &[(u32, u32, u32)]) -> String {
+    let full_width = levels.first().map_or(1, |&(_, width, _)| width.max(1));
+    let entries: Vec<String> = levels
+        .iter()
+        .enumerate()
+        .map(|(level, &(page, width, height))| {
+            format!(
+                "{{\"level\":{},\"page\":{},\"width\":{},\"height\":{},\"scale\":{}}}",
+                level, page, width, height, json_f64(full_width as f64 / width.max(1) as f64)
+            )
+        })
+        .collect();
+    format!("[{}]", entries.join(","))
+}
+
+/// Pages `TiffDocument` keeps decoded by default, and the memory they may
+/// take (see `TiffDocument::set_page_cache_byte_budget`).
+const PAGE_CACHE_CAPACITY: usize = 4;
+const PAGE_CACHE_BYTE_BUDGET: usize = 256 << 20;
+
+/// A TIFF buffer opened once for repeated use. The free functions
+/// (`decode_tiff_page`, `tiff_page_count`, ...) each gunzip the input and
+/// walk the IFD chain again; a viewer that switches pages, re-reads tags
+/// and re-takes stats does that work here only at construction: the
+/// (decompressed) bytes are kept, every page's dimensions are read in one
+/// walk, and each page's tag dump is built on first request and cached.
+/// The most recently decoded pages are kept too, so paging back and forth
+/// copies them instead of decoding again.
+#[wasm_bindgen]
+pub struct TiffDocument {
+    data: Vec<u8>,
+    // Display (post-Orientation) width/height of every top-level page.
+    dimensions: Vec<(u32, u32)>,
+    // `all_tags_json` per page, filled lazily by `metadata`/`decode_page`.
+    metadata: Vec<OnceCell<String>>,
+    // Decoded pages, untouched by what callers do with their copies.
+    pages: RefCell<ChunkLru<TiffResult>>,
+    page_hits: Cell<u64>,
+    page_misses: Cell<u64>,
+}
+
+#[wasm_bindgen]
+impl TiffDocument {
+    /// Parse the header and every top-level IFD of `data` (gzip-wrapped
+    /// files are inflated once, here).
+    #[wasm_bindgen(constructor)]
+    pub fn new(data: Vec<u8>) -> Result<TiffDocument, JsValue> {
+        use tiff::tags::Tag;
+
+        #[cfg(feature = "console_error_panic_hook")]
+        console_error_panic_hook::set_once();
+
+        let data = match gunzip_if_needed(&data)? {
+            Cow::Owned(inflated) => inflated,
+            Cow::Borrowed(_) => data,
+        };
+        let mut dimensions = Vec::new();
+        {
+            let mut decoder = Decoder::new(Cursor::new(&data[..]))
+                .map_err(|e| TiffError::from_tiff("Failed to create decoder", e))?;
+            loop {
+                let (width, height) = decoder.dimensions()
+                    .map_err(|e| JsValue::from_str(&format!("Page {}: failed to get dimensions: {}", dimensions.len(), e)))?;
+                let transposed = TiffOrientation::from_tag(decoder.get_tag_u32(Tag::Orientation).unwrap_or(1)).transposes();
+                dimensions.push(if transposed { (height, width) } else { (width, height) });
+                if !decoder.more_images() {
+                    break;
+                }
+                decoder.next_image()
+                    .map_err(|e| TiffError::from_tiff("Failed to enumerate TIFF pages", e))?;
+            }
+        }
+        let metadata = dimensions.iter().map(|_| OnceCell::new()).collect();
+        Ok(TiffDocument {
+            data,
+            dimensions,
+            metadata,
+            pages: RefCell::new(ChunkLru::new(PAGE_CACHE_CAPACITY, PAGE_CACHE_BYTE_BUDGET)),
+            page_hits: Cell::new(0),
+            page_misses: Cell::new(0),
+        })
+    }
+
+    /// Number of top-level pages.
+    #[wasm_bindgen]
+    pub fn page_count(&self) -> u32 {
+        self.dimensions.len() as u32
+    }
+
+    /// `[width, height]` of page `page_index` as `decode_page` returns it
+    /// (swapped for transposing orientations).
+    #[wasm_bindgen]
+    pub fn dimensions(&self, page_index: u32) -> Result<Vec<u32>, JsValue> {
+        let (width, height) = self.page(page_index)?;
+        Ok(vec![width, height])
+    }
+
+    /// Every tag of page `page_index`, in the shape of
+    /// `TiffResult.all_tags_json`; built once per page.
+    #[wasm_bindgen]
+    pub fn metadata(&self, page_index: u32) -> Result<String, JsValue> {
+        self.page(page_index)?;
+        Ok(self.metadata[page_index as usize]
+            .get_or_init(|| extract_page_tags_json(&self.data, page_index))
+            .clone())
+    }
+
+    /// Decode page `page_index` with min/max statistics, like
+    /// `decode_tiff_page`, from the bytes held by the document. A page
+    /// still in the page cache is returned sharing its planes with the
+    /// cached copy instead; either way the caller owns its result and may
+    /// take its data (copied while still shared) or dispose it.
+    #[wasm_bindgen]
+    pub fn decode_page(&self, page_index: u32) -> Result<TiffResult, JsValue> {
+        self.page(page_index)?;
+        if let Some(cached) = self.pages.borrow_mut().get(page_index) {
+            self.page_hits.set(self.page_hits.get() + 1);
+            return Ok(cached.clone());
+        }
+        self.page_misses.set(self.page_misses.get() + 1);
+        let result = decode_tiff_impl(&self.data, true, page_index, false, None)?;
+        let _ = self.metadata[page_index as usize].set(result.all_tags_json.clone());
+        self.pages.borrow_mut().insert(page_index, result.clone(), result.retained_bytes());
+        Ok(result)
+    }
+
+    /// Per-pixel difference of two pages of a temporal stack, page `j` minus
+    /// page `i` in their f32 views, as a float32 result with min/max taken,
+    /// so change detection between two time steps runs here instead of on
+    /// two exported frames: its `summary`, `histograms` and
+    /// `compute_histogram` give the statistics of the change. Both pages
+    /// must have the same dimensions and channel count; every channel is
+    /// differenced, and a pixel where either sample is nodata is NaN. The
+    /// georeferencing of page `i` is kept (without its nodata value). Both
+    /// pages go through the page cache.
+    #[wasm_bindgen]
+    pub fn diff_pages(&self, i: u32, j: u32) -> Result<TiffResult, JsValue> {
+        let (before, after) = (self.decode_page(i)?, self.decode_page(j)?);
+        if (before.width, before.height, before.channels) != (after.width, after.height, after.channels) {
+            return Err(JsValue::from_str(&format!(
+                "Page difference: pages {} and {} differ in shape ({}x{}x{} vs {}x{}x{})",
+                i, j, before.width, before.height, before.channels, after.width, after.height, after.channels
+            )));
+        }
+        before.check_band(0)?;
+        after.check_band(0)?;
+        let (before_nodata, after_nodata) = (before.nodata_matcher(), after.nodata_matcher());
+        let difference = before
+            .samples_f32()
+            .iter()
+            .zip(after.samples_f32())
+            .map(|(&a, &b)| if before_nodata(a) || after_nodata(b) { f32::NAN } else { b - a })
+            .collect();
+        let mut result =
+            TiffResult::from_decoded(before.width, before.height, before.channels, DecodingResult::F32(difference), String::new());
+        result.geo = GeoInfo { nodata: None, ..before.geo.clone() };
+        result.pixel_aspect_ratio = before.pixel_aspect_ratio;
+        result.source_frame = before.source_frame;
+        Ok(result)
+    }
+
+    /// Change how many decoded pages are kept; evicts the least recently
+    /// used ones beyond the new capacity. 0 disables the page cache.
+    /// Defaults to 4.
+    #[wasm_bindgen]
+    pub fn set_page_cache_capacity(&self, capacity: u32) {
+        self.pages.borrow_mut().set_capacity(capacity as usize);
+    }
+
+    /// Cap the memory the cached pages may hold (their sample buffers), on
+    /// top of the page-count capacity; a page larger than the budget is
+    /// returned but not kept. 0 disables the page cache. Defaults to
+    /// 256 MiB.
+    #[wasm_bindgen]
+    pub fn set_page_cache_byte_budget(&self, bytes: f64) {
+        let bytes = if bytes.is_finite() && bytes > 0.0 { bytes.min(usize::MAX as f64) as usize } else { 0 };
+        self.pages.borrow_mut().set_byte_budget(bytes);
+    }
+
+    /// Page cache state as JSON `{"pages":[...],"bytes","capacity",
+    /// "byte_budget","hits","misses","evictions"}`: the cached page indices
+    /// most recently used first, the bytes they hold, the limits, and how
+    /// many `decode_page` calls were served from the cache, decoded, and how
+    /// many pages were dropped to stay within the limits.
+    #[wasm_bindgen]
+    pub fn page_cache_stats(&self) -> String {
+        let pages = self.pages.borrow();
+        let indices: Vec<String> = pages.entries.iter().map(|(page, _, _)| page.to_string()).collect();
+        format!(
+            "{{\"pages\":[{}],\"bytes\":{},\"capacity\":{},\"byte_budget\":{},\"hits\":{},\"misses\":{},\"evictions\":{}}}",
+            indices.join(","),
+            pages.bytes(),
+            pages.capacity,
+            pages.byte_budget(),
+            self.page_hits.get(),
+            self.page_misses.get(),
+            pages.evictions
+        )
+    }
+
+    /// Drop every cached page; the statistics keep counting.
+    #[wasm_bindgen]
+    pub fn clear_page_cache(&self) {
+        self.pages.borrow_mut().clear();
+    }
+}
+
+impl TiffDocument {
+    /// Dimensions of page `page_index`, or an out-of-range error.
+    fn page(&self, page_index: u32) -> Result<(u32, u32), JsValue> {
+        self.dimensions.get(page_index as usize).copied().ok_or_else(|| {
+            JsValue::from_str(&format!(
+                "TIFF page index {} is out of range (only {} page(s))",
+                page_index,
+                self.dimensions.len()
+            ))
+        })
+    }
+}
+
+/// A TIFF read through a JS callback instead of a byte slice, for files too
+/// big to load into memory (a multi-gigabyte `File`/`Blob`). `read(offset,
+/// length)` must synchronously return a `Uint8Array` holding exactly those
+/// bytes of the file - e.g. `FileReaderSync.readAsArrayBuffer(file.slice(
+/// offset, offset + length))` in a worker, or `fs.readSync` in Node - and
+/// is only ever asked for ranges inside `length`. Construction fetches the
+/// header and walks the IFD chain; a page's out-of-line tag values are
+/// fetched when it is decoded or opened, and its strips/tiles as they are
+/// needed: all of them for `decode_page`, only those a read touches for the
+/// `TiffFile` that `open_page` returns. Gzipped TIFFs can't be read this way.
+#[wasm_bindgen]
+pub struct TiffStream {
+    source: Rc<RangeSource>,
+    little_endian: bool,
+    bigtiff: bool,
+    // File offset of every top-level IFD, in page order.
+    ifd_offsets: Vec<u64>,
+}
+
+#[wasm_bindgen]
+impl TiffStream {
+    /// Read the header and every top-level IFD of the `length`-byte file
+    /// behind `read`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(read: js_sys::Function, length: f64) -> Result<TiffStream, JsValue> {
+        #[cfg(feature = "console_error_panic_hook")]
+        console_error_panic_hook::set_once();
+
+        if !(length.is_finite() && length >= 0.0) {
+            return Err(JsValue::from_str(&format!("TiffStream: invalid file length {}", length)));
+        }
+        let fetch = move |offset: u64, length: u64| -> Result<Vec<u8>, JsValue> {
+            let bytes = read.call2(&JsValue::NULL, &JsValue::from_f64(offset as f64), &JsValue::from_f64(length as f64))?;
+            Ok(js_sys::Uint8Array::new(&bytes).to_vec())
+        };
+        TiffStream::from_source(RangeSource { read: Box::new(fetch), length: length as u64, bytes_read: Cell::new(0) })
+    }
+
+    /// Number of top-level pages.
+    #[wasm_bindgen]
+    pub fn page_count(&self) -> u32 {
+        self.ifd_offsets.len() as u32
+    }
+
+    /// Length of the file, as passed to the constructor.
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> f64 {
+        self.source.length as f64
+    }
+
+    /// Total bytes fetched through `read` so far, by this stream and the
+    /// `TiffFile`s it opened.
+    #[wasm_bindgen(getter)]
+    pub fn bytes_read(&self) -> f64 {
+        self.source.bytes_read.get() as f64
+    }
+
+    /// The overview levels of the file, as `tiff_overviews` lists them,
+    /// read from the IFDs fetched at construction. Decode a level's `page`
+    /// with `decode_page` or `open_page`.
+    #[wasm_bindgen]
+    pub fn overviews(&self) -> Result<String, JsValue> {
+        Ok(overview_levels_json(&overview_levels(&self.page_subfile_layout()?)))
+    }
+
+    /// Decode page `page_index` with min/max statistics, like
+    /// `decode_tiff_page`. Only that page's tag values and strips/tiles are
+    /// fetched, into a compact single-page copy (`stream_page_copy`), so its
+    /// tag dump reports the copy's strip/tile offsets and leaves out
+    /// sub-IFDs (Exif, GPS, SubIFDs).
+    #[wasm_bindgen]
+    pub fn decode_page(&self, page_index: u32) -> Result<TiffResult, JsValue> {
+        let (mut data, ranges) = stream_page_copy(&self.source, self.little_endian, self.bigtiff, self.ifd(page_index)?, false)?;
+        for (offset, count) in ranges {
+            if !is_sparse_chunk(offset, count) {
+                data.extend_from_slice(&self.source.read(offset, count)?);
+            }
+        }
+        decode_tiff_impl(&data, true, 0, false, None)
+    }
+
+    /// Open page `page_index` as a `TiffFile` whose strips/tiles are fetched
+    /// through `read` when a read first needs them (and then cached
+    /// decoded, like any `TiffFile`), so only the viewed part of the page
+    /// is ever transferred. The `TiffFile` keeps this stream's source.
+    #[wasm_bindgen]
+    pub fn open_page(&self, page_index: u32) -> Result<TiffFile, JsValue> {
+        let (header, ranges) = stream_page_copy(&self.source, self.little_endian, self.bigtiff, self.ifd(page_index)?, true)?;
+        let stream = StreamedChunks { source: Rc::clone(&self.source), ranges, header_len: header.len(), reverse_bits: false };
+        TiffFile::open(header, 0, Some(stream))
+    }
+}
+
+impl TiffStream {
+    fn from_source(source: RangeSource) -> Result<TiffStream, JsValue> {
+        let header = source.read(0, source.length.min(16))?;
+        if header.starts_with(&[0x1f, 0x8b]) {
+            return Err(JsValue::from_str("TiffStream: gzipped TIFFs can't be read in ranges; inflate the file first"));
+        }
+        let little_endian = tiff_is_little_endian(&header).ok_or_else(|| JsValue::from_str("TiffStream: not a TIFF header"))?;
+        let reader = TiffByteReader { data: &header, little_endian, bigtiff: header[2] == 43 || header[3] == 43 };
+        let first_ifd = if reader.bigtiff { reader.uint(8, 8) } else { reader.uint(4, 4) };
+        let mut next = first_ifd.map_err(|_| JsValue::from_str("TiffStream: the file ends inside the TIFF header"))?;
+
+        let mut ifd_offsets = Vec::new();
+        let mut visited = std::collections::HashSet::new();
+        while next != 0 && visited.insert(next) {
+            let table = stream_ifd_table(&source, little_endian, reader.bigtiff, next)?;
+            let table_reader = TiffByteReader { data: &table, little_endian, bigtiff: reader.bigtiff };
+            let next_size = if reader.bigtiff { 8 } else { 4 };
+            ifd_offsets.push(next);
+            next = table_reader.uint(table.len() as u64 - next_size, next_size).unwrap_or(0);
+        }
+        if ifd_offsets.is_empty() {
+            return Err(JsValue::from_str("TiffStream: the file has no image directory"));
+        }
+        Ok(TiffStream { bigtiff: reader.bigtiff, source: Rc::new(source), little_endian, ifd_offsets })
+    }
+
+    /// (width, height, NewSubfileType) of every page, like
+    /// `page_subfile_layout`; all three are inline in the IFD entries.
+    fn page_subfile_layout(&self) -> Result<Vec<(u32, u32, u32)>, JsValue> {
+        let (count_size, entry_size) = if self.bigtiff { (8, 20) } else { (2, 12) };
+        let mut pages = Vec::new();
+        for &offset in &self.ifd_offsets {
+            let table = stream_ifd_table(&self.source, self.little_endian, self.bigtiff, offset)?;
+            let reader = TiffByteReader { data: &table, little_endian: self.little_endian, bigtiff: self.bigtiff };
+            let mut values = [0u64; 3];
+            for i in 0..reader.uint(0, count_size).unwrap_or(0) {
+                let entry = count_size + i * entry_size;
+                let slot = match reader.uint(entry, 2) {
+                    Ok(256) => 0,
+                    Ok(257) => 1,
+                    Ok(254) => 2,
+                    _ => continue,
+                };
+                values[slot] = reader.entry_values(entry).ok().and_then(|v| v.first().copied()).unwrap_or(0);
+            }
+            pages.push((values[0] as u32, values[1] as u32, values[2] as u32));
+        }
+        Ok(pages)
+    }
+
+    /// File offset of page `page_index`'s IFD, or an out-of-range error.
+    fn ifd(&self, page_index: u32) -> Result<u64, JsValue> {
+        self.ifd_offsets.get(page_index as usize).copied().ok_or_else(|| {
+            JsValue::from_str(&format!(
+                "TIFF page index {} is out of range (only {} page(s))",
+                page_index,
+                self.ifd_offsets.len()
+            ))
+        })
+    }
+}
+
+/// Byte ranges of a file that isn't held in memory (`TiffStream`).
+struct RangeSource {
+    read: Box<dyn Fn(u64, u64) -> Result<Vec<u8>, JsValue>>,
+    length: u64,
+    bytes_read: Cell<u64>,
+}
+
+impl RangeSource {
+    /// `length` bytes at `offset`, which must lie inside the file.
+    fn read(&self, offset: u64, length: u64) -> Result<Vec<u8>, JsValue> {
+        if offset.checked_add(length).is_none_or(|end| end > self.length) {
+            return Err(JsValue::from_str(&format!(
+                "TiffStream: {} bytes at offset {} run past the end of the {}-byte file",
+                length, offset, self.length
+            )));
+        }
+        let bytes = (self.read)(offset, length)?;
+        if bytes.len() as u64 != length {
+            return Err(JsValue::from_str(&format!(
+                "TiffStream: read({}, {}) returned {} bytes",
+                offset, length, bytes.len()
+            )));
+        }
+        self.bytes_read.set(self.bytes_read.get() + length);
+        Ok(bytes)
+    }
+}
+
+/// (offset, byte count) of a strip/tile in a `TiffStream` file.
+type ChunkRange = (u64, u64);
+
+/// Strip/tile access of a `TiffFile` opened by `TiffStream::open_page`: its
+/// decoder reads the page's compact copy (`stream_page_copy` with a shared
+/// chunk slot), and each chunk read fetches the chunk's original bytes into
+/// that slot at `header_len` first.
+struct StreamedChunks {
+    source: Rc<RangeSource>,
+    ranges: Vec<ChunkRange>,
+    header_len: usize,
+    // FillOrder 2: the fetched bytes are bit-reversed the way
+    // `fill_order_reversed` does for in-memory files.
+    reverse_bits: bool,
+}
+
+/// The entry table of the IFD at `offset` (entry count, entries and the
+/// next-IFD offset), fetched in two reads.
+fn stream_ifd_table(source: &RangeSource, little_endian: bool, bigtiff: bool, offset: u64) -> Result<Vec<u8>, JsValue> {
+    let (count_size, entry_size, next_size) = if bigtiff { (8, 20, 8) } else { (2, 12, 4) };
+    let mut table = source.read(offset, count_size)?;
+    let entry_count = TiffByteReader { data: &table, little_endian, bigtiff }.uint(0, count_size).unwrap_or(0);
+    let rest = entry_count.saturating_mul(entry_size).saturating_add(next_size);
+    table.extend_from_slice(&source.read(offset + count_size, rest)?);
+    Ok(table)
+}
+
+/// Rebuild the IFD at `ifd_offset` of a `TiffStream` file as a stand-alone
+/// single-page TIFF in the same byte order: header, the IFD and its
+/// out-of-line tag values (fetched here), and nothing else. Returns that
+/// and the original (offset, byte count) of every strip/tile. The copy's
+/// offsets point past its end, where the caller appends the fetched chunks
+/// in order, skipping `is_sparse_chunk` ones (which keep offset 0); with
+/// `shared_chunk_slot` they all point at the same place, where a `TiffFile`
+/// puts whichever chunk it is decoding. Sub-IFD pointers (SubIFDs, Exif,
+/// GPS, Interoperability) would dangle and are dropped.
+fn stream_page_copy(
+    source: &RangeSource,
+    little_endian: bool,
+    bigtiff: bool,
+    ifd_offset: u64,
+    shared_chunk_slot: bool,
+) -> Result<(Vec<u8>, Vec<ChunkRange>), JsValue> {
+    let (count_size, entry_size, field_size) = if bigtiff { (8u64, 20u64, 8u64) } else { (2, 12, 4) };
+    let table = stream_ifd_table(source, little_endian, bigtiff, ifd_offset)?;
+    let reader = TiffByteReader { data: &table, little_endian, bigtiff };
+    let cut_off = |_| JsValue::from_str(&format!("TiffStream: IFD at offset {} is cut off", ifd_offset));
+
+    // (tag, type, count, value bytes) of every entry kept.
+    let mut entries: Vec<(u64, u64, u64, Vec<u8>)> = Vec::new();
+    for i in 0..reader.uint(0, count_size).map_err(cut_off)? {
+        let entry = count_size + i * entry_size;
+        let tag = reader.uint(entry, 2).map_err(cut_off)?;
+        let type_id = reader.uint(entry + 2, 2).map_err(cut_off)?;
+        let count = reader.uint(entry + 4, field_size).map_err(cut_off)?;
+        let value_size = match type_id {
+            1 | 2 | 6 | 7 => 1,
+            3 | 8 => 2,
+            4 | 9 | 11 => 4,
+            5 | 10 | 12 | 16 | 17 => 8,
+            _ => continue,
+        };
+        if matches!(tag, 330 | 34665 | 34853 | 40965) {
+            continue;
+        }
+        let field = entry + 4 + field_size;
+        let size = count.saturating_mul(value_size);
+        let value = if size <= field_size {
+            table[field as usize..(field + size) as usize].to_vec()
+        } else {
+            source.read(reader.uint(field, field_size).map_err(cut_off)?, size)?
+        };
+        entries.push((tag, type_id, count, value));
+    }
+
+    let values_of = |tag: u64| -> Option<Vec<u64>> {
+        let (_, type_id, count, value) = entries.iter().find(|entry| entry.0 == tag)?;
+        let size = match type_id { 3 => 2, 4 => 4, 16 => 8, _ => return None };
+        let value_reader = TiffByteReader { data: value, little_endian, bigtiff };
+        (0..*count).map(|i| value_reader.uint(i * size, size).ok()).collect()
+    };
+    let tiled = entries.iter().any(|entry| entry.0 == 324);
+    let (offsets_tag, counts_tag) = if tiled { (324, 325) } else { (273, 279) };
+    let offsets = values_of(offsets_tag)
+        .ok_or_else(|| JsValue::from_str(&format!("TiffStream: IFD at offset {} has no usable strip/tile offsets", ifd_offset)))?;
+    let counts = values_of(counts_tag).unwrap_or_default();
+    if counts.len() != offsets.len() {
+        return Err(JsValue::from_str(&format!("TiffStream: {} offsets but {} byte counts", offsets.len(), counts.len())));
+    }
+    let ranges: Vec<ChunkRange> = offsets.into_iter().zip(counts).collect();
+
+    let put = |out: &mut Vec<u8>, value: u64, size: u64| {
+        let bytes = if little_endian { value.to_le_bytes() } else { value.to_be_bytes() };
+        let bytes = if little_endian { &bytes[..size as usize] } else { &bytes[8 - size as usize..] };
+        out.extend_from_slice(bytes);
+    };
+    let header_len: u64 = if bigtiff { 16 } else { 8 };
+    let offsets_type = if bigtiff { 16 } else { 4 };
+    let ifd_len = count_size + entries.len() as u64 * entry_size + field_size;
+    // Out-of-line values follow the IFD (word-aligned), then the chunks.
+    // The rewritten offsets are LONG (LONG8 in BigTIFF), one field each.
+    let value_len = |&(tag, _, count, ref value): &(u64, u64, u64, Vec<u8>)| -> u64 {
+        let size = if tag == offsets_tag { count * field_size } else { value.len() as u64 };
+        if size <= field_size { 0 } else { size.next_multiple_of(2) }
+    };
+    let chunks_start = header_len + ifd_len + entries.iter().map(value_len).sum::<u64>();
+    let mut next_chunk = chunks_start;
+    let mut chunk_offsets = Vec::with_capacity(ranges.len());
+    for &(offset, count) in &ranges {
+        if is_sparse_chunk(offset, count) {
+            chunk_offsets.push(0);
+            continue;
+        }
+        chunk_offsets.push(next_chunk);
+        if !shared_chunk_slot {
+            next_chunk += count;
+        }
+    }
+    if !bigtiff && next_chunk > u32::MAX as u64 {
+        return Err(JsValue::from_str("TiffStream: page is too large for a classic TIFF copy; use open_page"));
+    }
+
+    let mut out = Vec::with_capacity(chunks_start as usize);
+    out.extend_from_slice(if little_endian { b"II" } else { b"MM" });
+    if bigtiff {
+        put(&mut out, 43, 2);
+        put(&mut out, 8, 2);
+        put(&mut out, 0, 2);
+    } else {
+        put(&mut out, 42, 2);
+    }
+    put(&mut out, header_len, field_size);
+    put(&mut out, entries.len() as u64, count_size);
+    let mut values = Vec::new();
+    let mut next_value = header_len + ifd_len;
+    for (tag, type_id, count, value) in &entries {
+        let (type_id, value) = if *tag == offsets_tag {
+            let mut rewritten = Vec::new();
+            for &offset in &chunk_offsets {
+                put(&mut rewritten, offset, field_size);
+            }
+            (offsets_type, rewritten)
+        } else {
+            (*type_id, value.clone())
+        };
+        put(&mut out, *tag, 2);
+        put(&mut out, type_id, 2);
+        put(&mut out, *count, field_size);
+        if value.len() as u64 <= field_size {
+            out.extend_from_slice(&value);
+            out.resize(out.len() + (field_size as usize - value.len()), 0);
+        } else {
+            put(&mut out, next_value, field_size);
+            values.extend_from_slice(&value);
+            values.resize(values.len().next_multiple_of(2), 0);
+            next_value = header_len + ifd_len + values.len() as u64;
+        }
+    }
+    put(&mut out, 0, field_size);
+    out.extend_from_slice(&values);
+    debug_assert_eq!(out.len() as u64, chunks_start);
+    Ok((out, ranges))
+}
+
+/// Smallest range a `RemoteTiffReader` asks for when a header, IFD or tag
+/// value it needs hasn't been fetched: one round trip then usually brings in
+/// the header and the first IFDs together.
+const REMOTE_MIN_FETCH: u64 = 16 * 1024;
+
+/// Strips/tiles closer together than this are fetched in one request (the
+/// gap is downloaded and thrown away), since a round trip costs more than
+/// a few extra kilobytes.
+const REMOTE_MERGE_GAP: u64 = 64 * 1024;
+
+/// A TIFF on a server that answers HTTP range requests - a Cloud Optimized
+/// GeoTIFF, say - read without downloading the whole file. `fetch(offset,
+/// length)` returns a `Promise<Uint8Array>` of those bytes, e.g.
+/// `fetch(url, { headers: { Range: \`bytes=${offset}-${offset + length - 1}\` } })
+/// .then(r => r.arrayBuffer()).then(b => new Uint8Array(b))`, and `length`
+/// is the file size (from a HEAD request's Content-Length or a ranged
+/// response's Content-Range). Headers, IFDs and tag values are fetched in
+/// `REMOTE_MIN_FETCH` blocks and kept; strips/tiles are fetched per call,
+/// neighbouring ones merged into one request (`REMOTE_MERGE_GAP`), and
+/// dropped once decoded. Decoding itself is `TiffStream`'s, over the
+/// fetched ranges.
+#[wasm_bindgen]
+pub struct RemoteTiffReader {
+    source: Rc<RemoteSource>,
+    stream: Rc<TiffStream>,
+    // Pages opened by `read_region`, kept so their chunk LRU is reused.
+    pages: Rc<RefCell<HashMap<u32, TiffFile>>>,
+}
+
+#[wasm_bindgen]
+impl RemoteTiffReader {
+    /// Fetch the header and walk every top-level IFD of the `length`-byte
+    /// file behind `fetch`.
+    pub async fn open(fetch: js_sys::Function, length: f64) -> Result<RemoteTiffReader, JsValue> {
+        #[cfg(feature = "console_error_panic_hook")]
+        console_error_panic_hook::set_once();
+
+        if !(length.is_finite() && length >= 0.0) {
+            return Err(JsValue::from_str(&format!("RemoteTiffReader: invalid file length {}", length)));
+        }
+        let source = Rc::new(RemoteSource {
+            fetch,
+            length: length as u64,
+            blocks: RefCell::new(Vec::new()),
+            missing: Cell::new(None),
+            request_count: Cell::new(0),
+            bytes_fetched: Cell::new(0),
+        });
+        let stream = source.retrying(|| TiffStream::from_source(source.range_source())).await?;
+        Ok(RemoteTiffReader { source, stream: Rc::new(stream), pages: Rc::new(RefCell::new(HashMap::new())) })
+    }
+
+    /// Number of top-level pages.
+    #[wasm_bindgen]
+    pub fn page_count(&self) -> u32 {
+        self.stream.page_count()
+    }
+
+    /// Length of the remote file, as passed to `open`.
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> f64 {
+        self.source.length as f64
+    }
+
+    /// The overview levels of the remote file, as `tiff_overviews` lists
+    /// them; no request is made (the IFDs were fetched by `open`). Pick the
+    /// level for the zoom and pass its `page` to `decode_page`/`read_region`.
+    #[wasm_bindgen]
+    pub fn overviews(&self) -> Result<String, JsValue> {
+        self.stream.overviews()
+    }
+
+    /// Number of `fetch` calls made so far.
+    #[wasm_bindgen(getter)]
+    pub fn request_count(&self) -> u32 {
+        self.source.request_count.get()
+    }
+
+    /// Total bytes requested through `fetch` so far.
+    #[wasm_bindgen(getter)]
+    pub fn bytes_fetched(&self) -> f64 {
+        self.source.bytes_fetched.get() as f64
+    }
+
+    /// Decode page `page_index` like `TiffStream::decode_page`, fetching its
+    /// tag values and then every strip/tile. Resolves to a `TiffResult`.
+    #[wasm_bindgen]
+    pub fn decode_page(&self, page_index: u32) -> js_sys::Promise {
+        let (source, stream) = (Rc::clone(&self.source), Rc::clone(&self.stream));
+        wasm_bindgen_futures::future_to_promise(async move {
+            let ifd = stream.ifd(page_index)?;
+            let (_, ranges) = source
+                .retrying(|| stream_page_copy(&stream.source, stream.little_endian, stream.bigtiff, ifd, false))
+                .await?;
+            let kept = source.blocks.borrow().len();
+            source.fetch_ranges(ranges).await?;
+            let result = source.retrying(|| stream.decode_page(page_index)).await;
+            source.blocks.borrow_mut().truncate(kept);
+            result.map(JsValue::from)
+        })
+    }
+
+    /// Read the `width` x `height` region at (`x`, `y`) of page
+    /// `page_index` like `TiffFile::read_region` (stored orientation, every
+    /// `step`-th pixel), fetching only the strips/tiles the region overlaps
+    /// that the page's chunk cache doesn't already hold. Resolves to a
+    /// `Float32Array` of interleaved samples.
+    #[wasm_bindgen]
+    pub fn read_region(&self, page_index: u32, x: u32, y: u32, width: u32, height: u32, step: u32) -> js_sys::Promise {
+        let (source, stream, pages) = (Rc::clone(&self.source), Rc::clone(&self.stream), Rc::clone(&self.pages));
+        wasm_bindgen_futures::future_to_promise(async move {
+            if !pages.borrow().contains_key(&page_index) {
+                let file = source.retrying(|| stream.open_page(page_index)).await?;
+                pages.borrow_mut().insert(page_index, file);
+            }
+            // Pages are never removed from `pages`, so the lookups can't miss.
+            let ranges = pages.borrow().get(&page_index).map(|file| file.region_chunk_ranges(x, y, width, height));
+            let kept = source.blocks.borrow().len();
+            source.fetch_ranges(ranges.unwrap_or_default()).await?;
+            let values = source
+                .retrying(|| match pages.borrow_mut().get_mut(&page_index) {
+                    Some(file) => file.read_region(x, y, width, height, step),
+                    None => Ok(Vec::new()),
+                })
+                .await;
+            source.blocks.borrow_mut().truncate(kept);
+            Ok(js_sys::Float32Array::from(&values?[..]).into())
+        })
+    }
+
+    /// Sample page `page_index` like `TiffFile::decode_strided`, fetching
+    /// only the strips/tiles a sampled pixel lands in, so a quick look at a
+    /// large remote file costs a few range requests instead of the whole
+    /// page. Resolves to a `TiffResult` in the stored sample type.
+    #[wasm_bindgen]
+    pub fn decode_strided(&self, page_index: u32, row_step: u32, col_step: u32) -> js_sys::Promise {
+        let (source, stream, pages) = (Rc::clone(&self.source), Rc::clone(&self.stream), Rc::clone(&self.pages));
+        wasm_bindgen_futures::future_to_promise(async move {
+            if !pages.borrow().contains_key(&page_index) {
+                let file = source.retrying(|| stream.open_page(page_index)).await?;
+                pages.borrow_mut().insert(page_index, file);
+            }
+            let ranges = pages.borrow().get(&page_index).map(|file| file.strided_chunk_ranges(row_step, col_step));
+            let kept = source.blocks.borrow().len();
+            source.fetch_ranges(ranges.unwrap_or_default()).await?;
+            let result = source
+                .retrying(|| match pages.borrow_mut().get_mut(&page_index) {
+                    Some(file) => file.decode_strided(row_step, col_step),
+                    None => Err(JsValue::from_str("RemoteTiffReader: page is not open")),
+                })
+                .await;
+            source.blocks.borrow_mut().truncate(kept);
+            result.map(JsValue::from)
+        })
+    }
+}
+
+/// The fetched byte ranges behind a `RemoteTiffReader`. Reads are served
+/// from `blocks` only, so the synchronous decode code can run over them; a
+/// read outside every block records the range in `missing` and fails, and
+/// `retrying` fetches it and runs the decode step again.
+struct RemoteSource {
+    fetch: js_sys::Function,
+    length: u64,
+    // (file offset, bytes), metadata first; strip/tile blocks come last and
+    // are truncated away after each call.
+    blocks: RefCell<Vec<(u64, Vec<u8>)>>,
+    missing: Cell<Option<ChunkRange>>,
+    request_count: Cell<u32>,
+    bytes_fetched: Cell<u64>,
+}
+
+impl RemoteSource {
+    /// A `RangeSource` reading from this source's fetched blocks.
+    fn range_source(self: &Rc<Self>) -> RangeSource {
+        let source = Rc::clone(self);
+        RangeSource { read: Box::new(move |offset, length| source.read(offset, length)), length: self.length, bytes_read: Cell::new(0) }
+    }
+
+    /// `length` bytes at `offset` from a fetched block, or the "not fetched"
+    /// error (with the range noted in `missing`).
+    fn read(&self, offset: u64, length: u64) -> Result<Vec<u8>, JsValue> {
+        let blocks = self.blocks.borrow();
+        let block = blocks.iter().find(|(start, bytes)| offset >= *start && offset + length <= start + bytes.len() as u64);
+        match block {
+            Some((start, bytes)) => Ok(bytes[(offset - start) as usize..(offset - start + length) as usize].to_vec()),
+            None => {
+                self.missing.set(Some((offset, length)));
+                Err(JsValue::from_str(&format!("RemoteTiffReader: {} bytes at offset {} have not been fetched", length, offset)))
+            }
+        }
+    }
+
+    /// Run `step`, fetching whatever range it failed to find (rounded up to
+    /// `REMOTE_MIN_FETCH`) and running it again, until it succeeds or fails
+    /// for another reason.
+    async fn retrying<T>(&self, mut step: impl FnMut() -> Result<T, JsValue>) -> Result<T, JsValue> {
+        loop {
+            self.missing.set(None);
+            let error = match step() {
+                Ok(value) => return Ok(value),
+                Err(error) => error,
+            };
+            let Some((offset, length)) = self.missing.take() else { return Err(error) };
+            let end = (offset + length.max(REMOTE_MIN_FETCH)).min(self.length);
+            self.fetch_ranges(vec![(offset, end - offset)]).await?;
+        }
+    }
+
+    /// Fetch `ranges` (sparse ones skipped) into `blocks`, merging ranges
+    /// less than `REMOTE_MERGE_GAP` apart; the requests run concurrently.
+    async fn fetch_ranges(&self, mut ranges: Vec<ChunkRange>) -> Result<(), JsValue> {
+        ranges.retain(|&(offset, count)| !is_sparse_chunk(offset, count));
+        ranges.sort_unstable();
+        let mut merged: Vec<ChunkRange> = Vec::new();
+        for (offset, count) in ranges {
+            let end = offset.saturating_add(count);
+            match merged.last_mut() {
+                Some((start, length)) if offset <= *start + *length + REMOTE_MERGE_GAP => {
+                    *length = end.max(*start + *length) - *start;
+                }
+                _ => merged.push((offset, count)),
+            }
+        }
+
+        let requests = js_sys::Array::new();
+        for &(offset, length) in &merged {
+            if offset.checked_add(length).is_none_or(|end| end > self.length) {
+                return Err(JsValue::from_str(&format!(
+                    "RemoteTiffReader: {} bytes at offset {} run past the end of the {}-byte file",
+                    length, offset, self.length
+                )));
+            }
+            let request = self.fetch.call2(&JsValue::NULL, &JsValue::from_f64(offset as f64), &JsValue::from_f64(length as f64))?;
+            requests.push(&js_sys::Promise::resolve(&request));
+            self.request_count.set(self.request_count.get() + 1);
+            self.bytes_fetched.set(self.bytes_fetched.get() + length);
+        }
+        let responses = wasm_bindgen_futures::JsFuture::from(js_sys::Promise::all(&requests)).await?;
+        let responses = js_sys::Array::from(&responses);
+        let mut blocks = self.blocks.borrow_mut();
+        for (index, &(offset, length)) in merged.iter().enumerate() {
+            let bytes = js_sys::Uint8Array::new(&responses.get(index as u32)).to_vec();
+            if bytes.len() as u64 != length {
+                return Err(JsValue::from_str(&format!(
+                    "RemoteTiffReader: fetch({}, {}) returned {} bytes (does the server support range requests?)",
+                    offset, length, bytes.len()
+                )));
+            }
+            blocks.push((offset, bytes));
+        }
+        Ok(())
+    }
+}
+
+/// Byte-level map of a TIFF for a JS layer that fetches ranges itself: where
+/// each top-level IFD sits and where its strips/tiles are, read straight from
+/// the header without decoding anything. `data` may be just a prefix of the
+/// file; when it ends before an IFD or one of its offset/byte-count arrays,
+/// the walk stops with `"complete":false` and `needed_bytes` set to the file
+/// length required to get further (fetch that much and call again). JSON:
+/// `{"little_endian","bigtiff","complete","needed_bytes","ifds":[{"index",
+/// "offset","size","next_ifd_offset","width","height","samples_per_pixel",
+/// "planar_configuration","tiled","chunk_width","chunk_height",
+/// "chunk_offsets":[...],"chunk_byte_counts":[...]}]}`, where `size` covers
+/// the entry table (out-of-line tag values live elsewhere) and chunks are in
+/// file order (all planes of plane 0 first for PlanarConfiguration 2). The
+/// bytes at `chunk_offsets[i]..+chunk_byte_counts[i]` are exactly what
+/// `decode_tiff_page` reads for that strip/tile.
+#[wasm_bindgen]
+pub fn tiff_byte_layout(data: &[u8]) -> Result<String, JsValue> {
+    let little_endian = tiff_is_little_endian(data)
+        .ok_or_else(|| JsValue::from_str("TIFF byte layout: not a TIFF header"))?;
+    let reader = TiffByteReader { data, little_endian, bigtiff: data[2] == 43 || data[3] == 43 };
+    let first_ifd = if reader.bigtiff { reader.uint(8, 8) } else { reader.uint(4, 4) };
+
+    let mut ifds = Vec::new();
+    let mut needed: Option<u64> = None;
+    let mut visited = std::collections::HashSet::new();
+    let mut next = match first_ifd {
+        Ok(offset) => offset,
+        Err(end) => {
+            needed = Some(end);
+            0
+        }
+    };
+    while next != 0 && visited.insert(next) {
+        match reader.ifd_layout(ifds.len(), next) {
+            Ok((json, following)) => {
+                ifds.push(json);
+                next = following;
+            }
+            Err(end) => {
+                needed = Some(end);
+                break;
+            }
+        }
+    }
+    Ok(format!(
+        "{{\"little_endian\":{},\"bigtiff\":{},\"complete\":{},\"needed_bytes\":{},\"ifds\":[{}]}}",
+        little_endian,
+        reader.bigtiff,
+        needed.is_none(),
+        needed.map_or("null".to_string(), |end| end.to_string()),
+        ifds.join(",")
+    ))
+}
+
+/// Bounds-checked reads for `tiff_byte_layout`, `get_all_tags` and
+/// `get_exif`. Every failed read reports the file length it would have
+/// needed (`Err(end)`).
+struct TiffByteReader<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+    bigtiff: bool,
+}
+
+impl TiffByteReader<'_> {
+    /// Unsigned integer of `size` (1, 2, 4 or 8) bytes at `offset`.
+    fn uint(&self, offset: u64, size: u64) -> Result<u64, u64> {
+        let end = offset.saturating_add(size);
+        let bytes = self.data.get(offset as usize..end as usize).ok_or(end)?;
+        let fold = |acc: u64, &b: &u8| (acc << 8) | b as u64;
+        Ok(if self.little_endian { bytes.iter().rev().fold(0, fold) } else { bytes.iter().fold(0, fold) })
+    }
+
+    /// The unsigned values of the IFD entry at `entry` (BYTE/SHORT/LONG/
+    /// LONG8/IFD types), inline or out of line.
+    fn entry_values(&self, entry: u64) -> Result<Vec<u64>, u64> {
+        let (count_size, field_size) = if self.bigtiff { (8, 8) } else { (4, 4) };
+        let type_id = self.uint(entry + 2, 2)?;
+        let count = self.uint(entry + 4, count_size)?;
+        let size = match type_id {
+            1 => 1,
+            3 => 2,
+            4 | 13 => 4,
+            16 | 18 => 8,
+            _ => return Ok(Vec::new()),
+        };
+        let field = entry + 4 + count_size;
+        let start = if count.saturating_mul(size) <= field_size { field } else { self.uint(field, field_size)? };
+        let end = start.saturating_add(count.saturating_mul(size));
+        if end > self.data.len() as u64 {
+            return Err(end);
+        }
+        (0..count).map(|i| self.uint(start + i * size, size)).collect()
+    }
+
+    /// Offset of page `page_index`'s IFD, following the chain from the
+    /// header; errors are prefixed with `context`.
+    fn page_ifd_offset(&self, page_index: u32, context: &str) -> Result<u64, JsValue> {
+        let (count_size, entry_size, next_size) = if self.bigtiff { (8, 20, 8) } else { (2, 12, 4) };
+        let truncated = |end: u64| self.truncated(context, end);
+        let out_of_range = |pages: u32| JsValue::from_str(&format!("{}: page {} is out of range (file has {} page(s))", context, page_index, pages));
+        let mut offset = if self.bigtiff { self.uint(8, 8) } else { self.uint(4, 4) }.map_err(truncated)?;
+        let mut visited = std::collections::HashSet::new();
+        for page in 0..page_index {
+            if offset == 0 || !visited.insert(offset) {
+                return Err(out_of_range(page));
+            }
+            let entry_count = self.uint(offset, count_size).map_err(truncated)?;
+            offset = self.uint((offset + count_size).saturating_add(entry_count.saturating_mul(entry_size)), next_size).map_err(truncated)?;
+        }
+        if offset == 0 || visited.contains(&offset) {
+            return Err(out_of_range(page_index));
+        }
+        Ok(offset)
+    }
+
+    /// The error for a read that needed the file to be `end` bytes long.
+    fn truncated(&self, context: &str, end: u64) -> JsValue {
+        JsValue::from_str(&format!("{}: directory runs past the end of the file ({} bytes, {} needed)", context, self.data.len(), end))
+    }
+
+    /// Position of each entry of the IFD at `offset`, by tag.
+    fn ifd_entries(&self, offset: u64) -> Result<HashMap<u16, u64>, u64> {
+        let (count_size, entry_size) = if self.bigtiff { (8, 20) } else { (2, 12) };
+        let entry_count = self.uint(offset, count_size)?;
+        let mut entries = HashMap::new();
+        for i in 0..entry_count {
+            let entry = offset + count_size + i * entry_size;
+            entries.entry(self.uint(entry, 2)? as u16).or_insert(entry);
+        }
+        Ok(entries)
+    }
+
+    /// Where the values (`size` bytes each) of the IFD entry at `entry`
+    /// start, and how many there are, or `None` if they lie outside the
+    /// file.
+    fn entry_span(&self, entry: u64, size: u64) -> Option<(u64, u64)> {
+        let (count_size, field_size) = if self.bigtiff { (8, 8) } else { (4, 4) };
+        let count = self.uint(entry + 4, count_size).ok()?;
+        let field = entry + 4 + count_size;
+        let start = if count.saturating_mul(size) <= field_size { field } else { self.uint(field, field_size).ok()? };
+        (start.saturating_add(count.saturating_mul(size)) <= self.data.len() as u64).then_some((start, count))
+    }
+
+    /// The text of an ASCII (or UNDEFINED, as Exif stores some strings)
+    /// entry, trimmed; `None` when empty or unreadable.
+    fn entry_text(&self, entry: u64) -> Option<String> {
+        if !matches!(self.uint(entry + 2, 2).ok()?, 2 | 7) {
+            return None;
+        }
+        let (start, count) = self.entry_span(entry, 1)?;
+        let bytes = &self.data[start as usize..(start + count.min(TAG_DUMP_MAX_VALUES)) as usize];
+        let text = String::from_utf8_lossy(bytes).trim_end_matches('\0').trim().to_string();
+        (!text.is_empty()).then_some(text)
+    }
+
+    /// The values of a numeric IFD entry as f64, (S)RATIONALs divided out;
+    /// `None` for text or unknown types and values outside the file.
+    fn entry_numbers(&self, entry: u64) -> Option<Vec<f64>> {
+        let type_id = self.uint(entry + 2, 2).ok()?;
+        let size = match type_id {
+            1 | 6 => 1,
+            3 | 8 => 2,
+            4 | 9 | 11 | 13 => 4,
+            5 | 10 | 12 | 16 | 17 | 18 => 8,
+            _ => return None,
+        };
+        let (start, count) = self.entry_span(entry, size)?;
+        let unsigned = |at: u64, width: u64| self.uint(at, width).unwrap_or(0);
+        let signed = |at: u64, width: u64| {
+            let bits = 64 - 8 * width as u32;
+            (((unsigned(at, width) << bits) as i64) >> bits) as f64
+        };
+        let value = |at: u64| match type_id {
+            5 => unsigned(at, 4) as f64 / unsigned(at + 4, 4) as f64,
+            10 => signed(at, 4) / signed(at + 4, 4),
+            11 => f32::from_bits(unsigned(at, 4) as u32) as f64,
+            12 => f64::from_bits(unsigned(at, 8)),
+            6 | 8 | 9 | 17 => signed(at, size),
+            _ => unsigned(at, size) as f64,
+        };
+        Some((0..count.min(TAG_DUMP_MAX_VALUES)).map(|i| value(start + i * size)).collect())
+    }
+
+    /// JSON for the IFD at `offset` and the offset of the next one.
+    fn ifd_layout(&self, index: usize, offset: u64) -> Result<(String, u64), u64> {
+        let (count_size, entry_size, next_size) = if self.bigtiff { (8, 20, 8) } else { (2, 12, 4) };
+        let entry_count = self.uint(offset, count_size)?;
+        let table_end = (offset + count_size).saturating_add(entry_count.saturating_mul(entry_size));
+        let next_ifd = self.uint(table_end, next_size)?;
+
+        let mut tags: HashMap<u64, Vec<u64>> = HashMap::new();
+        for i in 0..entry_count {
+            let entry = offset + count_size + i * entry_size;
+            let tag = self.uint(entry, 2)?;
+            if matches!(tag, 256 | 257 | 273 | 277 | 278 | 279 | 284 | 322 | 323 | 324 | 325) {
+                tags.insert(tag, self.entry_values(entry)?);
+            }
+        }
+        let first = |tag: u64, default: u64| tags.get(&tag).and_then(|v| v.first().copied()).unwrap_or(default);
+        let (width, height) = (first(256, 0), first(257, 0));
+        let tiled = tags.contains_key(&322);
+        let (chunk_width, chunk_height, offsets, counts) = if tiled {
+            (first(322, 0), first(323, 0), tags.get(&324), tags.get(&325))
+        } else {
+            (width, first(278, height).min(height), tags.get(&273), tags.get(&279))
+        };
+        let list = |values: Option<&Vec<u64>>| values.map_or(String::new(), |v| v.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(","));
+        let json = format!(
+            "{{\"index\":{},\"offset\":{},\"size\":{},\"next_ifd_offset\":{},\"width\":{},\"height\":{},\"samples_per_pixel\":{},\"planar_configuration\":{},\"tiled\":{},\"chunk_width\":{},\"chunk_height\":{},\"chunk_offsets\":[{}],\"chunk_byte_counts\":[{}]}}",
+            index,
+            offset,
+            table_end + next_size - offset,
+            next_ifd,
+            width,
+            height,
+            first(277, 1),
+            first(284, 1),
+            tiled,
+            chunk_width,
+            chunk_height,
+            list(offsets),
+            list(counts)
+        );
+        Ok((json, next_ifd))
+    }
+
+    /// Push a `get_all_tags` row for every entry of the IFD at `offset`,
+    /// then walk the Exif/GPS/Interoperability IFDs it points to.
+    fn dump_ifd(&self, offset: u64, group: &str, visited: &mut std::collections::HashSet<u64>, out: &mut Vec<String>) -> Result<(), u64> {
+        let (count_size, entry_size) = if self.bigtiff { (8, 20) } else { (2, 12) };
+        if !visited.insert(offset) {
+            return Ok(());
+        }
+        let entry_count = self.uint(offset, count_size)?;
+        let mut sub_ifds = Vec::new();
+        for i in 0..entry_count {
+            let entry = offset + count_size + i * entry_size;
+            let tag = self.uint(entry, 2)? as u16;
+            let (json, values) = self.tag_dump_entry(entry)?;
+            out.push(format!(
+                "{{\"id\":{},\"name\":\"{}\",\"group\":\"{}\",{}}}",
+                tag,
+                json_escape(&format!("{:?}", tiff::tags::Tag::from_u16_exhaustive(tag))),
+                group,
+                json
+            ));
+            let sub_group = match tag {
+                34665 => "Exif",
+                34853 => "GPS",
+                40965 => "Interop",
+                _ => continue,
+            };
+            if let Some(&sub_offset) = values.first() {
+                sub_ifds.push((sub_offset, sub_group));
+            }
+        }
+        for (sub_offset, sub_group) in sub_ifds {
+            self.dump_ifd(sub_offset, sub_group, visited, out)?;
+        }
+        Ok(())
+    }
+
+    /// The `"type","type_name","count","value","truncated"` fields of the
+    /// IFD entry at `entry`, plus its values when they are offsets (for
+    /// following sub-IFD pointers). A value lying outside the file is
+    /// `null`; the entry table itself must be complete.
+    fn tag_dump_entry(&self, entry: u64) -> Result<(String, Vec<u64>), u64> {
+        let (count_size, field_size) = if self.bigtiff { (8, 8) } else { (4, 4) };
+        let type_id = self.uint(entry + 2, 2)?;
+        let count = self.uint(entry + 4, count_size)?;
+        let field = entry + 4 + count_size;
+        let (type_name, size) = match type_id {
+            1 => ("BYTE", 1),
+            2 => ("ASCII", 1),
+            3 => ("SHORT", 2),
+            4 => ("LONG", 4),
+            5 => ("RATIONAL", 8),
+            6 => ("SBYTE", 1),
+            7 => ("UNDEFINED", 1),
+            8 => ("SSHORT", 2),
+            9 => ("SLONG", 4),
+            10 => ("SRATIONAL", 8),
+            11 => ("FLOAT", 4),
+            12 => ("DOUBLE", 8),
+            13 => ("IFD", 4),
+            16 => ("LONG8", 8),
+            17 => ("SLONG8", 8),
+            18 => ("IFD8", 8),
+            _ => ("", 0),
+        };
+        self.uint(field, field_size)?;
+        let fields = |value: &str, truncated: bool| {
+            format!(
+                "\"type\":{},\"type_name\":{},\"count\":{},\"value\":{},\"truncated\":{}",
+                type_id,
+                if type_name.is_empty() { "null".to_string() } else { format!("\"{}\"", type_name) },
+                count,
+                value,
+                truncated
+            )
+        };
+        if size == 0 {
+            // Unknown field type: its size is unknown too, so all there is
+            // to show is the raw value/offset field.
+            let raw: Vec<String> = (0..field_size).map(|i| format!("{:02x}", self.uint(field + i, 1).unwrap_or(0))).collect();
+            return Ok((fields(&format!("\"{}\"", raw.concat()), false), Vec::new()));
+        }
+
+        let shown = count.min(TAG_DUMP_MAX_VALUES);
+        let start = if count.saturating_mul(size) <= field_size { Ok(field) } else { self.uint(field, field_size) };
+        let Some(start) = start.ok().filter(|start| start.saturating_add(shown * size) <= self.data.len() as u64) else {
+            return Ok((fields("null", false), Vec::new()));
+        };
+        let unsigned = |i: u64, width: u64| self.uint(start + i * width, width).unwrap_or(0);
+        let signed = |i: u64, width: u64| {
+            let bits = 64 - 8 * width as u32;
+            ((unsigned(i, width) << bits) as i64) >> bits
+        };
+        let numbers = |render: &dyn Fn(u64) -> String| (0..shown).map(render).collect::<Vec<_>>().join(",");
+        let value = match type_id {
+            2 => {
+                let bytes = &self.data[start as usize..(start + shown) as usize];
+                let text = String::from_utf8_lossy(bytes);
+                format!("\"{}\"", json_escape(text.trim_end_matches('\0')))
+            }
+            5 => format!("[{}]", numbers(&|i| format!("[{},{}]", unsigned(2 * i, 4), unsigned(2 * i + 1, 4)))),
+            10 => format!("[{}]", numbers(&|i| format!("[{},{}]", signed(2 * i, 4), signed(2 * i + 1, 4)))),
+            11 => format!("[{}]", numbers(&|i| json_f64(f32::from_bits(unsigned(i, 4) as u32) as f64))),
+            12 => format!("[{}]", numbers(&|i| json_f64(f64::from_bits(unsigned(i, 8))))),
+            6 | 8 | 9 | 17 => format!("[{}]", numbers(&|i| signed(i, size).to_string())),
+            _ => format!("[{}]", numbers(&|i| unsigned(i, size).to_string())),
+        };
+        let offsets = if matches!(type_id, 4 | 13 | 16 | 18) { (0..shown).map(|i| unsigned(i, size)).collect() } else { Vec::new() };
+        Ok((fields(&value, shown < count), offsets))
+    }
+}
+
+/// Most values `get_all_tags` lists per tag; longer arrays (the strip
+/// offsets of a huge page, embedded profiles and blobs) are cut there, with
+/// `count` still giving the full length.
+const TAG_DUMP_MAX_VALUES: u64 = 4096;
+
+/// Every IFD entry of page `page_index` exactly as stored, read from the
+/// raw directory rather than through the `tiff` crate, so private and
+/// unknown tags, odd field types and values the decoder would reject all
+/// show up. Entries of the Exif, GPS and Interoperability IFDs the page
+/// points to follow its own, in `"group"` "Exif"/"GPS"/"Interop" (the
+/// page's are "TIFF"). JSON array of `{"id","name","group","type",
+/// "type_name","count","value","truncated"}`: `type` is the TIFF field type
+/// (`type_name` its name, null for a type the spec doesn't define), and
+/// `value` a string for ASCII, `[numerator, denominator]` pairs for
+/// (S)RATIONAL, an array of numbers for every other known type, and the raw
+/// value field as hex for an unknown one. Arrays stop after
+/// `TAG_DUMP_MAX_VALUES` entries (`truncated`). `all_tags_json` is the
+/// friendlier, display-formatted view of the same tags.
+#[wasm_bindgen]
+pub fn get_all_tags(data: &[u8], page_index: u32) -> Result<String, JsValue> {
+    let gunzipped = gunzip_if_needed(data)?;
+    let data: &[u8] = &gunzipped;
+    let little_endian = tiff_is_little_endian(data)
+        .ok_or_else(|| JsValue::from_str("Tag dump: not a TIFF header"))?;
+    let reader = TiffByteReader { data, little_endian, bigtiff: data[2] == 43 || data[3] == 43 };
+    let offset = reader.page_ifd_offset(page_index, "Tag dump")?;
+    let mut out = Vec::new();
+    reader
+        .dump_ifd(offset, "TIFF", &mut std::collections::HashSet::new(), &mut out)
+        .map_err(|end| reader.truncated("Tag dump", end))?;
+    Ok(format!("[{}]", out.join(",")))
+}
+
+/// Capture settings of page `page_index` from the Exif IFD it points to
+/// (34665), with the camera and lens names from the page itself, for an
+/// info panel next to the pixels. JSON object with `make`, `model`,
+/// `lens_make`, `lens_model`, `serial_number`, `software`, `exposure_time`
+/// (seconds) and `exposure_time_text` ("1/250"), `f_number`, `iso`,
+/// `focal_length` and `focal_length_35mm` (mm), `exposure_bias` (EV),
+/// `exposure_program`, `metering_mode` and `white_balance` (names),
+/// `flash_fired`, `date_time_original`, `date_time_digitized` (as stored)
+/// and `capture_time` (ISO 8601 from DateTimeOriginal with its sub-seconds
+/// and offset); each key is `null` when the file doesn't say. Exposure and
+/// aperture fall back to the APEX ShutterSpeedValue/ApertureValue. The whole
+/// result is `null` for a page without an Exif IFD; `get_all_tags` lists
+/// every raw entry.
+#[wasm_bindgen]
+pub fn get_exif(data: &[u8], page_index: u32) -> Result<String, JsValue> {
+    let gunzipped = gunzip_if_needed(data)?;
+    let data: &[u8] = &gunzipped;
+    let little_endian = tiff_is_little_endian(data).ok_or_else(|| JsValue::from_str("EXIF: not a TIFF header"))?;
+    let reader = TiffByteReader { data, little_endian, bigtiff: data[2] == 43 || data[3] == 43 };
+    let truncated = |end: u64| reader.truncated("EXIF", end);
+    let page = reader.ifd_entries(reader.page_ifd_offset(page_index, "EXIF")?).map_err(truncated)?;
+    let Some(exif_offset) = page.get(&34665).and_then(|&entry| reader.entry_numbers(entry)).and_then(|v| v.first().copied()) else {
+        return Ok("null".to_string());
+    };
+    let exif = reader.ifd_entries(exif_offset as u64).map_err(truncated)?;
+
+    let text = |ifd: &HashMap<u16, u64>, tag: u16| ifd.get(&tag).and_then(|&entry| reader.entry_text(entry));
+    let number = |tag: u16| exif.get(&tag).and_then(|&entry| reader.entry_numbers(entry)).and_then(|v| v.first().copied()).filter(|v| v.is_finite());
+    let json_text = |value: Option<String>| value.map_or("null".to_string(), |v| format!("\"{}\"", json_escape(&v)));
+    let json_number = |value: Option<f64>| value.map_or("null".to_string(), json_f64);
+    let name = |tag: u16, names: &[&'static str]| number(tag).and_then(|v| names.get(v as usize).copied()).filter(|n| !n.is_empty()).map(str::to_string);
+
+    let exposure_time = number(33434).filter(|&t| t > 0.0).or_else(|| number(37377).map(|tv| 2f64.powf(-tv)));
+    let exposure_time_text = exposure_time.map(|t| {
+        let reciprocal = 1.0 / t;
+        if t < 1.0 && (reciprocal - reciprocal.round()).abs() < 0.01 * reciprocal {
+            format!("1/{}", reciprocal.round())
+        } else {
+            format!("{}", (t * 10.0).round() / 10.0)
+        }
+    });
+    let f_number = number(33437).filter(|&f| f > 0.0).or_else(|| number(37378).map(|av| 2f64.powf(av / 2.0)));
+    let capture_time = text(&exif, 36867).and_then(|stamp| {
+        let (date, time) = stamp.split_once(' ')?;
+        let fields: Vec<&str> = date.split(':').chain(time.split(':')).collect();
+        if fields.len() != 6 || fields.iter().any(|f| f.is_empty() || !f.bytes().all(|b| b.is_ascii_digit())) || fields[0] == "0000" {
+            return None;
+        }
+        let mut iso = format!("{}-{}-{}T{}:{}:{}", fields[0], fields[1], fields[2], fields[3], fields[4], fields[5]);
+        if let Some(sub) = text(&exif, 37521).filter(|sub| sub.bytes().all(|b| b.is_ascii_digit())) {
+            iso.push('.');
+            iso.push_str(&sub);
+        }
+        if let Some(offset) = text(&exif, 36881) {
+            iso.push_str(&offset);
+        }
+        Some(iso)
+    });
+
+    const PROGRAMS: [&str; 9] = ["", "manual", "normal", "aperture_priority", "shutter_priority", "creative", "action", "portrait", "landscape"];
+    const METERING: [&str; 7] = ["unknown", "average", "center_weighted_average", "spot", "multi_spot", "pattern", "partial"];
+    Ok(format!(
+        "{{\"make\":{},\"model\":{},\"lens_make\":{},\"lens_model\":{},\"serial_number\":{},\"software\":{},\"exposure_time\":{},\"exposure_time_text\":{},\"f_number\":{},\"iso\":{},\"focal_length\":{},\"focal_length_35mm\":{},\"exposure_bias\":{},\"exposure_program\":{},\"metering_mode\":{},\"white_balance\":{},\"flash_fired\":{},\"date_time_original\":{},\"date_time_digitized\":{},\"capture_time\":{}}}",
+        json_text(text(&page, 271)),
+        json_text(text(&page, 272)),
+        json_text(text(&exif, 42035)),
+        json_text(text(&exif, 42036)),
+        json_text(text(&exif, 42033)),
+        json_text(text(&page, 305)),
+        json_number(exposure_time),
+        json_text(exposure_time_text),
+        json_number(f_number),
+        json_number(number(34855)),
+        json_number(number(37386)),
+        json_number(number(41989).filter(|&f| f > 0.0)),
+        json_number(number(37380)),
+        json_text(name(34850, &PROGRAMS)),
+        json_text(name(37383, &METERING).or_else(|| number(37383).filter(|&m| m == 255.0).map(|_| "other".to_string()))),
+        json_text(name(41987, &["auto", "manual"])),
+        number(37385).map_or("null".to_string(), |flash| (flash as u64 & 1 == 1).to_string()),
+        json_text(text(&exif, 36867)),
+        json_text(text(&exif, 36868)),
+        json_text(capture_time)
+    ))
+}
+
+/// Look for a stereo capture in a TIFF: either two pages holding the left
+/// and right views, or one page packing both views side by side (or over
+/// under). Only metadata decides, since a same-sized second page is just as
+/// often a depth map or mask: a PageName (285) or ImageDescription naming the
+/// pages "left"/"right" (or the file "stereo") marks a two-page pair, and an
+/// ImageDescription mentioning "side-by-side"/"sbs" or "over-under"/
+/// "top-bottom" a packed page. Returns JSON
+/// `{"layout":"pages"|"side_by_side"|"over_under","left":<page>,"right":<page>}`
+/// (for packed layouts both are the packed page), or `null` when the file
+/// does not look like stereo. Render with `TiffResult::anaglyph_with` /
+/// `side_by_side_with`, or `packed_stereo_anaglyph` for packed pages.
+#[wasm_bindgen]
+pub fn detect_stereo(data: &[u8]) -> Result<String, JsValue> {
+    use tiff::tags::Tag;
+
+    let data = gunzip_if_needed(data)?;
+    let data: &[u8] = &data;
+    let mut decoder = Decoder::new(Cursor::new(data))
+        .map_err(|e| TiffError::from_tiff("Failed to create decoder", e))?;
+    // (dimensions, lower-cased PageName + ImageDescription) per page.
+    let mut pages = Vec::new();
+    loop {
+        let dims = decoder.dimensions().ok();
+        let mut text = String::new();
+        for tag in [Tag::from_u16_exhaustive(285), Tag::ImageDescription] {
+            if let Ok(value) = decoder.get_tag_ascii_string(tag) {
+                text.push_str(&value.to_ascii_lowercase());
+                text.push(' ');
+            }
+        }
+        pages.push((dims, text));
+        if !decoder.more_images() || decoder.next_image().is_err() {
+            break;
+        }
+    }
+
+    // Whole words only ("copyright" is not "right"); phrases as substrings.
+    let mentions = |text: &str, words: &[&str]| {
+        words.iter().any(|&word| {
+            if word.contains(|c: char| !c.is_ascii_alphanumeric()) {
+                text.contains(word)
+            } else {
+                text.split(|c: char| !c.is_ascii_alphanumeric()).any(|token| token == word)
+            }
+        })
+    };
+    let stereo_pair = |left: usize, right: usize| format!("{{\"layout\":\"pages\",\"left\":{},\"right\":{}}}", left, right);
+    if pages.len() == 2 && pages[0].0.is_some() && pages[0].0 == pages[1].0 {
+        let (first, second) = (&pages[0].1, &pages[1].1);
+        if mentions(first, &["right"]) && mentions(second, &["left"]) {
+            return Ok(stereo_pair(1, 0));
+        }
+        if (mentions(first, &["left"]) && mentions(second, &["right"])) || mentions(first, &["stereo"]) {
+            return Ok(stereo_pair(0, 1));
+        }
+    }
+    let text = &pages[0].1;
+    let packed = if mentions(text, &["side-by-side", "side by side", "sbs"]) {
+        "side_by_side"
+    } else if mentions(text, &["over-under", "over/under", "top-bottom", "top/bottom"]) {
+        "over_under"
+    } else {
+        return Ok("null".to_string());
+    };
+    Ok(format!("{{\"layout\":\"{}\",\"left\":0,\"right\":0}}", packed))
+}
+
+/// Archived rasters are often stored as `.tif.gz`. Every TIFF entry point
+/// inflates input starting with the gzip magic (1f 8b, deflate method) here
+/// and borrows everything else untouched. Concatenated gzip members are
+/// inflated as one stream, like `gunzip` does.
+fn gunzip_if_needed(data: &[u8]) -> Result<Cow<'_, [u8]>, TiffError> {
+    use std::io::Read;
+
+    if !data.starts_with(&[0x1f, 0x8b, 0x08]) {
+        return Ok(Cow::Borrowed(data));
+    }
+    let mut out = Vec::with_capacity(data.len().saturating_mul(3));
+    flate2::read::MultiGzDecoder::new(data)
+        .read_to_end(&mut out)
+        .map_err(|e| TiffError::new(TiffErrorKind::Corrupt, format!("Failed to gunzip input: {}", e)))?;
+    Ok(Cow::Owned(out))
+}
+
+/// Decode an arbitrary zero-based TIFF page and compute min/max statistics.
+#[wasm_bindgen]
+pub fn decode_tiff_page(data: &[u8], page_index: u32) -> Result<TiffResult, JsValue> {
+    decode_tiff_impl(data, true, page_index, false, None)
+}
+
+/// Walk a raw Exif-only IFD blob (a JPEG APP1 payload with its "Exif\0\0"
+/// prefix already stripped, or a PNG eXIf chunk's raw bytes) and return
+/// every tag as JSON, in the same shape as `TiffResult.all_tags_json`.
+///
+/// These blobs are TIFF-*structured* (byte order + magic 42 + IFD entries)
+/// but are not full TIFF files — they carry no ImageWidth/PhotometricInterpretation/
+/// etc., so the `tiff` crate's `Decoder::new()` (which always validates a
+/// full image directory) rejects them. `extract_bare_ifd_tags_json` reads
+/// the IFD structure directly instead, bypassing `Decoder` entirely; real
+/// `.tif`/`.tiff` files keep using the `Decoder`-based `extract_all_tags_json`
+/// via `decode_tiff`/`decode_tiff_fast` above.
+#[wasm_bindgen]
+pub fn extract_exif_tags(data: &[u8]) -> String {
+    extract_bare_ifd_tags_json(data)
+}
+
+/// Decode a TIFF file without eagerly computing min/max statistics.
+///
+/// The webview render path computes stats lazily when a non-gamma mode needs
+/// them. Skipping eager stats saves a full pass over large float TIFFs during
+/// the common gamma-mode initial load.
+#[wasm_bindgen]
+pub fn decode_tiff_fast(data: &[u8]) -> Result<TiffResult, JsValue> {
+    decode_tiff_impl(data, false, 0, false, None)
+}
+
+/// Decode an arbitrary zero-based TIFF page without eagerly computing stats.
+#[wasm_bindgen]
+pub fn decode_tiff_page_fast(data: &[u8], page_index: u32) -> Result<TiffResult, JsValue> {
+    decode_tiff_impl(data, false, page_index, false, None)
+}
+
+/// Choices for `decode_tiff_with_options`, built with `new DecodeOptions()`
+/// and adjusted with its setters; the defaults decode like `decode_tiff_page`.
+#[wasm_bindgen]
+#[derive(Clone, Copy)]
+pub struct DecodeOptions {
+    compute_stats: bool,
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen]
+impl DecodeOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> DecodeOptions {
+        DecodeOptions { compute_stats: true }
+    }
+
+    /// Whether the decode takes min/max over the samples (the default).
+    /// Without it the full-buffer stats pass is skipped, as in
+    /// `decode_tiff_fast`, which quick thumbnails of very large pages can do
+    /// without; `TiffResult::compute_stats` runs it later when needed.
+    #[wasm_bindgen(getter)]
+    pub fn compute_stats(&self) -> bool {
+        self.compute_stats
+    }
+
+    #[wasm_bindgen]
+    pub fn set_compute_stats(&mut self, compute_stats: bool) {
+        self.compute_stats = compute_stats;
+    }
+}
+
+/// Choices for `TiffResult::render_to_rgba`, built with
+/// `new RenderOptions()` and adjusted with its setters; the defaults render
+/// the image's own min..max linearly in gray.
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct RenderOptions {
+    min: Option<f64>,
+    max: Option<f64>,
+    gamma: f64,
+    srgb_transfer: bool,
+    colormap: String,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen]
+impl RenderOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> RenderOptions {
+        RenderOptions { min: None, max: None, gamma: 1.0, srgb_transfer: false, colormap: String::new() }
+    }
+
+    /// Sample value drawn black; undefined (the default) takes the image's
+    /// minimum.
+    #[wasm_bindgen(getter)]
+    pub fn min(&self) -> Option<f64> {
+        self.min
+    }
+
+    #[wasm_bindgen]
+    pub fn set_min(&mut self, min: Option<f64>) {
+        self.min = min;
+    }
+
+    /// Sample value drawn white; undefined (the default) takes the image's
+    /// maximum.
+    #[wasm_bindgen(getter)]
+    pub fn max(&self) -> Option<f64> {
+        self.max
+    }
+
+    #[wasm_bindgen]
+    pub fn set_max(&mut self, max: Option<f64>) {
+        self.max = max;
+    }
+
+    /// Display gamma: normalized values are raised to `1 / gamma`, so
+    /// values above 1 brighten the shadows. 1 (the default) is linear.
+    #[wasm_bindgen(getter)]
+    pub fn gamma(&self) -> f64 {
+        self.gamma
+    }
+
+    #[wasm_bindgen]
+    pub fn set_gamma(&mut self, gamma: f64) {
+        self.gamma = gamma;
+    }
+
+    /// Treat the samples as linear light and encode them with the sRGB
+    /// curve (the inverse of the sRGB EOTF) instead of the `gamma` power,
+    /// the correct display for linear float data such as EXR-style renders
+    /// or radiometric measurements, which otherwise look too dark. Off by
+    /// default; `gamma` is ignored while it is on.
+    #[wasm_bindgen(getter)]
+    pub fn srgb_transfer(&self) -> bool {
+        self.srgb_transfer
+    }
+
+    #[wasm_bindgen]
+    pub fn set_srgb_transfer(&mut self, srgb_transfer: bool) {
+        self.srgb_transfer = srgb_transfer;
+    }
+
+    /// Colormap for single-channel (and gray + alpha) images, by name:
+    /// any of `display_colormap_names()` - viridis, plasma, inferno, magma,
+    /// jet, hot, cool, turbo, gray - or "grayscale" for gray. "" (the
+    /// default) or "none" renders gray without a lookup table; RGB images
+    /// ignore it. Unknown names are reported by `render_to_rgba`.
+    #[wasm_bindgen(getter)]
+    pub fn colormap(&self) -> String {
+        self.colormap.clone()
+    }
+
+    #[wasm_bindgen]
+    pub fn set_colormap(&mut self, colormap: &str) {
+        self.colormap = colormap.to_string();
+    }
+}
+
+/// Vector marks to composite into rendered RGBA output, built with
+/// `new Annotations()` and its `add_*` methods. Coordinates are in pixels of
+/// the rendered (stored-orientation) image, (0, 0) being the top-left corner
+/// of its first pixel; `display_to_source` converts positions picked on an
+/// oriented view. A pixel is painted when its centre falls inside a shape.
+/// Colours are RGB or RGBA bytes and are blended over the image ("source
+/// over"), so a translucent alpha leaves the data visible underneath.
+#[wasm_bindgen]
+#[derive(Clone, Default)]
+pub struct Annotations {
+    shapes: Vec<Annotation>,
+}
+
+#[derive(Clone)]
+enum Annotation {
+    /// A border `line_width` wide just inside the rectangle's edges, or the
+    /// whole rectangle when `filled`.
+    Rect { x: f64, y: f64, width: f64, height: f64, line_width: f64, filled: bool, rgba: [u8; 4] },
+    /// Segments `line_width` wide with round joins and ends; a single
+    /// point is a disc of diameter `line_width`.
+    Polyline { points: Vec<(f64, f64)>, line_width: f64, rgba: [u8; 4] },
+}
+
+#[wasm_bindgen]
+impl Annotations {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Annotations {
+        Annotations::default()
+    }
+
+    /// Number of shapes added so far.
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> u32 {
+        self.shapes.len() as u32
+    }
+
+    /// A `width` x `height` rectangle with its top-left corner at (`x`, `y`),
+    /// so (10, 10, 20, 10) spans pixels 10..29 by 10..19: outlined with a
+    /// `line_width` pixel border inside those edges, or filled.
+    #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_rect(
+        &mut self,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        line_width: f64,
+        filled: bool,
+        color: &[u8],
+    ) -> Result<(), JsValue> {
+        check_annotation_numbers(&[x, y, width, height, line_width])?;
+        if width < 0.0 || height < 0.0 || (!filled && line_width <= 0.0) {
+            return Err(JsValue::from_str(&format!(
+                "Annotations: rectangle {}x{} with line width {} is empty", width, height, line_width
+            )));
+        }
+        let rgba = annotation_rgba(color)?;
+        self.shapes.push(Annotation::Rect { x, y, width, height, line_width, filled, rgba });
+        Ok(())
+    }
+
+    /// A filled disc of `radius` pixels centred on (`x`, `y`).
+    #[wasm_bindgen]
+    pub fn add_point(&mut self, x: f64, y: f64, radius: f64, color: &[u8]) -> Result<(), JsValue> {
+        check_annotation_numbers(&[x, y, radius])?;
+        if radius <= 0.0 {
+            return Err(JsValue::from_str(&format!("Annotations: point radius {} must be positive", radius)));
+        }
+        let rgba = annotation_rgba(color)?;
+        self.shapes.push(Annotation::Polyline { points: vec![(x, y)], line_width: 2.0 * radius, rgba });
+        Ok(())
+    }
+
+    /// Connected segments through `coords` (`[x0, y0, x1, y1, ...]`),
+    /// `line_width` pixels wide. Close a polygon by repeating its first
+    /// point at the end.
+    #[wasm_bindgen]
+    pub fn add_polyline(&mut self, coords: &[f64], line_width: f64, color: &[u8]) -> Result<(), JsValue> {
+        check_annotation_numbers(coords)?;
+        check_annotation_numbers(&[line_width])?;
+        if coords.is_empty() || !coords.len().is_multiple_of(2) {
+            return Err(JsValue::from_str(&format!(
+                "Annotations: a polyline needs x, y pairs (got {} numbers)", coords.len()
+            )));
+        }
+        if line_width <= 0.0 {
+            return Err(JsValue::from_str(&format!("Annotations: line width {} must be positive", line_width)));
+        }
+        let rgba = annotation_rgba(color)?;
+        let points = coords.chunks_exact(2).map(|xy| (xy[0], xy[1])).collect();
+        self.shapes.push(Annotation::Polyline { points, line_width, rgba });
+        Ok(())
+    }
+
+    /// Remove every shape.
+    #[wasm_bindgen]
+    pub fn clear(&mut self) {
+        self.shapes.clear();
+    }
+
+    /// Composite the shapes, in the order they were added, into `rgba`
+    /// (`width * height` RGBA bytes such as `render_rgba` or
+    /// `render_to_rgba` return) in place. Parts outside the image are
+    /// clipped.
+    #[wasm_bindgen]
+    pub fn burn_into(&self, rgba: &mut [u8], width: u32, height: u32) -> Result<(), JsValue> {
+        let (width, height) = (width as usize, height as usize);
+        if rgba.len() != width * height * 4 {
+            return Err(JsValue::from_str(&format!(
+                "Annotations: {} bytes is not a {}x{} RGBA buffer", rgba.len(), width, height
+            )));
+        }
+        for shape in &self.shapes {
+            let (pixels, colour) = shape.covered_pixels(width, height);
+            let alpha = colour[3] as u32;
+            for pixel in pixels {
+                let out = &mut rgba[pixel * 4..pixel * 4 + 4];
+                for c in 0..3 {
+                    out[c] = ((colour[c] as u32 * alpha + out[c] as u32 * (255 - alpha) + 127) / 255) as u8;
+                }
+                out[3] = (alpha + (out[3] as u32 * (255 - alpha) + 127) / 255) as u8;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Annotation {
+    /// Indices of the pixels of a `width` x `height` image whose centres the
+    /// shape covers, each once, and its colour.
+    fn covered_pixels(&self, width: usize, height: usize) -> (Vec<usize>, [u8; 4]) {
+        let mut pixels = Vec::new();
+        // Pixels with centres in [x0, x1] x [y0, y1], clipped to the image.
+        let mut visit = |x0: f64, y0: f64, x1: f64, y1: f64, covers: &dyn Fn(f64, f64) -> bool| {
+            let span = |lo: f64, hi: f64, size: usize| {
+                ((lo - 0.5).ceil().max(0.0) as usize, ((hi - 0.5).floor() + 1.0).clamp(0.0, size as f64) as usize)
+            };
+            let ((col0, col1), (row0, row1)) = (span(x0, x1, width), span(y0, y1, height));
+            for row in row0..row1 {
+                for col in col0..col1 {
+                    if covers(col as f64 + 0.5, row as f64 + 0.5) {
+                        pixels.push(row * width + col);
+                    }
+                }
+            }
+        };
+        let rgba = match *self {
+            Annotation::Rect { x, y, width: w, height: h, line_width, filled, rgba } => {
+                let outer = [x, y, x + w, y + h];
+                let inner = [x + line_width, y + line_width, x + w - line_width, y + h - line_width];
+                // Half-open, so shapes sharing an edge don't both paint it.
+                let inside = |r: [f64; 4], px: f64, py: f64| px >= r[0] && px < r[2] && py >= r[1] && py < r[3];
+                visit(outer[0], outer[1], outer[2], outer[3], &|px, py| {
+                    inside(outer, px, py) && (filled || !inside(inner, px, py))
+                });
+                rgba
+            }
+            Annotation::Polyline { ref points, line_width, rgba } => {
+                let radius = line_width / 2.0;
+                let segments = points.windows(2).map(|pair| (pair[0], pair[1]));
+                let segments: Vec<_> = if points.len() == 1 { vec![(points[0], points[0])] } else { segments.collect() };
+                for ((ax, ay), (bx, by)) in segments {
+                    let (dx, dy) = (bx - ax, by - ay);
+                    let length_sq = dx * dx + dy * dy;
+                    visit(ax.min(bx) - radius, ay.min(by) - radius, ax.max(bx) + radius, ay.max(by) + radius, &|px, py| {
+                        let t = if length_sq > 0.0 { (((px - ax) * dx + (py - ay) * dy) / length_sq).clamp(0.0, 1.0) } else { 0.0 };
+                        (px - ax - t * dx).hypot(py - ay - t * dy) <= radius
+                    });
+                }
+                rgba
+            }
+        };
+        // Joins and overlapping segments must not blend twice.
+        pixels.sort_unstable();
+        pixels.dedup();
+        (pixels, rgba)
+    }
+}
+
+/// An annotation colour from RGB or RGBA bytes (RGB is opaque).
+fn annotation_rgba(color: &[u8]) -> Result<[u8; 4], JsValue> {
+    match *color {
+        [r, g, b] => Ok([r, g, b, 255]),
+        [r, g, b, a] => Ok([r, g, b, a]),
+        _ => Err(JsValue::from_str("Annotations: color needs 3 (RGB) or 4 (RGBA) bytes")),
+    }
+}
+
+fn check_annotation_numbers(values: &[f64]) -> Result<(), JsValue> {
+    match values.iter().find(|v| !v.is_finite()) {
+        Some(v) => Err(JsValue::from_str(&format!("Annotations: coordinate or size {} is not finite", v))),
+        None => Ok(()),
+    }
+}
+
+/// Decode zero-based page `page_index` with the given `DecodeOptions`.
+#[wasm_bindgen]
+pub fn decode_tiff_with_options(data: &[u8], page_index: u32, options: &DecodeOptions) -> Result<TiffResult, JsValue> {
+    decode_tiff_impl(data, options.compute_stats, page_index, false, None)
+}
+
+/// Decode a TIFF page like `decode_tiff_page`, additionally recording the
+/// decode time and compressed/decompressed size of every strip or tile in
+/// `TiffResult.chunk_profile_json`. Meant for "this file is slow" reports:
+/// together with the `timing_*_ms` getters it shows whether decompression or
+/// conversion dominates, and which strips/tiles are the expensive ones.
+///
+/// Images whose decode path works on the whole image at once (palette,
+/// CCITT, JPEG, or the `tiff` crate's `read_image()` fallback) report an
+/// empty `chunks` list; eligible chunky images are routed through
+/// `try_decode_streaming_chunks` regardless of size so they can be profiled.
+#[wasm_bindgen]
+pub fn decode_tiff_page_profiled(data: &[u8], page_index: u32) -> Result<TiffResult, JsValue> {
+    decode_tiff_impl(data, true, page_index, true, None)
+}
+
+/// Largest decoded page `decode_tiff_safe` accepts (bytes of samples at
+/// their stored width); wasm32 memory tops out at 4 GiB and the decode
+/// needs the buffer at least twice over while converting.
+const SAFE_DECODE_MAX_BYTES: u64 = 1 << 31;
+
+/// Decode a TIFF page like `decode_tiff_page`, but validate the structure
+/// up front and never let a failure escape as anything but a `TiffError`
+/// that says where the file went wrong as well as what: `ifd` is the page
+/// and `ifd_offset` its IFD, `tag` the tag whose value is at fault (the
+/// offsets tag when a strip/tile does not decode) and `offset` the file
+/// offset the problem was found at - that IFD entry, the strip/tile, or
+/// else the page's IFD. Validation checks the header, that the page
+/// exists, sane dimensions/sample layout, a decoded size below
+/// `SAFE_DECODE_MAX_BYTES`, and that every strip/tile lies inside the file,
+/// so most malformed inputs are refused before a dependency can trip over
+/// them. The decode itself runs under `catch_unwind` (a panic comes back as
+/// `TiffErrorKind::Other`); note that this only isolates panics in builds
+/// that unwind (native, or wasm built with `panic = "unwind"`) - the
+/// default wasm32 target aborts on panic, so there the validation is what
+/// keeps the instance alive. After a failed decode the strips/tiles are
+/// decoded one by one (`first_failing_chunk`) to find the one at fault.
+#[wasm_bindgen]
+pub fn decode_tiff_safe(data: &[u8], page_index: u32) -> Result<TiffResult, TiffError> {
+    let ifd_offset = |data: &[u8]| {
+        let little_endian = tiff_is_little_endian(data)?;
+        let reader = TiffByteReader { data, little_endian, bigtiff: data[2] == 43 || data[3] == 43 };
+        reader.page_ifd_offset(page_index, "TIFF").ok()
+    };
+    let in_page = |mut error: TiffError, ifd_offset: Option<u64>| {
+        error.ifd = Some(page_index);
+        error.ifd_offset = ifd_offset;
+        error.offset = error.offset.or(ifd_offset);
+        error
+    };
+    let data = gunzip_if_needed(data).map_err(|e| in_page(e.at(None, Some(0)), None))?;
+    let ifd = ifd_offset(&data);
+    validate_tiff_page(&data, page_index).map_err(|error| in_page(error, ifd))?;
+
+    let located = |error: TiffError| {
+        if error.tag.is_some() {
+            return error;
+        }
+        let chunk = std::panic::catch_unwind(|| first_failing_chunk(&data, page_index)).ok().flatten();
+        error.at(chunk.map(|(tag, _)| tag), chunk.map(|(_, offset)| offset))
+    };
+    let decode = || decode_tiff_pipeline(&data, true, page_index, false, None);
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(decode)) {
+        Ok(Ok(result)) => Ok(result),
+        Ok(Err(e)) => Err(in_page(located(e), ifd)),
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|m| m.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "decoder panicked".to_string());
+            Err(in_page(located(TiffError::new(TiffErrorKind::Other, message)), ifd))
+        }
+    }
+}
+
+/// What kind of failure a `TiffError` is, so the extension can tell e.g.
+/// "unsupported compression" (another decoder may cope) from "truncated
+/// file" (none will) without parsing the message.
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TiffErrorKind {
+    /// Not a TIFF, or for `decode_image` not any format built in.
+    UnrecognizedFormat = 1,
+    /// A valid file using something this decoder doesn't implement
+    /// (compression, sample layout, ...).
+    Unsupported = 2,
+    /// The file ends before data it points to.
+    Truncated = 3,
+    /// Malformed tags or undecodable compressed data.
+    Corrupt = 4,
+    /// The page is larger than the decoder will allocate.
+    TooLarge = 5,
+    /// The call's own arguments are out of range (page index, region, ...).
+    InvalidArgument = 6,
+    /// Anything not classified above.
+    Other = 7,
+}
+
+impl TiffErrorKind {
+    fn code(self) -> &'static str {
+        match self {
+            TiffErrorKind::UnrecognizedFormat => "unrecognized_format",
+            TiffErrorKind::Unsupported => "unsupported",
+            TiffErrorKind::Truncated => "truncated",
+            TiffErrorKind::Corrupt => "corrupt",
+            TiffErrorKind::TooLarge => "too_large",
+            TiffErrorKind::InvalidArgument => "invalid_argument",
+            TiffErrorKind::Other => "other",
+        }
+    }
+}
+
+/// The error the TIFF decode entry points (`decode_tiff`, `decode_tiff_page`,
+/// `decode_tiff_region`, `decode_tiff_safe`, `decode_image`, ...) throw:
+/// a `kind`, the human-readable `message` (also what `toString` gives, so
+/// `String(error)` reads as before), and where known the offending `tag`
+/// and file `offset`. Errors from elsewhere in the decoder arrive as
+/// `TiffErrorKind::Other` with their message.
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct TiffError {
+    kind: TiffErrorKind,
+    message: String,
+    tag: Option<u16>,
+    offset: Option<u64>,
+    // Page index and IFD offset, set by `decode_tiff_safe`.
+    ifd: Option<u32>,
+    ifd_offset: Option<u64>,
+    // Set for transfers refused by `TiffResult::set_transfer_byte_budget`.
+    suggested_downsample: Option<u32>,
+}
+
+#[wasm_bindgen]
+impl TiffError {
+    #[wasm_bindgen(getter)]
+    pub fn kind(&self) -> TiffErrorKind {
+        self.kind
+    }
+
+    /// `kind` as a stable string: "unrecognized_format", "unsupported",
+    /// "truncated", "corrupt", "too_large", "invalid_argument" or "other".
+    #[wasm_bindgen(getter)]
+    pub fn code(&self) -> String {
+        self.kind.code().to_string()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+
+    /// The tag whose value is at fault, if one is.
+    #[wasm_bindgen(getter)]
+    pub fn tag(&self) -> Option<u16> {
+        self.tag
+    }
+
+    /// The file offset the problem was found at, if known.
+    #[wasm_bindgen(getter)]
+    pub fn offset(&self) -> Option<f64> {
+        self.offset.map(|offset| offset as f64)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn ifd(&self) -> Option<u32> {
+        self.ifd
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn ifd_offset(&self) -> Option<f64> {
+        self.ifd_offset.map(|offset| offset as f64)
+    }
+
+    /// For a "too_large" transfer refused under
+    /// `TiffResult::set_transfer_byte_budget`: the smallest `step` of
+    /// `decode_strided` / `read_region` whose output would fit the budget.
+    #[wasm_bindgen(getter)]
+    pub fn suggested_downsample(&self) -> Option<u32> {
+        self.suggested_downsample
+    }
+
+    /// `{"code","message","ifd","ifd_offset","tag","offset",
+    /// "suggested_downsample"}` with `null` for what isn't known, for
+    /// passing the error on through `postMessage`, which can't clone the
+    /// object itself.
+    #[wasm_bindgen]
+    pub fn to_json(&self) -> String {
+        let number = |value: Option<u64>| value.map_or_else(|| "null".to_string(), |value| value.to_string());
+        format!(
+            "{{\"code\":\"{}\",\"message\":\"{}\",\"ifd\":{},\"ifd_offset\":{},\"tag\":{},\"offset\":{},\"suggested_downsample\":{}}}",
+            self.kind.code(),
+            json_escape(&self.message),
+            number(self.ifd.map(u64::from)),
+            number(self.ifd_offset),
+            number(self.tag.map(u64::from)),
+            number(self.offset),
+            number(self.suggested_downsample.map(u64::from)),
+        )
+    }
+
+    #[wasm_bindgen(js_name = toString)]
+    pub fn to_js_string(&self) -> String {
+        self.message.clone()
+    }
+}
+
+impl TiffError {
+    fn new(kind: TiffErrorKind, message: impl Into<String>) -> TiffError {
+        TiffError {
+            kind,
+            message: message.into(),
+            tag: None,
+            offset: None,
+            ifd: None,
+            ifd_offset: None,
+            suggested_downsample: None,
+        }
+    }
+
+    /// An error nothing more specific is known about, as a plain message
+    /// (`TiffErrorKind::Other`).
+    fn other(message: impl Into<String>) -> TiffError {
+        TiffError::new(TiffErrorKind::Other, message)
+    }
+
+    fn at(mut self, tag: Option<u16>, offset: Option<u64>) -> TiffError {
+        self.tag = tag;
+        self.offset = offset;
+        self
+    }
+
+    /// Classify an error from the `tiff` crate, prefixing `context` to its
+    /// message.
+    fn from_tiff(context: &str, error: tiff::TiffError) -> TiffError {
+        use tiff::{TiffError as E, TiffFormatError as F};
+
+        let message = format!("{}: {}", context, error);
+        match &error {
+            E::FormatError(F::TiffSignatureNotFound | F::TiffSignatureInvalid) => {
+                TiffError::new(TiffErrorKind::UnrecognizedFormat, message).at(None, Some(0))
+            }
+            E::FormatError(
+                F::RequiredTagNotFound(tag) | F::InvalidTagValueType(tag) | F::InvalidCountForTag(tag, _),
+            ) => TiffError::new(TiffErrorKind::Corrupt, message).at(Some(tag.to_u16()), None),
+            E::FormatError(_) => TiffError::new(TiffErrorKind::Corrupt, message),
+            E::UnsupportedError(_) => TiffError::new(TiffErrorKind::Unsupported, message),
+            E::IoError(io) if io.kind() == std::io::ErrorKind::UnexpectedEof => {
+                TiffError::new(TiffErrorKind::Truncated, message)
+            }
+            E::IoError(_) => TiffError::new(TiffErrorKind::Corrupt, message),
+            E::LimitsExceeded | E::IntSizeError => TiffError::new(TiffErrorKind::TooLarge, message),
+            E::UsageError(_) => TiffError::new(TiffErrorKind::InvalidArgument, message),
+        }
+    }
+
+    /// The `TiffError` behind a thrown `JsValue`: itself when it is one, a
+    /// plain string error as `TiffErrorKind::Other`.
+    fn from_js(value: JsValue) -> TiffError {
+        use wasm_bindgen::convert::TryFromJsValue;
+
+        TiffError::try_from_js_value(value).unwrap_or_else(|value| {
+            TiffError::new(TiffErrorKind::Other, value.as_string().unwrap_or_else(|| "unknown error".to_string()))
+        })
+    }
+}
+
+/// Structural checks behind `decode_tiff_safe`; the error says what is
+/// wrong with the file and which tag/offset it was found at.
+fn validate_tiff_page(data: &[u8], page_index: u32) -> Result<(), TiffError> {
+    use tiff::tags::Tag;
+
+    let magic_ok = matches!(data.get(..4), Some([0x49, 0x49, 42 | 43, 0]) | Some([0x4d, 0x4d, 0, 42 | 43]));
+    if !magic_ok {
+        return Err(TiffError::new(TiffErrorKind::UnrecognizedFormat, "not a TIFF file (bad header)").at(None, Some(0)));
+    }
+    // Each tag is placed at its IFD entry; with the IFD unreadable (which
+    // `open_tiff_page` reports) the page's IFD offset stands in.
+    let reader = TiffByteReader { data, little_endian: data[0] == 0x49, bigtiff: data[2] == 43 || data[3] == 43 };
+    let entries = reader.page_ifd_offset(page_index, "TIFF").ok().and_then(|offset| reader.ifd_entries(offset).ok());
+    let at_tag = |message: String, tag: Tag| {
+        let tag = tag.to_u16();
+        TiffError::new(TiffErrorKind::Corrupt, message).at(Some(tag), entries.as_ref().and_then(|entries| entries.get(&tag).copied()))
+    };
+
+    let mut decoder = open_tiff_page(data, page_index)?;
+    let (width, height) = decoder.dimensions().map_err(|e| at_tag(format!("invalid dimensions: {}", e), Tag::ImageWidth))?;
+    if width == 0 || height == 0 {
+        let tag = if width == 0 { Tag::ImageWidth } else { Tag::ImageLength };
+        return Err(at_tag(format!("empty image ({}x{})", width, height), tag));
+    }
+    let samples = decoder.get_tag_u32(Tag::SamplesPerPixel).unwrap_or(1);
+    if samples == 0 || samples > 4096 {
+        return Err(at_tag(format!("implausible SamplesPerPixel {}", samples), Tag::SamplesPerPixel));
+    }
+    let bits = decoder.get_tag_u32_vec(Tag::BitsPerSample).unwrap_or_else(|_| vec![1]);
+    if bits.is_empty() || bits.iter().any(|&b| b == 0 || b > 64) {
+        return Err(at_tag(format!("invalid BitsPerSample {:?}", bits), Tag::BitsPerSample));
+    }
+    let max_bits = bits.iter().copied().max().unwrap_or(1) as u64;
+    let decoded = (width as u64)
+        .checked_mul(height as u64)
+        .and_then(|pixels| pixels.checked_mul(samples as u64))
+        .and_then(|count| count.checked_mul(max_bits.div_ceil(8)))
+        .unwrap_or(u64::MAX);
+    if decoded > SAFE_DECODE_MAX_BYTES {
+        let message = format!("decoded page would need {} bytes (limit {})", decoded, SAFE_DECODE_MAX_BYTES);
+        return Err(TiffError::new(TiffErrorKind::TooLarge, message));
+    }
+
+    let tiled = decoder.get_tag(Tag::TileWidth).is_ok();
+    let (offsets_tag, counts_tag) = if tiled {
+        for tag in [Tag::TileWidth, Tag::TileLength] {
+            if decoder.get_tag_u32(tag).unwrap_or(0) == 0 {
+                return Err(at_tag("zero tile size".to_string(), tag));
+            }
+        }
+        // Every tile is decoded at its full size, however little of it
+        // overlaps the image, so it is held to the same limit as the page.
+        let tile_bytes = (decoder.get_tag_u32(Tag::TileWidth).unwrap_or(0) as u64)
+            .checked_mul(decoder.get_tag_u32(Tag::TileLength).unwrap_or(0) as u64)
+            .and_then(|pixels| pixels.checked_mul(samples as u64))
+            .and_then(|count| count.checked_mul(max_bits.div_ceil(8)))
+            .unwrap_or(u64::MAX);
+        if tile_bytes > SAFE_DECODE_MAX_BYTES {
+            let message = format!("decoded tile would need {} bytes (limit {})", tile_bytes, SAFE_DECODE_MAX_BYTES);
+            return Err(TiffError::new(TiffErrorKind::TooLarge, message).at(Some(Tag::TileWidth.to_u16()), None));
+        }
+        (Tag::TileOffsets, Tag::TileByteCounts)
+    } else {
+        (Tag::StripOffsets, Tag::StripByteCounts)
+    };
+    let offsets = decoder.get_tag_u64_vec(offsets_tag).map_err(|e| at_tag(format!("missing {:?}: {}", offsets_tag, e), offsets_tag))?;
+    let counts = decoder.get_tag_u64_vec(counts_tag).unwrap_or_default();
+    if !counts.is_empty() && counts.len() != offsets.len() {
+        return Err(at_tag(format!("{} offsets but {} byte counts", offsets.len(), counts.len()), counts_tag));
+    }
+    for (index, (&offset, &count)) in offsets.iter().zip(&counts).enumerate() {
+        if is_sparse_chunk(offset, count) {
+            continue;
+        }
+        if offset.checked_add(count).is_none_or(|end| end > data.len() as u64) {
+            let message = format!(
+                "{} {} ({} bytes at offset {}) extends past the end of the file ({} bytes)",
+                if tiled { "tile" } else { "strip" },
+                index,
+                count,
+                offset,
+                data.len()
+            );
+            return Err(TiffError::new(TiffErrorKind::Truncated, message).at(Some(offsets_tag.to_u16()), Some(offset)));
+        }
+    }
+    Ok(())
+}
+
+/// The offsets tag and file offset of the first strip/tile of page
+/// `page_index` that fails to decode on its own, to place a failed
+/// `decode_tiff_safe`. `None` when every one decodes, or the `tiff` crate
+/// can't decode this layout chunk by chunk at all: the failure lies
+/// elsewhere then.
+fn first_failing_chunk(data: &[u8], page_index: u32) -> Option<(u16, u64)> {
+    use tiff::decoder::ChunkType;
+    use tiff::tags::Tag;
+    use tiff::TiffError;
+
+    let mut decoder = open_tiff_page(data, page_index).ok()?;
+    let (offsets_tag, counts_tag) = match decoder.get_chunk_type() {
+        ChunkType::Strip => (Tag::StripOffsets, Tag::StripByteCounts),
+        ChunkType::Tile => (Tag::TileOffsets, Tag::TileByteCounts),
+    };
+    let offsets = decoder.get_tag_u64_vec(offsets_tag).ok()?;
+    let counts = decoder.get_tag_u64_vec(counts_tag).unwrap_or_default();
+    for (index, &offset) in offsets.iter().enumerate() {
+        if is_sparse_chunk(offset, counts.get(index).copied().unwrap_or(0)) {
+            continue;
+        }
+        match decoder.read_chunk(index as u32) {
+            Ok(_) => {}
+            Err(TiffError::UnsupportedError(_) | TiffError::UsageError(_)) => return None,
+            Err(_) => return Some((offsets_tag.to_u16(), offset)),
+        }
+    }
+    None
+}
+
+/// List the TIFF images inside a ZIP archive, so datasets shipped as zips of
+/// TIFFs can be browsed without extracting them first. Returns a JSON array
+/// of `{"name","compressed_size","size"}` for every `.tif`/`.tiff` entry
+/// (also gzipped `.tif.gz`, see `gunzip_if_needed`) in archive order;
+/// directories and macOS `__MACOSX/` metadata are skipped. Pass a `name` to
+/// `decode_zip_tiff_page`.
+#[wasm_bindgen]
+pub fn zip_list_tiffs(data: &[u8]) -> Result<String, JsValue> {
+    let entries = zip_entries(data).map_err(|e| JsValue::from_str(&format!("ZIP: {}", e)))?;
+    let items: Vec<String> = entries
+        .iter()
+        .filter(|entry| zip_entry_is_tiff(&entry.name))
+        .map(|entry| {
+            format!(
+                "{{\"name\":\"{}\",\"compressed_size\":{},\"size\":{}}}",
+                json_escape(&entry.name),
+                entry.compressed_size,
+                entry.uncompressed_size
+            )
+        })
+        .collect();
+    Ok(format!("[{}]", items.join(",")))
+}
+
+/// Decode page `page_index` of the archive entry `name` (as listed by
+/// `zip_list_tiffs`). Only that entry is inflated.
+#[wasm_bindgen]
+pub fn decode_zip_tiff_page(data: &[u8], name: &str, page_index: u32) -> Result<TiffResult, JsValue> {
+    let entries = zip_entries(data).map_err(|e| JsValue::from_str(&format!("ZIP: {}", e)))?;
+    let entry = entries
+        .iter()
+        .find(|entry| entry.name == name)
+        .ok_or_else(|| JsValue::from_str(&format!("ZIP: no entry named '{}'", name)))?;
+    let tiff = zip_entry_data(data, entry).map_err(|e| JsValue::from_str(&format!("ZIP: {}: {}", name, e)))?;
+    decode_tiff_impl(&tiff, true, page_index, false, None)
+}
+
+/// List the arrays of a NumPy `.npz` archive (`np.savez` /
+/// `np.savez_compressed`), so ML debugging dumps open directly. Returns a
+/// JSON array of `{"name","dtype","shape","width","height","channels",
+/// "pages"}` in archive order, where `name` is the key the array was saved
+/// under and width/height/channels/pages describe how `decode_npz_array`
+/// reads it: a 3D array whose last axis has at most 4 entries is
+/// `(height, width, channels)`, any other 3D array a `(pages, height, width)`
+/// stack, and 4D arrays `(pages, height, width, channels)`; arrays of more
+/// dimensions are left out. Only each array's header is inflated.
+#[cfg(feature = "npy")]
+#[wasm_bindgen]
+pub fn npz_list_arrays(data: &[u8]) -> Result<String, JsValue> {
+    let entries = zip_entries(data).map_err(|e| JsValue::from_str(&format!("NPZ: {}", e)))?;
+    let mut items = Vec::new();
+    for entry in entries.iter().filter(|entry| entry.name.ends_with(".npy")) {
+        let header = npz_entry_header(data, entry)
+            .map_err(|e| JsValue::from_str(&format!("NPZ: {}: {}", entry.name, e)))?;
+        let Ok((pages, height, width, channels)) = header.layout(npy_pages_first(&header.shape)) else { continue };
+        let shape: Vec<String> = header.shape.iter().map(usize::to_string).collect();
+        items.push(format!(
+            "{{\"name\":\"{}\",\"dtype\":\"{}\",\"shape\":[{}],\"width\":{},\"height\":{},\"channels\":{},\"pages\":{}}}",
+            json_escape(entry.name.trim_end_matches(".npy")),
+            json_escape(&header.descr),
+            shape.join(","),
+            width,
+            height,
+            channels,
+            pages
+        ));
+    }
+    Ok(format!("[{}]", items.join(",")))
+}
+
+/// Decode page `page_index` of the `.npz` array saved under `name` (as
+/// listed by `npz_list_arrays`; a trailing `.npy` is accepted too). Only that
+/// array is inflated.
+#[cfg(feature = "npy")]
+#[wasm_bindgen]
+pub fn decode_npz_array(data: &[u8], name: &str, page_index: u32) -> Result<TiffResult, JsValue> {
+    let entries = zip_entries(data).map_err(|e| JsValue::from_str(&format!("NPZ: {}", e)))?;
+    let file_name = if name.ends_with(".npy") { name.to_string() } else { format!("{}.npy", name) };
+    let entry = entries
+        .iter()
+        .find(|entry| entry.name == file_name)
+        .ok_or_else(|| JsValue::from_str(&format!("NPZ: no array named '{}'", name)))?;
+    let npy = zip_entry_data(data, entry).map_err(|e| JsValue::from_str(&format!("NPZ: {}: {}", name, e)))?;
+    let (width, height, channels, values) = NpyHeader::parse(&npy)
+        .and_then(|header| decode_npy_page(&npy, &header, page_index as usize, npy_pages_first(&header.shape)))
+        .map_err(|e| JsValue::from_str(&format!("NPZ: {}: {}", name, e)))?;
+    Ok(TiffResult::from_decoded(width, height, channels, values, String::new()))
+}
+
+/// Whether a 3D array is read as a stack of pages rather than as one
+/// multi-channel image (see `npz_list_arrays`).
+#[cfg(feature = "npy")]
+fn npy_pages_first(shape: &[usize]) -> bool {
+    shape.len() == 3 && shape[2] > 4
+}
+
+/// Parse the `.npy` header of an `.npz` entry, inflating only the header.
+#[cfg(feature = "npy")]
+fn npz_entry_header(data: &[u8], entry: &ZipEntry) -> Result<NpyHeader, String> {
+    let prefix = zip_entry_head(data, entry, 12)?;
+    let (header_start, header_len) = npy_header_len(&prefix)?;
+    NpyHeader::parse(&zip_entry_head(data, entry, header_start + header_len)?)
+}
+
+/// One file in a ZIP central directory.
+struct ZipEntry {
+    name: String,
+    method: u16,
+    flags: u16,
+    compressed_size: u64,
+    uncompressed_size: u64,
+    local_header_offset: u64,
+}
+
+fn zip_entry_is_tiff(name: &str) -> bool {
+    let lower = name.to_ascii_lowercase();
+    !lower.ends_with('/')
+        && !lower.starts_with("__macosx/")
+        && [".tif", ".tiff", ".tif.gz", ".tiff.gz"].iter().any(|ext| lower.ends_with(ext))
+}
+
+/// Read the central directory (located through the end-of-central-directory
+/// record at the tail of the archive). ZIP64 archives are not supported.
+fn zip_entries(data: &[u8]) -> Result<Vec<ZipEntry>, String> {
+    let u16_at = |pos: usize| data.get(pos..pos + 2).map(|b| u16::from_le_bytes([b[0], b[1]]));
+    let u32_at = |pos: usize| data.get(pos..pos + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+
+    // The EOCD record is 22 bytes plus an archive comment of up to 64 KiB.
+    const EOCD_SIGNATURE: u32 = 0x0605_4b50;
+    let search_start = data.len().saturating_sub(22 + 0xFFFF);
+    let eocd = (search_start..data.len().saturating_sub(21))
+        .rev()
+        .find(|&pos| u32_at(pos) == Some(EOCD_SIGNATURE))
+        .ok_or("not a ZIP archive (no end of central directory record)")?;
+    let entry_count = u16_at(eocd + 10).ok_or("truncated end of central directory")? as usize;
+    let directory_offset = u32_at(eocd + 16).ok_or("truncated end of central directory")?;
+    if entry_count == 0xFFFF || directory_offset == 0xFFFF_FFFF {
+        return Err("ZIP64 archives are not supported".to_string());
+    }
+
+    let mut entries = Vec::with_capacity(entry_count);
+    let mut pos = directory_offset as usize;
+    for _ in 0..entry_count {
+        if u32_at(pos) != Some(0x0201_4b50) {
+            return Err("corrupt central directory".to_string());
+        }
+        let field = |offset: usize| u16_at(pos + offset).ok_or("truncated central directory");
+        let name_len = field(28)? as usize;
+        let extra_len = field(30)? as usize;
+        let comment_len = field(32)? as usize;
+        let name_start = pos.checked_add(46).ok_or("truncated central directory")?;
+        let name = name_start
+            .checked_add(name_len)
+            .and_then(|name_end| data.get(name_start..name_end))
+            .ok_or("truncated central directory")?;
+        entries.push(ZipEntry {
+            name: String::from_utf8_lossy(name).into_owned(),
+            flags: field(8)?,
+            method: field(10)?,
+            compressed_size: u32_at(pos + 20).ok_or("truncated central directory")? as u64,
+            uncompressed_size: u32_at(pos + 24).ok_or("truncated central directory")? as u64,
+            local_header_offset: u32_at(pos + 42).ok_or("truncated central directory")? as u64,
+        });
+        pos = name_start
+            .checked_add(name_len + extra_len + comment_len)
+            .ok_or("truncated central directory")?;
+    }
+    Ok(entries)
+}
+
+/// The uncompressed bytes of one entry (stored or deflated).
+fn zip_entry_data(data: &[u8], entry: &ZipEntry) -> Result<Vec<u8>, String> {
+    zip_entry_head(data, entry, usize::MAX)
+}
+
+/// At most the first `limit` uncompressed bytes of one entry; a deflated
+/// entry is only inflated as far as that needs, so headers can be peeked at
+/// without inflating whole arrays/images.
+fn zip_entry_head(data: &[u8], entry: &ZipEntry, limit: usize) -> Result<Vec<u8>, String> {
+    use std::io::Read;
+
+    if entry.flags & 1 != 0 {
+        return Err("encrypted entries are not supported".to_string());
+    }
+    // Offsets and sizes come from the file, so every sum is checked: on
+    // wasm32 a crafted archive could otherwise wrap them around.
+    let header = usize::try_from(entry.local_header_offset).map_err(|_| "corrupt local file header")?;
+    let local_header = header
+        .checked_add(30)
+        .and_then(|end| data.get(header..end))
+        .ok_or("truncated local file header")?;
+    if local_header[..4] != [0x50, 0x4b, 0x03, 0x04] {
+        return Err("corrupt local file header".to_string());
+    }
+    // The local header repeats name/extra with its own lengths; sizes come
+    // from the central directory, since streamed archives zero them here.
+    let name_len = u16::from_le_bytes([local_header[26], local_header[27]]) as usize;
+    let extra_len = u16::from_le_bytes([local_header[28], local_header[29]]) as usize;
+    let start = header + 30 + name_len + extra_len;
+    let compressed = usize::try_from(entry.compressed_size)
+        .ok()
+        .and_then(|size| start.checked_add(size))
+        .and_then(|end| data.get(start..end))
+        .ok_or("entry data is truncated")?;
+    match entry.method {
+        0 => Ok(compressed[..compressed.len().min(limit)].to_vec()),
+        8 => {
+            // The declared size is not trusted for the allocation (a crafted
+            // one could ask for gigabytes up front), but it does bound the
+            // inflate: one byte past it is read so a stream that keeps going
+            // (a deflate bomb) is rejected instead of filling memory.
+            let declared = entry.uncompressed_size;
+            let hint = usize::try_from(declared).unwrap_or(usize::MAX);
+            let mut out = Vec::with_capacity(hint.min(limit).min(compressed.len().saturating_mul(4)));
+            flate2::read::DeflateDecoder::new(compressed)
+                .take(declared.min(limit as u64).saturating_add(1))
+                .read_to_end(&mut out)
+                .map_err(|e| format!("inflate failed: {}", e))?;
+            if out.len() as u64 > declared {
+                return Err(format!("entry inflates past its declared size of {} bytes", declared));
+            }
+            out.truncate(limit);
+            Ok(out)
+        }
+        method => Err(format!("compression method {} is not supported", method)),
+    }
+}
+
+/// What `ImageFormat::decode_header` reads without touching the pixels: the
+/// size of the image `decode` returns (after any orientation is applied) and
+/// its channel count and sample type, with `bits_per_sample` and
+/// `sample_format` using the TIFF codes `TiffResult` reports (1 = unsigned,
+/// 2 = signed, 3 = float).
+struct ImageHeader {
+    width: u32,
+    height: u32,
+    channels: u32,
+    bits_per_sample: u32,
+    sample_format: u32,
+}
+
+impl ImageHeader {
+    fn to_json(&self, format: &str) -> String {
+        format!(
+            "{{\"format\":\"{}\",\"width\":{},\"height\":{},\"channels\":{},\"bits_per_sample\":{},\"sample_format\":{}}}",
+            json_escape(format),
+            self.width,
+            self.height,
+            self.channels,
+            self.bits_per_sample,
+            self.sample_format,
+        )
+    }
+}
+
+/// A single-image format `decode_image` can read. Each one is a unit struct
+/// listed in `image_formats`, behind its own Cargo feature (TIFF is always
+/// built), so a custom build carries exactly the decoders it needs and a new
+/// decoder is one impl plus one registry line. Containers are not formats:
+/// `decode_image` inflates gzip itself and refuses ZIP (see
+/// `sniff_container`).
+trait ImageFormat {
+    /// Short lowercase name, as `sniff_image_format` reports it.
+    fn name(&self) -> &'static str;
+    /// Whether `data` starts with this format's signature.
+    fn sniff(&self, data: &[u8]) -> bool;
+    fn decode_header(&self, data: &[u8]) -> Result<ImageHeader, JsValue>;
+    fn decode(&self, data: &[u8]) -> Result<TiffResult, JsValue>;
+}
+
+/// The formats compiled into this build, in sniffing order.
+fn image_formats() -> Vec<&'static dyn ImageFormat> {
+    // Only TIFF is left when every format feature is off.
+    #[allow(unused_mut)]
+    let mut formats: Vec<&'static dyn ImageFormat> = vec![&TiffFormat];
+    #[cfg(feature = "png")]
+    formats.push(&PngFormat);
+    #[cfg(feature = "jpeg")]
+    formats.push(&JpegFormat);
+    #[cfg(feature = "webp")]
+    formats.push(&WebpFormat);
+    #[cfg(feature = "exr")]
+    formats.push(&ExrFormat);
+    #[cfg(feature = "hdr")]
+    formats.push(&HdrFormat);
+    #[cfg(feature = "pfm")]
+    formats.push(&PfmFormat);
+    #[cfg(feature = "npy")]
+    formats.push(&NpyFormat);
+    #[cfg(feature = "flo")]
+    formats.push(&FloFormat);
+    formats
+}
+
+fn find_image_format(data: &[u8]) -> Option<&'static dyn ImageFormat> {
+    image_formats().into_iter().find(|format| format.sniff(data))
+}
+
+/// Wrappers `decode_image` looks through (gzip) or refuses (ZIP, which holds
+/// several images) instead of decoding as a format.
+fn sniff_container(data: &[u8]) -> Option<&'static str> {
+    match data {
+        [0x1f, 0x8b, 0x08, ..] => Some("gzip"),
+        [b'P', b'K', 0x03, 0x04, ..] | [b'P', b'K', 0x05, 0x06, ..] => Some("zip"),
+        _ => None,
+    }
+}
+
+/// The registered format of already-gunzipped `data`, or the error `caller`
+/// reports for a container or unrecognised bytes.
+fn image_format_for(data: &[u8], caller: &str) -> Result<&'static dyn ImageFormat, JsValue> {
+    if let Some(format) = find_image_format(data) {
+        return Ok(format);
+    }
+    Err(match sniff_container(data) {
+        Some("zip") => TiffError::new(TiffErrorKind::Unsupported, format!(
+            "{}: ZIP archives hold several images; use zip_list_tiffs / decode_zip_tiff_page (npz_list_arrays / decode_npz_array for .npz)",
+            caller,
+        )),
+        Some(_) => TiffError::new(TiffErrorKind::Unsupported, format!("{}: nested gzip streams are not supported", caller)),
+        None => TiffError::new(
+            TiffErrorKind::UnrecognizedFormat,
+            format!("{}: unrecognised image format, or one not built into this decoder", caller),
+        ),
+    }
+    .into())
+}
+
+struct TiffFormat;
+
+impl ImageFormat for TiffFormat {
+    fn name(&self) -> &'static str {
+        "tiff"
+    }
+
+    fn sniff(&self, data: &[u8]) -> bool {
+        matches!(data, [b'I', b'I', 0x2a | 0x2b, 0, ..] | [b'M', b'M', 0, 0x2a | 0x2b, ..])
+    }
+
+    fn decode_header(&self, data: &[u8]) -> Result<ImageHeader, JsValue> {
+        let mut decoder = open_tiff_page(data, 0)?;
+        let (width, height) = decoder.dimensions()
+            .map_err(|e| TiffError::from_tiff("Failed to get dimensions", e))?;
+        // Read from the tags rather than `colortype()`, which refuses the
+        // multiband RGB pages `decode_tiff_impl` keeps every band of.
+        let samples_per_pixel = decoder.get_tag_u32(tiff::tags::Tag::SamplesPerPixel).unwrap_or(1);
+        let stored_bits = decoder.get_tag_u64_vec(tiff::tags::Tag::BitsPerSample)
+            .ok()
+            .and_then(|values| values.first().copied())
+            .unwrap_or(1) as u32;
+        // Palette indices are looked up into 8-bit RGB, YCbCr comes back as
+        // RGB, CMYK is converted to RGB (keeping any extra bands) and bilevel
+        // pages are expanded to a byte per pixel.
+        let (channels, bits_per_sample) = match decoder.get_tag_u32(tiff::tags::Tag::PhotometricInterpretation).unwrap_or(1) {
+            3 => (3, 8),
+            6 => (3, stored_bits),
+            5 if samples_per_pixel >= 4 => (samples_per_pixel - 1, stored_bits),
+            _ if stored_bits == 1 => (samples_per_pixel, 8),
+            _ => (samples_per_pixel, stored_bits),
+        };
+        let sample_format = decoder.get_tag_u64_vec(tiff::tags::Tag::SampleFormat)
+            .ok()
+            .and_then(|values| values.first().copied())
+            .unwrap_or(1) as u32;
+        let orientation = TiffOrientation::from_tag(decoder.get_tag_u32(tiff::tags::Tag::Orientation).unwrap_or(1));
+        let (width, height) = if orientation.transposes() { (height, width) } else { (width, height) };
+        Ok(ImageHeader { width, height, channels, bits_per_sample, sample_format })
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<TiffResult, JsValue> {
+        decode_tiff_impl(data, true, 0, false, None)
+    }
+}
+
+#[cfg(feature = "png")]
+struct PngFormat;
+
+#[cfg(feature = "png")]
+impl ImageFormat for PngFormat {
+    fn name(&self) -> &'static str {
+        "png"
+    }
+
+    fn sniff(&self, data: &[u8]) -> bool {
+        data.starts_with(&[0x89, b'P', b'N', b'G'])
+    }
+
+    fn decode_header(&self, data: &[u8]) -> Result<ImageHeader, JsValue> {
+        let mut decoder = png::Decoder::new(Cursor::new(data));
+        decoder.set_transformations(png::Transformations::EXPAND);
+        let reader = decoder.read_info()
+            .map_err(|e| JsValue::from_str(&format!("Failed to read PNG info: {}", e)))?;
+        let (color_type, bit_depth) = reader.output_color_type();
+        Ok(ImageHeader {
+            width: reader.info().width,
+            height: reader.info().height,
+            channels: color_type.samples() as u32,
+            bits_per_sample: bit_depth as u32,
+            sample_format: 1,
+        })
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<TiffResult, JsValue> {
+        decode_png(data)
+    }
+}
+
+#[cfg(feature = "jpeg")]
+struct JpegFormat;
+
+#[cfg(feature = "jpeg")]
+impl ImageFormat for JpegFormat {
+    fn name(&self) -> &'static str {
+        "jpeg"
+    }
+
+    fn sniff(&self, data: &[u8]) -> bool {
+        data.starts_with(&[0xff, 0xd8, 0xff])
+    }
+
+    fn decode_header(&self, data: &[u8]) -> Result<ImageHeader, JsValue> {
+        let mut decoder = zune_jpeg::JpegDecoder::new(Cursor::new(data));
+        decoder.decode_headers()
+            .map_err(|e| JsValue::from_str(&format!("JPEG: failed to read header: {:?}", e)))?;
+        let (width, height) = decoder.dimensions()
+            .ok_or_else(|| JsValue::from_str("JPEG: missing image info"))?;
+        let channels = decoder.output_colorspace()
+            .ok_or_else(|| JsValue::from_str("JPEG: missing image info"))?
+            .num_components() as u32;
+        let exif = decoder.exif().map(|e| e.as_slice()).unwrap_or_default();
+        let orientation = TiffOrientation::from_tag(exif_orientation(exif).unwrap_or(1) as u32);
+        let (width, height) = (width as u32, height as u32);
+        let (width, height) = if orientation.transposes() { (height, width) } else { (width, height) };
+        Ok(ImageHeader { width, height, channels, bits_per_sample: 8, sample_format: 1 })
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<TiffResult, JsValue> {
+        decode_jpeg(data)
+    }
+}
+
+#[cfg(feature = "webp")]
+struct WebpFormat;
+
+#[cfg(feature = "webp")]
+impl ImageFormat for WebpFormat {
+    fn name(&self) -> &'static str {
+        "webp"
+    }
+
+    fn sniff(&self, data: &[u8]) -> bool {
+        matches!(data, [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..])
+    }
+
+    fn decode_header(&self, data: &[u8]) -> Result<ImageHeader, JsValue> {
+        let decoder = image_webp::WebPDecoder::new(Cursor::new(data))
+            .map_err(|e| JsValue::from_str(&format!("WebP: failed to read header: {}", e)))?;
+        let (width, height) = decoder.dimensions();
+        let channels = if decoder.has_alpha() { 4 } else { 3 };
+        Ok(ImageHeader { width, height, channels, bits_per_sample: 8, sample_format: 1 })
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<TiffResult, JsValue> {
+        decode_webp(data)
+    }
+}
+
+#[cfg(feature = "exr")]
+struct ExrFormat;
+
+#[cfg(feature = "exr")]
+impl ImageFormat for ExrFormat {
+    fn name(&self) -> &'static str {
+        "exr"
+    }
+
+    fn sniff(&self, data: &[u8]) -> bool {
+        data.starts_with(&[0x76, 0x2f, 0x31, 0x01])
+    }
+
+    fn decode_header(&self, data: &[u8]) -> Result<ImageHeader, JsValue> {
+        let meta = exr::meta::MetaData::read_from_buffered(Cursor::new(data), false)
+            .map_err(|e| JsValue::from_str(&format!("Failed to read EXR header: {}", e)))?;
+        // `decode_exr_impl` reads the first flat layer.
+        let header = meta.headers.iter()
+            .find(|header| !header.deep)
+            .ok_or_else(|| JsValue::from_str("EXR has no flat layers"))?;
+        let channel_names: Vec<String> = header.channels.list.iter().map(|channel| channel.name.to_string()).collect();
+        let channels = select_exr_display_channels(&channel_names).source_indices.len() as u32;
+        if channels == 0 {
+            return Err(JsValue::from_str("EXR has no displayable channels"));
+        }
+        Ok(ImageHeader {
+            width: header.layer_size.0 as u32,
+            height: header.layer_size.1 as u32,
+            channels,
+            bits_per_sample: 32,
+            sample_format: 3,
+        })
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<TiffResult, JsValue> {
+        let exr = decode_exr_impl(data)?;
+        let mut result = TiffResult::from_decoded(
+            exr.width,
+            exr.height,
+            exr.channels,
+            DecodingResult::F32(exr.data_f32),
+            exr.all_tags_json,
+        );
+        result.primaries = exr.primaries.filter(|primaries| rgb_to_xyz_matrix(primaries).is_some());
+        result.page_metadata.channel_names = exr.displayed_channels_csv.split(',').map(str::to_string).collect();
+        Ok(result)
+    }
+}
+
+#[cfg(feature = "hdr")]
+struct HdrFormat;
+
+#[cfg(feature = "hdr")]
+impl ImageFormat for HdrFormat {
+    fn name(&self) -> &'static str {
+        "hdr"
+    }
+
+    fn sniff(&self, data: &[u8]) -> bool {
+        data.starts_with(b"#?RADIANCE") || data.starts_with(b"#?RGBE")
+    }
+
+    fn decode_header(&self, data: &[u8]) -> Result<ImageHeader, JsValue> {
+        let header = read_hdr_header(data)?;
+        Ok(ImageHeader {
+            width: header.width as u32,
+            height: header.height as u32,
+            channels: 3,
+            bits_per_sample: 32,
+            sample_format: 3,
+        })
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<TiffResult, JsValue> {
+        let hdr = decode_hdr_impl(data)?;
+        let (width, height) = (hdr.metadata_f64[0] as u32, hdr.metadata_f64[1] as u32);
+        // The HDR decoder emits RGBA with a constant 1.0 alpha for the
+        // WebGL path; drop it.
+        let rgb: Vec<f32> = hdr.data_f32.chunks_exact(4).flat_map(|px| [px[0], px[1], px[2]]).collect();
+        Ok(TiffResult::from_decoded(width, height, 3, DecodingResult::F32(rgb), hdr.all_tags_json))
+    }
+}
+
+#[cfg(feature = "pfm")]
+struct PfmFormat;
+
+#[cfg(feature = "pfm")]
+impl ImageFormat for PfmFormat {
+    fn name(&self) -> &'static str {
+        "pfm"
+    }
+
+    fn sniff(&self, data: &[u8]) -> bool {
+        matches!(data, [b'P', b'F' | b'f', b'\n' | b'\r' | b' ' | b'\t', ..])
+    }
+
+    fn decode_header(&self, data: &[u8]) -> Result<ImageHeader, JsValue> {
+        let header = read_pfm_header(data).map_err(|e| JsValue::from_str(&format!("PFM: {}", e)))?;
+        Ok(ImageHeader {
+            width: header.width,
+            height: header.height,
+            channels: header.channels,
+            bits_per_sample: 32,
+            sample_format: 3,
+        })
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<TiffResult, JsValue> {
+        let (width, height, channels, values) = decode_pfm(data).map_err(|e| JsValue::from_str(&format!("PFM: {}", e)))?;
+        Ok(TiffResult::from_decoded(width, height, channels, DecodingResult::F32(values), String::new()))
+    }
+}
+
+#[cfg(feature = "npy")]
+struct NpyFormat;
+
+#[cfg(feature = "npy")]
+impl ImageFormat for NpyFormat {
+    fn name(&self) -> &'static str {
+        "npy"
+    }
+
+    fn sniff(&self, data: &[u8]) -> bool {
+        data.starts_with(&[0x93, b'N', b'U', b'M', b'P', b'Y'])
+    }
+
+    fn decode_header(&self, data: &[u8]) -> Result<ImageHeader, JsValue> {
+        let npy_error = |e: String| JsValue::from_str(&format!("NPY: {}", e));
+        let header = NpyHeader::parse(data).map_err(npy_error)?;
+        let (_, height, width, channels) = header.layout(false).map_err(npy_error)?;
+        let (_, kind, size) = header.dtype().map_err(npy_error)?;
+        // Half floats are widened to f32 on decode.
+        let (bits_per_sample, sample_format) = match kind {
+            b'f' => ((size.max(4) * 8) as u32, 3),
+            b'i' => ((size * 8) as u32, 2),
+            _ => ((size * 8) as u32, 1),
+        };
+        Ok(ImageHeader {
+            width: width as u32,
+            height: height as u32,
+            channels: channels as u32,
+            bits_per_sample,
+            sample_format,
+        })
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<TiffResult, JsValue> {
+        let (width, height, channels, values) = decode_npy(data).map_err(|e| JsValue::from_str(&format!("NPY: {}", e)))?;
+        Ok(TiffResult::from_decoded(width, height, channels, values, String::new()))
+    }
+}
+
+#[cfg(feature = "flo")]
+struct FloFormat;
+
+#[cfg(feature = "flo")]
+impl ImageFormat for FloFormat {
+    fn name(&self) -> &'static str {
+        "flo"
+    }
+
+    fn sniff(&self, data: &[u8]) -> bool {
+        data.starts_with(b"PIEH")
+    }
+
+    fn decode_header(&self, data: &[u8]) -> Result<ImageHeader, JsValue> {
+        let (width, height) = flo_dimensions(data).map_err(|e| JsValue::from_str(&format!("FLO: {}", e)))?;
+        Ok(ImageHeader { width, height, channels: 2, bits_per_sample: 32, sample_format: 3 })
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<TiffResult, JsValue> {
+        decode_flo(data)
+    }
+}
+
+/// The format `decode_image` would treat `data` as ("tiff", "exr", "hdr",
+/// "pfm", "npy", "flo", "png", "jpeg", "webp", "zip", "gzip"), or
+/// "" if unrecognised or not built into this decoder (see
+/// `supported_image_formats`).
+/// Gzip is reported as such, not as the format inside it.
+#[wasm_bindgen]
+pub fn sniff_image_format(data: &[u8]) -> String {
+    sniff_container(data)
+        .or_else(|| find_image_format(data).map(|format| format.name()))
+        .unwrap_or("")
+        .to_string()
+}
+
+/// JSON array of the format names `decode_image` accepts in this build
+/// (e.g. `["tiff","png","jpeg"]`), so a custom build compiled with only some
+/// of the format features can tell the UI which files it may send here.
+#[wasm_bindgen]
+pub fn supported_image_formats() -> String {
+    let names: Vec<String> = image_formats()
+        .iter()
+        .map(|format| format!("\"{}\"", json_escape(format.name())))
+        .collect();
+    format!("[{}]", names.join(","))
+}
+
+/// What `decode_image` would return for `data`, without decoding the pixels:
+/// `{"format","width","height","channels","bits_per_sample","sample_format"}`
+/// with the size after orientation and the sample layout of the decoded
+/// result (e.g. palette PNG/TIFF as 8-bit RGB, EXR/HDR as f32), so callers
+/// can size buffers or choose a view before paying for the decode.
+#[wasm_bindgen]
+pub fn decode_image_header(data: &[u8]) -> Result<String, JsValue> {
+    #[cfg(feature = "console_error_panic_hook")]
+    console_error_panic_hook::set_once();
+
+    let data = gunzip_if_needed(data)?;
+    let data: &[u8] = &data;
+    let format = image_format_for(data, "decode_image_header")?;
+    Ok(format.decode_header(data)?.to_json(format.name()))
+}
+
+/// Decode any supported single image by its magic bytes (TIFF page 0, PNG,
+/// JPEG, WebP, OpenEXR, Radiance HDR, PFM, NPY, Middlebury .flo; gzipped
+/// input is inflated first) into a `TiffResult`, so the JS side has one call
+/// and one result type for stats, normalization and pixel inspection
+/// regardless of format. OpenEXR comes back as f32 with the channels
+/// `decode_exr_fast` selects, HDR as f32 RGB, PNG/JPEG/WebP/.flo as in
+/// `decode_png` / `decode_jpeg` / `decode_webp` / `decode_flo` and PFM/NPY
+/// with their stored sample type; all are top-left origin.
+/// `all_tags_json` carries the format's own header/attribute dump where one
+/// exists. Formats left out of the build (see `image_formats`) are reported
+/// as unrecognised.
+#[wasm_bindgen]
+pub fn decode_image(data: &[u8]) -> Result<TiffResult, JsValue> {
+    #[cfg(feature = "console_error_panic_hook")]
+    console_error_panic_hook::set_once();
+
+    let data = gunzip_if_needed(data)?;
+    let data: &[u8] = &data;
+    image_format_for(data, "decode_image")?.decode(data).map_err(|e| TiffError::from_js(e).into())
+}
+
+/// The ASCII header of a portable float map: "PF" RGB / "Pf" gray, width,
+/// height, and a scale whose sign gives the byte order (negative = little
+/// endian).
+#[cfg(feature = "pfm")]
+struct PfmHeader {
+    width: u32,
+    height: u32,
+    channels: u32,
+    little_endian: bool,
+    /// Offset of the first raster byte.
+    data_start: usize,
+}
+
+#[cfg(feature = "pfm")]
+fn read_pfm_header(data: &[u8]) -> Result<PfmHeader, String> {
+    let mut pos = 0usize;
+    let mut tokens = Vec::with_capacity(4);
+    while tokens.len() < 4 {
+        while data.get(pos).is_some_and(|b| b.is_ascii_whitespace()) {
+            pos += 1;
+        }
+        let start = pos;
+        while data.get(pos).is_some_and(|b| !b.is_ascii_whitespace()) {
+            pos += 1;
+        }
+        if start == pos {
+            return Err("truncated header".to_string());
+        }
+        tokens.push(String::from_utf8_lossy(&data[start..pos]).into_owned());
+    }
+
+    let channels = match tokens[0].as_str() {
+        "PF" => 3,
+        "Pf" => 1,
+        other => return Err(format!("unknown identifier '{}'", other)),
+    };
+    let width: u32 = tokens[1].parse().map_err(|_| "bad width".to_string())?;
+    let height: u32 = tokens[2].parse().map_err(|_| "bad height".to_string())?;
+    let scale: f64 = tokens[3].parse().map_err(|_| "bad scale".to_string())?;
+    Ok(PfmHeader {
+        width,
+        height,
+        channels,
+        little_endian: scale < 0.0,
+        // Exactly one whitespace byte separates the scale from the raster.
+        data_start: pos + 1,
+    })
+}
+
+/// Portable float map (see `PfmHeader`): f32 rows stored bottom-to-top,
+/// flipped here to top-left origin.
+#[cfg(feature = "pfm")]
+fn decode_pfm(data: &[u8]) -> Result<(u32, u32, u32, Vec<f32>), String> {
+    let PfmHeader { width, height, channels, little_endian, data_start } = read_pfm_header(data)?;
+    let row_samples = (width as usize) * channels as usize;
+    let raster = data
+        .get(data_start..data_start + row_samples * height as usize * 4)
+        .ok_or("raster is truncated")?;
+    let mut values = Vec::with_capacity(row_samples * height as usize);
+    for row in raster.chunks_exact(row_samples * 4).rev() {
+        values.extend(row.chunks_exact(4).map(|b| {
+            let bytes = [b[0], b[1], b[2], b[3]];
+            if little_endian { f32::from_le_bytes(bytes) } else { f32::from_be_bytes(bytes) }
+        }));
+    }
+    Ok((width, height, channels, values))
+}
+
+/// A parsed `TiffResult::set_value_transform` expression and the min/max of
+/// the samples it produces.
+#[derive(Clone)]
+struct ValueTransform {
+    source: String,
+    expr: ValueExpr,
+}
+
+/// Expression tree of a value transform, evaluated per sample with `v`
+/// bound to the sample.
+#[derive(Clone)]
+enum ValueExpr {
+    Value,
+    Const(f64),
+    Neg(Box<ValueExpr>),
+    Binary(char, Box<ValueExpr>, Box<ValueExpr>),
+    Call(&'static str, Vec<ValueExpr>),
+}
+
+impl ValueExpr {
+    /// Parse an expression of `v`, with an optional `v =>` / `(v) =>` prefix.
+    fn parse(source: &str) -> Result<ValueExpr, String> {
+        let mut text = source.trim();
+        if let Some(position) = text.find("=>") {
+            let parameter = text[..position].trim().trim_start_matches('(').trim_end_matches(')').trim();
+            if parameter != "v" {
+                return Err(format!("the arrow function's parameter must be named v, not {:?}", parameter));
+            }
+            text = text[position + 2..].trim();
+        }
+        let mut parser = ValueExprParser { chars: text.chars().collect(), pos: 0 };
+        let expr = parser.sum()?;
+        parser.skip_spaces();
+        if parser.pos < parser.chars.len() {
+            return Err(format!("unexpected {:?} at position {}", parser.chars[parser.pos], parser.pos));
+        }
+        Ok(expr)
+    }
+
+    fn eval(&self, v: f64) -> f64 {
+        match self {
+            ValueExpr::Value => v,
+            ValueExpr::Const(c) => *c,
+            ValueExpr::Neg(a) => -a.eval(v),
+            ValueExpr::Binary(op, a, b) => {
+                let (a, b) = (a.eval(v), b.eval(v));
+                match op {
+                    '+' => a + b,
+                    '-' => a - b,
+                    '*' => a * b,
+                    '/' => a / b,
+                    '%' => a % b,
+                    _ => a.powf(b),
+                }
+            }
+            ValueExpr::Call(name, args) => {
+                let x = args[0].eval(v);
+                let arg = |i: usize| args[i].eval(v);
+                match *name {
+                    "abs" => x.abs(),
+                    "sqrt" => x.sqrt(),
+                    "cbrt" => x.cbrt(),
+                    "exp" => x.exp(),
+                    "ln" | "log" => x.ln(),
+                    "log2" => x.log2(),
+                    "log10" => x.log10(),
+                    "sin" => x.sin(),
+                    "cos" => x.cos(),
+                    "tan" => x.tan(),
+                    "floor" => x.floor(),
+                    "ceil" => x.ceil(),
+                    "round" => x.round(),
+                    "min" => x.min(arg(1)),
+                    "max" => x.max(arg(1)),
+                    "pow" => x.powf(arg(1)),
+                    _ => x.max(arg(1)).min(arg(2)),
+                }
+            }
+        }
+    }
+}
+
+/// Recursive-descent parser behind `ValueExpr::parse`: sums of products of
+/// right-associative powers of unary-signed atoms.
+struct ValueExprParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl ValueExprParser {
+    const FUNCTIONS: [(&'static str, usize); 18] = [
+        ("abs", 1), ("sqrt", 1), ("cbrt", 1), ("exp", 1), ("ln", 1), ("log", 1), ("log2", 1), ("log10", 1),
+        ("sin", 1), ("cos", 1), ("tan", 1), ("floor", 1), ("ceil", 1), ("round", 1),
+        ("min", 2), ("max", 2), ("pow", 2), ("clamp", 3),
+    ];
+
+    fn skip_spaces(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    /// Consume `c` (after whitespace) if it is next.
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_spaces();
+        if self.chars.get(self.pos) == Some(&c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn sum(&mut self) -> Result<ValueExpr, String> {
+        let mut expr = self.product()?;
+        loop {
+            let op = if self.eat('+') { '+' } else if self.eat('-') { '-' } else { return Ok(expr) };
+            expr = ValueExpr::Binary(op, Box::new(expr), Box::new(self.product()?));
+        }
+    }
+
+    fn product(&mut self) -> Result<ValueExpr, String> {
+        let mut expr = self.unary()?;
+        loop {
+            self.skip_spaces();
+            let op = match self.chars.get(self.pos) {
+                Some('*') if self.chars.get(self.pos + 1) != Some(&'*') => '*',
+                Some('/') => '/',
+                Some('%') => '%',
+                _ => return Ok(expr),
+            };
+            self.pos += 1;
+            expr = ValueExpr::Binary(op, Box::new(expr), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<ValueExpr, String> {
+        if self.eat('-') {
+            return Ok(ValueExpr::Neg(Box::new(self.unary()?)));
+        }
+        if self.eat('+') {
+            return self.unary();
+        }
+        let base = self.atom()?;
+        self.skip_spaces();
+        if self.eat('^') || (self.chars.get(self.pos) == Some(&'*') && self.chars.get(self.pos + 1) == Some(&'*')) {
+            if self.chars.get(self.pos) == Some(&'*') {
+                self.pos += 2;
+            }
+            return Ok(ValueExpr::Binary('^', Box::new(base), Box::new(self.unary()?)));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<ValueExpr, String> {
+        self.skip_spaces();
+        let start = self.pos;
+        match self.chars.get(self.pos) {
+            None => Err("unexpected end of expression".to_string()),
+            Some('(') => {
+                self.pos += 1;
+                let expr = self.sum()?;
+                if !self.eat(')') {
+                    return Err(format!("missing ')' for the '(' at position {}", start));
+                }
+                Ok(expr)
+            }
+            Some(c) if c.is_ascii_digit() || *c == '.' => {
+                while self.chars.get(self.pos).is_some_and(|c| c.is_ascii_digit() || *c == '.') {
+                    self.pos += 1;
+                }
+                if matches!(self.chars.get(self.pos), Some('e' | 'E')) {
+                    let mut end = self.pos + 1;
+                    if matches!(self.chars.get(end), Some('+' | '-')) {
+                        end += 1;
+                    }
+                    if self.chars.get(end).is_some_and(|c| c.is_ascii_digit()) {
+                        self.pos = end;
+                        while self.chars.get(self.pos).is_some_and(|c| c.is_ascii_digit()) {
+                            self.pos += 1;
+                        }
+                    }
+                }
+                let text: String = self.chars[start..self.pos].iter().collect();
+                text.parse().map(ValueExpr::Const).map_err(|_| format!("invalid number {:?}", text))
+            }
+            Some(c) if c.is_ascii_alphabetic() => {
+                while self.chars.get(self.pos).is_some_and(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '.') {
+                    self.pos += 1;
+                }
+                let text: String = self.chars[start..self.pos].iter().collect();
+                // Pasted JS arrow functions spell these Math.sqrt, Math.PI, ...
+                let name = text.strip_prefix("Math.").unwrap_or(&text);
+                match name {
+                    "v" => return Ok(ValueExpr::Value),
+                    "pi" | "PI" => return Ok(ValueExpr::Const(std::f64::consts::PI)),
+                    "e" | "E" => return Ok(ValueExpr::Const(std::f64::consts::E)),
+                    _ => {}
+                }
+                let &(function, arity) = Self::FUNCTIONS
+                    .iter()
+                    .find(|(function, _)| *function == name)
+                    .ok_or_else(|| format!("unknown name {:?}", name))?;
+                if !self.eat('(') {
+                    return Err(format!("{} needs parenthesised arguments", function));
+                }
+                let mut args = vec![self.sum()?];
+                while self.eat(',') {
+                    args.push(self.sum()?);
+                }
+                if !self.eat(')') {
+                    return Err(format!("missing ')' after the arguments of {}", function));
+                }
+                if args.len() != arity {
+                    return Err(format!("{} takes {} argument(s), got {}", function, arity, args.len()));
+                }
+                Ok(ValueExpr::Call(function, args))
+            }
+            Some(c) => Err(format!("unexpected {:?} at position {}", c, self.pos)),
+        }
+    }
+}
+
+/// Red from the left view, green and blue from the right one, per pixel of
+/// two equally shaped interleaved buffers (first channel only when there are
+/// fewer than three).
+fn anaglyph(left: &[f32], right: &[f32], channels: usize) -> Vec<f32> {
+    let mut out = Vec::with_capacity(left.len() / channels * 3);
+    for (l, r) in left.chunks_exact(channels).zip(right.chunks_exact(channels)) {
+        if channels >= 3 {
+            out.extend_from_slice(&[l[0], r[1], r[2]]);
+        } else {
+            out.extend_from_slice(&[l[0], r[0], r[0]]);
+        }
+    }
+    out
+}
+
+/// Write into `out[order[k]]` the entry of the sorted `targets` at the
+/// quantile of rank `k`, where `order` lists sample indices sorted by value.
+/// A run of equal values takes the quantile of its middle rank, so it maps
+/// to one value.
+fn match_quantiles(order: &[usize], samples: &[f32], targets: &[f32], out: &mut [f32]) {
+    let mut start = 0;
+    while start < order.len() {
+        let value = samples[order[start]];
+        let end = start + order[start..].iter().take_while(|&&i| samples[i] == value).count();
+        let quantile = (start + end) as f64 / 2.0 / order.len() as f64;
+        let target = targets[((quantile * targets.len() as f64) as usize).min(targets.len() - 1)];
+        for &i in &order[start..end] {
+            out[i] = target;
+        }
+        start = end;
+    }
+}
+
+/// Middlebury `.flo`: the tag 202021.25 as an f32 ("PIEH"), i32 width and
+/// height, then interleaved little-endian f32 (u, v) pairs, row-major from
+/// the top. Components beyond 1e9 are the format's "unknown flow" marker and
+/// become NaN.
+#[cfg(feature = "flo")]
+fn parse_flo(data: &[u8]) -> Result<(u32, u32, Vec<f32>), String> {
+    let (width, height) = flo_dimensions(data)?;
+    let end = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|pixels| pixels.checked_mul(2 * 4))
+        .and_then(|len| len.checked_add(12))
+        .ok_or_else(|| format!("invalid dimensions {}x{}", width, height))?;
+    let raw = data.get(12..end).ok_or("flow data is truncated")?;
+    let values = raw
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .map(|v| if v.abs() > 1e9 { f32::NAN } else { v })
+        .collect();
+    Ok((width, height, values))
+}
+
+/// Width and height from the 12-byte `.flo` header.
+#[cfg(feature = "flo")]
+fn flo_dimensions(data: &[u8]) -> Result<(u32, u32), String> {
+    let i32_at = |pos: usize| data.get(pos..pos + 4).map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+    if data.get(0..4) != Some(&b"PIEH"[..]) {
+        return Err("missing PIEH tag".to_string());
+    }
+    let (width, height) = (i32_at(4).ok_or("truncated header")?, i32_at(8).ok_or("truncated header")?);
+    if width <= 0 || height <= 0 {
+        return Err(format!("invalid dimensions {}x{}", width, height));
+    }
+    Ok((width as u32, height as u32))
+}
+
+/// Every name `display_colormap_table` knows, in the webview's
+/// `COLORMAP_NAMES` order (the picker's order).
+const DISPLAY_COLORMAP_NAMES: [&str; 9] = ["viridis", "plasma", "inferno", "magma", "jet", "hot", "cool", "turbo", "gray"];
+
+/// JSON array of the display colormap names `render_rgba` accepts, in the
+/// order `TiffResult::colormap_previews` stacks its thumbnails.
+#[wasm_bindgen]
+pub fn display_colormap_names() -> String {
+    let names: Vec<String> = DISPLAY_COLORMAP_NAMES.iter().map(|name| format!("\"{}\"", name)).collect();
+    format!("[{}]", names.join(","))
+}
+
+/// The webview's 256-entry display colormaps (`media/modules/colormaps.ts`),
+/// built the same way - matplotlib control points linearly interpolated, or
+/// the piecewise jet/hot/cool/gray ramps - so snapshots match the screen.
+fn display_colormap_table(name: &str) -> Option<Vec<[u8; 3]>> {
+    const VIRIDIS: [[f64; 3]; 11] = [
+        [0.267004, 0.004874, 0.329415], [0.282623, 0.140926, 0.457517],
+        [0.253935, 0.265254, 0.529983], [0.206756, 0.371758, 0.553117],
+        [0.163625, 0.471133, 0.558148], [0.127568, 0.566949, 0.550556],
+        [0.134692, 0.658636, 0.517649], [0.266941, 0.748751, 0.440573],
+        [0.477504, 0.821444, 0.318195], [0.741388, 0.873449, 0.149561],
+        [0.993248, 0.906157, 0.143936],
+    ];
+    const PLASMA: [[f64; 3]; 10] = [
+        [0.050383, 0.029803, 0.527975], [0.287076, 0.010384, 0.627010],
+        [0.476230, 0.011158, 0.657865], [0.647257, 0.125289, 0.593542],
+        [0.785914, 0.274290, 0.472908], [0.877850, 0.439704, 0.345067],
+        [0.936213, 0.605205, 0.231465], [0.972355, 0.771125, 0.155626],
+        [0.994617, 0.938336, 0.165141], [0.987053, 0.991438, 0.749504],
+    ];
+    const INFERNO: [[f64; 3]; 10] = [
+        [0.001462, 0.000466, 0.013866], [0.094329, 0.042852, 0.225802],
+        [0.239903, 0.067979, 0.343397], [0.412470, 0.102815, 0.380271],
+        [0.591217, 0.155410, 0.347824], [0.758643, 0.237267, 0.275196],
+        [0.889650, 0.360829, 0.210001], [0.969788, 0.514135, 0.186861],
+        [0.994738, 0.683489, 0.240902], [0.988362, 0.998364, 0.644924],
+    ];
+    const MAGMA: [[f64; 3]; 10] = [
+        [0.001462, 0.000466, 0.013866], [0.091904, 0.051667, 0.200303],
+        [0.234547, 0.090739, 0.348341], [0.408198, 0.131574, 0.416555],
+        [0.595732, 0.180653, 0.421399], [0.776405, 0.266630, 0.373397],
+        [0.924010, 0.406370, 0.330720], [0.987622, 0.583041, 0.382914],
+        [0.996212, 0.771453, 0.543135], [0.987053, 0.991438, 0.749504],
+    ];
+    const TURBO: [[f64; 3]; 10] = [
+        [0.18995, 0.07176, 0.23217], [0.25107, 0.25237, 0.63374],
+        [0.19659, 0.47276, 0.82300], [0.12756, 0.66813, 0.82565],
+        [0.13094, 0.82030, 0.65899], [0.37408, 0.92478, 0.41642],
+        [0.66987, 0.95987, 0.19659], [0.90842, 0.87640, 0.10899],
+        [0.98999, 0.64450, 0.03932], [0.93702, 0.25023, 0.01583],
+    ];
+
+    let byte = |value: f64| (value * 255.0).round() as u8;
+    let interpolate = |points: &[[f64; 3]]| -> Vec<[u8; 3]> {
+        (0..256)
+            .map(|i| {
+                let position = (i as f64 / 255.0) * (points.len() - 1) as f64;
+                let index = position.floor() as usize;
+                let frac = position - index as f64;
+                let (c1, c2) = (points[index.min(points.len() - 1)], points[(index + 1).min(points.len() - 1)]);
+                [0, 1, 2].map(|k| byte(c1[k] * (1.0 - frac) + c2[k] * frac))
+            })
+            .collect()
+    };
+    let ramp = |f: fn(f64) -> [f64; 3]| -> Vec<[u8; 3]> { (0..256).map(|i| f(i as f64 / 255.0).map(byte)).collect() };
+
+    Some(match name {
+        "viridis" => interpolate(&VIRIDIS),
+        "plasma" => interpolate(&PLASMA),
+        "inferno" => interpolate(&INFERNO),
+        "magma" => interpolate(&MAGMA),
+        "turbo" => interpolate(&TURBO),
+        "gray" | "grayscale" => (0..256).map(|i| [i as u8; 3]).collect(),
+        "jet" => ramp(|v| {
+            if v < 0.125 {
+                [0.0, 0.0, 0.5 + v * 4.0]
+            } else if v < 0.375 {
+                [0.0, (v - 0.125) * 4.0, 1.0]
+            } else if v < 0.625 {
+                [(v - 0.375) * 4.0, 1.0, 1.0 - (v - 0.375) * 4.0]
+            } else if v < 0.875 {
+                [1.0, 1.0 - (v - 0.625) * 4.0, 0.0]
+            } else {
+                [1.0 - (v - 0.875) * 4.0, 0.0, 0.0]
+            }
+        }),
+        "hot" => ramp(|v| {
+            if v < 0.33 {
+                [v / 0.33, 0.0, 0.0]
+            } else if v < 0.66 {
+                [1.0, (v - 0.33) / 0.33, 0.0]
+            } else {
+                [1.0, 1.0, (v - 0.66) / 0.34]
+            }
+        }),
+        "cool" => ramp(|v| [v, 1.0 - v, 1.0]),
+        _ => return None,
+    })
+}
+
+/// The 55-entry RGB color wheel of the Middlebury flow benchmark
+/// (`computeColor` in its reference code): red-yellow-green-cyan-blue-
+/// magenta segments of 15, 6, 4, 11, 13 and 6 steps.
+fn flow_color_wheel_table() -> Vec<[u8; 3]> {
+    const SEGMENTS: [usize; 6] = [15, 6, 4, 11, 13, 6];
+    let mut wheel = Vec::with_capacity(SEGMENTS.iter().sum());
+    for (segment, &steps) in SEGMENTS.iter().enumerate() {
+        for i in 0..steps {
+            let rising = (255 * i / steps) as u8;
+            let falling = 255 - rising;
+            wheel.push(match segment {
+                0 => [255, rising, 0],
+                1 => [falling, 255, 0],
+                2 => [0, 255, rising],
+                3 => [0, falling, 255],
+                4 => [rising, 0, 255],
+                _ => [255, 0, falling],
+            });
+        }
+    }
+    wheel
+}
+
+/// NumPy `.npy` (format versions 1-3): a 2D `(height, width)` or 3D
+/// `(height, width, channels)` array (1D arrays read as a single row) of
+/// bool/int/uint/float samples in either byte order or Fortran order, kept
+/// in its own sample type except f16, which is widened to f32. 4D
+/// `(pages, height, width, channels)` stacks yield their first page.
+#[cfg(feature = "npy")]
+fn decode_npy(data: &[u8]) -> Result<(u32, u32, u32, DecodingResult), String> {
+    let header = NpyHeader::parse(data)?;
+    decode_npy_page(data, &header, 0, false)
+}
+
+/// The parts of a `.npy` header the decoder needs.
+#[cfg(feature = "npy")]
+struct NpyHeader {
+    descr: String,
+    fortran_order: bool,
+    shape: Vec<usize>,
+    /// Offset of the first array element.
+    data_start: usize,
+}
+
+#[cfg(feature = "npy")]
+impl NpyHeader {
+    /// Parse the magic, version and dict header. `data` only has to reach
+    /// the end of the header (see `npy_header_len`).
+    fn parse(data: &[u8]) -> Result<NpyHeader, String> {
+        let (header_start, header_len) = npy_header_len(data)?;
+        let header = data
+            .get(header_start..header_start + header_len)
+            .map(String::from_utf8_lossy)
+            .ok_or("truncated header")?;
+
+        // The header is a Python dict literal, e.g.
+        // {'descr': '<f4', 'fortran_order': False, 'shape': (480, 640), }
+        let value_after = |key: &str| header.find(key).map(|at| header[at + key.len()..].trim_start_matches([':', ' ']));
+        let descr = value_after("'descr'")
+            .and_then(|rest| rest.strip_prefix('\''))
+            .and_then(|rest| rest.split('\'').next())
+            .ok_or("missing descr")?;
+        let fortran_order = value_after("'fortran_order'").is_some_and(|rest| rest.starts_with("True"));
+        let shape: Vec<usize> = value_after("'shape'")
+            .and_then(|rest| rest.strip_prefix('('))
+            .and_then(|rest| rest.split(')').next())
+            .ok_or("missing shape")?
+            .split(',')
+            .map(str::trim)
+            .filter(|dim| !dim.is_empty())
+            .map(|dim| dim.parse::<usize>().map_err(|_| format!("bad shape dimension '{}'", dim)))
+            .collect::<Result<_, _>>()?;
+        Ok(NpyHeader {
+            descr: descr.to_string(),
+            fortran_order,
+            shape,
+            data_start: header_start + header_len,
+        })
+    }
+
+    /// Byte order (true = little endian), kind character and element size
+    /// of `descr`, e.g. `<f4` -> (true, b'f', 4).
+    fn dtype(&self) -> Result<(bool, u8, usize), String> {
+        match self.descr.as_bytes() {
+            [order @ (b'<' | b'>' | b'|' | b'='), kind, size @ ..] => {
+                let size: usize = std::str::from_utf8(size).ok().and_then(|s| s.parse().ok()).ok_or("bad dtype size")?;
+                Ok((*order != b'>', *kind, size))
+            }
+            _ => Err(format!("unsupported dtype '{}'", self.descr)),
+        }
+    }
+
+    /// `(pages, height, width, channels)` for the array. A 3D array is
+    /// `(height, width, channels)` unless `pages_first`, in which case it is
+    /// a `(pages, height, width)` stack of single-channel images.
+    fn layout(&self, pages_first: bool) -> Result<(usize, usize, usize, usize), String> {
+        Ok(match self.shape[..] {
+            [width] => (1, 1, width, 1),
+            [height, width] => (1, height, width, 1),
+            [pages, height, width] if pages_first => (pages, height, width, 1),
+            [height, width, channels] => (1, height, width, channels),
+            [pages, height, width, channels] => (pages, height, width, channels),
+            _ => return Err(format!("{}D arrays are not supported", self.shape.len())),
+        })
+    }
+}
+
+/// `(header_start, header_len)` of a `.npy` dict header, from the first 12
+/// bytes of the file.
+#[cfg(feature = "npy")]
+fn npy_header_len(data: &[u8]) -> Result<(usize, usize), String> {
+    let (header_len, header_start) = match data.get(6) {
+        Some(1) => (data.get(8..10).map(|b| u16::from_le_bytes([b[0], b[1]]) as usize), 10),
+        Some(2 | 3) => (data.get(8..12).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize), 12),
+        _ => return Err("unsupported format version".to_string()),
+    };
+    Ok((header_start, header_len.ok_or("truncated header")?))
+}
+
+/// Decode page `page_index` of a `.npy` array laid out as `header.layout`
+/// describes, as `(width, height, channels, samples)`.
+#[cfg(feature = "npy")]
+fn decode_npy_page(
+    data: &[u8],
+    header: &NpyHeader,
+    page_index: usize,
+    pages_first: bool,
+) -> Result<(u32, u32, u32, DecodingResult), String> {
+    let (pages, height, width, channels) = header.layout(pages_first)?;
+    if page_index >= pages {
+        return Err(format!("page {} out of range (array has {})", page_index, pages));
+    }
+
+    let (little_endian, kind, size) = header.dtype()?;
+
+    let count = height
+        .checked_mul(width)
+        .and_then(|pixels| pixels.checked_mul(channels))
+        .ok_or("invalid dimensions")?;
+    let page_len = count.checked_mul(size).ok_or("invalid dimensions")?;
+    let end = pages
+        .checked_mul(page_len)
+        .and_then(|len| len.checked_add(header.data_start))
+        .ok_or("invalid dimensions")?;
+    let raw = data.get(header.data_start..end).ok_or("array data is truncated")?;
+    // Fortran order stores the first index fastest; gather whole elements of
+    // the page into C (row-major, channels innermost) order before typing
+    // them. C order pages are contiguous already.
+    let raw: Cow<[u8]> = if header.fortran_order && pages * count > 1 {
+        let (page_stride, y_stride, x_stride, c_stride) = (1, pages, pages * height, pages * height * width);
+        let mut reordered = vec![0u8; page_len];
+        for y in 0..height {
+            for x in 0..width {
+                for c in 0..channels {
+                    let src = (page_index * page_stride + y * y_stride + x * x_stride + c * c_stride) * size;
+                    let dst = ((y * width + x) * channels + c) * size;
+                    reordered[dst..dst + size].copy_from_slice(&raw[src..src + size]);
+                }
+            }
+        }
+        Cow::Owned(reordered)
+    } else {
+        Cow::Borrowed(&raw[page_index * page_len..(page_index + 1) * page_len])
+    };
+
+    macro_rules! samples {
+        ($t:ty, $n:literal) => {
+            raw.chunks_exact($n)
+                .map(|b| {
+                    let bytes: [u8; $n] = b.try_into().unwrap();
+                    if little_endian { <$t>::from_le_bytes(bytes) } else { <$t>::from_be_bytes(bytes) }
+                })
+                .collect()
+        };
+    }
+    let values = match (kind, size) {
+        (b'u' | b'b', 1) => DecodingResult::U8(raw.to_vec()),
+        (b'i', 1) => DecodingResult::I8(raw.iter().map(|&v| v as i8).collect()),
+        (b'u', 2) => DecodingResult::U16(samples!(u16, 2)),
+        (b'i', 2) => DecodingResult::I16(samples!(i16, 2)),
+        (b'u', 4) => DecodingResult::U32(samples!(u32, 4)),
+        (b'i', 4) => DecodingResult::I32(samples!(i32, 4)),
+        (b'u', 8) => DecodingResult::U64(samples!(u64, 8)),
+        (b'i', 8) => DecodingResult::I64(samples!(i64, 8)),
+        (b'f', 2) => {
+            let halves: Vec<u16> = samples!(u16, 2);
+            DecodingResult::F32(halves.into_iter().map(f16_bits_to_f32).collect())
+        }
+        (b'f', 4) => DecodingResult::F32(samples!(f32, 4)),
+        (b'f', 8) => DecodingResult::F64(samples!(f64, 8)),
+        _ => return Err(format!("unsupported dtype '{}'", header.descr)),
+    };
+    Ok((width as u32, height as u32, channels as u32, values))
+}
+
+/// IEEE 754 binary16 bits to f32 (subnormals, Inf and NaN included).
+fn f16_bits_to_f32(bits: u16) -> f32 {
+    let sign = ((bits >> 15) as u32) << 31;
+    let exponent = ((bits >> 10) & 0x1f) as u32;
+    let mantissa = (bits & 0x3ff) as u32;
+    let magnitude = match exponent {
+        0 => {
+            let value = mantissa as f32 * 2f32.powi(-24);
+            return if sign != 0 { -value } else { value };
+        }
+        0x1f => 0x7f80_0000 | (mantissa << 13),
+        _ => ((exponent + 112) << 23) | (mantissa << 13),
+    };
+    f32::from_bits(sign | magnitude)
+}
+
+/// Default number of decoded strips/tiles a `TiffFile` keeps around.
+const TIFF_FILE_DEFAULT_CACHE_CHUNKS: usize = 64;
+
+/// Default cap on the decoded bytes (f32 samples) a `TiffFile` cache holds,
+/// so 64 large tiles of a many-band file can't quietly grow to gigabytes.
+const TIFF_FILE_DEFAULT_CACHE_BYTES: usize = 256 * 1024 * 1024;
+
+/// An open TIFF page that never materialises the whole image. Strips/tiles
+/// are decoded on demand for the region being viewed and kept in a small LRU
+/// (`ChunkLru`), so a gigapixel file only ever costs its compressed bytes
+/// plus the chunks of the active viewport and recently visited ones, instead
+/// of the full `width * height * channels` buffer `decode_tiff` allocates.
+/// Opened through `TiffStream::open_page`, not even the compressed bytes
+/// are held: each chunk is fetched from the file when it is decoded.
+///
+/// Chunks are decoded with the `tiff` crate's `read_chunk()`, so this covers
+/// the common chunky 8/16/32/64-bit layouts; planar, sub-byte, palette, CCITT,
+/// ZSTD and JPEG-YCbCr pages (which `decode_tiff_impl` routes through its own
+/// dedicated paths) are rejected at open time rather than decoded wrongly.
+/// Coordinates are in stored (pre-Orientation) pixel space and samples are
+/// as stored (CMYK is not converted to RGB); `orientation` reports the tag
+/// so the caller can transform the view.
+#[wasm_bindgen]
+pub struct TiffFile {
+    // Open on the page for the life of the handle, so a cache miss only
+    // decodes the one strip/tile; owns the (patched, or header-only when
+    // streamed) file bytes.
+    decoder: Decoder<PageCursor<Vec<u8>>>,
+    page_index: u32,
+    chunk_offsets: Vec<u64>,
+    chunk_counts: Vec<u64>,
+    sparse_fill: f64,
+    width: u32,
+    height: u32,
+    channels: u32,
+    bits_per_sample: u32,
+    sample_format: u32,
+    orientation: u32,
+    chunk_width: u32,
+    chunk_height: u32,
+    chunks_across: u32,
+    chunk_count: u32,
+    tiled: bool,
+    cache: ChunkLru<Rc<DecodedChunk>>,
+    // Set when opened by `TiffStream::open_page`: chunks are fetched.
+    stream: Option<StreamedChunks>,
+    // Optional prefetcher (`set_prefetch_margin`): the strips/tiles within
+    // `prefetch_margin` chunks of the last region served, nearest first,
+    // which `prefetch` decodes into the LRU between requests.
+    prefetch_margin: u32,
+    prefetch_queue: Vec<u32>,
+    prefetch_reserved: PrefetchReserve,
+}
+
+/// Chunk count and decoded bytes of the LRU that the region last served
+/// and what has been prefetched around it take up; `TiffFile::prefetch`
+/// stops before the cache would have to evict any of them.
+#[derive(Clone, Copy, Default)]
+struct PrefetchReserve {
+    chunks: usize,
+    bytes: usize,
+}
+
+/// One decoded strip/tile as f32 samples, `TiffFile::channels` interleaved
+/// per pixel and `width` pixels per row (the encoder's edge padding is
+/// already stripped by `read_chunk()`; `width` comes from `chunk_valid_rect`).
+struct DecodedChunk {
+    width: u32,
+    values: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl TiffFile {
+    /// Open page `page_index` of `data`, reading only its tags.
+    #[wasm_bindgen(constructor)]
+    pub fn new(data: Vec<u8>, page_index: u32) -> Result<TiffFile, JsValue> {
+        #[cfg(feature = "console_error_panic_hook")]
+        console_error_panic_hook::set_once();
+
+        let data = match gunzip_if_needed(&data)? {
+            Cow::Owned(inflated) => inflated,
+            Cow::Borrowed(_) => data,
+        };
+        TiffFile::open(data, page_index, None)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn page_index(&self) -> u32 { self.page_index }
+
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 { self.width }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 { self.height }
+
+    #[wasm_bindgen(getter)]
+    pub fn channels(&self) -> u32 { self.channels }
+
+    #[wasm_bindgen(getter)]
+    pub fn bits_per_sample(&self) -> u32 { self.bits_per_sample }
+
+    #[wasm_bindgen(getter)]
+    pub fn sample_format(&self) -> u32 { self.sample_format }
+
+    #[wasm_bindgen(getter)]
+    pub fn orientation(&self) -> u32 { self.orientation }
+
+    #[wasm_bindgen(getter)]
+    pub fn tiled(&self) -> bool { self.tiled }
+
+    #[wasm_bindgen(getter)]
+    pub fn chunk_width(&self) -> u32 { self.chunk_width }
+
+    #[wasm_bindgen(getter)]
+    pub fn chunk_height(&self) -> u32 { self.chunk_height }
+
+    #[wasm_bindgen(getter)]
+    pub fn chunk_count(&self) -> u32 { self.chunk_count }
+
+    /// Columns of the tile grid `decode_tile` addresses (1 for strips,
+    /// which count as full-width tiles).
+    #[wasm_bindgen(getter)]
+    pub fn tiles_across(&self) -> u32 { self.chunks_across }
+
+    /// Rows of the tile grid (the strip count for stripped pages).
+    #[wasm_bindgen(getter)]
+    pub fn tiles_down(&self) -> u32 { self.chunk_count / self.chunks_across }
+
+    /// Number of decoded strips/tiles currently held in the LRU.
+    #[wasm_bindgen(getter)]
+    pub fn cached_chunk_count(&self) -> u32 { self.cache.len() as u32 }
+
+    /// Change how many decoded strips/tiles are kept; evicts the least
+    /// recently used ones beyond the new capacity. 0 disables caching.
+    #[wasm_bindgen]
+    pub fn set_cache_capacity(&mut self, capacity: u32) {
+        self.cache.set_capacity(capacity as usize);
+    }
+
+    /// Decoded bytes (4 per f32 sample) currently held in the LRU.
+    #[wasm_bindgen(getter)]
+    pub fn cached_bytes(&self) -> f64 { self.cache.bytes() as f64 }
+
+    /// Current byte budget of the LRU (see `set_cache_byte_budget`).
+    #[wasm_bindgen(getter)]
+    pub fn cache_byte_budget(&self) -> f64 { self.cache.byte_budget() as f64 }
+
+    /// Cap the decoded bytes the LRU may hold, on top of the chunk-count
+    /// capacity: least recently used strips/tiles are evicted until both
+    /// limits are met, and a single chunk larger than the budget is decoded
+    /// but not cached. Lets panning over a compressed file reuse inflated
+    /// chunks without the cache outgrowing the memory the host can spare.
+    /// 0 disables caching. Defaults to 256 MiB.
+    #[wasm_bindgen]
+    pub fn set_cache_byte_budget(&mut self, bytes: f64) {
+        let bytes = if bytes.is_finite() && bytes > 0.0 { bytes.min(usize::MAX as f64) as usize } else { 0 };
+        self.cache.set_byte_budget(bytes);
+    }
+
+    /// Drop every cached strip/tile.
+    #[wasm_bindgen]
+    pub fn clear_cache(&mut self) {
+        self.cache.clear();
+    }
+
+    /// Assemble the `width` x `height` region at (`x`, `y`) from the strips/
+    /// tiles it overlaps, sampling every `step`-th pixel in both directions
+    /// (nearest neighbour; `step` 1 is full resolution). Returns
+    /// `ceil(width/step) * ceil(height/step) * channels` interleaved f32
+    /// samples. Chunks no sampled pixel lands in are never decoded.
+    #[wasm_bindgen]
+    pub fn read_region(&mut self, x: u32, y: u32, width: u32, height: u32, step: u32) -> Result<Vec<f32>, JsValue> {
+        if width == 0 || height == 0 || step == 0 {
+            return Err(JsValue::from_str("TiffFile: region width, height and step must be non-zero"));
+        }
+        if x.checked_add(width).is_none_or(|end| end > self.width)
+            || y.checked_add(height).is_none_or(|end| end > self.height)
+        {
+            return Err(JsValue::from_str(&format!(
+                "TiffFile: region {}x{} at ({}, {}) is outside the {}x{} image",
+                width, height, x, y, self.width, self.height
+            )));
+        }
+
+        let out_width = width.div_ceil(step) as usize;
+        let out_height = height.div_ceil(step) as usize;
+        let channels = self.channels as usize;
+        let mut out = vec![0f32; out_width * out_height * channels];
+
+        // First output index whose source coordinate `origin + i * step` is
+        // at or past `bound`, clamped to `len`.
+        let first_sample_at = |origin: u32, bound: u32, len: usize| -> usize {
+            (bound.saturating_sub(origin).div_ceil(step) as usize).min(len)
+        };
+
+        let chunk_rows = (y / self.chunk_height)..=((y + height - 1) / self.chunk_height);
+        let chunk_cols = if self.tiled {
+            (x / self.chunk_width)..=((x + width - 1) / self.chunk_width)
+        } else {
+            0..=0
+        };
+        for chunk_row in chunk_rows.clone() {
+            let chunk_y0 = chunk_row * self.chunk_height;
+            let oy_start = first_sample_at(y, chunk_y0, out_height);
+            let oy_end = first_sample_at(y, chunk_y0 + self.chunk_height, out_height);
+            if oy_start >= oy_end {
+                continue;
+            }
+            for chunk_col in chunk_cols.clone() {
+                let chunk_x0 = chunk_col * self.chunk_width;
+                let chunk_x1 = if self.tiled { chunk_x0 + self.chunk_width } else { self.width };
+                let ox_start = first_sample_at(x, chunk_x0, out_width);
+                let ox_end = first_sample_at(x, chunk_x1, out_width);
+                if ox_start >= ox_end {
+                    continue;
+                }
+
+                let chunk = self.chunk(chunk_row * self.chunks_across + chunk_col)?;
+                for oy in oy_start..oy_end {
+                    let cy = (y as usize + oy * step as usize) - chunk_y0 as usize;
+                    for ox in ox_start..ox_end {
+                        let cx = (x as usize + ox * step as usize) - chunk_x0 as usize;
+                        let src = (cy * chunk.width as usize + cx) * channels;
+                        let dst = (oy * out_width + ox) * channels;
+                        out[dst..dst + channels].copy_from_slice(&chunk.values[src..src + channels]);
+                    }
+                }
+            }
+        }
+
+        self.queue_prefetch(chunk_rows, chunk_cols);
+        Ok(out)
+    }
+
+    /// Decode `num_rows` full-width rows starting at `start_row`, touching
+    /// only the strips (or tile rows) that cover them - for horizontal
+    /// profiles and striped progressive rendering. Returns
+    /// `width * num_rows * channels` interleaved f32 samples.
+    #[wasm_bindgen]
+    pub fn decode_rows(&mut self, start_row: u32, num_rows: u32) -> Result<Vec<f32>, JsValue> {
+        self.read_region(0, start_row, self.width, num_rows, 1)
+    }
+
+    /// `[x, y, width, height]` of tile (`tile_x`, `tile_y`) in the image;
+    /// right/bottom edge tiles are clipped to the image, which is the size
+    /// `decode_tile` returns.
+    #[wasm_bindgen]
+    pub fn tile_rect(&self, tile_x: u32, tile_y: u32) -> Result<Vec<u32>, JsValue> {
+        let index = self.tile_index(tile_x, tile_y)?;
+        let (x0, y0, width, height) = chunk_valid_rect(
+            index, self.width, self.height, self.chunk_width, self.chunk_height, self.chunks_across, self.tiled,
+        );
+        Ok(vec![x0, y0, width, height])
+    }
+
+    /// Decode the single tile (`tile_x`, `tile_y`) of the grid described by
+    /// `tiles_across`/`tiles_down`, for viewers that pull tiles lazily as
+    /// the viewport pans instead of decoding the page up front. Returns the
+    /// tile's `tile_rect` pixels as interleaved f32 samples; the tile goes
+    /// through the LRU, so revisiting it costs no decode.
+    #[wasm_bindgen]
+    pub fn decode_tile(&mut self, tile_x: u32, tile_y: u32) -> Result<Vec<f32>, JsValue> {
+        let index = self.tile_index(tile_x, tile_y)?;
+        let values = self.chunk(index)?.values.clone();
+        self.queue_prefetch(tile_y..=tile_y, tile_x..=tile_x);
+        Ok(values)
+    }
+
+    /// Turn on the prefetcher: after each `read_region`, `decode_rows` or
+    /// `decode_tile`, the strips/tiles within `margin` chunks of the ones
+    /// just served (the neighbouring tiles of the viewport, or the strips
+    /// above and below it) are queued, nearest first, for `prefetch` to
+    /// decode while the viewer is idle, so panning onto them costs no
+    /// decode. 0, the default, turns it off and drops the queue.
+    #[wasm_bindgen]
+    pub fn set_prefetch_margin(&mut self, margin: u32) {
+        self.prefetch_margin = margin;
+        if margin == 0 {
+            self.prefetch_queue.clear();
+        }
+    }
+
+    /// Current prefetch margin in strips/tiles (see `set_prefetch_margin`).
+    #[wasm_bindgen(getter)]
+    pub fn prefetch_margin(&self) -> u32 { self.prefetch_margin }
+
+    /// Number of strips/tiles still queued for `prefetch`.
+    #[wasm_bindgen(getter)]
+    pub fn pending_prefetch_count(&self) -> u32 { self.prefetch_queue.len() as u32 }
+
+    /// Decode up to `max_chunks` of the queued strips/tiles into the LRU and
+    /// return how many are still queued, for a `requestIdleCallback` loop
+    /// such as `while (deadline.timeRemaining() > 4 && file.prefetch(1) > 0);`
+    /// (wasm has no idle time of its own to run in). Prefetching never
+    /// evicts the region last served: it stops, dropping the queue, once
+    /// the LRU's chunk capacity or byte budget would not hold the next one
+    /// as well. A chunk that fails to decode is skipped; the error shows
+    /// when the region that needs it is read.
+    #[wasm_bindgen]
+    pub fn prefetch(&mut self, max_chunks: u32) -> Result<u32, JsValue> {
+        let mut decoded = 0;
+        while decoded < max_chunks && !self.prefetch_queue.is_empty() {
+            let index = self.prefetch_queue.remove(0);
+            if self.cache.contains(index) {
+                continue;
+            }
+            let bytes = self.chunk_f32_bytes(index);
+            let reserved = self.prefetch_reserved;
+            if reserved.chunks + 1 > self.cache.capacity() || reserved.bytes + bytes > self.cache.byte_budget() {
+                self.prefetch_queue.clear();
+                break;
+            }
+            if self.chunk(index).is_ok() {
+                self.prefetch_reserved = PrefetchReserve { chunks: reserved.chunks + 1, bytes: reserved.bytes + bytes };
+            }
+            decoded += 1;
+        }
+        Ok(self.prefetch_queue.len() as u32)
+    }
+
+    /// Every `row_step`-th row and `col_step`-th column of the page,
+    /// starting at (0, 0), as a `ceil(width/col_step)` x
+    /// `ceil(height/row_step)` `TiffResult` in the stored sample type (so
+    /// `get_data("auto")` and `exact_values` see the file's own values),
+    /// with min/max over the sampled pixels: a quick look for sparklines
+    /// and histogram estimates. Only the strips/tiles a sampled pixel lands
+    /// in are decoded, in stored orientation like `read_region`; they are
+    /// not added to the LRU, which holds f32 samples.
+    #[wasm_bindgen]
+    pub fn decode_strided(&mut self, row_step: u32, col_step: u32) -> Result<TiffResult, JsValue> {
+        if row_step == 0 || col_step == 0 {
+            return Err(JsValue::from_str("TiffFile: row_step and col_step must be non-zero"));
+        }
+        let cell = self.channels as usize;
+        let chunks = self.strided_chunks(row_step, col_step);
+        let across = self.chunks_across;
+        let mut out = None;
+        // One row of strips/tiles at a time, so the picks of each sampled
+        // row run left to right across its tiles.
+        for band in chunks.chunk_by(|a, b| a / across == b / across) {
+            let mut decoded = Vec::with_capacity(band.len());
+            let mut rects = Vec::with_capacity(band.len());
+            for &index in band {
+                let rect = chunk_valid_rect(
+                    index, self.width, self.height, self.chunk_width, self.chunk_height, self.chunks_across, self.tiled,
+                );
+                decoded.push(match self.native_chunk(index)? {
+                    Some(chunk) => chunk,
+                    None => {
+                        let len = (rect.2 as usize) * (rect.3 as usize) * cell;
+                        filled_chunk(self.sample_format, self.bits_per_sample, len, self.sparse_fill).ok_or_else(|| {
+                            JsValue::from_str("TiffFile: empty strips/tiles are not supported for this sample type")
+                        })?
+                    }
+                });
+                rects.push(rect);
+            }
+
+            let (_, y0, _, height) = rects[0];
+            let mut picks = Vec::new();
+            for y in (y0.div_ceil(row_step) * row_step..y0 + height).step_by(row_step as usize) {
+                for (chunk, &(x0, _, width, _)) in rects.iter().enumerate() {
+                    for x in (x0.div_ceil(col_step) * col_step..x0 + width).step_by(col_step as usize) {
+                        picks.push((chunk, (((y - y0) as usize) * (width as usize) + (x - x0) as usize) * cell));
+                    }
+                }
+            }
+            extend_picked(&mut out, &decoded, &picks, cell)?;
+        }
+
+        let out = out.ok_or_else(|| JsValue::from_str("TiffFile: the page has no pixels to sample"))?;
+        Ok(TiffResult::from_decoded(
+            self.width.div_ceil(col_step),
+            self.height.div_ceil(row_step),
+            self.channels,
+            out,
+            String::new(),
+        ))
+    }
+}
+
+impl TiffFile {
+    /// Open page `page_index` of `data` (already gunzipped), or, with
+    /// `stream`, of the compact header-only copy `TiffStream::open_page`
+    /// built.
+    fn open(data: Vec<u8>, page_index: u32, mut stream: Option<StreamedChunks>) -> Result<TiffFile, JsValue> {
+        use tiff::decoder::ChunkType;
+        use tiff::tags::Tag;
+
+        // Keep every band of RGB-plus-extra-samples pages and undo FillOrder
+        // 2; the bit-reversed copy replaces the original since chunks are
+        // only ever read from it. Streamed chunks aren't in `data` yet, so
+        // they are bit-reversed as they are fetched instead.
+        let (as_grayscale, page_copy) = {
+            let mut decoder = open_tiff_page(&data, page_index)?;
+            let photometric = decoder.get_tag_u32(Tag::PhotometricInterpretation).unwrap_or(1);
+            decodable_page(&data, &mut decoder, page_index, photometric)
+        };
+        if let Some(stream) = stream.as_mut() {
+            stream.reverse_bits = page_copy.is_some();
+        }
+        let mut decoder = open_decodable_page(page_copy.unwrap_or(data), page_index, as_grayscale)?;
+        let (width, height) = decoder.dimensions()
+            .map_err(|e| TiffError::from_tiff("Failed to get dimensions", e))?;
+        let compression = decoder.get_tag_u32(Tag::Compression).unwrap_or(1);
+        let photometric = decoder.get_tag_u32(Tag::PhotometricInterpretation).unwrap_or(1);
+        let planar_configuration = decoder.get_tag_u32(Tag::PlanarConfiguration).unwrap_or(1);
+        if photometric == 3 || matches!(compression, 2 | 3 | 4 | 50000) || (compression == 7 && photometric == 6) {
+            return Err(JsValue::from_str(&format!(
+                "TiffFile: compression {} / photometric {} needs a whole-image decode; use decode_tiff_page",
+                compression, photometric
+            )));
+        }
+        if planar_configuration != 1 {
+            return Err(JsValue::from_str("TiffFile: planar configuration 2 is not supported; use decode_tiff_page"));
+        }
+        let color_type = decoder.colortype()
+            .map_err(|e| TiffError::from_tiff("Failed to get color type", e))?;
+        let bits_per_sample = color_type.bit_depth() as u32;
+        if !matches!(bits_per_sample, 8 | 16 | 32 | 64) {
+            return Err(JsValue::from_str(&format!(
+                "TiffFile: {}-bit samples are not supported; use decode_tiff_page", bits_per_sample
+            )));
+        }
+        // `read_chunk()` hands back YCbCr as RGB, so `color_type` (not
+        // SamplesPerPixel) is the chunk stride.
+        let channels = if matches!(color_type, tiff::ColorType::YCbCr(_)) { 3 } else { color_type.num_samples() as u32 };
+        let sample_format = decoder.get_tag_u64_vec(Tag::SampleFormat)
+            .ok()
+            .and_then(|values| values.first().copied())
+            .unwrap_or(1) as u32;
+        let orientation = decoder.get_tag_u32(Tag::Orientation).unwrap_or(1);
+
+        let (chunk_width, chunk_height) = decoder.chunk_dimensions();
+        if chunk_width == 0 || chunk_height == 0 {
+            return Err(JsValue::from_str("TiffFile: page has no strip/tile layout"));
+        }
+        let tiled = decoder.get_chunk_type() == ChunkType::Tile;
+        let chunks_across = if tiled { width.div_ceil(chunk_width) } else { 1 };
+        let chunk_count = chunks_across * height.div_ceil(chunk_height);
+        let (offsets_tag, counts_tag) = if tiled {
+            (Tag::TileOffsets, Tag::TileByteCounts)
+        } else {
+            (Tag::StripOffsets, Tag::StripByteCounts)
+        };
+        let chunk_offsets = decoder.get_tag_u64_vec(offsets_tag).unwrap_or_default();
+        let chunk_counts = decoder.get_tag_u64_vec(counts_tag).unwrap_or_default();
+        let sparse_fill = sparse_fill_value(&mut decoder);
+
+        Ok(TiffFile {
+            decoder,
+            page_index,
+            chunk_offsets,
+            chunk_counts,
+            sparse_fill,
+            width,
+            height,
+            channels,
+            bits_per_sample,
+            sample_format,
+            orientation,
+            chunk_width,
+            chunk_height,
+            chunks_across,
+            chunk_count,
+            tiled,
+            cache: ChunkLru::new(TIFF_FILE_DEFAULT_CACHE_CHUNKS, TIFF_FILE_DEFAULT_CACHE_BYTES),
+            stream,
+            prefetch_margin: 0,
+            prefetch_queue: Vec::new(),
+            prefetch_reserved: PrefetchReserve::default(),
+        })
+    }
+
+    /// Chunk index of tile (`tile_x`, `tile_y`), checked against the grid.
+    fn tile_index(&self, tile_x: u32, tile_y: u32) -> Result<u32, JsValue> {
+        if tile_x >= self.tiles_across() || tile_y >= self.tiles_down() {
+            return Err(JsValue::from_str(&format!(
+                "TiffFile: tile ({}, {}) is outside the {}x{} tile grid",
+                tile_x, tile_y, self.tiles_across(), self.tiles_down()
+            )));
+        }
+        Ok(tile_y * self.chunks_across + tile_x)
+    }
+
+    /// Decoded bytes (f32 samples) of strip/tile `index` in the LRU.
+    fn chunk_f32_bytes(&self, index: u32) -> usize {
+        let (_, _, width, height) = chunk_valid_rect(
+            index, self.width, self.height, self.chunk_width, self.chunk_height, self.chunks_across, self.tiled,
+        );
+        (width as usize) * (height as usize) * (self.channels as usize) * std::mem::size_of::<f32>()
+    }
+
+    /// Replace the prefetch queue with the strips/tiles within
+    /// `prefetch_margin` of the chunk rows/columns just served and not
+    /// cached, nearest (by rings) first, and reserve the served ones in the
+    /// LRU. Does nothing while the prefetcher is off.
+    fn queue_prefetch(&mut self, rows: std::ops::RangeInclusive<u32>, cols: std::ops::RangeInclusive<u32>) {
+        if self.prefetch_margin == 0 {
+            return;
+        }
+        let (across, rows_down, margin) = (self.chunks_across, self.chunk_count / self.chunks_across, self.prefetch_margin);
+        let served: Vec<u32> = rows.clone().flat_map(|row| cols.clone().map(move |col| row * across + col)).collect();
+        let bytes = served.iter().map(|&index| self.chunk_f32_bytes(index)).sum();
+        self.prefetch_reserved = PrefetchReserve { chunks: served.len(), bytes };
+        // Chebyshev distance of a chunk row/column from the served range.
+        let distance = |value: u32, range: &std::ops::RangeInclusive<u32>| {
+            range.start().saturating_sub(value).max(value.saturating_sub(*range.end()))
+        };
+        let mut queue: Vec<(u32, u32)> = (rows.start().saturating_sub(margin)..=rows.end().saturating_add(margin).min(rows_down - 1))
+            .flat_map(|row| {
+                (cols.start().saturating_sub(margin)..=cols.end().saturating_add(margin).min(across - 1))
+                    .map(move |col| (row, col))
+            })
+            .map(|(row, col)| (distance(row, &rows).max(distance(col, &cols)), row * across + col))
+            .filter(|&(ring, index)| ring > 0 && !self.cache.contains(index))
+            .collect();
+        queue.sort_unstable();
+        self.prefetch_queue = queue.into_iter().map(|(_, index)| index).collect();
+    }
+
+    /// Original byte ranges of the strips/tiles the `width` x `height`
+    /// region at (`x`, `y`) overlaps and the LRU doesn't hold, for a file
+    /// opened by `TiffStream::open_page` (empty for others, and for regions
+    /// `read_region` would reject).
+    fn region_chunk_ranges(&self, x: u32, y: u32, width: u32, height: u32) -> Vec<ChunkRange> {
+        let Some(stream) = &self.stream else { return Vec::new() };
+        if width == 0
+            || height == 0
+            || x.checked_add(width).is_none_or(|end| end > self.width)
+            || y.checked_add(height).is_none_or(|end| end > self.height)
+        {
+            return Vec::new();
+        }
+        let chunk_rows = (y / self.chunk_height)..=((y + height - 1) / self.chunk_height);
+        let chunk_cols = if self.tiled {
+            (x / self.chunk_width)..=((x + width - 1) / self.chunk_width)
+        } else {
+            0..=0
+        };
+        chunk_rows
+            .flat_map(|row| chunk_cols.clone().map(move |col| row * self.chunks_across + col))
+            .filter(|&index| !self.cache.contains(index))
+            .filter_map(|index| stream.ranges.get(index as usize).copied())
+            .collect()
+    }
+
+    /// Decoded strip/tile `index`, from the LRU or freshly decoded (and
+    /// then cached). Sparse chunks (`is_sparse_chunk`) read as nodata.
+    fn chunk(&mut self, index: u32) -> Result<Rc<DecodedChunk>, JsValue> {
+        if let Some(chunk) = self.cache.get(index) {
+            return Ok(Rc::clone(chunk));
+        }
+
+        let (_, _, width, height) = chunk_valid_rect(
+            index, self.width, self.height, self.chunk_width, self.chunk_height, self.chunks_across, self.tiled,
+        );
+        let values = match self.native_chunk(index)? {
+            Some(decoded) => decoding_result_to_f32(&decoded),
+            None => vec![self.sparse_fill as f32; (width as usize) * (height as usize) * (self.channels as usize)],
+        };
+
+        let chunk = Rc::new(DecodedChunk { width, values });
+        let bytes = chunk.values.len() * std::mem::size_of::<f32>();
+        self.cache.insert(index, Rc::clone(&chunk), bytes);
+        Ok(chunk)
+    }
+
+    /// Strip/tile `index` decoded in its stored sample type, fetched first
+    /// when the file is streamed; bypasses the LRU. `None` for a sparse
+    /// chunk, which reads as `sparse_fill`.
+    fn native_chunk(&mut self, index: u32) -> Result<Option<DecodingResult>, JsValue> {
+        if let Some(stream) = &self.stream {
+            let (offset, count) = stream.ranges.get(index as usize).copied().unwrap_or((0, 0));
+            if !is_sparse_chunk(offset, count) {
+                let mut bytes = stream.source.read(offset, count)?;
+                if stream.reverse_bits {
+                    bytes.iter_mut().for_each(|byte| *byte = byte.reverse_bits());
+                }
+                let data = self.decoder.inner().cursor.get_mut();
+                data.truncate(stream.header_len);
+                data.extend_from_slice(&bytes);
+            }
+        }
+        let (_, _, width, height) = chunk_valid_rect(
+            index, self.width, self.height, self.chunk_width, self.chunk_height, self.chunks_across, self.tiled,
+        );
+        let offset = self.chunk_offsets.get(index as usize).copied().unwrap_or(0);
+        let count = self.chunk_counts.get(index as usize).copied().unwrap_or(0);
+        if is_sparse_chunk(offset, count) {
+            return Ok(None);
+        }
+        let decoded = self.decoder.read_chunk(index)
+            .map_err(|e| TiffError::from_tiff(&format!("Failed to decode TIFF chunk {}", index), e))?;
+        if decoding_result_len(&decoded) != (width as usize) * (height as usize) * (self.channels as usize) {
+            return Err(JsValue::from_str(&format!("TiffFile: chunk {} has an unexpected size", index)));
+        }
+        Ok(Some(decoded))
+    }
+
+    /// Indices of the strips/tiles `decode_strided` reads: those holding at
+    /// least one pixel on a sampled row and column.
+    fn strided_chunks(&self, row_step: u32, col_step: u32) -> Vec<u32> {
+        if row_step == 0 || col_step == 0 {
+            return Vec::new();
+        }
+        // Whether a multiple of `step` falls in `start..end`.
+        let sampled = |start: u32, end: u32, step: u32| start.div_ceil(step).saturating_mul(step) < end;
+        (0..self.chunk_count)
+            .filter(|&index| {
+                let (x0, y0, width, height) = chunk_valid_rect(
+                    index, self.width, self.height, self.chunk_width, self.chunk_height, self.chunks_across, self.tiled,
+                );
+                sampled(y0, y0 + height, row_step) && sampled(x0, x0 + width, col_step)
+            })
+            .collect()
+    }
+
+    /// Original byte ranges of the `strided_chunks`, for a file opened by
+    /// `TiffStream::open_page` (empty for others).
+    fn strided_chunk_ranges(&self, row_step: u32, col_step: u32) -> Vec<ChunkRange> {
+        let Some(stream) = &self.stream else { return Vec::new() };
+        self.strided_chunks(row_step, col_step)
+            .into_iter()
+            .filter_map(|index| stream.ranges.get(index as usize).copied())
+            .collect()
+    }
+}
+
+/// Append the `cell` samples at each `(chunk, offset)` of `picks`, in order,
+/// to `out`, which takes the sample type of the first chunk. Fails if the
+/// chunks (or an earlier `out`) differ in sample type.
+fn extend_picked(
+    out: &mut Option<DecodingResult>,
+    chunks: &[DecodingResult],
+    picks: &[(usize, usize)],
+    cell: usize,
+) -> Result<(), JsValue> {
+    let mixed = || JsValue::from_str("TiffFile: strips/tiles decoded to different sample types");
+    macro_rules! pick {
+        ($($variant:ident),*) => {
+            match chunks.first() {
+                $(Some(DecodingResult::$variant(_)) => {
+                    let DecodingResult::$variant(out) = out.get_or_insert_with(|| DecodingResult::$variant(Vec::new())) else {
+                        return Err(mixed());
+                    };
+                    for &(chunk, offset) in picks {
+                        let DecodingResult::$variant(values) = &chunks[chunk] else { return Err(mixed()) };
+                        out.extend_from_slice(&values[offset..offset + cell]);
+                    }
+                })*
+                None => {}
+            }
+        };
+    }
+    pick!(U8, U16, U32, U64, I8, I16, I32, I64, F16, F32, F64);
+    Ok(())
+}
+
+/// Open page `page_index` of `data` as a stream of `RowChunk`s of
+/// `rows_per_chunk` rows each (0 uses the file's strip/tile height, so every
+/// chunk decodes each strip/tile exactly once), top to bottom. The returned
+/// `TiffRowStream` follows the JS iterator protocol and is both iterable and
+/// async-iterable, so it works with `for (const chunk of stream)`,
+/// `for await (...)` and `ReadableStream.from(stream)` for piping decoded
+/// rows into an encoder or upload. Decoding is lazy: each `next()` decodes
+/// only the strips/tiles its rows overlap, through `TiffFile`'s LRU, and
+/// takes the same layouts `TiffFile` does.
+#[wasm_bindgen]
+pub fn tiff_row_stream(data: Vec<u8>, page_index: u32, rows_per_chunk: u32) -> Result<JsValue, JsValue> {
+    let file = TiffFile::new(data, page_index)?;
+    let rows_per_chunk = if rows_per_chunk == 0 { file.chunk_height.max(1) } else { rows_per_chunk };
+    let stream = JsValue::from(TiffRowStream { file, rows_per_chunk, next_row: 0 });
+    // `Object.prototype.valueOf` returns `this`, which is exactly what
+    // `[Symbol.iterator]()` / `[Symbol.asyncIterator]()` must do for an
+    // iterator, without building a function from source (CSP-safe).
+    let return_this = js_sys::Reflect::get(&js_sys::Object::new(), &JsValue::from_str("valueOf"))?;
+    js_sys::Reflect::set(&stream, &js_sys::Symbol::iterator(), &return_this)?;
+    js_sys::Reflect::set(&stream, &js_sys::Symbol::async_iterator(), &return_this)?;
+    Ok(stream)
+}
+
+/// Decode page `page_index` of `data` top to bottom, calling `on_rows` with
+/// each `RowChunk` of `rows_per_chunk` rows (0 = the strip/tile height) as
+/// soon as it is converted, for streaming re-encoders (TIFF to PNG tiles,
+/// to .npy, ...) of images whose full raster would not fit in wasm memory.
+/// Nothing but the compressed file and the rows in flight is kept: the
+/// chunk cache is limited to the two rows of strips/tiles a chunk can
+/// straddle, so each strip/tile is decoded once and dropped when passed, and
+/// the callback should `take_data()` rather than hold on to chunks.
+/// Returning `false` from `on_rows` stops early; a thrown exception is
+/// passed through. Returns the number of rows delivered. Takes the same
+/// layouts `TiffFile` does.
+#[wasm_bindgen]
+pub fn decode_tiff_rows(data: Vec<u8>, page_index: u32, rows_per_chunk: u32, on_rows: &js_sys::Function) -> Result<u32, JsValue> {
+    let mut file = TiffFile::new(data, page_index)?;
+    file.set_cache_capacity(file.chunks_across.saturating_mul(2));
+    let rows_per_chunk = if rows_per_chunk == 0 { file.chunk_height.max(1) } else { rows_per_chunk };
+    let mut stream = TiffRowStream { file, rows_per_chunk, next_row: 0 };
+    while let Some(chunk) = stream.next_chunk()? {
+        let keep_going = on_rows.call1(&JsValue::NULL, &JsValue::from(chunk))?;
+        if keep_going == JsValue::FALSE {
+            break;
+        }
+    }
+    Ok(stream.next_row)
+}
+
+/// Iterator over a page's rows (`tiff_row_stream`).
+#[wasm_bindgen]
+pub struct TiffRowStream {
+    file: TiffFile,
+    rows_per_chunk: u32,
+    next_row: u32,
+}
+
+#[wasm_bindgen]
+impl TiffRowStream {
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 { self.file.width }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 { self.file.height }
+
+    #[wasm_bindgen(getter)]
+    pub fn channels(&self) -> u32 { self.file.channels }
+
+    /// First row the next chunk will start at (`height` once exhausted).
+    #[wasm_bindgen(getter)]
+    pub fn next_row(&self) -> u32 { self.next_row }
+
+    /// The next chunk of rows, or undefined once every row was returned.
+    #[wasm_bindgen]
+    pub fn next_chunk(&mut self) -> Result<Option<RowChunk>, JsValue> {
+        if self.next_row >= self.file.height {
+            return Ok(None);
+        }
+        let start_row = self.next_row;
+        let row_count = self.rows_per_chunk.min(self.file.height - start_row);
+        let data = self.file.decode_rows(start_row, row_count)?;
+        self.next_row += row_count;
+        Ok(Some(RowChunk { start_row, row_count, width: self.file.width, channels: self.file.channels, data }))
+    }
+
+    /// Iterator protocol step: `{ value: RowChunk, done: false }`, then
+    /// `{ value: undefined, done: true }` at the end.
+    #[wasm_bindgen(js_name = next)]
+    pub fn iterator_next(&mut self) -> Result<JsValue, JsValue> {
+        let chunk = self.next_chunk()?;
+        let result = js_sys::Object::new();
+        let done = chunk.is_none();
+        let value = chunk.map_or(JsValue::UNDEFINED, JsValue::from);
+        js_sys::Reflect::set(&result, &JsValue::from_str("value"), &value)?;
+        js_sys::Reflect::set(&result, &JsValue::from_str("done"), &JsValue::from_bool(done))?;
+        Ok(result.into())
+    }
+}
+
+/// `row_count` full-width rows starting at `start_row`, as `width *
+/// row_count * channels` interleaved f32 samples (stored orientation, like
+/// `TiffFile::decode_rows`).
+#[wasm_bindgen]
+pub struct RowChunk {
+    start_row: u32,
+    row_count: u32,
+    width: u32,
+    channels: u32,
+    data: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl RowChunk {
+    #[wasm_bindgen(getter)]
+    pub fn start_row(&self) -> u32 { self.start_row }
+
+    #[wasm_bindgen(getter)]
+    pub fn row_count(&self) -> u32 { self.row_count }
+
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 { self.width }
+
+    #[wasm_bindgen(getter)]
+    pub fn channels(&self) -> u32 { self.channels }
+
+    /// Move the samples out (empty afterwards).
+    #[wasm_bindgen]
+    pub fn take_data(&mut self) -> Vec<f32> {
+        mem::take(&mut self.data)
+    }
+}
+
+/// Version of the `DecoderWorker` message protocol, sent as `v` in every
+/// request and response. Bumped whenever a request or response changes
+/// shape, so an embedder built against another version fails loudly instead
+/// of misreading fields.
+const WORKER_PROTOCOL_VERSION: u32 = 1;
+
+/// The `DecoderWorker` protocol version this build speaks.
+#[wasm_bindgen]
+pub fn worker_protocol_version() -> u32 {
+    WORKER_PROTOCOL_VERSION
+}
+
+/// Owns the images of one dedicated decode worker and answers the versioned
+/// message protocol, so every embedder can run the decoder off the main
+/// thread with the same few lines of glue instead of its own wrapper:
+///
+/// ```js
+/// const worker = new DecoderWorker();
+/// let pumping = false;
+/// const pump = () => {
+///   const reply = worker.run_next();
+///   if (reply === undefined) { pumping = false; return; }
+///   postMessage(reply, reply.transfer ?? []);
+///   setTimeout(pump);
+/// };
+/// onmessage = (e) => {
+///   for (const reply of worker.receive(e.data)) postMessage(reply);
+///   if (!pumping) { pumping = true; setTimeout(pump); }
+/// };
+/// ```
+///
+/// Only one `pump` loop runs at a time; it yields to the event loop between
+/// requests so a `cancel` can still reach `receive` while work is queued.
+///
+/// Requests are objects `{ v, id, type, ... }` with `id` a caller-chosen
+/// integer echoed in the response and `type` one of:
+///
+///  - `open` `{ data: Uint8Array }` -> `{ handle, format, pages }`. Only
+///    sniffs the format and counts pages; nothing is decoded yet.
+///  - `decode-page` `{ handle, page }` -> `{ page, width, height, channels,
+///    bits_per_sample, sample_format, min, max }`. Decodes the page (0 for
+///    non-TIFF formats) and keeps it as the handle's current image.
+///  - `render` `{ handle, min, max, gamma_in?, gamma_out?, exposure?,
+///    colormap?, nan_color?, output_space?, dither_seed?, clip_highlight? }`
+///    -> `{ width, height, rgba, settings }`, the current image through
+///    `TiffResult::render_rgba` (defaults: gamma 1, exposure 0, gray,
+///    magenta, "srgb", no dither, no clip highlight). `rgba` is a `Uint8ClampedArray` ready
+///    for `new ImageData(...)` and `settings` the parsed
+///    `render_settings_json` it was rendered with.
+///  - `stats` `{ handle, nodata?, valid_min?, valid_max? }` -> `{ bands }`,
+///    the parsed `StatsJob::result_json` of the current image.
+///  - `cancel` `{ target }` -> `{ target, dropped }`. Answered immediately
+///    by `receive`: a target still waiting in the queue is dropped and
+///    answered with a `cancelled` error. A request that is already running
+///    always completes, since each one is a single synchronous call.
+///  - `dispose` `{ handle }` -> `{}`. Frees the bytes and decoded page.
+///
+/// Every response is `{ v, id, type, ok: true, ... }` or `{ v, id, type,
+/// ok: false, error: { kind, message } }`, with `kind` "protocol"
+/// (malformed request, wrong version, or out-of-order use), "handle"
+/// (unknown or disposed handle), "decode" or "cancelled". Responses that
+/// carry pixels list their buffers in `transfer` for `postMessage`.
+#[wasm_bindgen]
+pub struct DecoderWorker {
+    images: HashMap<u32, WorkerImage>,
+    next_handle: u32,
+    queue: std::collections::VecDeque<(u32, WorkerRequest)>,
+}
+
+/// An `open`ed file: its (inflated) bytes and the last decoded page.
+struct WorkerImage {
+    data: Vec<u8>,
+    format: &'static str,
+    pages: u32,
+    current: Option<TiffResult>,
+}
+
+/// A parsed request, minus its `id`.
+enum WorkerRequest {
+    Open { data: Vec<u8> },
+    DecodePage { handle: u32, page: u32 },
+    Render { handle: u32, params: WorkerRenderParams },
+    Stats { handle: u32, nodata: Option<f64>, valid_min: Option<f64>, valid_max: Option<f64> },
+    Cancel { target: u32 },
+    Dispose { handle: u32 },
+}
+
+/// `render_rgba` arguments of a `render` request.
+struct WorkerRenderParams {
+    min: f64,
+    max: f64,
+    gamma_in: f64,
+    gamma_out: f64,
+    exposure_stops: f64,
+    colormap: String,
+    nan_color: Vec<u8>,
+    output_space: String,
+    dither_seed: Option<u32>,
+    clip_highlight: String,
+}
+
+/// The payload of a successful response.
+enum WorkerReply {
+    Opened { handle: u32, format: &'static str, pages: u32 },
+    Decoded { page: u32, width: u32, height: u32, channels: u32, bits_per_sample: u32, sample_format: u32, min: f64, max: f64 },
+    Rendered { width: u32, height: u32, rgba: Vec<u8>, settings_json: String },
+    Stats { bands_json: String },
+    Cancelled { target: u32, dropped: bool },
+    Disposed,
+}
+
+/// A failed response's `error`.
+struct WorkerError {
+    kind: &'static str,
+    message: String,
+}
+
+impl WorkerError {
+    fn new(kind: &'static str, message: impl Into<String>) -> Self {
+        WorkerError { kind, message: message.into() }
+    }
+
+    /// Wrap an error from the decoder's own API as a "decode" error.
+    fn decode(error: JsValue) -> Self {
+        WorkerError::new("decode", TiffError::from_js(error).message)
+    }
+}
+
+impl WorkerRequest {
+    /// The protocol's name for this request.
+    fn type_name(&self) -> &'static str {
+        match self {
+            WorkerRequest::Open { .. } => "open",
+            WorkerRequest::DecodePage { .. } => "decode-page",
+            WorkerRequest::Render { .. } => "render",
+            WorkerRequest::Stats { .. } => "stats",
+            WorkerRequest::Cancel { .. } => "cancel",
+            WorkerRequest::Dispose { .. } => "dispose",
+        }
+    }
+
+    /// Parse `{ v, id, type, ... }` into its `id` and request.
+    fn from_js(message: &JsValue) -> Result<(u32, WorkerRequest), WorkerError> {
+        let field = |name: &str| js_sys::Reflect::get(message, &JsValue::from_str(name)).unwrap_or(JsValue::UNDEFINED);
+        let optional_f64 = |name: &str| -> Result<Option<f64>, WorkerError> {
+            let value = field(name);
+            if value.is_undefined() || value.is_null() {
+                return Ok(None);
+            }
+            value.as_f64().map(Some).ok_or_else(|| WorkerError::new("protocol", format!("'{}' must be a number", name)))
+        };
+        let required_f64 = |name: &str| -> Result<f64, WorkerError> {
+            optional_f64(name)?.ok_or_else(|| WorkerError::new("protocol", format!("missing '{}'", name)))
+        };
+        let optional_u32 = |name: &str| -> Result<Option<u32>, WorkerError> {
+            match optional_f64(name)? {
+                Some(value) if value.fract() != 0.0 || !(0.0..=u32::MAX as f64).contains(&value) => {
+                    Err(WorkerError::new("protocol", format!("'{}' must be a non-negative integer", name)))
+                }
+                value => Ok(value.map(|value| value as u32)),
+            }
+        };
+        let required_u32 = |name: &str| -> Result<u32, WorkerError> {
+            optional_u32(name)?.ok_or_else(|| WorkerError::new("protocol", format!("missing '{}'", name)))
+        };
+        let optional_string = |name: &str, default: &str| -> Result<String, WorkerError> {
+            let value = field(name);
+            if value.is_undefined() || value.is_null() {
+                return Ok(default.to_string());
+            }
+            value.as_string().ok_or_else(|| WorkerError::new("protocol", format!("'{}' must be a string", name)))
+        };
+        let bytes = |name: &str, required: bool| -> Result<Vec<u8>, WorkerError> {
+            let value = field(name);
+            if let Some(array) = value.dyn_ref::<js_sys::Uint8Array>() {
+                Ok(array.to_vec())
+            } else if let Some(buffer) = value.dyn_ref::<js_sys::ArrayBuffer>() {
+                Ok(js_sys::Uint8Array::new(buffer).to_vec())
+            } else if !required && (value.is_undefined() || value.is_null()) {
+                Ok(Vec::new())
+            } else {
+                Err(WorkerError::new("protocol", format!("'{}' must be a Uint8Array or ArrayBuffer", name)))
+            }
+        };
+
+        if !message.is_object() {
+            return Err(WorkerError::new("protocol", "message must be an object"));
+        }
+        let version = optional_f64("v")?;
+        if version != Some(WORKER_PROTOCOL_VERSION as f64) {
+            return Err(WorkerError::new("protocol", format!(
+                "unsupported protocol version {} (this decoder speaks {})",
+                version.map_or("none".to_string(), |v| v.to_string()), WORKER_PROTOCOL_VERSION
+            )));
+        }
+        let id = required_u32("id")?;
+        let kind = optional_string("type", "")?;
+        let request = match kind.as_str() {
+            "open" => WorkerRequest::Open { data: bytes("data", true)? },
+            "decode-page" => WorkerRequest::DecodePage { handle: required_u32("handle")?, page: required_u32("page")? },
+            "render" => WorkerRequest::Render {
+                handle: required_u32("handle")?,
+                params: WorkerRenderParams {
+                    min: required_f64("min")?,
+                    max: required_f64("max")?,
+                    gamma_in: optional_f64("gamma_in")?.unwrap_or(1.0),
+                    gamma_out: optional_f64("gamma_out")?.unwrap_or(1.0),
+                    exposure_stops: optional_f64("exposure")?.unwrap_or(0.0),
+                    colormap: optional_string("colormap", "")?,
+                    nan_color: bytes("nan_color", false)?,
+                    output_space: optional_string("output_space", "srgb")?,
+                    dither_seed: optional_u32("dither_seed")?,
+                    clip_highlight: optional_string("clip_highlight", "")?,
+                },
+            },
+            "stats" => WorkerRequest::Stats {
+                handle: required_u32("handle")?,
+                nodata: optional_f64("nodata")?,
+                valid_min: optional_f64("valid_min")?,
+                valid_max: optional_f64("valid_max")?,
+            },
+            "cancel" => WorkerRequest::Cancel { target: required_u32("target")? },
+            "dispose" => WorkerRequest::Dispose { handle: required_u32("handle")? },
+            other => return Err(WorkerError::new("protocol", format!("unknown request type '{}'", other))),
+        };
+        Ok((id, request))
+    }
+}
+
+/// Build the response object for request `id` of type `kind`.
+fn worker_response(id: Option<u32>, kind: &str, outcome: Result<WorkerReply, WorkerError>) -> JsValue {
+    let object = js_sys::Object::new();
+    let set = |name: &str, value: JsValue| {
+        let _ = js_sys::Reflect::set(&object, &JsValue::from_str(name), &value);
+    };
+    set("v", JsValue::from(WORKER_PROTOCOL_VERSION));
+    set("id", id.map_or(JsValue::NULL, JsValue::from));
+    set("type", JsValue::from_str(kind));
+    set("ok", JsValue::from_bool(outcome.is_ok()));
+    match outcome {
+        Ok(WorkerReply::Opened { handle, format, pages }) => {
+            set("handle", JsValue::from(handle));
+            set("format", JsValue::from_str(format));
+            set("pages", JsValue::from(pages));
+        }
+        Ok(WorkerReply::Decoded { page, width, height, channels, bits_per_sample, sample_format, min, max }) => {
+            set("page", JsValue::from(page));
+            set("width", JsValue::from(width));
+            set("height", JsValue::from(height));
+            set("channels", JsValue::from(channels));
+            set("bits_per_sample", JsValue::from(bits_per_sample));
+            set("sample_format", JsValue::from(sample_format));
+            set("min", JsValue::from(min));
+            set("max", JsValue::from(max));
+        }
+        Ok(WorkerReply::Rendered { width, height, rgba, settings_json }) => {
+            let rgba = js_sys::Uint8ClampedArray::from(rgba.as_slice());
+            set("width", JsValue::from(width));
+            set("height", JsValue::from(height));
+            set("settings", js_sys::JSON::parse(&settings_json).unwrap_or(JsValue::NULL));
+            set("transfer", js_sys::Array::of1(&rgba.buffer()).into());
+            set("rgba", rgba.into());
+        }
+        Ok(WorkerReply::Stats { bands_json }) => {
+            set("bands", js_sys::JSON::parse(&bands_json).unwrap_or(JsValue::NULL));
+        }
+        Ok(WorkerReply::Cancelled { target, dropped }) => {
+            set("target", JsValue::from(target));
+            set("dropped", JsValue::from_bool(dropped));
+        }
+        Ok(WorkerReply::Disposed) => {}
+        Err(error) => {
+            let details = js_sys::Object::new();
+            let _ = js_sys::Reflect::set(&details, &JsValue::from_str("kind"), &JsValue::from_str(error.kind));
+            let _ = js_sys::Reflect::set(&details, &JsValue::from_str("message"), &JsValue::from_str(&error.message));
+            set("error", details.into());
+        }
+    }
+    object.into()
+}
+
+impl Default for DecoderWorker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen]
+impl DecoderWorker {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> DecoderWorker {
+        #[cfg(feature = "console_error_panic_hook")]
+        console_error_panic_hook::set_once();
+
+        DecoderWorker { images: HashMap::new(), next_handle: 1, queue: std::collections::VecDeque::new() }
+    }
+
+    /// Requests queued by `receive` and not yet run.
+    #[wasm_bindgen(getter)]
+    pub fn pending(&self) -> u32 {
+        self.queue.len() as u32
+    }
+
+    /// Take one incoming message. Work is queued for `run_next`; the
+    /// returned array holds the responses that are due right away: a
+    /// malformed request's error, or a `cancel`'s acknowledgement preceded
+    /// by the cancelled request's own `cancelled` error.
+    #[wasm_bindgen]
+    pub fn receive(&mut self, message: JsValue) -> js_sys::Array {
+        let replies = js_sys::Array::new();
+        match WorkerRequest::from_js(&message) {
+            Ok((id, WorkerRequest::Cancel { target })) => {
+                if let Some(kind) = self.cancel_queued(target) {
+                    replies.push(&worker_response(Some(target), kind, Err(WorkerError::new("cancelled", "cancelled before it started"))));
+                    replies.push(&worker_response(Some(id), "cancel", Ok(WorkerReply::Cancelled { target, dropped: true })));
+                } else {
+                    replies.push(&worker_response(Some(id), "cancel", Ok(WorkerReply::Cancelled { target, dropped: false })));
+                }
+            }
+            Ok((id, request)) => self.queue.push_back((id, request)),
+            Err(error) => {
+                let field = |name: &str| js_sys::Reflect::get(&message, &JsValue::from_str(name)).ok();
+                let id = field("id").and_then(|id| id.as_f64()).filter(|id| id.fract() == 0.0 && (0.0..=u32::MAX as f64).contains(id));
+                let kind = field("type").and_then(|kind| kind.as_string()).unwrap_or_default();
+                replies.push(&worker_response(id.map(|id| id as u32), &kind, Err(error)));
+            }
+        }
+        replies
+    }
+
+    /// Run the oldest queued request and return its response, or undefined
+    /// when the queue is empty.
+    #[wasm_bindgen]
+    pub fn run_next(&mut self) -> JsValue {
+        match self.queue.pop_front() {
+            Some((id, request)) => {
+                let kind = request.type_name();
+                worker_response(Some(id), kind, self.execute(request))
+            }
+            None => JsValue::UNDEFINED,
+        }
+    }
+}
+
+impl DecoderWorker {
+    /// Drop queued request `target`, returning its type if it was waiting.
+    fn cancel_queued(&mut self, target: u32) -> Option<&'static str> {
+        let position = self.queue.iter().position(|(id, _)| *id == target)?;
+        self.queue.remove(position).map(|(_, request)| request.type_name())
+    }
+
+    fn image(&mut self, handle: u32) -> Result<&mut WorkerImage, WorkerError> {
+        self.images.get_mut(&handle).ok_or_else(|| WorkerError::new("handle", format!("no open image with handle {}", handle)))
+    }
+
+    /// The handle's decoded page, for `render` and `stats`.
+    fn current(&mut self, handle: u32) -> Result<&TiffResult, WorkerError> {
+        match &self.image(handle)?.current {
+            Some(result) => Ok(result),
+            None => Err(WorkerError::new("protocol", format!("handle {} has no decoded page; send decode-page first", handle))),
+        }
+    }
+
+    fn execute(&mut self, request: WorkerRequest) -> Result<WorkerReply, WorkerError> {
+        match request {
+            WorkerRequest::Open { data } => {
+                let data = match gunzip_if_needed(&data).map_err(|e| WorkerError::new("decode", e.message))? {
+                    Cow::Owned(inflated) => inflated,
+                    Cow::Borrowed(_) => data,
+                };
+                let format = image_format_for(&data, "open").map_err(WorkerError::decode)?.name();
+                let pages = if format == "tiff" { tiff_page_count(&data).map_err(WorkerError::decode)? } else { 1 };
+                let handle = self.next_handle;
+                self.next_handle += 1;
+                self.images.insert(handle, WorkerImage { data, format, pages, current: None });
+                Ok(WorkerReply::Opened { handle, format, pages })
+            }
+            WorkerRequest::DecodePage { handle, page } => {
+                let image = self.image(handle)?;
+                if page >= image.pages {
+                    return Err(WorkerError::new("decode", format!("page {} is out of range ({} pages)", page, image.pages)));
+                }
+                // Free the previous page before decoding the next one.
+                image.current = None;
+                let result = if image.format == "tiff" {
+                    decode_tiff_page(&image.data, page)
+                } else {
+                    decode_image(&image.data)
+                }.map_err(WorkerError::decode)?;
+                let reply = WorkerReply::Decoded {
+                    page,
+                    width: result.width,
+                    height: result.height,
+                    channels: result.channels,
+                    bits_per_sample: result.bits_per_sample,
+                    sample_format: result.sample_format,
+                    min: result.value_range().0,
+                    max: result.value_range().1,
+                };
+                image.current = Some(result);
+                Ok(reply)
+            }
+            WorkerRequest::Render { handle, params } => {
+                let result = self.current(handle)?;
+                let settings = RenderSettings::resolve(
+                    "Render",
+                    params.min,
+                    params.max,
+                    params.gamma_in,
+                    params.gamma_out,
+                    params.exposure_stops,
+                    &params.colormap,
+                    &params.nan_color,
+                    &params.output_space,
+                    params.dither_seed,
+                    &params.clip_highlight,
+                ).map_err(WorkerError::decode)?;
+                let rgba = result.render_display_rgba(&settings).map_err(WorkerError::decode)?;
+                let (source, color_managed) = result.render_source();
+                let settings_json = settings.to_json(&source, color_managed);
+                Ok(WorkerReply::Rendered { width: result.width, height: result.height, rgba, settings_json })
+            }
+            WorkerRequest::Stats { handle, nodata, valid_min, valid_max } => {
+                let result = self.current(handle)?;
+                let mut job = result.start_stats(nodata, valid_min, valid_max).map_err(WorkerError::decode)?;
+                while !job.step(result, u32::MAX).map_err(WorkerError::decode)? {}
+                Ok(WorkerReply::Stats { bands_json: job.result_json() })
+            }
+            WorkerRequest::Cancel { target } => Ok(WorkerReply::Cancelled { target, dropped: self.cancel_queued(target).is_some() }),
+            WorkerRequest::Dispose { handle } => {
+                self.images.remove(&handle)
+                    .ok_or_else(|| WorkerError::new("handle", format!("no open image with handle {}", handle)))?;
+                Ok(WorkerReply::Disposed)
+            }
+        }
+    }
+}
+
+/// Every band's value at pixel (`x`, `y`) of a page (stored coordinates, i.e.
+/// before the Orientation tag is applied), for a spectrum plot on hover.
+/// Only the strip/tile holding the pixel is decoded - one per band for
+/// planar (PlanarConfiguration 2) files - and uncompressed data skips
+/// decoding entirely, reading just the sample bytes at the pixel's offset.
+/// Sparse chunks read as nodata (`sparse_fill_value`).
+#[wasm_bindgen]
+pub fn spectrum_at(data: &[u8], page_index: u32, x: u32, y: u32) -> Result<Vec<f32>, JsValue> {
+    use tiff::decoder::ChunkType;
+    use tiff::tags::Tag;
+
+    let gunzipped = gunzip_if_needed(data)?;
+    let data: &[u8] = &gunzipped;
+    let mut decoder = open_tiff_page(data, page_index)?;
+    let photometric = decoder.get_tag_u32(Tag::PhotometricInterpretation).unwrap_or(1);
+    let (as_grayscale, page_copy) = decodable_page(data, &mut decoder, page_index, photometric);
+    let data: &[u8] = page_copy.as_deref().unwrap_or(data);
+    let mut decoder = if as_grayscale || page_copy.is_some() { open_decodable_page(data, page_index, as_grayscale)? } else { decoder };
+    let (width, height) = decoder.dimensions()
+        .map_err(|e| TiffError::from_tiff("Failed to get dimensions", e))?;
+    if x >= width || y >= height {
+        return Err(JsValue::from_str(&format!(
+            "Pixel ({}, {}) is outside the {}x{} image", x, y, width, height
+        )));
+    }
+    let compression = decoder.get_tag_u32(Tag::Compression).unwrap_or(1);
+    let photometric = decoder.get_tag_u32(Tag::PhotometricInterpretation).unwrap_or(1);
+    if photometric == 3 || matches!(compression, 2 | 3 | 4 | 50000) || (compression == 7 && photometric == 6) {
+        return Err(JsValue::from_str(&format!(
+            "spectrum_at: compression {} / photometric {} needs a whole-image decode; use decode_tiff_page",
+            compression, photometric
+        )));
+    }
+    let predictor = decoder.get_tag_u32(Tag::Predictor).unwrap_or(1);
+    let planar = decoder.get_tag_u32(Tag::PlanarConfiguration).unwrap_or(1);
+    let bands = decoder.get_tag_u32(Tag::SamplesPerPixel).unwrap_or(1).max(1);
+    let bits_per_sample = decoder.get_tag_u16_vec(Tag::BitsPerSample)
+        .ok()
+        .and_then(|values| values.first().copied())
+        .unwrap_or(1) as u32;
+    let sample_format = decoder.get_tag_u64_vec(Tag::SampleFormat)
+        .ok()
+        .and_then(|values| values.first().copied())
+        .unwrap_or(1) as u32;
+    if !matches!(bits_per_sample, 8 | 16 | 32 | 64) {
+        return Err(JsValue::from_str(&format!(
+            "spectrum_at: {}-bit samples are not supported; use decode_tiff_page", bits_per_sample
+        )));
+    }
+
+    let (chunk_width, chunk_height) = decoder.chunk_dimensions();
+    if chunk_width == 0 || chunk_height == 0 {
+        return Err(JsValue::from_str("spectrum_at: page has no strip/tile layout"));
+    }
+    let tiled = decoder.get_chunk_type() == ChunkType::Tile;
+    let chunks_across = if tiled { width.div_ceil(chunk_width) } else { 1 };
+    let chunks_per_plane = chunks_across * height.div_ceil(chunk_height);
+    let chunk_in_plane = (y / chunk_height) * chunks_across + if tiled { x / chunk_width } else { 0 };
+    let (cx, cy) = (if tiled { x % chunk_width } else { x }, y % chunk_height);
+    let (offsets, counts) = if tiled {
+        (decoder.get_tag_u64_vec(Tag::TileOffsets), decoder.get_tag_u64_vec(Tag::TileByteCounts))
+    } else {
+        (decoder.get_tag_u64_vec(Tag::StripOffsets), decoder.get_tag_u64_vec(Tag::StripByteCounts))
+    };
+    let (offsets, counts) = (offsets.unwrap_or_default(), counts.unwrap_or_default());
+
+    // (chunk index, first band it holds, number of bands it holds)
+    let chunks: Vec<(u32, u32, u32)> = if planar == 2 {
+        (0..bands).map(|band| (band * chunks_per_plane + chunk_in_plane, band, 1)).collect()
+    } else {
+        vec![(chunk_in_plane, 0, bands)]
+    };
+    let little_endian = tiff_is_little_endian(data).unwrap_or(true);
+    let bytes_per_sample = (bits_per_sample / 8) as usize;
+    let mut spectrum = vec![0f32; bands as usize];
+    for (index, first_band, band_count) in chunks {
+        let offset = offsets.get(index as usize).copied().unwrap_or(0);
+        let count = counts.get(index as usize).copied().unwrap_or(0);
+        let out = &mut spectrum[first_band as usize..(first_band + band_count) as usize];
+        if is_sparse_chunk(offset, count) {
+            out.fill(sparse_fill_value(&mut decoder) as f32);
+            continue;
+        }
+        // f16 has no raw-byte conversion here; it goes through `read_chunk`.
+        if compression == 1 && predictor == 1 && !(sample_format == 3 && bytes_per_sample == 2) {
+            // Stored rows are `chunk_width` samples wide (the image width for
+            // strips, the full padded tile width for tiles).
+            let pixel = (cy as usize) * (chunk_width as usize) + cx as usize;
+            let start = offset as usize + pixel * band_count as usize * bytes_per_sample;
+            let end = start + band_count as usize * bytes_per_sample;
+            let bytes = data.get(start..end)
+                .ok_or_else(|| JsValue::from_str(&format!("spectrum_at: chunk {} is truncated", index)))?;
+            for (value, sample) in out.iter_mut().zip(bytes.chunks_exact(bytes_per_sample)) {
+                *value = sample_bytes_to_f32(sample, little_endian, sample_format);
+            }
+            continue;
+        }
+        let (data_width, _) = decoder.chunk_data_dimensions(index);
+        let decoded = decoder.read_chunk(index)
+            .map_err(|e| TiffError::from_tiff(&format!("Failed to decode TIFF chunk {}", index), e))?;
+        let values = decoding_result_to_f32(&decoded);
+        let start = ((cy as usize) * (data_width as usize) + cx as usize) * band_count as usize;
+        let samples = values.get(start..start + band_count as usize)
+            .ok_or_else(|| JsValue::from_str(&format!("spectrum_at: chunk {} has an unexpected size", index)))?;
+        out.copy_from_slice(samples);
+    }
+    Ok(spectrum)
+}
+
+/// Band `plane` of a page as a `width * height` f32 plane in display
+/// orientation - the same values as `decode_tiff_page(..).get_band_as_f32(plane)`
+/// - for switching between the R/G/B or spectral bands of an already-open
+/// file without re-running the whole-image decode. For planar
+/// (PlanarConfiguration 2) pages only that plane's strips/tiles are read at
+/// all, so the cost is one band's decompression instead of every band's
+/// plus interleaving; chunky pages still have to decode every strip/tile
+/// but skip building the full interleaved raster and its statistics.
+/// Takes the layouts `spectrum_at` does, and additionally rejects CMYK and
+/// YCbCr pages whose whole-image decode converts the bands to RGB.
+#[wasm_bindgen]
+pub fn decode_tiff_plane(data: &[u8], page_index: u32, plane: u32) -> Result<Vec<f32>, JsValue> {
+    use tiff::decoder::ChunkType;
+    use tiff::tags::Tag;
+
+    let gunzipped = gunzip_if_needed(data)?;
+    let data: &[u8] = &gunzipped;
+    let mut decoder = open_tiff_page(data, page_index)?;
+    let photometric = decoder.get_tag_u32(Tag::PhotometricInterpretation).unwrap_or(1);
+    let (as_grayscale, page_copy) = decodable_page(data, &mut decoder, page_index, photometric);
+    let data: &[u8] = page_copy.as_deref().unwrap_or(data);
+    let mut decoder = if as_grayscale || page_copy.is_some() { open_decodable_page(data, page_index, as_grayscale)? } else { decoder };
+    let (width, height) = decoder.dimensions()
+        .map_err(|e| TiffError::from_tiff("Failed to get dimensions", e))?;
+    let compression = decoder.get_tag_u32(Tag::Compression).unwrap_or(1);
+    let photometric = decoder.get_tag_u32(Tag::PhotometricInterpretation).unwrap_or(1);
+    if matches!(photometric, 3 | 5 | 6) || matches!(compression, 2 | 3 | 4 | 50000) {
+        return Err(JsValue::from_str(&format!(
+            "decode_tiff_plane: compression {} / photometric {} needs a whole-image decode; use decode_tiff_page",
+            compression, photometric
+        )));
+    }
+    let planar = decoder.get_tag_u32(Tag::PlanarConfiguration).unwrap_or(1);
+    let bands = decoder.get_tag_u32(Tag::SamplesPerPixel).unwrap_or(1).max(1);
+    if plane >= bands {
+        return Err(JsValue::from_str(&format!(
+            "decode_tiff_plane: plane {} is out of range for a {}-band page", plane, bands
+        )));
+    }
+    let bits_per_sample = decoder.get_tag_u16_vec(Tag::BitsPerSample)
+        .ok()
+        .and_then(|values| values.first().copied())
+        .unwrap_or(1) as u32;
+    if !matches!(bits_per_sample, 8 | 16 | 32 | 64) {
+        return Err(JsValue::from_str(&format!(
+            "decode_tiff_plane: {}-bit samples are not supported; use decode_tiff_page", bits_per_sample
+        )));
+    }
+    let orientation = TiffOrientation::from_tag(decoder.get_tag_u32(Tag::Orientation).unwrap_or(1));
+
+    let (chunk_width, chunk_height) = decoder.chunk_dimensions();
+    if chunk_width == 0 || chunk_height == 0 {
+        return Err(JsValue::from_str("decode_tiff_plane: page has no strip/tile layout"));
+    }
+    let tiled = decoder.get_chunk_type() == ChunkType::Tile;
+    let chunks_across = if tiled { width.div_ceil(chunk_width) } else { 1 };
+    let chunks_per_plane = chunks_across * height.div_ceil(chunk_height);
+    let (offsets, counts) = if tiled {
+        (decoder.get_tag_u64_vec(Tag::TileOffsets), decoder.get_tag_u64_vec(Tag::TileByteCounts))
+    } else {
+        (decoder.get_tag_u64_vec(Tag::StripOffsets), decoder.get_tag_u64_vec(Tag::StripByteCounts))
+    };
+    let (offsets, counts) = (offsets.unwrap_or_default(), counts.unwrap_or_default());
+
+    // Planar chunks hold one band each; chunky ones hold all of them.
+    let (first_chunk, stride, band_offset) = if planar == 2 {
+        (plane * chunks_per_plane, 1usize, 0usize)
+    } else {
+        (0, bands as usize, plane as usize)
+    };
+    let mut out = vec![0f32; (width as usize) * (height as usize)];
+    for chunk_in_plane in 0..chunks_per_plane {
+        let index = first_chunk + chunk_in_plane;
+        let (x0, y0, valid_width, valid_height) =
+            chunk_valid_rect(chunk_in_plane, width, height, chunk_width, chunk_height, chunks_across, tiled);
+        let (valid_width, valid_height) = (valid_width as usize, valid_height as usize);
+        let offset = offsets.get(index as usize).copied().unwrap_or(0);
+        let count = counts.get(index as usize).copied().unwrap_or(0);
+        if is_sparse_chunk(offset, count) {
+            let fill = sparse_fill_value(&mut decoder) as f32;
+            for row in 0..valid_height {
+                let start = (y0 as usize + row) * (width as usize) + x0 as usize;
+                out[start..start + valid_width].fill(fill);
+            }
+            continue;
+        }
+        let decoded = decoder.read_chunk(index)
+            .map_err(|e| TiffError::from_tiff(&format!("Failed to decode TIFF chunk {}", index), e))?;
+        let values = decoding_result_to_f32(&decoded);
+        let row_samples = valid_width * stride;
+        if values.len() < row_samples * valid_height {
+            return Err(JsValue::from_str(&format!("decode_tiff_plane: chunk {} has an unexpected size", index)));
+        }
+        for (row, samples) in values.chunks_exact(row_samples).take(valid_height).enumerate() {
+            let start = (y0 as usize + row) * (width as usize) + x0 as usize;
+            for (value, sample) in out[start..start + valid_width].iter_mut().zip(samples.iter().skip(band_offset).step_by(stride)) {
+                *value = *sample;
+            }
+        }
+    }
+
+    if orientation != TiffOrientation::TopLeft {
+        out = apply_orientation(&out, width, height, 1, orientation).0;
+    }
+    Ok(out)
+}
+
+/// One raw 1/2/4/8-byte sample as f32, per its byte order and SampleFormat
+/// (integer or f32/f64; f16 is not handled).
+fn sample_bytes_to_f32(bytes: &[u8], little_endian: bool, sample_format: u32) -> f32 {
+    let mut raw = [0u8; 8];
+    raw[..bytes.len()].copy_from_slice(bytes);
+    if !little_endian {
+        raw[..bytes.len()].reverse();
+    }
+    match (sample_format, bytes.len()) {
+        (2, 1) => raw[0] as i8 as f32,
+        (2, 2) => i16::from_le_bytes([raw[0], raw[1]]) as f32,
+        (2, 4) => i32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]) as f32,
+        (2, 8) => i64::from_le_bytes(raw) as f32,
+        (3, 4) => f32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]),
+        (3, 8) => f64::from_le_bytes(raw) as f32,
+        _ => u64::from_le_bytes(raw) as f32,
+    }
+}
+
+/// Least-recently-used cache keyed by strip/tile (or page) index, bounded
+/// both by an entry count and by the total byte size the caller reports per
+/// entry. Capacities are small (tens of chunks), so a most-recent-first
+/// `Vec` with a linear lookup is simpler than a linked hash map and just as
+/// fast.
+struct ChunkLru<T> {
+    capacity: usize,
+    byte_budget: usize,
+    bytes: usize,
+    entries: Vec<(u32, T, usize)>,
+    // Entries dropped to keep within the limits (not by `clear`).
+    evictions: u64,
+}
+
+impl<T> ChunkLru<T> {
+    fn new(capacity: usize, byte_budget: usize) -> Self {
+        ChunkLru { capacity, byte_budget, bytes: 0, entries: Vec::new(), evictions: 0 }
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    fn byte_budget(&self) -> usize {
+        self.byte_budget
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn contains(&self, index: u32) -> bool {
+        self.entries.iter().any(|(key, _, _)| *key == index)
+    }
+
+    /// Look up `index`, marking it most recently used.
+    fn get(&mut self, index: u32) -> Option<&T> {
+        let position = self.entries.iter().position(|(key, _, _)| *key == index)?;
+        let entry = self.entries.remove(position);
+        self.entries.insert(0, entry);
+        self.entries.first().map(|(_, value, _)| value)
+    }
+
+    /// Cache `value` as `index`, costing `bytes` of the budget. Entries that
+    /// alone exceed the budget are not kept (they would evict everything).
+    fn insert(&mut self, index: u32, value: T, bytes: usize) {
+        if self.capacity == 0 || bytes > self.byte_budget {
+            return;
+        }
+        if let Some(position) = self.entries.iter().position(|(key, _, _)| *key == index) {
+            let (_, _, old) = self.entries.remove(position);
+            self.bytes -= old;
+        }
+        self.entries.insert(0, (index, value, bytes));
+        self.bytes += bytes;
+        self.evict();
+    }
+
+    fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.evict();
+    }
+
+    fn set_byte_budget(&mut self, byte_budget: usize) {
+        self.byte_budget = byte_budget;
+        self.evict();
+    }
+
+    /// Drop least recently used entries until both limits hold.
+    fn evict(&mut self) {
+        while self.entries.len() > self.capacity || self.bytes > self.byte_budget {
+            match self.entries.pop() {
+                Some((_, _, bytes)) => {
+                    self.bytes -= bytes;
+                    self.evictions += 1;
+                }
+                None => break,
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.bytes = 0;
+    }
+}
+
+/// Stored samples of a page kept for point queries
+/// (`TiffResult::exact_values`): `channels` interleaved little-endian
+/// samples of type `element` per pixel, laid out like `get_data_bytes`
+/// (Orientation applied, origin top-left).
+#[wasm_bindgen]
+pub struct ExactValues {
+    width: u32,
+    height: u32,
+    channels: u32,
+    element: &'static str,
+    bytes_per_sample: usize,
+    data: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl ExactValues {
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 { self.width }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 { self.height }
+
+    #[wasm_bindgen(getter)]
+    pub fn channels(&self) -> u32 { self.channels }
+
+    /// Element type as in `TiffResult::layout`: "uint8".."uint64",
+    /// "int8".."int64", "float16", "float32" or "float64".
+    #[wasm_bindgen(getter)]
+    pub fn element(&self) -> String { self.element.to_string() }
+
+    #[wasm_bindgen(getter)]
+    pub fn byte_length(&self) -> usize { self.data.len() }
+
+    /// Every band's value at full-resolution pixel (`x`, `y`). A readout over
+    /// a preview or overview maps its position back first, e.g. `x << (level
+    /// + 1)` for `session_overview` level `level`. Exact for every element
+    /// type except 64-bit integers beyond 2^53; `value_text_at` has those.
+    #[wasm_bindgen]
+    pub fn values_at(&self, x: u32, y: u32) -> Result<Vec<f64>, JsValue> {
+        Ok(self.pixel(x, y)?.map(|sample| self.decode(sample)).collect())
+    }
+
+    /// Every band's value at pixel (`x`, `y`) as a JSON array of decimal
+    /// strings: integers in full, floats in the shortest form that reads back
+    /// to the same bits (so an f32 shows as "0.1", not "0.10000000149").
+    #[wasm_bindgen]
+    pub fn value_text_at(&self, x: u32, y: u32) -> Result<String, JsValue> {
+        let texts: Vec<String> = self
+            .pixel(x, y)?
+            .map(|sample| {
+                let text = match self.element {
+                    "uint64" => u64::from_le_bytes(sample.try_into().unwrap_or_default()).to_string(),
+                    "int64" => i64::from_le_bytes(sample.try_into().unwrap_or_default()).to_string(),
+                    "float32" => f32::from_le_bytes([sample[0], sample[1], sample[2], sample[3]]).to_string(),
+                    "float16" => f16_bits_to_f32(u16::from_le_bytes([sample[0], sample[1]])).to_string(),
+                    _ => self.decode(sample).to_string(),
+                };
+                format!("\"{}\"", text)
+            })
+            .collect();
+        Ok(format!("[{}]", texts.join(",")))
+    }
+}
+
+impl ExactValues {
+    /// The samples of pixel (`x`, `y`), one slice per band.
+    fn pixel(&self, x: u32, y: u32) -> Result<std::slice::ChunksExact<'_, u8>, JsValue> {
+        if x >= self.width || y >= self.height {
+            return Err(JsValue::from_str(&format!(
+                "Pixel ({}, {}) is outside the {}x{} image", x, y, self.width, self.height
+            )));
+        }
+        let pixel_bytes = self.channels as usize * self.bytes_per_sample;
+        let start = (y as usize * self.width as usize + x as usize) * pixel_bytes;
+        Ok(self.data[start..start + pixel_bytes].chunks_exact(self.bytes_per_sample))
+    }
+
+    fn decode(&self, sample: &[u8]) -> f64 {
+        let bytes8 = || sample.try_into().unwrap_or_default();
+        match self.element {
+            "uint8" => sample[0] as f64,
+            "int8" => sample[0] as i8 as f64,
+            "uint16" => u16::from_le_bytes([sample[0], sample[1]]) as f64,
+            "int16" => i16::from_le_bytes([sample[0], sample[1]]) as f64,
+            "uint32" => u32::from_le_bytes([sample[0], sample[1], sample[2], sample[3]]) as f64,
+            "int32" => i32::from_le_bytes([sample[0], sample[1], sample[2], sample[3]]) as f64,
+            "float16" => f16_bits_to_f32(u16::from_le_bytes([sample[0], sample[1]])) as f64,
+            "float32" => f32::from_le_bytes([sample[0], sample[1], sample[2], sample[3]]) as f64,
+            "uint64" => u64::from_le_bytes(bytes8()) as f64,
+            "int64" => i64::from_le_bytes(bytes8()) as f64,
+            _ => f64::from_le_bytes(bytes8()),
+        }
+    }
+}
+
+/// Size in bytes of one sample of `element` (an `ExactValues::element` name).
+fn element_bytes(element: &str) -> usize {
+    match element {
+        "int8" | "uint8" => 1,
+        "float16" | "int16" | "uint16" => 2,
+        "float32" | "int32" | "uint32" => 4,
+        _ => 8,
+    }
+}
+
+/// A JS typed array of `element` samples viewing a copy of the
+/// little-endian `bytes` (wasm memory is little endian too); float16 comes
+/// as a Uint16Array of the raw bits.
+fn typed_array(element: &str, bytes: &[u8]) -> JsValue {
+    let buffer = js_sys::Uint8Array::from(bytes).buffer();
+    match element {
+        "uint8" => js_sys::Uint8Array::new(&buffer).into(),
+        "int8" => js_sys::Int8Array::new(&buffer).into(),
+        "uint16" | "float16" => js_sys::Uint16Array::new(&buffer).into(),
+        "int16" => js_sys::Int16Array::new(&buffer).into(),
+        "uint32" => js_sys::Uint32Array::new(&buffer).into(),
+        "int32" => js_sys::Int32Array::new(&buffer).into(),
+        "float32" => js_sys::Float32Array::new(&buffer).into(),
+        "uint64" => js_sys::BigUint64Array::new(&buffer).into(),
+        "int64" => js_sys::BigInt64Array::new(&buffer).into(),
+        _ => js_sys::Float64Array::new(&buffer).into(),
+    }
+}
+
+/// One reduced-resolution level of a session overview pyramid
+/// (`TiffResult::build_session_overviews`): `channels` interleaved f32
+/// samples per pixel.
+#[wasm_bindgen]
+pub struct OverviewLevel {
+    width: u32,
+    height: u32,
+    channels: u32,
+    data: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl OverviewLevel {
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 { self.width }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 { self.height }
+
+    #[wasm_bindgen(getter)]
+    pub fn channels(&self) -> u32 { self.channels }
+
+    #[wasm_bindgen]
+    pub fn get_data_as_f32(&self) -> Vec<f32> {
+        self.data.clone()
+    }
+}
+
+/// Memory the session overview pyramids may take by default (see
+/// `set_session_overview_byte_budget`).
+const SESSION_OVERVIEW_BYTE_BUDGET: usize = 256 << 20;
+
+/// Overview pyramids built during this session, keyed by whatever the
+/// caller identifies the image with (e.g. document URI + page), most
+/// recently used first. Like `ChunkLru`, but keyed by string and bounded by
+/// bytes only: pyramids are evicted least recently used first once their
+/// samples exceed the budget.
+struct SessionOverviews {
+    byte_budget: usize,
+    bytes: usize,
+    entries: Vec<(String, Vec<OverviewLevel>)>,
+}
+
+impl SessionOverviews {
+    fn pyramid_bytes(levels: &[OverviewLevel]) -> usize {
+        levels.iter().map(|level| level.data.len() * 4).sum()
+    }
+
+    /// Look up `key`, marking it most recently used.
+    fn get(&mut self, key: &str) -> Option<&[OverviewLevel]> {
+        let position = self.entries.iter().position(|(k, _)| k == key)?;
+        let entry = self.entries.remove(position);
+        self.entries.insert(0, entry);
+        self.entries.first().map(|(_, levels)| levels.as_slice())
+    }
+
+    /// Cache `levels` as `key`, replacing any previous pyramid for it. A
+    /// pyramid that alone exceeds the budget is not kept.
+    fn insert(&mut self, key: &str, levels: Vec<OverviewLevel>) {
+        self.remove(key);
+        let bytes = Self::pyramid_bytes(&levels);
+        if bytes > self.byte_budget {
+            return;
+        }
+        self.entries.insert(0, (key.to_string(), levels));
+        self.bytes += bytes;
+        self.evict();
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(position) = self.entries.iter().position(|(k, _)| k == key) {
+            let (_, levels) = self.entries.remove(position);
+            self.bytes -= Self::pyramid_bytes(&levels);
+        }
+    }
+
+    fn set_byte_budget(&mut self, byte_budget: usize) {
+        self.byte_budget = byte_budget;
+        self.evict();
+    }
+
+    /// Drop least recently used pyramids until the budget holds.
+    fn evict(&mut self) {
+        while self.bytes > self.byte_budget {
+            match self.entries.pop() {
+                Some((_, levels)) => self.bytes -= Self::pyramid_bytes(&levels),
+                None => break,
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.bytes = 0;
+    }
+}
+
+thread_local! {
+    // Levels are finest first; each is half the size of the one before.
+    static SESSION_OVERVIEWS: RefCell<SessionOverviews> = const {
+        RefCell::new(SessionOverviews { byte_budget: SESSION_OVERVIEW_BYTE_BUDGET, bytes: 0, entries: Vec::new() })
+    };
+}
+
+/// Number of overview levels cached for `key` (0 if none were built, or
+/// they were evicted).
+#[wasm_bindgen]
+pub fn session_overview_count(key: &str) -> u32 {
+    SESSION_OVERVIEWS.with(|cache| cache.borrow_mut().get(key).map_or(0, |levels| levels.len() as u32))
+}
+
+/// Overview `level` (0 = half resolution) cached for `key`, without decoding
+/// anything.
+#[wasm_bindgen]
+pub fn session_overview(key: &str, level: u32) -> Result<OverviewLevel, JsValue> {
+    SESSION_OVERVIEWS.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        let overview = cache.get(key)
+            .and_then(|levels| levels.get(level as usize))
+            .ok_or_else(|| JsValue::from_str(&format!("No session overview level {} for '{}'", level, key)))?;
+        Ok(OverviewLevel {
+            width: overview.width,
+            height: overview.height,
+            channels: overview.channels,
+            data: overview.data.clone(),
+        })
+    })
+}
+
+/// Drop the overviews cached for `key` (e.g. when its document closes).
+#[wasm_bindgen]
+pub fn clear_session_overviews(key: &str) {
+    SESSION_OVERVIEWS.with(|cache| cache.borrow_mut().remove(key));
+}
+
+/// Drop every cached session overview.
+#[wasm_bindgen]
+pub fn clear_all_session_overviews() {
+    SESSION_OVERVIEWS.with(|cache| cache.borrow_mut().clear());
+}
+
+/// Cap the memory the session overview pyramids may hold (4 bytes per
+/// sample); least recently used pyramids are evicted beyond it, and one
+/// larger than the budget is not kept. 0 disables the cache. Defaults to
+/// 256 MiB.
+#[wasm_bindgen]
+pub fn set_session_overview_byte_budget(bytes: f64) {
+    let bytes = if bytes.is_finite() && bytes > 0.0 { bytes.min(usize::MAX as f64) as usize } else { 0 };
+    SESSION_OVERVIEWS.with(|cache| cache.borrow_mut().set_byte_budget(bytes));
+}
+
+/// Bytes the cached session overview pyramids currently hold.
+#[wasm_bindgen]
+pub fn session_overview_bytes() -> f64 {
+    SESSION_OVERVIEWS.with(|cache| cache.borrow().bytes as f64)
+}
+
+/// CIE xy primaries and white point (red, green, blue, white) of sRGB /
+/// Rec. 709, the assumed source gamut and the default output.
+const SRGB_PRIMARIES: [f64; 8] = [0.64, 0.33, 0.30, 0.60, 0.15, 0.06, 0.3127, 0.3290];
+
+/// Display-P3 (DCI-P3 primaries, D65 white), what wide-gamut displays and a
+/// `display-p3` canvas use.
+const DISPLAY_P3_PRIMARIES: [f64; 8] = [0.680, 0.320, 0.265, 0.690, 0.150, 0.060, 0.3127, 0.3290];
+
+type Matrix3 = [[f64; 3]; 3];
+
+fn mat3_mul(a: &Matrix3, b: &Matrix3) -> Matrix3 {
+    std::array::from_fn(|row| std::array::from_fn(|col| (0..3).map(|k| a[row][k] * b[k][col]).sum()))
+}
+
+fn mat3_apply(m: &Matrix3, v: [f64; 3]) -> [f64; 3] {
+    std::array::from_fn(|row| m[row][0] * v[0] + m[row][1] * v[1] + m[row][2] * v[2])
+}
+
+fn mat3_inverse(m: &Matrix3) -> Option<Matrix3> {
+    let cofactor = |r: usize, c: usize| {
+        let (r1, r2, c1, c2) = ((r + 1) % 3, (r + 2) % 3, (c + 1) % 3, (c + 2) % 3);
+        m[r1][c1] * m[r2][c2] - m[r1][c2] * m[r2][c1]
+    };
+    let det: f64 = (0..3).map(|c| m[0][c] * cofactor(0, c)).sum();
+    if !det.is_finite() || det.abs() < 1e-12 {
+        return None;
+    }
+    // The inverse is the transposed cofactor matrix over the determinant.
+    Some(std::array::from_fn(|row| std::array::from_fn(|col| cofactor(col, row) / det)))
+}
+
+/// Linear RGB to CIE XYZ for the given xy primaries and white point,
+/// scaled so white has Y = 1; None when the values don't span a gamut.
+fn rgb_to_xyz_matrix(primaries: &[f64; 8]) -> Option<Matrix3> {
+    // Primaries may be imaginary (ACES AP0 blue has y < 0), white may not.
+    if primaries.iter().any(|v| !v.is_finite()) || [1, 3, 5].iter().any(|&i| primaries[i] == 0.0) || primaries[7] <= 0.0 {
+        return None;
+    }
+    let xyz = |x: f64, y: f64| [x / y, 1.0, (1.0 - x - y) / y];
+    let columns = [
+        xyz(primaries[0], primaries[1]),
+        xyz(primaries[2], primaries[3]),
+        xyz(primaries[4], primaries[5]),
+    ];
+    let unscaled: Matrix3 = std::array::from_fn(|row| std::array::from_fn(|col| columns[col][row]));
+    let scale = mat3_apply(&mat3_inverse(&unscaled)?, xyz(primaries[6], primaries[7]));
+    Some(std::array::from_fn(|row| std::array::from_fn(|col| unscaled[row][col] * scale[col])))
+}
+
+/// Bradford chromatic adaptation of XYZ from one xy white point to another.
+fn bradford_adaptation(from: (f64, f64), to: (f64, f64)) -> Option<Matrix3> {
+    const BRADFORD: Matrix3 = [
+        [0.8951, 0.2664, -0.1614],
+        [-0.7502, 1.7135, 0.0367],
+        [0.0389, -0.0685, 1.0296],
+    ];
+    let cone = |(x, y): (f64, f64)| mat3_apply(&BRADFORD, [x / y, 1.0, (1.0 - x - y) / y]);
+    let (source, destination) = (cone(from), cone(to));
+    let gain: Matrix3 = std::array::from_fn(|row| {
+        std::array::from_fn(|col| if row == col { destination[row] / source[row] } else { 0.0 })
+    });
+    Some(mat3_mul(&mat3_inverse(&BRADFORD)?, &mat3_mul(&gain, &BRADFORD)))
+}
+
+/// An embedded ICC profile, read as far as this decoder uses it: the header
+/// fields, the description, and for RGB matrix/TRC ("matrix-shaper")
+/// profiles the colorant matrix and tone curves. LUT-based profiles (A2B0)
+/// are only described, not applied.
+struct IccProfile {
+    version: (u8, u8),
+    device_class: String,
+    color_space: String,
+    pcs: String,
+    description: Option<String>,
+    /// Linear RGB to PCS XYZ (D50): rXYZ, gXYZ and bXYZ as columns.
+    matrix: Option<Matrix3>,
+    /// rTRC, gTRC and bTRC.
+    curves: Option<[ToneCurve; 3]>,
+}
+
+/// An ICC tone curve from encoded 0..1 values to linear light.
+enum ToneCurve {
+    Gamma(f64),
+    /// Evenly spaced samples over 0..1, interpolated linearly.
+    Table(Vec<f64>),
+    /// The ICC v4 parametric function with all seven parameters (`g`, `a`,
+    /// `b`, `c`, `d`, `e`, `f`) filled in: `(a*x + b)^g + e` from `x >= d`,
+    /// `c*x + f` below. `function` is the stored type (0-4), for display.
+    Parametric { function: u16, params: [f64; 7] },
+}
+
+impl ToneCurve {
+    fn to_linear(&self, encoded: f64) -> f64 {
+        let x = encoded.clamp(0.0, 1.0);
+        match self {
+            ToneCurve::Gamma(gamma) => x.powf(*gamma),
+            ToneCurve::Table(table) => {
+                let position = x * (table.len() - 1) as f64;
+                let index = (position as usize).min(table.len() - 2);
+                let t = position - index as f64;
+                table[index] + t * (table[index + 1] - table[index])
+            }
+            ToneCurve::Parametric { params: [g, a, b, c, d, e, f], .. } => {
+                if x >= *d { (a * x + b).max(0.0).powf(*g) + e } else { c * x + f }
+            }
+        }
+    }
+
+    fn to_json(&self) -> String {
+        match self {
+            ToneCurve::Gamma(gamma) => format!("{{\"kind\":\"gamma\",\"gamma\":{}}}", json_f64(*gamma)),
+            ToneCurve::Table(table) => format!("{{\"kind\":\"table\",\"entries\":{}}}", table.len()),
+            ToneCurve::Parametric { function, params } => format!(
+                "{{\"kind\":\"parametric\",\"function\":{},\"params\":[{}]}}",
+                function,
+                params.iter().map(|&v| json_f64(v)).collect::<Vec<_>>().join(",")
+            ),
+        }
+    }
+}
+
+impl IccProfile {
+    /// None unless `data` has a complete ICC header and tag table.
+    fn parse(data: &[u8]) -> Option<IccProfile> {
+        let u32_at = |offset: usize| data.get(offset..offset + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]));
+        let signature = |offset: usize| data.get(offset..offset + 4).map(|b| String::from_utf8_lossy(b).trim_end().to_string());
+        if data.get(36..40)? != b"acsp" {
+            return None;
+        }
+        let tag_count = u32_at(128)? as usize;
+        let mut tags = HashMap::new();
+        for i in 0..tag_count.min(1024) {
+            let entry = 132 + i * 12;
+            let (offset, size) = (u32_at(entry + 4)? as usize, u32_at(entry + 8)? as usize);
+            if let Some(body) = data.get(offset..offset.saturating_add(size)) {
+                tags.insert(data.get(entry..entry + 4)?, body);
+            }
+        }
+        // s15Fixed16Number
+        let fixed = |b: &[u8], offset: usize| b.get(offset..offset + 4).map(|v| i32::from_be_bytes([v[0], v[1], v[2], v[3]]) as f64 / 65536.0);
+        let xyz = |name: &[u8]| {
+            let body = tags.get(name).filter(|body| body.starts_with(b"XYZ "))?;
+            Some([fixed(body, 8)?, fixed(body, 12)?, fixed(body, 16)?])
+        };
+        let curve = |name: &[u8]| -> Option<ToneCurve> {
+            let body = *tags.get(name)?;
+            let be16 = |offset: usize| body.get(offset..offset + 2).map(|v| u16::from_be_bytes([v[0], v[1]]));
+            match body.get(0..4)? {
+                b"curv" => {
+                    let count = u32::from_be_bytes(body.get(8..12)?.try_into().ok()?) as usize;
+                    match count {
+                        0 => Some(ToneCurve::Gamma(1.0)),
+                        1 => Some(ToneCurve::Gamma(be16(12)? as f64 / 256.0)),
+                        _ => (0..count).map(|i| be16(12 + 2 * i).map(|v| v as f64 / 65535.0)).collect::<Option<Vec<_>>>().map(ToneCurve::Table),
+                    }
+                }
+                b"para" => {
+                    let function = be16(8)?;
+                    let count = [1, 3, 4, 5, 7].get(function as usize)?;
+                    let stored: Vec<f64> = (0..*count).map(|i| fixed(body, 12 + 4 * i)).collect::<Option<_>>()?;
+                    let [g, a, b] = [stored[0], stored.get(1).copied().unwrap_or(1.0), stored.get(2).copied().unwrap_or(0.0)];
+                    let cutoff = if a != 0.0 { -b / a } else { 0.0 };
+                    let params = match function {
+                        0 => [g, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+                        1 => [g, a, b, 0.0, cutoff, 0.0, 0.0],
+                        2 => [g, a, b, 0.0, cutoff, stored[3], stored[3]],
+                        3 => [g, a, b, stored[3], stored[4], 0.0, 0.0],
+                        _ => [g, a, b, stored[3], stored[4], stored[5], stored[6]],
+                    };
+                    Some(ToneCurve::Parametric { function, params })
+                }
+                _ => None,
+            }
+        };
+        let description = tags.get(&b"desc"[..]).and_then(|body| {
+            match body.get(0..4)? {
+                b"desc" => {
+                    let length = u32::from_be_bytes(body.get(8..12)?.try_into().ok()?) as usize;
+                    let text = body.get(12..12usize.saturating_add(length))?;
+                    Some(String::from_utf8_lossy(text).trim_end_matches('\0').to_string())
+                }
+                b"mluc" => {
+                    let length = u32::from_be_bytes(body.get(20..24)?.try_into().ok()?) as usize;
+                    let offset = u32::from_be_bytes(body.get(24..28)?.try_into().ok()?) as usize;
+                    let units: Vec<u16> = body.get(offset..offset.saturating_add(length))?.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect();
+                    Some(String::from_utf16_lossy(&units).trim_end_matches('\0').to_string())
+                }
+                _ => None,
+            }
+        }).filter(|text| !text.is_empty());
+
+        let matrix = match (xyz(b"rXYZ"), xyz(b"gXYZ"), xyz(b"bXYZ")) {
+            (Some(r), Some(g), Some(b)) => {
+                let columns = [r, g, b];
+                let matrix: Matrix3 = std::array::from_fn(|row| std::array::from_fn(|col| columns[col][row]));
+                mat3_inverse(&matrix).map(|_| matrix)
+            }
+            _ => None,
+        };
+        let curves = match (curve(b"rTRC"), curve(b"gTRC"), curve(b"bTRC")) {
+            (Some(r), Some(g), Some(b)) => Some([r, g, b]),
+            _ => None,
+        };
+        Some(IccProfile {
+            version: (*data.get(8)?, data.get(9)? >> 4),
+            device_class: signature(12)?,
+            color_space: signature(16)?,
+            pcs: signature(20)?,
+            description,
+            matrix,
+            curves,
+        })
+    }
+
+    /// Whether `render_rgba` can colour-manage with this profile: RGB data,
+    /// an XYZ connection space and the full matrix/TRC set.
+    fn usable(&self) -> bool {
+        self.color_space == "RGB" && self.pcs == "XYZ" && self.matrix.is_some() && self.curves.is_some()
+    }
+
+    /// The colorants as `source_primaries`-style xy values (adapted to D50,
+    /// as ICC stores them), white being the colorant sum (D50 for a
+    /// well-formed profile).
+    fn primaries(&self) -> Option<[f64; 8]> {
+        let matrix = self.matrix?;
+        let xy = |v: [f64; 3]| {
+            let sum = v[0] + v[1] + v[2];
+            [v[0] / sum, v[1] / sum]
+        };
+        let column = |col: usize| [matrix[0][col], matrix[1][col], matrix[2][col]];
+        let white = mat3_apply(&matrix, [1.0, 1.0, 1.0]);
+        [xy(column(0)), xy(column(1)), xy(column(2)), xy(white)].concat().try_into().ok()
+    }
+
+    /// `TiffResult::icc_profile_json`.
+    fn to_json(&self, size: usize) -> String {
+        let text = |value: &str| format!("\"{}\"", json_escape(value));
+        format!(
+            "{{\"size\":{},\"version\":\"{}.{}\",\"device_class\":{},\"color_space\":{},\"pcs\":{},\"description\":{},\"matrix_shaper\":{},\"usable\":{},\"primaries\":{},\"tone_curves\":{}}}",
+            size,
+            self.version.0,
+            self.version.1,
+            text(&self.device_class),
+            text(&self.color_space),
+            text(&self.pcs),
+            self.description.as_deref().map_or("null".to_string(), text),
+            self.matrix.is_some() && self.curves.is_some(),
+            self.usable(),
+            self.primaries().map_or("null".to_string(), |p| format!("[{}]", p.iter().map(|&v| json_f64(v)).collect::<Vec<_>>().join(","))),
+            self.curves.as_ref().map_or("null".to_string(), |curves| format!("[{}]", curves.iter().map(ToneCurve::to_json).collect::<Vec<_>>().join(",")))
+        )
+    }
+}
+
+/// Converts display-encoded colour between two sets of primaries that share
+/// the sRGB transfer curve (as Display-P3 does): decode, convert in linear
+/// light, bring out-of-gamut colours in and encode again. Out-of-gamut
+/// colours are moved toward the gray of the same luminance just far enough
+/// for every channel to fit, which keeps hue and lightness where clipping
+/// each channel would shift them.
+struct GamutMapper {
+    matrix: Matrix3,
+    /// Target-space linear RGB weights giving luminance (Y).
+    luminance: [f64; 3],
+    /// The source's own tone curves (an ICC profile's TRCs) in place of the
+    /// sRGB curve; the output is always sRGB-encoded.
+    source_curves: Option<[ToneCurve; 3]>,
+}
+
+impl GamutMapper {
+    /// None when no conversion is needed (same primaries) or possible.
+    fn new(source: &[f64; 8], target: &[f64; 8]) -> Option<GamutMapper> {
+        if source == target {
+            return None;
+        }
+        let to_xyz = rgb_to_xyz_matrix(source)?;
+        let target_to_xyz = rgb_to_xyz_matrix(target)?;
+        let adapt = bradford_adaptation((source[6], source[7]), (target[6], target[7]))?;
+        let matrix = mat3_mul(&mat3_inverse(&target_to_xyz)?, &mat3_mul(&adapt, &to_xyz));
+        Some(GamutMapper { matrix, luminance: target_to_xyz[1], source_curves: None })
+    }
+
+    /// From colour encoded with an ICC matrix/TRC profile: decoded through
+    /// its tone curves, taken to PCS XYZ by its colorants and adapted from
+    /// the PCS white (D50, as the colorants sum to) to the target white.
+    /// Never a no-op, even for an sRGB profile, whose small differences from
+    /// the built-in curve are the point.
+    fn from_icc(profile: IccProfile, target: &[f64; 8]) -> Option<GamutMapper> {
+        let source = profile.primaries()?;
+        let (to_xyz, source_curves) = (profile.matrix?, profile.curves?);
+        let target_to_xyz = rgb_to_xyz_matrix(target)?;
+        let adapt = bradford_adaptation((source[6], source[7]), (target[6], target[7]))?;
+        let matrix = mat3_mul(&mat3_inverse(&target_to_xyz)?, &mat3_mul(&adapt, &to_xyz));
+        Some(GamutMapper { matrix, luminance: target_to_xyz[1], source_curves: Some(source_curves) })
+    }
+
+    /// Map encoded 0..1 RGB into the target gamut (encoded 0..1).
+    fn map(&self, encoded: [f64; 3]) -> [f64; 3] {
+        let decoded = match &self.source_curves {
+            Some(curves) => [0, 1, 2].map(|c| curves[c].to_linear(encoded[c])),
+            None => encoded.map(|v| srgb_to_linear(v as f32) as f64),
+        };
+        let linear = mat3_apply(&self.matrix, decoded);
+        let gray = (0..3).map(|i| self.luminance[i] * linear[i]).sum::<f64>().clamp(0.0, 1.0);
+        // Largest step from gray toward the colour that keeps each channel
+        // within 0..1.
+        let t = linear.iter().fold(1.0f64, |t, &c| {
+            if c < 0.0 {
+                t.min(gray / (gray - c))
+            } else if c > 1.0 {
+                t.min((1.0 - gray) / (c - gray))
+            } else {
+                t
+            }
+        });
+        linear.map(|c| linear_to_srgb((gray + t * (c - gray)).clamp(0.0, 1.0) as f32) as f64)
+    }
+
+    fn map_bytes(&self, rgb: [u8; 3]) -> [u8; 3] {
+        self.map(rgb.map(|v| v as f64 / 255.0)).map(|v| (v * 255.0).round() as u8)
+    }
+}
+
+/// Revision of the display renderer (`render_rgba`, `export_snapshot_png`),
+/// reported by `render_settings_json`. Bump it with any change that can
+/// alter the bytes rendered for the same settings, so stored golden images
+/// are known to be stale rather than silently failing.
+const RENDER_VERSION: u32 = 1;
+
+/// The validated inputs of one display render, with defaults resolved.
+struct RenderSettings {
+    min: f64,
+    max: f64,
+    gamma_in: f64,
+    gamma_out: f64,
+    exposure_stops: f64,
+    /// Encode with the sRGB curve in place of the `1 / gamma_out` power.
+    srgb_transfer: bool,
+    /// Colormap name and its sRGB table; `None` keeps gray.
+    colormap: Option<String>,
+    lut: Option<Vec<[u8; 3]>>,
+    nan_rgba: [u8; 4],
+    output_space: &'static str,
+    target: &'static [f64; 8],
+    dither_seed: Option<u32>,
+    clip_highlight: Option<ClipHighlight>,
+}
+
+/// How `render_rgba` marks pixels whose colour samples fall outside the
+/// display range: filled, or with diagonal stripes over the rendered pixel
+/// so the image detail stays visible between them. Below `min` is drawn in
+/// `CLIP_BELOW_RGB` and above `max` (which wins when a colour pixel is
+/// both) in `CLIP_ABOVE_RGB`; alpha is kept.
+#[derive(Clone, Copy, PartialEq)]
+enum ClipHighlight {
+    Solid,
+    Zebra,
+}
+
+impl ClipHighlight {
+    fn name(self) -> &'static str {
+        match self {
+            ClipHighlight::Solid => "solid",
+            ClipHighlight::Zebra => "zebra",
+        }
+    }
+
+    /// Whether the pixel at (`x`, `y`) is painted: every pixel for solid,
+    /// 4 px wide diagonal stripes for zebra.
+    fn covers(self, x: usize, y: usize) -> bool {
+        self == ClipHighlight::Solid || ((x + y) / 4).is_multiple_of(2)
+    }
+}
+
+/// How `TiffResult::render_confidence_rgba` shows the confidence band: as
+/// the pixel's alpha, or as the saturation of its colormap colour.
+#[derive(Clone, Copy)]
+enum ConfidenceMode {
+    Alpha,
+    Saturation,
+}
+
+const CLIP_BELOW_RGB: [u8; 3] = [0, 0, 255];
+const CLIP_ABOVE_RGB: [u8; 3] = [255, 0, 0];
+
+impl RenderSettings {
+    /// A sample's display value in 0..1 before it is quantized: normalized
+    /// from `min`..`max` and clamped, then put through the gamma/exposure
+    /// curve unless that is an identity, ending in the sRGB curve instead
+    /// of `1 / gamma_out` with `srgb_transfer`. A range that isn't finite and
+    /// increasing (e.g. the NaN `min_value` of a page with no valid samples)
+    /// maps every finite sample to 0, the same as a flat range, instead of
+    /// whatever NaN arithmetic yields.
+    fn unit_mapper(&self) -> impl Fn(f32) -> f64 {
+        let RenderSettings { min, max, gamma_in, gamma_out, exposure_stops, srgb_transfer, .. } = *self;
+        let identity = (gamma_in - gamma_out).abs() < 0.001 && exposure_stops == 0.0 && !srgb_transfer;
+        let valid_range = min.is_finite() && max.is_finite() && max > min;
+        let (min, inv_range) = if valid_range { (min, 1.0 / (max - min)) } else { (0.0, 0.0) };
+        let exposure = 2f64.powf(exposure_stops);
+        move |value: f32| -> f64 {
+            let normalized = ((value as f64 - min) * inv_range).clamp(0.0, 1.0);
+            if identity {
+                normalized
+            } else if srgb_transfer {
+                linear_to_srgb((normalized.powf(gamma_in) * exposure).clamp(0.0, 1.0) as f32) as f64
+            } else {
+                ((normalized.powf(gamma_in) * exposure).powf(1.0 / gamma_out)).clamp(0.0, 1.0)
+            }
+        }
+    }
+
+    /// Check `render_rgba`'s arguments; `context` prefixes error messages.
+    #[allow(clippy::too_many_arguments)]
+    fn resolve(
+        context: &str,
+        min: f64,
+        max: f64,
+        gamma_in: f64,
+        gamma_out: f64,
+        exposure_stops: f64,
+        colormap: &str,
+        nan_color: &[u8],
+        output_space: &str,
+        dither_seed: Option<u32>,
+        clip_highlight: &str,
+    ) -> Result<RenderSettings, JsValue> {
+        let (output_space, target) = match output_space {
+            "srgb" => ("srgb", &SRGB_PRIMARIES),
+            "display-p3" => ("display-p3", &DISPLAY_P3_PRIMARIES),
+            other => {
+                return Err(JsValue::from_str(&format!(
+                    "{}: unknown output colour space '{}' (expected \"srgb\" or \"display-p3\")", context, other
+                )))
+            }
+        };
+        let (colormap, lut) = match colormap {
+            "" | "none" => (None, None),
+            name => {
+                let lut = display_colormap_table(name)
+                    .ok_or_else(|| JsValue::from_str(&format!("{}: unknown colormap '{}'", context, name)))?;
+                (Some(name.to_string()), Some(lut))
+            }
+        };
+        let nan_rgba = match nan_color {
+            [] => [255, 0, 255, 255],
+            [r, g, b] => [*r, *g, *b, 255],
+            [r, g, b, a] => [*r, *g, *b, *a],
+            _ => return Err(JsValue::from_str(&format!("{}: nan_color needs 3 (RGB) or 4 (RGBA) bytes", context))),
+        };
+        let clip_highlight = match clip_highlight {
+            "" | "none" => None,
+            "solid" => Some(ClipHighlight::Solid),
+            "zebra" => Some(ClipHighlight::Zebra),
+            other => {
+                return Err(JsValue::from_str(&format!(
+                    "{}: unknown clip highlight '{}' (expected \"solid\" or \"zebra\")", context, other
+                )))
+            }
+        };
+        Ok(RenderSettings {
+            min,
+            max,
+            gamma_in,
+            gamma_out,
+            exposure_stops,
+            srgb_transfer: false,
+            colormap,
+            lut,
+            nan_rgba,
+            output_space,
+            target,
+            dither_seed,
+            clip_highlight,
+        })
+    }
+
+    /// `TiffResult::render_settings_json` for an image in `source` primaries
+    /// (an ICC profile's colorants when `color_managed`).
+    fn to_json(&self, source: &[f64; 8], color_managed: bool) -> String {
+        let list = |values: &[f64]| values.iter().map(|&v| json_f64(v)).collect::<Vec<_>>().join(",");
+        format!(
+            "{{\"render_version\":{},\"min\":{},\"max\":{},\"gamma_in\":{},\"gamma_out\":{},\"exposure_stops\":{},\"srgb_transfer\":{},\"colormap\":{},\"nan_color\":[{}],\"output_space\":\"{}\",\"source_primaries\":[{}],\"color_managed\":{},\"gamut_mapped\":{},\"dither\":{},\"clip_highlight\":{}}}",
+            RENDER_VERSION,
+            json_f64(self.min),
+            json_f64(self.max),
+            json_f64(self.gamma_in),
+            json_f64(self.gamma_out),
+            json_f64(self.exposure_stops),
+            self.srgb_transfer,
+            self.colormap.as_ref().map_or("null".to_string(), |name| format!("\"{}\"", json_escape(name))),
+            self.nan_rgba.map(|v| v.to_string()).join(","),
+            self.output_space,
+            list(source),
+            color_managed,
+            color_managed || GamutMapper::new(source, self.target).is_some(),
+            self.dither_seed.map_or("null".to_string(), |seed| format!("{{\"kind\":\"tpdf\",\"seed\":{}}}", seed)),
+            self.clip_highlight.map_or("null".to_string(), |style| format!(
+                "{{\"style\":\"{}\",\"below\":[{}],\"above\":[{}]}}",
+                style.name(),
+                CLIP_BELOW_RGB.map(|v| v.to_string()).join(","),
+                CLIP_ABOVE_RGB.map(|v| v.to_string()).join(",")
+            )),
+        )
+    }
+}
+
+/// Triangular (TPDF) dither offset in -1..1 output steps for sample `index`
+/// (pixel * 4 + channel) under `seed`. A counter-based hash (splitmix64's
+/// finalizer) rather than a sequential generator, so each offset depends
+/// only on the seed and the sample's position: rendering by rows, tiles or
+/// on several workers gives the same bytes as one pass.
+fn dither_offset(seed: u32, index: u64) -> f64 {
+    let z = counter_hash(seed, index);
+    let unit = |bits: u64| (bits & 0xff_ffff) as f64 / (1u64 << 24) as f64;
+    unit(z >> 32) + unit(z) - 1.0
+}
+
+/// 64 well-mixed bits for `index` under `seed` (splitmix64's finalizer), for
+/// pseudo-random choices that must not depend on visiting order.
+fn counter_hash(seed: u32, index: u64) -> u64 {
+    let mut z = index.wrapping_add((seed as u64) << 40).wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// sRGB electro-optical transfer function: encoded 0..1 to linear light.
+fn srgb_to_linear(encoded: f32) -> f32 {
+    if encoded <= 0.04045 { encoded / 12.92 } else { ((encoded + 0.055) / 1.055).powf(2.4) }
+}
+
+/// Inverse of `srgb_to_linear`.
+fn linear_to_srgb(linear: f32) -> f32 {
+    if linear <= 0.0031308 { linear * 12.92 } else { 1.055 * linear.powf(1.0 / 2.4) - 0.055 }
+}
+
+/// Where one output sample of `TiffResult::resample_viewport` comes from:
+/// a copy of one stored sample, or the weighted sum of four.
+enum ViewportTap {
+    Nearest(usize),
+    Blend([usize; 4], [f64; 4]),
+}
+
+/// Apply a `resample_viewport` plan to stored samples of any type, blending
+/// in f64 and converting back with `from_f64`.
+fn resample_by_plan<T: Copy>(
+    values: &[T],
+    plan: &[ViewportTap],
+    to_f64: impl Fn(T) -> f64,
+    from_f64: impl Fn(f64) -> T,
+) -> Vec<T> {
+    plan.iter()
+        .map(|tap| match *tap {
+            ViewportTap::Nearest(index) => values[index],
+            ViewportTap::Blend(indices, weights) => {
+                from_f64(indices.iter().zip(weights).map(|(&index, weight)| to_f64(values[index]) * weight).sum())
+            }
+        })
+        .collect()
+}
+
+/// Halve an interleaved f32 raster in both directions by averaging each 2x2
+/// block (a single row/column at odd edges). NaN samples are left out of the
+/// average, so nodata holes don't spread; an all-NaN block stays NaN.
+fn downsample_2x_f32(values: &[f32], width: u32, height: u32, channels: u32) -> (Vec<f32>, u32, u32) {
+    let (out_width, out_height) = (width.div_ceil(2), height.div_ceil(2));
+    let (w, c) = (width as usize, channels as usize);
+    let mut out = vec![0f32; (out_width as usize) * (out_height as usize) * c];
+    for oy in 0..out_height as usize {
+        let rows = (2 * oy)..(2 * oy + 2).min(height as usize);
+        for ox in 0..out_width as usize {
+            let cols = (2 * ox)..(2 * ox + 2).min(w);
+            for ch in 0..c {
+                let mut sum = 0f32;
+                let mut count = 0u32;
+                for y in rows.clone() {
+                    for x in cols.clone() {
+                        let value = values[(y * w + x) * c + ch];
+                        if !value.is_nan() {
+                            sum += value;
+                            count += 1;
+                        }
+                    }
+                }
+                out[(oy * out_width as usize + ox) * c + ch] = if count > 0 { sum / count as f32 } else { f32::NAN };
+            }
+        }
+    }
+    (out, out_width, out_height)
+}
+
+/// Counts of a 2D histogram (`TiffResult::joint_histogram`), row-major with
+/// `bins_x` columns: bin (`i`, `j`) is `counts[j * bins_x + i]`, covering
+/// `x_min + i * (x_max - x_min) / bins_x` and likewise for y. The last bin on
+/// each axis includes its upper edge.
+#[wasm_bindgen]
+pub struct JointHistogram {
+    bins_x: u32,
+    bins_y: u32,
+    x_min: f64,
+    x_max: f64,
+    y_min: f64,
+    y_max: f64,
+    counts: Vec<u32>,
+}
+
+#[wasm_bindgen]
+impl JointHistogram {
+    #[wasm_bindgen(getter)]
+    pub fn bins_x(&self) -> u32 { self.bins_x }
+
+    #[wasm_bindgen(getter)]
+    pub fn bins_y(&self) -> u32 { self.bins_y }
+
+    #[wasm_bindgen(getter)]
+    pub fn x_min(&self) -> f64 { self.x_min }
+
+    #[wasm_bindgen(getter)]
+    pub fn x_max(&self) -> f64 { self.x_max }
+
+    #[wasm_bindgen(getter)]
+    pub fn y_min(&self) -> f64 { self.y_min }
+
+    #[wasm_bindgen(getter)]
+    pub fn y_max(&self) -> f64 { self.y_max }
+
+    #[wasm_bindgen]
+    pub fn get_counts(&self) -> Vec<u32> {
+        self.counts.clone()
+    }
+}
+
+impl JointHistogram {
+    /// Bin finite `(x, y)` pairs over their own min..max ranges. An axis with
+    /// a single distinct value puts everything in its first bin; no pairs
+    /// leaves all counts 0 and the ranges NaN.
+    fn from_pairs(pairs: &[(f32, f32)], bins_x: u32, bins_y: u32) -> Self {
+        let (mut x_min, mut x_max, mut y_min, mut y_max) = (f32::INFINITY, f32::NEG_INFINITY, f32::INFINITY, f32::NEG_INFINITY);
+        for &(x, y) in pairs {
+            x_min = x_min.min(x);
+            x_max = x_max.max(x);
+            y_min = y_min.min(y);
+            y_max = y_max.max(y);
+        }
+        let bin = |value: f32, min: f32, max: f32, bins: u32| -> usize {
+            if max > min {
+                ((((value - min) as f64 / (max - min) as f64) * bins as f64) as usize).min(bins as usize - 1)
+            } else {
+                0
+            }
+        };
+        let mut counts = vec![0u32; (bins_x as usize) * (bins_y as usize)];
+        for &(x, y) in pairs {
+            let (i, j) = (bin(x, x_min, x_max, bins_x), bin(y, y_min, y_max, bins_y));
+            counts[j * bins_x as usize + i] += 1;
+        }
+        let range = |min: f32, max: f32| if pairs.is_empty() { (f64::NAN, f64::NAN) } else { (min as f64, max as f64) };
+        let ((x_min, x_max), (y_min, y_max)) = (range(x_min, x_max), range(y_min, y_max));
+        JointHistogram { bins_x, bins_y, x_min, x_max, y_min, y_max, counts }
+    }
+}
+
+/// Bins per band of the histogram `StatsJob` builds in its second pass to
+/// locate percentiles; they are accurate to `(max - min) / 4096`.
+const STATS_JOB_HISTOGRAM_BINS: usize = 4096;
+
+/// Resumable per-band statistics over a `TiffResult`
+/// (`TiffResult::start_stats`). Work happens in two passes over the samples,
+/// each split into slices by `step`: the first gathers min/max/mean/standard
+/// deviation and the NaN/Inf/nodata/out-of-range counts, the second bins the
+/// valid samples over each band's min..max to read off the 1st, 5th, 25th,
+/// 50th, 75th, 95th and 99th percentiles. The job holds no reference to the
+/// image, so the same `TiffResult` must be passed to every `step`.
+#[wasm_bindgen]
+pub struct StatsJob {
+    width: u32,
+    height: u32,
+    channels: usize,
+    nodata: Option<f64>,
+    valid_min: f64,
+    valid_max: f64,
+    // Band whose zero marks a fully transparent pixel to skip, and how many
+    // were skipped (`TiffResult::set_include_transparent_pixels`).
+    alpha_band: Option<usize>,
+    transparent_count: u64,
+    // Next pixel to visit and which pass (0 moments, 1 histogram, 2 done).
+    position: usize,
+    pass: u32,
+    bands: Vec<StatsJobBand>,
+}
+
+enum StatsSample {
+    Valid(f64),
+    Nodata,
+    Nan,
+    Inf,
+    OutOfRange,
+}
+
+struct StatsJobBand {
+    count: u64,
+    nan_count: u64,
+    inf_count: u64,
+    nodata_count: u64,
+    out_of_range_count: u64,
+    min: f64,
+    max: f64,
+    sum: f64,
+    sum_squares: f64,
+    histogram: Vec<u64>,
+}
+
+#[wasm_bindgen]
+impl StatsJob {
+    /// Fraction (0-1) of the work done across both passes.
+    #[wasm_bindgen(getter)]
+    pub fn progress(&self) -> f64 {
+        let pixels = (self.width as usize) * (self.height as usize);
+        if self.pass >= 2 || pixels == 0 {
+            return 1.0;
+        }
+        (self.pass as f64 + self.position as f64 / pixels as f64) / 2.0
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn done(&self) -> bool {
+        self.pass >= 2
+    }
+
+    /// Process up to `max_pixels` more pixels (all bands) of `image`, the
+    /// result `start_stats` was called on. Returns true once both passes are
+    /// complete; further calls do nothing.
+    #[wasm_bindgen]
+    pub fn step(&mut self, image: &TiffResult, max_pixels: u32) -> Result<bool, JsValue> {
+        image.live()?;
+        if (image.width, image.height, image.channels as usize) != (self.width, self.height, self.channels) {
+            return Err(JsValue::from_str("StatsJob: step was given a different image than start_stats"));
+        }
+        image.check_band(0)?;
+        let pixels = (self.width as usize) * (self.height as usize);
+        let samples = image.samples_f32();
+        let mut budget = max_pixels.max(1) as usize;
+        while self.pass < 2 && budget > 0 {
+            let end = pixels.min(self.position + budget);
+            let slice = &samples[self.position * self.channels..end * self.channels];
+            if self.pass == 0 {
+                self.accumulate_moments(slice);
+            } else {
+                self.accumulate_histogram(slice);
+            }
+            budget -= end - self.position;
+            self.position = end;
+            if self.position == pixels {
+                self.position = 0;
+                self.pass += 1;
+            }
+        }
+        Ok(self.pass >= 2)
+    }
+
+    /// JSON array with one object per band: `count` of valid samples, `min`,
+    /// `max`, `mean`, `std` (population), `p1`..`p99`, and `nan_count`,
+    /// `inf_count`, `nodata_count`, `out_of_range_count` and
+    /// `transparent_count` (fully transparent pixels skipped, the same for
+    /// every band). Values not yet computed (or for a band with no valid
+    /// samples) are `null`.
+    #[wasm_bindgen]
+    pub fn result_json(&self) -> String {
+        const PERCENTILES: [u32; 7] = [1, 5, 25, 50, 75, 95, 99];
+
+        let bands: Vec<String> = self
+            .bands
+            .iter()
+            .map(|band| {
+                let moments_done = self.pass >= 1 && band.count > 0;
+                let (min, max, mean, std) = if moments_done {
+                    let mean = band.sum / band.count as f64;
+                    let variance = (band.sum_squares / band.count as f64 - mean * mean).max(0.0);
+                    (band.min, band.max, mean, variance.sqrt())
+                } else {
+                    (f64::NAN, f64::NAN, f64::NAN, f64::NAN)
+                };
+                let percentiles: Vec<String> = PERCENTILES
+                    .iter()
+                    .map(|&p| {
+                        let value = if self.pass >= 2 && band.count > 0 { band.percentile(p) } else { f64::NAN };
+                        format!("\"p{}\":{}", p, json_f64(value))
+                    })
+                    .collect();
+                format!(
+                    "{{\"count\":{},\"min\":{},\"max\":{},\"mean\":{},\"std\":{},{},\"nan_count\":{},\"inf_count\":{},\"nodata_count\":{},\"out_of_range_count\":{},\"transparent_count\":{}}}",
+                    band.count,
+                    json_f64(min),
+                    json_f64(max),
+                    json_f64(mean),
+                    json_f64(std),
+                    percentiles.join(","),
+                    band.nan_count,
+                    band.inf_count,
+                    band.nodata_count,
+                    band.out_of_range_count,
+                    self.transparent_count
+                )
+            })
+            .collect();
+        format!("[{}]", bands.join(","))
+    }
+}
+
+impl StatsJob {
+    fn new(image: &TiffResult, nodata: Option<f64>, valid_min: Option<f64>, valid_max: Option<f64>) -> Self {
+        let band = || StatsJobBand {
+            count: 0,
+            nan_count: 0,
+            inf_count: 0,
+            nodata_count: 0,
+            out_of_range_count: 0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+            sum: 0.0,
+            sum_squares: 0.0,
+            histogram: Vec::new(),
+        };
+        StatsJob {
+            width: image.width,
+            height: image.height,
+            channels: image.channels as usize,
+            nodata,
+            valid_min: valid_min.unwrap_or(f64::NEG_INFINITY),
+            valid_max: valid_max.unwrap_or(f64::INFINITY),
+            alpha_band: image.transparent_alpha_band(),
+            transparent_count: 0,
+            position: 0,
+            pass: 0,
+            bands: (0..image.channels).map(|_| band()).collect(),
+        }
+    }
+
+    fn classify(&self, sample: f32) -> StatsSample {
+        let value = sample as f64;
+        if self.nodata.is_some_and(|nd| value == nd || (nd.is_nan() && sample.is_nan())) {
+            StatsSample::Nodata
+        } else if sample.is_nan() {
+            StatsSample::Nan
+        } else if sample.is_infinite() {
+            StatsSample::Inf
+        } else if value < self.valid_min || value > self.valid_max {
+            StatsSample::OutOfRange
+        } else {
+            StatsSample::Valid(value)
+        }
+    }
+
+    fn transparent(&self, pixel: &[f32]) -> bool {
+        self.alpha_band.is_some_and(|band| pixel[band] <= 0.0)
+    }
+
+    fn accumulate_moments(&mut self, samples: &[f32]) {
+        for pixel in samples.chunks_exact(self.channels) {
+            if self.transparent(pixel) {
+                self.transparent_count += 1;
+                continue;
+            }
+            for (band_index, &sample) in pixel.iter().enumerate() {
+                let class = self.classify(sample);
+                let band = &mut self.bands[band_index];
+                match class {
+                    StatsSample::Valid(value) => {
+                        band.count += 1;
+                        band.min = band.min.min(value);
+                        band.max = band.max.max(value);
+                        band.sum += value;
+                        band.sum_squares += value * value;
+                    }
+                    StatsSample::Nodata => band.nodata_count += 1,
+                    StatsSample::Nan => band.nan_count += 1,
+                    StatsSample::Inf => band.inf_count += 1,
+                    StatsSample::OutOfRange => band.out_of_range_count += 1,
+                }
+            }
+        }
+    }
+
+    fn accumulate_histogram(&mut self, samples: &[f32]) {
+        for band in &mut self.bands {
+            if band.histogram.is_empty() && band.count > 0 {
+                band.histogram = vec![0; STATS_JOB_HISTOGRAM_BINS];
+            }
+        }
+        for pixel in samples.chunks_exact(self.channels) {
+            if self.transparent(pixel) {
+                continue;
+            }
+            for (band_index, &sample) in pixel.iter().enumerate() {
+                if let StatsSample::Valid(value) = self.classify(sample) {
+                    let band = &mut self.bands[band_index];
+                    let bin = band.bin(value);
+                    band.histogram[bin] += 1;
+                }
+            }
+        }
+    }
+}
+
+impl StatsJobBand {
+    fn bin(&self, value: f64) -> usize {
+        if self.max > self.min {
+            (((value - self.min) / (self.max - self.min) * STATS_JOB_HISTOGRAM_BINS as f64) as usize).min(STATS_JOB_HISTOGRAM_BINS - 1)
+        } else {
+            0
+        }
+    }
+
+    /// Nearest-rank percentile `p` read off the histogram: the centre of the
+    /// bin holding that rank, clamped to min..max.
+    fn percentile(&self, p: u32) -> f64 {
+        let rank = ((p as f64 / 100.0) * (self.count - 1) as f64).round() as u64;
+        let mut seen = 0u64;
+        for (index, &count) in self.histogram.iter().enumerate() {
+            seen += count;
+            if seen > rank {
+                let width = (self.max - self.min) / STATS_JOB_HISTOGRAM_BINS as f64;
+                return (self.min + (index as f64 + 0.5) * width).clamp(self.min, self.max);
+            }
+        }
+        self.max
+    }
+}
+
+/// Per-channel histograms (`TiffResult::histograms`), channel-major: bin `i`
+/// of channel `c` is `counts[c * bins + i]`, covering
+/// `mins[c] + i * (maxs[c] - mins[c]) / bins`. The last bin includes its
+/// upper edge; a channel with no valid samples has all counts 0 and a NaN
+/// range.
+#[wasm_bindgen]
+pub struct ChannelHistograms {
+    bins: u32,
+    channels: u32,
+    mins: Vec<f64>,
+    maxs: Vec<f64>,
+    counts: Vec<u32>,
+}
+
+#[wasm_bindgen]
+impl ChannelHistograms {
+    #[wasm_bindgen(getter)]
+    pub fn bins(&self) -> u32 { self.bins }
+
+    #[wasm_bindgen(getter)]
+    pub fn channels(&self) -> u32 { self.channels }
+
+    #[wasm_bindgen]
+    pub fn get_mins(&self) -> Vec<f64> {
+        self.mins.clone()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_maxs(&self) -> Vec<f64> {
+        self.maxs.clone()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_counts(&self) -> Vec<u32> {
+        self.counts.clone()
+    }
+}
+
+#[cfg(feature = "exr")]
+#[wasm_bindgen]
+pub fn decode_exr_fast(data: &[u8]) -> Result<ExrResult, JsValue> {
+    #[cfg(feature = "console_error_panic_hook")]
+    console_error_panic_hook::set_once();
+
+    decode_exr_impl(data)
+}
+
+#[cfg(feature = "png")]
+#[wasm_bindgen]
+pub fn decode_png16_fast(data: &[u8]) -> Result<PngResult, JsValue> {
+    #[cfg(feature = "console_error_panic_hook")]
+    console_error_panic_hook::set_once();
+
+    decode_png16_impl(data)
+}
+
+#[cfg(feature = "png")]
+fn decode_png16_impl(data: &[u8]) -> Result<PngResult, JsValue> {
+    let start_time = now_ms();
+    let cursor = Cursor::new(data);
+    let limits = png::Limits { bytes: 512 * 1024 * 1024 };
+    let decoder = png::Decoder::new_with_limits(cursor, limits);
+    let mut reader = decoder.read_info()
+        .map_err(|e| JsValue::from_str(&format!("Failed to read PNG info: {}", e)))?;
+    let read_info_time = now_ms() - start_time;
+
+    let decode_start = now_ms();
+    let mut raw = vec![0u8; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut raw)
+        .map_err(|e| JsValue::from_str(&format!("Failed to decode PNG frame: {}", e)))?;
+    raw.truncate(info.buffer_size());
+    let decode_time = now_ms() - decode_start;
+
+    if info.bit_depth != png::BitDepth::Sixteen {
+        return Err(JsValue::from_str("Rust PNG fast path only supports 16-bit PNG output"));
+    }
+    let channels = match info.color_type {
+        png::ColorType::Grayscale => 1,
+        png::ColorType::Rgb => 3,
+        png::ColorType::GrayscaleAlpha => 2,
+        png::ColorType::Rgba => 4,
+        png::ColorType::Indexed => return Err(JsValue::from_str("Rust PNG fast path does not support indexed 16-bit PNG")),
+    };
+
+    let expected_values = (info.width as usize)
+        .checked_mul(info.height as usize)
+        .and_then(|v| v.checked_mul(channels as usize))
+        .ok_or_else(|| JsValue::from_str("PNG dimensions overflow"))?;
+    if raw.len() < expected_values * 2 {
+        return Err(JsValue::from_str("PNG decoded byte count is smaller than expected"));
+    }
+
+    let convert_start = now_ms();
+    let mut values: Vec<u16> = Vec::with_capacity(expected_values);
+    let src_ptr = raw.as_ptr();
+    let dst = values.as_mut_ptr();
+    for i in 0..expected_values {
+        // SAFETY: `raw` was checked to contain at least `expected_values * 2`
+        // bytes, and `values` has capacity for every output sample.
+        unsafe {
+            let be = (src_ptr.add(i * 2) as *const u16).read_unaligned();
+            dst.add(i).write(u16::from_be(be));
+        }
+    }
+    unsafe {
+        values.set_len(expected_values);
+    }
+    let convert_time = now_ms() - convert_start;
+    let total_time = now_ms() - start_time;
+
+    Ok(PngResult {
         width: info.width,
         height: info.height,
         channels,
@@ -603,6 +10268,49 @@ fn decode_png16_impl(data: &[u8]) -> Result<PngResult, JsValue> {
     })
 }
 
+/// Decode any PNG (1-16 bit gray/gray+alpha/RGB/RGBA or palette) at its
+/// full precision into a `TiffResult`. The browser's decoder clamps 16-bit
+/// samples to 8 bits, which flattens depth maps and breaks their stats and
+/// normalization; here 16-bit PNGs stay u16 (bits_per_sample 16), while
+/// palette and sub-byte images are expanded to 8-bit samples and a tRNS
+/// chunk becomes an alpha channel. Any eXIf chunk is reported through
+/// `all_tags_json`.
+#[cfg(feature = "png")]
+#[wasm_bindgen]
+pub fn decode_png(data: &[u8]) -> Result<TiffResult, JsValue> {
+    #[cfg(feature = "console_error_panic_hook")]
+    console_error_panic_hook::set_once();
+
+    let limits = png::Limits { bytes: 512 * 1024 * 1024 };
+    let mut decoder = png::Decoder::new_with_limits(Cursor::new(data), limits);
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let mut reader = decoder.read_info()
+        .map_err(|e| JsValue::from_str(&format!("Failed to read PNG info: {}", e)))?;
+    let all_tags_json = reader.info().exif_metadata.as_deref().map(extract_bare_ifd_tags_json).unwrap_or_default();
+    let mut raw = vec![0u8; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut raw)
+        .map_err(|e| JsValue::from_str(&format!("Failed to decode PNG frame: {}", e)))?;
+    raw.truncate(info.buffer_size());
+
+    let (color_type, bit_depth) = reader.output_color_type();
+    let channels = match color_type {
+        png::ColorType::Grayscale => 1,
+        png::ColorType::GrayscaleAlpha => 2,
+        png::ColorType::Rgb => 3,
+        png::ColorType::Rgba => 4,
+        png::ColorType::Indexed => return Err(JsValue::from_str("PNG: palette was not expanded")),
+    };
+    let pixels = match bit_depth {
+        png::BitDepth::Eight => DecodingResult::U8(raw),
+        png::BitDepth::Sixteen => DecodingResult::U16(
+            raw.chunks_exact(2).map(|b| u16::from_be_bytes([b[0], b[1]])).collect()
+        ),
+        other => return Err(JsValue::from_str(&format!("PNG: unexpected {:?} output after expansion", other))),
+    };
+    Ok(TiffResult::from_decoded(info.width, info.height, channels, pixels, all_tags_json))
+}
+
+#[cfg(feature = "png")]
 fn png_color_type_to_u32(color_type: png::ColorType) -> u32 {
     match color_type {
         png::ColorType::Grayscale => 0,
@@ -613,6 +10321,7 @@ fn png_color_type_to_u32(color_type: png::ColorType) -> u32 {
     }
 }
 
+#[cfg(feature = "hdr")]
 #[wasm_bindgen]
 pub fn decode_hdr_fast(data: &[u8]) -> Result<HdrResult, JsValue> {
     #[cfg(feature = "console_error_panic_hook")]
@@ -624,6 +10333,7 @@ pub fn decode_hdr_fast(data: &[u8]) -> Result<HdrResult, JsValue> {
 /// Turn Radiance HDR header lines into generic {name, value} tags: `KEY=VALUE`
 /// lines split on the first `=`, `#`-prefixed lines become "Comment" rows,
 /// anything else (e.g. the resolution line) is kept verbatim under "Header".
+#[cfg(feature = "hdr")]
 fn hdr_header_lines_to_json(lines: &[String]) -> String {
     let mut out = Vec::new();
     for line in lines {
@@ -639,16 +10349,32 @@ fn hdr_header_lines_to_json(lines: &[String]) -> String {
     format!("[{}]", out.join(","))
 }
 
-fn decode_hdr_impl(data: &[u8]) -> Result<HdrResult, JsValue> {
-    let start_time = js_sys::Date::now();
+/// The text header of a Radiance HDR file, up to and including the
+/// resolution line.
+#[cfg(feature = "hdr")]
+struct HdrHeader {
+    width: usize,
+    height: usize,
+    exposure: f32,
+    gamma: f32,
+    /// Every non-empty header line (comments, SOFTWARE=, VIEW=, custom
+    /// fields, and the recognized ones), kept generically for the Metadata
+    /// panel.
+    lines: Vec<String>,
+    /// Offset of the first scanline.
+    data_start: usize,
+}
+
+/// Parse and validate the header: only `-Y h +X w` RLE RGBE files are
+/// decoded, so anything else is rejected here already.
+#[cfg(feature = "hdr")]
+fn read_hdr_header(data: &[u8]) -> Result<HdrHeader, JsValue> {
     let mut offset = 0usize;
     let mut width = 0usize;
     let mut height = 0usize;
     let mut exposure = 1.0f32;
     let mut gamma = 1.0f32;
     let mut rle = false;
-    // Every non-empty header line (comments, SOFTWARE=, VIEW=, custom fields,
-    // and the recognized ones below), kept generically for the Metadata panel.
     let mut header_lines: Vec<String> = Vec::new();
 
     for _ in 0..128 {
@@ -692,7 +10418,6 @@ fn decode_hdr_impl(data: &[u8]) -> Result<HdrResult, JsValue> {
         }
     }
 
-    let header_time = js_sys::Date::now() - start_time;
     if width == 0 || height == 0 {
         return Err(JsValue::from_str("HDR resolution line not found"));
     }
@@ -703,19 +10428,29 @@ fn decode_hdr_impl(data: &[u8]) -> Result<HdrResult, JsValue> {
         return Err(JsValue::from_str("HDR scanline is too wide for RLE"));
     }
 
+    Ok(HdrHeader { width, height, exposure, gamma, lines: header_lines, data_start: offset })
+}
+
+#[cfg(feature = "hdr")]
+fn decode_hdr_impl(data: &[u8]) -> Result<HdrResult, JsValue> {
+    let start_time = now_ms();
+    let HdrHeader { width, height, exposure, gamma, lines: header_lines, data_start } = read_hdr_header(data)?;
+    let mut offset = data_start;
+    let header_time = now_ms() - start_time;
+
     let pixel_count = width.checked_mul(height)
         .ok_or_else(|| JsValue::from_str("HDR dimensions overflow"))?;
     let mut scanline = vec![0u8; width * 4];
     let mut output = vec![0f32; pixel_count * 4];
     let mut scales = [0f32; 256];
-    for e in 1..256 {
-        scales[e] = 2f32.powi(e as i32 - 128) / 255.0;
+    for (e, scale) in scales.iter_mut().enumerate().skip(1) {
+        *scale = 2f32.powi(e as i32 - 128) / 255.0;
     }
     let mut rle_time = 0.0;
     let mut convert_time = 0.0;
 
     for y in 0..height {
-        let rle_start = js_sys::Date::now();
+        let rle_start = now_ms();
         if offset + 4 > data.len() {
             return Err(JsValue::from_str("Unexpected EOF in HDR scanline header"));
         }
@@ -767,9 +10502,9 @@ fn decode_hdr_impl(data: &[u8]) -> Result<HdrResult, JsValue> {
                 }
             }
         }
-        rle_time += js_sys::Date::now() - rle_start;
+        rle_time += now_ms() - rle_start;
 
-        let convert_start = js_sys::Date::now();
+        let convert_start = now_ms();
         let row_offset = y * width * 4;
         for x in 0..width {
             let e = scanline[x + width * 3] as usize;
@@ -786,7 +10521,7 @@ fn decode_hdr_impl(data: &[u8]) -> Result<HdrResult, JsValue> {
             }
             output[out + 3] = 1.0;
         }
-        convert_time += js_sys::Date::now() - convert_start;
+        convert_time += now_ms() - convert_start;
     }
 
     Ok(HdrResult {
@@ -799,7 +10534,7 @@ fn decode_hdr_impl(data: &[u8]) -> Result<HdrResult, JsValue> {
             header_time,
             rle_time,
             convert_time,
-            js_sys::Date::now() - start_time,
+            now_ms() - start_time,
         ],
         all_tags_json: hdr_header_lines_to_json(&header_lines),
     })
@@ -817,6 +10552,7 @@ fn push_generic_attr_row(out: &mut Vec<String>, group: &str, name: &str, value_d
 /// each named `Option<T>` field on `ImageAttributes`/`LayerAttributes` plus
 /// the crate's own catch-all `other` maps for custom/vendor attributes, so
 /// nothing an EXR file carries is left out.
+#[cfg(feature = "exr")]
 fn extract_exr_tags_json(
     image_attrs: &exr::meta::header::ImageAttributes,
     layer_attrs: &exr::meta::header::LayerAttributes,
@@ -882,10 +10618,11 @@ fn extract_exr_tags_json(
     format!("[{}]", out.join(","))
 }
 
+#[cfg(feature = "exr")]
 fn decode_exr_impl(data: &[u8]) -> Result<ExrResult, JsValue> {
     use exr::prelude::*;
 
-    let start_time = js_sys::Date::now();
+    let start_time = now_ms();
     let cursor = Cursor::new(data);
     let image = read()
         .no_deep_data()
@@ -895,8 +10632,8 @@ fn decode_exr_impl(data: &[u8]) -> Result<ExrResult, JsValue> {
         .all_attributes()
         .from_buffered(cursor)
         .map_err(|e| JsValue::from_str(&format!("Failed to decode EXR: {}", e)))?;
-    let read_time = js_sys::Date::now() - start_time;
-    let pack_start = js_sys::Date::now();
+    let read_time = now_ms() - start_time;
+    let pack_start = now_ms();
 
     let layer = image.layer_data;
     let width = layer.size.0;
@@ -954,8 +10691,8 @@ fn decode_exr_impl(data: &[u8]) -> Result<ExrResult, JsValue> {
     };
 
     let format = if output_channels == 1 { 1028 } else { 1023 };
-    let pack_time = js_sys::Date::now() - pack_start;
-    let total_time = js_sys::Date::now() - start_time;
+    let pack_time = now_ms() - pack_start;
+    let total_time = now_ms() - start_time;
     let all_tags_json = extract_exr_tags_json(&image.attributes, &layer.attributes);
 
     Ok(ExrResult {
@@ -971,14 +10708,19 @@ fn decode_exr_impl(data: &[u8]) -> Result<ExrResult, JsValue> {
         timing_pack_ms: pack_time,
         timing_total_ms: total_time,
         all_tags_json,
+        primaries: image.attributes.chromaticities.map(|c| {
+            [c.red.0, c.red.1, c.green.0, c.green.1, c.blue.0, c.blue.1, c.white.0, c.white.1].map(f64::from)
+        }),
     })
 }
 
+#[cfg(feature = "exr")]
 struct ExrChannelSelection {
     source_indices: Vec<Option<usize>>,
     displayed_names: Vec<String>,
 }
 
+#[cfg(feature = "exr")]
 fn select_exr_display_channels(channel_names: &[String]) -> ExrChannelSelection {
     let mut y = None;
     let mut r = None;
@@ -1036,10 +10778,12 @@ fn select_exr_display_channels(channel_names: &[String]) -> ExrChannelSelection
     ExrChannelSelection { source_indices: Vec::new(), displayed_names: Vec::new() }
 }
 
+#[cfg(feature = "exr")]
 fn exr_base_channel_name(name: &str) -> &str {
     name.rsplit('.').next().unwrap_or(name)
 }
 
+#[cfg(feature = "exr")]
 fn copy_exr_channel_to_interleaved(
     samples: &FlatSamples,
     out: &mut [f32],
@@ -1066,6 +10810,7 @@ fn copy_exr_channel_to_interleaved(
     }
 }
 
+#[cfg(feature = "exr")]
 fn exr_samples_into_f32_vec(samples: FlatSamples, pixel_count: usize) -> Vec<f32> {
     match samples {
         FlatSamples::F16(values) => {
@@ -1089,6 +10834,7 @@ fn exr_samples_into_f32_vec(samples: FlatSamples, pixel_count: usize) -> Vec<f32
     }
 }
 
+#[cfg(feature = "exr")]
 fn fill_exr_interleaved_channel(
     out: &mut [f32],
     out_channel: usize,
@@ -1156,7 +10902,7 @@ fn value_to_display_string(value: &tiff::decoder::ifd::Value) -> String {
 /// object fragments. This walks the raw tag map generically, so it surfaces
 /// every tag present in the file rather than a curated subset.
 fn append_ifd_tags(
-    decoder: &mut Decoder<Cursor<&[u8]>>,
+    decoder: &mut PageDecoder<'_>,
     entries: Vec<(tiff::tags::Tag, tiff::decoder::ifd::Value)>,
     group: &str,
     out: &mut Vec<String>,
@@ -1216,7 +10962,7 @@ fn extract_ome_xml(data: &[u8]) -> String {
 }
 
 fn extract_page_tags_json(data: &[u8], page_index: u32) -> String {
-    let mut decoder = match Decoder::new(Cursor::new(data)) {
+    let mut decoder = match Decoder::new(PageCursor::new(data)) {
         Ok(d) => d,
         Err(_) => return "[]".to_string(),
     };
@@ -1225,16 +10971,171 @@ fn extract_page_tags_json(data: &[u8], page_index: u32) -> String {
             return "[]".to_string();
         }
     }
-    let main_entries: Vec<_> = decoder
+    // Photoshop's layer/resource blobs are listed with a short summary
+    // instead of their value: ImageSourceData alone is often hundreds of MB
+    // (past the decoder's IFD value limit, so it would otherwise just vanish
+    // from the list), and as a list of bytes it would dwarf everything else.
+    let tags: Vec<(tiff::tags::Tag, u64)> = decoder
         .image_ifd()
-        .tag_iter()
-        .filter_map(|r| r.ok())
+        .directory()
+        .iter()
+        .map(|(tag, entry)| (tag, entry.count()))
         .collect();
+    let mut main_entries = Vec::with_capacity(tags.len());
+    for (tag, count) in tags {
+        let summary = match tag.to_u16() {
+            PHOTOSHOP_IMAGE_SOURCE_DATA => match read_photoshop_layer_names(&mut decoder) {
+                Some(names) if !names.is_empty() => format!("{} bytes (Photoshop layers: {})", count, names.join(", ")),
+                _ => format!("{} bytes (Photoshop layer data)", count),
+            },
+            PHOTOSHOP_IMAGE_RESOURCES => format!("{} bytes (Photoshop image resources)", count),
+            _ => {
+                if let Ok(Some(value)) = decoder.find_tag(tag) {
+                    main_entries.push((tag, value));
+                }
+                continue;
+            }
+        };
+        main_entries.push((tag, tiff::decoder::ifd::Value::Ascii(summary)));
+    }
     let mut out = Vec::new();
     append_ifd_tags(&mut decoder, main_entries, "TIFF", &mut out);
     format!("[{}]", out.join(","))
 }
 
+/// Photoshop's layered-document blob ("ImageSourceData") and its image
+/// resource block. Neither is needed to show the image: Photoshop always
+/// writes the flattened composite as the regular page data.
+const PHOTOSHOP_IMAGE_SOURCE_DATA: u16 = 37724;
+const PHOTOSHOP_IMAGE_RESOURCES: u16 = 34377;
+
+/// Layer names of a TIFF saved from Photoshop with layers, bottom layer
+/// first, as a JSON array of strings (`[]` for files without layer data).
+/// The page itself always decodes to the flattened composite; this only
+/// lists what the layered source contained.
+#[wasm_bindgen]
+pub fn photoshop_layer_names(data: &[u8], page_index: u32) -> String {
+    let Ok(mut decoder) = Decoder::new(PageCursor::new(data)) else { return "[]".to_string() };
+    for _ in 0..page_index {
+        if decoder.next_image().is_err() {
+            return "[]".to_string();
+        }
+    }
+    let names = read_photoshop_layer_names(&mut decoder).unwrap_or_default();
+    let items: Vec<String> = names.iter().map(|name| format!("\"{}\"", json_escape(name))).collect();
+    format!("[{}]", items.join(","))
+}
+
+/// Read the current page's ImageSourceData and pull the layer names out of
+/// it; None if the tag is absent or not a layer block this understands.
+fn read_photoshop_layer_names(decoder: &mut PageDecoder<'_>) -> Option<Vec<String>> {
+    // The layer records sit ahead of the layers' pixel data, so a prefix of
+    // the blob is enough. Read as raw bytes: the regular value reader
+    // refuses values over the decoder's 1 MiB IFD value limit.
+    const PREFIX: u64 = 16 * 1024 * 1024;
+    let tag = tiff::tags::Tag::from_u16_exhaustive(PHOTOSHOP_IMAGE_SOURCE_DATA);
+    let count = decoder.image_ifd().find_entry(tag)?.count();
+    let mut blob = vec![0u8; count.min(PREFIX) as usize];
+    let read = decoder.image_ifd().find_tag_bytes(tag, &mut blob, 0).ok()??;
+    blob.truncate(read);
+    parse_photoshop_layer_names(&blob)
+}
+
+/// Layer names from (a prefix of) an ImageSourceData blob: the "Adobe
+/// Photoshop Document Data Block" signature followed by PSD-style tagged
+/// blocks, whose "Layr" (or 16/32-bit "Lr16"/"Lr32") block holds the layer
+/// records. The blob uses the TIFF file's byte order, which shows in the
+/// block signature ("8BIM" big endian, "MIB8" little endian). The Unicode
+/// name ("luni") is preferred over the legacy Pascal name, which Photoshop
+/// truncates.
+fn parse_photoshop_layer_names(blob: &[u8]) -> Option<Vec<String>> {
+    const HEADER: &[u8] = b"Adobe Photoshop Document Data Block\0";
+    let body = blob.strip_prefix(HEADER)?;
+    let little_endian = body.get(0..4)? == b"MIB8";
+    let u16_at = |data: &[u8], pos: usize| psd_u16(data, pos, little_endian);
+    let u32_at = |data: &[u8], pos: usize| psd_u32(data, pos, little_endian);
+
+    let (_, layers) = PsdBlocks::new(body, little_endian).find(|(key, _)| matches!(key, b"Layr" | b"Lr16" | b"Lr32"))?;
+    // A negative count only flags that the first alpha channel holds the
+    // merged transparency.
+    let layer_count = (u16_at(layers, 0)? as i16).unsigned_abs() as usize;
+    let mut names = Vec::with_capacity(layer_count);
+    let mut pos = 2usize;
+    for _ in 0..layer_count {
+        let channel_count = u16_at(layers, pos + 16)? as usize;
+        pos += 18 + channel_count * 6 + 12; // bounds, channels, blend mode/opacity/flags
+        let extra_len = u32_at(layers, pos)? as usize;
+        let extra = layers.get(pos + 4..(pos + 4).checked_add(extra_len)?)?;
+        pos += 4 + extra_len;
+
+        let mask_len = u32_at(extra, 0)? as usize;
+        let ranges_at = 4 + mask_len;
+        let ranges_len = u32_at(extra, ranges_at)? as usize;
+        let name_at = ranges_at + 4 + ranges_len;
+        let name_len = *extra.get(name_at)? as usize;
+        let pascal = extra.get(name_at + 1..name_at + 1 + name_len)?;
+        let mut name = String::from_utf8_lossy(pascal).into_owned();
+        let info = extra.get(name_at + (1 + name_len).div_ceil(4) * 4..).unwrap_or_default();
+        if let Some((_, unicode)) = PsdBlocks::new(info, little_endian).find(|(key, _)| key == b"luni") {
+            let chars = u32_at(unicode, 0).unwrap_or(0) as usize;
+            let units: Vec<u16> = (0..chars).map_while(|i| u16_at(unicode, 4 + i * 2)).collect();
+            name = String::from_utf16_lossy(&units).trim_end_matches('\0').to_string();
+        }
+        names.push(name);
+    }
+    Some(names)
+}
+
+fn psd_u16(data: &[u8], pos: usize, little_endian: bool) -> Option<u16> {
+    let b = data.get(pos..pos + 2)?;
+    Some(if little_endian { u16::from_le_bytes([b[0], b[1]]) } else { u16::from_be_bytes([b[0], b[1]]) })
+}
+
+fn psd_u32(data: &[u8], pos: usize, little_endian: bool) -> Option<u32> {
+    let b = data.get(pos..pos + 4)?;
+    let b = [b[0], b[1], b[2], b[3]];
+    Some(if little_endian { u32::from_le_bytes(b) } else { u32::from_be_bytes(b) })
+}
+
+/// Photoshop tagged blocks (signature "8BIM"/"8B64", 4-byte key, u32 length,
+/// data padded to 4 bytes) as `(key, data)`. Signatures and keys are 4-byte
+/// values in the blob's byte order, so they are un-reversed for little
+/// endian. A final block cut short by a truncated read yields what is there.
+struct PsdBlocks<'a> {
+    data: &'a [u8],
+    pos: usize,
+    little_endian: bool,
+}
+
+impl<'a> PsdBlocks<'a> {
+    fn new(data: &'a [u8], little_endian: bool) -> Self {
+        PsdBlocks { data, pos: 0, little_endian }
+    }
+
+    fn key_at(&self, pos: usize) -> Option<[u8; 4]> {
+        let b = self.data.get(pos..pos + 4)?;
+        Some(if self.little_endian { [b[3], b[2], b[1], b[0]] } else { [b[0], b[1], b[2], b[3]] })
+    }
+}
+
+impl<'a> Iterator for PsdBlocks<'a> {
+    type Item = ([u8; 4], &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let signature = self.key_at(self.pos)?;
+        if &signature != b"8BIM" && &signature != b"8B64" {
+            return None;
+        }
+        let key = self.key_at(self.pos + 4)?;
+        let len = psd_u32(self.data, self.pos + 8, self.little_endian)? as usize;
+        let start = self.pos + 12;
+        let end = start.saturating_add(len);
+        let block = self.data.get(start..end.min(self.data.len()))?;
+        self.pos = start.saturating_add(len.div_ceil(4) * 4);
+        Some((key, block))
+    }
+}
+
 /// TIFF/Exif field type sizes in bytes, per the TIFF6/Exif spec (type IDs 1-12).
 fn ifd_type_size(type_id: u16) -> usize {
     match type_id {
@@ -1395,6 +11296,23 @@ fn decoding_result_len(result: &DecodingResult) -> usize {
     }
 }
 
+/// Every sample of `result` as f32 (integers by value, not normalised).
+fn decoding_result_to_f32(result: &DecodingResult) -> Vec<f32> {
+    match result {
+        DecodingResult::U8(v) => v.iter().map(|&x| x as f32).collect(),
+        DecodingResult::U16(v) => v.iter().map(|&x| x as f32).collect(),
+        DecodingResult::U32(v) => v.iter().map(|&x| x as f32).collect(),
+        DecodingResult::U64(v) => v.iter().map(|&x| x as f32).collect(),
+        DecodingResult::I8(v) => v.iter().map(|&x| x as f32).collect(),
+        DecodingResult::I16(v) => v.iter().map(|&x| x as f32).collect(),
+        DecodingResult::I32(v) => v.iter().map(|&x| x as f32).collect(),
+        DecodingResult::I64(v) => v.iter().map(|&x| x as f32).collect(),
+        DecodingResult::F32(v) => v.clone(),
+        DecodingResult::F64(v) => v.iter().map(|&x| x as f32).collect(),
+        DecodingResult::F16(v) => v.iter().map(|x| x.to_f32()).collect(),
+    }
+}
+
 /// Naive, uncalibrated CMYK -> RGB conversion (no ICC profile applied):
 /// `R = (max-C)*(max-K)/max`, and likewise for G/B from M/Y. `max` is the
 /// full-scale value for the sample's numeric range (2^bits-1 for integer
@@ -1506,6 +11424,86 @@ impl TiffOrientation {
             TiffOrientation::LeftTop | TiffOrientation::RightTop | TiffOrientation::RightBottom | TiffOrientation::LeftBottom
         )
     }
+
+    /// The rectangle `(x, y, width, height)` of the stored `width` x
+    /// `height` raster that `apply_orientation` turns into the displayed
+    /// rectangle `rect` - the inverse of its per-pixel mapping, used by
+    /// `decode_tiff_region` to pick the strips/tiles to decode.
+    fn stored_rect(self, rect: (u32, u32, u32, u32), width: u32, height: u32) -> (u32, u32, u32, u32) {
+        let (x, y, rw, rh) = rect;
+        match self {
+            TiffOrientation::TopLeft => (x, y, rw, rh),
+            TiffOrientation::TopRight => (width - x - rw, y, rw, rh),
+            TiffOrientation::BottomRight => (width - x - rw, height - y - rh, rw, rh),
+            TiffOrientation::BottomLeft => (x, height - y - rh, rw, rh),
+            TiffOrientation::LeftTop => (y, x, rh, rw),
+            TiffOrientation::RightTop => (y, height - x - rw, rh, rw),
+            TiffOrientation::RightBottom => (width - y - rh, height - x - rw, rh, rw),
+            TiffOrientation::LeftBottom => (width - y - rh, x, rh, rw),
+        }
+    }
+
+    /// The stored-raster point under the displayed point `(x, y)`, in
+    /// continuous pixel coordinates (pixel `(i, j)` covers `[i, i + 1) x
+    /// [j, j + 1)`) of a stored `width` x `height` raster - the same mapping
+    /// as `apply_orientation`, so pixel centres land on pixel centres.
+    fn display_to_stored(self, x: f64, y: f64, width: u32, height: u32) -> (f64, f64) {
+        let (w, h) = (width as f64, height as f64);
+        match self {
+            TiffOrientation::TopLeft => (x, y),
+            TiffOrientation::TopRight => (w - x, y),
+            TiffOrientation::BottomRight => (w - x, h - y),
+            TiffOrientation::BottomLeft => (x, h - y),
+            TiffOrientation::LeftTop => (y, x),
+            TiffOrientation::RightTop => (y, h - x),
+            TiffOrientation::RightBottom => (w - y, h - x),
+            TiffOrientation::LeftBottom => (w - y, x),
+        }
+    }
+
+    /// The inverse of `display_to_stored`.
+    fn stored_to_display(self, x: f64, y: f64, width: u32, height: u32) -> (f64, f64) {
+        let (w, h) = (width as f64, height as f64);
+        match self {
+            TiffOrientation::TopLeft => (x, y),
+            TiffOrientation::TopRight => (w - x, y),
+            TiffOrientation::BottomRight => (w - x, h - y),
+            TiffOrientation::BottomLeft => (x, h - y),
+            TiffOrientation::LeftTop => (y, x),
+            TiffOrientation::RightTop => (h - y, x),
+            TiffOrientation::RightBottom => (h - y, w - x),
+            TiffOrientation::LeftBottom => (y, w - x),
+        }
+    }
+}
+
+/// How a decoded raster relates to the page it came from: the Orientation
+/// applied to the stored `stored_width` x `stored_height` raster, and the
+/// display-space offset of a `decode_tiff_region` crop. Behind
+/// `TiffResult::display_to_source` and its inverses.
+#[derive(Clone, Copy)]
+struct SourceFrame {
+    orientation: TiffOrientation,
+    stored_width: u32,
+    stored_height: u32,
+    origin_x: u32,
+    origin_y: u32,
+}
+
+impl SourceFrame {
+    fn new(orientation: TiffOrientation, stored_width: u32, stored_height: u32) -> Self {
+        SourceFrame { orientation, stored_width, stored_height, origin_x: 0, origin_y: 0 }
+    }
+
+    fn to_source(self, x: f64, y: f64) -> (f64, f64) {
+        let (x, y) = (x + self.origin_x as f64, y + self.origin_y as f64);
+        self.orientation.display_to_stored(x, y, self.stored_width, self.stored_height)
+    }
+
+    fn to_display(self, x: f64, y: f64) -> (f64, f64) {
+        let (x, y) = self.orientation.stored_to_display(x, y, self.stored_width, self.stored_height);
+        (x - self.origin_x as f64, y - self.origin_y as f64)
+    }
 }
 
 /// Apply a TIFF Orientation tag transform to an interleaved pixel buffer,
@@ -1547,6 +11545,37 @@ fn apply_orientation<T: Copy>(
     (out, out_w as u32, out_h as u32)
 }
 
+/// The `(x, y, width, height)` pixel window of an interleaved raster
+/// `row_pixels` pixels wide, each pixel `cell` elements (samples, or bytes
+/// for packed integer data) long.
+fn crop_interleaved<T: Copy>(data: &[T], row_pixels: u32, cell: usize, rect: (u32, u32, u32, u32)) -> Vec<T> {
+    let (x, y, width, height) = (rect.0 as usize, rect.1 as usize, rect.2 as usize, rect.3 as usize);
+    let row_len = row_pixels as usize * cell;
+    let mut out = Vec::with_capacity(width * height * cell);
+    for row in data.chunks_exact(row_len).skip(y).take(height) {
+        out.extend_from_slice(&row[x * cell..(x + width) * cell]);
+    }
+    out
+}
+
+/// `crop_interleaved` for every `DecodingResult` variant.
+fn crop_decoding_result(result: &DecodingResult, row_pixels: u32, channels: u32, rect: (u32, u32, u32, u32)) -> DecodingResult {
+    let cell = channels as usize;
+    match result {
+        DecodingResult::U8(v) => DecodingResult::U8(crop_interleaved(v, row_pixels, cell, rect)),
+        DecodingResult::U16(v) => DecodingResult::U16(crop_interleaved(v, row_pixels, cell, rect)),
+        DecodingResult::U32(v) => DecodingResult::U32(crop_interleaved(v, row_pixels, cell, rect)),
+        DecodingResult::U64(v) => DecodingResult::U64(crop_interleaved(v, row_pixels, cell, rect)),
+        DecodingResult::I8(v) => DecodingResult::I8(crop_interleaved(v, row_pixels, cell, rect)),
+        DecodingResult::I16(v) => DecodingResult::I16(crop_interleaved(v, row_pixels, cell, rect)),
+        DecodingResult::I32(v) => DecodingResult::I32(crop_interleaved(v, row_pixels, cell, rect)),
+        DecodingResult::I64(v) => DecodingResult::I64(crop_interleaved(v, row_pixels, cell, rect)),
+        DecodingResult::F16(v) => DecodingResult::F16(crop_interleaved(v, row_pixels, cell, rect)),
+        DecodingResult::F32(v) => DecodingResult::F32(crop_interleaved(v, row_pixels, cell, rect)),
+        DecodingResult::F64(v) => DecodingResult::F64(crop_interleaved(v, row_pixels, cell, rect)),
+    }
+}
+
 /// Shared post-decode finalization for the decode paths that produce their
 /// own complete `TiffResult` early (`decode_ccitt`, `decode_jpeg_ycbcr`,
 /// `decode_palette`) rather than flowing through `decode_tiff_impl`'s main
@@ -1585,7 +11614,7 @@ fn finalize_decode_bytes(
         return (data, width, height, channels);
     }
     let pixel_count = (width as usize) * (height as usize);
-    let bytes_per_pixel = if pixel_count > 0 { data.len() / pixel_count } else { 0 };
+    let bytes_per_pixel = data.len().checked_div(pixel_count).unwrap_or(0);
     if bytes_per_pixel == 0 {
         return (data, width, height, channels);
     }
@@ -1593,42 +11622,165 @@ fn finalize_decode_bytes(
     (oriented, w, h, channels)
 }
 
-fn decode_tiff_impl(data: &[u8], compute_stats: bool, page_index: u32) -> Result<TiffResult, JsValue> {
-    #[cfg(feature = "console_error_panic_hook")]
-    console_error_panic_hook::set_once();
+/// A `tiff` decoder over a file held in memory (see `PageCursor`).
+type PageDecoder<'a> = Decoder<PageCursor<&'a [u8]>>;
+
+/// Reader behind `PageDecoder`: a `Cursor` over the file's bytes that can
+/// read one inline SHORT tag value as something else, so a page decodes as
+/// if the tag said otherwise without copying the file. `TiffFile` keeps one
+/// that owns its bytes.
+struct PageCursor<B> {
+    cursor: Cursor<B>,
+    /// File offset of two bytes to read as the given ones instead.
+    patch: Option<(usize, [u8; 2])>,
+}
+
+impl<B: AsRef<[u8]>> PageCursor<B> {
+    fn new(data: B) -> Self {
+        PageCursor { cursor: Cursor::new(data), patch: None }
+    }
+}
+
+impl<B: AsRef<[u8]>> std::io::Read for PageCursor<B> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let start = self.cursor.position();
+        let read = self.cursor.read(buf)?;
+        if let Some((at, bytes)) = self.patch {
+            for (offset, byte) in (at..).zip(bytes) {
+                if let Some(slot) = (offset as u64).checked_sub(start).and_then(|i| buf[..read].get_mut(i as usize)) {
+                    *slot = byte;
+                }
+            }
+        }
+        Ok(read)
+    }
+}
+
+impl<B: AsRef<[u8]>> std::io::Seek for PageCursor<B> {
+    fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+        self.cursor.seek(pos)
+    }
+}
+
+/// Create a `tiff` decoder positioned on the zero-based top-level page
+/// `page_index`.
+fn open_tiff_page(data: &[u8], page_index: u32) -> Result<PageDecoder<'_>, TiffError> {
+    open_tiff_page_from(PageCursor::new(data), page_index)
+}
+
+/// `open_tiff_page` for a page `multiband_rgb_as_grayscale` picked: the
+/// decoder reads its PhotometricInterpretation as BlackIsZero, so no band is
+/// dropped, while `data` itself stays as it is.
+fn open_tiff_page_as_grayscale<B: AsRef<[u8]>>(data: B, page_index: u32) -> Result<Decoder<PageCursor<B>>, TiffError> {
+    let patch = inline_short_tag_offset(data.as_ref(), page_index, 262)
+        .map(|(at, little_endian)| (at, if little_endian { 1u16.to_le_bytes() } else { 1u16.to_be_bytes() }));
+    open_tiff_page_from(PageCursor { patch, ..PageCursor::new(data) }, page_index)
+}
+
+fn open_tiff_page_from<B: AsRef<[u8]>>(cursor: PageCursor<B>, page_index: u32) -> Result<Decoder<PageCursor<B>>, TiffError> {
+    let mut decoder = Decoder::new(cursor)
+        .map_err(|e| TiffError::from_tiff("Failed to create decoder", e))?;
+
+    for current in 0..page_index {
+        if !decoder.more_images() {
+            return Err(TiffError::new(TiffErrorKind::InvalidArgument, format!(
+                "TIFF page index {} is out of range (only {} page(s))",
+                page_index,
+                current + 1
+            )));
+        }
+        decoder.next_image()
+            .map_err(|e| TiffError::from_tiff(&format!("Failed to select TIFF page {}", page_index), e))?;
+    }
+    Ok(decoder)
+}
+
+/// The TIFF decode behind `decode_tiff`, `decode_tiff_page`,
+/// `decode_tiff_region` and friends; whatever fails comes back as a
+/// `TiffError`.
+fn decode_tiff_impl(
+    data: &[u8],
+    compute_stats: bool,
+    page_index: u32,
+    profile_chunks: bool,
+    region: Option<(u32, u32, u32, u32)>,
+) -> Result<TiffResult, JsValue> {
+    Ok(decode_tiff_pipeline(data, compute_stats, page_index, profile_chunks, region)?)
+}
+
+fn decode_tiff_pipeline(
+    data: &[u8],
+    compute_stats: bool,
+    page_index: u32,
+    profile_chunks: bool,
+    region: Option<(u32, u32, u32, u32)>,
+) -> Result<TiffResult, TiffError> {
+    #[cfg(feature = "console_error_panic_hook")]
+    console_error_panic_hook::set_once();
+
+    let start_time = now_ms();
+
+    let gunzipped = gunzip_if_needed(data)?;
+    let data: &[u8] = &gunzipped;
+
+    // RGB pages carrying more than the RGB(A) samples are read as
+    // BlackIsZero so no band is dropped (see `multiband_rgb_as_grayscale`),
+    // FillOrder 2 pages through a bit-reversed copy (`fill_order_reversed`);
+    // the original bytes and tag values are still what gets reported in
+    // `photometric_interpretation` and the tag dump.
+    let original_data = data;
+    let mut decoder = open_tiff_page(data, page_index)?;
+    let original_photometric = decoder.get_tag_u32(tiff::tags::Tag::PhotometricInterpretation).unwrap_or(1);
+    let (as_grayscale, page_copy) = decodable_page(data, &mut decoder, page_index, original_photometric);
+    let data: &[u8] = page_copy.as_deref().unwrap_or(data);
+    let mut decoder = if as_grayscale || page_copy.is_some() { open_decodable_page(data, page_index, as_grayscale)? } else { decoder };
 
-    let start_time = js_sys::Date::now();
+    let (width, height) = decoder.dimensions()
+        .map_err(|e| TiffError::from_tiff("Failed to get dimensions", e))?;
 
-    let cursor = Cursor::new(data);
-    let mut decoder = Decoder::new(cursor)
-        .map_err(|e| JsValue::from_str(&format!("Failed to create decoder: {}", e)))?;
+    // Orientation tag (274, default 1 = top-left / no transform). Applied as a
+    // pixel-buffer transform near the end of this function (after the decode
+    // path produces its final interleaved bytes/floats), and via
+    // `finalize_decode_bytes` for the CCITT/JPEG-YCbCr/palette early-return
+    // paths below, so it's shared by every decode path uniformly; every result
+    // records it in `source_frame`. The raw tag
+    // value is preserved here for `extract_all_tags_json` to report in the
+    // Metadata panel.
+    let orientation = TiffOrientation::from_tag(
+        decoder.get_tag_u32(tiff::tags::Tag::Orientation).unwrap_or(1)
+    );
 
-    for current in 0..page_index {
-        if !decoder.more_images() {
-            return Err(JsValue::from_str(&format!(
-                "TIFF page index {} is out of range (only {} page(s))",
-                page_index,
-                current + 1
+    // `decode_tiff_region` rectangles are in display coordinates, so they
+    // are checked against the oriented dimensions.
+    if let Some((x, y, region_width, region_height)) = region {
+        let (display_width, display_height) = if orientation.transposes() { (height, width) } else { (width, height) };
+        if region_width == 0
+            || region_height == 0
+            || x as u64 + region_width as u64 > display_width as u64
+            || y as u64 + region_height as u64 > display_height as u64
+        {
+            return Err(TiffError::new(TiffErrorKind::InvalidArgument, format!(
+                "Region {}x{} at ({}, {}) is outside the {}x{} image",
+                region_width, region_height, x, y, display_width, display_height
             )));
         }
-        decoder.next_image()
-            .map_err(|e| JsValue::from_str(&format!("Failed to select TIFF page {}: {}", page_index, e)))?;
     }
 
-    let (width, height) = decoder.dimensions()
-        .map_err(|e| JsValue::from_str(&format!("Failed to get dimensions: {}", e)))?;
-
     // Palette (RGBPalette, PhotometricInterpretation 3) images are rejected by
     // the tiff crate's colortype()/read_image(), so handle them via a dedicated
     // index + ColorMap path before those calls error out.
-    let photometric_early = decoder.get_tag_u32(tiff::tags::Tag::PhotometricInterpretation).unwrap_or(1);
-    if photometric_early == 3 {
-        return decode_palette(data, width, height, page_index);
+    if original_photometric == 3 {
+        let mut result = decode_palette(data, width, height, page_index)?;
+        result.source_frame = Some(SourceFrame::new(orientation, width, height));
+        if let Some(rect) = region {
+            result.crop_to_region(rect);
+        }
+        return Ok(result);
     }
 
     // Get color type and bits per sample
     let color_type = decoder.colortype()
-        .map_err(|e| JsValue::from_str(&format!("Failed to get color type: {}", e)))?;
+        .map_err(|e| TiffError::from_tiff("Failed to get color type", e))?;
 
     // `channels` MUST equal the actual per-pixel stride of the buffer we hand
     // back below, so SamplesPerPixel (tag 277) - not `color_type` - is the
@@ -1666,25 +11818,14 @@ fn decode_tiff_impl(data: &[u8], compute_stats: bool, page_index: u32) -> Result
     let predictor = decoder.get_tag_u32(tiff::tags::Tag::Predictor)
         .unwrap_or(1);
     
-    // Get photometric interpretation (default to 1 = BlackIsZero if not found)
-    let photometric_interpretation = decoder.get_tag_u32(tiff::tags::Tag::PhotometricInterpretation)
-        .unwrap_or(1);
+    // Photometric interpretation (default to 1 = BlackIsZero if not found),
+    // read above before any multiband patching
+    let photometric_interpretation = original_photometric;
     
     // Get planar configuration (default to 1 = Chunky if not found)
     let planar_configuration = decoder.get_tag_u32(tiff::tags::Tag::PlanarConfiguration)
         .unwrap_or(1);
 
-    // Orientation tag (274, default 1 = top-left / no transform). Applied as a
-    // pixel-buffer transform near the end of this function (after the decode
-    // path produces its final interleaved bytes/floats), and via
-    // `finalize_decode_bytes` for the CCITT/JPEG-YCbCr/palette early-return
-    // paths below, so it's shared by every decode path uniformly. The raw tag
-    // value is preserved here for `extract_all_tags_json` to report in the
-    // Metadata panel.
-    let orientation = TiffOrientation::from_tag(
-        decoder.get_tag_u32(tiff::tags::Tag::Orientation).unwrap_or(1)
-    );
-
     let rows_per_strip = decoder.get_tag_u32(tiff::tags::Tag::RowsPerStrip).unwrap_or(height);
     let strip_byte_counts = decoder.get_tag_u64_vec(tiff::tags::Tag::StripByteCounts).unwrap_or_default();
     let strip_count = strip_byte_counts.len() as u32;
@@ -1701,9 +11842,9 @@ fn decode_tiff_impl(data: &[u8], compute_stats: bool, page_index: u32) -> Result
     // them through hayro-ccitt, which understands the TIFF encoding options.
     if compression == 2 || compression == 3 || compression == 4 {
         let offsets = decoder.get_tag_u64_vec(tiff::tags::Tag::StripOffsets)
-            .map_err(|e| JsValue::from_str(&format!("CCITT: missing StripOffsets: {}", e)))?;
+            .map_err(|e| TiffError::other(format!("CCITT: missing StripOffsets: {}", e)))?;
         let counts = decoder.get_tag_u64_vec(tiff::tags::Tag::StripByteCounts)
-            .map_err(|e| JsValue::from_str(&format!("CCITT: missing StripByteCounts: {}", e)))?;
+            .map_err(|e| TiffError::other(format!("CCITT: missing StripByteCounts: {}", e)))?;
         // FillOrder defaults to 1 (MSB first); T4Options (tag 292) defaults to 0.
         let fill_order = decoder.get_tag_u32(tiff::tags::Tag::FillOrder).unwrap_or(1);
         let t4_options = decoder.get_tag_u32(tiff::tags::Tag::Unknown(292)).unwrap_or(0);
@@ -1715,74 +11856,538 @@ fn decode_tiff_impl(data: &[u8], compute_stats: bool, page_index: u32) -> Result
             &offsets, &counts, fill_order, t4_options, rows_per_strip, orientation,
         )?;
         result.all_tags_json = extract_page_tags_json(data, page_index);
+        result.geo = GeoInfo::read(&mut decoder);
+        result.page_metadata = PageMetadata::read(&mut decoder);
+        result.pixel_aspect_ratio = read_pixel_aspect_ratio(&mut decoder);
+        result.source_frame = Some(SourceFrame::new(orientation, width, height));
+        if let Some(rect) = region {
+            result.crop_to_region(rect);
+        }
+        return Ok(result);
+    }
+
+    // JPEG-compressed YCbCr (compression 7, PhotometricInterpretation 6). The
+    // tiff crate applies a YCbCr->RGB conversion on top of zune-jpeg's already
+    // converted RGB output (a double conversion), which tints grayscale-stored
+    // images. Decode the JPEG strips directly with zune-jpeg, which is correct.
+    if compression == 7 && photometric_interpretation == 6 {
+        let mut result = decode_jpeg_ycbcr(data, &mut decoder, width, height, orientation)?;
+        result.all_tags_json = extract_page_tags_json(data, page_index);
+        result.geo = GeoInfo::read(&mut decoder);
+        result.page_metadata = PageMetadata::read(&mut decoder);
+        result.pixel_aspect_ratio = read_pixel_aspect_ratio(&mut decoder);
+        result.primaries = read_tiff_primaries(&mut decoder);
+        result.icc_profile = read_tiff_icc_profile(&mut decoder);
+        result.source_frame = Some(SourceFrame::new(orientation, width, height));
+        if let Some(rect) = region {
+            result.crop_to_region(rect);
+        }
         return Ok(result);
     }
 
-    // JPEG-compressed YCbCr (compression 7, PhotometricInterpretation 6). The
-    // tiff crate applies a YCbCr->RGB conversion on top of zune-jpeg's already
-    // converted RGB output (a double conversion), which tints grayscale-stored
-    // images. Decode the JPEG strips directly with zune-jpeg, which is correct.
-    if compression == 7 && photometric_interpretation == 6 {
-        let mut result = decode_jpeg_ycbcr(data, &mut decoder, width, height, orientation)?;
-        result.all_tags_json = extract_page_tags_json(data, page_index);
-        return Ok(result);
-    }
+    // Pages with an alpha band take their min/max once the samples are
+    // packed, over the pixels that aren't fully transparent
+    // (`TiffResult::measure_stored_range`), in place of the decode paths'
+    // own stats pass, which sees samples rather than pixels.
+    let alpha_stats = compute_stats && alpha_band_index(photometric_interpretation, channels).is_some();
+    let chunk_stats = compute_stats && !alpha_stats;
+
+    let decode_start = now_ms();
+
+    // Read image data (decompression happens here). ZSTD (50000) is decoded
+    // with the pure-Rust ruzstd crate rather than the tiff crate's C zstd, so
+    // the WASM build needs no C toolchain. The decompressed strips are rebuilt
+    // into an uncompressed TIFF and handed back to the tiff crate, which still
+    // performs predictor un-application and type/endianness handling.
+    let mut direct_decode = false;
+    let mut prepacked = None;
+    // Min/max the direct strip paths folded in while each strip was decoded,
+    // sparing `pack_decoding_result` a second pass over the whole image.
+    let mut strip_stats = None;
+    let mut profile = ChunkProfile::new(profile_chunks);
+    // A region only the streaming path can decode directly (every other path
+    // works on the whole page) is handed to it first, mapped back to stored
+    // coordinates; when it declines, the full result is cropped at the end.
+    let stored_region = region.map(|rect| orientation.stored_rect(rect, width, height));
+    let mut region_decoded = false;
+    let decode_result = if compression == 50000 {
+        profile.path = "zstd";
+        Some(decode_zstd(data, &mut decoder, &mut profile)?)
+    } else if let Some(result) = match stored_region {
+        Some(rect) => try_decode_streaming_chunks(
+            &mut decoder,
+            width,
+            height,
+            channels,
+            bits_per_sample,
+            photometric_interpretation,
+            planar_configuration,
+            chunk_stats,
+            Some(rect),
+            &mut profile,
+        )?,
+        None => None,
+    } {
+        profile.path = "region";
+        region_decoded = true;
+        prepacked = Some(result);
+        None
+    } else if let Some(result) = try_decode_general_strips_tiles(
+        data,
+        &mut decoder,
+        width,
+        height,
+        channels,
+        bits_per_sample,
+        compression,
+        predictor,
+        planar_configuration,
+        tile_width,
+        tile_length,
+        chunk_stats,
+        &mut profile,
+    )? {
+        direct_decode = true;
+        profile.path = "planar_tiled";
+        strip_stats = result.1;
+        Some(result.0)
+    } else if let Some(result) = try_decode_subbit_strips(
+        data,
+        &mut decoder,
+        width,
+        height,
+        channels,
+        bits_per_sample,
+        compression,
+        predictor,
+        planar_configuration,
+        chunk_stats,
+        &mut profile,
+    )? {
+        direct_decode = true;
+        profile.path = "subbit";
+        strip_stats = result.1;
+        Some(result.0)
+    } else if let Some(result) = try_copy_uncompressed_strips(
+        data,
+        &mut decoder,
+        width,
+        height,
+        channels,
+        bits_per_sample,
+        compression,
+        predictor,
+        photometric_interpretation,
+        planar_configuration,
+        chunk_stats,
+        &mut profile,
+    )? {
+        direct_decode = true;
+        profile.path = "uncompressed_copy";
+        prepacked = Some(result);
+        None
+    } else if let Some(result) = try_decode_streaming_chunks(
+        &mut decoder,
+        width,
+        height,
+        channels,
+        bits_per_sample,
+        photometric_interpretation,
+        planar_configuration,
+        chunk_stats,
+        None,
+        &mut profile,
+    )? {
+        profile.path = "streaming";
+        prepacked = Some(result);
+        None
+    } else if let Some(result) = try_decode_uncompressed_strips(
+        data,
+        &mut decoder,
+        width,
+        height,
+        channels,
+        bits_per_sample,
+        compression,
+        predictor,
+        planar_configuration,
+        &mut profile,
+    )? {
+        direct_decode = true;
+        profile.path = "uncompressed";
+        Some(result)
+    } else {
+        profile.path = "read_image";
+        Some(decoder.read_image()
+            .map_err(|e| TiffError::from_tiff("Failed to decode image", e))?)
+    };
+
+    let decompress_time = now_ms() - decode_start;
+    let convert_start = now_ms();
+    let mut stats_time = 0.0;
+    let mut pack_time = 0.0;
+    let mut prepacked_convert_time = 0.0;
+
+    // Plain uncompressed strips (`try_copy_uncompressed_strips`) and large
+    // chunky images (`try_decode_streaming_chunks`) come back already packed
+    // with their stats computed, so everything up to the Orientation step
+    // below only applies to whole-image results.
+    let (packed, decompress_time) = match (decode_result, prepacked) {
+        (_, Some((packed, timings))) => {
+            stats_time = timings.stats_ms;
+            pack_time = timings.pack_ms;
+            prepacked_convert_time = timings.stats_ms + timings.pack_ms;
+            (packed, timings.decode_ms)
+        }
+        (Some(decode_result), None) => {
+            let packed = pack_decoding_result(
+                decode_result,
+                direct_decode,
+                width,
+                height,
+                &mut channels,
+                &mut bits_per_sample,
+                photometric_interpretation,
+                chunk_stats,
+                strip_stats,
+                &mut stats_time,
+                &mut pack_time,
+            );
+            (packed, decompress_time)
+        }
+        (None, None) => unreachable!("every decode path yields either a DecodingResult or a packed raster"),
+    };
+    let PackedRaster {
+        bytes: mut data_bytes,
+        floats: mut data_f32,
+        mut native_float,
+        sample_format,
+        min: min_val,
+        max: max_val,
+    } = packed;
+    let source_frame = SourceFrame::new(orientation, width, height);
+    let (width, height) = match stored_region {
+        Some((_, _, region_width, region_height)) if region_decoded => (region_width, region_height),
+        _ => (width, height),
+    };
+
+    // Orientation tag (274): apply here, once, to whichever final buffer the
+    // decode path produced (bytes for integer samples, f32 or native f16/f64
+    // for float) - this is after bilevel unpacking above so every buffer at
+    // this point is a plain one-sample-per-element interleaved raster,
+    // regardless of which decode path produced it. `bytes_per_pixel` is measured from the actual
+    // buffer rather than trusted from `bits_per_sample`, since sub-16-bit
+    // direct-decoded samples (9-15 bit) are packed as 2 bytes/sample even
+    // though `bits_per_sample` reports the true (smaller) bit depth.
+    let (width, height) = if orientation == TiffOrientation::TopLeft {
+        (width, height)
+    } else if !data_bytes.is_empty() {
+        let pixel_count = (width as usize) * (height as usize);
+        let bytes_per_pixel = data_bytes.len().checked_div(pixel_count).unwrap_or(0);
+        if bytes_per_pixel > 0 {
+            let (oriented, w, h) = apply_orientation(&data_bytes, width, height, bytes_per_pixel as u32, orientation);
+            data_bytes = oriented;
+            (w, h)
+        } else {
+            (width, height)
+        }
+    } else if !data_f32.is_empty() {
+        let (oriented, w, h) = apply_orientation(&data_f32, width, height, channels, orientation);
+        data_f32 = oriented;
+        (w, h)
+    } else if let Some(DecodingResult::F64(values)) = &native_float {
+        let (oriented, w, h) = apply_orientation(values, width, height, channels, orientation);
+        native_float = Some(DecodingResult::F64(oriented));
+        (w, h)
+    } else if let Some(DecodingResult::F16(values)) = &native_float {
+        let (oriented, w, h) = apply_orientation(values, width, height, channels, orientation);
+        native_float = Some(DecodingResult::F16(oriented));
+        (w, h)
+    } else {
+        (width, height)
+    };
+
+    // Fast decodes skip the stats pass; a plausible range from the sample
+    // value tags stands in for it (`TiffResult::range_from_tags`).
+    let tagged_range = if compute_stats {
+        None
+    } else {
+        tagged_sample_range(&mut decoder, photometric_interpretation)
+    };
+    let (min_val, max_val) = tagged_range.unwrap_or((min_val, max_val));
+
+    let convert_time = now_ms() - convert_start + prepacked_convert_time;
+    let total_time = now_ms() - start_time;
+    let metadata_time = total_time - decompress_time - convert_time;
+
+    let mut result = TiffResult {
+        width,
+        height,
+        channels,
+        bits_per_sample,
+        sample_format,
+        compression,
+        predictor,
+        photometric_interpretation,
+        planar_configuration,
+        rows_per_strip,
+        strip_count,
+        strip_byte_count_total,
+        strip_byte_count_max,
+        tile_width,
+        tile_length,
+        tile_count,
+        direct_decode,
+        data: Rc::new(data_bytes),
+        data_f32: Rc::new(data_f32),
+        native_float: native_float.map(Rc::new),
+        data_f32_cache: OnceCell::new(),
+        min_value: min_val,
+        max_value: max_val,
+        range_from_tags: tagged_range.is_some(),
+        timing_metadata_ms: metadata_time,
+        timing_decode_ms: decompress_time,
+        timing_convert_ms: convert_time,
+        timing_stats_ms: stats_time,
+        timing_pack_ms: pack_time,
+        all_tags_json: extract_page_tags_json(original_data, page_index),
+        ome_xml: extract_ome_xml(original_data),
+        chunk_profile_json: profile.to_json(tile_width > 0 && tile_length > 0),
+        geo: GeoInfo::read(&mut decoder),
+        page_metadata: PageMetadata::read(&mut decoder),
+        pixel_aspect_ratio: read_pixel_aspect_ratio(&mut decoder),
+        source_frame: Some(source_frame),
+        fractional_bits: 0,
+        value_transform: None,
+        include_transparent: false,
+        channel_stats: OnceCell::new(),
+        primaries: read_tiff_primaries(&mut decoder),
+        icc_profile: read_tiff_icc_profile(&mut decoder),
+        color_managed: false,
+        transfer_byte_budget: 0,
+        disposed: false,
+    };
+    match region {
+        // The streaming path already decoded just the region.
+        Some((x, y, _, _)) if region_decoded => result.offset_source_frame(x, y),
+        Some(rect) => result.crop_to_region(rect),
+        None => {}
+    }
+    if alpha_stats || (compute_stats && result.transparent_alpha_band().is_some()) {
+        let stats_start = now_ms();
+        (result.min_value, result.max_value) = result.measure_stored_range();
+        result.timing_stats_ms += now_ms() - stats_start;
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    web_sys::console::log_1(&format!(
+        "[Rust] Total: {:.2}ms (metadata: {:.2}ms, decompress: {:.2}ms, convert: {:.2}ms)", 
+        total_time, metadata_time, decompress_time, convert_time
+    ).into());
+    
+    Ok(result)
+}
+
+// Statistics. With the `simd` feature the min/max scans run on 128-bit
+// `wide` lanes, which compile to wasm `v128` operations when the module is
+// built with `-C target-feature=+simd128`; without it they are plain
+// per-sample loops. Both give identical results.
+
+/// How many leading samples of a `len`-sample buffer the `simd` build
+/// handles as whole 16-byte lanes of `size`-byte samples.
+#[cfg(feature = "simd")]
+fn lane_split(len: usize, size: usize) -> usize {
+    len - len % (16 / size)
+}
+
+/// Little-endian bytes of a slice of `$ty` samples. On little-endian targets
+/// (wasm32 among them) that is the slice's own memory, copied in one go.
+macro_rules! le_bytes {
+    ($data:expr, $ty:ty) => {{
+        let data: &[$ty] = $data;
+        if cfg!(target_endian = "little") {
+            bytemuck::cast_slice::<$ty, u8>(data).to_vec()
+        } else {
+            data.iter().flat_map(|value| value.to_le_bytes()).collect::<Vec<u8>>()
+        }
+    }};
+}
+
+/// Min/max of `$ty` samples given as `$lanes`, arrays filling one
+/// `wide::$lane`, followed by the scalar `$tail`.
+#[cfg(feature = "simd")]
+macro_rules! min_max {
+    ($ty:ty, $lane:ident, $lanes:expr, $tail:expr) => {{
+        let (mut lane_min, mut lane_max) = (wide::$lane::splat(<$ty>::MAX), wide::$lane::splat(<$ty>::MIN));
+        for lane in $lanes {
+            let lane = wide::$lane::new(lane);
+            lane_min = lane_min.min(lane);
+            lane_max = lane_max.max(lane);
+        }
+        let mut min = lane_min.to_array().into_iter().fold(<$ty>::MAX, <$ty>::min);
+        let mut max = lane_max.to_array().into_iter().fold(<$ty>::MIN, <$ty>::max);
+        for value in $tail {
+            min = min.min(value);
+            max = max.max(value);
+        }
+        (min, max)
+    }};
+    ($data:expr, $ty:ty, $lane:ident) => {{
+        let data: &[$ty] = $data;
+        let (lanes, tail) = data.split_at(lane_split(data.len(), std::mem::size_of::<$ty>()));
+        min_max!(
+            $ty,
+            $lane,
+            lanes.chunks_exact(16 / std::mem::size_of::<$ty>()).map(|lane| lane.try_into().unwrap()),
+            tail.iter().copied()
+        )
+    }};
+}
+
+/// Min/max of the `$ty` samples in `$data`, one at a time.
+#[cfg(not(feature = "simd"))]
+macro_rules! min_max {
+    ($data:expr, $ty:ty, $lane:ident) => {{
+        let data: &[$ty] = $data;
+        data.iter().fold((<$ty>::MAX, <$ty>::MIN), |(min, max), &value| (min.min(value), max.max(value)))
+    }};
+}
+
+/// Min/max of the finite samples in `$data`, `(inf, -inf)` when there are
+/// none. Non-finite lane entries are replaced by the identities of min and
+/// max before they are folded in.
+#[cfg(feature = "simd")]
+macro_rules! finite_min_max {
+    ($data:expr, $ty:ty, $lane:ident) => {{
+        let data: &[$ty] = $data;
+        let (lanes, tail) = data.split_at(lane_split(data.len(), std::mem::size_of::<$ty>()));
+        let (mut lane_min, mut lane_max) = (wide::$lane::splat(<$ty>::INFINITY), wide::$lane::splat(<$ty>::NEG_INFINITY));
+        for lane in lanes.chunks_exact(16 / std::mem::size_of::<$ty>()) {
+            let lane = wide::$lane::new(lane.try_into().unwrap());
+            let finite = lane.is_finite();
+            lane_min = lane_min.min(finite.blend(lane, wide::$lane::splat(<$ty>::INFINITY)));
+            lane_max = lane_max.max(finite.blend(lane, wide::$lane::splat(<$ty>::NEG_INFINITY)));
+        }
+        let mut min = lane_min.to_array().into_iter().fold(<$ty>::INFINITY, <$ty>::min);
+        let mut max = lane_max.to_array().into_iter().fold(<$ty>::NEG_INFINITY, <$ty>::max);
+        for &value in tail {
+            if value.is_finite() {
+                min = min.min(value);
+                max = max.max(value);
+            }
+        }
+        (min as f64, max as f64)
+    }};
+}
+
+/// Min/max of the finite samples in `$data`, `(inf, -inf)` when there are
+/// none.
+#[cfg(not(feature = "simd"))]
+macro_rules! finite_min_max {
+    ($data:expr, $ty:ty, $lane:ident) => {{
+        let data: &[$ty] = $data;
+        let (min, max) = data
+            .iter()
+            .filter(|value| value.is_finite())
+            .fold((<$ty>::INFINITY, <$ty>::NEG_INFINITY), |(min, max), &value| (min.min(value), max.max(value)));
+        (min as f64, max as f64)
+    }};
+}
+
+/// Convert u16 slice to little-endian bytes
+#[inline]
+fn convert_u16_to_bytes_simd(data: &[u16]) -> Vec<u8> {
+    le_bytes!(data, u16)
+}
+
+/// Little-endian bytes of 32-bit float samples, as `get_data_bytes` returns
+/// float pages.
+fn f32_to_le_bytes(data: &[f32]) -> Vec<u8> {
+    le_bytes!(data, f32)
+}
+
+/// Little-endian bytes of 64-bit float samples.
+fn f64_to_le_bytes(data: &[f64]) -> Vec<u8> {
+    le_bytes!(data, f64)
+}
+
+// Statistics computation functions
+
+fn compute_stats_u8(data: &[u8]) -> (u8, u8) {
+    min_max!(data, u8, u8x16)
+}
+
+fn compute_stats_u16(data: &[u16]) -> (u16, u16) {
+    min_max!(data, u16, u16x8)
+}
+
+fn compute_stats_u32(data: &[u32]) -> (u32, u32) {
+    min_max!(data, u32, u32x4)
+}
+
+fn compute_stats_u64(data: &[u64]) -> (u64, u64) {
+    let mut min = u64::MAX;
+    let mut max = u64::MIN;
+    for &v in data {
+        min = min.min(v);
+        max = max.max(v);
+    }
+    (min, max)
+}
+
+fn compute_stats_i8(data: &[i8]) -> (i8, i8) {
+    min_max!(data, i8, i8x16)
+}
+
+fn compute_stats_i16(data: &[i16]) -> (i16, i16) {
+    min_max!(data, i16, i16x8)
+}
+
+fn compute_stats_i32(data: &[i32]) -> (i32, i32) {
+    min_max!(data, i32, i32x4)
+}
+
+fn compute_stats_i64(data: &[i64]) -> (i64, i64) {
+    let mut min = i64::MAX;
+    let mut max = i64::MIN;
+    for &v in data {
+        min = min.min(v);
+        max = max.max(v);
+    }
+    (min, max)
+}
+
+fn compute_stats_f32(data: &[f32]) -> (f64, f64) {
+    finite_min_max!(data, f32, f32x4)
+}
 
-    let decode_start = js_sys::Date::now();
+fn compute_stats_f64(data: &[f64]) -> (f64, f64) {
+    finite_min_max!(data, f64, f64x2)
+}
 
-    // Read image data (decompression happens here). ZSTD (50000) is decoded
-    // with the pure-Rust ruzstd crate rather than the tiff crate's C zstd, so
-    // the WASM build needs no C toolchain. The decompressed strips are rebuilt
-    // into an uncompressed TIFF and handed back to the tiff crate, which still
-    // performs predictor un-application and type/endianness handling.
-    let mut direct_decode = false;
-    let mut decode_result = if compression == 50000 {
-        decode_zstd(data, &mut decoder)?
-    } else if let Some(result) = try_decode_general_strips_tiles(
-        data,
-        &mut decoder,
-        width,
-        height,
-        channels,
-        bits_per_sample,
-        compression,
-        predictor,
-        planar_configuration,
-        tile_width,
-        tile_length,
-    )? {
-        direct_decode = true;
-        result
-    } else if let Some(result) = try_decode_subbit_strips(
-        data,
-        &mut decoder,
-        width,
-        height,
-        channels,
-        bits_per_sample,
-        compression,
-        predictor,
-        planar_configuration,
-    )? {
-        direct_decode = true;
-        result
-    } else if let Some(result) = try_decode_uncompressed_strips(
-        data,
-        &mut decoder,
-        width,
-        height,
-        channels,
-        bits_per_sample,
-        compression,
-        predictor,
-        planar_configuration,
-    )? {
-        direct_decode = true;
-        result
-    } else {
-        decoder.read_image()
-            .map_err(|e| JsValue::from_str(&format!("Failed to decode image: {}", e)))?
-    };
+/// Turn a whole-image `DecodingResult` into `TiffResult`'s packed buffers:
+/// re-derive the channel stride for the `read_image()` fallback, convert CMYK
+/// to RGB(A), expand bilevel rows, and pack integer samples as little-endian
+/// bytes / f32 samples as-is (f16/f64 are kept native for lazy conversion),
+/// computing min/max along the way when asked. `known_stats` is a min/max the
+/// decode path already took strip by strip; it replaces the whole-image pass
+/// unless CMYK conversion or bilevel expansion changes the samples first.
+#[allow(clippy::too_many_arguments)]
+fn pack_decoding_result(
+    mut decode_result: DecodingResult,
+    direct_decode: bool,
+    width: u32,
+    height: u32,
+    channels: &mut u32,
+    bits_per_sample: &mut u32,
+    photometric_interpretation: u32,
+    compute_stats: bool,
+    known_stats: Option<(f64, f64)>,
+    stats_time: &mut f64,
+    pack_time: &mut f64,
+) -> PackedRaster {
+    let known_stats = known_stats.filter(|_| photometric_interpretation != 5 && *bits_per_sample != 1);
+    let compute_stats = compute_stats && known_stats.is_none();
 
     // The direct-decode paths above (`try_decode_general_strips_tiles`,
     // `try_decode_subbit_strips`, `try_decode_uncompressed_strips`) are
@@ -1797,10 +12402,10 @@ fn decode_tiff_impl(data: &[u8], compute_stats: bool, page_index: u32) -> Result
     if !direct_decode {
         let element_count = decoding_result_len(&decode_result);
         let pixel_count = (width as usize) * (height as usize);
-        if pixel_count > 0 && element_count % pixel_count == 0 {
+        if pixel_count > 0 && element_count.is_multiple_of(pixel_count) {
             let actual_channels = (element_count / pixel_count) as u32;
             if actual_channels > 0 {
-                channels = actual_channels;
+                *channels = actual_channels;
             }
         }
     }
@@ -1816,32 +12421,28 @@ fn decode_tiff_impl(data: &[u8], compute_stats: bool, page_index: u32) -> Result
     // TiffResult/metadata below is intentionally left as the raw tag value
     // (5) - only the pixel data changes.
     if photometric_interpretation == 5 {
-        let (converted, converted_channels) = convert_cmyk_to_rgb(decode_result, channels);
+        let (converted, converted_channels) = convert_cmyk_to_rgb(decode_result, *channels);
         decode_result = converted;
-        channels = converted_channels;
+        *channels = converted_channels;
     }
 
-    let decompress_time = js_sys::Date::now() - decode_start;
-    let convert_start = js_sys::Date::now();
-    let mut stats_time = 0.0;
-    let mut pack_time = 0.0;
-
     // Determine sample format and convert data to bytes
-    let (mut data_bytes, mut data_f32, sample_format, min_val, max_val) = match decode_result {
+    let mut native_float = None;
+    let (bytes, floats, sample_format, min, max) = match decode_result {
         DecodingResult::U8(data) => {
-            if bits_per_sample == 1 {
+            if *bits_per_sample == 1 {
                 // Uncompressed (or LZW/PackBits/Deflate) bilevel images are
                 // returned as MSB-first packed bits with each row padded to a
                 // byte boundary. Expand to one byte per pixel so they render
                 // like any other 8-bit grayscale image.
-                let pack_start = js_sys::Date::now();
+                let pack_start = now_ms();
                 let expanded = unpack_bilevel(&data, width, height, photometric_interpretation);
-                bits_per_sample = 8;
-                pack_time += js_sys::Date::now() - pack_start;
+                *bits_per_sample = 8;
+                *pack_time += now_ms() - pack_start;
                 let (min, max) = if compute_stats {
-                    let stats_start = js_sys::Date::now();
+                    let stats_start = now_ms();
                     let stats = compute_stats_u8(&expanded);
-                    stats_time += js_sys::Date::now() - stats_start;
+                    *stats_time += now_ms() - stats_start;
                     (stats.0 as f64, stats.1 as f64)
                 } else {
                     (f64::NAN, f64::NAN)
@@ -1849,9 +12450,9 @@ fn decode_tiff_impl(data: &[u8], compute_stats: bool, page_index: u32) -> Result
                 (expanded, Vec::new(), 1u32, min, max)
             } else {
                 let (min, max) = if compute_stats {
-                    let stats_start = js_sys::Date::now();
+                    let stats_start = now_ms();
                     let stats = compute_stats_u8(&data);
-                    stats_time += js_sys::Date::now() - stats_start;
+                    *stats_time += now_ms() - stats_start;
                     (stats.0 as f64, stats.1 as f64)
                 } else {
                     (f64::NAN, f64::NAN)
@@ -1861,236 +12462,1064 @@ fn decode_tiff_impl(data: &[u8], compute_stats: bool, page_index: u32) -> Result
         }
         DecodingResult::U16(data) => {
             let (min, max) = if compute_stats {
-                let stats_start = js_sys::Date::now();
+                let stats_start = now_ms();
                 let stats = compute_stats_u16(&data);
-                stats_time += js_sys::Date::now() - stats_start;
+                *stats_time += now_ms() - stats_start;
                 (stats.0 as f64, stats.1 as f64)
             } else {
                 (f64::NAN, f64::NAN)
             };
             // SIMD-optimized byte conversion
-            let pack_start = js_sys::Date::now();
+            let pack_start = now_ms();
             let bytes = convert_u16_to_bytes_simd(&data);
-            pack_time += js_sys::Date::now() - pack_start;
+            *pack_time += now_ms() - pack_start;
             (bytes, Vec::new(), 1u32, min, max)
         }
         DecodingResult::U32(data) => {
             let (min, max) = if compute_stats {
-                let stats_start = js_sys::Date::now();
+                let stats_start = now_ms();
                 let stats = compute_stats_u32(&data);
-                stats_time += js_sys::Date::now() - stats_start;
+                *stats_time += now_ms() - stats_start;
                 (stats.0 as f64, stats.1 as f64)
             } else {
                 (f64::NAN, f64::NAN)
             };
-            let pack_start = js_sys::Date::now();
-            let bytes: Vec<u8> = data.iter()
-                .flat_map(|&v| v.to_le_bytes())
-                .collect();
-            pack_time += js_sys::Date::now() - pack_start;
+            let pack_start = now_ms();
+            let bytes = le_bytes!(&data, u32);
+            *pack_time += now_ms() - pack_start;
             (bytes, Vec::new(), 1u32, min, max)
         }
         DecodingResult::U64(data) => {
             let (min, max) = if compute_stats {
-                let stats_start = js_sys::Date::now();
+                let stats_start = now_ms();
                 let stats = compute_stats_u64(&data);
-                stats_time += js_sys::Date::now() - stats_start;
+                *stats_time += now_ms() - stats_start;
                 (stats.0 as f64, stats.1 as f64)
             } else {
                 (f64::NAN, f64::NAN)
             };
-            let pack_start = js_sys::Date::now();
-            let bytes: Vec<u8> = data.iter()
-                .flat_map(|&v| v.to_le_bytes())
-                .collect();
-            pack_time += js_sys::Date::now() - pack_start;
+            let pack_start = now_ms();
+            let bytes = le_bytes!(&data, u64);
+            *pack_time += now_ms() - pack_start;
             (bytes, Vec::new(), 1u32, min, max)
         }
         DecodingResult::I8(data) => {
             let (min, max) = if compute_stats {
-                let stats_start = js_sys::Date::now();
+                let stats_start = now_ms();
                 let stats = compute_stats_i8(&data);
-                stats_time += js_sys::Date::now() - stats_start;
+                *stats_time += now_ms() - stats_start;
                 (stats.0 as f64, stats.1 as f64)
             } else {
                 (f64::NAN, f64::NAN)
             };
-            let pack_start = js_sys::Date::now();
+            let pack_start = now_ms();
             let ubytes: Vec<u8> = data.iter().map(|&v| v as u8).collect();
-            pack_time += js_sys::Date::now() - pack_start;
+            *pack_time += now_ms() - pack_start;
             (ubytes, Vec::new(), 2u32, min, max)
         }
         DecodingResult::I16(data) => {
             let (min, max) = if compute_stats {
-                let stats_start = js_sys::Date::now();
+                let stats_start = now_ms();
                 let stats = compute_stats_i16(&data);
-                stats_time += js_sys::Date::now() - stats_start;
+                *stats_time += now_ms() - stats_start;
                 (stats.0 as f64, stats.1 as f64)
             } else {
                 (f64::NAN, f64::NAN)
             };
-            let pack_start = js_sys::Date::now();
-            let bytes: Vec<u8> = data.iter()
-                .flat_map(|&v| v.to_le_bytes())
-                .collect();
-            pack_time += js_sys::Date::now() - pack_start;
+            let pack_start = now_ms();
+            let bytes = le_bytes!(&data, i16);
+            *pack_time += now_ms() - pack_start;
             (bytes, Vec::new(), 2u32, min, max)
         }
         DecodingResult::I32(data) => {
             let (min, max) = if compute_stats {
-                let stats_start = js_sys::Date::now();
+                let stats_start = now_ms();
                 let stats = compute_stats_i32(&data);
-                stats_time += js_sys::Date::now() - stats_start;
+                *stats_time += now_ms() - stats_start;
                 (stats.0 as f64, stats.1 as f64)
             } else {
                 (f64::NAN, f64::NAN)
             };
-            let pack_start = js_sys::Date::now();
-            let bytes: Vec<u8> = data.iter()
-                .flat_map(|&v| v.to_le_bytes())
-                .collect();
-            pack_time += js_sys::Date::now() - pack_start;
+            let pack_start = now_ms();
+            let bytes = le_bytes!(&data, i32);
+            *pack_time += now_ms() - pack_start;
+            (bytes, Vec::new(), 2u32, min, max)
+        }
+        DecodingResult::I64(data) => {
+            let (min, max) = if compute_stats {
+                let stats_start = now_ms();
+                let stats = compute_stats_i64(&data);
+                *stats_time += now_ms() - stats_start;
+                (stats.0 as f64, stats.1 as f64)
+            } else {
+                (f64::NAN, f64::NAN)
+            };
+            let pack_start = now_ms();
+            let bytes = le_bytes!(&data, i64);
+            *pack_time += now_ms() - pack_start;
             (bytes, Vec::new(), 2u32, min, max)
         }
-        DecodingResult::I64(data) => {
-            let (min, max) = if compute_stats {
-                let stats_start = js_sys::Date::now();
-                let stats = compute_stats_i64(&data);
-                stats_time += js_sys::Date::now() - stats_start;
-                (stats.0 as f64, stats.1 as f64)
-            } else {
-                (f64::NAN, f64::NAN)
-            };
-            let pack_start = js_sys::Date::now();
-            let bytes: Vec<u8> = data.iter()
-                .flat_map(|&v| v.to_le_bytes())
-                .collect();
-            pack_time += js_sys::Date::now() - pack_start;
-            (bytes, Vec::new(), 2u32, min, max)
+        DecodingResult::F32(data) => {
+            let (min, max) = if compute_stats {
+                let stats_start = now_ms();
+                let stats = compute_stats_f32(&data);
+                *stats_time += now_ms() - stats_start;
+                stats
+            } else {
+                (f64::NAN, f64::NAN)
+            };
+            (Vec::new(), data, 3u32, min, max)
+        }
+        DecodingResult::F64(data) => {
+            let (min, max) = if compute_stats {
+                let stats_start = now_ms();
+                let stats = compute_stats_f64(&data);
+                *stats_time += now_ms() - stats_start;
+                stats
+            } else {
+                (f64::NAN, f64::NAN)
+            };
+            native_float = Some(DecodingResult::F64(data));
+            (Vec::new(), Vec::new(), 3u32, min, max)
+        }
+        DecodingResult::F16(data) => {
+            let (min, max) = if compute_stats {
+                let stats_start = now_ms();
+                let mut min_val = f32::INFINITY;
+                let mut max_val = f32::NEG_INFINITY;
+                for &val in &data {
+                    let f32_val = val.to_f32();
+                    if f32_val.is_finite() {
+                        min_val = min_val.min(f32_val);
+                        max_val = max_val.max(f32_val);
+                    }
+                }
+                *stats_time += now_ms() - stats_start;
+                (min_val as f64, max_val as f64)
+            } else {
+                (f64::NAN, f64::NAN)
+            };
+            native_float = Some(DecodingResult::F16(data));
+            (Vec::new(), Vec::new(), 3u32, min, max)
+        }
+    };
+    let (min, max) = known_stats.unwrap_or((min, max));
+    PackedRaster { bytes, floats, native_float, sample_format, min, max }
+}
+
+/// Output sizes at or above this are decoded by `try_decode_streaming_chunks`,
+/// which packs every strip/tile into the final buffer as soon as it is
+/// decoded. Below it, holding a whole-image `DecodingResult` next to its
+/// packed copy costs little and the existing whole-image paths are kept.
+const STREAMING_DECODE_MIN_BYTES: usize = 32 * 1024 * 1024;
+
+/// Sample buffers in the exact form `TiffResult` stores them: integer samples
+/// as little-endian bytes in `bytes`, f32 samples in `floats`, and f16/f64
+/// samples either as their native `DecodingResult` (whole-image paths, see
+/// `TiffResult::native_float`) or narrowed to `floats` (streamed chunks).
+/// Exactly one of the three is populated.
+struct PackedRaster {
+    bytes: Vec<u8>,
+    floats: Vec<f32>,
+    native_float: Option<DecodingResult>,
+    sample_format: u32,
+    min: f64,
+    max: f64,
+}
+
+/// Wall-clock split of a chunk-by-chunk decode, in the same buckets
+/// `TiffResult`'s `timing_*_ms` getters report.
+#[derive(Default)]
+struct ChunkTimings {
+    decode_ms: f64,
+    pack_ms: f64,
+    stats_ms: f64,
+}
+
+/// One strip/tile as recorded by a profiled decode.
+struct ChunkRecord {
+    index: usize,
+    compressed_bytes: u64,
+    decoded_bytes: usize,
+    decode_ms: f64,
+}
+
+/// Opt-in per-strip/tile telemetry (`decode_tiff_page_profiled`). Every
+/// chunk-by-chunk decode path reports each strip or tile it decompresses
+/// through `record`, which is a no-op unless profiling was requested, and
+/// `path` names the decode path that produced the image so a slow file's
+/// report also says which code handled it.
+#[derive(Default)]
+struct ChunkProfile {
+    enabled: bool,
+    path: &'static str,
+    records: Vec<ChunkRecord>,
+}
+
+impl ChunkProfile {
+    fn new(enabled: bool) -> Self {
+        ChunkProfile { enabled, ..Default::default() }
+    }
+
+    fn record(&mut self, index: usize, compressed_bytes: u64, decoded_bytes: usize, decode_ms: f64) {
+        if self.enabled {
+            self.records.push(ChunkRecord { index, compressed_bytes, decoded_bytes, decode_ms });
+        }
+    }
+
+    /// `{"path":..,"chunk_kind":"strip"|"tile","chunks":[{"index","compressed_bytes","decoded_bytes","decode_ms"},..]}`,
+    /// or an empty string when profiling was not requested.
+    fn to_json(&self, tiled: bool) -> String {
+        if !self.enabled {
+            return String::new();
+        }
+        let mut json = format!(
+            "{{\"path\":\"{}\",\"chunk_kind\":\"{}\",\"chunks\":[",
+            self.path,
+            if tiled { "tile" } else { "strip" }
+        );
+        for (i, record) in self.records.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            json.push_str(&format!(
+                "{{\"index\":{},\"compressed_bytes\":{},\"decoded_bytes\":{},\"decode_ms\":{}}}",
+                record.index, record.compressed_bytes, record.decoded_bytes, record.decode_ms
+            ));
+        }
+        json.push_str("]}");
+        json
+    }
+}
+
+/// Copy one decoded chunk's rows (`row_samples` samples each) into `dst`,
+/// starting at sample `dst_start` with rows `dst_stride` samples apart. Each
+/// destination sample occupies `cell` elements of `dst` (N bytes for packed
+/// integers, one element for f32) and is filled in by `write`.
+fn scatter_chunk_rows<T: Copy, D>(
+    src: &[T],
+    row_samples: usize,
+    dst: &mut [D],
+    dst_start: usize,
+    dst_stride: usize,
+    cell: usize,
+    write: impl Fn(&mut [D], T),
+) {
+    for (row, src_row) in src.chunks_exact(row_samples).enumerate() {
+        let base = (dst_start + row * dst_stride) * cell;
+        let dst_row = &mut dst[base..base + row_samples * cell];
+        for (out, &value) in dst_row.chunks_exact_mut(cell).zip(src_row) {
+            write(out, value);
+        }
+    }
+}
+
+impl PackedRaster {
+    /// Allocate the full output for `sample_count` samples of the kind
+    /// `first_chunk` decoded to.
+    fn for_chunk_kind(first_chunk: &DecodingResult, sample_count: usize, compute_stats: bool) -> Result<Self, TiffError> {
+        let (bytes_per_sample, sample_format) = match first_chunk {
+            DecodingResult::U8(_) => (1, 1),
+            DecodingResult::U16(_) => (2, 1),
+            DecodingResult::U32(_) => (4, 1),
+            DecodingResult::U64(_) => (8, 1),
+            DecodingResult::I8(_) => (1, 2),
+            DecodingResult::I16(_) => (2, 2),
+            DecodingResult::I32(_) => (4, 2),
+            DecodingResult::I64(_) => (8, 2),
+            DecodingResult::F16(_) | DecodingResult::F32(_) | DecodingResult::F64(_) => (0, 3),
+        };
+        let (bytes, floats) = if sample_format == 3 {
+            (Vec::new(), vec![0f32; sample_count])
+        } else {
+            let len = sample_count
+                .checked_mul(bytes_per_sample)
+                .ok_or_else(|| TiffError::other("Streaming TIFF decode: raster byte count overflow"))?;
+            (vec![0u8; len], Vec::new())
+        };
+        let (min, max) = if compute_stats { (f64::INFINITY, f64::NEG_INFINITY) } else { (f64::NAN, f64::NAN) };
+        Ok(PackedRaster { bytes, floats, native_float: None, sample_format, min, max })
+    }
+
+    fn merge_stats(&mut self, (min, max): (f64, f64)) {
+        self.min = self.min.min(min);
+        self.max = self.max.max(max);
+    }
+
+    /// Pack one decoded chunk (`chunk_width` samples-per-pixel-interleaved
+    /// pixels per row, padding already stripped by the `tiff` crate) into
+    /// its place in the output, folding its min/max into the running stats.
+    #[allow(clippy::too_many_arguments)]
+    fn store_chunk(
+        &mut self,
+        chunk: &DecodingResult,
+        chunk_width: usize,
+        dst_start: usize,
+        dst_stride: usize,
+        channels: usize,
+        compute_stats: bool,
+        timings: &mut ChunkTimings,
+    ) -> Result<(), TiffError> {
+        let row_samples = chunk_width * channels;
+        if row_samples == 0 {
+            return Ok(());
+        }
+
+        macro_rules! ints {
+            ($values:expr, $n:expr, $stats:ident) => {{
+                if compute_stats {
+                    let stats_start = now_ms();
+                    let (min, max) = $stats($values);
+                    self.merge_stats((min as f64, max as f64));
+                    timings.stats_ms += now_ms() - stats_start;
+                }
+                let pack_start = now_ms();
+                scatter_chunk_rows($values, row_samples, &mut self.bytes, dst_start, dst_stride, $n,
+                    |out, v| out.copy_from_slice(&v.to_le_bytes()));
+                timings.pack_ms += now_ms() - pack_start;
+            }};
+        }
+
+        match (chunk, self.sample_format) {
+            (DecodingResult::U8(v), 1) => ints!(v, 1, compute_stats_u8),
+            (DecodingResult::U16(v), 1) => ints!(v, 2, compute_stats_u16),
+            (DecodingResult::U32(v), 1) => ints!(v, 4, compute_stats_u32),
+            (DecodingResult::U64(v), 1) => ints!(v, 8, compute_stats_u64),
+            (DecodingResult::I8(v), 2) => ints!(v, 1, compute_stats_i8),
+            (DecodingResult::I16(v), 2) => ints!(v, 2, compute_stats_i16),
+            (DecodingResult::I32(v), 2) => ints!(v, 4, compute_stats_i32),
+            (DecodingResult::I64(v), 2) => ints!(v, 8, compute_stats_i64),
+            (DecodingResult::F32(v), 3) => {
+                if compute_stats {
+                    let stats_start = now_ms();
+                    self.merge_stats(compute_stats_f32(v));
+                    timings.stats_ms += now_ms() - stats_start;
+                }
+                let pack_start = now_ms();
+                scatter_chunk_rows(v, row_samples, &mut self.floats, dst_start, dst_stride, 1, |out, v| out[0] = v);
+                timings.pack_ms += now_ms() - pack_start;
+            }
+            (DecodingResult::F64(v), 3) => {
+                if compute_stats {
+                    let stats_start = now_ms();
+                    self.merge_stats(compute_stats_f64(v));
+                    timings.stats_ms += now_ms() - stats_start;
+                }
+                let pack_start = now_ms();
+                scatter_chunk_rows(v, row_samples, &mut self.floats, dst_start, dst_stride, 1, |out, v| out[0] = v as f32);
+                timings.pack_ms += now_ms() - pack_start;
+            }
+            (DecodingResult::F16(v), 3) => {
+                // Stats are taken on the converted values while packing, so
+                // f16 chunks are only walked once.
+                let pack_start = now_ms();
+                let mut min = f64::INFINITY;
+                let mut max = f64::NEG_INFINITY;
+                scatter_chunk_rows(v, row_samples, &mut self.floats, dst_start, dst_stride, 1, |out, v| out[0] = v.to_f32());
+                if compute_stats {
+                    for &value in v {
+                        let value = value.to_f32();
+                        if value.is_finite() {
+                            min = min.min(value as f64);
+                            max = max.max(value as f64);
+                        }
+                    }
+                    self.merge_stats((min, max));
+                }
+                timings.pack_ms += now_ms() - pack_start;
+            }
+            _ => return Err(TiffError::other("Streaming TIFF decode: chunks decoded to mixed sample types")),
+        }
+        Ok(())
+    }
+}
+
+/// Decode large chunky images one strip/tile at a time through the `tiff`
+/// crate's `read_chunk()`, packing each chunk straight into the final
+/// `TiffResult` buffer. The whole-image paths hold the decoded
+/// `DecodingResult` and its packed copy at the same time (2x the output for
+/// 16-bit data, more for u64/f64); here only the output plus one chunk is
+/// ever alive, so peak memory stays close to 1x the output size.
+///
+/// Scoped to what `read_chunk()` hands back ready to pack: chunky layout,
+/// 8/16/32/64-bit samples, and photometric interpretations that need no
+/// whole-image post-processing (CMYK conversion and YCbCr are left to the
+/// existing paths, as are images below `STREAMING_DECODE_MIN_BYTES`).
+/// Returns `Ok(None)` whenever the image is out of scope.
+///
+/// With a `region` (`(x, y, width, height)` in stored coordinates, from
+/// `decode_tiff_region`) the output is just that rectangle: chunks that
+/// don't overlap it are never read, the others are cropped to the overlap
+/// before packing, so the stats also only cover the rectangle.
+#[allow(clippy::too_many_arguments)]
+fn try_decode_streaming_chunks(
+    decoder: &mut PageDecoder<'_>,
+    width: u32,
+    height: u32,
+    channels: u32,
+    bits_per_sample: u32,
+    photometric_interpretation: u32,
+    planar_configuration: u32,
+    compute_stats: bool,
+    region: Option<(u32, u32, u32, u32)>,
+    profile: &mut ChunkProfile,
+) -> Result<Option<(PackedRaster, ChunkTimings)>, TiffError> {
+    use tiff::decoder::ChunkType;
+    use tiff::tags::Tag;
+
+    if planar_configuration != 1 || !matches!(bits_per_sample, 8 | 16 | 32 | 64) {
+        return Ok(None);
+    }
+    if photometric_interpretation > 2 {
+        return Ok(None);
+    }
+    // Like `read_image()`, `read_chunk()` compacts away extra non-alpha
+    // samples down to what the color type implies (see
+    // `pack_decoding_result`). The whole-image direct paths keep every band,
+    // so leave such images to them.
+    if decoder.colortype().map(|color_type| color_type.num_samples() as u32).ok() != Some(channels) {
+        return Ok(None);
+    }
+
+    let (offsets, compressed_counts) = match decoder.get_chunk_type() {
+        ChunkType::Strip => (decoder.get_tag_u64_vec(Tag::StripOffsets), decoder.get_tag_u64_vec(Tag::StripByteCounts)),
+        ChunkType::Tile => (decoder.get_tag_u64_vec(Tag::TileOffsets), decoder.get_tag_u64_vec(Tag::TileByteCounts)),
+    };
+    let (offsets, compressed_counts) = (offsets.unwrap_or_default(), compressed_counts.unwrap_or_default());
+    let sparse = offsets.iter().zip(&compressed_counts).any(|(&offset, &count)| is_sparse_chunk(offset, count));
+
+    // Sparse files always come through here (whatever their size), since it
+    // is the one chunky path that fills empty strips/tiles instead of failing.
+    let output_bytes = (width as usize)
+        .saturating_mul(height as usize)
+        .saturating_mul(channels as usize)
+        .saturating_mul((bits_per_sample as usize / 8).max(4));
+    if output_bytes < STREAMING_DECODE_MIN_BYTES && !profile.enabled && !sparse && region.is_none() {
+        return Ok(None);
+    }
+    let (out_x, out_y, out_width, out_height) = region.unwrap_or((0, 0, width, height));
+    let pixel_count = (out_width as usize) * (out_height as usize);
+
+    let (chunk_width, chunk_height) = decoder.chunk_dimensions();
+    if chunk_width == 0 || chunk_height == 0 {
+        return Ok(None);
+    }
+    let chunks_across = width.div_ceil(chunk_width);
+    let chunk_count = match decoder.get_chunk_type() {
+        ChunkType::Strip => height.div_ceil(chunk_height),
+        ChunkType::Tile => chunks_across * height.div_ceil(chunk_height),
+    };
+    let sample_format = decoder.get_tag_u64_vec(Tag::SampleFormat)
+        .ok()
+        .and_then(|values| values.first().copied())
+        .unwrap_or(1) as u32;
+    let fill_value = sparse_fill_value(decoder);
+
+    let mut timings = ChunkTimings::default();
+    let mut packed: Option<PackedRaster> = None;
+    let out_channels = channels as usize;
+    for index in 0..chunk_count {
+        let (data_width, data_height) = decoder.chunk_data_dimensions(index);
+        let chunk_pixels = (data_width as usize) * (data_height as usize);
+        let x0 = (index % chunks_across) * chunk_width;
+        let y0 = (index / chunks_across) * chunk_height;
+        let (keep_x0, keep_y0) = (x0.max(out_x), y0.max(out_y));
+        let keep_x1 = (x0 + data_width).min(out_x + out_width);
+        let keep_y1 = (y0 + data_height).min(out_y + out_height);
+        if chunk_pixels == 0 || keep_x0 >= keep_x1 || keep_y0 >= keep_y1 {
+            continue;
+        }
+
+        let decode_start = now_ms();
+        let offset = offsets.get(index as usize).copied().unwrap_or(0);
+        let chunk = if is_sparse_chunk(offset, compressed_counts.get(index as usize).copied().unwrap_or(0)) {
+            filled_chunk(sample_format, bits_per_sample, chunk_pixels * out_channels, fill_value)
+                .ok_or_else(|| TiffError::other("Sparse TIFF: empty strips/tiles are not supported for this sample type"))?
+        } else {
+            decoder.read_chunk(index)
+                .map_err(|e| TiffError::from_tiff(&format!("Failed to decode TIFF chunk {}", index), e))?
+        };
+        let chunk_ms = now_ms() - decode_start;
+        timings.decode_ms += chunk_ms;
+
+        if packed.is_none() {
+            packed = Some(PackedRaster::for_chunk_kind(&chunk, pixel_count * out_channels, compute_stats)?);
+        }
+        if decoding_result_len(&chunk) != chunk_pixels * out_channels {
+            return Err(TiffError::other(format!("Streaming TIFF decode: chunk {} has an unexpected size", index)));
+        }
+        profile.record(
+            index as usize,
+            compressed_counts.get(index as usize).copied().unwrap_or(0),
+            decoding_result_len(&chunk) * (bits_per_sample as usize / 8),
+            chunk_ms,
+        );
+
+        let keep = (keep_x0 - x0, keep_y0 - y0, keep_x1 - keep_x0, keep_y1 - keep_y0);
+        let chunk = if keep == (0, 0, data_width, data_height) {
+            chunk
+        } else {
+            crop_decoding_result(&chunk, data_width, channels, keep)
+        };
+        let dst_start = (((keep_y0 - out_y) as usize) * (out_width as usize) + (keep_x0 - out_x) as usize) * out_channels;
+        if let Some(packed) = packed.as_mut() {
+            packed.store_chunk(
+                &chunk,
+                keep.2 as usize,
+                dst_start,
+                (out_width as usize) * out_channels,
+                out_channels,
+                compute_stats,
+                &mut timings,
+            )?;
+        }
+    }
+
+    Ok(packed.map(|packed| (packed, timings)))
+}
+
+/// Origin and size `(x0, y0, width, height)` of the in-image part of strip/
+/// tile `chunk_in_plane` (its index within one plane), i.e. with the right
+/// and bottom edge padding of partial tiles clipped off. Computed from the
+/// page geometry rather than taken from the `tiff` crate's
+/// `chunk_data_dimensions`, which keeps the bottom padding of edge tiles in
+/// every plane after the first, so padded samples never reach a region,
+/// band plane or the stats/histograms computed from them.
+fn chunk_valid_rect(
+    chunk_in_plane: u32,
+    width: u32,
+    height: u32,
+    chunk_width: u32,
+    chunk_height: u32,
+    chunks_across: u32,
+    tiled: bool,
+) -> (u32, u32, u32, u32) {
+    let x0 = (chunk_in_plane % chunks_across) * chunk_width;
+    let y0 = (chunk_in_plane / chunks_across) * chunk_height;
+    let valid_width = if tiled { chunk_width.min(width.saturating_sub(x0)) } else { width };
+    (x0, y0, valid_width, chunk_height.min(height.saturating_sub(y0)))
+}
+
+/// GDAL writes "sparse" files (e.g. global mosaics) by leaving strips/tiles
+/// that hold no data out of the file entirely, recording them with a zero
+/// offset and/or byte count. Such a chunk reads back as all-nodata.
+fn is_sparse_chunk(offset: u64, byte_count: u64) -> bool {
+    offset == 0 || byte_count == 0
+}
+
+/// The GDAL_NODATA tag (42113, an ASCII number such as "-9999" or "nan").
+fn gdal_nodata(decoder: &mut Decoder<PageCursor<impl AsRef<[u8]>>>) -> Option<f64> {
+    decoder.get_tag_ascii_string(tiff::tags::Tag::GdalNodata)
+        .ok()
+        .and_then(|text| text.trim_matches(|c: char| c == '\0' || c.is_whitespace()).parse::<f64>().ok())
+}
+
+/// Value empty (sparse) strips/tiles are filled with: `gdal_nodata` when
+/// present, else 0.
+fn sparse_fill_value(decoder: &mut Decoder<PageCursor<impl AsRef<[u8]>>>) -> f64 {
+    gdal_nodata(decoder).unwrap_or(0.0)
+}
+
+/// GeoTIFF georeferencing of a page plus its GDAL nodata value, as reported
+/// by `TiffResult::summary`, and GDAL's per-band colour interpretation (see
+/// `TiffResult::color_interpretation_json`). Tags the page doesn't carry
+/// stay empty/`None`.
+#[derive(Clone, Default)]
+struct GeoInfo {
+    nodata: Option<f64>,
+    pixel_scale: Vec<f64>,
+    tiepoint: Vec<f64>,
+    transformation: Vec<f64>,
+    epsg: Option<u32>,
+    color_interp: Vec<Option<String>>,
+    descriptions: Vec<Option<String>>,
+}
+
+/// One per-band item GDAL records in GDAL_METADATA (tag 42112), indexed by
+/// sample; bands without an entry are `None`. `item` is "COLORINTERP" for
+/// `<Item name="COLORINTERP" sample="N" role="colorinterp">Red</Item>`,
+/// which GDAL only writes when the interpretation differs from what the
+/// photometric tags imply (e.g. BGR band order or an alpha band it cannot
+/// flag in ExtraSamples), or "DESCRIPTION" for the band descriptions set
+/// with `SetDescription` ("depth", "NIR", ...).
+fn gdal_band_items(decoder: &mut PageDecoder<'_>, item: &str) -> Vec<Option<String>> {
+    let Ok(metadata) = decoder.get_tag_ascii_string(tiff::tags::Tag::Unknown(42112)) else {
+        return Vec::new();
+    };
+    let mut bands: Vec<Option<String>> = Vec::new();
+    let mut rest = metadata.as_str();
+    while let Some(item_start) = rest.find("<Item") {
+        rest = &rest[item_start + 5..];
+        let Some(tag_end) = rest.find('>') else { break };
+        let (attributes, body) = rest.split_at(tag_end);
+        let Some(body_end) = body.find("</Item>") else { break };
+        let value = body[1..body_end].trim();
+        rest = &body[body_end..];
+        let is_item = xml_attribute(attributes, "role").is_some_and(|role| role.eq_ignore_ascii_case(item))
+            || xml_attribute(attributes, "name").is_some_and(|name| name.eq_ignore_ascii_case(item));
+        let Some(sample) = xml_attribute(attributes, "sample").and_then(|sample| sample.parse::<usize>().ok()) else {
+            continue;
+        };
+        if !is_item || value.is_empty() || sample >= 65536 {
+            continue;
+        }
+        if bands.len() <= sample {
+            bands.resize(sample + 1, None);
+        }
+        bands[sample] = Some(xml_unescape(value));
+    }
+    bands
+}
+
+/// The value of XML attribute `name` in an element's `attributes` text,
+/// unescaped, or None when the element doesn't have it.
+fn xml_attribute(attributes: &str, name: &str) -> Option<String> {
+    let pattern = format!("{}=\"", name);
+    let mut search = 0;
+    while let Some(found) = attributes[search..].find(&pattern) {
+        let start = search + found;
+        // Skip matches inside a longer attribute name (`ShortName=` for `Name=`).
+        if start == 0 || attributes[..start].ends_with(|c: char| c.is_ascii_whitespace()) {
+            let value_start = start + pattern.len();
+            let end = attributes[value_start..].find('"')? + value_start;
+            return Some(xml_unescape(&attributes[value_start..end]));
+        }
+        search = start + pattern.len();
+    }
+    None
+}
+
+/// Replace the five predefined XML entities in `text`.
+fn xml_unescape(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    text.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&apos;", "'").replace("&amp;", "&")
+}
+
+/// The `Name` of every `Channel` element of an OME-XML document, in order
+/// (`None` for a channel without one); namespace prefixes are allowed.
+fn ome_channel_names(xml: &str) -> Vec<Option<String>> {
+    let mut names = Vec::new();
+    let mut rest = xml;
+    while let Some(open) = rest.find('<') {
+        rest = &rest[open + 1..];
+        let element = &rest[..rest.find('>').unwrap_or(rest.len())];
+        let name_end = element.find(|c: char| c.is_ascii_whitespace() || c == '/').unwrap_or(element.len());
+        let name = &element[..name_end];
+        if name.rsplit(':').next() == Some("Channel") {
+            names.push(xml_attribute(&element[name_end..], "Name").filter(|name| !name.is_empty()));
+        }
+    }
+    names
+}
+
+/// PrimaryChromaticities (319) and WhitePoint (318) as the eight xy values
+/// of `TiffResult::source_primaries`, or None unless both are present and
+/// describe a usable gamut.
+fn read_tiff_primaries(decoder: &mut PageDecoder<'_>) -> Option<[f64; 8]> {
+    use tiff::decoder::ifd::Value;
+    use tiff::tags::Tag;
+
+    let mut rationals = |tag| -> Option<Vec<f64>> {
+        match decoder.get_tag(tag).ok()? {
+            Value::List(values) => values.into_iter().map(|value| match value {
+                Value::Rational(n, d) if d != 0 => Some(n as f64 / d as f64),
+                _ => None,
+            }).collect(),
+            _ => None,
+        }
+    };
+    let (primaries, white) = (rationals(Tag::Unknown(319))?, rationals(Tag::Unknown(318))?);
+    let primaries: [f64; 8] = [primaries, white].concat().try_into().ok()?;
+    rgb_to_xyz_matrix(&primaries).map(|_| primaries)
+}
+
+/// The InterColorProfile (34675) bytes, empty when the page has none.
+fn read_tiff_icc_profile(decoder: &mut PageDecoder<'_>) -> Vec<u8> {
+    decoder.get_tag_u8_vec(tiff::tags::Tag::IccProfile).unwrap_or_default()
+}
+
+/// Width / height of one pixel as XResolution / YResolution imply (pixels
+/// per unit, so the ratio is YResolution / XResolution), inverted when the
+/// Orientation transposes the image. 1.0 when either tag is missing or
+/// invalid, or the pixels are square to within rounding.
+fn read_pixel_aspect_ratio(decoder: &mut PageDecoder<'_>) -> f64 {
+    use tiff::tags::Tag;
+
+    let (x, y) = (read_resolution(decoder, Tag::XResolution), read_resolution(decoder, Tag::YResolution));
+    let ratio = y / x;
+    if !ratio.is_finite() || ratio <= 0.0 || (ratio - 1.0).abs() < 1e-6 {
+        return 1.0;
+    }
+    let transposed = TiffOrientation::from_tag(decoder.get_tag_u32(Tag::Orientation).unwrap_or(1)).transposes();
+    if transposed { 1.0 / ratio } else { ratio }
+}
+
+/// XResolution or YResolution (`tag`) as a number, NaN when missing or
+/// not numeric.
+fn read_resolution(decoder: &mut PageDecoder<'_>, tag: tiff::tags::Tag) -> f64 {
+    use tiff::decoder::ifd::Value;
+
+    match decoder.get_tag(tag) {
+        Ok(Value::Rational(n, d)) if d != 0 => n as f64 / d as f64,
+        Ok(Value::Float(v)) => v as f64,
+        Ok(Value::Double(v)) => v,
+        Ok(value) => value.into_u32().map_or(f64::NAN, |v| v as f64),
+        Err(_) => f64::NAN,
+    }
+}
+
+/// The descriptive ASCII tags `TiffResult::metadata_json` reports, with
+/// their JSON keys.
+const DESCRIPTIVE_TAGS: [(u16, &str); 10] = [
+    (269, "document_name"),
+    (270, "image_description"),
+    (271, "make"),
+    (272, "model"),
+    (285, "page_name"),
+    (305, "software"),
+    (306, "date_time"),
+    (315, "artist"),
+    (316, "host_computer"),
+    (33432, "copyright"),
+];
+
+/// What `TiffResult::metadata_json` reports beyond the decoded layout: the
+/// BitsPerSample values as stored (one per sample, before any widening or
+/// palette/CMYK conversion), the resolution and the descriptive tags the
+/// page carries. Empty for non-TIFF formats, except for the channel names
+/// of formats that store them (EXR).
+#[derive(Clone, Default)]
+struct PageMetadata {
+    bits_per_channel: Vec<u32>,
+    // (XResolution, YResolution, ResolutionUnit).
+    resolution: Option<(f64, f64, u32)>,
+    text: Vec<(&'static str, String)>,
+    // One per decoded channel, for `TiffResult::channel_labels_json`.
+    channel_names: Vec<String>,
+}
+
+impl PageMetadata {
+    fn read(decoder: &mut PageDecoder<'_>) -> Self {
+        use tiff::tags::Tag;
+
+        let (x, y) = (read_resolution(decoder, Tag::XResolution), read_resolution(decoder, Tag::YResolution));
+        let resolution = (x.is_finite() && y.is_finite())
+            .then(|| (x, y, decoder.get_tag_u32(Tag::ResolutionUnit).unwrap_or(2)));
+        let text = DESCRIPTIVE_TAGS
+            .iter()
+            .filter_map(|&(tag, key)| {
+                let value = decoder.get_tag_ascii_string(Tag::from_u16_exhaustive(tag)).ok()?;
+                let value = value.trim_end_matches('\0').trim();
+                (!value.is_empty()).then(|| (key, value.to_string()))
+            })
+            .collect();
+        PageMetadata {
+            bits_per_channel: decoder.get_tag_u32_vec(Tag::BitsPerSample).unwrap_or_default(),
+            resolution,
+            text,
+            channel_names: Vec::new(),
+        }
+    }
+}
+
+/// Name of a TIFF Compression code, for `TiffResult::metadata_json`.
+fn compression_name(compression: u32) -> &'static str {
+    match compression {
+        1 => "none",
+        2 => "CCITT RLE",
+        3 => "CCITT Group 3",
+        4 => "CCITT Group 4",
+        5 => "LZW",
+        6 => "old-style JPEG",
+        7 => "JPEG",
+        8 | 32946 => "Deflate",
+        32773 => "PackBits",
+        34712 => "JPEG 2000",
+        34887 => "LERC",
+        34925 => "LZMA",
+        50000 => "ZSTD",
+        50001 => "WebP",
+        _ => "unknown",
+    }
+}
+
+/// Name of a TIFF PhotometricInterpretation code, for
+/// `TiffResult::metadata_json`.
+fn photometric_name(photometric: u32) -> &'static str {
+    match photometric {
+        0 => "min-is-white",
+        1 => "min-is-black",
+        2 => "RGB",
+        3 => "palette",
+        4 => "mask",
+        5 => "CMYK",
+        6 => "YCbCr",
+        8 => "CIELab",
+        9 => "ICCLab",
+        10 => "ITULab",
+        32803 => "CFA",
+        32844 => "LogL",
+        32845 => "LogLuv",
+        34892 => "LinearRaw",
+        _ => "unknown",
+    }
+}
+
+/// Every number in a tag value, whatever its TIFF type (lists flattened).
+fn tag_value_numbers(value: tiff::decoder::ifd::Value) -> Vec<f64> {
+    use tiff::decoder::ifd::Value;
+    match value {
+        Value::Byte(v) => vec![v as f64],
+        Value::Short(v) => vec![v as f64],
+        Value::SignedByte(v) => vec![v as f64],
+        Value::SignedShort(v) => vec![v as f64],
+        Value::Signed(v) => vec![v as f64],
+        Value::SignedBig(v) => vec![v as f64],
+        Value::Unsigned(v) => vec![v as f64],
+        Value::UnsignedBig(v) => vec![v as f64],
+        Value::Float(v) => vec![v as f64],
+        Value::Double(v) => vec![v],
+        Value::Rational(n, d) if d != 0 => vec![n as f64 / d as f64],
+        Value::SRational(n, d) if d != 0 => vec![n as f64 / d as f64],
+        Value::List(items) => items.into_iter().flat_map(tag_value_numbers).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The value range a page declares in SMinSampleValue/SMaxSampleValue
+/// (340/341) or, for unsigned integers, MinSampleValue/MaxSampleValue
+/// (280/281), combined over all samples. Only returned when it is plausible
+/// as a display range for what the decode hands back: 8-bit-or-wider
+/// grayscale/RGB samples (sub-byte, palette, CMYK and YCbCr pages are
+/// expanded or converted first), finite, non-empty, inside the sample type's
+/// range and narrower than all of it - writers that emit the spec defaults
+/// (0 and 2^bits - 1) say nothing about the data.
+fn tagged_sample_range(decoder: &mut PageDecoder<'_>, photometric_interpretation: u32) -> Option<(f64, f64)> {
+    use tiff::tags::Tag;
+
+    let bits_per_sample = decoder.get_tag_u32_vec(Tag::BitsPerSample).ok()?.first().copied().unwrap_or(1);
+    let sample_format = decoder.get_tag_u32_vec(Tag::SampleFormat)
+        .ok()
+        .and_then(|values| values.first().copied())
+        .unwrap_or(1);
+    if !(8..=64).contains(&bits_per_sample) || photometric_interpretation > 2 {
+        return None;
+    }
+    let mut read = |tag: u16| -> Option<Vec<f64>> {
+        let values = tag_value_numbers(decoder.find_tag(Tag::Unknown(tag)).ok()??);
+        (!values.is_empty()).then_some(values)
+    };
+    let (mins, maxs) = match (read(340), read(341)) {
+        (Some(mins), Some(maxs)) => (mins, maxs),
+        _ if sample_format == 1 => (read(280)?, read(281)?),
+        _ => return None,
+    };
+    let min = mins.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = maxs.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let (type_min, type_max) = match sample_format {
+        1 => (0.0, 2f64.powi(bits_per_sample as i32) - 1.0),
+        2 => (-(2f64.powi(bits_per_sample as i32 - 1)), 2f64.powi(bits_per_sample as i32 - 1) - 1.0),
+        3 => (f64::NEG_INFINITY, f64::INFINITY),
+        _ => return None,
+    };
+    let plausible = min.is_finite()
+        && max.is_finite()
+        && min < max
+        && min >= type_min
+        && max <= type_max
+        && (min > type_min || max < type_max);
+    plausible.then_some((min, max))
+}
+
+impl GeoInfo {
+    fn read(decoder: &mut PageDecoder<'_>) -> Self {
+        use tiff::tags::Tag;
+
+        // GeoKeyDirectory is a 4-value header followed by
+        // [key, location, count, value] entries; location 0 stores the value
+        // inline. ProjectedCSTypeGeoKey (3072) takes precedence over
+        // GeographicTypeGeoKey (2048); 32767 means user-defined (no code).
+        let keys = decoder.get_tag_u16_vec(Tag::GeoKeyDirectoryTag).unwrap_or_default();
+        let mut projected = None;
+        let mut geographic = None;
+        for entry in keys.get(4..).unwrap_or(&[]).chunks_exact(4) {
+            if entry[1] != 0 || entry[3] == 0 || entry[3] == 32767 {
+                continue;
+            }
+            match entry[0] {
+                3072 => projected = Some(entry[3] as u32),
+                2048 => geographic = Some(entry[3] as u32),
+                _ => {}
+            }
         }
-        DecodingResult::F32(data) => {
-            let (min, max) = if compute_stats {
-                let stats_start = js_sys::Date::now();
-                let stats = compute_stats_f32(&data);
-                stats_time += js_sys::Date::now() - stats_start;
-                stats
-            } else {
-                (f64::NAN, f64::NAN)
-            };
-            (Vec::new(), data, 3u32, min, max)
+
+        GeoInfo {
+            nodata: gdal_nodata(decoder),
+            pixel_scale: decoder.get_tag_f64_vec(Tag::ModelPixelScaleTag).unwrap_or_default(),
+            tiepoint: decoder.get_tag_f64_vec(Tag::ModelTiepointTag).unwrap_or_default(),
+            transformation: decoder.get_tag_f64_vec(Tag::ModelTransformationTag).unwrap_or_default(),
+            epsg: projected.or(geographic),
+            color_interp: gdal_band_items(decoder, "COLORINTERP"),
+            descriptions: gdal_band_items(decoder, "DESCRIPTION"),
         }
-        DecodingResult::F64(data) => {
-            let (min, max) = if compute_stats {
-                let stats_start = js_sys::Date::now();
-                let stats = compute_stats_f64(&data);
-                stats_time += js_sys::Date::now() - stats_start;
-                stats
-            } else {
-                (f64::NAN, f64::NAN)
-            };
-            let pack_start = js_sys::Date::now();
-            let mut values = Vec::with_capacity(data.len());
-            for &val in &data {
-                values.push(val as f32);
+    }
+
+    fn is_georeferenced(&self) -> bool {
+        !self.tiepoint.is_empty() || !self.transformation.is_empty() || self.epsg.is_some()
+    }
+
+    /// The affine map `[a, b, c, d, e, f]` from stored raster coordinates
+    /// `(i, j)` to model `(a*i + b*j + c, d*i + e*j + f)`: the
+    /// ModelTransformation's first two rows, otherwise the first tiepoint
+    /// with the pixel scale (Y pointing up, as GeoTIFF defines it). None
+    /// without either, or for tiepoint-only (rubber-sheet) pages.
+    fn raster_to_model(&self) -> Option<[f64; 6]> {
+        if let [a, b, _, c, d, e, _, f, ..] = self.transformation[..] {
+            return Some([a, b, c, d, e, f]);
+        }
+        match (&self.tiepoint[..], &self.pixel_scale[..]) {
+            ([i, j, _, x, y, ..], [scale_x, scale_y, ..]) => {
+                Some([*scale_x, 0.0, x - i * scale_x, 0.0, -scale_y, y + j * scale_y])
             }
-            pack_time += js_sys::Date::now() - pack_start;
-            (Vec::new(), values, 3u32, min, max)
+            _ => None,
         }
-        DecodingResult::F16(data) => {
-            // Convert f16 to f32 for processing and pre-allocate
-            let pack_start = js_sys::Date::now();
-            let mut values = Vec::with_capacity(data.len());
-            let mut min_val = f32::INFINITY;
-            let mut max_val = f32::NEG_INFINITY;
+    }
 
-            if compute_stats {
-                for &val in &data {
-                    let f32_val = val.to_f32();
-                    if f32_val < min_val { min_val = f32_val; }
-                    if f32_val > max_val { max_val = f32_val; }
-                    values.push(f32_val);
-                }
-            } else {
-                for &val in &data {
-                    values.push(val.to_f32());
-                }
-            }
-            pack_time += js_sys::Date::now() - pack_start;
-            let min = if compute_stats { min_val as f64 } else { f64::NAN };
-            let max = if compute_stats { max_val as f64 } else { f64::NAN };
-            (Vec::new(), values, 3u32, min, max)
+    /// `{"epsg","pixel_scale","tiepoint","transformation"}`, or `null` when
+    /// the page has no georeferencing.
+    fn to_json(&self) -> String {
+        if !self.is_georeferenced() {
+            return "null".to_string();
         }
-    };
+        let list = |values: &[f64]| values.iter().map(|&v| json_f64(v)).collect::<Vec<_>>().join(",");
+        format!(
+            "{{\"epsg\":{},\"pixel_scale\":[{}],\"tiepoint\":[{}],\"transformation\":[{}]}}",
+            self.epsg.map_or("null".to_string(), |code| code.to_string()),
+            list(&self.pixel_scale),
+            list(&self.tiepoint),
+            list(&self.transformation)
+        )
+    }
+}
 
-    // Orientation tag (274): apply here, once, to whichever final buffer the
-    // decode path produced (bytes for integer samples, f32 for float) - this
-    // is after bilevel unpacking above so every buffer at this point is a
-    // plain one-sample-per-element interleaved raster, regardless of which
-    // decode path produced it. `bytes_per_pixel` is measured from the actual
-    // buffer rather than trusted from `bits_per_sample`, since sub-16-bit
-    // direct-decoded samples (9-15 bit) are packed as 2 bytes/sample even
-    // though `bits_per_sample` reports the true (smaller) bit depth.
-    let (width, height) = if orientation == TiffOrientation::TopLeft {
-        (width, height)
-    } else if !data_bytes.is_empty() {
-        let pixel_count = (width as usize) * (height as usize);
-        let bytes_per_pixel = if pixel_count > 0 { data_bytes.len() / pixel_count } else { 0 };
-        if bytes_per_pixel > 0 {
-            let (oriented, w, h) = apply_orientation(&data_bytes, width, height, bytes_per_pixel as u32, orientation);
-            data_bytes = oriented;
-            (w, h)
-        } else {
-            (width, height)
+/// The `[x, y, width, height]` bounding box of the rectangle `(x, y, width,
+/// height)` after mapping its corners through `map`.
+fn map_rect(map: impl Fn(f64, f64) -> (f64, f64), x: f64, y: f64, width: f64, height: f64) -> Vec<f64> {
+    let (x0, y0) = map(x, y);
+    let (x1, y1) = map(x + width, y + height);
+    vec![x0.min(x1), y0.min(y1), (x1 - x0).abs(), (y1 - y0).abs()]
+}
+
+/// The band of a page's pixels that is alpha: the second of gray + alpha,
+/// the fourth of RGBA (as `layout` names the channels).
+fn alpha_band_index(photometric_interpretation: u32, channels: u32) -> Option<usize> {
+    match (matches!(photometric_interpretation, 0 | 1), channels) {
+        (true, 2) => Some(1),
+        (false, 4) => Some(3),
+        _ => None,
+    }
+}
+
+/// Call `visit` with each pixel of the `channels` interleaved bands of
+/// `samples`, skipping pixels whose `alpha` band is zero or below.
+fn for_each_kept_pixel(
+    samples: impl Iterator<Item = f64>,
+    channels: usize,
+    alpha: Option<usize>,
+    visit: &mut impl FnMut(&[f64]),
+) {
+    let mut pixel = vec![0.0; channels];
+    for (index, value) in samples.enumerate() {
+        let channel = index % channels;
+        pixel[channel] = value;
+        if channel + 1 < channels || alpha.is_some_and(|band| pixel[band] <= 0.0) {
+            continue;
         }
-    } else if !data_f32.is_empty() {
-        let (oriented, w, h) = apply_orientation(&data_f32, width, height, channels, orientation);
-        data_f32 = oriented;
-        (w, h)
-    } else {
-        (width, height)
-    };
+        visit(&pixel);
+    }
+}
 
-    let convert_time = js_sys::Date::now() - convert_start;
-    let total_time = js_sys::Date::now() - start_time;
-    let metadata_time = total_time - decompress_time - convert_time;
+/// Running statistics of the finite samples of one channel
+/// (`TiffResult::channel_stats`). Sums are of the offsets from the first
+/// sample, which keeps the variance accurate for data far from zero (e.g.
+/// elevations or timestamps).
+#[derive(Clone, Copy)]
+struct ChannelStats {
+    min: f64,
+    max: f64,
+    count: u64,
+    shift: f64,
+    sum: f64,
+    sum_squares: f64,
+}
 
-    let result = Ok(TiffResult {
-        width,
-        height,
-        channels,
-        bits_per_sample,
-        sample_format,
-        compression,
-        predictor,
-        photometric_interpretation,
-        planar_configuration,
-        rows_per_strip,
-        strip_count,
-        strip_byte_count_total,
-        strip_byte_count_max,
-        tile_width,
-        tile_length,
-        tile_count,
-        direct_decode,
-        data: data_bytes,
-        data_f32,
-        min_value: min_val,
-        max_value: max_val,
-        timing_metadata_ms: metadata_time,
-        timing_decode_ms: decompress_time,
-        timing_convert_ms: convert_time,
-        timing_stats_ms: stats_time,
-        timing_pack_ms: pack_time,
-        all_tags_json: extract_page_tags_json(data, page_index),
-        ome_xml: extract_ome_xml(data),
-    });
+impl Default for ChannelStats {
+    fn default() -> Self {
+        ChannelStats { min: f64::INFINITY, max: f64::NEG_INFINITY, count: 0, shift: 0.0, sum: 0.0, sum_squares: 0.0 }
+    }
+}
 
-    web_sys::console::log_1(&format!(
-        "[Rust] Total: {:.2}ms (metadata: {:.2}ms, decompress: {:.2}ms, convert: {:.2}ms)", 
-        total_time, metadata_time, decompress_time, convert_time
-    ).into());
-    
-    result
+impl ChannelStats {
+    /// Take `value` into account unless it is NaN/Inf.
+    fn add(&mut self, value: f64) {
+        if !value.is_finite() {
+            return;
+        }
+        if self.count == 0 {
+            self.shift = value;
+        }
+        let offset = value - self.shift;
+        self.count += 1;
+        self.sum += offset;
+        self.sum_squares += offset * offset;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    /// `(min, max)`, `(inf, -inf)` without a finite sample.
+    fn range(&self) -> (f64, f64) {
+        (self.min, self.max)
+    }
+
+    fn mean(&self) -> f64 {
+        if self.count == 0 { f64::NAN } else { self.shift + self.sum / self.count as f64 }
+    }
+
+    /// Population standard deviation.
+    fn std_dev(&self) -> f64 {
+        if self.count == 0 {
+            return f64::NAN;
+        }
+        let n = self.count as f64;
+        (self.sum_squares / n - (self.sum / n).powi(2)).max(0.0).sqrt()
+    }
+}
+
+/// A JSON number, or `null` for NaN/Inf (which JSON cannot represent).
+fn json_f64(value: f64) -> String {
+    if value.is_finite() { value.to_string() } else { "null".to_string() }
+}
+
+/// `len` samples all set to `value` (saturated into the sample type), in the
+/// `DecodingResult` variant `read_chunk()` produces for this sample format,
+/// standing in for a sparse chunk. `None` for f16 and other layouts no
+/// chunk-by-chunk path handles.
+fn filled_chunk(sample_format: u32, bits_per_sample: u32, len: usize, value: f64) -> Option<DecodingResult> {
+    Some(match (sample_format, bits_per_sample) {
+        (1, 8) => DecodingResult::U8(vec![value as u8; len]),
+        (1, 16) => DecodingResult::U16(vec![value as u16; len]),
+        (1, 32) => DecodingResult::U32(vec![value as u32; len]),
+        (1, 64) => DecodingResult::U64(vec![value as u64; len]),
+        (2, 8) => DecodingResult::I8(vec![value as i8; len]),
+        (2, 16) => DecodingResult::I16(vec![value as i16; len]),
+        (2, 32) => DecodingResult::I32(vec![value as i32; len]),
+        (2, 64) => DecodingResult::I64(vec![value as i64; len]),
+        (3, 32) => DecodingResult::F32(vec![value as f32; len]),
+        (3, 64) => DecodingResult::F64(vec![value; len]),
+        _ => return None,
+    })
 }
 
 fn tiff_is_little_endian(data: &[u8]) -> Option<bool> {
@@ -2101,9 +13530,25 @@ fn tiff_is_little_endian(data: &[u8]) -> Option<bool> {
     }
 }
 
-fn try_decode_uncompressed_strips(
+/// Whether `read_uncompressed_strip_bytes` handles this sample type: whole
+/// bytes per sample, and a width the `DecodingResult`/`TiffResult` layouts
+/// have a slot for.
+fn uncompressed_sample_kind_supported(sample_format: u32, bits_per_sample: u32) -> bool {
+    matches!(
+        (sample_format, bits_per_sample),
+        (1 | 2, 8 | 16 | 32 | 64) | (3, 32 | 64)
+    )
+}
+
+/// Gather the raw sample bytes of an uncompressed, chunky, stripped image
+/// straight out of the file, in file byte order. Returns the raster together
+/// with whether the file is little-endian and its SampleFormat, or `None`
+/// when the image is not laid out that simply (tiled, predicted, truncated
+/// strips, ...) and a more general path has to handle it.
+#[allow(clippy::too_many_arguments)]
+fn read_uncompressed_strip_bytes(
     data: &[u8],
-    decoder: &mut Decoder<Cursor<&[u8]>>,
+    decoder: &mut PageDecoder<'_>,
     width: u32,
     height: u32,
     channels: u32,
@@ -2111,13 +13556,14 @@ fn try_decode_uncompressed_strips(
     compression: u32,
     predictor: u32,
     planar_configuration: u32,
-) -> Result<Option<DecodingResult>, JsValue> {
+    profile: &mut ChunkProfile,
+) -> Result<Option<(Vec<u8>, bool, u32)>, TiffError> {
     use tiff::tags::Tag;
 
     if compression != 1 || predictor != 1 || planar_configuration != 1 {
         return Ok(None);
     }
-    if bits_per_sample == 0 || bits_per_sample % 8 != 0 {
+    if bits_per_sample == 0 || !bits_per_sample.is_multiple_of(8) {
         return Ok(None);
     }
     if decoder.get_tag_u64_vec(Tag::TileOffsets).is_ok() {
@@ -2128,6 +13574,13 @@ fn try_decode_uncompressed_strips(
         Some(value) => value,
         None => return Ok(None),
     };
+    let sample_format = decoder.get_tag_u64_vec(Tag::SampleFormat)
+        .ok()
+        .and_then(|values| values.first().copied())
+        .unwrap_or(1) as u32;
+    if !uncompressed_sample_kind_supported(sample_format, bits_per_sample) {
+        return Ok(None);
+    }
     let offsets = match decoder.get_tag_u64_vec(Tag::StripOffsets) {
         Ok(value) if !value.is_empty() => value,
         _ => return Ok(None),
@@ -2137,28 +13590,24 @@ fn try_decode_uncompressed_strips(
         _ => return Ok(None),
     };
 
-    let sample_format = decoder.get_tag_u64_vec(Tag::SampleFormat)
-        .ok()
-        .and_then(|values| values.first().copied())
-        .unwrap_or(1) as u32;
     let sample_count = (width as usize)
         .checked_mul(height as usize)
         .and_then(|v| v.checked_mul(channels as usize))
-        .ok_or_else(|| JsValue::from_str("Direct TIFF decode: image dimensions overflow"))?;
+        .ok_or_else(|| TiffError::other("Direct TIFF decode: image dimensions overflow"))?;
     let bytes_per_sample = (bits_per_sample / 8) as usize;
     let expected_bytes = sample_count
         .checked_mul(bytes_per_sample)
-        .ok_or_else(|| JsValue::from_str("Direct TIFF decode: raster byte count overflow"))?;
+        .ok_or_else(|| TiffError::other("Direct TIFF decode: raster byte count overflow"))?;
 
     let total_available = counts.iter().try_fold(0usize, |acc, &count| {
         acc.checked_add(count as usize)
-    }).ok_or_else(|| JsValue::from_str("Direct TIFF decode: strip byte count overflow"))?;
+    }).ok_or_else(|| TiffError::other("Direct TIFF decode: strip byte count overflow"))?;
     if total_available < expected_bytes {
         return Ok(None);
     }
 
     let mut raster = Vec::with_capacity(expected_bytes);
-    for (&offset, &count) in offsets.iter().zip(counts.iter()) {
+    for (index, (&offset, &count)) in offsets.iter().zip(counts.iter()).enumerate() {
         if raster.len() >= expected_bytes {
             break;
         }
@@ -2169,100 +13618,312 @@ fn try_decode_uncompressed_strips(
             None => return Ok(None),
         };
         if end > data.len() {
+            profile.records.clear();
             return Ok(None);
         }
         let remaining = expected_bytes - raster.len();
         let take = remaining.min(count_usize);
+        let copy_start = now_ms();
         raster.extend_from_slice(&data[start..start + take]);
+        profile.record(index, count, take, now_ms() - copy_start);
+    }
+    if raster.len() != expected_bytes {
+        profile.records.clear();
+        return Ok(None);
+    }
+
+    Ok(Some((raster, little_endian, sample_format)))
+}
+
+/// Reverse the byte order of every `width`-byte sample in `bytes`, turning
+/// a big-endian raster into the little-endian layout `TiffResult` stores.
+fn swap_sample_bytes(bytes: &mut [u8], width: usize) {
+    if !matches!(width, 2 | 4 | 8) {
+        return;
+    }
+    #[cfg(feature = "simd")]
+    let bytes = swap_sample_bytes_lanes(bytes, width);
+    bytes.chunks_exact_mut(width).for_each(|sample| sample.reverse());
+}
+
+/// `swap_sample_bytes` on 128-bit lanes (16 bytes at a time) with shifts and
+/// masks, so big-endian (MM) files cost about what a copy does. Returns the
+/// tail shorter than a lane, still to be swapped sample by sample.
+#[cfg(feature = "simd")]
+fn swap_sample_bytes_lanes(bytes: &mut [u8], width: usize) -> &mut [u8] {
+    use wide::*;
+
+    let mut lanes = bytes.chunks_exact_mut(16);
+    for lane in &mut lanes {
+        match width {
+            2 => {
+                let v = u16x8::new(std::array::from_fn(|i| u16::from_le_bytes([lane[2 * i], lane[2 * i + 1]])));
+                let swapped = (v << 8u32) | (v >> 8u32);
+                for (dest, value) in lane.chunks_exact_mut(2).zip(swapped.to_array()) {
+                    dest.copy_from_slice(&value.to_le_bytes());
+                }
+            }
+            4 => {
+                let v = u32x4::new(std::array::from_fn(|i| u32::from_le_bytes(lane[4 * i..4 * i + 4].try_into().unwrap())));
+                let bytes_mask = u32x4::splat(0x00FF_00FF);
+                let v = ((v & bytes_mask) << 8u32) | ((v >> 8u32) & bytes_mask);
+                let swapped = (v << 16u32) | (v >> 16u32);
+                for (dest, value) in lane.chunks_exact_mut(4).zip(swapped.to_array()) {
+                    dest.copy_from_slice(&value.to_le_bytes());
+                }
+            }
+            _ => {
+                let v = u64x2::new(std::array::from_fn(|i| u64::from_le_bytes(lane[8 * i..8 * i + 8].try_into().unwrap())));
+                let bytes_mask = u64x2::splat(0x00FF_00FF_00FF_00FF);
+                let halves_mask = u64x2::splat(0x0000_FFFF_0000_FFFF);
+                let v = ((v & bytes_mask) << 8u32) | ((v >> 8u32) & bytes_mask);
+                let v = ((v & halves_mask) << 16u32) | ((v >> 16u32) & halves_mask);
+                let swapped = (v << 32u32) | (v >> 32u32);
+                for (dest, value) in lane.chunks_exact_mut(8).zip(swapped.to_array()) {
+                    dest.copy_from_slice(&value.to_le_bytes());
+                }
+            }
+        }
+    }
+    lanes.into_remainder()
+}
+
+/// Min/max of a little-endian integer raster as packed by
+/// `try_copy_uncompressed_strips`, without materialising typed samples.
+fn compute_stats_le_bytes(bytes: &[u8], sample_format: u32, bytes_per_sample: usize) -> (f64, f64) {
+    macro_rules! le_stats {
+        ($ty:ty, $n:expr) => {{
+            let mut min = <$ty>::MAX;
+            let mut max = <$ty>::MIN;
+            for b in bytes.chunks_exact($n) {
+                let value = <$ty>::from_le_bytes(b.try_into().unwrap());
+                min = min.min(value);
+                max = max.max(value);
+            }
+            (min as f64, max as f64)
+        }};
+        ($ty:ty, $n:expr, $lane:ident) => {{
+            #[cfg(not(feature = "simd"))]
+            let stats = le_stats!($ty, $n);
+            #[cfg(feature = "simd")]
+            let stats = {
+                let sample = |b: &[u8]| <$ty>::from_le_bytes(b.try_into().unwrap());
+                let (lanes, tail) = bytes.split_at(lane_split(bytes.len() / $n, $n) * $n);
+                let (min, max) = min_max!(
+                    $ty,
+                    $lane,
+                    lanes.chunks_exact(16).map(|lane| std::array::from_fn(|i| sample(&lane[$n * i..$n * (i + 1)]))),
+                    tail.chunks_exact($n).map(sample)
+                );
+                (min as f64, max as f64)
+            };
+            stats
+        }};
+    }
+
+    match (sample_format, bytes_per_sample) {
+        (1, 1) => {
+            let (min, max) = compute_stats_u8(bytes);
+            (min as f64, max as f64)
+        }
+        (1, 2) => le_stats!(u16, 2, u16x8),
+        (1, 4) => le_stats!(u32, 4, u32x4),
+        (1, 8) => le_stats!(u64, 8),
+        (2, 1) => le_stats!(i8, 1, i8x16),
+        (2, 2) => le_stats!(i16, 2, i16x8),
+        (2, 4) => le_stats!(i32, 4, i32x4),
+        (2, 8) => le_stats!(i64, 8),
+        _ => (f64::NAN, f64::NAN),
+    }
+}
+
+/// Fast path for uncompressed chunky strips whose on-disk sample layout is
+/// already the layout `TiffResult` hands to JS: the strip bytes are copied
+/// once, straight into the output buffer, instead of being parsed into a
+/// typed `DecodingResult` and then re-serialised by `pack_decoding_result`.
+/// Big-endian files get one bulk in-place byte swap (`swap_sample_bytes`);
+/// float samples get one bulk conversion into `floats` (f32) or the native
+/// f64 buffer. CMYK still needs `convert_cmyk_to_rgb`, so it is left to
+/// `try_decode_uncompressed_strips`.
+#[allow(clippy::too_many_arguments)]
+fn try_copy_uncompressed_strips(
+    data: &[u8],
+    decoder: &mut PageDecoder<'_>,
+    width: u32,
+    height: u32,
+    channels: u32,
+    bits_per_sample: u32,
+    compression: u32,
+    predictor: u32,
+    photometric_interpretation: u32,
+    planar_configuration: u32,
+    compute_stats: bool,
+    profile: &mut ChunkProfile,
+) -> Result<Option<(PackedRaster, ChunkTimings)>, TiffError> {
+    if photometric_interpretation == 5 {
+        return Ok(None);
+    }
+
+    let mut timings = ChunkTimings::default();
+    let copy_start = now_ms();
+    let (mut raster, little_endian, sample_format) = match read_uncompressed_strip_bytes(
+        data,
+        decoder,
+        width,
+        height,
+        channels,
+        bits_per_sample,
+        compression,
+        predictor,
+        planar_configuration,
+        profile,
+    )? {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+    timings.decode_ms = now_ms() - copy_start;
+
+    let bytes_per_sample = (bits_per_sample / 8) as usize;
+    let pack_start = now_ms();
+    if !little_endian {
+        swap_sample_bytes(&mut raster, bytes_per_sample);
+    }
+    let (bytes, floats, native_float) = match (sample_format, bits_per_sample) {
+        (3, 32) => {
+            let values: Vec<f32> = raster.chunks_exact(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect();
+            (Vec::new(), values, None)
+        }
+        (3, _) => {
+            let values: Vec<f64> = raster.chunks_exact(8)
+                .map(|b| f64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]))
+                .collect();
+            (Vec::new(), Vec::new(), Some(DecodingResult::F64(values)))
+        }
+        _ => (raster, Vec::new(), None),
+    };
+    timings.pack_ms = now_ms() - pack_start;
+
+    let (min, max) = if compute_stats {
+        let stats_start = now_ms();
+        let stats = match &native_float {
+            Some(DecodingResult::F64(values)) => compute_stats_f64(values),
+            _ if sample_format == 3 => compute_stats_f32(&floats),
+            _ => compute_stats_le_bytes(&bytes, sample_format, bytes_per_sample),
+        };
+        timings.stats_ms = now_ms() - stats_start;
+        stats
+    } else {
+        (f64::NAN, f64::NAN)
+    };
+
+    Ok(Some((PackedRaster { bytes, floats, native_float, sample_format, min, max }, timings)))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn try_decode_uncompressed_strips(
+    data: &[u8],
+    decoder: &mut PageDecoder<'_>,
+    width: u32,
+    height: u32,
+    channels: u32,
+    bits_per_sample: u32,
+    compression: u32,
+    predictor: u32,
+    planar_configuration: u32,
+    profile: &mut ChunkProfile,
+) -> Result<Option<DecodingResult>, TiffError> {
+    let (mut raster, little_endian, sample_format) = match read_uncompressed_strip_bytes(
+        data,
+        decoder,
+        width,
+        height,
+        channels,
+        bits_per_sample,
+        compression,
+        predictor,
+        planar_configuration,
+        profile,
+    )? {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+
+    // Big-endian rasters are swapped in bulk once, so every sample below
+    // is read in the one (little-endian) byte order.
+    if !little_endian {
+        swap_sample_bytes(&mut raster, (bits_per_sample / 8) as usize);
     }
-    if raster.len() != expected_bytes {
-        return Ok(None);
+    macro_rules! words {
+        ($variant:ident, $ty:ty, $n:expr) => {
+            DecodingResult::$variant(raster.chunks_exact($n).map(|b| <$ty>::from_le_bytes(b.try_into().unwrap())).collect())
+        };
     }
 
     let result = match (sample_format, bits_per_sample) {
         (1, 8) => DecodingResult::U8(raster),
-        (1, 16) => {
-            let values = raster.chunks_exact(2)
-                .map(|b| {
-                    if little_endian {
-                        u16::from_le_bytes([b[0], b[1]])
-                    } else {
-                        u16::from_be_bytes([b[0], b[1]])
-                    }
-                })
-                .collect();
-            DecodingResult::U16(values)
-        }
-        (1, 32) => {
-            let values = raster.chunks_exact(4)
-                .map(|b| {
-                    if little_endian {
-                        u32::from_le_bytes([b[0], b[1], b[2], b[3]])
-                    } else {
-                        u32::from_be_bytes([b[0], b[1], b[2], b[3]])
-                    }
-                })
-                .collect();
-            DecodingResult::U32(values)
-        }
+        (1, 16) => words!(U16, u16, 2),
+        (1, 32) => words!(U32, u32, 4),
+        (1, 64) => words!(U64, u64, 8),
         (2, 8) => DecodingResult::I8(raster.into_iter().map(|v| v as i8).collect()),
-        (2, 16) => {
-            let values = raster.chunks_exact(2)
-                .map(|b| {
-                    if little_endian {
-                        i16::from_le_bytes([b[0], b[1]])
-                    } else {
-                        i16::from_be_bytes([b[0], b[1]])
-                    }
-                })
-                .collect();
-            DecodingResult::I16(values)
-        }
-        (2, 32) => {
-            let values = raster.chunks_exact(4)
-                .map(|b| {
-                    if little_endian {
-                        i32::from_le_bytes([b[0], b[1], b[2], b[3]])
-                    } else {
-                        i32::from_be_bytes([b[0], b[1], b[2], b[3]])
-                    }
-                })
-                .collect();
-            DecodingResult::I32(values)
-        }
-        (3, 32) => {
-            let values = raster.chunks_exact(4)
-                .map(|b| {
-                    if little_endian {
-                        f32::from_le_bytes([b[0], b[1], b[2], b[3]])
-                    } else {
-                        f32::from_be_bytes([b[0], b[1], b[2], b[3]])
-                    }
-                })
-                .collect();
-            DecodingResult::F32(values)
-        }
-        (3, 64) => {
-            let values = raster.chunks_exact(8)
-                .map(|b| {
-                    if little_endian {
-                        f64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]])
-                    } else {
-                        f64::from_be_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]])
-                    }
-                })
-                .collect();
-            DecodingResult::F64(values)
-        }
+        (2, 16) => words!(I16, i16, 2),
+        (2, 32) => words!(I32, i32, 4),
+        (2, 64) => words!(I64, i64, 8),
+        (3, 32) => words!(F32, f32, 4),
+        (3, 64) => words!(F64, f64, 8),
         _ => return Ok(None),
     };
 
     Ok(Some(result))
 }
 
+/// Number of workers `map_in_worker_pool` spreads jobs over: the host's
+/// available parallelism on native builds, and 1 on wasm32, whose default
+/// target has no shared memory and cannot spawn std threads.
+fn worker_pool_size() -> usize {
+    if cfg!(target_arch = "wasm32") {
+        1
+    } else {
+        std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+    }
+}
+
+/// Map `job` over `inputs` on a small pool of scoped worker threads, keeping
+/// results in input order. Falls back to a plain sequential map when the pool
+/// has a single worker (always the case in the WASM build), so callers can
+/// hand it independent per-strip/tile work unconditionally. There is no
+/// rayon dependency; a threaded WASM build would plug its pool in here.
+fn map_in_worker_pool<I: Sync, O: Send>(inputs: &[I], job: impl Fn(&I) -> O + Sync) -> Vec<O> {
+    map_on_workers(worker_pool_size(), inputs, job)
+}
+
+/// `map_in_worker_pool` with an explicit worker count.
+fn map_on_workers<I: Sync, O: Send>(workers: usize, inputs: &[I], job: impl Fn(&I) -> O + Sync) -> Vec<O> {
+    let workers = workers.min(inputs.len());
+    if workers <= 1 {
+        return inputs.iter().map(job).collect();
+    }
+
+    let per_worker = inputs.len().div_ceil(workers);
+    let job = &job;
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = inputs
+            .chunks(per_worker)
+            .map(|slice| scope.spawn(move || slice.iter().map(job).collect::<Vec<O>>()))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+            .collect()
+    })
+}
+
 /// Decompress one strip/tile's compressed bytes (compression None/LZW/
 /// Deflate) into exactly `expected_len` bytes, shared by
-/// `try_decode_subbit_strips` and `try_decode_general_strips_tiles`.
+/// `try_decode_subbit_strips` and `try_decode_general_strips_tiles`. Errors
+/// are plain `String`s (not `JsValue`, which is `!Send`) so blocks can be
+/// decompressed on `map_in_worker_pool` workers.
 ///
 /// LZW is decoded through weezl's low-level `decode_bytes` (rather than the
 /// `decode()`/`into_vec()` convenience wrapper) precisely because it does
@@ -2273,7 +13934,7 @@ fn try_decode_uncompressed_strips(
 /// this, and so does this path, since we already know the exact decompressed
 /// size from the image/tile geometry and don't need the stream to tell us
 /// when to stop.
-fn decompress_strip_or_tile(block: &[u8], compression: u32, expected_len: usize, context: &str) -> Result<Vec<u8>, JsValue> {
+fn decompress_strip_or_tile(block: &[u8], compression: u32, expected_len: usize, context: &str) -> Result<Vec<u8>, String> {
     use std::io::Read;
 
     match compression {
@@ -2298,15 +13959,15 @@ fn decompress_strip_or_tile(block: &[u8], compression: u32, expected_len: usize,
                             // the expected length below.
                             break;
                         }
-                        return Err(JsValue::from_str(&format!("{}: LZW decode stalled before end of input", context)));
+                        return Err(format!("{}: LZW decode stalled before end of input", context));
                     }
-                    Err(e) => return Err(JsValue::from_str(&format!("{}: LZW decode failed: {}", context, e))),
+                    Err(e) => return Err(format!("{}: LZW decode failed: {}", context, e)),
                 }
             }
             if out_pos < expected_len {
-                return Err(JsValue::from_str(&format!(
+                return Err(format!(
                     "{}: LZW stream produced {} bytes, expected {}", context, out_pos, expected_len
-                )));
+                ));
             }
             Ok(out)
         }
@@ -2314,10 +13975,10 @@ fn decompress_strip_or_tile(block: &[u8], compression: u32, expected_len: usize,
             let mut zd = flate2::read::ZlibDecoder::new(block);
             let mut buf = Vec::new();
             zd.read_to_end(&mut buf)
-                .map_err(|e| JsValue::from_str(&format!("{}: Deflate decode failed: {}", context, e)))?;
+                .map_err(|e| format!("{}: Deflate decode failed: {}", context, e))?;
             Ok(buf)
         }
-        _ => Err(JsValue::from_str(&format!("{}: compression {} is not supported", context, compression))),
+        _ => Err(format!("{}: compression {} is not supported", context, compression)),
     }
 }
 
@@ -2349,8 +14010,9 @@ fn unpack_msb_packed_row(row: &[u8], samples_per_row: usize, bits_per_sample: u3
 
 /// Apply the horizontal (predictor 2) differencing predictor in place to one
 /// decoded row of `row_width` pixels x `channels` samples, wrapping modulo
-/// 2^bits_per_sample (via `max_value`). Shared by `try_decode_subbit_strips`
-/// and `try_decode_general_strips_tiles`.
+/// 2^bits_per_sample (via `max_value`). Used by `try_decode_subbit_strips`;
+/// `try_decode_general_strips_tiles` uses the 64-bit-wide
+/// `apply_horizontal_predictor2_wide`.
 fn apply_horizontal_predictor2(row_values: &mut [u16], row_width: usize, channels: usize, max_value: u32) {
     for x in 1..row_width {
         for c in 0..channels {
@@ -2362,6 +14024,10 @@ fn apply_horizontal_predictor2(row_values: &mut [u16], row_width: usize, channel
     }
 }
 
+/// Samples from a direct strip/tile path, with the min/max it folded in
+/// along the way when stats were requested.
+type StripDecode = (DecodingResult, Option<(f64, f64)>);
+
 /// Decode chunky, strip-based, unsigned-integer samples whose bit depth is
 /// non-byte-aligned (9..=15 bits, e.g. 10/12/14-bit RGB or grayscale, common
 /// for RAW-derived TIFFs). The tiff crate's read_image() only supports 8/16/
@@ -2379,10 +14045,14 @@ fn apply_horizontal_predictor2(row_values: &mut [u16], row_width: usize, channel
 /// after this one). Returns `Err` for cases within this path's scope that
 /// are known to not be decodable (LSB fill order, non-unsigned sample
 /// format, unsupported predictor).
+///
+/// With `compute_stats`, min/max are folded in row by row while each strip's
+/// rows are still cache-hot and returned alongside the samples, so the caller
+/// skips its separate whole-image stats pass.
 #[allow(clippy::too_many_arguments)]
 fn try_decode_subbit_strips(
     data: &[u8],
-    decoder: &mut Decoder<Cursor<&[u8]>>,
+    decoder: &mut PageDecoder<'_>,
     width: u32,
     height: u32,
     channels: u32,
@@ -2390,7 +14060,9 @@ fn try_decode_subbit_strips(
     compression: u32,
     predictor: u32,
     planar_configuration: u32,
-) -> Result<Option<DecodingResult>, JsValue> {
+    compute_stats: bool,
+    profile: &mut ChunkProfile,
+) -> Result<Option<StripDecode>, TiffError> {
     use tiff::tags::Tag;
 
     if !(9..=15).contains(&bits_per_sample) {
@@ -2405,18 +14077,18 @@ fn try_decode_subbit_strips(
         return Ok(None);
     }
     if decoder.get_tag_u64_vec(Tag::TileOffsets).is_ok() {
-        return Err(JsValue::from_str(
+        return Err(TiffError::other(
             "Sub-16-bit TIFF: tiled layout is not supported by the direct decode path",
         ));
     }
     if predictor != 1 && predictor != 2 {
-        return Err(JsValue::from_str(&format!(
+        return Err(TiffError::other(format!(
             "Sub-16-bit TIFF: predictor {} is not supported", predictor
         )));
     }
     let fill_order = decoder.get_tag_u32(Tag::FillOrder).unwrap_or(1);
     if fill_order != 1 {
-        return Err(JsValue::from_str(
+        return Err(TiffError::other(
             "Sub-16-bit TIFF: FillOrder 2 (LSB-first) is not supported",
         ));
     }
@@ -2425,7 +14097,7 @@ fn try_decode_subbit_strips(
         .and_then(|values| values.first().copied())
         .unwrap_or(1) as u32;
     if sample_format != 1 {
-        return Err(JsValue::from_str(&format!(
+        return Err(TiffError::other(format!(
             "Sub-16-bit TIFF: sample format {} is not supported (only unsigned integer)", sample_format
         )));
     }
@@ -2441,28 +14113,32 @@ fn try_decode_subbit_strips(
     let rows_per_strip = decoder.get_tag_u32(Tag::RowsPerStrip).unwrap_or(height).max(1);
 
     let samples_per_row = (width as usize).saturating_mul(channels as usize);
-    let row_bytes = (samples_per_row * bits_per_sample as usize + 7) / 8;
+    let row_bytes = (samples_per_row * bits_per_sample as usize).div_ceil(8);
     let max_value = (1u32 << bits_per_sample) - 1;
 
     let mut out: Vec<u16> = Vec::with_capacity(samples_per_row.saturating_mul(height as usize));
     let mut rows_decoded: u32 = 0;
+    let (mut min, mut max) = (u16::MAX, u16::MIN);
 
-    for (&offset, &count) in offsets.iter().zip(counts.iter()) {
+    for (index, (&offset, &count)) in offsets.iter().zip(counts.iter()).enumerate() {
         if rows_decoded >= height {
             break;
         }
         let start = offset as usize;
         let end = start.saturating_add(count as usize);
         if end > data.len() {
-            return Err(JsValue::from_str("Sub-16-bit TIFF: strip byte range out of bounds"));
+            return Err(TiffError::other("Sub-16-bit TIFF: strip byte range out of bounds"));
         }
         let strip = &data[start..end];
 
         let rows_in_strip = rows_per_strip.min(height - rows_decoded) as usize;
         let expected_bytes = row_bytes.saturating_mul(rows_in_strip);
-        let decompressed = decompress_strip_or_tile(strip, compression, expected_bytes, "Sub-16-bit TIFF")?;
+        let decompress_start = now_ms();
+        let decompressed = decompress_strip_or_tile(strip, compression, expected_bytes, "Sub-16-bit TIFF")
+            .map_err(|e| TiffError::other(&e))?;
+        profile.record(index, count, decompressed.len(), now_ms() - decompress_start);
         if decompressed.len() < expected_bytes {
-            return Err(JsValue::from_str(&format!(
+            return Err(TiffError::other(format!(
                 "Sub-16-bit TIFF: strip decompressed to {} bytes, expected at least {}",
                 decompressed.len(), expected_bytes
             )));
@@ -2476,21 +14152,27 @@ fn try_decode_subbit_strips(
                 apply_horizontal_predictor2(&mut row_values, width as usize, channels as usize, max_value);
             }
 
+            if compute_stats {
+                let (row_min, row_max) = compute_stats_u16(&row_values);
+                min = min.min(row_min);
+                max = max.max(row_max);
+            }
             out.extend_from_slice(&row_values);
         }
         rows_decoded += rows_in_strip as u32;
     }
 
     if rows_decoded != height {
-        return Err(JsValue::from_str(&format!(
+        return Err(TiffError::other(format!(
             "Sub-16-bit TIFF: decoded {} of {} rows", rows_decoded, height
         )));
     }
 
-    Ok(Some(DecodingResult::U16(out)))
+    let stats = compute_stats.then_some((min as f64, max as f64));
+    Ok(Some((DecodingResult::U16(out), stats)))
 }
 
-/// Decode strip- or tile-based, unsigned-integer TIFFs for the two cases the
+/// Decode strip- or tile-based integer/float TIFFs for the two cases the
 /// `tiff` crate's `read_image()` gets wrong or refuses outright:
 ///
 ///  - **Any `PlanarConfiguration == 2` image** (strips or tiles, any of the
@@ -2519,16 +14201,23 @@ fn try_decode_subbit_strips(
 /// JS side expects (the caller still reports the true
 /// `PlanarConfiguration` tag value in `TiffResult` metadata).
 ///
-/// Supports 8-bit and 9..=16-bit unsigned integer samples (matching what
-/// `TiffResult::get_data_as_f32` knows how to unpack), predictor 1/2,
-/// compression None/LZW/Deflate, and MSB-first fill order. Returns `Err` with
-/// a clear message for anything else within its trigger scope (planar float,
-/// planar 32-bit, LSB fill order, unsupported predictor/compression) rather
-/// than silently producing wrong pixels.
+/// Supports 9..=16-bit unsigned integers and byte-aligned 8/16/32/64-bit
+/// unsigned, signed and (32/64-bit) float samples in the file's byte order,
+/// predictor 1/2 (horizontal differencing is undone on the raw sample bit
+/// patterns, as libtiff does, for any channel count), compression
+/// None/LZW/Deflate, and MSB-first fill order. Returns `Err` with a clear
+/// message for anything else within its trigger scope (16-bit float, signed
+/// sub-byte-aligned depths, predictor 3, LSB fill order, unsupported
+/// compression) rather than silently producing wrong pixels.
+///
+/// With `compute_stats`, each batch's rows are folded into a running min/max
+/// (`fold_sample_bits_stats`) as they are placed, while the next batch is
+/// still ahead of us, and the result is returned alongside the samples so the
+/// caller skips its separate whole-image stats pass.
 #[allow(clippy::too_many_arguments)]
 fn try_decode_general_strips_tiles(
     data: &[u8],
-    decoder: &mut Decoder<Cursor<&[u8]>>,
+    decoder: &mut PageDecoder<'_>,
     width: u32,
     height: u32,
     channels: u32,
@@ -2538,7 +14227,9 @@ fn try_decode_general_strips_tiles(
     planar_configuration: u32,
     tile_width: u32,
     tile_length: u32,
-) -> Result<Option<DecodingResult>, JsValue> {
+    compute_stats: bool,
+    profile: &mut ChunkProfile,
+) -> Result<Option<StripDecode>, TiffError> {
     use tiff::tags::Tag;
 
     let is_tiled = tile_width > 0 && tile_length > 0;
@@ -2548,30 +14239,43 @@ fn try_decode_general_strips_tiles(
 
     const CTX: &str = "Planar/tiled TIFF";
 
-    if bits_per_sample != 8 && !(9..=16).contains(&bits_per_sample) {
-        return Err(JsValue::from_str(&format!(
-            "{}: {}-bit samples are not supported", CTX, bits_per_sample
-        )));
-    }
     if compression != 1 && compression != 5 && compression != 8 && compression != 32946 {
-        return Err(JsValue::from_str(&format!("{}: compression {} is not supported", CTX, compression)));
+        return Err(TiffError::other(format!("{}: compression {} is not supported", CTX, compression)));
     }
     if predictor != 1 && predictor != 2 {
-        return Err(JsValue::from_str(&format!("{}: predictor {} is not supported", CTX, predictor)));
+        return Err(TiffError::other(format!("{}: predictor {} is not supported", CTX, predictor)));
     }
     let fill_order = decoder.get_tag_u32(Tag::FillOrder).unwrap_or(1);
     if fill_order != 1 {
-        return Err(JsValue::from_str(&format!("{}: FillOrder 2 (LSB-first) is not supported", CTX)));
+        return Err(TiffError::other(format!("{}: FillOrder 2 (LSB-first) is not supported", CTX)));
     }
     let sample_format = decoder.get_tag_u64_vec(Tag::SampleFormat)
         .ok()
         .and_then(|values| values.first().copied())
         .unwrap_or(1) as u32;
-    if sample_format != 1 {
-        return Err(JsValue::from_str(&format!(
-            "{}: sample format {} is not supported (only unsigned integer)", CTX, sample_format
-        )));
-    }
+    // Bytes each decoded sample occupies in `out` below. Sub-byte-aligned
+    // depths (9..=15) only exist as unsigned integers; 16-bit floats are not
+    // something any JS-side processor handles.
+    let out_sample_bytes: usize = match (sample_format, bits_per_sample) {
+        (1 | 2, 8) => 1,
+        (1, 9..=16) | (2, 16) => 2,
+        (1..=3, 32) => 4,
+        (1..=3, 64) => 8,
+        (1, _) | (3, 8 | 16) => {
+            return Err(TiffError::other(format!(
+                "{}: {}-bit samples are not supported", CTX, bits_per_sample
+            )));
+        }
+        _ => {
+            return Err(TiffError::other(format!(
+                "{}: sample format {} is not supported", CTX, sample_format
+            )));
+        }
+    };
+    // Big-endian blocks of byte-aligned samples are swapped in bulk right
+    // after decompression, on the worker pool, so rows are then unpacked as
+    // little-endian. 9..=15-bit samples are a bit stream in either order.
+    let swap_block_bytes = data.get(0..2) == Some(b"MM") && matches!(bits_per_sample, 16 | 32 | 64);
 
     let planes = if planar_configuration == 2 { channels } else { 1 };
     let channels_per_block = if planar_configuration == 2 { 1 } else { channels };
@@ -2583,12 +14287,12 @@ fn try_decode_general_strips_tiles(
     // rows/columns past width/height are dropped below when assembling the
     // output.
     let (block_width, block_height, blocks_across, blocks_down, rows_per_strip) = if is_tiled {
-        let across = ((width as u64) + tile_width as u64 - 1) / tile_width as u64;
-        let down = ((height as u64) + tile_length as u64 - 1) / tile_length as u64;
+        let across = (width as u64).div_ceil(tile_width as u64);
+        let down = (height as u64).div_ceil(tile_length as u64);
         (tile_width, tile_length, across as u32, down as u32, 0u32)
     } else {
         let rps = decoder.get_tag_u32(Tag::RowsPerStrip).unwrap_or(height).max(1);
-        let down = ((height as u64) + rps as u64 - 1) / rps as u64;
+        let down = (height as u64).div_ceil(rps as u64);
         (width, rps, 1u32, down as u32, rps)
     };
     let blocks_per_plane = (blocks_across as u64) * (blocks_down as u64);
@@ -2597,86 +14301,242 @@ fn try_decode_general_strips_tiles(
         decoder.get_tag_u64_vec(Tag::TileOffsets)
     } else {
         decoder.get_tag_u64_vec(Tag::StripOffsets)
-    }).map_err(|e| JsValue::from_str(&format!("{}: missing offsets: {}", CTX, e)))?;
+    }).map_err(|e| TiffError::other(format!("{}: missing offsets: {}", CTX, e)))?;
     let counts = (if is_tiled {
         decoder.get_tag_u64_vec(Tag::TileByteCounts)
     } else {
         decoder.get_tag_u64_vec(Tag::StripByteCounts)
-    }).map_err(|e| JsValue::from_str(&format!("{}: missing byte counts: {}", CTX, e)))?;
+    }).map_err(|e| TiffError::other(format!("{}: missing byte counts: {}", CTX, e)))?;
 
     let expected_blocks = blocks_per_plane.checked_mul(planes as u64)
-        .ok_or_else(|| JsValue::from_str(&format!("{}: block count overflow", CTX)))?;
+        .ok_or_else(|| TiffError::other(format!("{}: block count overflow", CTX)))?;
     if offsets.len() as u64 != expected_blocks || counts.len() as u64 != expected_blocks {
-        return Err(JsValue::from_str(&format!(
+        return Err(TiffError::other(format!(
             "{}: expected {} strip/tile offsets, found {}", CTX, expected_blocks, offsets.len()
         )));
     }
 
-    let max_value = (1u32 << bits_per_sample.min(31)) - 1;
+    let sample_mask = if bits_per_sample >= 64 { u64::MAX } else { (1u64 << bits_per_sample) - 1 };
     let samples_per_row = (block_width as usize) * (channels_per_block as usize);
-    let row_bytes = (samples_per_row * bits_per_sample as usize + 7) / 8;
-    let mut out: Vec<u16> = vec![0u16; (width as usize) * (height as usize) * (channels as usize)];
-
-    let mut block_idx = 0usize;
+    let row_bytes = (samples_per_row * bits_per_sample as usize).div_ceil(8);
+    // Little-endian sample bytes, `out_sample_bytes` per sample, converted
+    // to the matching `DecodingResult` variant once every block is placed.
+    let mut out: Vec<u8> = vec![0u8; (width as usize) * (height as usize) * (channels as usize) * out_sample_bytes];
+
+    // Lay out every block up front (validating its byte range), then
+    // decompress them a batch at a time on the worker pool - decompression
+    // is independent per block and dominates tiled Deflate/LZW decode time -
+    // while unpacking and placing each batch's rows stays on this thread.
+    // Batching bounds how many decompressed blocks are alive at once.
+    let mut blocks = Vec::with_capacity(offsets.len());
     for plane in 0..planes {
         for tile_row in 0..blocks_down {
             for tile_col in 0..blocks_across {
-                let offset = offsets[block_idx];
-                let count = counts[block_idx];
-                block_idx += 1;
+                let block_idx = blocks.len();
+                // Sparse blocks get an empty range and are filled below.
+                let range = if is_sparse_chunk(offsets[block_idx], counts[block_idx]) {
+                    0..0
+                } else {
+                    let start = offsets[block_idx] as usize;
+                    let end = start.saturating_add(counts[block_idx] as usize);
+                    if end > data.len() {
+                        return Err(TiffError::other(format!("{}: strip/tile byte range out of bounds", CTX)));
+                    }
+                    start..end
+                };
+                blocks.push((block_idx, plane, tile_row, tile_col, range));
+            }
+        }
+    }
 
-                let start = offset as usize;
-                let end = start.saturating_add(count as usize);
-                if end > data.len() {
-                    return Err(JsValue::from_str(&format!("{}: strip/tile byte range out of bounds", CTX)));
+    // Tiles always decode to their full TileLength rows; the last strip
+    // only holds the rows left over at the bottom of the image.
+    let block_rows = |tile_row: u32| if is_tiled {
+        block_height
+    } else {
+        block_height.min(height.saturating_sub(tile_row * rows_per_strip))
+    };
+    let sparse_fill = sample_bits_from_f64(sparse_fill_value(decoder), sample_format, bits_per_sample);
+    let mut stats = (f64::INFINITY, f64::NEG_INFINITY);
+    // Blocks are timed here on the calling thread, never inside the pool
+    // jobs (`Date.now()` is main-thread only in the browser), so a profiled
+    // decode hands the pool one block at a time to keep each recorded time
+    // that block's own.
+    let batch_size = if profile.enabled { 1 } else { worker_pool_size() * 2 };
+    for batch in blocks.chunks(batch_size) {
+        let decompress_start = now_ms();
+        let decompressed_batch = map_in_worker_pool(batch, |(_, _, tile_row, _, range)| {
+            if range.is_empty() {
+                return Ok(Vec::new());
+            }
+            let expected_bytes = row_bytes.saturating_mul(block_rows(*tile_row) as usize);
+            let mut decompressed = decompress_strip_or_tile(&data[range.clone()], compression, expected_bytes, CTX);
+            if let (Ok(bytes), true) = (&mut decompressed, swap_block_bytes) {
+                swap_sample_bytes(bytes, (bits_per_sample / 8) as usize);
+            }
+            decompressed
+        });
+        let decompress_ms = now_ms() - decompress_start;
+
+        for (&(block_idx, plane, tile_row, tile_col, ref range), decompressed) in batch.iter().zip(decompressed_batch) {
+            let decompressed = decompressed.map_err(|e| TiffError::other(&e))?;
+            profile.record(block_idx, range.len() as u64, decompressed.len(), if range.is_empty() { 0.0 } else { decompress_ms });
+
+            let image_row_start = if is_tiled { tile_row * tile_length } else { tile_row * rows_per_strip };
+            let image_col_start = tile_col * block_width;
+            let valid_rows = block_height.min(height.saturating_sub(image_row_start));
+            let valid_cols = block_width.min(width.saturating_sub(image_col_start));
+
+            if range.is_empty() {
+                if compute_stats && valid_rows > 0 && valid_cols > 0 {
+                    fold_sample_bits_stats(&mut stats, &[sparse_fill], sample_format, bits_per_sample);
                 }
-                let block_bytes = &data[start..end];
-
-                let image_row_start = if is_tiled { tile_row * tile_length } else { tile_row * rows_per_strip };
-                let image_col_start = tile_col * block_width;
-                let valid_rows = block_height.min(height.saturating_sub(image_row_start));
-                let valid_cols = block_width.min(width.saturating_sub(image_col_start));
-
-                let expected_bytes = row_bytes.saturating_mul(block_height as usize);
-                let decompressed = decompress_strip_or_tile(block_bytes, compression, expected_bytes, CTX)?;
-                if decompressed.len() < expected_bytes {
-                    return Err(JsValue::from_str(&format!(
-                        "{}: block decompressed to {} bytes, expected at least {}",
-                        CTX, decompressed.len(), expected_bytes
-                    )));
+                if sparse_fill != 0 {
+                    for row in image_row_start..image_row_start + valid_rows {
+                        let row_base = (row as usize) * (width as usize) * (channels as usize);
+                        for col in image_col_start..image_col_start + valid_cols {
+                            for c in 0..(channels_per_block as usize) {
+                                let dest_channel = if planar_configuration == 2 { plane as usize } else { c };
+                                let at = (row_base + (col as usize) * (channels as usize) + dest_channel) * out_sample_bytes;
+                                out[at..at + out_sample_bytes].copy_from_slice(&sparse_fill.to_le_bytes()[..out_sample_bytes]);
+                            }
+                        }
+                    }
                 }
+                continue;
+            }
+            let expected_bytes = row_bytes.saturating_mul(block_rows(tile_row) as usize);
+            if decompressed.len() < expected_bytes {
+                return Err(TiffError::other(format!(
+                    "{}: block decompressed to {} bytes, expected at least {}",
+                    CTX, decompressed.len(), expected_bytes
+                )));
+            }
 
-                for row_idx in 0..(block_height as usize) {
-                    if (row_idx as u32) >= valid_rows {
-                        continue;
-                    }
-                    let row = &decompressed[row_idx * row_bytes..(row_idx + 1) * row_bytes];
-                    let mut row_values = unpack_msb_packed_row(row, samples_per_row, bits_per_sample);
+            for row_idx in 0..(valid_rows as usize) {
+                let row = &decompressed[row_idx * row_bytes..(row_idx + 1) * row_bytes];
+                let mut row_values = unpack_sample_row(row, samples_per_row, bits_per_sample);
 
-                    if predictor == 2 {
-                        apply_horizontal_predictor2(&mut row_values, block_width as usize, channels_per_block as usize, max_value);
-                    }
+                if predictor == 2 {
+                    apply_horizontal_predictor2_wide(&mut row_values, block_width as usize, channels_per_block as usize, sample_mask);
+                }
+                if compute_stats {
+                    let valid_samples = (valid_cols as usize) * (channels_per_block as usize);
+                    fold_sample_bits_stats(&mut stats, &row_values[..valid_samples], sample_format, bits_per_sample);
+                }
 
-                    let out_row = (image_row_start as usize) + row_idx;
-                    let out_row_base = out_row * (width as usize) * (channels as usize);
+                let out_row = (image_row_start as usize) + row_idx;
+                let out_row_base = out_row * (width as usize) * (channels as usize);
 
-                    for col in 0..(valid_cols as usize) {
-                        let out_col = (image_col_start as usize) + col;
-                        for c in 0..(channels_per_block as usize) {
-                            let dest_channel = if planar_configuration == 2 { plane as usize } else { c };
-                            out[out_row_base + out_col * (channels as usize) + dest_channel] =
-                                row_values[col * (channels_per_block as usize) + c];
-                        }
+                for col in 0..(valid_cols as usize) {
+                    let out_col = (image_col_start as usize) + col;
+                    for c in 0..(channels_per_block as usize) {
+                        let dest_channel = if planar_configuration == 2 { plane as usize } else { c };
+                        let at = (out_row_base + out_col * (channels as usize) + dest_channel) * out_sample_bytes;
+                        let value = row_values[col * (channels_per_block as usize) + c];
+                        out[at..at + out_sample_bytes].copy_from_slice(&value.to_le_bytes()[..out_sample_bytes]);
                     }
                 }
             }
         }
     }
 
-    if bits_per_sample == 8 {
-        Ok(Some(DecodingResult::U8(out.into_iter().map(|v| v as u8).collect())))
-    } else {
-        Ok(Some(DecodingResult::U16(out)))
+    let stats = compute_stats.then_some(stats);
+    Ok(Some((decoding_result_from_le_bytes(out, sample_format, bits_per_sample), stats)))
+}
+
+/// Unpack one decoded strip/tile row into `samples_per_row` sample bit
+/// patterns. Byte-aligned 16/32/64-bit samples are little-endian (big-endian
+/// blocks are swapped with `swap_sample_bytes` first); 9..=15-bit samples
+/// are an MSB-first bit stream either way (see `unpack_msb_packed_row`).
+fn unpack_sample_row(row: &[u8], samples_per_row: usize, bits_per_sample: u32) -> Vec<u64> {
+    if !matches!(bits_per_sample, 8 | 16 | 32 | 64) {
+        return unpack_msb_packed_row(row, samples_per_row, bits_per_sample).into_iter().map(u64::from).collect();
+    }
+    let sample_bytes = (bits_per_sample / 8) as usize;
+    row.chunks_exact(sample_bytes)
+        .take(samples_per_row)
+        .map(|bytes| {
+            let mut word = [0u8; 8];
+            word[..sample_bytes].copy_from_slice(bytes);
+            u64::from_le_bytes(word)
+        })
+        .collect()
+}
+
+/// `apply_horizontal_predictor2` for sample bit patterns of any width up to 64
+/// bits, wrapping modulo 2^bits via `mask`. Signed and floating-point samples
+/// are differenced on their raw bit patterns, exactly like libtiff's
+/// `horAcc8/16/32/64`, so the same wrapping sum undoes them.
+fn apply_horizontal_predictor2_wide(row_values: &mut [u64], row_width: usize, channels: usize, mask: u64) {
+    for x in 1..row_width {
+        for c in 0..channels {
+            let idx = x * channels + c;
+            row_values[idx] = row_values[idx].wrapping_add(row_values[idx - channels]) & mask;
+        }
+    }
+}
+
+/// Fold one row of sample bit patterns (as unpacked by `unpack_sample_row`)
+/// into a running min/max, reading them as the SampleFormat/BitsPerSample
+/// `decoding_result_from_le_bytes` would. Non-finite floats are skipped, as
+/// in `compute_stats_f32`/`compute_stats_f64`.
+fn fold_sample_bits_stats(stats: &mut (f64, f64), row_values: &[u64], sample_format: u32, bits_per_sample: u32) {
+    let shift = 64 - bits_per_sample.min(64);
+    for &bits in row_values {
+        let value = match (sample_format, bits_per_sample) {
+            (3, 32) => f32::from_bits(bits as u32) as f64,
+            (3, _) => f64::from_bits(bits),
+            (2, _) => (((bits << shift) as i64) >> shift) as f64,
+            _ => bits as f64,
+        };
+        if value.is_finite() {
+            stats.0 = stats.0.min(value);
+            stats.1 = stats.1.max(value);
+        }
+    }
+}
+
+/// The bit pattern a sample of the given SampleFormat/BitsPerSample stores for
+/// `value` (used for sparse-block fill), saturating to the representable
+/// integer range.
+fn sample_bits_from_f64(value: f64, sample_format: u32, bits_per_sample: u32) -> u64 {
+    match (sample_format, bits_per_sample) {
+        (3, 32) => (value as f32).to_bits() as u64,
+        (3, _) => value.to_bits(),
+        (2, bits) => {
+            let max = (1i128 << (bits - 1)) - 1;
+            let clamped = (value as i128).clamp(-max - 1, max);
+            (clamped as u64) & if bits >= 64 { u64::MAX } else { (1u64 << bits) - 1 }
+        }
+        (_, bits) => {
+            let max = if bits >= 64 { u64::MAX } else { (1u64 << bits) - 1 };
+            if value <= 0.0 { 0 } else { (value as u64).min(max) }
+        }
+    }
+}
+
+/// Reinterpret little-endian sample bytes (1/2/4/8 per sample, as laid out by
+/// `try_decode_general_strips_tiles`) as the `DecodingResult` variant the
+/// `tiff` crate's `read_image()` would have returned for the same
+/// SampleFormat/BitsPerSample.
+fn decoding_result_from_le_bytes(bytes: Vec<u8>, sample_format: u32, bits_per_sample: u32) -> DecodingResult {
+    macro_rules! words {
+        ($variant:ident, $ty:ty, $n:expr) => {
+            DecodingResult::$variant(bytes.chunks_exact($n).map(|b| <$ty>::from_le_bytes(b.try_into().unwrap())).collect())
+        };
+    }
+    match (sample_format, bits_per_sample) {
+        (2, 8) => DecodingResult::I8(bytes.into_iter().map(|b| b as i8).collect()),
+        (_, 8) => DecodingResult::U8(bytes),
+        (2, 16) => words!(I16, i16, 2),
+        (2, 32) => words!(I32, i32, 4),
+        (3, 32) => words!(F32, f32, 4),
+        (_, 32) => words!(U32, u32, 4),
+        (2, 64) => words!(I64, i64, 8),
+        (3, 64) => words!(F64, f64, 8),
+        (_, 64) => words!(U64, u64, 8),
+        _ => words!(U16, u16, 2),
     }
 }
 
@@ -2689,25 +14549,26 @@ fn try_decode_general_strips_tiles(
 /// Tiled images and planar configuration 2 are not supported by this path.
 fn decode_zstd(
     original: &[u8],
-    decoder: &mut Decoder<Cursor<&[u8]>>,
-) -> Result<DecodingResult, JsValue> {
+    decoder: &mut PageDecoder<'_>,
+    profile: &mut ChunkProfile,
+) -> Result<DecodingResult, TiffError> {
     use std::io::Read;
     use tiff::tags::Tag;
 
     if decoder.get_tag_u64_vec(Tag::TileOffsets).is_ok() {
-        return Err(JsValue::from_str("ZSTD: tiled TIFFs are not supported by the pure-Rust path"));
+        return Err(TiffError::other("ZSTD: tiled TIFFs are not supported by the pure-Rust path"));
     }
     let planar = decoder.get_tag_u32(Tag::PlanarConfiguration).unwrap_or(1);
     if planar != 1 {
-        return Err(JsValue::from_str("ZSTD: planar configuration 2 is not supported"));
+        return Err(TiffError::other("ZSTD: planar configuration 2 is not supported"));
     }
 
     let (width, height) = decoder.dimensions()
-        .map_err(|e| JsValue::from_str(&format!("ZSTD: dimensions: {}", e)))?;
+        .map_err(|e| TiffError::other(format!("ZSTD: dimensions: {}", e)))?;
     let offsets = decoder.get_tag_u64_vec(Tag::StripOffsets)
-        .map_err(|e| JsValue::from_str(&format!("ZSTD: StripOffsets: {}", e)))?;
+        .map_err(|e| TiffError::other(format!("ZSTD: StripOffsets: {}", e)))?;
     let counts = decoder.get_tag_u64_vec(Tag::StripByteCounts)
-        .map_err(|e| JsValue::from_str(&format!("ZSTD: StripByteCounts: {}", e)))?;
+        .map_err(|e| TiffError::other(format!("ZSTD: StripByteCounts: {}", e)))?;
     let spp = decoder.get_tag_u32(Tag::SamplesPerPixel).unwrap_or(1);
     let predictor = decoder.get_tag_u32(Tag::Predictor).unwrap_or(1);
     let photometric = decoder.get_tag_u32(Tag::PhotometricInterpretation).unwrap_or(1);
@@ -2720,16 +14581,18 @@ fn decode_zstd(
 
     // Decompress every strip with pure-Rust ruzstd, concatenated in row order.
     let mut raster: Vec<u8> = Vec::new();
-    for (off, cnt) in offsets.iter().zip(counts.iter()) {
+    for (index, (off, cnt)) in offsets.iter().zip(counts.iter()).enumerate() {
         let start = *off as usize;
         let end = start.saturating_add(*cnt as usize);
         if end > original.len() {
-            return Err(JsValue::from_str("ZSTD: strip byte range out of bounds"));
+            return Err(TiffError::other("ZSTD: strip byte range out of bounds"));
         }
+        let decompress_start = now_ms();
         let mut dec = ruzstd::decoding::StreamingDecoder::new(Cursor::new(&original[start..end]))
-            .map_err(|e| JsValue::from_str(&format!("ZSTD: decoder init: {:?}", e)))?;
-        dec.read_to_end(&mut raster)
-            .map_err(|e| JsValue::from_str(&format!("ZSTD: decompress: {:?}", e)))?;
+            .map_err(|e| TiffError::other(format!("ZSTD: decoder init: {:?}", e)))?;
+        let strip_bytes = dec.read_to_end(&mut raster)
+            .map_err(|e| TiffError::other(format!("ZSTD: decompress: {:?}", e)))?;
+        profile.record(index, *cnt, strip_bytes, now_ms() - decompress_start);
     }
 
     // Match the rebuilt TIFF's byte order to the original so multi-byte samples
@@ -2739,9 +14602,9 @@ fn decode_zstd(
         little_endian, width, height, spp, &bits, &sample_format, photometric, predictor, &raster,
     );
     let mut d = Decoder::new(Cursor::new(rebuilt.as_slice()))
-        .map_err(|e| JsValue::from_str(&format!("ZSTD: rebuilt decoder: {}", e)))?;
+        .map_err(|e| TiffError::other(format!("ZSTD: rebuilt decoder: {}", e)))?;
     d.read_image()
-        .map_err(|e| JsValue::from_str(&format!("ZSTD: rebuilt read_image: {}", e)))
+        .map_err(|e| TiffError::other(format!("ZSTD: rebuilt read_image: {}", e)))
 }
 
 /// Build a minimal single-strip, uncompressed classic TIFF wrapping `raster`,
@@ -2857,21 +14720,21 @@ fn build_jpeg(tables: Option<&[u8]>, strip: &[u8]) -> Vec<u8> {
 /// not handled here.
 fn decode_jpeg_ycbcr(
     data: &[u8],
-    decoder: &mut Decoder<Cursor<&[u8]>>,
+    decoder: &mut PageDecoder<'_>,
     width: u32,
     height: u32,
     orientation: TiffOrientation,
-) -> Result<TiffResult, JsValue> {
+) -> Result<TiffResult, TiffError> {
     use tiff::tags::Tag;
     use zune_jpeg::JpegDecoder;
 
     if decoder.get_tag_u64_vec(Tag::TileOffsets).is_ok() {
-        return Err(JsValue::from_str("JPEG: tiled YCbCr JPEG is not supported by the direct path"));
+        return Err(TiffError::other("JPEG: tiled YCbCr JPEG is not supported by the direct path"));
     }
     let offsets = decoder.get_tag_u64_vec(Tag::StripOffsets)
-        .map_err(|e| JsValue::from_str(&format!("JPEG: StripOffsets: {}", e)))?;
+        .map_err(|e| TiffError::other(format!("JPEG: StripOffsets: {}", e)))?;
     let counts = decoder.get_tag_u64_vec(Tag::StripByteCounts)
-        .map_err(|e| JsValue::from_str(&format!("JPEG: StripByteCounts: {}", e)))?;
+        .map_err(|e| TiffError::other(format!("JPEG: StripByteCounts: {}", e)))?;
     // JPEGTables (tag 347): optional abbreviated table stream shared by strips.
     let tables: Option<Vec<u8>> = decoder.get_tag_u8_vec(Tag::Unknown(347)).ok();
 
@@ -2881,23 +14744,23 @@ fn decode_jpeg_ycbcr(
         let start = *off as usize;
         let end = start.saturating_add(*cnt as usize);
         if end > data.len() {
-            return Err(JsValue::from_str("JPEG: strip byte range out of bounds"));
+            return Err(TiffError::other("JPEG: strip byte range out of bounds"));
         }
         let jpeg = build_jpeg(tables.as_deref(), &data[start..end]);
         let mut jd = JpegDecoder::new(Cursor::new(jpeg));
         let px = jd.decode()
-            .map_err(|e| JsValue::from_str(&format!("JPEG decode failed: {:?}", e)))?;
+            .map_err(|e| TiffError::other(format!("JPEG decode failed: {:?}", e)))?;
         let info = jd.info()
-            .ok_or_else(|| JsValue::from_str("JPEG: missing image info"))?;
+            .ok_or_else(|| TiffError::other("JPEG: missing image info"))?;
         let pixels = (info.width as usize).saturating_mul(info.height as usize);
         if pixels == 0 {
-            return Err(JsValue::from_str("JPEG: empty strip"));
+            return Err(TiffError::other("JPEG: empty strip"));
         }
         channels = (px.len() / pixels) as u32;
         rgb.extend_from_slice(&px);
     }
     if channels != 1 && channels != 3 {
-        return Err(JsValue::from_str("JPEG: unexpected channel count"));
+        return Err(TiffError::other("JPEG: unexpected channel count"));
     }
 
     // Data is now decoded RGB (or grayscale), never CMYK, so
@@ -2926,10 +14789,13 @@ fn decode_jpeg_ycbcr(
         tile_length: 0,
         tile_count: 0,
         direct_decode: false,
-        data: rgb,
-        data_f32: Vec::new(),
+        data: Rc::new(rgb),
+        data_f32: Rc::default(),
+        native_float: None,
+        data_f32_cache: OnceCell::new(),
         min_value: min as f64,
         max_value: max as f64,
+        range_from_tags: false,
         timing_metadata_ms: 0.0,
         timing_decode_ms: 0.0,
         timing_convert_ms: 0.0,
@@ -2937,6 +14803,20 @@ fn decode_jpeg_ycbcr(
         timing_pack_ms: 0.0,
         all_tags_json: extract_all_tags_json(data),
         ome_xml: extract_ome_xml(data),
+        chunk_profile_json: String::new(),
+        geo: GeoInfo::default(),
+        page_metadata: PageMetadata::default(),
+        pixel_aspect_ratio: 1.0,
+        source_frame: None,
+        fractional_bits: 0,
+        value_transform: None,
+        include_transparent: false,
+        channel_stats: OnceCell::new(),
+        primaries: None,
+        icc_profile: Vec::new(),
+        color_managed: false,
+        transfer_byte_budget: 0,
+        disposed: false,
     })
 }
 
@@ -2947,7 +14827,7 @@ fn decode_jpeg_ycbcr(
 fn unpack_bilevel(data: &[u8], width: u32, height: u32, photometric: u32) -> Vec<u8> {
     let width = width as usize;
     let height = height as usize;
-    let row_bytes = (width + 7) / 8;
+    let row_bytes = width.div_ceil(8);
     let white_is_zero = photometric == 0;
     let mut out = Vec::with_capacity(width.saturating_mul(height));
     for y in 0..height {
@@ -2968,77 +14848,159 @@ fn unpack_bilevel(data: &[u8], width: u32, height: u32, photometric: u32) -> Vec
 /// leaves the buffer untouched) for anything it does not understand, e.g.
 /// BigTIFF.
 fn patch_photometric_to_grayscale(buf: &mut [u8], page_index: u32) -> bool {
-    if buf.len() < 8 {
+    patch_inline_short_tag(buf, page_index, 262, 1)
+}
+
+/// Overwrite the inline SHORT value of `tag` in one IFD, in place. Returns
+/// false (and leaves the buffer untouched) when the page has no such tag or
+/// the file is not a classic TIFF.
+fn patch_inline_short_tag(buf: &mut [u8], page_index: u32, tag: u16, value: u16) -> bool {
+    let Some((at, little_endian)) = inline_short_tag_offset(buf, page_index, tag) else {
         return false;
-    }
-    let le = match &buf[0..2] {
+    };
+    let bytes = if little_endian { value.to_le_bytes() } else { value.to_be_bytes() };
+    buf[at..at + 2].copy_from_slice(&bytes);
+    true
+}
+
+/// File offset of the inline SHORT value of `tag` in one IFD, and whether
+/// the file is little-endian; `None` when the page has no such tag or the
+/// file is not a classic TIFF (BigTIFF, magic 43, is left to fall back).
+fn inline_short_tag_offset(buf: &[u8], page_index: u32, tag: u16) -> Option<(usize, bool)> {
+    let le = match buf.get(0..2)? {
         b"II" => true,
         b"MM" => false,
-        _ => return false,
+        _ => return None,
     };
-    let rd16 = |b: &[u8]| if le { u16::from_le_bytes([b[0], b[1]]) } else { u16::from_be_bytes([b[0], b[1]]) };
-    let rd32 = |b: &[u8]| if le {
-        u32::from_le_bytes([b[0], b[1], b[2], b[3]])
-    } else {
-        u32::from_be_bytes([b[0], b[1], b[2], b[3]])
+    let rd16 = |at: usize| {
+        let b = buf.get(at..at.checked_add(2)?)?;
+        Some(if le { u16::from_le_bytes([b[0], b[1]]) } else { u16::from_be_bytes([b[0], b[1]]) })
     };
-    // Only classic TIFF (magic 42) is handled; BigTIFF (43) is left to fall back.
-    if rd16(&buf[2..4]) != 42 {
-        return false;
+    let rd32 = |at: usize| {
+        let b = buf.get(at..at.checked_add(4)?)?;
+        Some(if le { u32::from_le_bytes([b[0], b[1], b[2], b[3]]) } else { u32::from_be_bytes([b[0], b[1], b[2], b[3]]) })
+    };
+    if rd16(2)? != 42 {
+        return None;
     }
-    let mut ifd = rd32(&buf[4..8]) as usize;
+    let mut ifd = rd32(4)? as usize;
     for _ in 0..page_index {
-        if ifd + 2 > buf.len() {
-            return false;
-        }
-        let count = rd16(&buf[ifd..ifd + 2]) as usize;
-        let next_offset_pos = ifd + 2 + count * 12;
-        if next_offset_pos + 4 > buf.len() {
-            return false;
-        }
-        ifd = rd32(&buf[next_offset_pos..next_offset_pos + 4]) as usize;
+        let count = rd16(ifd)? as usize;
+        ifd = rd32(ifd.checked_add(2 + count * 12)?)? as usize;
         if ifd == 0 {
-            return false;
+            return None;
         }
     }
-    if ifd + 2 > buf.len() {
+    let count = rd16(ifd)? as usize;
+    (0..count)
+        .map(|i| ifd + 2 + i * 12)
+        .take_while(|&entry| entry + 12 <= buf.len())
+        // SHORT value stored inline in the entry's value field.
+        .find(|&entry| rd16(entry) == Some(tag))
+        .map(|entry| (entry + 8, le))
+}
+
+/// Multispectral pages are often written as PhotometricInterpretation RGB
+/// with the remaining bands as unspecified ExtraSamples (or with no
+/// ExtraSamples tag at all). The tiff crate compacts those extra bands away in
+/// `read_image()`/`read_chunk()` and rejects the untagged variant outright in
+/// `colortype()`, but keeps every sample of a BlackIsZero page as
+/// `ColorType::Multiband`. True for such pages, which every decode path then
+/// opens through `open_tiff_page_as_grayscale` so it sees all
+/// SamplesPerPixel bands; false for every other page, including plain
+/// RGB/RGBA and JPEG-compressed data (whose codec depends on the color
+/// space). `decoder` is open on the page, whose photometric is `photometric`.
+fn multiband_rgb_as_grayscale(decoder: &mut PageDecoder<'_>, photometric: u32) -> bool {
+    use tiff::tags::Tag;
+
+    if photometric != 2 || matches!(decoder.get_tag_u32(Tag::Compression).unwrap_or(1), 6 | 7) {
         return false;
     }
-    let count = rd16(&buf[ifd..ifd + 2]) as usize;
-    for i in 0..count {
-        let e = ifd + 2 + i * 12;
-        if e + 12 > buf.len() {
-            return false;
+    let samples_per_pixel = decoder.get_tag_u32(Tag::SamplesPerPixel).unwrap_or(1);
+    let kept_samples = decoder.colortype().map(|c| c.num_samples() as u32).unwrap_or(0);
+    kept_samples < samples_per_pixel
+}
+
+/// FillOrder 2 (some fax and scanner TIFFs) stores each byte's bits
+/// least-significant first. Like libtiff, reverse the bits of every raw
+/// strip/tile byte before it reaches the codec and return that copy with the
+/// tag patched to 1, so every decode path - 1-bit `unpack_bilevel`, sub-byte
+/// grayscale in `read_image()`, `try_decode_subbit_strips` and
+/// `try_decode_general_strips_tiles` - sees MSB-first data. Only the
+/// codecs libtiff bit-reverses for are handled (None, LZW, Deflate, PackBits,
+/// ZSTD); CCITT fax reads the tag itself (`decode_ccitt`) and JPEG/WebP
+/// streams carry their own bit order. `None` for every other page, and for
+/// BigTIFF, which `patch_inline_short_tag` does not rewrite. `decoder` is
+/// open on the page.
+fn fill_order_reversed(data: &[u8], decoder: &mut PageDecoder<'_>, page_index: u32) -> Option<Vec<u8>> {
+    use tiff::tags::Tag;
+
+    if decoder.get_tag_u32(Tag::FillOrder).unwrap_or(1) != 2
+        || !matches!(decoder.get_tag_u32(Tag::Compression).unwrap_or(1), 1 | 5 | 8 | 32773 | 32946 | 50000)
+    {
+        return None;
+    }
+    let (offsets, counts) = match decoder.get_tag_u64_vec(Tag::TileOffsets) {
+        Ok(offsets) => (offsets, decoder.get_tag_u64_vec(Tag::TileByteCounts).ok()?),
+        Err(_) => (
+            decoder.get_tag_u64_vec(Tag::StripOffsets).ok()?,
+            decoder.get_tag_u64_vec(Tag::StripByteCounts).ok()?,
+        ),
+    };
+    let mut patched = data.to_vec();
+    if !patch_inline_short_tag(&mut patched, page_index, 266, 1) {
+        return None;
+    }
+    // Writers may point identical blocks at one shared copy; reversing that
+    // twice would undo it.
+    let mut reversed = std::collections::HashSet::new();
+    for (&offset, &count) in offsets.iter().zip(counts.iter()) {
+        if is_sparse_chunk(offset, count) || !reversed.insert(offset) {
+            continue;
         }
-        if rd16(&buf[e..e + 2]) == 262 {
-            // SHORT value stored inline in the entry's value field.
-            let one = if le { [1u8, 0u8] } else { [0u8, 1u8] };
-            buf[e + 8] = one[0];
-            buf[e + 9] = one[1];
-            return true;
+        let start = (offset as usize).min(patched.len());
+        let end = start.saturating_add(count as usize).min(patched.len());
+        for byte in &mut patched[start..end] {
+            *byte = byte.reverse_bits();
         }
     }
-    false
+    Some(patched)
+}
+
+/// How every decode entry point has to read page `page_index` of `data`,
+/// worked out from the `decoder` already open on it (whose photometric is
+/// `photometric`): whether as BlackIsZero (`multiband_rgb_as_grayscale`),
+/// and the bit-reversed copy of `data` to read it from
+/// (`fill_order_reversed`), if any. Reopen the page with
+/// `open_decodable_page` when either applies.
+fn decodable_page(data: &[u8], decoder: &mut PageDecoder<'_>, page_index: u32, photometric: u32) -> (bool, Option<Vec<u8>>) {
+    (multiband_rgb_as_grayscale(decoder, photometric), fill_order_reversed(data, decoder, page_index))
+}
+
+/// Open page `page_index` of `data` as `decodable_page` found it has to be
+/// read.
+fn open_decodable_page<B: AsRef<[u8]>>(data: B, page_index: u32, as_grayscale: bool) -> Result<Decoder<PageCursor<B>>, TiffError> {
+    if as_grayscale { open_tiff_page_as_grayscale(data, page_index) } else { open_tiff_page_from(PageCursor::new(data), page_index) }
 }
 
 /// Decode a palette (RGBPalette) TIFF by reading the raw indices and expanding
 /// them through the ColorMap tag into interleaved 8-bit RGB.
-fn decode_palette(data: &[u8], width: u32, height: u32, page_index: u32) -> Result<TiffResult, JsValue> {
+fn decode_palette(data: &[u8], width: u32, height: u32, page_index: u32) -> Result<TiffResult, TiffError> {
     use tiff::tags::Tag;
 
     // ColorMap (tag 320): 3 * 2^bits 16-bit entries, laid out as all reds, then
     // all greens, then all blues.
     let cmap = {
         let mut d = Decoder::new(Cursor::new(data))
-            .map_err(|e| JsValue::from_str(&format!("Palette: decoder init: {}", e)))?;
+            .map_err(|e| TiffError::other(format!("Palette: decoder init: {}", e)))?;
         for _ in 0..page_index {
-            d.next_image().map_err(|e| JsValue::from_str(&format!("Palette: page select: {}", e)))?;
+            d.next_image().map_err(|e| TiffError::other(format!("Palette: page select: {}", e)))?;
         }
         d.get_tag_u16_vec(Tag::Unknown(320))
-            .map_err(|e| JsValue::from_str(&format!("Palette: missing ColorMap: {}", e)))?
+            .map_err(|e| TiffError::other(format!("Palette: missing ColorMap: {}", e)))?
     };
     if cmap.is_empty() || cmap.len() % 3 != 0 {
-        return Err(JsValue::from_str("Palette: invalid ColorMap length"));
+        return Err(TiffError::other("Palette: invalid ColorMap length"));
     }
     let n_colors = cmap.len() / 3;
 
@@ -3046,13 +15008,13 @@ fn decode_palette(data: &[u8], width: u32, height: u32, page_index: u32) -> Resu
     // reusing all of its compression / predictor / strip handling.
     let mut patched = data.to_vec();
     if !patch_photometric_to_grayscale(&mut patched, page_index) {
-        return Err(JsValue::from_str("Palette: could not patch photometric tag"));
+        return Err(TiffError::other("Palette: could not patch photometric tag"));
     }
 
-    let mut d = Decoder::new(Cursor::new(patched.as_slice()))
-        .map_err(|e| JsValue::from_str(&format!("Palette: patched decoder init: {}", e)))?;
+    let mut d = Decoder::new(PageCursor::new(patched.as_slice()))
+        .map_err(|e| TiffError::other(format!("Palette: patched decoder init: {}", e)))?;
     for _ in 0..page_index {
-        d.next_image().map_err(|e| JsValue::from_str(&format!("Palette: patched page select: {}", e)))?;
+        d.next_image().map_err(|e| TiffError::other(format!("Palette: patched page select: {}", e)))?;
     }
     let compression = d.get_tag_u32(Tag::Compression).unwrap_or(1);
     let predictor = d.get_tag_u32(Tag::Predictor).unwrap_or(1);
@@ -3071,11 +15033,11 @@ fn decode_palette(data: &[u8], width: u32, height: u32, page_index: u32) -> Resu
     let orientation = TiffOrientation::from_tag(d.get_tag_u32(Tag::Orientation).unwrap_or(1));
 
     let indices: Vec<usize> = match d.read_image()
-        .map_err(|e| JsValue::from_str(&format!("Palette: index decode failed: {}", e)))?
+        .map_err(|e| TiffError::other(format!("Palette: index decode failed: {}", e)))?
     {
         DecodingResult::U8(v) => v.iter().map(|&x| x as usize).collect(),
         DecodingResult::U16(v) => v.iter().map(|&x| x as usize).collect(),
-        _ => return Err(JsValue::from_str("Palette: unexpected index sample type")),
+        _ => return Err(TiffError::other("Palette: unexpected index sample type")),
     };
 
     // ColorMap entries are 16-bit; scale down to 8-bit per channel.
@@ -3114,10 +15076,13 @@ fn decode_palette(data: &[u8], width: u32, height: u32, page_index: u32) -> Resu
         tile_length,
         tile_count,
         direct_decode: false,
-        data: rgb,
-        data_f32: Vec::new(),
+        data: Rc::new(rgb),
+        data_f32: Rc::default(),
+        native_float: None,
+        data_f32_cache: OnceCell::new(),
         min_value: min as f64,
         max_value: max as f64,
+        range_from_tags: false,
         timing_metadata_ms: 0.0,
         timing_decode_ms: 0.0,
         timing_convert_ms: 0.0,
@@ -3125,6 +15090,20 @@ fn decode_palette(data: &[u8], width: u32, height: u32, page_index: u32) -> Resu
         timing_pack_ms: 0.0,
         all_tags_json: extract_page_tags_json(data, page_index),
         ome_xml: extract_ome_xml(data),
+        chunk_profile_json: String::new(),
+        geo: GeoInfo::read(&mut d),
+        page_metadata: PageMetadata::read(&mut d),
+        pixel_aspect_ratio: read_pixel_aspect_ratio(&mut d),
+        source_frame: None,
+        fractional_bits: 0,
+        value_transform: None,
+        include_transparent: false,
+        channel_stats: OnceCell::new(),
+        primaries: read_tiff_primaries(&mut d),
+        icc_profile: read_tiff_icc_profile(&mut d),
+        color_managed: false,
+        transfer_byte_budget: 0,
+        disposed: false,
     })
 }
 
@@ -3148,7 +15127,7 @@ fn decode_ccitt(
     t4_options: u32,
     rows_per_strip: u32,
     orientation: TiffOrientation,
-) -> Result<TiffResult, JsValue> {
+) -> Result<TiffResult, TiffError> {
     use hayro_ccitt::{decode, DecodeSettings, DecoderContext, EncodingMode, Decoder as CcittDecoder};
 
     // Map the TIFF compression + T4Options to a hayro encoding mode.
@@ -3219,7 +15198,7 @@ fn decode_ccitt(
         let start = *off as usize;
         let end = start.saturating_add(*cnt as usize);
         if end > data.len() {
-            return Err(JsValue::from_str("CCITT: strip byte range out of bounds"));
+            return Err(TiffError::other("CCITT: strip byte range out of bounds"));
         }
         let rows_in_strip = height.saturating_sub(i as u32 * rps).min(rps);
         if rows_in_strip == 0 {
@@ -3244,7 +15223,7 @@ fn decode_ccitt(
         let mut ctx = DecoderContext::new(settings);
         collector.cur_x = 0;
         decode(&strip, &mut collector, &mut ctx)
-            .map_err(|e| JsValue::from_str(&format!("CCITT strip {} decode failed: {:?}", i, e)))?;
+            .map_err(|e| TiffError::other(format!("CCITT strip {} decode failed: {:?}", i, e)))?;
     }
 
     let mut pixels = collector.pixels;
@@ -3276,10 +15255,13 @@ fn decode_ccitt(
         tile_length: 0,
         tile_count: 0,
         direct_decode: false,
-        data: pixels,
-        data_f32: Vec::new(),
+        data: Rc::new(pixels),
+        data_f32: Rc::default(),
+        native_float: None,
+        data_f32_cache: OnceCell::new(),
         min_value: min as f64,
         max_value: max as f64,
+        range_from_tags: false,
         timing_metadata_ms: 0.0,
         timing_decode_ms: 0.0,
         timing_convert_ms: 0.0,
@@ -3287,145 +15269,175 @@ fn decode_ccitt(
         timing_pack_ms: 0.0,
         all_tags_json: extract_all_tags_json(data),
         ome_xml: extract_ome_xml(data),
+        chunk_profile_json: String::new(),
+        geo: GeoInfo::default(),
+        page_metadata: PageMetadata::default(),
+        pixel_aspect_ratio: 1.0,
+        source_frame: None,
+        fractional_bits: 0,
+        value_transform: None,
+        include_transparent: false,
+        channel_stats: OnceCell::new(),
+        primaries: None,
+        icc_profile: Vec::new(),
+        color_managed: false,
+        transfer_byte_budget: 0,
+        disposed: false,
     })
 }
 
-// SIMD-optimized conversion functions
 
-/// Convert u16 slice to little-endian bytes using SIMD
-#[inline]
-fn convert_u16_to_bytes_simd(data: &[u16]) -> Vec<u8> {
-    use wide::*;
-    
-    let mut bytes = Vec::with_capacity(data.len() * 2);
-    
-    // Process 8 u16s at a time (128-bit SIMD)
-    let chunks = data.chunks_exact(8);
-    let remainder = chunks.remainder();
-    
-    for chunk in chunks {
-        let simd = u16x8::new([
-            chunk[0], chunk[1], chunk[2], chunk[3],
-            chunk[4], chunk[5], chunk[6], chunk[7]
-        ]);
-        
-        let arr = simd.to_array();
-        for val in arr {
-            bytes.extend_from_slice(&val.to_le_bytes());
-        }
-    }
-    
-    // Handle remainder
-    for &val in remainder {
-        bytes.extend_from_slice(&val.to_le_bytes());
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(name: &str) -> Vec<u8> {
+        std::fs::read(std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../../test-samples").join(name)).unwrap()
+    }
+
+    #[test]
+    fn worker_pool_keeps_input_order_across_threads() {
+        let inputs: Vec<u32> = (0..100).collect();
+        let threads = std::sync::Mutex::new(std::collections::HashSet::new());
+        let doubled = map_on_workers(4, &inputs, |&n| {
+            threads.lock().unwrap().insert(std::thread::current().id());
+            n * 2
+        });
+        assert_eq!(doubled, (0..100).map(|n| n * 2).collect::<Vec<_>>());
+        assert_eq!(threads.into_inner().unwrap().len(), 4);
+    }
+
+    /// A one-entry archive body (local header and deflated data) for
+    /// `zip_entry_head`, declaring `declared_size` uncompressed bytes.
+    fn deflated_zip_entry(contents: &[u8], declared_size: u64) -> (Vec<u8>, ZipEntry) {
+        use std::io::Write;
+
+        let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(contents).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let mut data = vec![0u8; 30];
+        data[..4].copy_from_slice(&[0x50, 0x4b, 0x03, 0x04]);
+        data.extend_from_slice(&compressed);
+        let entry = ZipEntry {
+            name: "bomb.tif".to_string(),
+            method: 8,
+            flags: 0,
+            compressed_size: compressed.len() as u64,
+            uncompressed_size: declared_size,
+            local_header_offset: 0,
+        };
+        (data, entry)
     }
-    
-    bytes
-}
 
-// Statistics computation functions
+    #[test]
+    fn zip_entry_stops_at_declared_size() {
+        let contents = vec![0u8; 1 << 20];
+        let (data, honest) = deflated_zip_entry(&contents, contents.len() as u64);
+        assert_eq!(zip_entry_data(&data, &honest).unwrap(), contents);
+        assert_eq!(zip_entry_head(&data, &honest, 12).unwrap().len(), 12);
 
-fn compute_stats_u8(data: &[u8]) -> (u8, u8) {
-    let mut min = u8::MAX;
-    let mut max = u8::MIN;
-    for &v in data {
-        min = min.min(v);
-        max = max.max(v);
+        // A megabyte of zeros declared as 100 bytes is refused after 101.
+        let (data, bomb) = deflated_zip_entry(&contents, 100);
+        assert!(zip_entry_data(&data, &bomb).unwrap_err().contains("declared size"));
+        assert_eq!(zip_entry_head(&data, &bomb, 12).unwrap().len(), 12);
     }
-    (min, max)
-}
 
-fn compute_stats_u16(data: &[u16]) -> (u16, u16) {
-    let mut min = u16::MAX;
-    let mut max = u16::MIN;
-    for &v in data {
-        min = min.min(v);
-        max = max.max(v);
-    }
-    (min, max)
-}
+    /// xorshift64*, so the fuzz inputs are the same on every run.
+    struct FuzzRng(u64);
 
-fn compute_stats_u32(data: &[u32]) -> (u32, u32) {
-    let mut min = u32::MAX;
-    let mut max = u32::MIN;
-    for &v in data {
-        min = min.min(v);
-        max = max.max(v);
-    }
-    (min, max)
-}
+    impl FuzzRng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 >> 12;
+            self.0 ^= self.0 << 25;
+            self.0 ^= self.0 >> 27;
+            self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+        }
 
-fn compute_stats_u64(data: &[u64]) -> (u64, u64) {
-    let mut min = u64::MAX;
-    let mut max = u64::MIN;
-    for &v in data {
-        min = min.min(v);
-        max = max.max(v);
+        fn below(&mut self, n: usize) -> usize {
+            (self.next() % n as u64) as usize
+        }
     }
-    (min, max)
-}
 
-fn compute_stats_i8(data: &[i8]) -> (i8, i8) {
-    let mut min = i8::MAX;
-    let mut max = i8::MIN;
-    for &v in data {
-        min = min.min(v);
-        max = max.max(v);
-    }
-    (min, max)
-}
+    /// Truncated and byte-mutated copies of every TIFF in test-samples must
+    /// come back from `decode_tiff_safe` as `Ok` or a `TiffError` without
+    /// panicking anywhere on the way: `decode_tiff_safe` would turn a panic
+    /// into a `TiffError` here, but the wasm32 build aborts on one.
+    #[test]
+    fn decode_tiff_safe_survives_mutated_samples() {
+        let samples = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../../test-samples");
+        let mut files: Vec<_> = std::fs::read_dir(samples)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "tif"))
+            .collect();
+        files.sort();
+
+        // The decode installs console_error_panic_hook on first use; let it
+        // do so now so it cannot replace the recording hook below.
+        #[cfg(feature = "console_error_panic_hook")]
+        console_error_panic_hook::set_once();
+        static PANICS: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+        let previous_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(|info| PANICS.lock().unwrap().push(info.to_string())));
+
+        let mut rng = FuzzRng(0x5EED_7175_F00D_0490);
+        let mut failures = Vec::new();
+        for path in &files {
+            let original = std::fs::read(path).unwrap();
+            let mut inputs = Vec::new();
+            for eighth in 0..8 {
+                inputs.push(original[..original.len() * eighth / 8].to_vec());
+            }
+            for _ in 0..24 {
+                let mut mutated = original.clone();
+                // Headers and IFDs sit near the start of most samples, so
+                // half the mutations stay in the first 512 bytes.
+                let span = if rng.below(2) == 0 { mutated.len().min(512) } else { mutated.len() };
+                for _ in 0..1 + rng.below(8) {
+                    let at = rng.below(span);
+                    mutated[at] = rng.next() as u8;
+                }
+                inputs.push(mutated);
+            }
 
-fn compute_stats_i16(data: &[i16]) -> (i16, i16) {
-    let mut min = i16::MAX;
-    let mut max = i16::MIN;
-    for &v in data {
-        min = min.min(v);
-        max = max.max(v);
-    }
-    (min, max)
-}
+            for (index, input) in inputs.iter().enumerate() {
+                PANICS.lock().unwrap().clear();
+                let outcome = std::panic::catch_unwind(|| decode_tiff_safe(input, 0).map(|_| ()));
+                let panics = PANICS.lock().unwrap().clone();
+                if outcome.is_err() || !panics.is_empty() {
+                    failures.push(format!("{} input {}: {:?}", path.display(), index, panics));
+                }
+            }
+        }
 
-fn compute_stats_i32(data: &[i32]) -> (i32, i32) {
-    let mut min = i32::MAX;
-    let mut max = i32::MIN;
-    for &v in data {
-        min = min.min(v);
-        max = max.max(v);
+        std::panic::set_hook(previous_hook);
+        assert!(failures.is_empty(), "decoder panics:\n{}", failures.join("\n"));
     }
-    (min, max)
-}
 
-fn compute_stats_i64(data: &[i64]) -> (i64, i64) {
-    let mut min = i64::MAX;
-    let mut max = i64::MIN;
-    for &v in data {
-        min = min.min(v);
-        max = max.max(v);
-    }
-    (min, max)
-}
+    #[test]
+    fn transparent_pixels_are_left_out_without_an_f32_copy() {
+        let bytes = sample("rgba_transparent_padding_u8.tif");
+        let mut image = decode_tiff(&bytes).unwrap();
+        assert_eq!(image.value_range(), (50.0, 255.0));
+        assert!(image.data_f32_cache.get().is_none());
+        assert!(image.channel_stats.get().is_none());
+        image.set_include_transparent_pixels(true).unwrap();
+        assert_eq!(image.value_range(), (0.0, 255.0));
+        assert!(image.data_f32_cache.get().is_none());
 
-fn compute_stats_f32(data: &[f32]) -> (f64, f64) {
-    let mut min = f64::INFINITY;
-    let mut max = f64::NEG_INFINITY;
-    for &v in data {
-        if !v.is_nan() && v.is_finite() {
-            let v64 = v as f64;
-            min = min.min(v64);
-            max = max.max(v64);
-        }
+        let mut fast = decode_tiff_fast(&bytes).unwrap();
+        assert!(fast.value_range().0.is_nan());
+        assert!(fast.channel_stats.get().is_none());
+        fast.compute_stats().unwrap();
+        assert_eq!(fast.value_range(), (50.0, 255.0));
     }
-    (min, max)
-}
 
-fn compute_stats_f64(data: &[f64]) -> (f64, f64) {
-    let mut min = f64::INFINITY;
-    let mut max = f64::NEG_INFINITY;
-    for &v in data {
-        if !v.is_nan() && v.is_finite() {
-            min = min.min(v);
-            max = max.max(v);
-        }
+    #[test]
+    fn tiled_planar_lzw_decodes_natively() {
+        // Goes through the batched worker-pool decompression in
+        // `try_decode_general_strips_tiles`, timed with the native clock.
+        let result = decode_tiff(&sample("shapes_lzw_tiled_planar.tif")).unwrap();
+        assert!(result.direct_decode);
+        assert_eq!(*result.data, sample("shapes_lzw_tiled_planar.gt.u8.bin"));
     }
-    (min, max)
 }